@@ -2,7 +2,7 @@
 
 use anoma_apps::cli;
 use anoma_apps::cli::cmds::*;
-use anoma_apps::client::{gossip, rpc, tx, utils};
+use anoma_apps::client::{decode, gossip, repl, rpc, signing, tx, utils};
 use color_eyre::eyre::Result;
 
 pub async fn main() -> Result<()> {
@@ -13,47 +13,126 @@ pub async fn main() -> Result<()> {
             match cmd {
                 // Ledger cmds
                 Sub::TxCustom(TxCustom(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
                     tx::submit_custom(ctx, args).await;
                 }
                 Sub::TxTransfer(TxTransfer(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
                     tx::submit_transfer(ctx, args).await;
                 }
+                Sub::TxTokenAdmin(TxTokenAdmin(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
+                    tx::submit_token_admin(ctx, args).await;
+                }
+                Sub::TxMint(TxMint(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
+                    tx::submit_mint(ctx, args).await;
+                }
+                Sub::TxTransferLists(TxTransferLists(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
+                    tx::submit_transfer_lists(ctx, args).await;
+                }
                 Sub::TxUpdateVp(TxUpdateVp(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
                     tx::submit_update_vp(ctx, args).await;
                 }
                 Sub::TxInitAccount(TxInitAccount(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
                     tx::submit_init_account(ctx, args).await;
                 }
                 Sub::TxInitValidator(TxInitValidator(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
                     tx::submit_init_validator(ctx, args).await;
                 }
+                Sub::TxBundle(TxBundle(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
+                    tx::submit_bundle(ctx, args).await;
+                }
+                Sub::BroadcastTx(BroadcastTx(args)) => {
+                    tx::submit_raw_tx(args).await;
+                }
+                Sub::DecodeTx(DecodeTx(args)) => {
+                    decode::decode_tx(args);
+                }
+                Sub::ConvertAmount(ConvertAmount(args)) => {
+                    decode::convert_amount(ctx, args);
+                }
+                Sub::ResolveAlias(ResolveAlias(args)) => {
+                    decode::resolve_alias(ctx, args);
+                }
+                Sub::ResolveAddress(ResolveAddress(args)) => {
+                    decode::resolve_address(ctx, args);
+                }
+                Sub::SignMessage(SignMessage(args)) => {
+                    signing::sign_message(ctx, args).await;
+                }
+                Sub::VerifyMessage(VerifyMessage(args)) => {
+                    signing::verify_message(ctx, args).await;
+                }
                 Sub::TxInitNft(TxInitNft(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
                     tx::submit_init_nft(ctx, args).await;
                 }
                 Sub::TxMintNft(TxMintNft(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
                     tx::submit_mint_nft(ctx, args).await;
                 }
                 Sub::TxInitProposal(TxInitProposal(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
                     tx::submit_init_proposal(ctx, args).await;
                 }
                 Sub::TxVoteProposal(TxVoteProposal(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
                     tx::submit_vote_proposal(ctx, args).await;
                 }
                 Sub::Bond(Bond(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
                     tx::submit_bond(ctx, args).await;
                 }
                 Sub::Unbond(Unbond(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
                     tx::submit_unbond(ctx, args).await;
                 }
                 Sub::Withdraw(Withdraw(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
                     tx::submit_withdraw(ctx, args).await;
                 }
+                Sub::TxChangeCommissionRate(TxChangeCommissionRate(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
+                    tx::submit_change_commission_rate(ctx, args).await;
+                }
+                Sub::TxEditValidator(TxEditValidator(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
+                    tx::submit_edit_validator(ctx, args).await;
+                }
+                Sub::TxClaimRewards(TxClaimRewards(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
+                    tx::submit_claim_rewards(ctx, args).await;
+                }
+                Sub::TxUnjailValidator(TxUnjailValidator(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
+                    tx::submit_unjail_validator(ctx, args).await;
+                }
+                Sub::TxRotateConsensusKey(TxRotateConsensusKey(args)) => {
+                    tx::wait_for_epoch(&args.tx).await;
+                    tx::submit_rotate_consensus_key(ctx, args).await;
+                }
                 // Ledger queries
                 Sub::QueryEpoch(QueryEpoch(args)) => {
-                    rpc::query_epoch(args).await;
+                    rpc::query_epoch(args.clone()).await;
+                    rpc::query_epoch_progress(args).await;
+                }
+                Sub::QueryBaseFee(QueryBaseFee(args)) => {
+                    rpc::query_base_fee(args).await;
+                }
+                Sub::QueryEpochSeed(QueryEpochSeed(args)) => {
+                    rpc::query_epoch_seed(args).await;
                 }
                 Sub::QueryBalance(QueryBalance(args)) => {
-                    rpc::query_balance(ctx, args).await;
+                    rpc::query_balance(&ctx, args).await;
+                }
+                Sub::QueryBalances(QueryBalances(args)) => {
+                    rpc::query_balances(ctx, args).await;
                 }
                 Sub::QueryBonds(QueryBonds(args)) => {
                     rpc::query_bonds(ctx, args).await;
@@ -61,18 +140,57 @@ pub async fn main() -> Result<()> {
                 Sub::QueryVotingPower(QueryVotingPower(args)) => {
                     rpc::query_voting_power(ctx, args).await;
                 }
+                Sub::QueryValidatorSet(QueryValidatorSet(args)) => {
+                    rpc::query_validator_set(args).await;
+                }
                 Sub::QuerySlashes(QuerySlashes(args)) => {
                     rpc::query_slashes(ctx, args).await;
                 }
+                Sub::QueryCommissionRate(QueryCommissionRate(args)) => {
+                    rpc::query_commission_rate(ctx, args).await;
+                }
+                Sub::QueryValidator(QueryValidator(args)) => {
+                    rpc::query_validator(ctx, args).await;
+                }
+                Sub::QueryRewards(QueryRewards(args)) => {
+                    rpc::query_rewards(ctx, args).await;
+                }
                 Sub::QueryResult(QueryResult(args)) => {
                     rpc::query_result(ctx, args).await;
                 }
                 Sub::QueryRawBytes(QueryRawBytes(args)) => {
-                    rpc::query_raw_bytes(ctx, args).await;
+                    rpc::query_raw_bytes(&ctx, args).await;
+                }
+                Sub::QueryMetadata(QueryMetadata(args)) => {
+                    rpc::query_metadata(ctx, args).await;
                 }
 
                 Sub::QueryProposal(QueryProposal(args)) => {
-                    rpc::query_proposal(ctx, args).await;
+                    rpc::query_proposal(&ctx, args).await;
+                }
+                Sub::QueryProposalCode(QueryProposalCode(args)) => {
+                    rpc::query_proposal_code(args).await;
+                }
+                Sub::QueryProtocolParameters(QueryProtocolParameters(args)) => {
+                    rpc::query_protocol_parameters(args).await;
+                }
+                Sub::SimulateBlock(SimulateBlock(args)) => {
+                    rpc::simulate_block(args).await;
+                }
+                Sub::QueryEvents(QueryEvents(args)) => {
+                    rpc::query_events(args).await;
+                }
+                Sub::QueryMempool(QueryMempool(args)) => {
+                    rpc::query_mempool(args).await;
+                }
+                Sub::QueryNodeStatus(QueryNodeStatus(args)) => {
+                    rpc::query_node_status(args).await;
+                }
+                Sub::QueryStorageSize(QueryStorageSize(args)) => {
+                    rpc::query_storage_size(args).await;
+                }
+                Sub::Repl(Repl(args)) => {
+                    repl::run(ctx, args).await;
                 }
                 // Gossip cmds
                 Sub::Intent(Intent(args)) => {
@@ -94,6 +212,9 @@ pub async fn main() -> Result<()> {
             Utils::InitGenesisValidator(InitGenesisValidator(args)) => {
                 utils::init_genesis_validator(global_args, args)
             }
+            Utils::VerifyGenesis(VerifyGenesis(args)) => {
+                utils::verify_genesis(global_args, args)
+            }
         },
     }
     Ok(())
@@ -19,6 +19,17 @@ pub fn main() -> Result<()> {
                 ledger::reset(ctx.config.ledger)
                     .wrap_err("Failed to reset Anoma node")?;
             }
+            cmds::Ledger::Tail(cmds::LedgerTail(args)) => {
+                ledger::tail(ctx.config.ledger, args);
+            }
+            cmds::Ledger::ExportEvents(cmds::LedgerExportEvents(args)) => {
+                ledger::export_events(ctx.config.ledger, args)
+                    .wrap_err("Failed to export the events")?;
+            }
+            cmds::Ledger::Compact(_) => {
+                ledger::compact(ctx.config.ledger)
+                    .wrap_err("Failed to compact the DB")?;
+            }
         },
         cmds::AnomaNode::Gossip(sub) => match sub {
             cmds::Gossip::Run(cmds::GossipRun(args::GossipRun {
@@ -41,21 +41,54 @@ pub fn main() -> Result<()> {
 }
 
 /// Generate a new keypair and derive implicit address from it and store them in
-/// the wallet.
+/// the wallet. The public key is printed in the requested format.
 fn key_and_address_gen(
     ctx: Context,
     args::KeyAndAddressGen {
         alias,
         unsafe_dont_encrypt,
+        format,
+        force,
     }: args::KeyAndAddressGen,
 ) {
     let mut wallet = ctx.wallet;
-    let (alias, _key) = wallet.gen_key(alias, unsafe_dont_encrypt);
+    if let Some(alias) = alias.as_ref() {
+        if wallet.contains_alias(alias) {
+            if force {
+                wallet.remove_alias(alias);
+            } else {
+                eprintln!(
+                    "A key or an address with alias \"{}\" already exists. \
+                     Use --force to overwrite it.",
+                    alias
+                );
+                cli::safe_exit(1)
+            }
+        }
+    }
+    let (alias, key) = wallet.gen_key(alias, unsafe_dont_encrypt);
     wallet.save().unwrap_or_else(|err| eprintln!("{}", err));
-    println!(
-        "Successfully added a key and an address with alias: \"{}\"",
-        alias
-    );
+    let public_key = key.ref_to();
+    match format {
+        args::KeyFormat::Hex => {
+            println!(
+                "Successfully added a key and an address with alias: \"{}\"",
+                alias
+            );
+            println!("Public key: {}", public_key);
+        }
+        args::KeyFormat::Json => {
+            let address = wallet
+                .find_address(&alias)
+                .expect("The generated address must be in the wallet");
+            let json = serde_json::json!({
+                "alias": alias,
+                "public_key": public_key.to_string(),
+                "address": address.encode(),
+            });
+            println!("{}", json);
+        }
+    }
 }
 
 /// Find a keypair in the wallet store.
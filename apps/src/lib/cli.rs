@@ -7,11 +7,12 @@
 //! respectively.
 
 pub mod context;
+pub mod output;
 mod utils;
 
 use clap::{crate_authors, AppSettings, ArgMatches};
-pub use utils::safe_exit;
 use utils::*;
+pub use utils::{safe_exit, set_in_session};
 
 pub use self::context::Context;
 
@@ -170,9 +171,20 @@ pub mod cmds {
                 // Simple transactions
                 .subcommand(TxCustom::def().display_order(1))
                 .subcommand(TxTransfer::def().display_order(1))
+                .subcommand(TxTokenAdmin::def().display_order(1))
+                .subcommand(TxMint::def().display_order(1))
+                .subcommand(TxTransferLists::def().display_order(1))
                 .subcommand(TxUpdateVp::def().display_order(1))
                 .subcommand(TxInitAccount::def().display_order(1))
                 .subcommand(TxInitValidator::def().display_order(1))
+                .subcommand(TxBundle::def().display_order(1))
+                .subcommand(BroadcastTx::def().display_order(1))
+                .subcommand(DecodeTx::def().display_order(1))
+                .subcommand(ConvertAmount::def().display_order(1))
+                .subcommand(ResolveAlias::def().display_order(1))
+                .subcommand(ResolveAddress::def().display_order(1))
+                .subcommand(SignMessage::def().display_order(1))
+                .subcommand(VerifyMessage::def().display_order(1))
                 // Nft transactions
                 .subcommand(TxInitNft::def().display_order(1))
                 .subcommand(TxMintNft::def().display_order(1))
@@ -183,15 +195,36 @@ pub mod cmds {
                 .subcommand(Bond::def().display_order(2))
                 .subcommand(Unbond::def().display_order(2))
                 .subcommand(Withdraw::def().display_order(2))
+                .subcommand(TxChangeCommissionRate::def().display_order(2))
+                .subcommand(TxEditValidator::def().display_order(2))
+                .subcommand(TxClaimRewards::def().display_order(2))
+                .subcommand(TxUnjailValidator::def().display_order(2))
+                .subcommand(TxRotateConsensusKey::def().display_order(2))
                 // Queries
                 .subcommand(QueryEpoch::def().display_order(3))
+                .subcommand(QueryBaseFee::def().display_order(3))
+                .subcommand(QueryEpochSeed::def().display_order(3))
                 .subcommand(QueryBalance::def().display_order(3))
+                .subcommand(QueryBalances::def().display_order(3))
                 .subcommand(QueryBonds::def().display_order(3))
                 .subcommand(QueryVotingPower::def().display_order(3))
+                .subcommand(QueryValidatorSet::def().display_order(3))
                 .subcommand(QuerySlashes::def().display_order(3))
+                .subcommand(QueryCommissionRate::def().display_order(3))
+                .subcommand(QueryValidator::def().display_order(3))
+                .subcommand(QueryRewards::def().display_order(3))
                 .subcommand(QueryResult::def().display_order(3))
                 .subcommand(QueryRawBytes::def().display_order(3))
+                .subcommand(QueryMetadata::def().display_order(3))
                 .subcommand(QueryProposal::def().display_order(3))
+                .subcommand(QueryProposalCode::def().display_order(3))
+                .subcommand(QueryProtocolParameters::def().display_order(3))
+                .subcommand(SimulateBlock::def().display_order(3))
+                .subcommand(QueryEvents::def().display_order(3))
+                .subcommand(QueryMempool::def().display_order(3))
+                .subcommand(QueryNodeStatus::def().display_order(3))
+                .subcommand(QueryStorageSize::def().display_order(3))
+                .subcommand(Repl::def().display_order(3))
                 // Intents
                 .subcommand(Intent::def().display_order(4))
                 .subcommand(SubscribeTopic::def().display_order(4))
@@ -203,10 +236,22 @@ pub mod cmds {
             use AnomaClientWithContext::*;
             let tx_custom = Self::parse_with_ctx(matches, TxCustom);
             let tx_transfer = Self::parse_with_ctx(matches, TxTransfer);
+            let tx_token_admin = Self::parse_with_ctx(matches, TxTokenAdmin);
+            let tx_mint = Self::parse_with_ctx(matches, TxMint);
+            let tx_transfer_lists =
+                Self::parse_with_ctx(matches, TxTransferLists);
             let tx_update_vp = Self::parse_with_ctx(matches, TxUpdateVp);
             let tx_init_account = Self::parse_with_ctx(matches, TxInitAccount);
             let tx_init_validator =
                 Self::parse_with_ctx(matches, TxInitValidator);
+            let tx_bundle = Self::parse_with_ctx(matches, TxBundle);
+            let broadcast_tx = Self::parse_with_ctx(matches, BroadcastTx);
+            let decode_tx = Self::parse_with_ctx(matches, DecodeTx);
+            let convert_amount = Self::parse_with_ctx(matches, ConvertAmount);
+            let resolve_alias = Self::parse_with_ctx(matches, ResolveAlias);
+            let resolve_address = Self::parse_with_ctx(matches, ResolveAddress);
+            let sign_message = Self::parse_with_ctx(matches, SignMessage);
+            let verify_message = Self::parse_with_ctx(matches, VerifyMessage);
             let tx_nft_create = Self::parse_with_ctx(matches, TxInitNft);
             let tx_nft_mint = Self::parse_with_ctx(matches, TxMintNft);
             let tx_init_proposal =
@@ -216,23 +261,67 @@ pub mod cmds {
             let bond = Self::parse_with_ctx(matches, Bond);
             let unbond = Self::parse_with_ctx(matches, Unbond);
             let withdraw = Self::parse_with_ctx(matches, Withdraw);
+            let tx_change_commission_rate =
+                Self::parse_with_ctx(matches, TxChangeCommissionRate);
+            let tx_edit_validator =
+                Self::parse_with_ctx(matches, TxEditValidator);
+            let tx_claim_rewards =
+                Self::parse_with_ctx(matches, TxClaimRewards);
+            let tx_unjail_validator =
+                Self::parse_with_ctx(matches, TxUnjailValidator);
+            let tx_rotate_consensus_key =
+                Self::parse_with_ctx(matches, TxRotateConsensusKey);
             let query_epoch = Self::parse_with_ctx(matches, QueryEpoch);
+            let query_base_fee = Self::parse_with_ctx(matches, QueryBaseFee);
+            let query_epoch_seed =
+                Self::parse_with_ctx(matches, QueryEpochSeed);
             let query_balance = Self::parse_with_ctx(matches, QueryBalance);
+            let query_balances = Self::parse_with_ctx(matches, QueryBalances);
             let query_bonds = Self::parse_with_ctx(matches, QueryBonds);
             let query_voting_power =
                 Self::parse_with_ctx(matches, QueryVotingPower);
+            let query_validator_set =
+                Self::parse_with_ctx(matches, QueryValidatorSet);
             let query_slashes = Self::parse_with_ctx(matches, QuerySlashes);
+            let query_commission_rate =
+                Self::parse_with_ctx(matches, QueryCommissionRate);
+            let query_validator = Self::parse_with_ctx(matches, QueryValidator);
+            let query_rewards = Self::parse_with_ctx(matches, QueryRewards);
             let query_result = Self::parse_with_ctx(matches, QueryResult);
             let query_raw_bytes = Self::parse_with_ctx(matches, QueryRawBytes);
+            let query_metadata = Self::parse_with_ctx(matches, QueryMetadata);
             let query_proposal = Self::parse_with_ctx(matches, QueryProposal);
+            let query_proposal_code =
+                Self::parse_with_ctx(matches, QueryProposalCode);
+            let query_protocol_parameters =
+                Self::parse_with_ctx(matches, QueryProtocolParameters);
+            let simulate_block = Self::parse_with_ctx(matches, SimulateBlock);
+            let query_events = Self::parse_with_ctx(matches, QueryEvents);
+            let query_mempool = Self::parse_with_ctx(matches, QueryMempool);
+            let query_node_status =
+                Self::parse_with_ctx(matches, QueryNodeStatus);
+            let query_storage_size =
+                Self::parse_with_ctx(matches, QueryStorageSize);
+            let repl = Self::parse_with_ctx(matches, Repl);
             let intent = Self::parse_with_ctx(matches, Intent);
             let subscribe_topic = Self::parse_with_ctx(matches, SubscribeTopic);
             let utils = SubCmd::parse(matches).map(Self::WithoutContext);
             tx_custom
                 .or(tx_transfer)
+                .or(tx_token_admin)
+                .or(tx_mint)
+                .or(tx_transfer_lists)
                 .or(tx_update_vp)
                 .or(tx_init_account)
                 .or(tx_init_validator)
+                .or(tx_bundle)
+                .or(broadcast_tx)
+                .or(decode_tx)
+                .or(convert_amount)
+                .or(resolve_alias)
+                .or(resolve_address)
+                .or(sign_message)
+                .or(verify_message)
                 .or(tx_nft_create)
                 .or(tx_nft_mint)
                 .or(tx_init_proposal)
@@ -240,14 +329,35 @@ pub mod cmds {
                 .or(bond)
                 .or(unbond)
                 .or(withdraw)
+                .or(tx_change_commission_rate)
+                .or(tx_edit_validator)
+                .or(tx_claim_rewards)
+                .or(tx_unjail_validator)
+                .or(tx_rotate_consensus_key)
                 .or(query_epoch)
+                .or(query_base_fee)
+                .or(query_epoch_seed)
                 .or(query_balance)
+                .or(query_balances)
                 .or(query_bonds)
                 .or(query_voting_power)
+                .or(query_validator_set)
                 .or(query_slashes)
+                .or(query_commission_rate)
+                .or(query_validator)
+                .or(query_rewards)
                 .or(query_result)
                 .or(query_raw_bytes)
+                .or(query_metadata)
                 .or(query_proposal)
+                .or(query_proposal_code)
+                .or(query_protocol_parameters)
+                .or(simulate_block)
+                .or(query_events)
+                .or(query_mempool)
+                .or(query_node_status)
+                .or(query_storage_size)
+                .or(repl)
                 .or(intent)
                 .or(subscribe_topic)
                 .or(utils)
@@ -288,10 +398,21 @@ pub mod cmds {
         // Ledger cmds
         TxCustom(TxCustom),
         TxTransfer(TxTransfer),
+        TxTokenAdmin(TxTokenAdmin),
+        TxMint(TxMint),
+        TxTransferLists(TxTransferLists),
         QueryResult(QueryResult),
         TxUpdateVp(TxUpdateVp),
         TxInitAccount(TxInitAccount),
         TxInitValidator(TxInitValidator),
+        TxBundle(TxBundle),
+        BroadcastTx(BroadcastTx),
+        DecodeTx(DecodeTx),
+        ConvertAmount(ConvertAmount),
+        ResolveAlias(ResolveAlias),
+        ResolveAddress(ResolveAddress),
+        SignMessage(SignMessage),
+        VerifyMessage(VerifyMessage),
         TxInitNft(TxInitNft),
         TxMintNft(TxMintNft),
         TxInitProposal(TxInitProposal),
@@ -299,13 +420,34 @@ pub mod cmds {
         Bond(Bond),
         Unbond(Unbond),
         Withdraw(Withdraw),
+        TxChangeCommissionRate(TxChangeCommissionRate),
+        TxEditValidator(TxEditValidator),
+        TxClaimRewards(TxClaimRewards),
+        TxUnjailValidator(TxUnjailValidator),
+        TxRotateConsensusKey(TxRotateConsensusKey),
         QueryEpoch(QueryEpoch),
+        QueryBaseFee(QueryBaseFee),
+        QueryEpochSeed(QueryEpochSeed),
         QueryBalance(QueryBalance),
+        QueryBalances(QueryBalances),
         QueryBonds(QueryBonds),
         QueryVotingPower(QueryVotingPower),
+        QueryValidatorSet(QueryValidatorSet),
         QuerySlashes(QuerySlashes),
+        QueryCommissionRate(QueryCommissionRate),
+        QueryValidator(QueryValidator),
+        QueryRewards(QueryRewards),
         QueryRawBytes(QueryRawBytes),
+        QueryMetadata(QueryMetadata),
         QueryProposal(QueryProposal),
+        QueryProposalCode(QueryProposalCode),
+        QueryProtocolParameters(QueryProtocolParameters),
+        SimulateBlock(SimulateBlock),
+        QueryEvents(QueryEvents),
+        QueryMempool(QueryMempool),
+        QueryNodeStatus(QueryNodeStatus),
+        QueryStorageSize(QueryStorageSize),
+        Repl(Repl),
         // Gossip cmds
         Intent(Intent),
         SubscribeTopic(SubscribeTopic),
@@ -374,6 +516,7 @@ pub mod cmds {
 
         fn def() -> App {
             App::new(Self::CMD)
+                .alias("keypair")
                 .about(
                     "Keypair management, including methods to generate and \
                      look-up keys.",
@@ -588,6 +731,9 @@ pub mod cmds {
     pub enum Ledger {
         Run(LedgerRun),
         Reset(LedgerReset),
+        Tail(LedgerTail),
+        ExportEvents(LedgerExportEvents),
+        Compact(LedgerCompact),
     }
 
     impl SubCmd for Ledger {
@@ -597,7 +743,14 @@ pub mod cmds {
             matches.subcommand_matches(Self::CMD).and_then(|matches| {
                 let run = SubCmd::parse(matches).map(Self::Run);
                 let reset = SubCmd::parse(matches).map(Self::Reset);
+                let tail = SubCmd::parse(matches).map(Self::Tail);
+                let export_events =
+                    SubCmd::parse(matches).map(Self::ExportEvents);
+                let compact = SubCmd::parse(matches).map(Self::Compact);
                 run.or(reset)
+                    .or(tail)
+                    .or(export_events)
+                    .or(compact)
                     // The `run` command is the default if no sub-command given
                     .or(Some(Self::Run(LedgerRun)))
             })
@@ -611,6 +764,9 @@ pub mod cmds {
                 )
                 .subcommand(LedgerRun::def())
                 .subcommand(LedgerReset::def())
+                .subcommand(LedgerTail::def())
+                .subcommand(LedgerExportEvents::def())
+                .subcommand(LedgerCompact::def())
         }
     }
 
@@ -647,6 +803,70 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct LedgerTail(pub args::LedgerTail);
+
+    impl SubCmd for LedgerTail {
+        const CMD: &'static str = "tail";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| LedgerTail(args::LedgerTail::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Print a line for every block committed by the ledger \
+                     node.",
+                )
+                .add_args::<args::LedgerTail>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerExportEvents(pub args::LedgerExportEvents);
+
+    impl SubCmd for LedgerExportEvents {
+        const CMD: &'static str = "export-events";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                LedgerExportEvents(args::LedgerExportEvents::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Export the events of the committed blocks from the \
+                     ledger node's storage as JSON lines. The node must be \
+                     stopped.",
+                )
+                .add_args::<args::LedgerExportEvents>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerCompact;
+
+    impl SubCmd for LedgerCompact {
+        const CMD: &'static str = "compact";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|_matches| Self)
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD).about(
+                "Compact the ledger node's storage to reclaim the disk space \
+                 of the deleted and overwritten data, e.g. of the pruned \
+                 block events. The node must be stopped.",
+            )
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub enum Gossip {
         Run(GossipRun),
@@ -797,346 +1017,1093 @@ pub mod cmds {
     }
 
     #[derive(Clone, Debug)]
-    pub struct TxCustom(pub args::TxCustom);
+    pub struct QueryProposalCode(pub args::QueryProposalCode);
 
-    impl SubCmd for TxCustom {
-        const CMD: &'static str = "tx";
+    impl SubCmd for QueryProposalCode {
+        const CMD: &'static str = "query-proposal-code";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches
-                .subcommand_matches(Self::CMD)
-                .map(|matches| TxCustom(args::TxCustom::parse(matches)))
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryProposalCode(args::QueryProposalCode::parse(matches))
+            })
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Send a transaction with custom WASM code.")
-                .add_args::<args::TxCustom>()
+                .about(
+                    "Query the wasm code executed by a proposal if it's \
+                     accepted. Prints the code's hash and size and optionally \
+                     writes the code to a file, to review it before voting.",
+                )
+                .add_args::<args::QueryProposalCode>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct TxTransfer(pub args::TxTransfer);
+    pub struct QueryProtocolParameters(pub args::Query);
 
-    impl SubCmd for TxTransfer {
-        const CMD: &'static str = "transfer";
+    impl SubCmd for QueryProtocolParameters {
+        const CMD: &'static str = "query-params";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches
-                .subcommand_matches(Self::CMD)
-                .map(|matches| TxTransfer(args::TxTransfer::parse(matches)))
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryProtocolParameters(args::Query::parse(matches))
+            })
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Send a signed transfer transaction.")
-                .add_args::<args::TxTransfer>()
+                .about("Query the protocol, PoS and governance parameters.")
+                .add_args::<args::Query>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct TxUpdateVp(pub args::TxUpdateVp);
+    pub struct SimulateBlock(pub args::SimulateBlock);
 
-    impl SubCmd for TxUpdateVp {
-        const CMD: &'static str = "update";
+    impl SubCmd for SimulateBlock {
+        const CMD: &'static str = "simulate-block";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches
-                .subcommand_matches(Self::CMD)
-                .map(|matches| TxUpdateVp(args::TxUpdateVp::parse(matches)))
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                SimulateBlock(args::SimulateBlock::parse(matches))
+            })
         }
 
         fn def() -> App {
             App::new(Self::CMD)
                 .about(
-                    "Send a signed transaction to update account's validity \
-                     predicate.",
+                    "Simulate applying a block of signed transactions on top \
+                     of the current state, without committing them, and print \
+                     their results and the resulting merkle root.",
                 )
-                .add_args::<args::TxUpdateVp>()
+                .add_args::<args::SimulateBlock>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct TxInitAccount(pub args::TxInitAccount);
+    pub struct QueryEvents(pub args::QueryEvents);
 
-    impl SubCmd for TxInitAccount {
-        const CMD: &'static str = "init-account";
+    impl SubCmd for QueryEvents {
+        const CMD: &'static str = "query-events";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches.subcommand_matches(Self::CMD).map(|matches| {
-                TxInitAccount(args::TxInitAccount::parse(matches))
-            })
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryEvents(args::QueryEvents::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Query the events emitted in a committed block.")
+                .add_args::<args::QueryEvents>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryMempool(pub args::QueryMempool);
+
+    impl SubCmd for QueryMempool {
+        const CMD: &'static str = "query-mempool";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryMempool(args::QueryMempool::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
                 .about(
-                    "Send a signed transaction to create a new established \
-                     account.",
+                    "Query the number of transactions in the node's mempool \
+                     and the total gas they would consume.",
                 )
-                .add_args::<args::TxInitAccount>()
+                .add_args::<args::QueryMempool>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct TxInitValidator(pub args::TxInitValidator);
+    pub struct QueryStorageSize(pub args::QueryStorageSize);
 
-    impl SubCmd for TxInitValidator {
-        const CMD: &'static str = "init-validator";
+    impl SubCmd for QueryStorageSize {
+        const CMD: &'static str = "query-storage-size";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches.subcommand_matches(Self::CMD).map(|matches| {
-                TxInitValidator(args::TxInitValidator::parse(matches))
+                QueryStorageSize(args::QueryStorageSize::parse(matches))
             })
         }
 
         fn def() -> App {
             App::new(Self::CMD)
                 .about(
-                    "Send a signed transaction to create a new validator and \
-                     its staking reward account.",
+                    "Query the number of keys in the node's last committed \
+                     storage and the sizes of their values, grouped by the \
+                     kind of the keys, and the largest values.",
                 )
-                .add_args::<args::TxInitValidator>()
+                .add_args::<args::QueryStorageSize>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct Bond(pub args::Bond);
+    pub struct TxCustom(pub args::TxCustom);
 
-    impl SubCmd for Bond {
-        const CMD: &'static str = "bond";
+    impl SubCmd for TxCustom {
+        const CMD: &'static str = "tx";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| Bond(args::Bond::parse(matches)))
+                .map(|matches| TxCustom(args::TxCustom::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Bond tokens in PoS system.")
-                .add_args::<args::Bond>()
+                .about("Send a transaction with custom WASM code.")
+                .add_args::<args::TxCustom>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct Unbond(pub args::Unbond);
+    pub struct TxTransfer(pub args::TxTransfer);
 
-    impl SubCmd for Unbond {
-        const CMD: &'static str = "unbond";
+    impl SubCmd for TxTransfer {
+        const CMD: &'static str = "transfer";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| Unbond(args::Unbond::parse(matches)))
+                .map(|matches| TxTransfer(args::TxTransfer::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Unbond tokens from a PoS bond.")
-                .add_args::<args::Unbond>()
+                .about("Send a signed transfer transaction.")
+                .add_args::<args::TxTransfer>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct Withdraw(pub args::Withdraw);
+    pub struct TxTokenAdmin(pub args::TxTokenAdmin);
 
-    impl SubCmd for Withdraw {
-        const CMD: &'static str = "withdraw";
+    impl SubCmd for TxTokenAdmin {
+        const CMD: &'static str = "token-admin";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| Withdraw(args::Withdraw::parse(matches)))
+                .map(|matches| TxTokenAdmin(args::TxTokenAdmin::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Withdraw tokens from previously unbonded PoS bond.")
-                .add_args::<args::Withdraw>()
+                .about(
+                    "Freeze or unfreeze all the transfers of a token. Must be \
+                     signed by the token's admin.",
+                )
+                .add_args::<args::TxTokenAdmin>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct QueryEpoch(pub args::Query);
+    pub struct TxMint(pub args::TxMint);
 
-    impl SubCmd for QueryEpoch {
-        const CMD: &'static str = "epoch";
+    impl SubCmd for TxMint {
+        const CMD: &'static str = "mint";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| QueryEpoch(args::Query::parse(matches)))
+                .map(|matches| TxMint(args::TxMint::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Query the epoch of the last committed block.")
-                .add_args::<args::Query>()
+                .about(
+                    "Mint new tokens to a target's balance. Must be signed by \
+                     the token's minter.",
+                )
+                .add_args::<args::TxMint>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct QueryBalance(pub args::QueryBalance);
+    pub struct TxTransferLists(pub args::TxTransferLists);
 
-    impl SubCmd for QueryBalance {
-        const CMD: &'static str = "balance";
+    impl SubCmd for TxTransferLists {
+        const CMD: &'static str = "transfer-lists";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches
-                .subcommand_matches(Self::CMD)
-                .map(|matches| QueryBalance(args::QueryBalance::parse(matches)))
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxTransferLists(args::TxTransferLists::parse(matches))
+            })
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Query balance(s) of tokens.")
-                .add_args::<args::QueryBalance>()
+                .about(
+                    "Set the transfer allow and deny lists of an account, \
+                     which restrict the counterparties of its transfers. Must \
+                     be signed by the account.",
+                )
+                .add_args::<args::TxTransferLists>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct QueryBonds(pub args::QueryBonds);
+    pub struct TxBundle(pub args::TxBundle);
 
-    impl SubCmd for QueryBonds {
-        const CMD: &'static str = "bonds";
+    impl SubCmd for TxBundle {
+        const CMD: &'static str = "submit-bundle";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| QueryBonds(args::QueryBonds::parse(matches)))
+                .map(|matches| TxBundle(args::TxBundle::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Query PoS bond(s).")
-                .add_args::<args::QueryBonds>()
+                .about(
+                    "Submit a bundle of signed transactions that are applied \
+                     atomically: either all of them are applied in order in \
+                     the same block, or none of them is.",
+                )
+                .add_args::<args::TxBundle>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct QueryVotingPower(pub args::QueryVotingPower);
+    pub struct BroadcastTx(pub args::BroadcastTx);
 
-    impl SubCmd for QueryVotingPower {
-        const CMD: &'static str = "voting-power";
+    impl SubCmd for BroadcastTx {
+        const CMD: &'static str = "broadcast-tx";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches.subcommand_matches(Self::CMD).map(|matches| {
-                QueryVotingPower(args::QueryVotingPower::parse(matches))
-            })
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| BroadcastTx(args::BroadcastTx::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Query PoS voting power.")
-                .add_args::<args::QueryVotingPower>()
+                .about(
+                    "Broadcast a signed transaction previously written to a \
+                     file with the `--raw-tx-out` argument.",
+                )
+                .add_args::<args::BroadcastTx>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct QuerySlashes(pub args::QuerySlashes);
+    pub struct DecodeTx(pub args::DecodeTx);
 
-    impl SubCmd for QuerySlashes {
-        const CMD: &'static str = "slashes";
+    impl SubCmd for DecodeTx {
+        const CMD: &'static str = "decode-tx";
 
-        fn parse(matches: &ArgMatches) -> Option<Self>
-        where
-            Self: Sized,
-        {
+        fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| QuerySlashes(args::QuerySlashes::parse(matches)))
+                .map(|matches| DecodeTx(args::DecodeTx::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Query PoS applied slashes.")
-                .add_args::<args::QuerySlashes>()
+                .about(
+                    "Decode a serialized transaction, e.g. one written to a \
+                     file with the `--raw-tx-out` argument, and print its \
+                     contents. This doesn't connect to a ledger node.",
+                )
+                .add_args::<args::DecodeTx>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct QueryRawBytes(pub args::QueryRawBytes);
+    pub struct ConvertAmount(pub args::ConvertAmount);
 
-    impl SubCmd for QueryRawBytes {
-        const CMD: &'static str = "query-bytes";
+    impl SubCmd for ConvertAmount {
+        const CMD: &'static str = "convert-amount";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches.subcommand_matches(Self::CMD).map(|matches| {
-                QueryRawBytes(args::QueryRawBytes::parse(matches))
+                ConvertAmount(args::ConvertAmount::parse(matches))
             })
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Query the raw bytes of a given storage key")
-                .add_args::<args::QueryRawBytes>()
+                .about(
+                    "Convert a token amount between its human readable and \
+                     raw representations. This doesn't connect to a ledger \
+                     node.",
+                )
+                .add_args::<args::ConvertAmount>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct TxInitNft(pub args::NftCreate);
+    pub struct ResolveAlias(pub args::ResolveAlias);
 
-    impl SubCmd for TxInitNft {
-        const CMD: &'static str = "init-nft";
+    impl SubCmd for ResolveAlias {
+        const CMD: &'static str = "resolve-alias";
 
-        fn parse(matches: &ArgMatches) -> Option<Self>
-        where
-            Self: Sized,
-        {
+        fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| TxInitNft(args::NftCreate::parse(matches)))
+                .map(|matches| ResolveAlias(args::ResolveAlias::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Create a new NFT.")
-                .add_args::<args::NftCreate>()
+                .about(
+                    "Print the address stored in the wallet with the given \
+                     alias. This doesn't connect to a ledger node.",
+                )
+                .add_args::<args::ResolveAlias>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct TxMintNft(pub args::NftMint);
+    pub struct ResolveAddress(pub args::ResolveAddress);
 
-    impl SubCmd for TxMintNft {
-        const CMD: &'static str = "mint-nft";
+    impl SubCmd for ResolveAddress {
+        const CMD: &'static str = "resolve-address";
 
-        fn parse(matches: &ArgMatches) -> Option<Self>
-        where
-            Self: Sized,
-        {
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                ResolveAddress(args::ResolveAddress::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Print the alias with which the given address is stored \
+                     in the wallet, or \"unknown\" if it's not stored. This \
+                     doesn't connect to a ledger node.",
+                )
+                .add_args::<args::ResolveAddress>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct SignMessage(pub args::SignMessage);
+
+    impl SubCmd for SignMessage {
+        const CMD: &'static str = "sign-message";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| TxMintNft(args::NftMint::parse(matches)))
+                .map(|matches| SignMessage(args::SignMessage::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Mint new NFT tokens.")
-                .add_args::<args::NftMint>()
+                .about(
+                    "Sign an off-chain message with the key of an account, \
+                     e.g. to authenticate the account to a dApp. The \
+                     signature can't be used to sign a transaction.",
+                )
+                .add_args::<args::SignMessage>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct TxInitProposal(pub args::InitProposal);
+    pub struct VerifyMessage(pub args::VerifyMessage);
 
-    impl SubCmd for TxInitProposal {
-        const CMD: &'static str = "init-proposal";
+    impl SubCmd for VerifyMessage {
+        const CMD: &'static str = "verify-message";
 
-        fn parse(matches: &ArgMatches) -> Option<Self>
-        where
-            Self: Sized,
-        {
+        fn parse(matches: &ArgMatches) -> Option<Self> {
             matches.subcommand_matches(Self::CMD).map(|matches| {
-                TxInitProposal(args::InitProposal::parse(matches))
+                VerifyMessage(args::VerifyMessage::parse(matches))
             })
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Create a new proposal.")
-                .add_args::<args::InitProposal>()
+                .about(
+                    "Verify the signature of an off-chain message made with \
+                     the `sign-message` command against the public key of an \
+                     account.",
+                )
+                .add_args::<args::VerifyMessage>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxUpdateVp(pub args::TxUpdateVp);
+
+    impl SubCmd for TxUpdateVp {
+        const CMD: &'static str = "update";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| TxUpdateVp(args::TxUpdateVp::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Send a signed transaction to update account's validity \
+                     predicate.",
+                )
+                .add_args::<args::TxUpdateVp>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxInitAccount(pub args::TxInitAccount);
+
+    impl SubCmd for TxInitAccount {
+        const CMD: &'static str = "init-account";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxInitAccount(args::TxInitAccount::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Send a signed transaction to create a new established \
+                     account.",
+                )
+                .add_args::<args::TxInitAccount>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxInitValidator(pub args::TxInitValidator);
+
+    impl SubCmd for TxInitValidator {
+        const CMD: &'static str = "init-validator";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxInitValidator(args::TxInitValidator::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Send a signed transaction to create a new validator and \
+                     its staking reward account.",
+                )
+                .add_args::<args::TxInitValidator>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Bond(pub args::Bond);
+
+    impl SubCmd for Bond {
+        const CMD: &'static str = "bond";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Bond(args::Bond::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Bond tokens in PoS system.")
+                .add_args::<args::Bond>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Unbond(pub args::Unbond);
+
+    impl SubCmd for Unbond {
+        const CMD: &'static str = "unbond";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Unbond(args::Unbond::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Unbond tokens from a PoS bond.")
+                .add_args::<args::Unbond>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Withdraw(pub args::Withdraw);
+
+    impl SubCmd for Withdraw {
+        const CMD: &'static str = "withdraw";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Withdraw(args::Withdraw::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Withdraw tokens from previously unbonded PoS bond.")
+                .add_args::<args::Withdraw>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxChangeCommissionRate(pub args::TxChangeCommissionRate);
+
+    impl SubCmd for TxChangeCommissionRate {
+        const CMD: &'static str = "change-commission-rate";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxChangeCommissionRate(args::TxChangeCommissionRate::parse(
+                    matches,
+                ))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Change a validator's commission rate.")
+                .add_args::<args::TxChangeCommissionRate>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxEditValidator(pub args::TxEditValidator);
+
+    impl SubCmd for TxEditValidator {
+        const CMD: &'static str = "edit-validator";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxEditValidator(args::TxEditValidator::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Update a validator's metadata. Only the given fields are \
+                     changed.",
+                )
+                .add_args::<args::TxEditValidator>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxClaimRewards(pub args::ClaimRewards);
+
+    impl SubCmd for TxClaimRewards {
+        const CMD: &'static str = "claim-rewards";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxClaimRewards(args::ClaimRewards::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Claim the unclaimed staking rewards of a PoS bond.")
+                .add_args::<args::ClaimRewards>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxUnjailValidator(pub args::TxUnjailValidator);
+
+    impl SubCmd for TxUnjailValidator {
+        const CMD: &'static str = "unjail-validator";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxUnjailValidator(args::TxUnjailValidator::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Unjail a jailed validator once its jail duration is \
+                     over. The validator becomes a candidate again from the \
+                     pipeline epoch.",
+                )
+                .add_args::<args::TxUnjailValidator>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxRotateConsensusKey(pub args::TxRotateConsensusKey);
+
+    impl SubCmd for TxRotateConsensusKey {
+        const CMD: &'static str = "rotate-consensus-key";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxRotateConsensusKey(args::TxRotateConsensusKey::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Rotate a validator's consensus key. The new key is used \
+                     for the validator from the pipeline epoch, without \
+                     affecting the validator's account key.",
+                )
+                .add_args::<args::TxRotateConsensusKey>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryEpoch(pub args::Query);
+
+    impl SubCmd for QueryEpoch {
+        const CMD: &'static str = "epoch";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryEpoch(args::Query::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Query the epoch of the last committed block.")
+                .add_args::<args::Query>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryNodeStatus(pub args::Query);
+
+    impl SubCmd for QueryNodeStatus {
+        const CMD: &'static str = "node-status";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryNodeStatus(args::Query::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query the status of a node, including the genesis time \
+                     of the chain and whether it has been reached.",
+                )
+                .add_args::<args::Query>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Repl(pub args::Query);
+
+    impl SubCmd for Repl {
+        const CMD: &'static str = "repl";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Repl(args::Query::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Start an interactive session for queries, which reuses \
+                     the connection to the ledger node and the wallet across \
+                     the commands.",
+                )
+                .add_args::<args::Query>()
+        }
+    }
+
+    /// The commands of the client's interactive session, started with the
+    /// `repl` command
+    #[derive(Clone, Debug)]
+    pub enum ReplCmd {
+        QueryBalance(QueryBalance),
+        QueryRawBytes(QueryRawBytes),
+        QueryProposal(QueryProposal),
+        QueryEpoch(QueryEpoch),
+        QueryNodeStatus(QueryNodeStatus),
+    }
+
+    impl ReplCmd {
+        /// The names of the commands
+        pub const CMDS: [&'static str; 5] = [
+            QueryBalance::CMD,
+            QueryRawBytes::CMD,
+            QueryProposal::CMD,
+            QueryEpoch::CMD,
+            QueryNodeStatus::CMD,
+        ];
+    }
+
+    impl Cmd for ReplCmd {
+        fn add_sub(app: App) -> App {
+            app.subcommand(QueryBalance::def())
+                .subcommand(QueryRawBytes::def())
+                .subcommand(QueryProposal::def())
+                .subcommand(QueryEpoch::def())
+                .subcommand(QueryNodeStatus::def())
+        }
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            let query_balance = SubCmd::parse(matches).map(Self::QueryBalance);
+            let query_raw_bytes =
+                SubCmd::parse(matches).map(Self::QueryRawBytes);
+            let query_proposal =
+                SubCmd::parse(matches).map(Self::QueryProposal);
+            let query_epoch = SubCmd::parse(matches).map(Self::QueryEpoch);
+            let query_node_status =
+                SubCmd::parse(matches).map(Self::QueryNodeStatus);
+            query_balance
+                .or(query_raw_bytes)
+                .or(query_proposal)
+                .or(query_epoch)
+                .or(query_node_status)
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryBaseFee(pub args::Query);
+
+    impl SubCmd for QueryBaseFee {
+        const CMD: &'static str = "base-fee";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryBaseFee(args::Query::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query the base fee per unit of gas that transactions \
+                     must pay in the next block.",
+                )
+                .add_args::<args::Query>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryEpochSeed(pub args::Query);
+
+    impl SubCmd for QueryEpochSeed {
+        const CMD: &'static str = "query-epoch-seed";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryEpochSeed(args::Query::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query the random seed of the current epoch that is \
+                     accessible to validity predicates and transactions.",
+                )
+                .add_args::<args::Query>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryBalance(pub args::QueryBalance);
+
+    impl SubCmd for QueryBalance {
+        const CMD: &'static str = "balance";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryBalance(args::QueryBalance::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Query balance(s) of tokens.")
+                .add_args::<args::QueryBalance>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryBalances(pub args::QueryBalances);
+
+    impl SubCmd for QueryBalances {
+        const CMD: &'static str = "query-balances";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryBalances(args::QueryBalances::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query the token balances of all the addresses listed in \
+                     a file.",
+                )
+                .add_args::<args::QueryBalances>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryBonds(pub args::QueryBonds);
+
+    impl SubCmd for QueryBonds {
+        const CMD: &'static str = "bonds";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryBonds(args::QueryBonds::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Query PoS bond(s).")
+                .add_args::<args::QueryBonds>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryVotingPower(pub args::QueryVotingPower);
+
+    impl SubCmd for QueryVotingPower {
+        const CMD: &'static str = "voting-power";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryVotingPower(args::QueryVotingPower::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Query PoS voting power.")
+                .add_args::<args::QueryVotingPower>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryValidatorSet(pub args::QueryValidatorSet);
+
+    impl SubCmd for QueryValidatorSet {
+        const CMD: &'static str = "validator-set";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryValidatorSet(args::QueryValidatorSet::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query the consensus validator set of an epoch, i.e. the \
+                     active validators with their voting powers and consensus \
+                     keys as they are given to Tendermint.",
+                )
+                .add_args::<args::QueryValidatorSet>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QuerySlashes(pub args::QuerySlashes);
+
+    impl SubCmd for QuerySlashes {
+        const CMD: &'static str = "slashes";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QuerySlashes(args::QuerySlashes::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Query PoS applied slashes.")
+                .add_args::<args::QuerySlashes>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryCommissionRate(pub args::QueryCommissionRate);
+
+    impl SubCmd for QueryCommissionRate {
+        const CMD: &'static str = "query-commission";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryCommissionRate(args::QueryCommissionRate::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Query a PoS validator's commission rate.")
+                .add_args::<args::QueryCommissionRate>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryValidator(pub args::QueryValidator);
+
+    impl SubCmd for QueryValidator {
+        const CMD: &'static str = "query-validator";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryValidator(args::QueryValidator::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Query a PoS validator's metadata.")
+                .add_args::<args::QueryValidator>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryRewards(pub args::QueryRewards);
+
+    impl SubCmd for QueryRewards {
+        const CMD: &'static str = "query-rewards";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryRewards(args::QueryRewards::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Query unclaimed PoS staking rewards.")
+                .add_args::<args::QueryRewards>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryMetadata(pub args::QueryMetadata);
+
+    impl SubCmd for QueryMetadata {
+        const CMD: &'static str = "query-metadata";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryMetadata(args::QueryMetadata::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query the kind of an account: its VP template, whether \
+                     it's a validator and its governance role.",
+                )
+                .add_args::<args::QueryMetadata>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryRawBytes(pub args::QueryRawBytes);
+
+    impl SubCmd for QueryRawBytes {
+        const CMD: &'static str = "query-bytes";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryRawBytes(args::QueryRawBytes::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Query the raw bytes of a given storage key")
+                .add_args::<args::QueryRawBytes>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxInitNft(pub args::NftCreate);
+
+    impl SubCmd for TxInitNft {
+        const CMD: &'static str = "init-nft";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| TxInitNft(args::NftCreate::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Create a new NFT.")
+                .add_args::<args::NftCreate>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxMintNft(pub args::NftMint);
+
+    impl SubCmd for TxMintNft {
+        const CMD: &'static str = "mint-nft";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| TxMintNft(args::NftMint::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Mint new NFT tokens.")
+                .add_args::<args::NftMint>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxInitProposal(pub args::InitProposal);
+
+    impl SubCmd for TxInitProposal {
+        const CMD: &'static str = "init-proposal";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxInitProposal(args::InitProposal::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Create a new proposal.")
+                .add_args::<args::InitProposal>()
         }
     }
 
@@ -1208,6 +2175,7 @@ pub mod cmds {
         JoinNetwork(JoinNetwork),
         InitNetwork(InitNetwork),
         InitGenesisValidator(InitGenesisValidator),
+        VerifyGenesis(VerifyGenesis),
     }
 
     impl SubCmd for Utils {
@@ -1221,7 +2189,12 @@ pub mod cmds {
                     SubCmd::parse(matches).map(Self::InitNetwork);
                 let init_genesis =
                     SubCmd::parse(matches).map(Self::InitGenesisValidator);
-                join_network.or(init_network).or(init_genesis)
+                let verify_genesis =
+                    SubCmd::parse(matches).map(Self::VerifyGenesis);
+                join_network
+                    .or(init_network)
+                    .or(init_genesis)
+                    .or(verify_genesis)
             })
         }
 
@@ -1231,6 +2204,7 @@ pub mod cmds {
                 .subcommand(JoinNetwork::def())
                 .subcommand(InitNetwork::def())
                 .subcommand(InitGenesisValidator::def())
+                .subcommand(VerifyGenesis::def())
                 .setting(AppSettings::SubcommandRequiredElseHelp)
         }
     }
@@ -1295,6 +2269,28 @@ pub mod cmds {
                 .add_args::<args::InitGenesisValidator>()
         }
     }
+
+    #[derive(Clone, Debug)]
+    pub struct VerifyGenesis(pub args::VerifyGenesis);
+
+    impl SubCmd for VerifyGenesis {
+        const CMD: &'static str = "verify-genesis";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::VerifyGenesis::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Validate a genesis configuration file offline and report \
+                     every problem found in it.",
+                )
+                .add_args::<args::VerifyGenesis>()
+        }
+    }
 }
 
 pub mod args {
@@ -1311,8 +2307,9 @@ pub mod args {
     use anoma::types::governance::ProposalVote;
     use anoma::types::intent::{DecimalWrapper, Exchange};
     use anoma::types::key::*;
-    use anoma::types::storage::{self, Epoch};
+    use anoma::types::storage::{self, BlockHeight, Epoch};
     use anoma::types::token;
+    use anoma::types::transaction::pos::ValidatorMetadata;
     use anoma::types::transaction::GasLimit;
     use libp2p::Multiaddr;
     use serde::Deserialize;
@@ -1326,16 +2323,21 @@ pub mod args {
     use tendermint_stable::Timeout;
 
     use super::context::{WalletAddress, WalletKeypair, WalletPublicKey};
+    use super::output::OutputMode;
     use super::utils::*;
     use super::ArgMatches;
     use crate::config;
     use crate::config::TendermintMode;
 
     const ADDRESS: Arg<WalletAddress> = arg("address");
+    const ADDRESSES_FILE: Arg<PathBuf> = arg("addresses-file");
     const ALIAS_OPT: ArgOpt<String> = ALIAS.opt();
     const ALIAS: Arg<String> = arg("alias");
+    const ALL: ArgFlag = flag("all");
+    const ALLOW: ArgMulti<WalletAddress> = arg_multi("allow");
     const ALLOW_DUPLICATE_IP: ArgFlag = flag("allow-duplicate-ip");
     const AMOUNT: Arg<token::Amount> = arg("amount");
+    const ASSERT_MAX_GAS: ArgOpt<u64> = arg_opt("assert-max-gas");
     const BASE_DIR: ArgDefault<PathBuf> = arg_default(
         "base-dir",
         DefaultFn(|| match env::var("ANOMA_BASE_DIR") {
@@ -1343,30 +2345,47 @@ pub mod args {
             Err(_) => config::DEFAULT_BASE_DIR.into(),
         }),
     );
+    const BLOCK_HEIGHT: Arg<u64> = arg("height");
     const BROADCAST_ONLY: ArgFlag = flag("broadcast-only");
     const CHAIN_ID: Arg<ChainId> = arg("chain-id");
+    const CHECK: ArgFlag = flag("check");
     const CHAIN_ID_OPT: ArgOpt<ChainId> = CHAIN_ID.opt();
     const CHAIN_ID_PREFIX: Arg<ChainIdPrefix> = arg("chain-prefix");
     const CODE_PATH: Arg<PathBuf> = arg("code-path");
     const CODE_PATH_OPT: ArgOpt<PathBuf> = CODE_PATH.opt();
+    const COMMISSION_RATE: Arg<u64> = arg("commission-rate");
+    const COMMISSION_RATE_DEFAULT: ArgDefault<u64> =
+        COMMISSION_RATE.default(DefaultFn(|| 0));
     const CONSENSUS_TIMEOUT_COMMIT: ArgDefault<Timeout> = arg_default(
         "consensus-timeout-commit",
         DefaultFn(|| Timeout::from_str("1s").unwrap()),
     );
+    const CURSOR_PATH: ArgOpt<PathBuf> = arg_opt("cursor");
     const DATA_PATH_OPT: ArgOpt<PathBuf> = arg_opt("data-path");
     const DATA_PATH: Arg<PathBuf> = arg("data-path");
+    const DECIMALS: ArgOpt<u32> = arg_opt("decimals");
+    const DECODE_AS: ArgOpt<ValueType> = arg_opt("as");
     const DECRYPT: ArgFlag = flag("decrypt");
+    const DENY: ArgMulti<WalletAddress> = arg_multi("deny");
+    const DESCRIPTION: ArgOpt<String> = arg_opt("description");
     const DONT_ARCHIVE: ArgFlag = flag("dont-archive");
+    const DRY_RUN_AT_HEIGHT: ArgOpt<u64> = arg_opt("dry-run-at-height");
     const DRY_RUN_TX: ArgFlag = flag("dry-run");
     const EPOCH: ArgOpt<Epoch> = arg_opt("epoch");
-    const FEE_AMOUNT: ArgDefault<token::Amount> =
-        arg_default("fee-amount", DefaultFn(|| token::Amount::from(0)));
+    const EVENT_TYPE: ArgOpt<String> = arg_opt("type");
+    const EXPLAIN: ArgFlag = flag("explain");
+    const FEE_AMOUNT: ArgOpt<token::Amount> = arg_opt("fee-amount");
     const FEE_TOKEN: ArgDefaultFromCtx<WalletAddress> =
         arg_default_from_ctx("fee-token", DefaultFn(|| "XAN".into()));
     const FORCE: ArgFlag = flag("force");
+    const FREEZE: ArgFlag = flag("freeze");
+    const FROM_EPOCH: ArgOpt<Epoch> = arg_opt("from-epoch");
+    const FROM_HEIGHT: ArgOpt<u64> = arg_opt("from-height");
     const GAS_LIMIT: ArgDefault<token::Amount> =
         arg_default("gas-limit", DefaultFn(|| token::Amount::from(0)));
+    const GAS_PRICE: ArgOpt<token::Amount> = arg_opt("gas-price");
     const GENESIS_PATH: Arg<PathBuf> = arg("genesis-path");
+    const HUMAN_AMOUNT: ArgOpt<String> = arg_opt("human");
     const INTENT_GOSSIPER_ADDR: ArgDefault<SocketAddr> = arg_default(
         "intent-gossiper",
         DefaultFn(|| {
@@ -1384,39 +2403,67 @@ pub mod args {
         }));
 
     const LEDGER_ADDRESS: Arg<TendermintAddress> = arg("ledger-address");
+    const LIST_TXS: ArgFlag = flag("list-txs");
     const LOCALHOST: ArgFlag = flag("localhost");
     const MATCHMAKER_PATH: ArgOpt<PathBuf> = arg_opt("matchmaker-path");
     const MODE: ArgOpt<String> = arg_opt("mode");
+    const MONIKER: ArgOpt<String> = arg_opt("moniker");
     const MULTIADDR_OPT: ArgOpt<Multiaddr> = arg_opt("address");
     const NODE_OPT: ArgOpt<String> = arg_opt("node");
+    const MESSAGE: Arg<String> = arg("message");
     const NODE: Arg<String> = arg("node");
+    const NO_INTERACTIVE: ArgFlag = flag("no-interactive");
     const NFT_ADDRESS: Arg<Address> = arg("nft-address");
+    const OUTPUT_FORMAT: ArgDefault<OutputFormat> =
+        arg_default("format", DefaultFn(|| OutputFormat::Text));
+    const OUTPUT_MODE: ArgDefault<OutputMode> =
+        arg_default("output", DefaultFn(|| OutputMode::Text));
+    const KEY_FORMAT: ArgDefault<KeyFormat> =
+        arg_default("format", DefaultFn(|| KeyFormat::Hex));
+    const OUT_FILE_PATH: Arg<PathBuf> = arg("out");
+    const OUT_FILE_PATH_OPT: ArgOpt<PathBuf> = OUT_FILE_PATH.opt();
     const OWNER: ArgOpt<WalletAddress> = arg_opt("owner");
+    const PATH: Arg<PathBuf> = arg("path");
     const PROPOSAL_OFFLINE: ArgFlag = flag("offline");
     const PROTOCOL_KEY: ArgOpt<WalletPublicKey> = arg_opt("protocol-key");
+    const PROVE: ArgFlag = flag("prove");
     const PUBLIC_KEY: Arg<WalletPublicKey> = arg("public-key");
+    const RAW_TX_OUT: ArgOpt<PathBuf> = arg_opt("raw-tx-out");
     const PROPOSAL_ID: Arg<u64> = arg("proposal-id");
     const PROPOSAL_ID_OPT: ArgOpt<u64> = arg_opt("proposal-id");
     const PROPOSAL_VOTE: Arg<ProposalVote> = arg("vote");
     const RAW_ADDRESS: Arg<Address> = arg("address");
+    const RAW_AMOUNT: ArgOpt<u64> = arg_opt("raw");
     const RAW_PUBLIC_KEY_OPT: ArgOpt<common::PublicKey> = arg_opt("public-key");
     const REWARDS_CODE_PATH: ArgOpt<PathBuf> = arg_opt("rewards-code-path");
     const REWARDS_KEY: ArgOpt<WalletPublicKey> = arg_opt("rewards-key");
     const RPC_SOCKET_ADDR: ArgOpt<SocketAddr> = arg_opt("rpc");
-    const SIGNER: ArgOpt<WalletAddress> = arg_opt("signer");
+    const SECURITY_CONTACT: ArgOpt<String> = arg_opt("security-contact");
+    const SIGNATURE: Arg<common::Signature> = arg("signature");
+    const SIGNER: Arg<WalletAddress> = arg("signer");
+    const SIGNER_OPT: ArgOpt<WalletAddress> = SIGNER.opt();
+    const SINCE_EPOCH: ArgOpt<Epoch> = arg_opt("since-epoch");
     const SIGNING_KEY_OPT: ArgOpt<WalletKeypair> = SIGNING_KEY.opt();
     const SIGNING_KEY: Arg<WalletKeypair> = arg("signing-key");
     const SOURCE: Arg<WalletAddress> = arg("source");
     const SOURCE_OPT: ArgOpt<WalletAddress> = SOURCE.opt();
     const STORAGE_KEY: Arg<storage::Key> = arg("storage-key");
+    const STORAGE_PREFIX: ArgOpt<storage::Key> = arg_opt("prefix");
     const TARGET: Arg<WalletAddress> = arg("target");
+    const TO_EPOCH: ArgOpt<Epoch> = arg_opt("to-epoch");
+    const TO_HEIGHT: ArgOpt<u64> = arg_opt("to-height");
     const TO_STDOUT: ArgFlag = flag("stdout");
     const TOKEN_OPT: ArgOpt<WalletAddress> = TOKEN.opt();
     const TOKEN: Arg<WalletAddress> = arg("token");
     const TOPIC_OPT: ArgOpt<String> = arg_opt("topic");
     const TOPIC: Arg<String> = arg("topic");
+    const TRACE_TX: ArgFlag = flag("trace");
     const TX_CODE_PATH: ArgOpt<PathBuf> = arg_opt("tx-code-path");
+    const TX_FILE_OR_HEX: Arg<String> = arg("in");
     const TX_HASH: Arg<String> = arg("tx-hash");
+    const TX_PATH_IN: Arg<PathBuf> = arg("in");
+    const TXS_FILE: Arg<PathBuf> = arg("txs-file");
+    const UNFREEZE: ArgFlag = flag("unfreeze");
     const UNSAFE_DONT_ENCRYPT: ArgFlag = flag("unsafe-dont-encrypt");
     const UNSAFE_SHOW_SECRET: ArgFlag = flag("unsafe-show-secret");
     const VALIDATOR: Arg<WalletAddress> = arg("validator");
@@ -1427,8 +2474,126 @@ pub mod args {
         arg_opt("consensus-key");
     const VALIDATOR_CODE_PATH: ArgOpt<PathBuf> = arg_opt("validator-code-path");
     const VALUE: ArgOpt<String> = arg_opt("value");
+    const VP_TEMPLATE: ArgOpt<VpTemplate> = arg_opt("vp-template");
+    const WAIT_FOR_EPOCH: ArgOpt<Epoch> = arg_opt("wait-for-epoch");
+    const WAIT_TIMEOUT: ArgOpt<u64> = arg_opt("wait-timeout");
     const WASM_CHECKSUMS_PATH: Arg<PathBuf> = arg("wasm-checksums-path");
     const WASM_DIR: ArgOpt<PathBuf> = arg_opt("wasm-dir");
+    const WEBSITE: ArgOpt<String> = arg_opt("website");
+
+    /// A built-in validity predicate that can be used by its name instead of
+    /// a path to its WASM code
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum VpTemplate {
+        /// The default VP of user accounts
+        User,
+        /// The VP of fungible token accounts
+        Token,
+    }
+
+    impl VpTemplate {
+        /// The name of the template's WASM file in the WASM directory
+        pub fn wasm_file_name(&self) -> &'static str {
+            match self {
+                VpTemplate::User => "vp_user.wasm",
+                VpTemplate::Token => "vp_token.wasm",
+            }
+        }
+    }
+
+    impl FromStr for VpTemplate {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "user" => Ok(VpTemplate::User),
+                "token" => Ok(VpTemplate::Token),
+                _ => Err(format!(
+                    "Unknown VP template \"{}\", expected one of: user, token",
+                    s
+                )),
+            }
+        }
+    }
+
+    /// The format of a query's output
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum OutputFormat {
+        /// Human-readable text
+        Text,
+        /// Comma-separated values with a header row
+        Csv,
+    }
+
+    impl FromStr for OutputFormat {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "text" => Ok(OutputFormat::Text),
+                "csv" => Ok(OutputFormat::Csv),
+                _ => Err(format!(
+                    "Unknown output format \"{}\", expected one of: text, csv",
+                    s
+                )),
+            }
+        }
+    }
+
+    /// The type of a raw storage value, used to decode it for display
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum ValueType {
+        /// A token amount
+        Amount,
+        /// A public key
+        PublicKey,
+        /// The code of a validity predicate, displayed as its hash
+        VpHash,
+        /// An address
+        Address,
+    }
+
+    impl FromStr for ValueType {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "amount" => Ok(ValueType::Amount),
+                "pubkey" => Ok(ValueType::PublicKey),
+                "vp-hash" => Ok(ValueType::VpHash),
+                "address" => Ok(ValueType::Address),
+                _ => Err(format!(
+                    "Unknown value type \"{}\", expected one of: amount, \
+                     pubkey, vp-hash, address",
+                    s
+                )),
+            }
+        }
+    }
+
+    /// The format of a generated public key printed by the wallet
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum KeyFormat {
+        /// Hex string, as accepted by `--public-key` arguments
+        Hex,
+        /// JSON object with the alias, the public key and the address
+        Json,
+    }
+
+    impl FromStr for KeyFormat {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "hex" => Ok(KeyFormat::Hex),
+                "json" => Ok(KeyFormat::Json),
+                _ => Err(format!(
+                    "Unknown key format \"{}\", expected one of: hex, json",
+                    s
+                )),
+            }
+        }
+    }
 
     /// Global command arguments
     #[derive(Clone, Debug)]
@@ -1437,6 +2602,7 @@ pub mod args {
         pub base_dir: PathBuf,
         pub wasm_dir: Option<PathBuf>,
         pub mode: Option<TendermintMode>,
+        pub output: OutputMode,
     }
 
     impl Global {
@@ -1446,11 +2612,13 @@ pub mod args {
             let base_dir = BASE_DIR.parse(matches);
             let wasm_dir = WASM_DIR.parse(matches);
             let mode = MODE.parse(matches).map(TendermintMode::from);
+            let output = OUTPUT_MODE.parse(matches);
             Global {
                 chain_id,
                 base_dir,
                 wasm_dir,
                 mode,
+                output,
             }
         }
 
@@ -1477,6 +2645,12 @@ pub mod args {
                     "The mode in which to run Anoma. Options are \n\t * \
                      Validator (default)\n\t * Full\n\t * Seed",
                 ))
+                .arg(OUTPUT_MODE.def().about(
+                    "The output mode of the commands. Options are \n\t * text \
+                     (default)\n\t * json: the result is printed as a JSON \
+                     object with a \"status\" field, either \"ok\" or \
+                     \"error\". Errors are printed to stderr.",
+                ))
         }
     }
 
@@ -1505,6 +2679,280 @@ pub mod args {
         }
     }
 
+    /// Transaction bundle arguments
+    #[derive(Clone, Debug)]
+    pub struct TxBundle {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Path to the file with the paths of the bundle's transactions
+        pub txs_file: PathBuf,
+    }
+
+    impl Args for TxBundle {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let txs_file = TXS_FILE.parse(matches);
+            Self { tx, txs_file }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>().arg(TXS_FILE.def().about(
+                "Path to a file with the paths of the signed transactions of \
+                 the bundle, e.g. written with `--raw-tx-out`, one per line, \
+                 in the order in which to apply them. Only the bundle's own \
+                 wrapper pays a fee.",
+            ))
+        }
+    }
+
+    /// Broadcast a signed transaction arguments
+    #[derive(Clone, Debug)]
+    pub struct BroadcastTx {
+        /// Do not wait for the transaction to be applied
+        pub broadcast_only: bool,
+        /// The address of the ledger node as host:port
+        pub ledger_address: TendermintAddress,
+        /// Path to the file with the signed transaction
+        pub tx_path: PathBuf,
+    }
+
+    impl Args for BroadcastTx {
+        fn parse(matches: &ArgMatches) -> Self {
+            let broadcast_only = BROADCAST_ONLY.parse(matches);
+            let ledger_address = LEDGER_ADDRESS_DEFAULT.parse(matches);
+            let tx_path = TX_PATH_IN.parse(matches);
+            Self {
+                broadcast_only,
+                ledger_address,
+                tx_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(BROADCAST_ONLY.def().about(
+                "Do not wait for the transaction to be applied. This will \
+                 return once the transaction is added to the mempool.",
+            ))
+            .arg(LEDGER_ADDRESS_DEFAULT.def().about(LEDGER_ADDRESS_ABOUT))
+            .arg(TX_PATH_IN.def().about(
+                "The path to the file with the signed transaction to \
+                 broadcast.",
+            ))
+        }
+    }
+
+    /// Decode a serialized transaction arguments
+    #[derive(Clone, Debug)]
+    pub struct DecodeTx {
+        /// Path to the file with the transaction or the hex encoded
+        /// transaction
+        pub tx_in: String,
+    }
+
+    impl Args for DecodeTx {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx_in = TX_FILE_OR_HEX.parse(matches);
+            Self { tx_in }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(TX_FILE_OR_HEX.def().about(
+                "The path to the file with the transaction, or the hex \
+                 encoded transaction.",
+            ))
+        }
+    }
+
+    /// Off-chain message signing arguments
+    #[derive(Clone, Debug)]
+    pub struct SignMessage {
+        /// Common query args, used to look-up the signer's public key
+        pub query: Query,
+        /// The address whose key signs the message
+        pub signer: WalletAddress,
+        /// The message to sign
+        pub message: String,
+    }
+
+    impl Args for SignMessage {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let signer = SIGNER.parse(matches);
+            let message = MESSAGE.parse(matches);
+            Self {
+                query,
+                signer,
+                message,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>()
+                .arg(SIGNER.def().about(
+                    "The address whose key from your wallet signs the \
+                     message. The public key of an established account is \
+                     looked-up from the ledger.",
+                ))
+                .arg(MESSAGE.def().about("The message to sign."))
+        }
+    }
+
+    /// Off-chain message verification arguments
+    #[derive(Clone, Debug)]
+    pub struct VerifyMessage {
+        /// Common query args, used to look-up the address' public key
+        pub query: Query,
+        /// The address that signed the message
+        pub address: WalletAddress,
+        /// The signed message
+        pub message: String,
+        /// The signature of the message
+        pub signature: common::Signature,
+        /// The public key to verify the signature with instead of the
+        /// address' public key from the ledger
+        pub public_key: Option<common::PublicKey>,
+    }
+
+    impl Args for VerifyMessage {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let address = ADDRESS.parse(matches);
+            let message = MESSAGE.parse(matches);
+            let signature = SIGNATURE.parse(matches);
+            let public_key = RAW_PUBLIC_KEY_OPT.parse(matches);
+            Self {
+                query,
+                address,
+                message,
+                signature,
+                public_key,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>()
+                .arg(
+                    ADDRESS.def().about("The address that signed the message."),
+                )
+                .arg(MESSAGE.def().about("The signed message."))
+                .arg(
+                    SIGNATURE
+                        .def()
+                        .about("The hex encoded signature of the message."),
+                )
+                .arg(RAW_PUBLIC_KEY_OPT.def().about(
+                    "The public key to verify the signature with. If not \
+                     given, the public key of the address is looked-up from \
+                     the ledger. The public key of an implicit address must \
+                     match the address.",
+                ))
+        }
+    }
+
+    /// Token amount conversion arguments
+    #[derive(Clone, Debug)]
+    pub struct ConvertAmount {
+        /// The token whose precision is used for the conversion
+        pub token: Option<WalletAddress>,
+        /// An explicit number of decimal places used for the conversion
+        pub decimals: Option<u32>,
+        /// A human readable amount to convert to a raw amount
+        pub human: Option<String>,
+        /// A raw amount to convert to a human readable amount
+        pub raw: Option<u64>,
+    }
+
+    impl Args for ConvertAmount {
+        fn parse(matches: &ArgMatches) -> Self {
+            let token = TOKEN_OPT.parse(matches);
+            let decimals = DECIMALS.parse(matches);
+            let human = HUMAN_AMOUNT.parse(matches);
+            let raw = RAW_AMOUNT.parse(matches);
+            Self {
+                token,
+                decimals,
+                human,
+                raw,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(
+                TOKEN_OPT
+                    .def()
+                    .about("The token whose precision is used.")
+                    .required_unless_present(DECIMALS.name),
+            )
+            .arg(
+                DECIMALS
+                    .def()
+                    .about(
+                        "An explicit number of decimal places to use instead \
+                         of a token's precision.",
+                    )
+                    .conflicts_with(TOKEN_OPT.name),
+            )
+            .arg(
+                HUMAN_AMOUNT
+                    .def()
+                    .about(
+                        "A human readable amount of whole tokens to convert \
+                         to a raw amount, e.g. \"10.1\".",
+                    )
+                    .required_unless_present(RAW_AMOUNT.name),
+            )
+            .arg(
+                RAW_AMOUNT
+                    .def()
+                    .about(
+                        "A raw amount in the smallest denomination of the \
+                         token to convert to a human readable amount.",
+                    )
+                    .conflicts_with(HUMAN_AMOUNT.name),
+            )
+        }
+    }
+
+    /// Alias resolution arguments
+    #[derive(Clone, Debug)]
+    pub struct ResolveAlias {
+        /// The alias to resolve to an address
+        pub alias: String,
+    }
+
+    impl Args for ResolveAlias {
+        fn parse(matches: &ArgMatches) -> Self {
+            let alias = ALIAS.parse(matches);
+            Self { alias }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(ALIAS.def().about("The alias of an address in the wallet."))
+        }
+    }
+
+    /// Reverse address to alias resolution arguments
+    #[derive(Clone, Debug)]
+    pub struct ResolveAddress {
+        /// The address to resolve to its alias
+        pub address: Address,
+    }
+
+    impl Args for ResolveAddress {
+        fn parse(matches: &ArgMatches) -> Self {
+            let address = RAW_ADDRESS.parse(matches);
+            Self { address }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(
+                RAW_ADDRESS
+                    .def()
+                    .about("The address whose alias in the wallet to print."),
+            )
+        }
+    }
+
     /// Custom transaction arguments
     #[derive(Clone, Debug)]
     pub struct TxCustom {
@@ -1554,8 +3002,11 @@ pub mod args {
         pub target: WalletAddress,
         /// Transferred token address
         pub token: WalletAddress,
-        /// Transferred token amount
+        /// Transferred token amount. It's ignored with `all`.
         pub amount: token::Amount,
+        /// Transfer the source's entire balance of the token, less the fee
+        /// when it's paid by the source in the same token
+        pub all: bool,
     }
 
     impl Args for TxTransfer {
@@ -1564,13 +3015,19 @@ pub mod args {
             let source = SOURCE.parse(matches);
             let target = TARGET.parse(matches);
             let token = TOKEN.parse(matches);
-            let amount = AMOUNT.parse(matches);
+            let all = ALL.parse(matches);
+            let amount = if all {
+                token::Amount::default()
+            } else {
+                AMOUNT.parse(matches)
+            };
             Self {
                 tx,
                 source,
                 target,
                 token,
                 amount,
+                all,
             }
         }
 
@@ -1582,7 +3039,150 @@ pub mod args {
                 ))
                 .arg(TARGET.def().about("The target account address."))
                 .arg(TOKEN.def().about("The transfer token."))
-                .arg(AMOUNT.def().about("The amount to transfer in decimal."))
+                .arg(
+                    AMOUNT
+                        .def()
+                        .about("The amount to transfer in decimal.")
+                        .required_unless_present(ALL.name),
+                )
+                .arg(
+                    ALL.def()
+                        .about(
+                            "Transfer the source's entire balance of the \
+                             token. When the fee is paid by the source in the \
+                             same token, it's subtracted from the transferred \
+                             amount.",
+                        )
+                        .conflicts_with(AMOUNT.name),
+                )
+        }
+    }
+
+    /// Mint transaction arguments
+    #[derive(Clone, Debug)]
+    pub struct TxMint {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// The minted token address
+        pub token: WalletAddress,
+        /// The target address receiving the minted tokens
+        pub target: WalletAddress,
+        /// The minted token amount
+        pub amount: token::Amount,
+    }
+
+    impl Args for TxMint {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let token = TOKEN.parse(matches);
+            let target = TARGET.parse(matches);
+            let amount = AMOUNT.parse(matches);
+            Self {
+                tx,
+                token,
+                target,
+                amount,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(TOKEN.def().about("The minted token."))
+                .arg(TARGET.def().about(
+                    "The target account address receiving the minted tokens.",
+                ))
+                .arg(AMOUNT.def().about("The amount to mint in decimal."))
+        }
+    }
+
+    /// Token admin transaction arguments
+    #[derive(Clone, Debug)]
+    pub struct TxTokenAdmin {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// The administered token address
+        pub token: WalletAddress,
+        /// Freeze the token's transfers
+        pub freeze: bool,
+        /// Unfreeze the token's transfers
+        pub unfreeze: bool,
+    }
+
+    impl Args for TxTokenAdmin {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let token = TOKEN.parse(matches);
+            let freeze = FREEZE.parse(matches);
+            let unfreeze = UNFREEZE.parse(matches);
+            Self {
+                tx,
+                token,
+                freeze,
+                unfreeze,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(TOKEN.def().about("The administered token."))
+                .arg(
+                    FREEZE
+                        .def()
+                        .about("Reject all the transfers of the token.")
+                        .conflicts_with(UNFREEZE.name),
+                )
+                .arg(
+                    UNFREEZE
+                        .def()
+                        .about("Allow the transfers of a frozen token again.")
+                        .conflicts_with(FREEZE.name),
+                )
+        }
+    }
+
+    /// Transfer allow and deny lists transaction arguments
+    #[derive(Clone, Debug)]
+    pub struct TxTransferLists {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// The account whose lists are set
+        pub address: WalletAddress,
+        /// The only allowed counterparties. Any counterparty that isn't
+        /// denied is allowed if it's empty.
+        pub allow: Vec<WalletAddress>,
+        /// The denied counterparties
+        pub deny: Vec<WalletAddress>,
+    }
+
+    impl Args for TxTransferLists {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let address = ADDRESS.parse(matches);
+            let allow = ALLOW.parse(matches);
+            let deny = DENY.parse(matches);
+            Self {
+                tx,
+                address,
+                allow,
+                deny,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(ADDRESS.def().about(
+                    "The account whose transfer lists are set. It must sign \
+                     the transaction.",
+                ))
+                .arg(ALLOW.def().about(
+                    "The only addresses that the account may send tokens to \
+                     and receive tokens from. When not given, any address \
+                     that isn't denied is allowed.",
+                ))
+                .arg(DENY.def().about(
+                    "The addresses that the account may not send tokens to or \
+                     receive tokens from.",
+                ))
         }
     }
 
@@ -1595,6 +3195,8 @@ pub mod args {
         pub source: WalletAddress,
         /// Path to the VP WASM code file for the new account
         pub vp_code_path: Option<PathBuf>,
+        /// Built-in VP template for the new account
+        pub vp_template: Option<VpTemplate>,
         /// Public key for the new account
         pub public_key: WalletPublicKey,
     }
@@ -1604,11 +3206,13 @@ pub mod args {
             let tx = Tx::parse(matches);
             let source = SOURCE.parse(matches);
             let vp_code_path = CODE_PATH_OPT.parse(matches);
+            let vp_template = VP_TEMPLATE.parse(matches);
             let public_key = PUBLIC_KEY.parse(matches);
             Self {
                 tx,
                 source,
                 vp_code_path,
+                vp_template,
                 public_key,
             }
         }
@@ -1618,14 +3222,31 @@ pub mod args {
                 .arg(SOURCE.def().about(
                     "The source account's address that signs the transaction.",
                 ))
-                .arg(CODE_PATH_OPT.def().about(
-                    "The path to the validity predicate WASM code to be used \
-                     for the new account. Uses the default user VP if none \
-                     specified.",
-                ))
+                .arg(
+                    CODE_PATH_OPT
+                        .def()
+                        .about(
+                            "The path to the validity predicate WASM code to \
+                             be used for the new account. Uses the default \
+                             user VP if neither this nor a VP template is \
+                             specified.",
+                        )
+                        .conflicts_with(VP_TEMPLATE.name),
+                )
+                .arg(
+                    VP_TEMPLATE
+                        .def()
+                        .about(
+                            "The name of a built-in validity predicate to be \
+                             used for the new account, one of: user, token.",
+                        )
+                        .conflicts_with(CODE_PATH_OPT.name),
+                )
                 .arg(PUBLIC_KEY.def().about(
                     "A public key to be used for the new account in \
-                     hexadecimal encoding.",
+                     hexadecimal encoding. A key of a specific signature \
+                     scheme can be given with the scheme's prefix, e.g. \
+                     \"secp256k1:<hex>\" or \"ed25519:<hex>\".",
                 ))
         }
     }
@@ -1641,6 +3262,8 @@ pub mod args {
         pub protocol_key: Option<WalletPublicKey>,
         pub validator_vp_code_path: Option<PathBuf>,
         pub rewards_vp_code_path: Option<PathBuf>,
+        pub commission_rate: u64,
+        pub metadata: ValidatorMetadata,
         pub unsafe_dont_encrypt: bool,
     }
 
@@ -1654,6 +3277,8 @@ pub mod args {
             let protocol_key = PROTOCOL_KEY.parse(matches);
             let validator_vp_code_path = VALIDATOR_CODE_PATH.parse(matches);
             let rewards_vp_code_path = REWARDS_CODE_PATH.parse(matches);
+            let commission_rate = COMMISSION_RATE_DEFAULT.parse(matches);
+            let metadata = ValidatorMetadata::parse(matches);
             let unsafe_dont_encrypt = UNSAFE_DONT_ENCRYPT.parse(matches);
             Self {
                 tx,
@@ -1664,6 +3289,8 @@ pub mod args {
                 protocol_key,
                 validator_vp_code_path,
                 rewards_vp_code_path,
+                commission_rate,
+                metadata,
                 unsafe_dont_encrypt,
             }
         }
@@ -1699,6 +3326,12 @@ pub mod args {
                      for the staking reward account. Uses the default staking \
                      reward VP if none specified.",
                 ))
+                .arg(COMMISSION_RATE_DEFAULT.def().about(
+                    "The portion of the validator's rewards that the \
+                     validator takes as a commission, given in basis points \
+                     (e.g. 1000 for 10%). Defaults to 0.",
+                ))
+                .add_args::<ValidatorMetadata>()
                 .arg(UNSAFE_DONT_ENCRYPT.def().about(
                     "UNSAFE: Do not encrypt the generated keypairs. Do not \
                      use this for keys used in a live network.",
@@ -1712,7 +3345,9 @@ pub mod args {
         /// Common tx arguments
         pub tx: Tx,
         /// Path to the VP WASM code file
-        pub vp_code_path: PathBuf,
+        pub vp_code_path: Option<PathBuf>,
+        /// Built-in VP template, used instead of the VP WASM code file
+        pub vp_template: Option<VpTemplate>,
         /// Address of the account whose VP is to be updated
         pub addr: WalletAddress,
     }
@@ -1720,11 +3355,13 @@ pub mod args {
     impl Args for TxUpdateVp {
         fn parse(matches: &ArgMatches) -> Self {
             let tx = Tx::parse(matches);
-            let vp_code_path = CODE_PATH.parse(matches);
+            let vp_code_path = CODE_PATH_OPT.parse(matches);
+            let vp_template = VP_TEMPLATE.parse(matches);
             let addr = ADDRESS.parse(matches);
             Self {
                 tx,
                 vp_code_path,
+                vp_template,
                 addr,
             }
         }
@@ -1732,9 +3369,24 @@ pub mod args {
         fn def(app: App) -> App {
             app.add_args::<Tx>()
                 .arg(
-                    CODE_PATH.def().about(
-                        "The path to the new validity predicate WASM code.",
-                    ),
+                    CODE_PATH_OPT
+                        .def()
+                        .about(
+                            "The path to the new validity predicate WASM \
+                             code. Either this or a VP template must be \
+                             specified.",
+                        )
+                        .conflicts_with(VP_TEMPLATE.name),
+                )
+                .arg(
+                    VP_TEMPLATE
+                        .def()
+                        .about(
+                            "The name of a built-in validity predicate to be \
+                             used as the new validity predicate, one of: \
+                             user, token.",
+                        )
+                        .conflicts_with(CODE_PATH_OPT.name),
                 )
                 .arg(ADDRESS.def().about(
                     "The account's address. It's key is used to produce the \
@@ -1949,6 +3601,40 @@ pub mod args {
         }
     }
 
+    /// Query proposal code arguments
+    #[derive(Clone, Debug)]
+    pub struct QueryProposalCode {
+        /// Common query args
+        pub query: Query,
+        /// Proposal id
+        pub proposal_id: u64,
+        /// The file to write the proposal's code to
+        pub out: Option<PathBuf>,
+    }
+
+    impl Args for QueryProposalCode {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let proposal_id = PROPOSAL_ID.parse(matches);
+            let out = OUT_FILE_PATH_OPT.parse(matches);
+            Self {
+                query,
+                proposal_id,
+                out,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>()
+                .arg(PROPOSAL_ID.def().about("The proposal identifier."))
+                .arg(
+                    OUT_FILE_PATH_OPT
+                        .def()
+                        .about("Write the proposal's wasm code to this file."),
+                )
+        }
+    }
+
     /// Withdraw arguments
     #[derive(Clone, Debug)]
     pub struct Withdraw {
@@ -1984,6 +3670,197 @@ pub mod args {
         }
     }
 
+    /// Rewards claim arguments
+    #[derive(Clone, Debug)]
+    pub struct ClaimRewards {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Validator address
+        pub validator: WalletAddress,
+        /// Source address for claiming rewards of delegations. For claiming
+        /// rewards of self-bonds, the validator is also the source
+        pub source: Option<WalletAddress>,
+    }
+
+    impl Args for ClaimRewards {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let validator = VALIDATOR.parse(matches);
+            let source = SOURCE_OPT.parse(matches);
+            Self {
+                tx,
+                validator,
+                source,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(VALIDATOR.def().about("Validator address."))
+                .arg(SOURCE_OPT.def().about(
+                    "Source address for claiming rewards of delegations. For \
+                     claiming rewards of self-bonds, the validator is also \
+                     the source.",
+                ))
+        }
+    }
+
+    /// Validator unjail arguments
+    #[derive(Clone, Debug)]
+    pub struct TxUnjailValidator {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Validator address
+        pub validator: WalletAddress,
+    }
+
+    impl Args for TxUnjailValidator {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let validator = VALIDATOR.parse(matches);
+            Self { tx, validator }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(VALIDATOR.def().about("The jailed validator's address."))
+        }
+    }
+
+    /// Validator consensus key rotation arguments
+    #[derive(Clone, Debug)]
+    pub struct TxRotateConsensusKey {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Validator address
+        pub validator: WalletAddress,
+        /// The new consensus key, generated if not given
+        pub consensus_key: Option<WalletKeypair>,
+        /// Don't encrypt the generated consensus key
+        pub unsafe_dont_encrypt: bool,
+    }
+
+    impl Args for TxRotateConsensusKey {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let validator = VALIDATOR.parse(matches);
+            let consensus_key = VALIDATOR_CONSENSUS_KEY.parse(matches);
+            let unsafe_dont_encrypt = UNSAFE_DONT_ENCRYPT.parse(matches);
+            Self {
+                tx,
+                validator,
+                consensus_key,
+                unsafe_dont_encrypt,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(VALIDATOR.def().about("Validator address."))
+                .arg(VALIDATOR_CONSENSUS_KEY.def().about(
+                    "The new consensus key for the validator. A new one will \
+                     be generated if none given.",
+                ))
+                .arg(UNSAFE_DONT_ENCRYPT.def().about(
+                    "UNSAFE: Do not encrypt the generated keypair. Do not use \
+                     this for keys used in a live network.",
+                ))
+        }
+    }
+
+    /// Validator commission rate change arguments
+    #[derive(Clone, Debug)]
+    pub struct TxChangeCommissionRate {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Validator address
+        pub validator: WalletAddress,
+        /// The new commission rate in basis points
+        pub rate: u64,
+    }
+
+    impl Args for TxChangeCommissionRate {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let validator = VALIDATOR.parse(matches);
+            let rate = COMMISSION_RATE.parse(matches);
+            Self {
+                tx,
+                validator,
+                rate,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(VALIDATOR.def().about("Validator address."))
+                .arg(COMMISSION_RATE.def().about(
+                    "The new commission rate, given in basis points (e.g. \
+                     1000 for 10%).",
+                ))
+        }
+    }
+
+    impl Args for ValidatorMetadata {
+        fn parse(matches: &ArgMatches) -> Self {
+            let moniker = MONIKER.parse(matches);
+            let website = WEBSITE.parse(matches);
+            let security_contact = SECURITY_CONTACT.parse(matches);
+            let description = DESCRIPTION.parse(matches);
+            Self {
+                moniker,
+                website,
+                security_contact,
+                description,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(MONIKER.def().about("A display name of the validator."))
+                .arg(WEBSITE.def().about("The validator's website."))
+                .arg(
+                    SECURITY_CONTACT.def().about(
+                        "An e-mail or other contact for security issues.",
+                    ),
+                )
+                .arg(
+                    DESCRIPTION
+                        .def()
+                        .about("A longer description of the validator."),
+                )
+        }
+    }
+
+    /// Edit a validator's metadata
+    #[derive(Clone, Debug)]
+    pub struct TxEditValidator {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Validator address
+        pub validator: WalletAddress,
+        /// The metadata fields to update
+        pub metadata: ValidatorMetadata,
+    }
+
+    impl Args for TxEditValidator {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let validator = VALIDATOR.parse(matches);
+            let metadata = ValidatorMetadata::parse(matches);
+            Self {
+                tx,
+                validator,
+                metadata,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(VALIDATOR.def().about("Validator address."))
+                .add_args::<ValidatorMetadata>()
+        }
+    }
+
     // Transaction to create a new nft
     #[derive(Clone, Debug)]
     pub struct NftCreate {
@@ -2053,6 +3930,10 @@ pub mod args {
         pub owner: Option<WalletAddress>,
         /// Address of a token
         pub token: Option<WalletAddress>,
+        /// Query the balance as it was at the start of this epoch
+        pub epoch: Option<Epoch>,
+        /// Verify the balance with a merkle proof
+        pub prove: bool,
     }
 
     impl Args for QueryBalance {
@@ -2060,10 +3941,14 @@ pub mod args {
             let query = Query::parse(matches);
             let owner = OWNER.parse(matches);
             let token = TOKEN_OPT.parse(matches);
+            let epoch = EPOCH.parse(matches);
+            let prove = PROVE.parse(matches);
             Self {
                 query,
                 owner,
                 token,
+                epoch,
+                prove,
             }
         }
 
@@ -2079,6 +3964,66 @@ pub mod args {
                         .def()
                         .about("The token's address whose balance to query."),
                 )
+                .arg(EPOCH.def().about(
+                    "Query the balance as it was at the start of this epoch \
+                     (last committed state, if not specified).",
+                ))
+                .arg(
+                    PROVE
+                        .def()
+                        .about(
+                            "Verify the balance with a merkle proof against \
+                             the root of the last committed block reported by \
+                             the node.",
+                        )
+                        .requires(OWNER.name)
+                        .conflicts_with(EPOCH.name),
+                )
+        }
+    }
+
+    /// Query the balances of many owners
+    #[derive(Clone, Debug)]
+    pub struct QueryBalances {
+        /// Common query args
+        pub query: Query,
+        /// Path to a file with one owner's address or alias per line
+        pub addresses_file: PathBuf,
+        /// Address of a token
+        pub token: Option<WalletAddress>,
+        /// The format of the output
+        pub format: OutputFormat,
+    }
+
+    impl Args for QueryBalances {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let addresses_file = ADDRESSES_FILE.parse(matches);
+            let token = TOKEN_OPT.parse(matches);
+            let format = OUTPUT_FORMAT.parse(matches);
+            Self {
+                query,
+                addresses_file,
+                token,
+                format,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>()
+                .arg(ADDRESSES_FILE.def().about(
+                    "Path to a file with one owner's address or alias per \
+                     line.",
+                ))
+                .arg(TOKEN_OPT.def().about(
+                    "The token's address whose balances to query. When not \
+                     set, the balances of all the known tokens are queried.",
+                ))
+                .arg(OUTPUT_FORMAT.def().about(
+                    "The format of the output, either \"text\" or \"csv\". \
+                     The CSV output has the columns owner, token and amount. \
+                     Defaults to \"text\".",
+                ))
         }
     }
 
@@ -2143,26 +4088,76 @@ pub mod args {
         }
     }
 
-    /// Query PoS bond(s)
+    /// Query PoS bond(s)
+    #[derive(Clone, Debug)]
+    pub struct QueryBonds {
+        /// Common query args
+        pub query: Query,
+        /// Address of an owner
+        pub owner: Option<WalletAddress>,
+        /// Address of a validator
+        pub validator: Option<WalletAddress>,
+        /// Query the bonds as they were at the start of this epoch
+        pub epoch: Option<Epoch>,
+    }
+
+    impl Args for QueryBonds {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let owner = OWNER.parse(matches);
+            let validator = VALIDATOR_OPT.parse(matches);
+            let epoch = EPOCH.parse(matches);
+            Self {
+                query,
+                owner,
+                validator,
+                epoch,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>()
+                .arg(
+                    OWNER.def().about(
+                        "The owner account address whose bonds to query.",
+                    ),
+                )
+                .arg(
+                    VALIDATOR_OPT
+                        .def()
+                        .about("The validator's address whose bonds to query."),
+                )
+                .arg(EPOCH.def().about(
+                    "Query the bonds as they were at the start of this epoch \
+                     (last committed state, if not specified).",
+                ))
+        }
+    }
+
+    /// Query PoS unclaimed rewards
     #[derive(Clone, Debug)]
-    pub struct QueryBonds {
+    pub struct QueryRewards {
         /// Common query args
         pub query: Query,
         /// Address of an owner
         pub owner: Option<WalletAddress>,
         /// Address of a validator
         pub validator: Option<WalletAddress>,
+        /// Only show the rewards earned since this epoch
+        pub since_epoch: Option<Epoch>,
     }
 
-    impl Args for QueryBonds {
+    impl Args for QueryRewards {
         fn parse(matches: &ArgMatches) -> Self {
             let query = Query::parse(matches);
             let owner = OWNER.parse(matches);
             let validator = VALIDATOR_OPT.parse(matches);
+            let since_epoch = SINCE_EPOCH.parse(matches);
             Self {
                 query,
                 owner,
                 validator,
+                since_epoch,
             }
         }
 
@@ -2170,14 +4165,15 @@ pub mod args {
             app.add_args::<Query>()
                 .arg(
                     OWNER.def().about(
-                        "The owner account address whose bonds to query.",
+                        "The owner account address whose rewards to query.",
                     ),
                 )
-                .arg(
-                    VALIDATOR_OPT
-                        .def()
-                        .about("The validator's address whose bonds to query."),
-                )
+                .arg(VALIDATOR_OPT.def().about(
+                    "The validator's address whose bonds' rewards to query.",
+                ))
+                .arg(SINCE_EPOCH.def().about(
+                    "Only show the rewards earned in or after this epoch.",
+                ))
         }
     }
 
@@ -2190,6 +4186,10 @@ pub mod args {
         pub validator: Option<WalletAddress>,
         /// Epoch in which to find voting power
         pub epoch: Option<Epoch>,
+        /// The first epoch of the history of the validator's voting power
+        pub from_epoch: Option<Epoch>,
+        /// The last epoch of the history of the validator's voting power
+        pub to_epoch: Option<Epoch>,
     }
 
     impl Args for QueryVotingPower {
@@ -2197,10 +4197,14 @@ pub mod args {
             let query = Query::parse(matches);
             let validator = VALIDATOR_OPT.parse(matches);
             let epoch = EPOCH.parse(matches);
+            let from_epoch = FROM_EPOCH.parse(matches);
+            let to_epoch = TO_EPOCH.parse(matches);
             Self {
                 query,
                 validator,
                 epoch,
+                from_epoch,
+                to_epoch,
             }
         }
 
@@ -2211,11 +4215,211 @@ pub mod args {
                 ))
                 .arg(EPOCH.def().about(
                     "The epoch at which to query (last committed, if not \
-                     specified).",
+                     specified). The voting power in a past epoch is read \
+                     from the state at the start of that epoch.",
+                ))
+                .arg(
+                    FROM_EPOCH
+                        .def()
+                        .about(
+                            "Query the validator's voting power in every \
+                             epoch from this epoch up to the `--to-epoch`.",
+                        )
+                        .requires(VALIDATOR_OPT.name)
+                        .conflicts_with(EPOCH.name),
+                )
+                .arg(
+                    TO_EPOCH
+                        .def()
+                        .about(
+                            "The last epoch of the voting power history (last \
+                             committed, if not specified).",
+                        )
+                        .requires(FROM_EPOCH.name),
+                )
+        }
+    }
+
+    /// Query the consensus validator set
+    #[derive(Clone, Debug)]
+    pub struct QueryValidatorSet {
+        /// Common query args
+        pub query: Query,
+        /// Epoch of the validator set
+        pub epoch: Option<Epoch>,
+    }
+
+    impl Args for QueryValidatorSet {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let epoch = EPOCH.parse(matches);
+            Self { query, epoch }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>().arg(EPOCH.def().about(
+                "The epoch of the validator set (last committed, if not \
+                 specified). The validator sets are known up to the pipeline \
+                 length after the last committed epoch.",
+            ))
+        }
+    }
+
+    /// Query PoS validator's commission rate
+    #[derive(Clone, Debug)]
+    pub struct QueryCommissionRate {
+        /// Common query args
+        pub query: Query,
+        /// Address of a validator
+        pub validator: WalletAddress,
+    }
+
+    impl Args for QueryCommissionRate {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let validator = VALIDATOR.parse(matches);
+            Self { query, validator }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>().arg(VALIDATOR.def().about(
+                "The validator's address whose commission rate to query.",
+            ))
+        }
+    }
+
+    /// Query a validator's metadata
+    #[derive(Clone, Debug)]
+    pub struct QueryValidator {
+        /// Common query args
+        pub query: Query,
+        /// Address of a validator
+        pub validator: WalletAddress,
+    }
+
+    impl Args for QueryValidator {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let validator = VALIDATOR.parse(matches);
+            Self { query, validator }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>().arg(
+                VALIDATOR
+                    .def()
+                    .about("The validator's address whose metadata to query."),
+            )
+        }
+    }
+
+    /// Query an account's metadata
+    #[derive(Clone, Debug)]
+    pub struct QueryMetadata {
+        /// Common query args
+        pub query: Query,
+        /// Address of the account
+        pub address: WalletAddress,
+    }
+
+    impl Args for QueryMetadata {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let address = ADDRESS.parse(matches);
+            Self { query, address }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>().arg(
+                ADDRESS
+                    .def()
+                    .about("The address of the account whose kind to query."),
+            )
+        }
+    }
+
+    /// Query the events of a committed block
+    #[derive(Clone, Debug)]
+    pub struct QueryEvents {
+        /// Common query args
+        pub query: Query,
+        /// Height of the block
+        pub height: BlockHeight,
+        /// Only query the events of this type
+        pub event_type: Option<String>,
+    }
+
+    impl Args for QueryEvents {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let height = BlockHeight(BLOCK_HEIGHT.parse(matches));
+            let event_type = EVENT_TYPE.parse(matches);
+            Self {
+                query,
+                height,
+                event_type,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>()
+                .arg(BLOCK_HEIGHT.def().about("The height of the block."))
+                .arg(EVENT_TYPE.def().about(
+                    "Only query the events of the given type, e.g. \
+                     \"balance-change\" or \"applied\".",
                 ))
         }
     }
 
+    /// Query the contents of the mempool
+    #[derive(Clone, Debug)]
+    pub struct QueryMempool {
+        /// Common query args
+        pub query: Query,
+        /// List the transactions' hashes and fees
+        pub list_txs: bool,
+    }
+
+    impl Args for QueryMempool {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let list_txs = LIST_TXS.parse(matches);
+            Self { query, list_txs }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>().arg(LIST_TXS.def().about(
+                "List the hashes and fees of the transactions with the \
+                 highest fees.",
+            ))
+        }
+    }
+
+    /// Query the sizes of the values in storage
+    #[derive(Clone, Debug)]
+    pub struct QueryStorageSize {
+        /// Common query args
+        pub query: Query,
+        /// Only count the keys with this prefix
+        pub prefix: Option<storage::Key>,
+    }
+
+    impl Args for QueryStorageSize {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let prefix = STORAGE_PREFIX.parse(matches);
+            Self { query, prefix }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>().arg(
+                STORAGE_PREFIX
+                    .def()
+                    .about("Only count the keys with the given prefix."),
+            )
+        }
+    }
+
     /// Query PoS slashes
     #[derive(Clone, Debug)]
     pub struct QuerySlashes {
@@ -2247,18 +4451,64 @@ pub mod args {
         pub storage_key: storage::Key,
         /// Common query args
         pub query: Query,
+        /// Verify the value with a merkle proof
+        pub prove: bool,
+        /// Decode the value as the given type
+        pub decode_as: Option<ValueType>,
     }
 
     impl Args for QueryRawBytes {
         fn parse(matches: &ArgMatches) -> Self {
             let storage_key = STORAGE_KEY.parse(matches);
             let query = Query::parse(matches);
-            Self { storage_key, query }
+            let prove = PROVE.parse(matches);
+            let decode_as = DECODE_AS.parse(matches);
+            Self {
+                storage_key,
+                query,
+                prove,
+                decode_as,
+            }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Query>()
                 .arg(STORAGE_KEY.def().about("Storage key"))
+                .arg(PROVE.def().about(
+                    "Verify the value with a merkle proof against the root of \
+                     the last committed block reported by the node.",
+                ))
+                .arg(DECODE_AS.def().about(
+                    "Decode the value as one of: amount, pubkey, vp-hash (the \
+                     hash of a validity predicate's code), address. The raw \
+                     bytes are printed in hex when it's not given or when the \
+                     value can't be decoded.",
+                ))
+        }
+    }
+
+    /// Simulate block arguments
+    #[derive(Clone, Debug)]
+    pub struct SimulateBlock {
+        /// Common query args
+        pub query: Query,
+        /// Path to a file with one signed transaction file path per line
+        pub txs_file: PathBuf,
+    }
+
+    impl Args for SimulateBlock {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let txs_file = TXS_FILE.parse(matches);
+            Self { query, txs_file }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>().arg(TXS_FILE.def().about(
+                "Path to a file with the paths of the signed transactions to \
+                 simulate, e.g. written with `--raw-tx-out`, one per line, in \
+                 the order in which to apply them.",
+            ))
         }
     }
     /// Intent arguments
@@ -2390,6 +4640,82 @@ pub mod args {
         }
     }
 
+    /// Print the committed blocks
+    #[derive(Clone, Debug)]
+    pub struct LedgerTail {
+        /// Start from the block with this height, instead of the next
+        /// committed block
+        pub from_height: Option<BlockHeight>,
+    }
+
+    impl Args for LedgerTail {
+        fn parse(matches: &ArgMatches) -> Self {
+            let from_height = FROM_HEIGHT.parse(matches).map(BlockHeight);
+            Self { from_height }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(FROM_HEIGHT.def().about(
+                "Start from the committed block with the given height and \
+                 catch up with the last committed block. By default, only the \
+                 blocks committed from now on are printed.",
+            ))
+        }
+    }
+
+    /// Export the events of the committed blocks
+    #[derive(Clone, Debug)]
+    pub struct LedgerExportEvents {
+        /// Export from the block with this height, instead of the height
+        /// saved in the cursor file or the first block
+        pub from_height: Option<BlockHeight>,
+        /// Export up to and including the block with this height, instead of
+        /// the last committed block
+        pub to_height: Option<BlockHeight>,
+        /// The file to write the events to
+        pub out: PathBuf,
+        /// The file with the height of the next block to export, updated
+        /// after the export to resume from where it stopped
+        pub cursor: Option<PathBuf>,
+    }
+
+    impl Args for LedgerExportEvents {
+        fn parse(matches: &ArgMatches) -> Self {
+            let from_height = FROM_HEIGHT.parse(matches).map(BlockHeight);
+            let to_height = TO_HEIGHT.parse(matches).map(BlockHeight);
+            let out = OUT_FILE_PATH.parse(matches);
+            let cursor = CURSOR_PATH.parse(matches);
+            Self {
+                from_height,
+                to_height,
+                out,
+                cursor,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(FROM_HEIGHT.def().about(
+                "Export from the committed block with the given height. By \
+                 default, the export resumes from the height saved in the \
+                 cursor file, if any, or starts from the first block.",
+            ))
+            .arg(TO_HEIGHT.def().about(
+                "Export up to and including the committed block with the \
+                 given height. By default, the export ends with the last \
+                 committed block.",
+            ))
+            .arg(OUT_FILE_PATH.def().about(
+                "The file to write the events to, one JSON object per line. \
+                 An existing file is overwritten.",
+            ))
+            .arg(CURSOR_PATH.def().about(
+                "A file with the height of the next block to export. It's \
+                 read to resume a previous export and it's updated after the \
+                 export.",
+            ))
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct GossipRun {
         pub addr: Option<Multiaddr>,
@@ -2478,17 +4804,37 @@ pub mod args {
     pub struct Tx {
         /// Simulate applying the transaction
         pub dry_run: bool,
+        /// Simulate applying the transaction against the state committed at
+        /// the given height instead of the latest state
+        pub dry_run_at_height: Option<BlockHeight>,
+        /// Show the validity predicates invoked by a simulated transaction
+        pub explain: bool,
+        /// Fail if the gas used by a simulated transaction exceeds the given
+        /// amount
+        pub assert_max_gas: Option<u64>,
         /// Submit the transaction even if it doesn't pass client checks
         pub force: bool,
+        /// Dry run the transaction before submitting it to check that no
+        /// validity predicate rejects it
+        pub check: bool,
+        /// Abort instead of asking for confirmation when the check fails
+        pub no_interactive: bool,
         /// Do not wait for the transaction to be added to the blockchain
         pub broadcast_only: bool,
+        /// Print a timeline of the stages reached by the submitted
+        /// transaction
+        pub trace: bool,
         /// The address of the ledger node as host:port
         pub ledger_address: TendermintAddress,
         /// If any new account is initialized by the tx, use the given alias to
         /// save it in the wallet.
         pub initialized_account_alias: Option<String>,
-        /// The amount being payed to include the transaction
-        pub fee_amount: token::Amount,
+        /// The amount being payed to include the transaction. Defaults to
+        /// zero, unless the `gas_price` is given.
+        pub fee_amount: Option<token::Amount>,
+        /// The price of a unit of gas. The fee amount is the price times the
+        /// gas limit.
+        pub gas_price: Option<token::Amount>,
         /// The token in which the fee is being paid
         pub fee_token: WalletAddress,
         /// The max amount of gas used to process tx
@@ -2497,6 +4843,13 @@ pub mod args {
         pub signing_key: Option<WalletKeypair>,
         /// Sign the tx with the keypair of the public key of the given address
         pub signer: Option<WalletAddress>,
+        /// Write the signed tx to the given file instead of broadcasting it
+        pub raw_tx_out: Option<PathBuf>,
+        /// Wait until the last committed epoch is at least the given one
+        /// before building the tx
+        pub wait_for_epoch: Option<Epoch>,
+        /// How many seconds to wait for the epoch before giving up
+        pub wait_timeout: Option<u64>,
     }
 
     impl Args for Tx {
@@ -2506,13 +4859,81 @@ pub mod args {
                     .def()
                     .about("Simulate the transaction application."),
             )
+            .arg(
+                DRY_RUN_AT_HEIGHT
+                    .def()
+                    .about(
+                        "Simulate the transaction against the state committed \
+                         at the given block height instead of the latest \
+                         state, e.g. to debug why a transaction that used to \
+                         be accepted is rejected now.",
+                    )
+                    .requires(DRY_RUN_TX.name),
+            )
+            .arg(
+                EXPLAIN
+                    .def()
+                    .about(
+                        "Show the addresses whose validity predicates are \
+                         invoked by the simulated transaction and their \
+                         verdicts.",
+                    )
+                    .requires(DRY_RUN_TX.name),
+            )
+            .arg(
+                ASSERT_MAX_GAS
+                    .def()
+                    .about(
+                        "Exit with an error if the gas used by the simulated \
+                         transaction exceeds the given amount. Useful to \
+                         guard the gas costs of transactions in a CI.",
+                    )
+                    .requires(DRY_RUN_TX.name),
+            )
             .arg(FORCE.def().about(
                 "Submit the transaction even if it doesn't pass client checks.",
             ))
+            .arg(
+                CHECK
+                    .def()
+                    .about(
+                        "Dry run the transaction before submitting it. If any \
+                         validity predicate would reject it, show the \
+                         rejecting validity predicates and ask for a \
+                         confirmation to submit it. Recommended for \
+                         interactive use.",
+                    )
+                    .conflicts_with_all(&[DRY_RUN_TX.name, RAW_TX_OUT.name]),
+            )
+            .arg(
+                NO_INTERACTIVE
+                    .def()
+                    .about(
+                        "Abort without asking for a confirmation when the \
+                         transaction check fails.",
+                    )
+                    .requires(CHECK.name),
+            )
             .arg(BROADCAST_ONLY.def().about(
                 "Do not wait for the transaction to be applied. This will \
                  return once the transaction is added to the mempool.",
             ))
+            .arg(
+                TRACE_TX
+                    .def()
+                    .about(
+                        "Print each stage reached by the submitted \
+                         transaction with a timestamp: the mempool \
+                         acceptance, the block it's included in, the block \
+                         commitment and the validity predicates' verdicts. \
+                         Shows at which stage a failing transaction halted.",
+                    )
+                    .conflicts_with_all(&[
+                        DRY_RUN_TX.name,
+                        BROADCAST_ONLY.name,
+                        RAW_TX_OUT.name,
+                    ]),
+            )
             .arg(LEDGER_ADDRESS_DEFAULT.def().about(LEDGER_ADDRESS_ABOUT))
             .arg(ALIAS_OPT.def().about(
                 "If any new account is initialized by the tx, use the given \
@@ -2521,7 +4942,8 @@ pub mod args {
                  address joined with a number.",
             ))
             .arg(FEE_AMOUNT.def().about(
-                "The amount being paid for the inclusion of this transaction",
+                "The amount being paid for the inclusion of this transaction. \
+                 Defaults to 0.",
             ))
             .arg(FEE_TOKEN.def().about("The token for paying the fee"))
             .arg(
@@ -2529,6 +4951,16 @@ pub mod args {
                     "The maximum amount of gas needed to run transaction",
                 ),
             )
+            .arg(
+                GAS_PRICE
+                    .def()
+                    .about(
+                        "The price to pay for every unit of gas. The fee \
+                         amount is the gas price times the gas limit and the \
+                         gas price must cover the current base fee.",
+                    )
+                    .conflicts_with(FEE_AMOUNT.name),
+            )
             .arg(
                 SIGNING_KEY_OPT
                     .def()
@@ -2537,10 +4969,10 @@ pub mod args {
                          public key, public key hash or alias from your \
                          wallet.",
                     )
-                    .conflicts_with(SIGNER.name),
+                    .conflicts_with(SIGNER_OPT.name),
             )
             .arg(
-                SIGNER
+                SIGNER_OPT
                     .def()
                     .about(
                         "Sign the transaction with the keypair of the public \
@@ -2548,31 +4980,88 @@ pub mod args {
                     )
                     .conflicts_with(SIGNING_KEY_OPT.name),
             )
+            .arg(
+                RAW_TX_OUT
+                    .def()
+                    .about(
+                        "Write the signed transaction to the given file \
+                         instead of broadcasting it, without connecting to \
+                         the ledger. The client checks are skipped and the \
+                         signer must be given with `--signing-key`. Use the \
+                         `broadcast-tx` command to submit the file.",
+                    )
+                    .conflicts_with_all(&[
+                        DRY_RUN_TX.name,
+                        BROADCAST_ONLY.name,
+                    ]),
+            )
+            .arg(
+                WAIT_FOR_EPOCH
+                    .def()
+                    .about(
+                        "Wait until the epoch of the last committed block is \
+                         at least the given epoch before building and \
+                         submitting the transaction, e.g. to queue a \
+                         withdrawal for when the tokens are unbonded.",
+                    )
+                    .conflicts_with(RAW_TX_OUT.name),
+            )
+            .arg(
+                WAIT_TIMEOUT
+                    .def()
+                    .about(
+                        "The number of seconds to wait for the epoch given \
+                         with `--wait-for-epoch` before giving up. Defaults \
+                         to 600.",
+                    )
+                    .requires(WAIT_FOR_EPOCH.name),
+            )
         }
 
         fn parse(matches: &ArgMatches) -> Self {
             let dry_run = DRY_RUN_TX.parse(matches);
+            let dry_run_at_height =
+                DRY_RUN_AT_HEIGHT.parse(matches).map(BlockHeight);
+            let explain = EXPLAIN.parse(matches);
+            let assert_max_gas = ASSERT_MAX_GAS.parse(matches);
             let force = FORCE.parse(matches);
+            let check = CHECK.parse(matches);
+            let no_interactive = NO_INTERACTIVE.parse(matches);
             let broadcast_only = BROADCAST_ONLY.parse(matches);
+            let trace = TRACE_TX.parse(matches);
             let ledger_address = LEDGER_ADDRESS_DEFAULT.parse(matches);
             let initialized_account_alias = ALIAS_OPT.parse(matches);
             let fee_amount = FEE_AMOUNT.parse(matches);
+            let gas_price = GAS_PRICE.parse(matches);
             let fee_token = FEE_TOKEN.parse(matches);
             let gas_limit = GAS_LIMIT.parse(matches).into();
 
             let signing_key = SIGNING_KEY_OPT.parse(matches);
-            let signer = SIGNER.parse(matches);
+            let signer = SIGNER_OPT.parse(matches);
+            let raw_tx_out = RAW_TX_OUT.parse(matches);
+            let wait_for_epoch = WAIT_FOR_EPOCH.parse(matches);
+            let wait_timeout = WAIT_TIMEOUT.parse(matches);
             Self {
                 dry_run,
+                dry_run_at_height,
+                explain,
+                assert_max_gas,
                 force,
+                check,
+                no_interactive,
                 broadcast_only,
+                trace,
                 ledger_address,
                 initialized_account_alias,
                 fee_amount,
+                gas_price,
                 fee_token,
                 gas_limit,
                 signing_key,
                 signer,
+                raw_tx_out,
+                wait_for_epoch,
+                wait_timeout,
             }
         }
     }
@@ -2602,15 +5091,23 @@ pub mod args {
         pub alias: Option<String>,
         /// Don't encrypt the keypair
         pub unsafe_dont_encrypt: bool,
+        /// The format in which to print the public key
+        pub format: KeyFormat,
+        /// Overwrite a key and an address with the same alias
+        pub force: bool,
     }
 
     impl Args for KeyAndAddressGen {
         fn parse(matches: &ArgMatches) -> Self {
             let alias = ALIAS_OPT.parse(matches);
             let unsafe_dont_encrypt = UNSAFE_DONT_ENCRYPT.parse(matches);
+            let format = KEY_FORMAT.parse(matches);
+            let force = FORCE.parse(matches);
             Self {
                 alias,
                 unsafe_dont_encrypt,
+                format,
+                force,
             }
         }
 
@@ -2623,6 +5120,14 @@ pub mod args {
                 "UNSAFE: Do not encrypt the keypair. Do not use this for keys \
                  used in a live network.",
             ))
+            .arg(KEY_FORMAT.def().about(
+                "The format in which to print the generated public key. One \
+                 of: hex, json. Defaults to hex.",
+            ))
+            .arg(FORCE.def().about(
+                "Overwrite a key and an address already stored with the same \
+                 alias.",
+            ))
         }
     }
 
@@ -2892,6 +5397,25 @@ pub mod args {
                 ))
         }
     }
+
+    #[derive(Clone, Debug)]
+    pub struct VerifyGenesis {
+        pub path: PathBuf,
+    }
+
+    impl Args for VerifyGenesis {
+        fn parse(matches: &ArgMatches) -> Self {
+            let path = PATH.parse(matches);
+            Self { path }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(
+                PATH.def()
+                    .about("Path to the genesis configuration file to verify."),
+            )
+        }
+    }
 }
 
 pub fn anoma_cli() -> (cmds::Anoma, String) {
@@ -2926,6 +5450,7 @@ pub fn anoma_client_cli() -> AnomaClient {
     match Cmd::parse(&matches) {
         Some(cmd) => {
             let global_args = args::Global::parse(&matches);
+            output::set_mode(global_args.output);
             match cmd {
                 cmds::AnomaClient::WithContext(sub_cmd) => {
                     let context = Context::new(global_args);
@@ -2943,6 +5468,21 @@ pub fn anoma_client_cli() -> AnomaClient {
     }
 }
 
+/// Parse a command of the client's interactive session from its words. On
+/// an invalid command, the returned error contains the usage to print.
+pub fn anoma_client_repl_cmd(
+    words: &[&str],
+) -> Result<cmds::ReplCmd, clap::Error> {
+    let app = cmds::ReplCmd::add_sub(
+        App::new("repl")
+            .setting(AppSettings::NoBinaryName)
+            .setting(AppSettings::SubcommandRequiredElseHelp),
+    );
+    let matches = app.try_get_matches_from(words)?;
+    Ok(cmds::ReplCmd::parse(&matches)
+        .expect("A sub-command is required by the app settings"))
+}
+
 pub fn anoma_wallet_cli() -> (cmds::AnomaWallet, Context) {
     let app = anoma_wallet_app();
     cmds::AnomaWallet::parse_or_print_help(app)
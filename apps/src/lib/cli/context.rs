@@ -6,12 +6,12 @@ use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::str::FromStr;
 
-use anoma::types::address::Address;
+use anoma::types::address::{self, Address};
 use anoma::types::chain::ChainId;
 use anoma::types::key::*;
 
 use super::args;
-use crate::cli::safe_exit;
+use crate::cli::output;
 use crate::config::genesis::genesis_config;
 use crate::config::global::GlobalConfig;
 use crate::config::{self, Config};
@@ -58,6 +58,16 @@ impl Context {
             &global_config.default_chain_id,
             global_args.mode.clone(),
         );
+        // Set the chain's addresses human-readable part before any address
+        // is decoded, so that the addresses of other chains are rejected
+        if let Some(hrp) = config.ledger.address_hrp.as_ref() {
+            if let Err(err) = address::set_address_hrp(hrp) {
+                output::fail(format!(
+                    "Invalid address human-readable part in the config: {}",
+                    err
+                ));
+            }
+        }
 
         let chain_dir = global_args
             .base_dir
@@ -73,11 +83,10 @@ impl Context {
         match global_args.wasm_dir.as_ref() {
             Some(wasm_dir) => {
                 if wasm_dir.is_absolute() {
-                    eprintln!(
+                    output::fail(
                         "The arg `--wasm-dir` cannot be an absolute path. It \
-                         is nested inside the chain directory."
+                         is nested inside the chain directory.",
                     );
-                    safe_exit(1);
                 }
                 config.wasm_dir = wasm_dir.clone();
             }
@@ -85,12 +94,11 @@ impl Context {
                 if let Ok(wasm_dir) = env::var(ENV_VAR_WASM_DIR) {
                     let wasm_dir: PathBuf = wasm_dir.into();
                     if wasm_dir.is_absolute() {
-                        eprintln!(
+                        output::fail(format!(
                             "The env var `{}` cannot be an absolute path. It \
                              is nested inside the chain directory.",
                             ENV_VAR_WASM_DIR
-                        );
-                        safe_exit(1);
+                        ));
                     }
                     config.wasm_dir = wasm_dir;
                 }
@@ -180,8 +188,7 @@ pub fn read_or_try_new_global_config(
             let chain_id = global_args.chain_id.clone().or_else(|| {
                 env::var(ENV_VAR_CHAIN_ID).ok().map(|chain_id| {
                     ChainId::from_str(&chain_id).unwrap_or_else(|err| {
-                        eprintln!("Invalid chain ID: {}", err);
-                        super::safe_exit(1)
+                        output::fail(format!("Invalid chain ID: {}", err))
                     })
                 })
             });
@@ -196,8 +203,7 @@ pub fn read_or_try_new_global_config(
             });
             config
         } else {
-            eprintln!("Error reading global config: {}", err);
-            super::safe_exit(1)
+            output::fail(format!("Error reading global config: {}", err))
         }
     })
 }
@@ -259,8 +265,7 @@ impl ArgFromContext for Address {
                 ctx.wallet
                     .find_address(raw)
                     .unwrap_or_else(|| {
-                        eprintln!("Unknown address {}", raw);
-                        safe_exit(1)
+                        output::fail(format!("Unknown address {}", raw))
                     })
                     .clone()
             })
@@ -276,8 +281,7 @@ impl ArgFromMutContext for Rc<common::SecretKey> {
             .unwrap_or_else(|_parse_err| {
                 // Or it can be an alias
                 ctx.wallet.find_key(raw).unwrap_or_else(|_find_err| {
-                    eprintln!("Unknown key {}", raw);
-                    safe_exit(1)
+                    output::fail(format!("Unknown key {}", raw))
                 })
             })
     }
@@ -0,0 +1,106 @@
+//! Machine-readable output of the commands, selected with the global
+//! `--output json` argument.
+//!
+//! In JSON mode, a command prints its result as a single line JSON object with
+//! a `"status"` field:
+//!
+//! - on success, `{"status": "ok", "result": ...}` is printed to stdout
+//! - on failure, `{"status": "error", "code": ..., "reason": ...,
+//!   "message": ...}` is printed to stderr and the process exits with a
+//!   non-zero code. The `code` and `reason` are the ledger's error code and
+//!   its name when the error comes from the ledger (see
+//!   [`crate::node::ledger::ErrorCodes`]), otherwise they are `null`.
+
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use num_traits::FromPrimitive;
+use serde_json::{json, Value};
+
+use super::safe_exit;
+use crate::node::ledger::ErrorCodes;
+
+/// The output mode of the commands
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Human-readable text
+    Text,
+    /// A JSON object with a stable schema
+    Json,
+}
+
+impl FromStr for OutputMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputMode::Text),
+            "json" => Ok(OutputMode::Json),
+            _ => Err(format!(
+                "Unknown output mode \"{}\", expected one of: text, json",
+                s
+            )),
+        }
+    }
+}
+
+/// Set when the JSON output mode is selected
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// Set the output mode for the rest of the process.
+pub fn set_mode(mode: OutputMode) {
+    JSON_OUTPUT.store(mode == OutputMode::Json, Ordering::Relaxed);
+}
+
+/// Is the JSON output mode selected? When it is, the human-readable
+/// messages should not be printed.
+pub fn is_json() -> bool {
+    JSON_OUTPUT.load(Ordering::Relaxed)
+}
+
+/// Print the result of a successful command in JSON mode. In text mode, this
+/// doesn't print anything, as the commands print their result themselves.
+pub fn print_ok(result: Value) {
+    if is_json() {
+        println!("{}", json!({ "status": "ok", "result": result }));
+    }
+}
+
+/// Print an error with the ledger's error code, if it comes from the ledger,
+/// and exit with a non-zero code.
+pub fn exit_with_error(code: Option<u32>, message: impl Display) -> ! {
+    if is_json() {
+        let reason = code
+            .and_then(ErrorCodes::from_u32)
+            .map(|code| format!("{:?}", code));
+        eprintln!(
+            "{}",
+            json!({
+                "status": "error",
+                "code": code,
+                "reason": reason,
+                "message": message.to_string(),
+            })
+        );
+    } else {
+        eprintln!("{}", message);
+    }
+    safe_exit(1)
+}
+
+/// Print an error detected by the client and exit with a non-zero code.
+pub fn fail(message: impl Display) -> ! {
+    exit_with_error(None, message)
+}
+
+/// Print an error detected by the client and exit with a non-zero code,
+/// unless the `force` argument is set, in which case the error is only
+/// printed as a warning to stderr and the command continues.
+pub fn fail_unless_forced(force: bool, message: impl Display) {
+    if force {
+        eprintln!("{}", message);
+    } else {
+        fail(message)
+    }
+}
@@ -2,11 +2,12 @@
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use clap::ArgMatches;
 
-use super::args;
 use super::context::{Context, FromContext};
+use super::{args, output};
 
 // We only use static strings
 pub type App = clap::App<'static>;
@@ -22,6 +23,7 @@ pub trait Cmd: Sized {
         match Self::parse(&matches) {
             Some(cmd) => {
                 let global_args = args::Global::parse(&matches);
+                output::set_mode(global_args.output);
                 let context = Context::new(global_args);
                 (cmd, context)
             }
@@ -317,12 +319,29 @@ where
     })
 }
 
+/// Set while a command runs in an interactive session, in which a failure
+/// must only end the command and not the whole process
+static IN_SESSION: AtomicBool = AtomicBool::new(false);
+
+/// Set whether the following commands run in an interactive session. In a
+/// session, [`safe_exit`] unwinds the command instead of exiting, which the
+/// session must catch.
+pub fn set_in_session(in_session: bool) {
+    IN_SESSION.store(in_session, Ordering::Relaxed);
+}
+
 /// A helper to exit after flushing output, borrowed from `clap::util` module.
+///
+/// In an interactive session, it unwinds with the exit code as the payload
+/// instead (see [`set_in_session`]).
 pub fn safe_exit(code: i32) -> ! {
     use std::io::Write;
 
     let _ = std::io::stdout().lock().flush();
     let _ = std::io::stderr().lock().flush();
 
+    if IN_SESSION.load(Ordering::Relaxed) {
+        std::panic::resume_unwind(Box::new(code))
+    }
     std::process::exit(code)
 }
@@ -0,0 +1,445 @@
+//! Offline decoding of serialized transactions, e.g. to review a transaction
+//! written to a file with `--raw-tx-out` before it's broadcast, of token
+//! amounts and of the wallet's aliases.
+
+use std::convert::TryFrom;
+use std::path::Path;
+
+use anoma::proto::{self, SignedTxData, Tx};
+use anoma::types::address::Address;
+use anoma::types::hash::Hash;
+use anoma::types::key::common;
+use anoma::types::storage::Epoch;
+use anoma::types::time::DateTimeUtc;
+use anoma::types::token;
+use anoma::types::transaction::{
+    pos, process_tx, EllipticCurve, Fee, PairingEngine, TxError, TxType,
+    WrapperTxErr,
+};
+use borsh::BorshDeserialize;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use crate::cli::{args, output, Context};
+use crate::wallet::Wallet;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Failed to read the transaction from {0}: {1}")]
+    ReadFile(String, std::io::Error),
+    #[error("The input is neither a file nor a hex encoded transaction: {0}")]
+    InvalidHex(hex::FromHexError),
+    #[error("Failed to decode the transaction: {0}")]
+    TxDecoding(proto::Error),
+    #[error("Invalid wrapper transaction: {0}")]
+    InvalidWrapper(TxError),
+    #[error("The inner transaction couldn't be decrypted: {0}")]
+    Decryption(WrapperTxErr),
+    #[error("Only wrapper and inner transactions can be decoded")]
+    UnsupportedTxType,
+}
+
+/// Decoding result
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A decoded transaction
+#[derive(Debug)]
+pub struct DecodedTx {
+    /// The hash of the transaction's code
+    pub code_hash: Hash,
+    /// The transaction's data
+    pub data: TxData,
+    /// Is the transaction's data signed?
+    pub signed: bool,
+    /// The time at which the transaction was created
+    pub timestamp: DateTimeUtc,
+    /// The fields of the wrapper, if the transaction is wrapped
+    pub wrapper: Option<WrapperFields>,
+}
+
+/// The fields of a wrapper transaction
+#[derive(Debug)]
+pub struct WrapperFields {
+    /// The key that signed the wrapper. Its implicit account pays the fee.
+    pub signer: common::PublicKey,
+    /// The fee paid for the transaction
+    pub fee: Fee,
+    /// The maximum amount of gas that the inner transaction may use
+    pub gas_limit: u64,
+    /// The epoch in which the transaction is to be submitted
+    pub epoch: Epoch,
+}
+
+/// The data of a transaction, decoded as one of the known types if possible
+#[derive(Debug)]
+pub enum TxData {
+    /// No data
+    None,
+    /// A token transfer
+    Transfer(token::Transfer),
+    /// A bond or an unbond
+    Bond(pos::Bond),
+    /// A withdrawal of unbonded tokens
+    Withdraw(pos::Withdraw),
+    /// Data of an unknown type
+    Unknown(Vec<u8>),
+}
+
+impl TxData {
+    /// Try to decode the data as one of the known types, falling back to
+    /// [`TxData::Unknown`].
+    fn decode(data: Option<Vec<u8>>) -> Self {
+        let data = match data {
+            Some(data) => data,
+            None => return Self::None,
+        };
+        if let Ok(transfer) = token::Transfer::try_from_slice(&data) {
+            Self::Transfer(transfer)
+        } else if let Ok(bond) = pos::Bond::try_from_slice(&data) {
+            Self::Bond(bond)
+        } else if let Ok(withdraw) = pos::Withdraw::try_from_slice(&data) {
+            Self::Withdraw(withdraw)
+        } else {
+            Self::Unknown(data)
+        }
+    }
+
+    /// The name of the data type
+    fn kind(&self) -> &'static str {
+        match self {
+            TxData::None => "none",
+            TxData::Transfer(_) => "transfer",
+            TxData::Bond(_) => "bond",
+            TxData::Withdraw(_) => "withdraw",
+            TxData::Unknown(_) => "unknown",
+        }
+    }
+
+    /// The data as a JSON value. Data of an unknown type is hex encoded.
+    fn to_json(&self) -> Value {
+        match self {
+            TxData::None => Value::Null,
+            TxData::Transfer(transfer) => json!({
+                "source": transfer.source.encode(),
+                "target": transfer.target.encode(),
+                "token": transfer.token.encode(),
+                "amount": transfer.amount.to_string(),
+            }),
+            TxData::Bond(bond) => json!({
+                "validator": bond.validator.encode(),
+                "amount": bond.amount.to_string(),
+                "source": bond.source.as_ref().map(|source| source.encode()),
+            }),
+            TxData::Withdraw(withdraw) => json!({
+                "validator": withdraw.validator.encode(),
+                "source": withdraw
+                    .source
+                    .as_ref()
+                    .map(|source| source.encode()),
+            }),
+            TxData::Unknown(data) => Value::String(hex::encode(data)),
+        }
+    }
+}
+
+/// Read a serialized transaction from the given file, or decode it from hex
+/// if there's no such file.
+pub fn read_tx_bytes(input: &str) -> Result<Vec<u8>> {
+    if Path::new(input).is_file() {
+        std::fs::read(input).map_err(|err| Error::ReadFile(input.into(), err))
+    } else {
+        let input = input.trim();
+        hex::decode(input.strip_prefix("0x").unwrap_or(input))
+            .map_err(Error::InvalidHex)
+    }
+}
+
+/// Decode a serialized wrapper or inner transaction into the inner
+/// transaction, which is decrypted from a wrapper, and the wrapper's fields.
+pub fn unwrap_tx_bytes(tx_bytes: &[u8]) -> Result<(Tx, Option<WrapperFields>)> {
+    let tx = Tx::try_from(tx_bytes).map_err(Error::TxDecoding)?;
+    // The wrapper and protocol txs have no code, their type is encoded in
+    // the data
+    if tx.code.is_empty() {
+        match process_tx(tx).map_err(Error::InvalidWrapper)? {
+            TxType::Wrapper(wrapper) => {
+                // TODO: This should not be hardcoded, same as in
+                // `prepare_proposal`
+                let privkey = <EllipticCurve as PairingEngine>::G2Affine::prime_subgroup_generator();
+                let inner_tx =
+                    wrapper.decrypt(privkey).map_err(Error::Decryption)?;
+                let fields = WrapperFields {
+                    signer: wrapper.pk,
+                    fee: wrapper.fee,
+                    gas_limit: u64::from(wrapper.gas_limit),
+                    epoch: wrapper.epoch,
+                };
+                Ok((inner_tx, Some(fields)))
+            }
+            TxType::Raw(tx) => Ok((tx, None)),
+            _ => Err(Error::UnsupportedTxType),
+        }
+    } else {
+        Ok((tx, None))
+    }
+}
+
+/// Decode a serialized wrapper or inner transaction. The inner transaction of
+/// a wrapper is decrypted.
+pub fn decode_tx_bytes(tx_bytes: &[u8]) -> Result<DecodedTx> {
+    let (inner_tx, wrapper) = unwrap_tx_bytes(tx_bytes)?;
+
+    let (data, signed) = match inner_tx
+        .data
+        .as_ref()
+        .map(|data| SignedTxData::try_from_slice(&data[..]))
+    {
+        Some(Ok(SignedTxData { data, sig: _ })) => (data, true),
+        _ => (inner_tx.data.clone(), false),
+    };
+    Ok(DecodedTx {
+        code_hash: Hash::sha256(&inner_tx.code),
+        data: TxData::decode(data),
+        signed,
+        timestamp: inner_tx.timestamp,
+        wrapper,
+    })
+}
+
+/// Decode a serialized transaction and print its contents. This doesn't need
+/// a connection to a ledger node.
+pub fn decode_tx(args: args::DecodeTx) {
+    let decoded = read_tx_bytes(&args.tx_in)
+        .and_then(|tx_bytes| decode_tx_bytes(&tx_bytes))
+        .unwrap_or_else(|err| output::fail(err));
+
+    if output::is_json() {
+        output::print_ok(json!({
+            "code_hash": decoded.code_hash.to_string(),
+            "data_type": decoded.data.kind(),
+            "data": decoded.data.to_json(),
+            "signed": decoded.signed,
+            "timestamp": decoded.timestamp.0.to_rfc3339(),
+            "wrapper": decoded.wrapper.as_ref().map(|wrapper| json!({
+                "signer": wrapper.signer.to_string(),
+                "fee_token": wrapper.fee.token.encode(),
+                "fee_amount": wrapper.fee.amount.to_string(),
+                "gas_limit": wrapper.gas_limit,
+                "epoch": wrapper.epoch.0,
+            })),
+        }));
+        return;
+    }
+    println!("Code hash: {}", decoded.code_hash);
+    match &decoded.data {
+        TxData::None => println!("Data: none"),
+        TxData::Unknown(data) => {
+            println!("Data (unknown type): {}", hex::encode(data))
+        }
+        data => println!(
+            "Data ({}): {}",
+            data.kind(),
+            serde_json::to_string_pretty(&data.to_json())
+                .expect("Serializing JSON shouldn't fail")
+        ),
+    }
+    println!("Signed: {}", if decoded.signed { "yes" } else { "no" });
+    println!("Timestamp: {}", decoded.timestamp.0.to_rfc3339());
+    match &decoded.wrapper {
+        Some(wrapper) => {
+            println!("Signer: {}", wrapper.signer);
+            println!(
+                "Fee: {} {}",
+                wrapper.fee.amount,
+                wrapper.fee.token.encode()
+            );
+            println!("Gas limit: {}", wrapper.gas_limit);
+            println!("Epoch: {}", wrapper.epoch);
+        }
+        None => println!("Not wrapped, no signer or fee"),
+    }
+}
+
+/// Convert a token amount between its human readable and raw representations
+/// and print the result. All the tokens share the precision of
+/// [`token::MAX_DECIMAL_PLACES`] decimal places, unless an explicit number of
+/// decimal places is given. This doesn't need a connection to a ledger node.
+pub fn convert_amount(ctx: Context, args: args::ConvertAmount) {
+    let decimals = match (args.decimals, &args.token) {
+        (Some(decimals), _) => decimals,
+        (None, Some(token_arg)) => {
+            // Only check that the token can be resolved
+            let _token = ctx.get(token_arg);
+            token::MAX_DECIMAL_PLACES
+        }
+        (None, None) => output::fail(
+            "Either a token or a number of decimal places is required",
+        ),
+    };
+    let (human, raw) = match (args.human, args.raw) {
+        (Some(human), _) => {
+            let raw = token::parse_amount_with_decimals(&human, decimals)
+                .unwrap_or_else(|err| output::fail(err));
+            if !output::is_json() {
+                println!("{}", raw);
+            }
+            (token::format_amount_with_decimals(raw, decimals), raw)
+        }
+        (None, Some(raw)) => {
+            let human = token::format_amount_with_decimals(raw, decimals);
+            if !output::is_json() {
+                println!("{}", human);
+            }
+            (human, raw)
+        }
+        (None, None) => {
+            output::fail("Either a human readable or a raw amount is required")
+        }
+    };
+    output::print_ok(json!({
+        "human": human,
+        "raw": raw.to_string(),
+        "decimals": decimals,
+    }));
+}
+
+/// The alias printed for an address that is not stored in the wallet
+pub const UNKNOWN_ALIAS: &str = "unknown";
+
+/// Resolve an alias to the address stored with it in the wallet and print it.
+pub fn resolve_alias(ctx: Context, args: args::ResolveAlias) {
+    let address = ctx.wallet.find_address(&args.alias).unwrap_or_else(|| {
+        output::fail(format!(
+            "No address with alias {} found in the wallet.",
+            args.alias.to_lowercase()
+        ))
+    });
+    if !output::is_json() {
+        println!("{}", address);
+    }
+    output::print_ok(json!({
+        "alias": args.alias.to_lowercase(),
+        "address": address.encode(),
+    }));
+}
+
+/// Resolve an address to the alias with which it's stored in the wallet and
+/// print it, or [`UNKNOWN_ALIAS`] if the address is not stored.
+pub fn resolve_address(ctx: Context, args: args::ResolveAddress) {
+    let alias = address_alias(&ctx.wallet, &args.address);
+    if !output::is_json() {
+        println!("{}", alias);
+    }
+    output::print_ok(json!({
+        "address": args.address.encode(),
+        "alias": alias,
+    }));
+}
+
+/// The alias of an address in the wallet, or [`UNKNOWN_ALIAS`] if the address
+/// is not stored.
+pub fn address_alias(wallet: &Wallet, address: &Address) -> String {
+    wallet
+        .find_alias(address)
+        .unwrap_or_else(|| UNKNOWN_ALIAS.to_string())
+}
+
+#[cfg(test)]
+mod test_decode_tx {
+    use anoma::types::address::{self, xan};
+    use anoma::types::key::testing::keypair_1;
+    use anoma::types::key::RefTo;
+    use anoma::types::transaction::{EncryptionKey, WrapperTx};
+    use borsh::BorshSerialize;
+
+    use super::*;
+
+    /// Test that a signed and wrapped transfer is decoded back to the same
+    /// source, target, token and amount
+    #[test]
+    fn test_decode_wrapped_transfer() {
+        let keypair = keypair_1();
+        let transfer = token::Transfer {
+            source: address::testing::established_address_1(),
+            target: address::testing::established_address_2(),
+            token: xan(),
+            amount: token::Amount::whole(42),
+        };
+        let tx = Tx::new(
+            b"tx_transfer".to_vec(),
+            Some(transfer.try_to_vec().expect("Test failed")),
+        )
+        .sign(&keypair);
+        let fee = Fee {
+            amount: token::Amount::whole(1),
+            token: xan(),
+            gas_price: None,
+        };
+        let wrapper = WrapperTx::new(
+            fee.clone(),
+            &keypair,
+            Epoch(3),
+            1_000_000.into(),
+            tx,
+            EncryptionKey::default(),
+        )
+        .sign(&keypair)
+        .expect("Test failed");
+
+        // Decode it from hex, like a tx pasted on the command line
+        let tx_bytes = read_tx_bytes(&hex::encode(wrapper.to_bytes()))
+            .expect("Test failed");
+        let decoded = decode_tx_bytes(&tx_bytes).expect("Test failed");
+
+        assert_eq!(decoded.code_hash, Hash::sha256(b"tx_transfer"));
+        assert!(decoded.signed);
+        match decoded.data {
+            TxData::Transfer(decoded) => assert_eq!(decoded, transfer),
+            data => panic!("Expected a transfer, got {:?}", data),
+        }
+        let wrapper = decoded.wrapper.expect("Test failed");
+        assert_eq!(wrapper.signer, keypair.ref_to());
+        assert_eq!(wrapper.fee, fee);
+        assert_eq!(wrapper.gas_limit, 1_000_000);
+        assert_eq!(wrapper.epoch, Epoch(3));
+    }
+
+    /// Test that data of an unknown type falls back to its raw bytes
+    #[test]
+    fn test_decode_unknown_data() {
+        let tx = Tx::new(b"tx_custom".to_vec(), Some(vec![1, 2, 3]));
+        let decoded = decode_tx_bytes(&tx.to_bytes()).expect("Test failed");
+        assert!(!decoded.signed);
+        assert!(decoded.wrapper.is_none());
+        assert!(
+            matches!(decoded.data, TxData::Unknown(data) if data == vec![1, 2, 3])
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_resolve_alias {
+    use anoma::types::address::testing::{
+        established_address_1, established_address_2,
+    };
+
+    use super::*;
+
+    /// Test that an address is resolved back to the alias with which it's
+    /// stored in the wallet and that an unknown address is reported as such
+    #[test]
+    fn test_resolve_address() {
+        let store_dir = tempfile::tempdir().expect("Test failed");
+        let mut wallet = Wallet::load_or_new(store_dir.path());
+        let bertha = established_address_1();
+        wallet.add_address("Bertha", bertha.clone());
+
+        assert_eq!(wallet.find_address("bertha"), Some(&bertha));
+        assert_eq!(address_alias(&wallet, &bertha), "bertha");
+        assert_eq!(
+            address_alias(&wallet, &established_address_2()),
+            UNKNOWN_ALIAS
+        );
+    }
+}
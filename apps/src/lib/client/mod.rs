@@ -1,4 +1,6 @@
+pub mod decode;
 pub mod gossip;
+pub mod repl;
 pub mod rpc;
 pub mod signing;
 mod tendermint_websocket_client;
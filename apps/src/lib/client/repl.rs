@@ -0,0 +1,135 @@
+//! The client's interactive session for queries.
+//!
+//! The session reads the commands line by line from stdin. The queries reuse
+//! a single client of the ledger node and the context (the chain ID and the
+//! wallet) loaded once at the start of the session. Besides the queries, the
+//! session supports these built-in commands:
+//!
+//! - `help`: print the usage of the queries
+//! - `history`: print the numbered commands entered in this session
+//! - `!N`: re-run the N-th command of the history
+//! - `complete PREFIX`: list the commands and the wallet's address aliases
+//!   starting with the given prefix
+//! - `exit` or `quit`: end the session (so does the end of input)
+
+use std::io::{self, BufRead, Write};
+use std::panic::AssertUnwindSafe;
+
+use futures::FutureExt;
+use itertools::Itertools;
+
+use crate::cli::cmds::*;
+use crate::cli::{self, args, Context};
+use crate::client::rpc;
+
+/// The built-in commands of the session
+const BUILT_IN_CMDS: [&str; 5] =
+    ["complete", "exit", "help", "history", "quit"];
+
+/// Run an interactive session until its input ends or it's exited.
+pub async fn run(ctx: Context, args: args::Query) {
+    println!(
+        "Querying the ledger node at {} of the chain {}. Type \"help\" for \
+         the available commands.",
+        args.ledger_address, ctx.global_config.default_chain_id
+    );
+    let stdin = io::stdin();
+    let mut history: Vec<String> = vec![];
+    loop {
+        print!("anoma> ");
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(err) => {
+                eprintln!("Failed to read the command: {}", err);
+                break;
+            }
+        }
+        let mut line = line.trim().to_owned();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(number) = line.strip_prefix('!') {
+            let cmd = number
+                .parse::<usize>()
+                .ok()
+                .and_then(|number| history.get(number.checked_sub(1)?));
+            match cmd {
+                Some(cmd) => {
+                    println!("{}", cmd);
+                    line = cmd.clone();
+                }
+                None => {
+                    eprintln!("No command {} in the history", number);
+                    continue;
+                }
+            }
+        }
+        history.push(line.clone());
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[0] {
+            "exit" | "quit" => break,
+            "help" => {
+                if let Err(err) = cli::anoma_client_repl_cmd(&[]) {
+                    println!("{}", err);
+                }
+                println!("Built-in commands: {}", BUILT_IN_CMDS.join(", "));
+            }
+            "history" => {
+                for (number, cmd) in history.iter().enumerate() {
+                    println!("{:5} {}", number + 1, cmd);
+                }
+            }
+            "complete" => {
+                let prefix = words.get(1).copied().unwrap_or_default();
+                let completions = ReplCmd::CMDS
+                    .iter()
+                    .chain(BUILT_IN_CMDS.iter())
+                    .map(|cmd| cmd.to_string())
+                    .chain(ctx.wallet.get_addresses().into_keys())
+                    .filter(|word| word.starts_with(prefix))
+                    .sorted()
+                    .join(" ");
+                println!("{}", completions);
+            }
+            _ => match cli::anoma_client_repl_cmd(&words) {
+                Ok(cmd) => run_cmd(&ctx, &args, cmd).await,
+                Err(err) => eprintln!("{}", err),
+            },
+        }
+    }
+}
+
+/// Run a query of the session against the session's ledger node. A failed
+/// query prints its error and returns, without ending the session.
+async fn run_cmd(ctx: &Context, query: &args::Query, cmd: ReplCmd) {
+    let query = query.clone();
+    let run = async move {
+        match cmd {
+            ReplCmd::QueryBalance(QueryBalance(args)) => {
+                rpc::query_balance(ctx, args::QueryBalance { query, ..args })
+                    .await
+            }
+            ReplCmd::QueryRawBytes(QueryRawBytes(args)) => {
+                rpc::query_raw_bytes(ctx, args::QueryRawBytes { query, ..args })
+                    .await
+            }
+            ReplCmd::QueryProposal(QueryProposal(args)) => {
+                rpc::query_proposal(ctx, args::QueryProposal { query, ..args })
+                    .await
+            }
+            ReplCmd::QueryEpoch(_) => {
+                rpc::query_epoch(query.clone()).await;
+                rpc::query_epoch_progress(query).await;
+            }
+            ReplCmd::QueryNodeStatus(_) => rpc::query_node_status(query).await,
+        }
+    };
+    cli::set_in_session(true);
+    // The error of a failed query has already been printed
+    let _result = AssertUnwindSafe(run).catch_unwind().await;
+    cli::set_in_session(false);
+}
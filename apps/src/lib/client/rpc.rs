@@ -4,31 +4,50 @@ use std::borrow::Cow;
 use std::collections::HashMap;
 use std::convert::TryInto;
 use std::io::{self, Write};
+use std::sync::Mutex;
+use std::time::Duration;
 
+use anoma::ledger::gas::StorageGasCosts;
 use anoma::ledger::governance::storage as gov_storage;
+use anoma::ledger::parameters::{
+    self, EpochDuration, FeeExemption, FeeRefundPolicy, FeeTokenRates,
+    TxOrdering, TxSizeLimits,
+};
 use anoma::ledger::pos::types::{
     Epoch as PosEpoch, VotingPower, WeightedValidator,
 };
 use anoma::ledger::pos::{
-    self, is_validator_slashes_key, Bonds, Slash, Unbonds,
+    self, is_validator_slashes_key, Bonds, PosParams, Slash, Unbonds,
+};
+use anoma::ledger::storage::{
+    verify_merkle_proof, EpochProgress, MerkleRoot, Sha256Hasher,
 };
 use anoma::types::address::Address;
+use anoma::types::governance::ProposalContentLimits;
+use anoma::types::hash::Hash;
 use anoma::types::key::*;
-use anoma::types::storage::{Epoch, PrefixValue};
+use anoma::types::storage::{BlockHeight, Epoch, PrefixValue};
+use anoma::types::time::DurationSecs;
 use anoma::types::token::{balance_key, Amount};
+use anoma::types::transaction::pos::ValidatorMetadata;
 use anoma::types::{address, storage, token};
-use borsh::BorshDeserialize;
+use borsh::{BorshDeserialize, BorshSerialize};
+use futures::StreamExt;
 use itertools::Itertools;
+use once_cell::sync::Lazy;
+use serde_json::json;
 #[cfg(not(feature = "ABCI"))]
 use tendermint::abci::Code;
 #[cfg(not(feature = "ABCI"))]
+use tendermint::block::Height;
+#[cfg(not(feature = "ABCI"))]
 use tendermint_config::net::Address as TendermintAddress;
 #[cfg(feature = "ABCI")]
 use tendermint_config_abci::net::Address as TendermintAddress;
 #[cfg(not(feature = "ABCI"))]
 use tendermint_rpc::error::Error as TError;
 #[cfg(not(feature = "ABCI"))]
-use tendermint_rpc::query::Query;
+use tendermint_rpc::query::{EventType, Query};
 #[cfg(not(feature = "ABCI"))]
 use tendermint_rpc::{Client, HttpClient};
 #[cfg(not(feature = "ABCI"))]
@@ -36,21 +55,43 @@ use tendermint_rpc::{Order, SubscriptionClient, WebSocketClient};
 #[cfg(feature = "ABCI")]
 use tendermint_rpc_abci::error::Error as TError;
 #[cfg(feature = "ABCI")]
-use tendermint_rpc_abci::query::Query;
+use tendermint_rpc_abci::query::{EventType, Query};
 #[cfg(feature = "ABCI")]
 use tendermint_rpc_abci::{Client, HttpClient};
 #[cfg(feature = "ABCI")]
 use tendermint_rpc_abci::{Order, SubscriptionClient, WebSocketClient};
 #[cfg(feature = "ABCI")]
 use tendermint_stable::abci::Code;
+#[cfg(feature = "ABCI")]
+use tendermint_stable::block::Height;
 
-use crate::cli::{self, args, Context};
+use crate::cli::{args, output, Context};
 use crate::client::tx::TxResponse;
-use crate::node::ledger::rpc::Path;
+use crate::node::ledger::rpc::{
+    AccountMetadata, BlockEvents, BlockSimResult, BlockSummary,
+    ConsensusValidatorSet, DryRunResult, MempoolContents, NodeStatus, Path,
+    SimulatedTx, StorageSize, MAX_BLOCK_EVENTS, MAX_MEMPOOL_TXS,
+};
+
+/// The clients of the ledger nodes queried by this process by their address,
+/// shared by the queries to reuse their connections
+static LEDGER_CLIENTS: Lazy<Mutex<HashMap<String, HttpClient>>> =
+    Lazy::new(Default::default);
+
+/// Get a client of the ledger node at the given address. The client is
+/// shared with all the queries of the same address in this process, so that
+/// a long-running session, like the REPL, reuses its connections.
+pub fn ledger_client(ledger_address: TendermintAddress) -> HttpClient {
+    let mut clients = LEDGER_CLIENTS.lock().unwrap();
+    clients
+        .entry(ledger_address.to_string())
+        .or_insert_with(|| HttpClient::new(ledger_address).unwrap())
+        .clone()
+}
 
 /// Query the epoch of the last committed block
 pub async fn query_epoch(args: args::Query) -> Epoch {
-    let client = HttpClient::new(args.ledger_address).unwrap();
+    let client = ledger_client(args.ledger_address);
     let path = Path::Epoch;
     let data = vec![];
     let response = client
@@ -60,25 +101,400 @@ pub async fn query_epoch(args: args::Query) -> Epoch {
     match response.code {
         Code::Ok => match Epoch::try_from_slice(&response.value[..]) {
             Ok(epoch) => {
-                println!("Last committed epoch: {}", epoch);
+                if !output::is_json() {
+                    println!("Last committed epoch: {}", epoch);
+                }
                 return epoch;
             }
 
             Err(err) => {
-                eprintln!("Error decoding the epoch value: {}", err)
+                output::fail(format!("Error decoding the epoch value: {}", err))
+            }
+        },
+        Code::Err(err) => output::fail(format!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        )),
+    }
+}
+
+/// Query the progress of the current epoch towards its end
+pub async fn query_epoch_progress(args: args::Query) -> EpochProgress {
+    let client = ledger_client(args.ledger_address);
+    let path = Path::EpochProgress;
+    let data = vec![];
+    let response = client
+        .abci_query(Some(path.into()), data, None, false)
+        .await
+        .unwrap();
+    match response.code {
+        Code::Ok => match EpochProgress::try_from_slice(&response.value[..]) {
+            Ok(progress) => {
+                if output::is_json() {
+                    output::print_ok(json!({
+                        "epoch": progress.epoch.0,
+                        "blocks_in_epoch": progress.num_of_blocks(),
+                        "next_epoch_min_start_height":
+                            progress.next_epoch_min_start_height.0,
+                        "next_epoch_min_start_time":
+                            progress.next_epoch_min_start_time.0.to_rfc3339(),
+                    }));
+                    return progress;
+                }
+                println!(
+                    "{:4}Blocks in the current epoch: {}",
+                    "",
+                    progress.num_of_blocks()
+                );
+                println!(
+                    "{:4}Min. start height of the next epoch: {} ({} blocks \
+                     remaining)",
+                    "",
+                    progress.next_epoch_min_start_height,
+                    progress.remaining_blocks()
+                );
+                println!(
+                    "{:4}Min. start time of the next epoch: {} ({}s remaining)",
+                    "",
+                    progress.next_epoch_min_start_time.0.to_rfc3339(),
+                    progress.remaining_duration().0
+                );
+                return progress;
             }
+            Err(err) => output::fail(format!(
+                "Error decoding the epoch progress value: {}",
+                err
+            )),
         },
-        Code::Err(err) => eprintln!(
+        Code::Err(err) => output::fail(format!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        )),
+    }
+}
+
+/// Query the kind of an account
+pub async fn query_metadata(ctx: Context, args: args::QueryMetadata) {
+    let client = ledger_client(args.query.ledger_address);
+    let address = ctx.get(&args.address);
+    let path = Path::AccountMetadata(address);
+    let data = vec![];
+    let response = client
+        .abci_query(Some(path.into()), data, None, false)
+        .await
+        .unwrap();
+    match response.code {
+        Code::Ok => {
+            match AccountMetadata::try_from_slice(&response.value[..]) {
+                Ok(metadata) => {
+                    println!("Address: {}", metadata.address.encode());
+                    println!("{:4}Kind: {}", "", metadata.kind());
+                    let vp_template = match &metadata.address {
+                        Address::Established(_) => {
+                            metadata.vp_template.as_deref().unwrap_or("custom")
+                        }
+                        _ => "none",
+                    };
+                    println!("{:4}VP template: {}", "", vp_template);
+                    println!("{:4}Validator: {}", "", metadata.is_validator);
+                    if metadata.authored_proposals.is_empty() {
+                        println!("{:4}Governance role: none", "");
+                    } else {
+                        println!(
+                            "{:4}Governance role: author of proposals {}",
+                            "",
+                            metadata.authored_proposals.iter().join(", ")
+                        );
+                    }
+                    return;
+                }
+                Err(err) => output::fail(format!(
+                    "Error decoding the account metadata: {}",
+                    err
+                )),
+            }
+        }
+        Code::Err(err) => output::fail(format!(
             "Error in the query {} (error code {})",
             response.info, err
+        )),
+    }
+}
+
+/// Query the base fee per unit of gas that transactions must pay
+pub async fn query_base_fee(args: args::Query) -> token::Amount {
+    let client = ledger_client(args.ledger_address);
+    let key = parameters::base_fee_storage_key();
+    match query_storage_value::<token::Amount>(&client, &key).await {
+        Some(base_fee) => {
+            println!("Base fee per unit of gas: {}", base_fee);
+            base_fee
+        }
+        None => output::fail("Unable to read the base fee"),
+    }
+}
+
+/// Query the random seed of the current epoch
+pub async fn query_epoch_seed(args: args::Query) -> Hash {
+    let client = ledger_client(args.ledger_address);
+    let key = parameters::epoch_seed_storage_key();
+    match query_storage_value::<Hash>(&client, &key).await {
+        Some(seed) => {
+            if output::is_json() {
+                output::print_ok(json!({ "epoch_seed": seed.to_string() }));
+            } else {
+                println!("Epoch seed: {}", seed);
+            }
+            seed
+        }
+        None => output::fail("Unable to read the epoch seed"),
+    }
+}
+
+/// Query the protocol, PoS and governance parameters
+pub async fn query_protocol_parameters(args: args::Query) {
+    let client = ledger_client(args.ledger_address);
+
+    println!("Protocol parameters:");
+    let key = parameters::epoch_storage_key();
+    let epoch_duration: EpochDuration =
+        query_parameter(&client, &key, "epoch duration").await;
+    println!(
+        "{:4}Min. number of blocks in an epoch: {}",
+        "", epoch_duration.min_num_of_blocks
+    );
+    println!(
+        "{:4}Min. duration of an epoch: {}s",
+        "", epoch_duration.min_duration.0
+    );
+    let key = parameters::max_expected_time_per_block_key();
+    let max_block_time: DurationSecs =
+        query_parameter(&client, &key, "max. expected time per block").await;
+    println!(
+        "{:4}Max. expected time per block: {}s",
+        "", max_block_time.0
+    );
+    let key = parameters::base_fee_storage_key();
+    let base_fee: token::Amount =
+        query_parameter(&client, &key, "base fee").await;
+    println!("{:4}Base fee per unit of gas: {}", "", base_fee);
+    let key = parameters::min_base_fee_storage_key();
+    let min_base_fee: token::Amount =
+        query_parameter(&client, &key, "min. base fee").await;
+    println!("{:4}Min. base fee per unit of gas: {}", "", min_base_fee);
+    let key = parameters::tx_ordering_storage_key();
+    let tx_ordering: TxOrdering =
+        query_parameter(&client, &key, "tx ordering").await;
+    println!("{:4}Tx ordering in a block: {}", "", tx_ordering);
+    let key = parameters::tx_size_limits_storage_key();
+    let tx_size_limits: TxSizeLimits =
+        query_parameter(&client, &key, "tx size limits").await;
+    println!("{:4}Max. tx sizes: {}", "", tx_size_limits);
+    let key = parameters::fee_exemption_storage_key();
+    let fee_exemption: FeeExemption =
+        query_parameter(&client, &key, "fee exemption").await;
+    println!("{:4}Fee exempt payers: {}", "", fee_exemption);
+    let key = parameters::fee_token_rates_storage_key();
+    let fee_token_rates: FeeTokenRates =
+        query_parameter(&client, &key, "fee token rates").await;
+    println!("{:4}Fee tokens: {}", "", fee_token_rates);
+    let key = parameters::fee_refund_policy_storage_key();
+    let fee_refund_policy: FeeRefundPolicy =
+        query_parameter(&client, &key, "fee refund policy").await;
+    println!("{:4}Fee refund policy: {}", "", fee_refund_policy);
+    let key = parameters::storage_gas_costs_storage_key();
+    let storage_gas_costs: StorageGasCosts =
+        query_parameter(&client, &key, "storage gas costs").await;
+    println!("{:4}Storage gas: {}", "", storage_gas_costs);
+    let key = parameters::write_log_flush_threshold_storage_key();
+    let flush_threshold: Option<u64> =
+        query_parameter(&client, &key, "write log flush threshold").await;
+    match flush_threshold {
+        Some(threshold) => println!(
+            "{:4}Block changes staged in the DB above: {} bytes",
+            "", threshold
+        ),
+        None => {
+            println!("{:4}Block changes staged in the DB: never", "")
+        }
+    }
+
+    println!("PoS parameters:");
+    let key = pos::params_key();
+    let pos_params: PosParams =
+        query_parameter(&client, &key, "PoS parameters").await;
+    println!(
+        "{:4}Max. number of active validators: {}",
+        "", pos_params.max_validator_slots
+    );
+    println!("{:4}Pipeline length: {}", "", pos_params.pipeline_len);
+    println!("{:4}Unbonding length: {}", "", pos_params.unbonding_len);
+    println!("{:4}Votes per token: {}", "", pos_params.votes_per_token);
+    println!(
+        "{:4}Block proposer reward: {}",
+        "", pos_params.block_proposer_reward
+    );
+    println!(
+        "{:4}Block vote reward: {}",
+        "", pos_params.block_vote_reward
+    );
+    println!(
+        "{:4}Duplicate vote slash rate: {}",
+        "", pos_params.duplicate_vote_slash_rate
+    );
+    println!(
+        "{:4}Light client attack slash rate: {}",
+        "", pos_params.light_client_attack_slash_rate
+    );
+    println!("{:4}Jail duration: {}", "", pos_params.jail_duration);
+    println!(
+        "{:4}Min. validator self-bond: {}",
+        "",
+        token::Amount::from(pos_params.min_validator_self_bond)
+    );
+    match pos_params.max_validator_stake {
+        Some(max_validator_stake) => println!(
+            "{:4}Max. validator stake: {}",
+            "",
+            token::Amount::from(max_validator_stake)
         ),
+        None => println!("{:4}Max. validator stake: no maximum", ""),
+    }
+    println!(
+        "{:4}Max. commission rate change per epoch: {}",
+        "", pos_params.max_commission_rate_change
+    );
+    println!(
+        "{:4}Liveness window length: {}",
+        "", pos_params.liveness_window_len
+    );
+    println!(
+        "{:4}Liveness max. missed blocks: {}",
+        "", pos_params.liveness_max_missed_blocks
+    );
+    println!(
+        "{:4}Liveness slash rate: {}",
+        "", pos_params.liveness_slash_rate
+    );
+    println!(
+        "{:4}Tombstone on double-sign: {}",
+        "", pos_params.tombstone_on_double_sign
+    );
+
+    println!("Governance parameters:");
+    let key = gov_storage::get_min_proposal_fund_key();
+    let min_proposal_fund: token::Amount =
+        query_parameter(&client, &key, "min. proposal fund").await;
+    println!("{:4}Min. proposal fund: {}", "", min_proposal_fund);
+    let key = gov_storage::get_max_proposal_code_size_key();
+    let max_proposal_code_size: u64 =
+        query_parameter(&client, &key, "max. proposal code size").await;
+    println!(
+        "{:4}Max. proposal code size: {}",
+        "", max_proposal_code_size
+    );
+    let key = gov_storage::get_min_proposal_period_key();
+    let min_proposal_period: u64 =
+        query_parameter(&client, &key, "min. proposal period").await;
+    println!("{:4}Min. proposal period: {}", "", min_proposal_period);
+    let key = gov_storage::get_max_proposal_content_key();
+    let max_proposal_content_size: u64 =
+        query_parameter(&client, &key, "max. proposal content size").await;
+    println!(
+        "{:4}Max. proposal content size: {}",
+        "", max_proposal_content_size
+    );
+    let key = gov_storage::get_min_proposal_grace_epoch_key();
+    let min_proposal_grace_epochs: u64 =
+        query_parameter(&client, &key, "min. proposal grace epochs").await;
+    println!(
+        "{:4}Min. proposal grace epochs: {}",
+        "", min_proposal_grace_epochs
+    );
+    // The chains initialized before the content limits were added use the
+    // default limits
+    let key = gov_storage::get_proposal_content_limits_key();
+    let content_limits: ProposalContentLimits =
+        query_storage_value(&client, &key).await.unwrap_or_default();
+    println!(
+        "{:4}Min. proposal content field size: {}",
+        "", content_limits.min_field_size
+    );
+    println!(
+        "{:4}Max. proposal title size: {}",
+        "", content_limits.max_title_size
+    );
+    println!(
+        "{:4}Max. proposal abstract size: {}",
+        "", content_limits.max_abstract_size
+    );
+}
+
+/// Query a parameter value, exits with an error if it's not found.
+async fn query_parameter<T>(
+    client: &HttpClient,
+    key: &storage::Key,
+    name: &str,
+) -> T
+where
+    T: BorshDeserialize,
+{
+    query_storage_value(client, key).await.unwrap_or_else(|| {
+        output::fail(format!("Unable to read the {} parameter", name))
+    })
+}
+
+/// Decode raw storage bytes as the given type for display. Returns `None`
+/// when the bytes are not a value of the type.
+pub fn decode_raw_bytes(
+    value_type: args::ValueType,
+    bytes: &[u8],
+) -> Option<String> {
+    match value_type {
+        args::ValueType::Amount => Amount::try_from_slice(bytes)
+            .ok()
+            .map(|amount| amount.to_string()),
+        args::ValueType::PublicKey => common::PublicKey::try_from_slice(bytes)
+            .ok()
+            .map(|pk| pk.to_string()),
+        // The VP code is stored as raw WASM bytes
+        args::ValueType::VpHash => {
+            (!bytes.is_empty()).then(|| Hash::sha256(bytes).to_string())
+        }
+        args::ValueType::Address => Address::try_from_slice(bytes)
+            .ok()
+            .map(|addr| addr.encode()),
+    }
+}
+
+/// Print the raw bytes of a storage value, decoded as the requested type if
+/// possible, otherwise in hex.
+fn print_raw_bytes(decode_as: Option<args::ValueType>, bytes: &[u8]) {
+    match decode_as {
+        Some(value_type) => match decode_raw_bytes(value_type, bytes) {
+            Some(decoded) => println!("{}", decoded),
+            None => {
+                eprintln!(
+                    "The value couldn't be decoded as {:?}, printing it in hex",
+                    value_type
+                );
+                println!("{}", hex::encode(bytes));
+            }
+        },
+        None => println!("{}", hex::encode(bytes)),
     }
-    cli::safe_exit(1)
 }
 
 /// Query the raw bytes of given storage key
-pub async fn query_raw_bytes(_ctx: Context, args: args::QueryRawBytes) {
-    let client = HttpClient::new(args.query.ledger_address).unwrap();
+pub async fn query_raw_bytes(_ctx: &Context, args: args::QueryRawBytes) {
+    let client = ledger_client(args.query.ledger_address);
+    if args.prove {
+        match query_proven_storage_value(&client, &args.storage_key).await {
+            Some(value) => print_raw_bytes(args.decode_as, &value),
+            None => println!("No value found for key: {}", args.storage_key),
+        }
+        return;
+    }
     let path = Path::Value(args.storage_key);
     let data = vec![];
     let response = client
@@ -87,22 +503,31 @@ pub async fn query_raw_bytes(_ctx: Context, args: args::QueryRawBytes) {
         .unwrap();
     match response.code {
         Code::Ok => {
-            println!("{}", hex::encode(&response.value));
-        }
-        Code::Err(err) => {
-            eprintln!(
-                "Error in the query {}  (error code {})",
-                response.info, err
-            );
-            cli::safe_exit(1)
+            print_raw_bytes(args.decode_as, &response.value);
         }
+        Code::Err(err) => output::fail(format!(
+            "Error in the query {}  (error code {})",
+            response.info, err
+        )),
     }
 }
 
 /// Query token balance(s)
-pub async fn query_balance(ctx: Context, args: args::QueryBalance) {
-    let client = HttpClient::new(args.query.ledger_address).unwrap();
+pub async fn query_balance(ctx: &Context, args: args::QueryBalance) {
+    let client = ledger_client(args.query.ledger_address);
+    let height = query_height_for_epoch(&client, args.epoch).await;
     let tokens = address::tokens();
+    let json = output::is_json();
+    // The balances found, reported in the JSON output mode
+    let mut found = vec![];
+    let mut report =
+        |owner: &Address, token: &Address, balance: token::Amount| {
+            found.push(json!({
+                "owner": owner.encode(),
+                "token": token.encode(),
+                "amount": balance.to_string(),
+            }))
+        };
     match (args.token, args.owner) {
         (Some(token), Some(owner)) => {
             let token = ctx.get(&token);
@@ -112,10 +537,18 @@ pub async fn query_balance(ctx: Context, args: args::QueryBalance) {
                 .get(&token)
                 .map(|c| Cow::Borrowed(*c))
                 .unwrap_or_else(|| Cow::Owned(token.to_string()));
-            match query_storage_value::<token::Amount>(&client, &key).await {
+            let balance = if args.prove {
+                query_proven_storage_value_as::<token::Amount>(&client, &key)
+                    .await
+            } else {
+                query_storage_value_at(&client, &key, height).await
+            };
+            match balance {
+                Some(balance) if json => report(&owner, &token, balance),
                 Some(balance) => {
                     println!("{}: {}", currency_code, balance);
                 }
+                None if json => {}
                 None => {
                     println!("No {} balance found for {}", currency_code, owner)
                 }
@@ -126,14 +559,24 @@ pub async fn query_balance(ctx: Context, args: args::QueryBalance) {
             let mut found_any = false;
             for (token, currency_code) in tokens {
                 let key = token::balance_key(&token, &owner);
-                if let Some(balance) =
-                    query_storage_value::<token::Amount>(&client, &key).await
-                {
-                    println!("{}: {}", currency_code, balance);
+                let balance = if args.prove {
+                    query_proven_storage_value_as::<token::Amount>(
+                        &client, &key,
+                    )
+                    .await
+                } else {
+                    query_storage_value_at(&client, &key, height).await
+                };
+                if let Some(balance) = balance {
+                    if json {
+                        report(&owner, &token, balance);
+                    } else {
+                        println!("{}: {}", currency_code, balance);
+                    }
                     found_any = true;
                 }
             }
-            if !found_any {
+            if !found_any && !json {
                 println!("No balance found for {}", owner);
             }
         }
@@ -141,8 +584,16 @@ pub async fn query_balance(ctx: Context, args: args::QueryBalance) {
             let token = ctx.get(&token);
             let key = token::balance_prefix(&token);
             let balances =
-                query_storage_prefix::<token::Amount>(client, key).await;
+                query_storage_prefix_at::<token::Amount>(client, key, height)
+                    .await;
             match balances {
+                Some(balances) if json => {
+                    for (key, balance) in balances {
+                        let owner =
+                            token::is_any_token_balance_key(&key).unwrap();
+                        report(owner, &token, balance);
+                    }
+                }
                 Some(balances) => {
                     let currency_code = tokens
                         .get(&token)
@@ -158,6 +609,7 @@ pub async fn query_balance(ctx: Context, args: args::QueryBalance) {
                             .unwrap();
                     }
                 }
+                None if json => {}
                 None => {
                     println!("No balances for token {}", token.encode())
                 }
@@ -168,10 +620,20 @@ pub async fn query_balance(ctx: Context, args: args::QueryBalance) {
             let mut w = stdout.lock();
             for (token, currency_code) in tokens {
                 let key = token::balance_prefix(&token);
-                let balances =
-                    query_storage_prefix::<token::Amount>(client.clone(), key)
-                        .await;
+                let balances = query_storage_prefix_at::<token::Amount>(
+                    client.clone(),
+                    key,
+                    height,
+                )
+                .await;
                 match balances {
+                    Some(balances) if json => {
+                        for (key, balance) in balances {
+                            let owner =
+                                token::is_any_token_balance_key(&key).unwrap();
+                            report(owner, &token, balance);
+                        }
+                    }
                     Some(balances) => {
                         writeln!(w, "Token {}:", currency_code).unwrap();
                         for (key, balance) in balances {
@@ -181,6 +643,7 @@ pub async fn query_balance(ctx: Context, args: args::QueryBalance) {
                                 .unwrap();
                         }
                     }
+                    None if json => {}
                     None => {
                         println!("No balances for token {}", token.encode())
                     }
@@ -188,10 +651,131 @@ pub async fn query_balance(ctx: Context, args: args::QueryBalance) {
             }
         }
     }
+    output::print_ok(json!({ "balances": found }));
+}
+
+/// Query the balances of the given owner in the given tokens. The tokens
+/// in which the owner has no balance are skipped.
+pub async fn query_owner_balances(
+    client: &HttpClient,
+    owner: &Address,
+    tokens: &[Address],
+) -> Result<Vec<(Address, token::Amount)>, String> {
+    let mut balances = vec![];
+    for token in tokens {
+        let key = token::balance_key(token, owner);
+        if let Some(balance) = try_query_storage_value(client, &key).await? {
+            balances.push((token.clone(), balance));
+        }
+    }
+    Ok(balances)
+}
+
+/// Query the token balances of all the addresses listed in a file. The
+/// balances are written out as soon as they're queried. The addresses whose
+/// balances cannot be queried are reported at the end.
+pub async fn query_balances(ctx: Context, args: args::QueryBalances) {
+    let client = ledger_client(args.query.ledger_address);
+    let file =
+        std::fs::File::open(&args.addresses_file).unwrap_or_else(|err| {
+            output::fail(format!(
+                "Cannot open the addresses file {}: {}",
+                args.addresses_file.to_string_lossy(),
+                err
+            ))
+        });
+    let known_tokens = address::tokens();
+    let tokens: Vec<Address> = match &args.token {
+        Some(token) => vec![ctx.get(token)],
+        None => known_tokens
+            .iter()
+            .sorted_by_key(|(_token, currency_code)| *currency_code)
+            .map(|(token, _currency_code)| token.clone())
+            .collect(),
+    };
+
+    let stdout = io::stdout();
+    let mut w = stdout.lock();
+    if args.format == args::OutputFormat::Csv {
+        writeln!(w, "owner,token,amount").unwrap();
+    }
+    let mut failures: Vec<(String, String)> = vec![];
+    for line in io::BufRead::lines(io::BufReader::new(file)) {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                failures.push((
+                    args.addresses_file.to_string_lossy().into_owned(),
+                    format!("Cannot read the addresses file: {}", err),
+                ));
+                break;
+            }
+        };
+        let raw_owner = line.trim();
+        if raw_owner.is_empty() {
+            continue;
+        }
+        let owner = match Address::decode(raw_owner) {
+            Ok(owner) => owner,
+            Err(_) => match ctx.wallet.find_address(raw_owner) {
+                Some(owner) => owner.clone(),
+                None => {
+                    failures.push((
+                        raw_owner.to_owned(),
+                        "Unknown address or alias".to_owned(),
+                    ));
+                    continue;
+                }
+            },
+        };
+        let mut balances =
+            match query_owner_balances(&client, &owner, &tokens).await {
+                Ok(balances) => balances,
+                Err(err) => {
+                    failures.push((raw_owner.to_owned(), err));
+                    continue;
+                }
+            };
+        // With a single token, every owner gets a row, even without a balance
+        if args.token.is_some() && balances.is_empty() {
+            balances.push((tokens[0].clone(), token::Amount::default()));
+        }
+        for (token, balance) in balances {
+            match args.format {
+                args::OutputFormat::Csv => writeln!(
+                    w,
+                    "{},{},{}",
+                    owner.encode(),
+                    token.encode(),
+                    balance
+                ),
+                args::OutputFormat::Text => {
+                    let currency_code = known_tokens
+                        .get(&token)
+                        .map(|c| Cow::Borrowed(*c))
+                        .unwrap_or_else(|| Cow::Owned(token.to_string()));
+                    writeln!(w, "{} {}: {}", owner, currency_code, balance)
+                }
+            }
+            .unwrap();
+        }
+    }
+    drop(w);
+
+    if !failures.is_empty() {
+        let mut message = format!(
+            "Failed to query the balances of {} addresses:",
+            failures.len()
+        );
+        for (owner, err) in failures {
+            message.push_str(&format!("\n  {}: {}", owner, err));
+        }
+        output::fail(message)
+    }
 }
 
 /// Query Proposals
-pub async fn query_proposal(_ctx: Context, args: args::QueryProposal) {
+pub async fn query_proposal(_ctx: &Context, args: args::QueryProposal) {
     async fn print_proposal(
         client: &HttpClient,
         id: u64,
@@ -239,7 +823,7 @@ pub async fn query_proposal(_ctx: Context, args: args::QueryProposal) {
         Some(())
     }
 
-    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let client = ledger_client(args.query.ledger_address);
     match args.proposal_id {
         Some(id) => {
             if print_proposal(&client, id, true).await.is_none() {
@@ -262,6 +846,67 @@ pub async fn query_proposal(_ctx: Context, args: args::QueryProposal) {
     }
 }
 
+/// Query the wasm code of a proposal and print its hash and size. The code is
+/// written to the output file, if any, so that it can be reviewed before
+/// voting.
+pub async fn query_proposal_code(args: args::QueryProposalCode) {
+    let client = ledger_client(args.query.ledger_address);
+    let id = args.proposal_id;
+    let author_key = gov_storage::get_author_key(id);
+    if query_storage_value::<Address>(&client, &author_key)
+        .await
+        .is_none()
+    {
+        output::fail(format!("No valid proposal was found with id {}", id))
+    }
+    let code_key = gov_storage::get_proposal_code_key(id);
+    let code = match query_storage_value::<Vec<u8>>(&client, &code_key).await {
+        Some(code) => code,
+        None => {
+            if output::is_json() {
+                output::print_ok(json!({
+                    "proposal_id": id,
+                    "code": null,
+                }));
+            } else {
+                println!("Proposal {} has no code.", id);
+            }
+            return;
+        }
+    };
+    if let Some(out) = args.out.as_ref() {
+        if let Err(err) = std::fs::write(out, &code) {
+            output::fail(format!(
+                "Failed to write the code of the proposal {} to {}: {}",
+                id,
+                out.to_string_lossy(),
+                err
+            ))
+        }
+    }
+    let hash = Hash::sha256(&code);
+    if output::is_json() {
+        output::print_ok(json!({
+            "proposal_id": id,
+            "code": {
+                "hash": hash.to_string(),
+                "size": code.len(),
+                "out": args.out.map(|out| out.to_string_lossy().into_owned()),
+            },
+        }));
+        return;
+    }
+    println!(
+        "Proposal {} code hash: {}, size: {} bytes",
+        id,
+        hash,
+        code.len()
+    );
+    if let Some(out) = args.out {
+        println!("The code has been written to {}.", out.to_string_lossy());
+    }
+}
+
 /// Query token amount of owner.
 pub async fn get_token_balance(
     client: &HttpClient,
@@ -274,8 +919,12 @@ pub async fn get_token_balance(
 
 /// Query PoS bond(s)
 pub async fn query_bonds(ctx: Context, args: args::QueryBonds) {
-    let epoch = query_epoch(args.query.clone()).await;
-    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let client = ledger_client(args.query.ledger_address.clone());
+    let epoch = match args.epoch {
+        Some(epoch) => epoch,
+        None => query_epoch(args.query.clone()).await,
+    };
+    let height = query_height_for_epoch(&client, args.epoch).await;
     match (args.owner, args.validator) {
         (Some(owner), Some(validator)) => {
             let source = ctx.get(&owner);
@@ -283,19 +932,28 @@ pub async fn query_bonds(ctx: Context, args: args::QueryBonds) {
             // Find owner's delegations to the given validator
             let bond_id = pos::BondId { source, validator };
             let bond_key = pos::bond_key(&bond_id);
-            let bonds =
-                query_storage_value::<pos::Bonds>(&client, &bond_key).await;
+            let bonds = query_storage_value_at::<pos::Bonds>(
+                &client, &bond_key, height,
+            )
+            .await;
             // Find owner's unbonded delegations from the given
             // validator
             let unbond_key = pos::unbond_key(&bond_id);
-            let unbonds =
-                query_storage_value::<pos::Unbonds>(&client, &unbond_key).await;
+            let unbonds = query_storage_value_at::<pos::Unbonds>(
+                &client,
+                &unbond_key,
+                height,
+            )
+            .await;
             // Find validator's slashes, if any
             let slashes_key = pos::validator_slashes_key(&bond_id.validator);
-            let slashes =
-                query_storage_value::<pos::Slashes>(&client, &slashes_key)
-                    .await
-                    .unwrap_or_default();
+            let slashes = query_storage_value_at::<pos::Slashes>(
+                &client,
+                &slashes_key,
+                height,
+            )
+            .await
+            .unwrap_or_default();
 
             let stdout = io::stdout();
             let mut w = stdout.lock();
@@ -342,18 +1000,27 @@ pub async fn query_bonds(ctx: Context, args: args::QueryBonds) {
                 validator,
             };
             let bond_key = pos::bond_key(&bond_id);
-            let bonds =
-                query_storage_value::<pos::Bonds>(&client, &bond_key).await;
+            let bonds = query_storage_value_at::<pos::Bonds>(
+                &client, &bond_key, height,
+            )
+            .await;
             // Find validator's unbonded self-bonds
             let unbond_key = pos::unbond_key(&bond_id);
-            let unbonds =
-                query_storage_value::<pos::Unbonds>(&client, &unbond_key).await;
+            let unbonds = query_storage_value_at::<pos::Unbonds>(
+                &client,
+                &unbond_key,
+                height,
+            )
+            .await;
             // Find validator's slashes, if any
             let slashes_key = pos::validator_slashes_key(&bond_id.validator);
-            let slashes =
-                query_storage_value::<pos::Slashes>(&client, &slashes_key)
-                    .await
-                    .unwrap_or_default();
+            let slashes = query_storage_value_at::<pos::Slashes>(
+                &client,
+                &slashes_key,
+                height,
+            )
+            .await
+            .unwrap_or_default();
 
             let stdout = io::stdout();
             let mut w = stdout.lock();
@@ -385,16 +1052,18 @@ pub async fn query_bonds(ctx: Context, args: args::QueryBonds) {
             let owner = ctx.get(&owner);
             // Find owner's bonds to any validator
             let bonds_prefix = pos::bonds_for_source_prefix(&owner);
-            let bonds = query_storage_prefix::<pos::Bonds>(
+            let bonds = query_storage_prefix_at::<pos::Bonds>(
                 client.clone(),
                 bonds_prefix,
+                height,
             )
             .await;
             // Find owner's unbonds to any validator
             let unbonds_prefix = pos::unbonds_for_source_prefix(&owner);
-            let unbonds = query_storage_prefix::<pos::Unbonds>(
+            let unbonds = query_storage_prefix_at::<pos::Unbonds>(
                 client.clone(),
                 unbonds_prefix,
+                height,
             )
             .await;
 
@@ -408,12 +1077,14 @@ pub async fn query_bonds(ctx: Context, args: args::QueryBonds) {
                             // Find validator's slashes, if any
                             let slashes_key =
                                 pos::validator_slashes_key(&validator);
-                            let slashes = query_storage_value::<pos::Slashes>(
-                                &client,
-                                &slashes_key,
-                            )
-                            .await
-                            .unwrap_or_default();
+                            let slashes =
+                                query_storage_value_at::<pos::Slashes>(
+                                    &client,
+                                    &slashes_key,
+                                    height,
+                                )
+                                .await
+                                .unwrap_or_default();
 
                             let stdout = io::stdout();
                             let mut w = stdout.lock();
@@ -459,12 +1130,14 @@ pub async fn query_bonds(ctx: Context, args: args::QueryBonds) {
                             // Find validator's slashes, if any
                             let slashes_key =
                                 pos::validator_slashes_key(&validator);
-                            let slashes = query_storage_value::<pos::Slashes>(
-                                &client,
-                                &slashes_key,
-                            )
-                            .await
-                            .unwrap_or_default();
+                            let slashes =
+                                query_storage_value_at::<pos::Slashes>(
+                                    &client,
+                                    &slashes_key,
+                                    height,
+                                )
+                                .await
+                                .unwrap_or_default();
 
                             let stdout = io::stdout();
                             let mut w = stdout.lock();
@@ -505,16 +1178,18 @@ pub async fn query_bonds(ctx: Context, args: args::QueryBonds) {
         (None, None) => {
             // Find all the bonds
             let bonds_prefix = pos::bonds_prefix();
-            let bonds = query_storage_prefix::<pos::Bonds>(
+            let bonds = query_storage_prefix_at::<pos::Bonds>(
                 client.clone(),
                 bonds_prefix,
+                height,
             )
             .await;
             // Find all the unbonds
             let unbonds_prefix = pos::unbonds_prefix();
-            let unbonds = query_storage_prefix::<pos::Unbonds>(
+            let unbonds = query_storage_prefix_at::<pos::Unbonds>(
                 client.clone(),
                 unbonds_prefix,
+                height,
             )
             .await;
 
@@ -527,12 +1202,14 @@ pub async fn query_bonds(ctx: Context, args: args::QueryBonds) {
                             // Find validator's slashes, if any
                             let slashes_key =
                                 pos::validator_slashes_key(&validator);
-                            let slashes = query_storage_value::<pos::Slashes>(
-                                &client,
-                                &slashes_key,
-                            )
-                            .await
-                            .unwrap_or_default();
+                            let slashes =
+                                query_storage_value_at::<pos::Slashes>(
+                                    &client,
+                                    &slashes_key,
+                                    height,
+                                )
+                                .await
+                                .unwrap_or_default();
 
                             let stdout = io::stdout();
                             let mut w = stdout.lock();
@@ -578,12 +1255,14 @@ pub async fn query_bonds(ctx: Context, args: args::QueryBonds) {
                             // Find validator's slashes, if any
                             let slashes_key =
                                 pos::validator_slashes_key(&validator);
-                            let slashes = query_storage_value::<pos::Slashes>(
-                                &client,
-                                &slashes_key,
-                            )
-                            .await
-                            .unwrap_or_default();
+                            let slashes =
+                                query_storage_value_at::<pos::Slashes>(
+                                    &client,
+                                    &slashes_key,
+                                    height,
+                                )
+                                .await
+                                .unwrap_or_default();
 
                             let stdout = io::stdout();
                             let mut w = stdout.lock();
@@ -629,18 +1308,61 @@ pub async fn query_bonds(ctx: Context, args: args::QueryBonds) {
 
 /// Query PoS voting power
 pub async fn query_voting_power(ctx: Context, args: args::QueryVotingPower) {
-    let epoch = match args.epoch {
-        Some(epoch) => epoch,
-        None => query_epoch(args.query.clone()).await,
+    let client = ledger_client(args.query.ledger_address.clone());
+    let current_epoch = query_epoch(args.query.clone()).await;
+    if let (Some(validator), Some(from_epoch)) =
+        (args.validator.as_ref(), args.from_epoch)
+    {
+        let validator = ctx.get(validator);
+        let to_epoch = args.to_epoch.unwrap_or(current_epoch);
+        let history = query_voting_power_history(
+            &client,
+            &validator,
+            from_epoch,
+            to_epoch,
+            current_epoch,
+        )
+        .await;
+        if output::is_json() {
+            let history: Vec<_> = history
+                .iter()
+                .map(|(epoch, voting_power)| {
+                    json!({
+                        "epoch": epoch.0,
+                        "voting_power": u64::from(*voting_power),
+                    })
+                })
+                .collect();
+            output::print_ok(json!({
+                "validator": validator.encode(),
+                "history": history,
+            }));
+            return;
+        }
+        println!("Voting power of validator {}:", validator.encode());
+        for (epoch, voting_power) in history {
+            println!("  Epoch {}: {}", epoch, voting_power);
+        }
+        return;
+    }
+    let epoch = args.epoch.unwrap_or(current_epoch);
+    // The state of a past epoch is read as it was at its start, the future
+    // epochs up to the pipeline length are found in the latest state
+    let height = if epoch < current_epoch {
+        Some(query_epoch_start_height(&client, epoch).await)
+    } else {
+        None
     };
-    let client = HttpClient::new(args.query.ledger_address).unwrap();
 
     // Find the validator set
     let validator_set_key = pos::validator_set_key();
-    let validator_sets =
-        query_storage_value::<pos::ValidatorSets>(&client, &validator_set_key)
-            .await
-            .expect("Validator set should always be set");
+    let validator_sets = query_storage_value_at::<pos::ValidatorSets>(
+        &client,
+        &validator_set_key,
+        height,
+    )
+    .await
+    .expect("Validator set should always be set");
     let validator_set = validator_sets
         .get(epoch)
         .expect("Validator set should be always set in the current epoch");
@@ -649,12 +1371,12 @@ pub async fn query_voting_power(ctx: Context, args: args::QueryVotingPower) {
             let validator = ctx.get(&validator);
             // Find voting power for the given validator
             let voting_power_key = pos::validator_voting_power_key(&validator);
-            let voting_powers =
-                query_storage_value::<pos::ValidatorVotingPowers>(
-                    &client,
-                    &voting_power_key,
-                )
-                .await;
+            let voting_powers = query_storage_value_at::<
+                pos::ValidatorVotingPowers,
+            >(
+                &client, &voting_power_key, height
+            )
+            .await;
             match voting_powers.and_then(|data| data.get(epoch)) {
                 Some(voting_power_delta) => {
                     let voting_power: VotingPower =
@@ -713,9 +1435,10 @@ pub async fn query_voting_power(ctx: Context, args: args::QueryVotingPower) {
         }
     }
     let total_voting_power_key = pos::total_voting_power_key();
-    let total_voting_powers = query_storage_value::<pos::TotalVotingPowers>(
+    let total_voting_powers = query_storage_value_at::<pos::TotalVotingPowers>(
         &client,
         &total_voting_power_key,
+        height,
     )
     .await
     .expect("Total voting power should always be set");
@@ -725,9 +1448,115 @@ pub async fn query_voting_power(ctx: Context, args: args::QueryVotingPower) {
     println!("Total voting power: {}", total_voting_power);
 }
 
+/// Query the voting power of a validator in every epoch of the given inclusive
+/// range. As with a single epoch, the voting power in a past epoch is read from
+/// the state at the start of that epoch and the current and future epochs are
+/// found in the latest state. Fails if the state of any of the past epochs
+/// has been pruned. An epoch in which the validator has no voting power yet
+/// is reported with zero.
+pub async fn query_voting_power_history(
+    client: &HttpClient,
+    validator: &Address,
+    from_epoch: Epoch,
+    to_epoch: Epoch,
+    current_epoch: Epoch,
+) -> Vec<(Epoch, VotingPower)> {
+    if from_epoch > to_epoch {
+        output::fail(format!(
+            "The epoch {} is after the epoch {}, there are no epochs to query.",
+            from_epoch, to_epoch
+        ));
+    }
+    let voting_power_key = pos::validator_voting_power_key(validator);
+    let mut history = vec![];
+    for epoch in from_epoch.0..=to_epoch.0 {
+        let epoch = Epoch(epoch);
+        let height = if epoch < current_epoch {
+            Some(read_epoch_start_height(client, epoch).await)
+        } else {
+            None
+        };
+        let voting_powers =
+            query_storage_value_at::<pos::ValidatorVotingPowers>(
+                client,
+                &voting_power_key,
+                height,
+            )
+            .await;
+        let voting_power = match voting_powers.and_then(|data| data.get(epoch))
+        {
+            Some(voting_power_delta) => voting_power_delta
+                .try_into()
+                .expect("The sum voting power deltas shouldn't be negative"),
+            None => VotingPower::default(),
+        };
+        history.push((epoch, voting_power));
+    }
+    history
+}
+
+/// Query the consensus validator set of an epoch, i.e. the validators given
+/// to Tendermint, which leaves out the inactive and the jailed validators
+pub async fn query_validator_set(args: args::QueryValidatorSet) {
+    let client = ledger_client(args.query.ledger_address);
+    let path = Path::ValidatorSet(args.epoch);
+    let data = vec![];
+    let response = client
+        .abci_query(Some(path.into()), data, None, false)
+        .await
+        .unwrap();
+    if response.code != Code::Ok {
+        output::fail(format!(
+            "Error querying the validator set: {}",
+            response.info
+        ))
+    }
+    let validator_set =
+        ConsensusValidatorSet::try_from_slice(&response.value[..])
+            .unwrap_or_else(|err| {
+                output::fail(format!(
+                    "Error decoding the validator set: {}",
+                    err
+                ))
+            });
+    let total_voting_power = validator_set.total_voting_power();
+    if output::is_json() {
+        let validators: Vec<_> = validator_set
+            .validators
+            .iter()
+            .map(|validator| {
+                json!({
+                    "address": validator.address.encode(),
+                    "voting_power": validator.voting_power,
+                    "consensus_key": validator.consensus_key.to_string(),
+                })
+            })
+            .collect();
+        output::print_ok(json!({
+            "epoch": validator_set.epoch.0,
+            "validators": validators,
+            "total_voting_power": total_voting_power,
+        }));
+        return;
+    }
+    println!(
+        "Consensus validator set of the epoch {}:",
+        validator_set.epoch
+    );
+    for validator in &validator_set.validators {
+        println!(
+            "  {}: voting power {}, consensus key {}",
+            validator.address.encode(),
+            validator.voting_power,
+            validator.consensus_key
+        );
+    }
+    println!("Total voting power: {}", total_voting_power);
+}
+
 /// Query PoS slashes
 pub async fn query_slashes(ctx: Context, args: args::QuerySlashes) {
-    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let client = ledger_client(args.query.ledger_address);
     match args.validator {
         Some(validator) => {
             let validator = ctx.get(&validator);
@@ -797,25 +1626,656 @@ pub async fn query_slashes(ctx: Context, args: args::QuerySlashes) {
     }
 }
 
-/// Dry run a transaction
-pub async fn dry_run_tx(ledger_address: &TendermintAddress, tx_bytes: Vec<u8>) {
-    let client = HttpClient::new(ledger_address.clone()).unwrap();
-    let path = Path::DryRunTx;
-    let response = client
-        .abci_query(Some(path.into()), tx_bytes, None, false)
-        .await
-        .unwrap();
-    println!("{:#?}", response);
+/// Query PoS validator's commission rate
+pub async fn query_commission_rate(
+    ctx: Context,
+    args: args::QueryCommissionRate,
+) {
+    let epoch = query_epoch(args.query.clone()).await;
+    let client = ledger_client(args.query.ledger_address);
+    let validator = ctx.get(&args.validator);
+    let commission_rate_key = pos::validator_commission_rate_key(&validator);
+    let commission_rates = query_storage_value::<pos::CommissionRates>(
+        &client,
+        &commission_rate_key,
+    )
+    .await;
+    match commission_rates {
+        Some(commission_rates) => {
+            let rate = commission_rates.get(epoch);
+            match rate {
+                Some(rate) => println!(
+                    "Validator {} commission rate: {}",
+                    validator.encode(),
+                    rate
+                ),
+                None => println!(
+                    "Validator {} has no commission rate in epoch {}",
+                    validator.encode(),
+                    epoch
+                ),
+            }
+            // Show a pending change of the rate, if any
+            let params: PosParams =
+                query_parameter(&client, &pos::params_key(), "PoS parameters")
+                    .await;
+            let pipeline_epoch = PosEpoch::from(epoch) + params.pipeline_len;
+            match commission_rates.get(pipeline_epoch) {
+                Some(pending_rate) if Some(pending_rate) != rate => {
+                    println!(
+                        "{:4}From epoch {}: {}",
+                        "", pipeline_epoch, pending_rate
+                    )
+                }
+                _ => {}
+            }
+        }
+        None => {
+            println!("No commission rate found for {}", validator.encode())
+        }
+    }
 }
 
-/// Get account's public key stored in its storage sub-space
-pub async fn get_public_key(
-    address: &Address,
-    ledger_address: TendermintAddress,
-) -> Option<common::PublicKey> {
-    let client = HttpClient::new(ledger_address).unwrap();
-    let key = pk_key(address);
-    query_storage_value(&client, &key).await
+/// Query PoS validator's metadata
+pub async fn query_validator(ctx: Context, args: args::QueryValidator) {
+    let validator = ctx.get(&args.validator);
+    if !is_validator(&validator, args.query.ledger_address.clone()).await {
+        output::fail(format!(
+            "The address {} doesn't belong to any known validator account.",
+            validator.encode()
+        ))
+    }
+    let client = ledger_client(args.query.ledger_address);
+    let metadata_key = pos::validator_metadata_key(&validator);
+    let ValidatorMetadata {
+        moniker,
+        website,
+        security_contact,
+        description,
+    } = query_storage_value::<ValidatorMetadata>(&client, &metadata_key)
+        .await
+        .unwrap_or_default();
+    let not_set = || "not set".to_owned();
+    println!("Validator {}", validator.encode());
+    println!("{:4}Moniker: {}", "", moniker.unwrap_or_else(not_set));
+    println!("{:4}Website: {}", "", website.unwrap_or_else(not_set));
+    println!(
+        "{:4}Security contact: {}",
+        "",
+        security_contact.unwrap_or_else(not_set)
+    );
+    println!(
+        "{:4}Description: {}",
+        "",
+        description.unwrap_or_else(not_set)
+    );
+}
+
+/// Query PoS unclaimed rewards, optionally only the rewards earned since the
+/// given epoch
+pub async fn query_rewards(ctx: Context, args: args::QueryRewards) {
+    let client = ledger_client(args.query.ledger_address);
+    let since_epoch: PosEpoch =
+        args.since_epoch.map(Into::into).unwrap_or_default();
+    let owner = ctx.get_opt(&args.owner);
+    let rewards: Vec<(pos::BondId, pos::Rewards)> = match args.validator {
+        Some(validator) => {
+            let validator = ctx.get(&validator);
+            // Find the rewards of the owner's delegation to the given
+            // validator or of the validator's self-bond
+            let bond_id = pos::BondId {
+                source: owner.unwrap_or_else(|| validator.clone()),
+                validator,
+            };
+            let rewards_key = pos::rewards_key(&bond_id);
+            query_storage_value::<pos::Rewards>(&client, &rewards_key)
+                .await
+                .map(|rewards| vec![(bond_id, rewards)])
+                .unwrap_or_default()
+        }
+        None => {
+            // Find the owner's rewards from any validator or all the rewards
+            let rewards_prefix = match &owner {
+                Some(owner) => pos::rewards_for_source_prefix(owner),
+                None => pos::rewards_prefix(),
+            };
+            query_storage_prefix::<pos::Rewards>(client.clone(), rewards_prefix)
+                .await
+                .map(|rewards| {
+                    rewards
+                        .filter_map(|(key, rewards)| {
+                            Some((pos::is_rewards_key(&key)?, rewards))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+    };
+
+    let stdout = io::stdout();
+    let mut w = stdout.lock();
+    let mut total: token::Amount = 0.into();
+    for (bond_id, rewards) in rewards {
+        let rewards: Vec<_> = rewards.range(since_epoch..).collect();
+        if rewards.is_empty() {
+            continue;
+        }
+        let bond_type = if bond_id.source == bond_id.validator {
+            format!("Self-bond rewards for {}", bond_id.validator.encode())
+        } else {
+            format!(
+                "Delegation rewards from {} to validator {}",
+                bond_id.source,
+                bond_id.validator.encode()
+            )
+        };
+        writeln!(w, "{}:", bond_type).unwrap();
+        for (epoch, amount) in rewards {
+            writeln!(w, "  Earned in epoch {}: {}", epoch, amount).unwrap();
+            total += *amount;
+        }
+    }
+    if total == 0.into() {
+        writeln!(w, "No unclaimed rewards found").unwrap();
+    } else {
+        writeln!(w, "Unclaimed rewards total: {}", total).unwrap();
+    }
+}
+
+/// Dry run a transaction, against the state committed at the given `height`
+/// or the latest state. Print whether the mempool would reject it and with
+/// `explain`, also print the addresses whose validity predicates were invoked
+/// by the transaction and their verdicts. With `assert_max_gas`, exit with an
+/// error if the transaction uses more gas or if it couldn't be applied.
+pub async fn dry_run_tx(
+    ledger_address: &TendermintAddress,
+    tx_bytes: Vec<u8>,
+    height: Option<BlockHeight>,
+    explain: bool,
+    assert_max_gas: Option<u64>,
+) {
+    let client = ledger_client(ledger_address.clone());
+    let path = Path::DryRunTx;
+    let response = client
+        .abci_query(Some(path.into()), tx_bytes, query_height(height), false)
+        .await
+        .unwrap();
+    println!("{:#?}", response);
+    let tx_result = if response.code != Code::Ok {
+        None
+    } else {
+        match DryRunResult::try_from_slice(&response.value[..]) {
+            Ok(result) => {
+                if let Some(error) = &result.mempool_error {
+                    println!(
+                        "The transaction would be rejected by the mempool: {}",
+                        error
+                    );
+                }
+                result.tx_result
+            }
+            Err(err) => {
+                eprintln!("Error decoding the dry run result: {}", err);
+                None
+            }
+        }
+    };
+    match &tx_result {
+        Some(result) if explain => {
+            println!("Invoked validity predicates:");
+            for (addr, accepted) in result.vps_result.verdicts() {
+                println!(
+                    "{:4}{}: {}",
+                    "",
+                    addr.encode(),
+                    if accepted { "accepted" } else { "rejected" }
+                );
+                for (_, error) in result
+                    .vps_result
+                    .errors
+                    .iter()
+                    .filter(|(err_addr, _)| err_addr == addr)
+                {
+                    println!("{:8}Error: {}", "", error);
+                }
+            }
+        }
+        _ => {}
+    }
+    if let Some(max_gas) = assert_max_gas {
+        match tx_result {
+            Some(result) if result.gas_used <= max_gas => {
+                println!(
+                    "Gas used {} is within the maximum of {}.",
+                    result.gas_used, max_gas
+                );
+            }
+            Some(result) => output::fail(format!(
+                "Gas used {} exceeds the maximum of {}.",
+                result.gas_used, max_gas
+            )),
+            None => output::fail(format!(
+                "The transaction couldn't be applied, so its gas use can't be \
+                 checked against the maximum of {}.",
+                max_gas
+            )),
+        }
+    }
+}
+
+/// Dry run a transaction and decode its structured result. Returns the
+/// ledger's error log if the transaction couldn't be applied.
+pub async fn query_dry_run_result(
+    ledger_address: &TendermintAddress,
+    tx_bytes: Vec<u8>,
+) -> Result<DryRunResult, String> {
+    let client = ledger_client(ledger_address.clone());
+    let path = Path::DryRunTx;
+    let response = client
+        .abci_query(Some(path.into()), tx_bytes, None, false)
+        .await
+        .unwrap();
+    if response.code != Code::Ok {
+        return Err(response.log.to_string());
+    }
+    DryRunResult::try_from_slice(&response.value[..])
+        .map_err(|err| format!("Error decoding the dry run result: {}", err))
+}
+
+/// Simulate applying the signed transactions listed in the given file in a
+/// block on top of the current state and print their results.
+pub async fn simulate_block(args: args::SimulateBlock) {
+    let file = std::fs::read_to_string(&args.txs_file).unwrap_or_else(|err| {
+        output::fail(format!(
+            "Cannot read the transactions file {}: {}",
+            args.txs_file.to_string_lossy(),
+            err
+        ))
+    });
+    let tx_paths: Vec<&str> = file
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    let txs: Vec<Vec<u8>> = tx_paths
+        .iter()
+        .map(|path| {
+            std::fs::read(path).unwrap_or_else(|err| {
+                output::fail(format!(
+                    "Failed to read the transaction from {}: {}",
+                    path, err
+                ))
+            })
+        })
+        .collect();
+
+    let client = ledger_client(args.query.ledger_address);
+    let path = Path::SimulateBlock;
+    let data = txs
+        .try_to_vec()
+        .expect("Encoding the transactions shouldn't fail");
+    let response = client
+        .abci_query(Some(path.into()), data, None, false)
+        .await
+        .unwrap();
+    if response.code != Code::Ok {
+        output::fail(format!("Error simulating the block: {}", response.info))
+    }
+    let result = BlockSimResult::try_from_slice(&response.value[..])
+        .unwrap_or_else(|err| {
+            output::fail(format!(
+                "Error decoding the simulation result: {}",
+                err
+            ))
+        });
+
+    let stdout = io::stdout();
+    let mut w = stdout.lock();
+    for (path, tx_result) in tx_paths.iter().zip(result.txs.iter()) {
+        match tx_result {
+            SimulatedTx::Applied(tx_result) => writeln!(
+                w,
+                "{}: {}, gas used: {}",
+                path,
+                if tx_result.is_accepted() {
+                    "accepted"
+                } else {
+                    "rejected"
+                },
+                tx_result.gas_used
+            )
+            .unwrap(),
+            SimulatedTx::Failed(err) => {
+                writeln!(w, "{}: failed: {}", path, err).unwrap()
+            }
+        }
+    }
+    writeln!(w, "Total gas used: {}", result.gas_used).unwrap();
+    writeln!(w, "Merkle root: {}", hex::encode_upper(&result.root)).unwrap();
+}
+
+/// Query the events emitted in a committed block and print them.
+pub async fn query_events(args: args::QueryEvents) {
+    let client = ledger_client(args.query.ledger_address);
+    let path = Path::BlockEvents {
+        height: args.height,
+        event_type: args.event_type,
+    };
+    let data = vec![];
+    let response = client
+        .abci_query(Some(path.into()), data, None, false)
+        .await
+        .unwrap();
+    if response.code != Code::Ok {
+        output::fail(format!(
+            "Error querying the block events: {}",
+            response.info
+        ))
+    }
+    let result = BlockEvents::try_from_slice(&response.value[..])
+        .unwrap_or_else(|err| {
+            output::fail(format!("Error decoding the block events: {}", err))
+        });
+
+    let stdout = io::stdout();
+    let mut w = stdout.lock();
+    if result.events.is_empty() {
+        writeln!(w, "No events found in the block {}.", args.height).unwrap();
+        return;
+    }
+    for event in &result.events {
+        writeln!(w, "{}:", event.event_type).unwrap();
+        for (key, value) in &event.attributes {
+            writeln!(w, "  {}: {}", key, value).unwrap();
+        }
+    }
+    if result.truncated {
+        writeln!(
+            w,
+            "Only the first {} events are shown, narrow the query with --type \
+             to see the rest.",
+            MAX_BLOCK_EVENTS
+        )
+        .unwrap();
+    }
+}
+
+/// Query the status of a node and print it.
+pub async fn query_node_status(args: args::Query) {
+    let client = ledger_client(args.ledger_address);
+    let path = Path::NodeStatus;
+    let data = vec![];
+    let response = client
+        .abci_query(Some(path.into()), data, None, false)
+        .await
+        .unwrap();
+    if response.code != Code::Ok {
+        output::fail(format!(
+            "Error querying the node status: {}",
+            response.info
+        ))
+    }
+    let status = NodeStatus::try_from_slice(&response.value[..])
+        .unwrap_or_else(|err| {
+            output::fail(format!("Error decoding the node status: {}", err))
+        });
+    if output::is_json() {
+        output::print_ok(json!({
+            "genesis_time": status
+                .genesis_time
+                .map(|genesis_time| genesis_time.0.to_rfc3339()),
+            "genesis_time_reached": status.genesis_time_reached,
+            "last_height": status.last_height.0,
+            "protocol_version": status.protocol_version,
+            "supported_protocol_version": status.supported_protocol_version,
+            "protocol_upgrade": status.protocol_upgrade.map(|upgrade| json!({
+                "version": upgrade.version,
+                "height": upgrade.height.0,
+            })),
+        }));
+        return;
+    }
+    match status.genesis_time {
+        Some(genesis_time) if status.genesis_time_reached => {
+            println!("Genesis time: {}", genesis_time.0.to_rfc3339())
+        }
+        Some(genesis_time) => println!(
+            "Genesis time: {} (not reached yet, blocks are not processed \
+             before it)",
+            genesis_time.0.to_rfc3339()
+        ),
+        None => println!("Genesis time: unknown"),
+    }
+    println!("Last committed block height: {}", status.last_height);
+    println!(
+        "Protocol version: {} (the node supports the version {})",
+        status.protocol_version, status.supported_protocol_version
+    );
+    if let Some(upgrade) = status.protocol_upgrade {
+        println!("Scheduled protocol upgrade: {}", upgrade);
+        if upgrade.version > status.supported_protocol_version {
+            println!(
+                "The node must be upgraded before the block height {} to keep \
+                 processing the blocks.",
+                upgrade.height
+            );
+        }
+    }
+}
+
+/// How long to wait before reconnecting to the ledger node in
+/// [`tail_blocks`]
+const TAIL_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Print a line for every block committed by the ledger node, until the
+/// process is stopped. Without a starting height, only the blocks committed
+/// from now on are printed. Every new block also catches up with any blocks
+/// committed since the last printed one, so that none are skipped when the
+/// connection to the node is lost and then re-opened.
+pub async fn tail_blocks(
+    ledger_address: TendermintAddress,
+    from_height: Option<BlockHeight>,
+) {
+    // No block is committed at the height 0
+    let mut next_height =
+        from_height.map(|height| BlockHeight(height.0.max(1)));
+    loop {
+        if let Err(err) =
+            tail_blocks_aux(&ledger_address, &mut next_height).await
+        {
+            eprintln!(
+                "Lost the connection to the ledger node at {}: {}. \
+                 Reconnecting...",
+                ledger_address, err
+            );
+            tokio::time::sleep(TAIL_RECONNECT_DELAY).await;
+        }
+    }
+}
+
+/// Print the blocks committed by the ledger node over a new connection, until
+/// it fails. The height of the next block to print is updated as the blocks
+/// get printed.
+async fn tail_blocks_aux(
+    ledger_address: &TendermintAddress,
+    next_height: &mut Option<BlockHeight>,
+) -> Result<(), String> {
+    let (client, driver) = WebSocketClient::new(ledger_address.clone())
+        .await
+        .map_err(|err| err.to_string())?;
+    let driver_handle = tokio::spawn(async move { driver.run().await });
+    let result = print_new_blocks(&client, next_height).await;
+    // Signal to the driver to terminate and await its termination to ensure
+    // proper connection closure
+    let _ = client.close();
+    let _ = driver_handle.await;
+    result
+}
+
+/// Print the blocks from the next height up to the last committed block,
+/// again on every new block, until the subscription to the new blocks fails.
+async fn print_new_blocks(
+    client: &WebSocketClient,
+    next_height: &mut Option<BlockHeight>,
+) -> Result<(), String> {
+    let mut new_blocks = client
+        .subscribe(Query::from(EventType::NewBlock))
+        .await
+        .map_err(|err| err.to_string())?;
+    loop {
+        let status: NodeStatus = query_path(client, Path::NodeStatus).await?;
+        let height =
+            next_height.get_or_insert_with(|| status.last_height.next_height());
+        while *height <= status.last_height {
+            match query_path::<BlockSummary>(
+                client,
+                Path::BlockSummary(*height),
+            )
+            .await
+            {
+                Ok(summary) => println!(
+                    "Block {}: root {}, {} txs, gas used {}, epoch {}",
+                    summary.height,
+                    hex::encode_upper(&summary.root[..4]),
+                    summary.num_txs,
+                    summary.gas_used,
+                    summary.epoch
+                ),
+                // The summary of a pruned block can't be queried, but the
+                // following blocks are still printed
+                Err(err) => eprintln!("Block {}: {}", height, err),
+            }
+            *height = height.next_height();
+        }
+        match new_blocks.next().await {
+            Some(Ok(_new_block)) => {}
+            Some(Err(err)) => return Err(err.to_string()),
+            None => {
+                return Err(
+                    "The subscription to the new blocks has ended".to_string()
+                );
+            }
+        }
+    }
+}
+
+/// Run the query with the given path on the client and decode its result
+async fn query_path<T: BorshDeserialize>(
+    client: &WebSocketClient,
+    path: Path,
+) -> Result<T, String> {
+    let response = client
+        .abci_query(Some(path.into()), vec![], None, false)
+        .await
+        .map_err(|err| format!("Error in the query: {}", err))?;
+    if response.code != Code::Ok {
+        return Err(response.info);
+    }
+    T::try_from_slice(&response.value[..])
+        .map_err(|err| format!("Error decoding the result: {}", err))
+}
+
+/// Query the contents of the node's mempool and print them.
+pub async fn query_mempool(args: args::QueryMempool) {
+    let client = ledger_client(args.query.ledger_address);
+    let path = Path::Mempool {
+        list_txs: args.list_txs,
+    };
+    let data = vec![];
+    let response = client
+        .abci_query(Some(path.into()), data, None, false)
+        .await
+        .unwrap();
+    if response.code != Code::Ok {
+        output::fail(format!("Error querying the mempool: {}", response.info))
+    }
+    let result = MempoolContents::try_from_slice(&response.value[..])
+        .unwrap_or_else(|err| {
+            output::fail(format!(
+                "Error decoding the mempool contents: {}",
+                err
+            ))
+        });
+
+    let stdout = io::stdout();
+    let mut w = stdout.lock();
+    writeln!(w, "Transactions in the mempool: {}", result.num_txs).unwrap();
+    writeln!(w, "Total gas limit: {}", result.total_gas).unwrap();
+    for tx in &result.txs {
+        write!(w, "{}:", tx.hash).unwrap();
+        match (&tx.fee, tx.gas_limit) {
+            (Some(fee), Some(gas_limit)) => writeln!(
+                w,
+                " fee {} {}, gas limit {}",
+                fee.amount, fee.token, gas_limit
+            )
+            .unwrap(),
+            _ => writeln!(w, " not a wrapper transaction").unwrap(),
+        }
+    }
+    if result.truncated {
+        writeln!(
+            w,
+            "Only the {} transactions with the highest fees are shown.",
+            MAX_MEMPOOL_TXS
+        )
+        .unwrap();
+    }
+}
+
+/// Query the number of keys in storage and the sizes of their values and
+/// print them.
+pub async fn query_storage_size(args: args::QueryStorageSize) {
+    let client = ledger_client(args.query.ledger_address);
+    let path = Path::StorageSize(args.prefix);
+    let data = vec![];
+    let response = client
+        .abci_query(Some(path.into()), data, None, false)
+        .await
+        .unwrap();
+    if response.code != Code::Ok {
+        output::fail(format!(
+            "Error querying the storage size: {}",
+            response.info
+        ))
+    }
+    let result = StorageSize::try_from_slice(&response.value[..])
+        .unwrap_or_else(|err| {
+            output::fail(format!("Error decoding the storage size: {}", err))
+        });
+
+    let stdout = io::stdout();
+    let mut w = stdout.lock();
+    writeln!(
+        w,
+        "Keys: {}, total size of the values: {} bytes",
+        result.num_keys, result.total_bytes
+    )
+    .unwrap();
+    for category in &result.categories {
+        writeln!(
+            w,
+            "{:4}{}: {} keys, {} bytes",
+            "", category.name, category.num_keys, category.bytes
+        )
+        .unwrap();
+    }
+    if !result.largest.is_empty() {
+        writeln!(w, "Largest values:").unwrap();
+    }
+    for value in &result.largest {
+        writeln!(w, "{:4}{}: {} bytes", "", value.key, value.bytes).unwrap();
+    }
+}
+
+/// Get account's public key stored in its storage sub-space
+pub async fn get_public_key(
+    address: &Address,
+    ledger_address: TendermintAddress,
+) -> Option<common::PublicKey> {
+    let client = ledger_client(ledger_address);
+    let key = pk_key(address);
+    query_storage_value(&client, &key).await
 }
 
 /// Check if the given address is a known validator.
@@ -823,7 +2283,7 @@ pub async fn is_validator(
     address: &Address,
     ledger_address: TendermintAddress,
 ) -> bool {
-    let client = HttpClient::new(ledger_address).unwrap();
+    let client = ledger_client(ledger_address);
     // Check if there's any validator state
     let key = pos::validator_state_key(address);
     // We do not need to decode it
@@ -840,7 +2300,7 @@ pub async fn known_address(
     address: &Address,
     ledger_address: TendermintAddress,
 ) -> bool {
-    let client = HttpClient::new(ledger_address).unwrap();
+    let client = ledger_client(ledger_address);
     match address {
         Address::Established(_) => {
             // Established account exists if it has a VP
@@ -987,32 +2447,151 @@ pub async fn query_storage_value<T>(
     client: &HttpClient,
     key: &storage::Key,
 ) -> Option<T>
+where
+    T: BorshDeserialize,
+{
+    query_storage_value_at(client, key, None).await
+}
+
+/// Query a storage value like [`query_storage_value`], as it was at the given
+/// block height, or at the latest height if `None`.
+pub async fn query_storage_value_at<T>(
+    client: &HttpClient,
+    key: &storage::Key,
+    height: Option<BlockHeight>,
+) -> Option<T>
 where
     T: BorshDeserialize,
 {
     let path = Path::Value(key.to_owned());
     let data = vec![];
     let response = client
-        .abci_query(Some(path.into()), data, None, false)
+        .abci_query(Some(path.into()), data, query_height(height), false)
         .await
         .unwrap();
     match response.code {
         Code::Ok => match T::try_from_slice(&response.value[..]) {
             Ok(value) => return Some(value),
-            Err(err) => eprintln!("Error decoding the value: {}", err),
+            Err(err) => {
+                output::fail(format!("Error decoding the value: {}", err))
+            }
         },
         Code::Err(err) => {
             if err == 1 {
                 return None;
             } else {
-                eprintln!(
+                output::fail(format!(
                     "Error in the query {} (error code {})",
                     response.info, err
-                )
+                ))
             }
         }
     }
-    cli::safe_exit(1)
+}
+
+/// Query the raw bytes of a storage value at the last committed block height
+/// together with its merkle proof, and verify the proof against the root of
+/// the merkle tree that the node reports for that block. Exits with an error
+/// if the proof is missing or invalid.
+///
+/// Only the existence of a value is verified. When the key has no value,
+/// `None` is returned without verifying its non-existence.
+pub async fn query_proven_storage_value(
+    client: &HttpClient,
+    key: &storage::Key,
+) -> Option<Vec<u8>> {
+    let info = client.abci_info().await.unwrap_or_else(|err| {
+        output::fail(format!(
+            "Error querying the last committed block: {}",
+            err
+        ))
+    });
+    let root = MerkleRoot(info.last_block_app_hash.to_vec());
+    let path = Path::Value(key.to_owned());
+    let data = vec![];
+    let response = client
+        .abci_query(Some(path.into()), data, Some(info.last_block_height), true)
+        .await
+        .unwrap();
+    match response.code {
+        Code::Ok => {
+            let proof = response.proof.unwrap_or_else(|| {
+                output::fail(format!(
+                    "The node didn't return a proof for key {}",
+                    key
+                ))
+            });
+            if !verify_merkle_proof::<Sha256Hasher>(
+                &root,
+                key,
+                &response.value,
+                &proof,
+            ) {
+                output::fail(format!(
+                    "The proof of the value of key {} is invalid for the \
+                     merkle root {} at height {}",
+                    key,
+                    hex::encode_upper(&root.0),
+                    info.last_block_height
+                ))
+            }
+            Some(response.value)
+        }
+        Code::Err(1) => {
+            eprintln!(
+                "Warning: The absence of a value for key {} is not verified",
+                key
+            );
+            None
+        }
+        Code::Err(err) => output::fail(format!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        )),
+    }
+}
+
+/// Query a storage value like [`query_proven_storage_value`] and decode it
+/// with [`BorshDeserialize`].
+pub async fn query_proven_storage_value_as<T>(
+    client: &HttpClient,
+    key: &storage::Key,
+) -> Option<T>
+where
+    T: BorshDeserialize,
+{
+    let value = query_proven_storage_value(client, key).await?;
+    match T::try_from_slice(&value[..]) {
+        Ok(value) => Some(value),
+        Err(err) => output::fail(format!("Error decoding the value: {}", err)),
+    }
+}
+
+/// Query a storage value like [`query_storage_value`], but return an error
+/// instead of exiting when the query fails.
+pub async fn try_query_storage_value<T>(
+    client: &HttpClient,
+    key: &storage::Key,
+) -> Result<Option<T>, String>
+where
+    T: BorshDeserialize,
+{
+    let path = Path::Value(key.to_owned());
+    let data = vec![];
+    let response = client
+        .abci_query(Some(path.into()), data, None, false)
+        .await
+        .map_err(|err| format!("Error in the query: {}", err))?;
+    match response.code {
+        Code::Ok => T::try_from_slice(&response.value[..])
+            .map(Some)
+            .map_err(|err| format!("Error decoding the value: {}", err)),
+        Code::Err(1) => Ok(None),
+        Code::Err(err) => Err(format!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        )),
+    }
 }
 
 /// Query a range of storage values with a matching prefix and decode them with
@@ -1022,13 +2601,26 @@ pub async fn query_storage_prefix<T>(
     client: HttpClient,
     key: storage::Key,
 ) -> Option<impl Iterator<Item = (storage::Key, T)>>
+where
+    T: BorshDeserialize,
+{
+    query_storage_prefix_at(client, key, None).await
+}
+
+/// Query a range of storage values like [`query_storage_prefix`], as they
+/// were at the given block height, or at the latest height if `None`.
+pub async fn query_storage_prefix_at<T>(
+    client: HttpClient,
+    key: storage::Key,
+    height: Option<BlockHeight>,
+) -> Option<impl Iterator<Item = (storage::Key, T)>>
 where
     T: BorshDeserialize,
 {
     let path = Path::Prefix(key);
     let data = vec![];
     let response = client
-        .abci_query(Some(path.into()), data, None, false)
+        .abci_query(Some(path.into()), data, query_height(height), false)
         .await
         .unwrap();
     match response.code {
@@ -1050,21 +2642,83 @@ where
                     };
                     return Some(values.into_iter().filter_map(decode));
                 }
-                Err(err) => eprintln!("Error decoding the values: {}", err),
+                Err(err) => {
+                    output::fail(format!("Error decoding the values: {}", err))
+                }
             }
         }
         Code::Err(err) => {
             if err == 1 {
                 return None;
             } else {
-                eprintln!(
+                output::fail(format!(
                     "Error in the query {} (error code {})",
                     response.info, err
-                )
+                ))
             }
         }
     }
-    cli::safe_exit(1)
+}
+
+/// Query the height of the first block of the given epoch, to read the
+/// state as it was at the start of the epoch. Exits with an error if the
+/// epoch hasn't started yet or if its state has been pruned.
+pub async fn query_epoch_start_height(
+    client: &HttpClient,
+    epoch: Epoch,
+) -> BlockHeight {
+    let height = read_epoch_start_height(client, epoch).await;
+    println!(
+        "Querying the state at the start of epoch {} (block height {})",
+        epoch, height
+    );
+    height
+}
+
+/// Query the height of the first block of the given epoch without reporting
+/// it. Fails if the epoch hasn't started yet or if its state has been pruned.
+async fn read_epoch_start_height(
+    client: &HttpClient,
+    epoch: Epoch,
+) -> BlockHeight {
+    let path = Path::EpochStartHeight(epoch);
+    let data = vec![];
+    let response = client
+        .abci_query(Some(path.into()), data, None, false)
+        .await
+        .unwrap();
+    match response.code {
+        Code::Ok => match BlockHeight::try_from_slice(&response.value[..]) {
+            Ok(height) => height,
+            Err(err) => output::fail(format!(
+                "Error decoding the block height: {}",
+                err
+            )),
+        },
+        Code::Err(err) => output::fail(format!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        )),
+    }
+}
+
+/// Resolve the state height at which to run a query for the given epoch, if
+/// any.
+async fn query_height_for_epoch(
+    client: &HttpClient,
+    epoch: Option<Epoch>,
+) -> Option<BlockHeight> {
+    match epoch {
+        Some(epoch) => Some(query_epoch_start_height(client, epoch).await),
+        None => None,
+    }
+}
+
+/// Convert a block height to the height argument of an ABCI query.
+fn query_height(height: Option<BlockHeight>) -> Option<Height> {
+    height.map(|height| {
+        Height::try_from(height.0).expect("The block height should be valid")
+    })
 }
 
 /// Query to check if the given storage key exists.
@@ -1081,16 +2735,15 @@ pub async fn query_has_storage_key(
     match response.code {
         Code::Ok => match bool::try_from_slice(&response.value[..]) {
             Ok(value) => return value,
-            Err(err) => eprintln!("Error decoding the value: {}", err),
+            Err(err) => {
+                output::fail(format!("Error decoding the value: {}", err))
+            }
         },
-        Code::Err(err) => {
-            eprintln!(
-                "Error in the query {} (error code {})",
-                response.info, err
-            )
-        }
+        Code::Err(err) => output::fail(format!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        )),
     }
-    cli::safe_exit(1)
 }
 
 /// Represents a query for an event pertaining to the specified transaction
@@ -1204,14 +2857,14 @@ pub async fn query_tx_response(
             event_map["initialized_accounts"],
         )
         .unwrap_or_default(),
+        state_changed: event_map.get("state_changed") == Some(&"true"),
     };
     // Signal to the driver to terminate.
     client.close()?;
     // Await the driver's termination to ensure proper connection closure.
-    let _ = driver_handle.await.unwrap_or_else(|x| {
-        eprintln!("{}", x);
-        cli::safe_exit(1)
-    });
+    let _ = driver_handle
+        .await
+        .unwrap_or_else(|x| output::fail(format!("{}", x)));
     Ok(result)
 }
 
@@ -1246,8 +2899,7 @@ pub async fn query_result(_ctx: Context, args: args::QueryResult) {
                 ),
                 Err(err2) => {
                     // Print the errors that caused the lookups to fail
-                    eprintln!("{}\n{}", err1, err2);
-                    cli::safe_exit(1)
+                    output::fail(format!("{}\n{}", err1, err2))
                 }
             }
         }
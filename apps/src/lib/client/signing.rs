@@ -5,13 +5,14 @@ use std::rc::Rc;
 
 use anoma::types::address::{Address, ImplicitAddress};
 use anoma::types::key::*;
+use serde_json::json;
 #[cfg(not(feature = "ABCI"))]
 use tendermint_config::net::Address as TendermintAddress;
 #[cfg(feature = "ABCI")]
 use tendermint_config_abci::net::Address as TendermintAddress;
 
 use super::rpc;
-use crate::cli;
+use crate::cli::{args, output, Context};
 use crate::wallet::Wallet;
 
 /// Find the public key for the given address and try to load the keypair
@@ -30,38 +31,106 @@ pub async fn find_keypair(
             let public_key = rpc::get_public_key(addr, ledger_address)
                 .await
                 .unwrap_or_else(|| {
-                    eprintln!(
+                    output::fail(format!(
                         "No public key found for the address {}",
                         addr.encode()
-                    );
-                    cli::safe_exit(1);
+                    ));
                 });
             wallet.find_key_by_pk(&public_key).unwrap_or_else(|err| {
-                eprintln!(
+                output::fail(format!(
                     "Unable to load the keypair from the wallet for public \
                      key {}. Failed with: {}",
                     public_key, err
-                );
-                cli::safe_exit(1)
+                ))
             })
         }
         Address::Implicit(ImplicitAddress(pkh)) => {
             wallet.find_key_by_pkh(pkh).unwrap_or_else(|err| {
-                eprintln!(
+                output::fail(format!(
                     "Unable to load the keypair from the wallet for the \
                      implicit address {}. Failed with: {}",
                     addr.encode(),
                     err
-                );
-                cli::safe_exit(1)
+                ))
             })
         }
-        Address::Internal(_) => {
-            eprintln!(
-                "Internal address {} doesn't have any signing keys.",
-                addr
-            );
-            cli::safe_exit(1)
+        Address::Internal(_) => output::fail(format!(
+            "Internal address {} doesn't have any signing keys.",
+            addr
+        )),
+    }
+}
+
+/// Sign an off-chain message with the keypair of the signer from the wallet.
+/// The message is signed in a domain separated from the txs, so the
+/// signature can't be replayed as a tx's signature.
+pub async fn sign_message(mut ctx: Context, args: args::SignMessage) {
+    let signer = ctx.get(&args.signer);
+    let keypair =
+        find_keypair(&mut ctx.wallet, &signer, args.query.ledger_address).await;
+    let signature = common::sign_message(&keypair, &args.message);
+    let public_key = keypair.ref_to();
+    if output::is_json() {
+        output::print_ok(json!({
+            "signer": signer.encode(),
+            "public_key": public_key.to_string(),
+            "signature": signature.to_string(),
+        }));
+    } else {
+        println!("Public key: {}", public_key);
+        println!("Signature: {}", signature);
+    }
+}
+
+/// Verify the signature of an off-chain message made with [`sign_message`].
+/// The signature is checked against the given public key or, if none is
+/// given, the address' public key from the ledger. Fails if the signature is
+/// not valid.
+pub async fn verify_message(ctx: Context, args: args::VerifyMessage) {
+    let address = ctx.get(&args.address);
+    let public_key = match args.public_key {
+        Some(public_key) => {
+            if let Address::Implicit(_) = &address {
+                if Address::from(&public_key) != address {
+                    output::fail(format!(
+                        "The public key {} doesn't belong to the implicit \
+                         address {}",
+                        public_key,
+                        address.encode()
+                    ))
+                }
+            }
+            public_key
+        }
+        None => rpc::get_public_key(&address, args.query.ledger_address)
+            .await
+            .unwrap_or_else(|| {
+                output::fail(format!(
+                    "No public key found for the address {}. Provide the \
+                     public key with the `--public-key` argument.",
+                    address.encode()
+                ))
+            }),
+    };
+    match common::verify_message(&public_key, &args.message, &args.signature) {
+        Ok(()) => {
+            if output::is_json() {
+                output::print_ok(json!({
+                    "address": address.encode(),
+                    "public_key": public_key.to_string(),
+                    "valid": true,
+                }));
+            } else {
+                println!(
+                    "The signature of the message by {} is valid.",
+                    address.encode()
+                );
+            }
         }
+        Err(err) => output::fail(format!(
+            "The signature of the message by {} is not valid: {}",
+            address.encode(),
+            err
+        )),
     }
 }
@@ -1,20 +1,35 @@
 use std::borrow::Cow;
+use std::collections::BTreeSet;
 use std::convert::TryFrom;
+use std::fmt::Display;
 use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 use anoma::ledger::governance::storage as gov_storage;
-use anoma::ledger::pos::{BondId, Bonds, Unbonds};
+use anoma::ledger::pos::anoma_proof_of_stake::validation;
+use anoma::ledger::pos::types::BasisPoints;
+use anoma::ledger::pos::{
+    BondId, Bonds, CommissionRates, PosParams, Rewards, Slashes, Unbonds,
+    ValidatorConsensusKeys, ValidatorStates,
+};
 use anoma::proto::Tx;
 use anoma::types::address::{xan as m1t, Address};
-use anoma::types::governance::{OfflineProposal, Proposal};
+use anoma::types::governance::{
+    OfflineProposal, Proposal, ProposalContentLimits,
+};
+use anoma::types::hash::Hash;
 use anoma::types::key::*;
 use anoma::types::nft::{self, Nft, NftToken};
 use anoma::types::storage::Epoch;
+use anoma::types::time::DateTimeUtc;
 use anoma::types::token::Amount;
+use anoma::types::transaction::bundle::TxBundle;
 use anoma::types::transaction::governance::InitProposalData;
 use anoma::types::transaction::nft::{CreateNft, MintNft};
 use anoma::types::transaction::{
-    hash_tx, pos, Fee, InitAccount, InitValidator, UpdateVp, WrapperTx,
+    self, hash_tx, pos, Fee, InitAccount, InitValidator, TxType, UpdateVp,
+    WrapperTx,
 };
 use anoma::types::{address, token};
 use anoma::{ledger, vm};
@@ -23,6 +38,7 @@ use borsh::BorshSerialize;
 use itertools::Either::*;
 use jsonpath_lib as jsonpath;
 use serde::Serialize;
+use serde_json::json;
 #[cfg(not(feature = "ABCI"))]
 use tendermint_config::net::Address as TendermintAddress;
 #[cfg(feature = "ABCI")]
@@ -40,27 +56,36 @@ use tendermint_rpc_abci::query::{EventType, Query};
 #[cfg(feature = "ABCI")]
 use tendermint_rpc_abci::{Client, HttpClient};
 
-use super::{rpc, signing};
+use super::{decode, rpc, signing};
 use crate::cli::context::WalletAddress;
-use crate::cli::{args, safe_exit, Context};
+use crate::cli::{args, output, safe_exit, Context};
 use crate::client::tendermint_websocket_client::{
     Error, TendermintWebsocketClient, WebSocketAddress,
 };
 #[cfg(not(feature = "ABCI"))]
 use crate::node::ledger::events::{Attributes, EventType as TmEventType};
-use crate::node::ledger::tendermint_node;
+use crate::node::ledger::rpc::DryRunResult;
+use crate::node::ledger::{tendermint_node, ErrorCodes};
 
 const TX_INIT_ACCOUNT_WASM: &str = "tx_init_account.wasm";
 const TX_INIT_VALIDATOR_WASM: &str = "tx_init_validator.wasm";
 const TX_INIT_PROPOSAL: &str = "tx_init_proposal.wasm";
 const TX_UPDATE_VP_WASM: &str = "tx_update_vp.wasm";
 const TX_TRANSFER_WASM: &str = "tx_transfer.wasm";
+const TX_FREEZE_TOKEN_WASM: &str = "tx_freeze_token.wasm";
+const TX_MINT_TOKEN_WASM: &str = "tx_mint_token.wasm";
+const TX_SET_TRANSFER_LISTS_WASM: &str = "tx_set_transfer_lists.wasm";
 const TX_INIT_NFT: &str = "tx_init_nft.wasm";
 const TX_MINT_NFT: &str = "tx_mint_nft.wasm";
 const VP_USER_WASM: &str = "vp_user.wasm";
 const TX_BOND_WASM: &str = "tx_bond.wasm";
 const TX_UNBOND_WASM: &str = "tx_unbond.wasm";
 const TX_WITHDRAW_WASM: &str = "tx_withdraw.wasm";
+const TX_CHANGE_COMMISSION_RATE_WASM: &str = "tx_change_commission_rate.wasm";
+const TX_CLAIM_REWARDS_WASM: &str = "tx_claim_rewards.wasm";
+const TX_EDIT_VALIDATOR_WASM: &str = "tx_edit_validator.wasm";
+const TX_UNJAIL_VALIDATOR_WASM: &str = "tx_unjail_validator.wasm";
+const TX_CHANGE_CONSENSUS_KEY_WASM: &str = "tx_change_consensus_key.wasm";
 const VP_NFT: &str = "vp_nft.wasm";
 
 /// Data needed for broadcasting a tx and
@@ -88,50 +113,94 @@ pub async fn submit_custom(ctx: Context, args: args::TxCustom) {
     save_initialized_accounts(ctx, &args.tx, initialized_accounts).await;
 }
 
+pub async fn submit_bundle(ctx: Context, args: args::TxBundle) {
+    let file = std::fs::read_to_string(&args.txs_file).unwrap_or_else(|err| {
+        output::fail(format!(
+            "Cannot read the transactions file {}: {}",
+            args.txs_file.to_string_lossy(),
+            err
+        ))
+    });
+    // The members are the inner txs, their wrappers are dropped
+    let txs: Vec<Tx> = file
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|path| {
+            std::fs::read(path)
+                .map_err(|err| decode::Error::ReadFile(path.into(), err))
+                .and_then(|tx_bytes| decode::unwrap_tx_bytes(&tx_bytes))
+                .map(|(tx, _wrapper)| tx)
+                .unwrap_or_else(|err| output::fail(err))
+        })
+        .collect();
+    if txs.is_empty() {
+        output::fail(format!(
+            "The transactions file {} is empty.",
+            args.txs_file.to_string_lossy()
+        ))
+    }
+
+    let bundle = TxBundle { txs };
+    if !output::is_json() {
+        println!(
+            "Submitting a bundle of {} transactions with id {}",
+            bundle.txs.len(),
+            bundle.id()
+        );
+    }
+    process_tx(ctx, &args.tx, bundle.to_tx(), None).await;
+}
+
 pub async fn submit_update_vp(ctx: Context, args: args::TxUpdateVp) {
     let addr = ctx.get(&args.addr);
 
     // Check that the address is established and exists on chain
     match &addr {
-        Address::Established(_) => {
+        Address::Established(_) if ledger_checks_enabled(&args.tx) => {
             let exists =
                 rpc::known_address(&addr, args.tx.ledger_address.clone()).await;
             if !exists {
-                eprintln!("The address {} doesn't exist on chain.", addr);
-                if !args.tx.force {
-                    safe_exit(1)
-                }
+                output::fail_unless_forced(
+                    args.tx.force,
+                    format!("The address {} doesn't exist on chain.", addr),
+                );
             }
         }
+        Address::Established(_) => {}
         Address::Implicit(_) => {
-            eprintln!(
+            output::fail_unless_forced(
+                args.tx.force,
                 "A validity predicate of an implicit address cannot be \
                  directly updated. You can use an established address for \
-                 this purpose."
+                 this purpose.",
             );
-            if !args.tx.force {
-                safe_exit(1)
-            }
         }
         Address::Internal(_) => {
-            eprintln!(
+            output::fail_unless_forced(
+                args.tx.force,
                 "A validity predicate of an internal address cannot be \
-                 directly updated."
+                 directly updated.",
             );
-            if !args.tx.force {
-                safe_exit(1)
-            }
         }
     }
 
-    let vp_code = ctx.read_wasm(args.vp_code_path);
+    let vp_code = match read_vp_code(&ctx, args.vp_code_path, args.vp_template)
+    {
+        Some(vp_code) => vp_code,
+        None => output::fail(
+            "Either a path to the validity predicate code or a VP template \
+             must be specified.",
+        ),
+    };
     // Validate the VP code
     if let Err(err) = vm::validate_untrusted_wasm(&vp_code) {
-        eprintln!("Validity predicate code validation failed with {}", err);
-        if !args.tx.force {
-            safe_exit(1)
-        }
+        output::fail_unless_forced(
+            args.tx.force,
+            format!("Validity predicate code validation failed with {}", err),
+        );
     }
+    check_vp_whitelisted(&vp_code, &args.tx).await;
 
     let tx_code = ctx.read_wasm(TX_UPDATE_VP_WASM);
 
@@ -144,17 +213,16 @@ pub async fn submit_update_vp(ctx: Context, args: args::TxUpdateVp) {
 
 pub async fn submit_init_account(mut ctx: Context, args: args::TxInitAccount) {
     let public_key = ctx.get_cached(&args.public_key);
-    let vp_code = args
-        .vp_code_path
-        .map(|path| ctx.read_wasm(path))
+    let vp_code = read_vp_code(&ctx, args.vp_code_path, args.vp_template)
         .unwrap_or_else(|| ctx.read_wasm(VP_USER_WASM));
     // Validate the VP code
     if let Err(err) = vm::validate_untrusted_wasm(&vp_code) {
-        eprintln!("Validity predicate code validation failed with {}", err);
-        if !args.tx.force {
-            safe_exit(1)
-        }
+        output::fail_unless_forced(
+            args.tx.force,
+            format!("Validity predicate code validation failed with {}", err),
+        );
     }
+    check_vp_whitelisted(&vp_code, &args.tx).await;
 
     let tx_code = ctx.read_wasm(TX_INIT_ACCOUNT_WASM);
     let data = InitAccount {
@@ -169,6 +237,99 @@ pub async fn submit_init_account(mut ctx: Context, args: args::TxInitAccount) {
     save_initialized_accounts(ctx, &args.tx, initialized_accounts).await;
 }
 
+/// Read the VP code from the given WASM file path or from the WASM file of the
+/// given built-in VP template. The template's file is resolved in the WASM
+/// directory by its name and checksum. Returns `None` if neither is given.
+fn read_vp_code(
+    ctx: &Context,
+    vp_code_path: Option<PathBuf>,
+    vp_template: Option<args::VpTemplate>,
+) -> Option<Vec<u8>> {
+    match (vp_code_path, vp_template) {
+        (Some(path), _) => Some(ctx.read_wasm(path)),
+        (None, Some(template)) => {
+            Some(ctx.read_wasm(template.wasm_file_name()))
+        }
+        (None, None) => None,
+    }
+}
+
+/// Whether the client checks that query the ledger can be run for the tx.
+/// The ledger isn't available for an offline tx, which is written out with
+/// `--raw-tx-out` to be submitted later, so its checks are skipped.
+fn ledger_checks_enabled(args: &args::Tx) -> bool {
+    args.raw_tx_out.is_none()
+}
+
+/// Check that the VP code is permitted by the chain's VP whitelist, if any.
+async fn check_vp_whitelisted(vp_code: &[u8], tx_args: &args::Tx) {
+    if !ledger_checks_enabled(tx_args) {
+        return;
+    }
+    let client = HttpClient::new(tx_args.ledger_address.clone()).unwrap();
+    let vp_whitelist_key = ledger::parameters::vp_whitelist_storage_key();
+    let vp_whitelist: Vec<String> =
+        rpc::query_storage_value(&client, &vp_whitelist_key)
+            .await
+            .unwrap_or_default();
+    if !ledger::parameters::is_vp_whitelisted(&vp_whitelist, vp_code) {
+        output::fail_unless_forced(
+            tx_args.force,
+            format!(
+                "The validity predicate with hash {} is not in the VP \
+                 whitelist of the chain.",
+                Hash::sha256(vp_code)
+            ),
+        );
+    }
+}
+
+/// Check that the gas price, converted to the native token with the chain's
+/// fee token rates, is not below the chain's current base fee.
+async fn check_gas_price(
+    tx_args: &args::Tx,
+    gas_price: Amount,
+    fee_token: &Address,
+) {
+    if !ledger_checks_enabled(tx_args) {
+        return;
+    }
+    let client = HttpClient::new(tx_args.ledger_address.clone()).unwrap();
+    let base_fee_key = ledger::parameters::base_fee_storage_key();
+    let base_fee: Amount = rpc::query_storage_value(&client, &base_fee_key)
+        .await
+        .unwrap_or_default();
+    let fee_token_rates: ledger::parameters::FeeTokenRates =
+        rpc::query_storage_value(
+            &client,
+            &ledger::parameters::fee_token_rates_storage_key(),
+        )
+        .await
+        .unwrap_or_default();
+    let gas_price = match fee_token_rates.to_native(fee_token, gas_price) {
+        Some(gas_price) => gas_price,
+        None => {
+            return output::fail_unless_forced(
+                tx_args.force,
+                format!(
+                    "The token {} can't pay fees on this chain, it has no \
+                     conversion rate to the native token.",
+                    fee_token
+                ),
+            );
+        }
+    };
+    if gas_price < base_fee {
+        output::fail_unless_forced(
+            tx_args.force,
+            format!(
+                "The gas price {} is below the current base fee {}.",
+                gas_price, base_fee
+            ),
+        );
+    }
+}
+
 pub async fn submit_init_validator(
     mut ctx: Context,
     args::TxInitValidator {
@@ -180,9 +341,24 @@ pub async fn submit_init_validator(
         protocol_key,
         validator_vp_code_path,
         rewards_vp_code_path,
+        commission_rate,
+        metadata,
         unsafe_dont_encrypt,
     }: args::TxInitValidator,
 ) {
+    if let Err(err) = metadata.validate() {
+        output::fail_unless_forced(tx_args.force, format!("{}", err));
+    }
+    if BasisPoints::new(commission_rate) > BasisPoints::HUNDRED_PERCENT {
+        output::fail_unless_forced(
+            tx_args.force,
+            format!(
+                "The commission rate {} must not be greater than 100%.",
+                BasisPoints::new(commission_rate)
+            ),
+        );
+    }
+
     let alias = tx_args
         .initialized_account_alias
         .as_ref()
@@ -237,28 +413,30 @@ pub async fn submit_init_validator(
         .unwrap_or_else(|| ctx.read_wasm(VP_USER_WASM));
     // Validate the validator VP code
     if let Err(err) = vm::validate_untrusted_wasm(&validator_vp_code) {
-        eprintln!(
-            "Validator validity predicate code validation failed with {}",
-            err
+        output::fail_unless_forced(
+            tx_args.force,
+            format!(
+                "Validator validity predicate code validation failed with {}",
+                err
+            ),
         );
-        if !tx_args.force {
-            safe_exit(1)
-        }
     }
+    check_vp_whitelisted(&validator_vp_code, &tx_args).await;
     let rewards_vp_code = rewards_vp_code_path
         .map(|path| ctx.read_wasm(path))
         .unwrap_or_else(|| ctx.read_wasm(VP_USER_WASM));
     // Validate the rewards VP code
     if let Err(err) = vm::validate_untrusted_wasm(&rewards_vp_code) {
-        eprintln!(
-            "Staking reward account validity predicate code validation failed \
-             with {}",
-            err
+        output::fail_unless_forced(
+            tx_args.force,
+            format!(
+                "Staking reward account validity predicate code validation \
+                 failed with {}",
+                err
+            ),
         );
-        if !tx_args.force {
-            safe_exit(1)
-        }
     }
+    check_vp_whitelisted(&rewards_vp_code, &tx_args).await;
     let tx_code = ctx.read_wasm(TX_INIT_VALIDATOR_WASM);
 
     let data = InitValidator {
@@ -269,12 +447,15 @@ pub async fn submit_init_validator(
         dkg_key,
         validator_vp_code,
         rewards_vp_code,
+        commission_rate,
+        metadata,
     };
     let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
     let tx = Tx::new(tx_code, Some(data));
     let (mut ctx, initialized_accounts) =
         process_tx(ctx, &tx_args, tx, Some(&source)).await;
-    if !tx_args.dry_run {
+    // The initialized accounts are only known once the tx has been applied
+    if !tx_args.dry_run && tx_args.raw_tx_out.is_none() {
         let (validator_address_alias, validator_address, rewards_address_alias) =
             match &initialized_accounts[..] {
                 // There should be 2 accounts, one for the validator itself, one
@@ -342,10 +523,7 @@ pub async fn submit_init_validator(
                         rewards_address_alias,
                     )
                 }
-                _ => {
-                    eprintln!("Expected two accounts to be created");
-                    safe_exit(1)
-                }
+                _ => output::fail("Expected two accounts to be created"),
             };
         // add validator address and keys to the wallet
         ctx.wallet
@@ -378,79 +556,349 @@ pub async fn submit_init_validator(
     }
 }
 
-pub async fn submit_transfer(ctx: Context, args: args::TxTransfer) {
+pub async fn submit_transfer(mut ctx: Context, args: args::TxTransfer) {
     let source = ctx.get(&args.source);
+    let target = ctx.get(&args.target);
+    let token = ctx.get(&args.token);
+    let amount = if args.all {
+        sweep_amount(&mut ctx, &args, &source, &token).await
+    } else {
+        args.amount
+    };
+    if ledger_checks_enabled(&args.tx) {
+        check_transfer(&args, &source, &target, &token, amount).await;
+    }
+    let tx_code = ctx.read_wasm(TX_TRANSFER_WASM);
+    let transfer = token::Transfer {
+        source,
+        target,
+        token,
+        amount,
+    };
+    tracing::debug!("Transfer data {:?}", transfer);
+    let data = transfer
+        .try_to_vec()
+        .expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.source)).await;
+}
+
+/// Find the amount of a `transfer --all`: the source's entire balance of the
+/// token, less the fee when it's paid by the source in the same token. When
+/// the fee is paid by another address or in another token, its balance must
+/// cover the fee separately.
+///
+/// The balance is queried from the last committed state. If it decreases
+/// before the transfer is applied, the token's VP rejects the transfer, so
+/// nothing is transferred and the sweep can be re-run. If it increases, the
+/// difference is left in the source's balance.
+async fn sweep_amount(
+    ctx: &mut Context,
+    args: &args::TxTransfer,
+    source: &Address,
+    token: &Address,
+) -> Amount {
+    if args.tx.raw_tx_out.is_some() {
+        output::fail(
+            "The balance transferred with `--all` must be queried from the \
+             ledger, so it can't be used for an offline transaction.",
+        )
+    }
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    let balance: Amount =
+        rpc::query_storage_value(&client, &token::balance_key(token, source))
+            .await
+            .unwrap_or_default();
+    let fee = wrapper_fee(ctx, &args.tx);
+    let fee_payer = fee_payer(ctx, &args.tx, Some(&args.source)).await;
+    let fee_exemption: ledger::parameters::FeeExemption =
+        rpc::query_storage_value(
+            &client,
+            &ledger::parameters::fee_exemption_storage_key(),
+        )
+        .await
+        .unwrap_or_default();
+    let fee_amount = if fee_exemption.is_exempt(&fee_payer) {
+        Amount::default()
+    } else {
+        fee.amount
+    };
+
+    let amount = if &fee_payer == source && &fee.token == token {
+        if balance < fee_amount {
+            output::fail(format!(
+                "The balance {} of the source {} of token {} doesn't cover \
+                 the fee {}.",
+                balance, source, token, fee_amount
+            ))
+        }
+        balance - fee_amount
+    } else {
+        let fee_balance: Amount = rpc::query_storage_value(
+            &client,
+            &token::balance_key(&fee.token, &fee_payer),
+        )
+        .await
+        .unwrap_or_default();
+        if fee_balance < fee_amount {
+            output::fail(format!(
+                "The fee {} of token {} can't be paid separately from the \
+                 transferred balance, the balance of the fee payer {} is {}.",
+                fee_amount, fee.token, fee_payer, fee_balance
+            ))
+        }
+        balance
+    };
+    if amount == Amount::default() {
+        output::fail(format!(
+            "The source {} has no balance of token {} to transfer.",
+            source, token
+        ))
+    }
+    println!(
+        "Transferring the entire balance of the source {} of token {}: {}.",
+        source, token, amount
+    );
+    amount
+}
+
+/// Find the address that pays the fee of a tx, which is the implicit address
+/// of the key that signs its wrapper. The key of an established signer is
+/// looked-up from the ledger.
+async fn fee_payer(
+    ctx: &mut Context,
+    args: &args::Tx,
+    default: Option<&WalletAddress>,
+) -> Address {
+    if let Some(signing_key) = &args.signing_key {
+        let signing_key = ctx.get_cached(signing_key);
+        return Address::from(&signing_key.ref_to());
+    }
+    let signer = match args.signer.as_ref().or(default) {
+        Some(signer) => ctx.get(signer),
+        None => output::fail(
+            "All transactions must be signed; please either specify the key \
+             or the address from which to look up the signing key.",
+        ),
+    };
+    match signer {
+        Address::Established(_) => {
+            rpc::get_public_key(&signer, args.ledger_address.clone())
+                .await
+                .map(|public_key| Address::from(&public_key))
+                .unwrap_or_else(|| {
+                    output::fail(format!(
+                        "No public key found for the address {}",
+                        signer.encode()
+                    ))
+                })
+        }
+        _ => signer,
+    }
+}
+
+/// Check that the addresses of a transfer exist on chain and that the source
+/// has sufficient balance for the given amount.
+async fn check_transfer(
+    args: &args::TxTransfer,
+    source: &Address,
+    target: &Address,
+    token: &Address,
+    amount: Amount,
+) {
     // Check that the source address exists on chain
     let source_exists =
-        rpc::known_address(&source, args.tx.ledger_address.clone()).await;
+        rpc::known_address(source, args.tx.ledger_address.clone()).await;
     if !source_exists {
-        eprintln!("The source address {} doesn't exist on chain.", source);
-        if !args.tx.force {
-            safe_exit(1)
-        }
+        output::fail_unless_forced(
+            args.tx.force,
+            format!("The source address {} doesn't exist on chain.", source),
+        );
     }
-    let target = ctx.get(&args.target);
     // Check that the target address exists on chain
     let target_exists =
-        rpc::known_address(&target, args.tx.ledger_address.clone()).await;
+        rpc::known_address(target, args.tx.ledger_address.clone()).await;
     if !target_exists {
-        eprintln!("The target address {} doesn't exist on chain.", target);
-        if !args.tx.force {
-            safe_exit(1)
-        }
+        output::fail_unless_forced(
+            args.tx.force,
+            format!("The target address {} doesn't exist on chain.", target),
+        );
     }
-    let token = ctx.get(&args.token);
     // Check that the token address exists on chain
     let token_exists =
-        rpc::known_address(&token, args.tx.ledger_address.clone()).await;
+        rpc::known_address(token, args.tx.ledger_address.clone()).await;
     if !token_exists {
-        eprintln!("The token address {} doesn't exist on chain.", token);
-        if !args.tx.force {
-            safe_exit(1)
-        }
+        output::fail_unless_forced(
+            args.tx.force,
+            format!("The token address {} doesn't exist on chain.", token),
+        );
     }
-    // Check source balance
-    let balance_key = token::balance_key(&token, &source);
+    // Check source balance, at the height of a historical dry run
+    let balance_key = token::balance_key(token, source);
     let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
-    match rpc::query_storage_value::<token::Amount>(&client, &balance_key).await
+    match rpc::query_storage_value_at::<token::Amount>(
+        &client,
+        &balance_key,
+        args.tx.dry_run_at_height,
+    )
+    .await
     {
         Some(balance) => {
-            if balance < args.amount {
-                eprintln!(
-                    "The balance of the source {} of token {} is lower than \
-                     the amount to be transferred. Amount to transfer is {} \
-                     and the balance is {}.",
-                    source, token, args.amount, balance
+            if balance < amount {
+                output::fail_unless_forced(
+                    args.tx.force,
+                    format!(
+                        "The balance of the source {} of token {} is lower \
+                         than the amount to be transferred. Amount to \
+                         transfer is {} and the balance is {}.",
+                        source, token, amount, balance
+                    ),
                 );
-                if !args.tx.force {
-                    safe_exit(1)
-                }
             }
         }
         None => {
-            eprintln!(
-                "No balance found for the source {} of token {}",
-                source, token
+            output::fail_unless_forced(
+                args.tx.force,
+                format!(
+                    "No balance found for the source {} of token {}",
+                    source, token
+                ),
             );
-            if !args.tx.force {
-                safe_exit(1)
-            }
         }
     }
-    let tx_code = ctx.read_wasm(TX_TRANSFER_WASM);
-    let transfer = token::Transfer {
-        source,
-        target,
+}
+
+pub async fn submit_token_admin(ctx: Context, args: args::TxTokenAdmin) {
+    if !args.freeze && !args.unfreeze {
+        output::fail("Either --freeze or --unfreeze must be given.")
+    }
+    let token = ctx.get(&args.token);
+    // The token's admin is the default signer, it's looked up from the
+    // ledger, so an offline tx must be given the signer explicitly
+    let admin = if ledger_checks_enabled(&args.tx) {
+        let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+        let admin = rpc::query_storage_value::<Address>(
+            &client,
+            &token::admin_key(&token),
+        )
+        .await;
+        if admin.is_none() {
+            output::fail_unless_forced(
+                args.tx.force,
+                format!("The token {} has no admin.", token),
+            );
+        }
+        admin.map(|admin| WalletAddress::new(admin.encode()))
+    } else {
+        None
+    };
+
+    let tx_code = ctx.read_wasm(TX_FREEZE_TOKEN_WASM);
+    let freeze = token::Freeze {
         token,
-        amount: args.amount,
+        frozen: args.freeze,
     };
-    tracing::debug!("Transfer data {:?}", transfer);
-    let data = transfer
+    tracing::debug!("Freeze data {:?}", freeze);
+    let data = freeze
         .try_to_vec()
         .expect("Encoding tx data shouldn't fail");
 
     let tx = Tx::new(tx_code, Some(data));
-    process_tx(ctx, &args.tx, tx, Some(&args.source)).await;
+    process_tx(ctx, &args.tx, tx, admin.as_ref()).await;
+}
+
+pub async fn submit_mint(ctx: Context, args: args::TxMint) {
+    let token = ctx.get(&args.token);
+    let target = ctx.get(&args.target);
+    // The token's minter is the default signer, it's looked up from the
+    // ledger, so an offline tx must be given the signer explicitly
+    let minter = if ledger_checks_enabled(&args.tx) {
+        let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+        let minter = rpc::query_storage_value::<Address>(
+            &client,
+            &token::minter_key(&token),
+        )
+        .await;
+        if minter.is_none() {
+            output::fail_unless_forced(
+                args.tx.force,
+                format!("The token {} has no minter.", token),
+            );
+        }
+        let mint_cap = rpc::query_storage_value::<token::Amount>(
+            &client,
+            &token::mint_cap_key(&token),
+        )
+        .await;
+        if let Some(mint_cap) = mint_cap {
+            let mut minted = rpc::query_storage_value::<token::Amount>(
+                &client,
+                &token::minted_key(&token),
+            )
+            .await
+            .unwrap_or_default();
+            minted.receive(&args.amount);
+            if minted > mint_cap {
+                output::fail_unless_forced(
+                    args.tx.force,
+                    format!(
+                        "The mint would exceed the mint cap {} of the token \
+                         {}.",
+                        mint_cap, token
+                    ),
+                );
+            }
+        }
+        minter.map(|minter| WalletAddress::new(minter.encode()))
+    } else {
+        None
+    };
+
+    let tx_code = ctx.read_wasm(TX_MINT_TOKEN_WASM);
+    let mint = token::Mint {
+        token,
+        target,
+        amount: args.amount,
+    };
+    tracing::debug!("Mint data {:?}", mint);
+    let data = mint.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, minter.as_ref()).await;
+}
+
+pub async fn submit_transfer_lists(ctx: Context, args: args::TxTransferLists) {
+    let address = ctx.get(&args.address);
+    for (name, list) in [("allow", &args.allow), ("deny", &args.deny)] {
+        if list.len() > token::MAX_TRANSFER_LIST_LEN {
+            output::fail_unless_forced(
+                args.tx.force,
+                format!(
+                    "The {} list has {} addresses, the maximum is {}.",
+                    name,
+                    list.len(),
+                    token::MAX_TRANSFER_LIST_LEN
+                ),
+            );
+        }
+    }
+    let allow: BTreeSet<Address> =
+        args.allow.iter().map(|address| ctx.get(address)).collect();
+    let deny: BTreeSet<Address> =
+        args.deny.iter().map(|address| ctx.get(address)).collect();
+
+    let tx_code = ctx.read_wasm(TX_SET_TRANSFER_LISTS_WASM);
+    let lists = token::TransferLists {
+        owner: address,
+        allow: if allow.is_empty() { None } else { Some(allow) },
+        deny,
+    };
+    tracing::debug!("Transfer lists data {:?}", lists);
+    let data = lists.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.address)).await;
 }
 
 pub async fn submit_init_nft(ctx: Context, args: args::NftCreate) {
@@ -499,8 +947,10 @@ pub async fn submit_mint_nft(ctx: Context, args: args::NftMint) {
         {
             Some(addr) => addr,
             None => {
-                eprintln!("No creator key found for {}", &args.nft_address);
-                safe_exit(1);
+                output::fail(format!(
+                    "No creator key found for {}",
+                    &args.nft_address
+                ));
             }
         };
 
@@ -533,9 +983,31 @@ pub async fn submit_init_proposal(mut ctx: Context, args: args::InitProposal) {
     let init_proposal_data = if let Ok(data) = tx_data {
         data
     } else {
-        eprintln!("Invalid data for init proposal transaction.");
-        safe_exit(1)
+        output::fail("Invalid data for init proposal transaction.")
+    };
+
+    // The limits of the fields of the content can only be queried from the
+    // ledger, so the default limits are used for offline proposals
+    let content_limits = if args.offline {
+        ProposalContentLimits::default()
+    } else {
+        let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+        let content_limits_key = gov_storage::get_proposal_content_limits_key();
+        rpc::query_storage_value(&client, &content_limits_key)
+            .await
+            .unwrap_or_default()
     };
+    let content_errors = content_limits.validate(&proposal.content);
+    if !content_errors.is_empty() {
+        let errors: Vec<String> = content_errors
+            .iter()
+            .map(|err| format!("  - {}", err))
+            .collect();
+        output::fail_unless_forced(
+            args.tx.force,
+            format!("Invalid proposal content:\n{}", errors.join("\n")),
+        );
+    }
 
     if args.offline {
         let signer = ctx.get(&signer);
@@ -552,10 +1024,10 @@ pub async fn submit_init_proposal(mut ctx: Context, args: args::InitProposal) {
             Ok(_) => {
                 println!("Proposal created: {}.", proposal_filename);
             }
-            Err(e) => {
-                eprintln!("Error while creating proposal file: {}.", e);
-                safe_exit(1)
-            }
+            Err(e) => output::fail(format!(
+                "Error while creating proposal file: {}.",
+                e
+            )),
         }
     } else {
         let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
@@ -569,11 +1041,10 @@ pub async fn submit_init_proposal(mut ctx: Context, args: args::InitProposal) {
             .await
             .unwrap_or_default();
         if balance < min_proposal_funds {
-            eprintln!(
+            output::fail(format!(
                 "Address {} doesn't have enough funds.",
                 &proposal.author
-            );
-            safe_exit(1);
+            ));
         }
 
         let data = init_proposal_data
@@ -592,54 +1063,62 @@ pub async fn submit_vote_proposal(_ctx: Context, _args: args::VoteProposal) {
 
 pub async fn submit_bond(ctx: Context, args: args::Bond) {
     let validator = ctx.get(&args.validator);
-    // Check that the validator address exists on chain
-    let is_validator =
-        rpc::is_validator(&validator, args.tx.ledger_address.clone()).await;
-    if !is_validator {
-        eprintln!(
-            "The address {} doesn't belong to any known validator account.",
-            validator
-        );
-        if !args.tx.force {
-            safe_exit(1)
-        }
-    }
     let source = ctx.get_opt(&args.source);
-    // Check that the source address exists on chain
-    if let Some(source) = &source {
-        let source_exists =
-            rpc::known_address(source, args.tx.ledger_address.clone()).await;
-        if !source_exists {
-            eprintln!("The source address {} doesn't exist on chain.", source);
-            if !args.tx.force {
-                safe_exit(1)
-            }
+    if ledger_checks_enabled(&args.tx) {
+        // Check that the validator address exists on chain
+        let is_validator =
+            rpc::is_validator(&validator, args.tx.ledger_address.clone()).await;
+        if !is_validator {
+            output::fail_unless_forced(
+                args.tx.force,
+                format!(
+                    "The address {} doesn't belong to any known validator \
+                     account.",
+                    validator
+                ),
+            );
         }
-    }
-    // Check bond's source (source for delegation or validator for self-bonds)
-    // balance
-    let bond_source = source.as_ref().unwrap_or(&validator);
-    let balance_key = token::balance_key(&address::xan(), bond_source);
-    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
-    match rpc::query_storage_value::<token::Amount>(&client, &balance_key).await
-    {
-        Some(balance) => {
-            if balance < args.amount {
-                eprintln!(
-                    "The balance of the source {} is lower than the amount to \
-                     be transferred. Amount to transfer is {} and the balance \
-                     is {}.",
-                    bond_source, args.amount, balance
+        // Check that the source address exists on chain
+        if let Some(source) = &source {
+            let source_exists =
+                rpc::known_address(source, args.tx.ledger_address.clone())
+                    .await;
+            if !source_exists {
+                output::fail_unless_forced(
+                    args.tx.force,
+                    format!(
+                        "The source address {} doesn't exist on chain.",
+                        source
+                    ),
                 );
-                if !args.tx.force {
-                    safe_exit(1)
-                }
             }
         }
-        None => {
-            eprintln!("No balance found for the source {}", bond_source);
-            if !args.tx.force {
-                safe_exit(1)
+        // Check bond's source (source for delegation or validator for self-bonds)
+        // balance
+        let bond_source = source.as_ref().unwrap_or(&validator);
+        let balance_key = token::balance_key(&address::xan(), bond_source);
+        let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+        match rpc::query_storage_value::<token::Amount>(&client, &balance_key)
+            .await
+        {
+            Some(balance) => {
+                if balance < args.amount {
+                    output::fail_unless_forced(
+                        args.tx.force,
+                        format!(
+                            "The balance of the source {} is lower than the \
+                             amount to be transferred. Amount to transfer is \
+                             {} and the balance is {}.",
+                            bond_source, args.amount, balance
+                        ),
+                    );
+                }
+            }
+            None => {
+                output::fail_unless_forced(
+                    args.tx.force,
+                    format!("No balance found for the source {}", bond_source),
+                );
             }
         }
     }
@@ -658,55 +1137,55 @@ pub async fn submit_bond(ctx: Context, args: args::Bond) {
 
 pub async fn submit_unbond(ctx: Context, args: args::Unbond) {
     let validator = ctx.get(&args.validator);
-    // Check that the validator address exists on chain
-    let is_validator =
-        rpc::is_validator(&validator, args.tx.ledger_address.clone()).await;
-    if !is_validator {
-        eprintln!(
-            "The address {} doesn't belong to any known validator account.",
-            validator
-        );
-        if !args.tx.force {
-            safe_exit(1)
-        }
-    }
-
     let source = ctx.get_opt(&args.source);
     let tx_code = ctx.read_wasm(TX_UNBOND_WASM);
 
-    // Check the source's current bond amount
-    let bond_source = source.clone().unwrap_or_else(|| validator.clone());
-    let bond_id = BondId {
-        source: bond_source.clone(),
-        validator: validator.clone(),
-    };
-    let bond_key = ledger::pos::bond_key(&bond_id);
-    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
-    let bonds = rpc::query_storage_value::<Bonds>(&client, &bond_key).await;
-    match bonds {
-        Some(bonds) => {
-            let mut bond_amount: token::Amount = 0.into();
-            for bond in bonds.iter() {
-                for delta in bond.deltas.values() {
-                    bond_amount += *delta;
+    if ledger_checks_enabled(&args.tx) {
+        // Check that the validator address exists on chain
+        let is_validator =
+            rpc::is_validator(&validator, args.tx.ledger_address.clone()).await;
+        if !is_validator {
+            output::fail_unless_forced(
+                args.tx.force,
+                format!(
+                    "The address {} doesn't belong to any known validator \
+                     account.",
+                    validator
+                ),
+            );
+        }
+
+        // Check the source's current bond amount
+        let bond_source = source.clone().unwrap_or_else(|| validator.clone());
+        let bond_id = BondId {
+            source: bond_source.clone(),
+            validator: validator.clone(),
+        };
+        let bond_key = ledger::pos::bond_key(&bond_id);
+        let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+        let bonds = rpc::query_storage_value::<Bonds>(&client, &bond_key).await;
+        match bonds {
+            Some(bonds) => {
+                let mut bond_amount: token::Amount = 0.into();
+                for bond in bonds.iter() {
+                    for delta in bond.deltas.values() {
+                        bond_amount += *delta;
+                    }
                 }
-            }
-            if args.amount > bond_amount {
-                eprintln!(
-                    "The total bonds of the source {} is lower than the \
-                     amount to be unbonded. Amount to unbond is {} and the \
-                     total bonds is {}.",
-                    bond_source, args.amount, bond_amount
-                );
-                if !args.tx.force {
-                    safe_exit(1)
+                if args.amount > bond_amount {
+                    output::fail_unless_forced(
+                        args.tx.force,
+                        format!(
+                            "The total bonds of the source {} is lower than \
+                             the amount to be unbonded. Amount to unbond is \
+                             {} and the total bonds is {}.",
+                            bond_source, args.amount, bond_amount
+                        ),
+                    );
                 }
             }
-        }
-        None => {
-            eprintln!("No bonds found");
-            if !args.tx.force {
-                safe_exit(1)
+            None => {
+                output::fail_unless_forced(args.tx.force, "No bonds found");
             }
         }
     }
@@ -724,60 +1203,64 @@ pub async fn submit_unbond(ctx: Context, args: args::Unbond) {
 }
 
 pub async fn submit_withdraw(ctx: Context, args: args::Withdraw) {
-    let epoch = rpc::query_epoch(args::Query {
-        ledger_address: args.tx.ledger_address.clone(),
-    })
-    .await;
-
     let validator = ctx.get(&args.validator);
-    // Check that the validator address exists on chain
-    let is_validator =
-        rpc::is_validator(&validator, args.tx.ledger_address.clone()).await;
-    if !is_validator {
-        eprintln!(
-            "The address {} doesn't belong to any known validator account.",
-            validator
-        );
-        if !args.tx.force {
-            safe_exit(1)
-        }
-    }
-
     let source = ctx.get_opt(&args.source);
     let tx_code = ctx.read_wasm(TX_WITHDRAW_WASM);
 
-    // Check the source's current unbond amount
-    let bond_source = source.clone().unwrap_or_else(|| validator.clone());
-    let bond_id = BondId {
-        source: bond_source.clone(),
-        validator: validator.clone(),
-    };
-    let bond_key = ledger::pos::unbond_key(&bond_id);
-    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
-    let unbonds = rpc::query_storage_value::<Unbonds>(&client, &bond_key).await;
-    match unbonds {
-        Some(unbonds) => {
-            let mut unbonded_amount: token::Amount = 0.into();
-            if let Some(unbond) = unbonds.get(epoch) {
-                for delta in unbond.deltas.values() {
-                    unbonded_amount += *delta;
-                }
-            }
-            if unbonded_amount == 0.into() {
-                eprintln!(
-                    "There are no unbonded bonds ready to withdraw in the \
-                     current epoch {}.",
-                    epoch
-                );
-                if !args.tx.force {
-                    safe_exit(1)
+    if ledger_checks_enabled(&args.tx) {
+        let epoch = rpc::query_epoch(args::Query {
+            ledger_address: args.tx.ledger_address.clone(),
+        })
+        .await;
+
+        // Check that the validator address exists on chain
+        let is_validator =
+            rpc::is_validator(&validator, args.tx.ledger_address.clone()).await;
+        if !is_validator {
+            output::fail_unless_forced(
+                args.tx.force,
+                format!(
+                    "The address {} doesn't belong to any known validator \
+                     account.",
+                    validator
+                ),
+            );
+        }
+
+        // Check the source's current unbond amount
+        let bond_source = source.clone().unwrap_or_else(|| validator.clone());
+        let bond_id = BondId {
+            source: bond_source.clone(),
+            validator: validator.clone(),
+        };
+        let bond_key = ledger::pos::unbond_key(&bond_id);
+        let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+        let unbonds =
+            rpc::query_storage_value::<Unbonds>(&client, &bond_key).await;
+        match unbonds {
+            Some(unbonds) => {
+                let mut unbonded_amount: token::Amount = 0.into();
+                if let Some(unbond) = unbonds.get(epoch) {
+                    for delta in unbond.deltas.values() {
+                        unbonded_amount += *delta;
+                    }
+                }
+                if unbonded_amount == 0.into() {
+                    output::fail_unless_forced(
+                        args.tx.force,
+                        format!(
+                            "There are no unbonded bonds ready to withdraw in \
+                             the current epoch {}.",
+                            epoch
+                        ),
+                    );
                 }
             }
-        }
-        None => {
-            eprintln!("No unbonded bonds found");
-            if !args.tx.force {
-                safe_exit(1)
+            None => {
+                output::fail_unless_forced(
+                    args.tx.force,
+                    "No unbonded bonds found",
+                );
             }
         }
     }
@@ -790,6 +1273,347 @@ pub async fn submit_withdraw(ctx: Context, args: args::Withdraw) {
     process_tx(ctx, &args.tx, tx, Some(default_signer)).await;
 }
 
+pub async fn submit_claim_rewards(ctx: Context, args: args::ClaimRewards) {
+    let validator = ctx.get(&args.validator);
+    let source = ctx.get_opt(&args.source);
+    let tx_code = ctx.read_wasm(TX_CLAIM_REWARDS_WASM);
+
+    if ledger_checks_enabled(&args.tx) {
+        // Check that the validator address exists on chain
+        let is_validator =
+            rpc::is_validator(&validator, args.tx.ledger_address.clone()).await;
+        if !is_validator {
+            output::fail_unless_forced(
+                args.tx.force,
+                format!(
+                    "The address {} doesn't belong to any known validator \
+                     account.",
+                    validator
+                ),
+            );
+        }
+
+        // Check the source's current unclaimed rewards
+        let bond_source = source.clone().unwrap_or_else(|| validator.clone());
+        let bond_id = BondId {
+            source: bond_source,
+            validator: validator.clone(),
+        };
+        let rewards_key = ledger::pos::rewards_key(&bond_id);
+        let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+        let rewards =
+            rpc::query_storage_value::<Rewards>(&client, &rewards_key).await;
+        let unclaimed: token::Amount = rewards
+            .iter()
+            .flat_map(|rewards| rewards.values())
+            .fold(0.into(), |acc, amount| acc + *amount);
+        if unclaimed == 0.into() {
+            output::fail_unless_forced(
+                args.tx.force,
+                format!(
+                    "There are no unclaimed rewards to claim for {}.",
+                    bond_id
+                ),
+            );
+        } else {
+            println!("Claiming unclaimed rewards: {}", unclaimed);
+        }
+    }
+
+    let data = pos::ClaimRewards { validator, source };
+    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    let default_signer = args.source.as_ref().unwrap_or(&args.validator);
+    process_tx(ctx, &args.tx, tx, Some(default_signer)).await;
+}
+
+pub async fn submit_unjail_validator(
+    ctx: Context,
+    args: args::TxUnjailValidator,
+) {
+    let validator = ctx.get(&args.validator);
+    let tx_code = ctx.read_wasm(TX_UNJAIL_VALIDATOR_WASM);
+
+    if ledger_checks_enabled(&args.tx) {
+        let epoch = rpc::query_epoch(args::Query {
+            ledger_address: args.tx.ledger_address.clone(),
+        })
+        .await;
+        let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+        let state_key = ledger::pos::validator_state_key(&validator);
+        match rpc::query_storage_value::<ValidatorStates>(&client, &state_key)
+            .await
+        {
+            Some(state) => {
+                let params = rpc::query_storage_value::<PosParams>(
+                    &client,
+                    &ledger::pos::params_key(),
+                )
+                .await
+                .expect("PoS parameters should always be set");
+                let slashes_key =
+                    ledger::pos::validator_slashes_key(&validator);
+                let slashes =
+                    rpc::query_storage_value::<Slashes>(&client, &slashes_key)
+                        .await
+                        .unwrap_or_default();
+                let tombstoned_key =
+                    ledger::pos::validator_tombstoned_key(&validator);
+                let is_tombstoned =
+                    rpc::query_storage_value::<bool>(&client, &tombstoned_key)
+                        .await
+                        .unwrap_or_default();
+                let self_bond_key = ledger::pos::bond_key(&BondId {
+                    source: validator.clone(),
+                    validator: validator.clone(),
+                });
+                let self_bond =
+                    rpc::query_storage_value::<Bonds>(&client, &self_bond_key)
+                        .await;
+                if let Err(err) = validation::check_unjail(
+                    &params,
+                    epoch,
+                    &validator,
+                    &state,
+                    &slashes,
+                    is_tombstoned,
+                    self_bond.as_ref(),
+                ) {
+                    output::fail_unless_forced(
+                        args.tx.force,
+                        format!("{}.", err),
+                    );
+                }
+            }
+            None => {
+                output::fail_unless_forced(
+                    args.tx.force,
+                    format!(
+                        "The address {} doesn't belong to any known validator \
+                         account.",
+                        validator
+                    ),
+                );
+            }
+        }
+    }
+
+    let data = pos::Unjail { validator };
+    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.validator)).await;
+}
+
+pub async fn submit_rotate_consensus_key(
+    mut ctx: Context,
+    args: args::TxRotateConsensusKey,
+) {
+    let validator = ctx.get(&args.validator);
+    let tx_code = ctx.read_wasm(TX_CHANGE_CONSENSUS_KEY_WASM);
+
+    let consensus_key =
+        ctx.get_opt_cached(&args.consensus_key).unwrap_or_else(|| {
+            println!("Generating consensus key...");
+            let (alias, consensus_key) =
+                ctx.wallet.gen_key(None, args.unsafe_dont_encrypt);
+            println!(
+                "The new consensus key was stored in the wallet as \"{}\".",
+                alias
+            );
+            consensus_key
+        });
+    ctx.wallet.save().unwrap_or_else(|err| eprintln!("{}", err));
+    let consensus_key = consensus_key.ref_to();
+
+    let mut pipeline_epoch = None;
+    if ledger_checks_enabled(&args.tx) {
+        let epoch = rpc::query_epoch(args::Query {
+            ledger_address: args.tx.ledger_address.clone(),
+        })
+        .await;
+        let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+        let consensus_key_key =
+            ledger::pos::validator_consensus_key_key(&validator);
+        match rpc::query_storage_value::<ValidatorConsensusKeys>(
+            &client,
+            &consensus_key_key,
+        )
+        .await
+        {
+            Some(consensus_keys) => {
+                let params = rpc::query_storage_value::<PosParams>(
+                    &client,
+                    &ledger::pos::params_key(),
+                )
+                .await
+                .expect("PoS parameters should always be set");
+                let epoch = epoch + params.pipeline_len;
+                if consensus_keys.get(epoch) == Some(&consensus_key) {
+                    output::fail_unless_forced(
+                        args.tx.force,
+                        format!(
+                            "The consensus key {} is already the validator's \
+                             consensus key from the epoch {}.",
+                            consensus_key, epoch
+                        ),
+                    );
+                }
+                pipeline_epoch = Some(epoch);
+            }
+            None => {
+                output::fail_unless_forced(
+                    args.tx.force,
+                    format!(
+                        "The address {} doesn't belong to any known validator \
+                         account.",
+                        validator
+                    ),
+                );
+            }
+        }
+    }
+
+    let data = pos::ConsensusKeyChange {
+        validator,
+        consensus_key: consensus_key.clone(),
+    };
+    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.validator)).await;
+    if let (Some(epoch), false) = (pipeline_epoch, args.tx.dry_run) {
+        println!(
+            "The consensus key {} is used for the validator from the epoch \
+             {}. Until then, the validator's node must keep signing with its \
+             current consensus key.",
+            consensus_key, epoch
+        );
+    }
+}
+
+pub async fn submit_change_commission_rate(
+    ctx: Context,
+    args: args::TxChangeCommissionRate,
+) {
+    let validator = ctx.get(&args.validator);
+    let new_rate = BasisPoints::new(args.rate);
+    let tx_code = ctx.read_wasm(TX_CHANGE_COMMISSION_RATE_WASM);
+
+    if new_rate > BasisPoints::HUNDRED_PERCENT {
+        output::fail_unless_forced(
+            args.tx.force,
+            format!(
+                "The commission rate {} must not be greater than 100%.",
+                new_rate
+            ),
+        );
+    }
+
+    if ledger_checks_enabled(&args.tx) {
+        let epoch = rpc::query_epoch(args::Query {
+            ledger_address: args.tx.ledger_address.clone(),
+        })
+        .await;
+
+        // Check that the rate change is within the maximum change per epoch
+        let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+        let commission_rate_key =
+            ledger::pos::validator_commission_rate_key(&validator);
+        let commission_rates = rpc::query_storage_value::<CommissionRates>(
+            &client,
+            &commission_rate_key,
+        )
+        .await;
+        match commission_rates {
+            Some(commission_rates) => {
+                let params = rpc::query_storage_value::<PosParams>(
+                    &client,
+                    &ledger::pos::params_key(),
+                )
+                .await
+                .expect("PoS parameters should always be set");
+                let rate_before = validation::commission_rate_before_pipeline(
+                    &commission_rates,
+                    epoch,
+                    &params,
+                );
+                if let Some(rate_before) = rate_before {
+                    let change = new_rate.abs_diff(&rate_before);
+                    if change > params.max_commission_rate_change {
+                        output::fail_unless_forced(
+                            args.tx.force,
+                            format!(
+                                "The commission rate change {} from {} is \
+                                 greater than the maximum change per epoch {}.",
+                                change,
+                                rate_before,
+                                params.max_commission_rate_change
+                            ),
+                        );
+                    }
+                }
+            }
+            None => {
+                output::fail_unless_forced(
+                    args.tx.force,
+                    format!(
+                        "The address {} doesn't belong to any known validator \
+                         account.",
+                        validator
+                    ),
+                );
+            }
+        }
+    }
+
+    let data = pos::CommissionChange {
+        validator,
+        new_rate: args.rate,
+    };
+    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.validator)).await;
+}
+
+pub async fn submit_edit_validator(ctx: Context, args: args::TxEditValidator) {
+    let validator = ctx.get(&args.validator);
+    let tx_code = ctx.read_wasm(TX_EDIT_VALIDATOR_WASM);
+
+    if args.metadata == pos::ValidatorMetadata::default() {
+        output::fail(
+            "No validator metadata given. At least one of the fields must be \
+             set.",
+        )
+    }
+    if let Err(err) = args.metadata.validate() {
+        output::fail_unless_forced(args.tx.force, format!("{}", err));
+    }
+
+    if ledger_checks_enabled(&args.tx)
+        && !rpc::is_validator(&validator, args.tx.ledger_address.clone()).await
+    {
+        output::fail_unless_forced(
+            args.tx.force,
+            format!(
+                "The address {} doesn't belong to any known validator account.",
+                validator
+            ),
+        );
+    }
+
+    let data = pos::EditValidator {
+        validator,
+        metadata: args.metadata,
+    };
+    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.validator)).await;
+}
+
 /// Sign a transaction with a given signing key or public key of a given signer.
 /// If no explicit signer given, use the `default`. If no `default` is given,
 /// panics.
@@ -809,6 +1633,16 @@ async fn sign_tx(
         (tx.sign(&signing_key), signing_key)
     } else if let Some(signer) = args.signer.as_ref().or(default) {
         let signer = ctx.get(signer);
+        if args.raw_tx_out.is_some() {
+            if let Address::Established(_) = &signer {
+                output::fail(format!(
+                    "The public key of the established address {} cannot be \
+                     looked-up from the ledger for an offline transaction. \
+                     Please specify the signing key with `--signing-key`.",
+                    signer
+                ))
+            }
+        }
         let signing_key = signing::find_keypair(
             &mut ctx.wallet,
             &signer,
@@ -822,10 +1656,19 @@ async fn sign_tx(
              or the address from which to look up the signing key."
         );
     };
-    let epoch = rpc::query_epoch(args::Query {
-        ledger_address: args.ledger_address.clone(),
-    })
-    .await;
+    let epoch = if args.raw_tx_out.is_some() {
+        // The ledger is not available for an offline tx. The wrapper's epoch
+        // is not validated by the ledger, so we use the default.
+        Epoch::default()
+    } else {
+        rpc::query_epoch(args::Query {
+            ledger_address: args.ledger_address.clone(),
+        })
+        .await
+    };
+    if args.check {
+        check_tx_locally(args, &tx).await;
+    }
     let broadcast_data = if args.dry_run {
         TxBroadcastData::DryRun(tx)
     } else {
@@ -834,6 +1677,71 @@ async fn sign_tx(
     (ctx, broadcast_data)
 }
 
+/// Dry run the signed tx against the current state of the ledger before it's
+/// submitted and check that neither the mempool nor any of the triggered
+/// validity predicates rejects it. On a rejection, print the mempool error or
+/// the rejecting VPs with their errors and ask the user whether to proceed, or
+/// exit in the non-interactive mode.
+async fn check_tx_locally(args: &args::Tx, tx: &Tx) {
+    match rpc::query_dry_run_result(&args.ledger_address, tx.to_bytes()).await {
+        Ok(result) if result.is_accepted() => return,
+        Ok(DryRunResult {
+            mempool_error: Some(error),
+            ..
+        }) => {
+            eprintln!(
+                "The transaction would be rejected by the mempool: {}",
+                error
+            );
+        }
+        Ok(DryRunResult {
+            tx_result: Some(result),
+            ..
+        }) => {
+            for addr in &result.vps_result.rejected_vps {
+                eprintln!(
+                    "The transaction would be rejected by the validity \
+                     predicate of {}.",
+                    addr
+                );
+                for (_, error) in result
+                    .vps_result
+                    .errors
+                    .iter()
+                    .filter(|(err_addr, _)| err_addr == addr)
+                {
+                    eprintln!("{:4}Error: {}", "", error);
+                }
+            }
+        }
+        Ok(DryRunResult {
+            tx_result: None, ..
+        }) => {
+            eprintln!("The transaction would not be applied.");
+        }
+        Err(err) => {
+            eprintln!("The transaction would fail to be applied: {}", err);
+        }
+    }
+    if args.no_interactive {
+        output::fail("Aborting, the transaction has not been submitted.")
+    }
+    loop {
+        print!("Do you want to submit the transaction anyway? [y/N]: ");
+        io::stdout().flush().await.unwrap();
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer).await.unwrap();
+        match answer.trim() {
+            "y" | "Y" => return,
+            "" | "n" | "N" => {
+                println!("The transaction has not been submitted.");
+                safe_exit(1)
+            }
+            _ => println!("Invalid option, try again."),
+        }
+    }
+}
+
 /// Create a wrapper tx from a normal tx. Get the hash of the
 /// wrapper and its payload which is needed for monitoring its
 /// progress on chain.
@@ -844,12 +1752,13 @@ async fn sign_wrapper(
     tx: Tx,
     keypair: &common::SecretKey,
 ) -> TxBroadcastData {
+    if let Some(gas_price) = args.gas_price {
+        check_gas_price(args, gas_price, &ctx.get(&args.fee_token)).await;
+    }
+    let fee = wrapper_fee(ctx, args);
     let tx = {
         WrapperTx::new(
-            Fee {
-                amount: args.fee_amount,
-                token: ctx.get(&args.fee_token),
-            },
+            fee,
             keypair,
             epoch,
             args.gas_limit.clone(),
@@ -859,6 +1768,35 @@ async fn sign_wrapper(
         )
     };
 
+    let (wrapper_hash, decrypted_hash) = wrapper_tx_hashes(&tx);
+    TxBroadcastData::Wrapper {
+        tx: tx
+            .sign(keypair)
+            .expect("Wrapper tx signing keypair should be correct"),
+        wrapper_hash,
+        decrypted_hash,
+    }
+}
+
+/// The fee of a wrapper tx given by the tx args, either as the fee amount or
+/// as the gas price.
+fn wrapper_fee(ctx: &Context, args: &args::Tx) -> Fee {
+    let fee_token = ctx.get(&args.fee_token);
+    match args.gas_price {
+        Some(gas_price) => {
+            Fee::from_gas_price(gas_price, fee_token, &args.gas_limit)
+        }
+        None => Fee {
+            amount: args.fee_amount.unwrap_or_default(),
+            token: fee_token,
+            gas_price: None,
+        },
+    }
+}
+
+/// Get the hash of a wrapper tx and of its decrypted payload, if any, which
+/// are needed for monitoring its progress on chain.
+fn wrapper_tx_hashes(tx: &WrapperTx) -> (String, Option<String>) {
     // We use this to determine when the wrapper tx makes it on-chain
     let wrapper_hash = if !cfg!(feature = "ABCI") {
         hash_tx(&tx.try_to_vec().unwrap()).to_string()
@@ -872,12 +1810,47 @@ async fn sign_wrapper(
     } else {
         None
     };
-    TxBroadcastData::Wrapper {
-        tx: tx
-            .sign(keypair)
-            .expect("Wrapper tx signing keypair should be correct"),
-        wrapper_hash,
-        decrypted_hash,
+    (wrapper_hash, decrypted_hash)
+}
+
+/// The default number of seconds to wait for the epoch given with
+/// `--wait-for-epoch`
+const DEFAULT_WAIT_TIMEOUT: u64 = 600;
+
+/// If the tx args ask for it with `--wait-for-epoch`, wait until the epoch of
+/// the last committed block reaches the given epoch, polling the ledger every
+/// second. Exits with an error if the epoch isn't reached within the timeout.
+///
+/// This is called before a tx is built, so that its client checks see the
+/// state at the epoch in which it's submitted.
+pub async fn wait_for_epoch(args: &args::Tx) {
+    let target = match args.wait_for_epoch {
+        Some(target) => target,
+        None => return,
+    };
+    let timeout = std::time::Duration::from_secs(
+        args.wait_timeout.unwrap_or(DEFAULT_WAIT_TIMEOUT),
+    );
+    let query = args::Query {
+        ledger_address: args.ledger_address.clone(),
+    };
+    let start = Instant::now();
+    println!("Waiting for epoch {} to submit the transaction...", target);
+    loop {
+        let epoch = rpc::query_epoch(query.clone()).await;
+        if epoch >= target {
+            return;
+        }
+        if start.elapsed() >= timeout {
+            output::fail(format!(
+                "Timed out after {}s waiting for epoch {}, the last committed \
+                 epoch is {}.",
+                timeout.as_secs(),
+                target,
+                epoch
+            ))
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
     }
 }
 
@@ -900,9 +1873,31 @@ async fn process_tx(
     // let request_body = request.into_json();
     // println!("HTTP request body: {}", request_body);
 
-    if args.dry_run {
+    if let Some(raw_tx_out) = &args.raw_tx_out {
+        if let TxBroadcastData::Wrapper {
+            tx,
+            wrapper_hash,
+            decrypted_hash,
+        } = to_broadcast
+        {
+            write_raw_tx(raw_tx_out, &tx, &wrapper_hash, &decrypted_hash);
+            (ctx, vec![])
+        } else {
+            panic!(
+                "Expected a wrapper transaction, received a dry-run \
+                 transaction instead"
+            );
+        }
+    } else if args.dry_run {
         if let TxBroadcastData::DryRun(tx) = to_broadcast {
-            rpc::dry_run_tx(&args.ledger_address, tx.to_bytes()).await;
+            rpc::dry_run_tx(
+                &args.ledger_address,
+                tx.to_bytes(),
+                args.dry_run_at_height,
+                args.explain,
+                args.assert_max_gas,
+            )
+            .await;
             (ctx, vec![])
         } else {
             panic!(
@@ -916,21 +1911,133 @@ async fn process_tx(
         let result = if args.broadcast_only {
             Left(broadcast_tx(args.ledger_address.clone(), &to_broadcast).await)
         } else {
-            Right(submit_tx(args.ledger_address.clone(), to_broadcast).await)
+            Right(
+                submit_tx(
+                    args.ledger_address.clone(),
+                    to_broadcast,
+                    args.trace,
+                )
+                .await,
+            )
         };
         // Return result based on executed operation, otherwise deal with
         // the encountered errors uniformly
         match result {
-            Right(Ok(result)) => (ctx, result.initialized_accounts),
-            Left(Ok(_)) => (ctx, Vec::default()),
-            Right(Err(err)) | Left(Err(err)) => {
-                eprintln!(
-                    "Encountered error while broadcasting transaction: {}",
-                    err
-                );
-                safe_exit(1)
+            Right(Ok(result)) => {
+                print_tx_result(&result);
+                (ctx, result.initialized_accounts)
+            }
+            Left(Ok(response)) => {
+                print_broadcast_result(&response);
+                (ctx, Vec::default())
             }
+            Right(Err(err)) | Left(Err(err)) => output::fail(format!(
+                "Encountered error while broadcasting transaction: {}",
+                err
+            )),
+        }
+    }
+}
+
+/// Write a signed tx to the given file, so it can be broadcast later.
+fn write_raw_tx(
+    path: &Path,
+    tx: &Tx,
+    wrapper_hash: &str,
+    _decrypted_hash: &Option<String>,
+) {
+    if let Err(err) = std::fs::write(path, tx.to_bytes()) {
+        output::fail(format!(
+            "Failed to write the transaction to {}: {}",
+            path.to_string_lossy(),
+            err
+        ))
+    }
+    if output::is_json() {
+        output::print_ok(json!({
+            "path": path.to_string_lossy(),
+            "hash": wrapper_hash,
+        }));
+        return;
+    }
+    println!("Signed transaction written to {}", path.to_string_lossy());
+    #[cfg(not(feature = "ABCI"))]
+    {
+        println!("Wrapper transaction hash: {:?}", wrapper_hash);
+        println!("Inner transaction hash: {:?}", _decrypted_hash);
+    }
+    #[cfg(feature = "ABCI")]
+    println!("Transaction hash: {:?}", wrapper_hash);
+}
+
+/// In the JSON output mode, print the result of an applied tx, or exit with
+/// the ledger's error code if the tx has been rejected.
+fn print_tx_result(result: &TxResponse) {
+    if !output::is_json() {
+        return;
+    }
+    match result.code.parse::<u32>() {
+        Ok(0) => output::print_ok(
+            serde_json::to_value(result)
+                .expect("Serializing tx response shouldn't fail"),
+        ),
+        code => output::exit_with_error(code.ok(), &result.info),
+    }
+}
+
+/// Suggest raising the gas limit of a tx that ran out of gas.
+fn print_out_of_gas_hint(result: &TxResponse) {
+    if result.code == String::from(ErrorCodes::OutOfGas) {
+        eprintln!(
+            "The transaction ran out of gas. Try to submit it again with a \
+             higher --gas-limit."
+        );
+    }
+}
+
+/// In the JSON output mode, print the hash of a tx added to the mempool.
+fn print_broadcast_result(response: &Response) {
+    output::print_ok(json!({ "hash": response.hash.to_string() }));
+}
+
+/// Broadcast a signed tx previously written to a file with `--raw-tx-out`.
+pub async fn submit_raw_tx(args: args::BroadcastTx) {
+    let tx_bytes = std::fs::read(&args.tx_path).unwrap_or_else(|err| {
+        output::fail(format!(
+            "Failed to read the transaction from {}: {}",
+            args.tx_path.to_string_lossy(),
+            err
+        ))
+    });
+    let tx = Tx::try_from(&tx_bytes[..]).unwrap_or_else(|err| {
+        output::fail(format!("Failed to decode the transaction: {}", err))
+    });
+    let wrapper = match transaction::process_tx(tx.clone()) {
+        Ok(TxType::Wrapper(wrapper)) => wrapper,
+        _ => {
+            output::fail("The transaction is not a signed wrapper transaction.")
         }
+    };
+    let (wrapper_hash, decrypted_hash) = wrapper_tx_hashes(&wrapper);
+    let to_broadcast = TxBroadcastData::Wrapper {
+        tx,
+        wrapper_hash,
+        decrypted_hash,
+    };
+    let result = if args.broadcast_only {
+        broadcast_tx(args.ledger_address, &to_broadcast)
+            .await
+            .map(|response| print_broadcast_result(&response))
+    } else {
+        submit_tx(args.ledger_address, to_broadcast, false)
+            .await
+            .map(|result| print_tx_result(&result))
+    };
+    if let Err(err) = result {
+        output::fail(format!(
+            "Encountered error while broadcasting transaction: {}",
+            err
+        ))
     }
 }
 
@@ -943,11 +2050,13 @@ async fn save_initialized_accounts(
     let len = initialized_accounts.len();
     if len != 0 {
         // Store newly initialized account addresses in the wallet
-        println!(
-            "The transaction initialized {} new account{}",
-            len,
-            if len == 1 { "" } else { "s" }
-        );
+        if !output::is_json() {
+            println!(
+                "The transaction initialized {} new account{}",
+                len,
+                if len == 1 { "" } else { "s" }
+            );
+        }
         // Store newly initialized account addresses in the wallet
         let wallet = &mut ctx.wallet;
         for (ix, address) in initialized_accounts.iter().enumerate() {
@@ -975,6 +2084,9 @@ async fn save_initialized_accounts(
             };
             let alias = alias.into_owned();
             let added = wallet.add_address(alias.clone(), address.clone());
+            if output::is_json() {
+                continue;
+            }
             match added {
                 Some(new_alias) if new_alias != encoded => {
                     println!(
@@ -987,7 +2099,7 @@ async fn save_initialized_accounts(
         }
         if !args.dry_run {
             wallet.save().unwrap_or_else(|err| eprintln!("{}", err));
-        } else {
+        } else if !output::is_json() {
             println!("Transaction dry run. No addresses have been saved.")
         }
     }
@@ -1022,6 +2134,9 @@ pub async fn broadcast_tx(
     wrapper_tx_subscription.close();
 
     if response.code == 0.into() {
+        if output::is_json() {
+            return Ok(response);
+        }
         println!("Transaction added to mempool: {:?}", response);
         // Print the transaction identifiers to enable the extraction of
         // acceptance/application results later
@@ -1045,11 +2160,15 @@ pub async fn broadcast_tx(
 /// 2. The tx with encrypted payload has been included on the blockchain
 /// 3. The decrypted payload of the tx has been included on the blockchain.
 ///
-/// In the case of errors in any of those stages, an error message is returned
+/// In the case of errors in any of those stages, an error message is returned.
+///
+/// With `trace`, each stage reached by the tx is printed with a timestamp.
 pub async fn submit_tx(
     address: TendermintAddress,
     to_broadcast: TxBroadcastData,
+    trace: bool,
 ) -> Result<TxResponse, Error> {
+    let mut trace = TxTrace::new(trace);
     let (_, wrapper_hash, _decrypted_hash) = match &to_broadcast {
         TxBroadcastData::Wrapper {
             tx,
@@ -1089,30 +2208,63 @@ pub async fn submit_tx(
         decrypted_tx_subscription
     };
     // Broadcast the supplied transaction
-    broadcast_tx(address, &to_broadcast).await?;
+    trace.reached("Broadcast", format!("sent to {}", address));
+    broadcast_tx(address.clone(), &to_broadcast)
+        .await
+        .map_err(|err| trace.halted(err))?;
+    if trace.enabled {
+        let height = query_last_committed_block(&address).await.0;
+        trace.reached(
+            "Mempool",
+            format!("accepted by CheckTx at height {}", height),
+        );
+    }
 
     #[cfg(not(feature = "ABCI"))]
     let parsed = {
         let parsed = parse(
-            wrapper_tx_subscription.receive_response()?,
+            wrapper_tx_subscription
+                .receive_response()
+                .map_err(|err| trace.halted(err))?,
             TmEventType::Accepted,
             wrapper_hash,
         );
-        println!(
-            "Transaction accepted with result: {}",
-            serde_json::to_string_pretty(&parsed).unwrap()
+        if !output::is_json() {
+            println!(
+                "Transaction accepted with result: {}",
+                serde_json::to_string_pretty(&parsed).unwrap()
+            );
+        }
+        trace.reached(
+            "Delivered",
+            format!(
+                "wrapper included in block {} with code {}",
+                parsed.height, parsed.code
+            ),
         );
         // The transaction is now on chain. We wait for it to be decrypted
         // and applied
         if parsed.code == 0.to_string() {
             let parsed = parse(
-                decrypted_tx_subscription.receive_response()?,
+                decrypted_tx_subscription
+                    .receive_response()
+                    .map_err(|err| trace.halted(err))?,
                 TmEventType::Applied,
                 _decrypted_hash.as_ref().unwrap(),
             );
-            println!(
-                "Transaction applied with result: {}",
-                serde_json::to_string_pretty(&parsed).unwrap()
+            if !output::is_json() {
+                println!(
+                    "Transaction applied with result: {}",
+                    serde_json::to_string_pretty(&parsed).unwrap()
+                );
+                print_out_of_gas_hint(&parsed);
+            }
+            trace.reached(
+                "Applied",
+                format!(
+                    "inner transaction applied in block {} with code {}",
+                    parsed.height, parsed.code
+                ),
             );
             Ok(parsed)
         } else {
@@ -1123,16 +2275,34 @@ pub async fn submit_tx(
     #[cfg(feature = "ABCI")]
     let parsed = {
         let parsed = TxResponse::find_tx(
-            wrapper_tx_subscription.receive_response()?,
+            wrapper_tx_subscription
+                .receive_response()
+                .map_err(|err| trace.halted(err))?,
             wrapper_hash,
         );
-        println!(
-            "Transaction applied with result: {}",
-            serde_json::to_string_pretty(&parsed).unwrap()
+        if !output::is_json() {
+            println!(
+                "Transaction applied with result: {}",
+                serde_json::to_string_pretty(&parsed).unwrap()
+            );
+            print_out_of_gas_hint(&parsed);
+        }
+        trace.reached(
+            "Applied",
+            format!(
+                "applied in block {} with code {}",
+                parsed.height, parsed.code
+            ),
         );
         Ok(parsed)
     };
 
+    if trace.enabled {
+        if let Ok(parsed) = &parsed {
+            trace_outcome(&mut trace, &address, parsed).await;
+        }
+    }
+
     wrapper_tx_subscription.unsubscribe()?;
     wrapper_tx_subscription.close();
     #[cfg(not(feature = "ABCI"))]
@@ -1144,6 +2314,109 @@ pub async fn submit_tx(
     parsed
 }
 
+/// Report the commitment of the block with the given tx and the validity
+/// predicates' verdicts.
+async fn trace_outcome(
+    trace: &mut TxTrace,
+    address: &TendermintAddress,
+    response: &TxResponse,
+) {
+    let (height, app_hash) = query_last_committed_block(address).await;
+    if height == response.height {
+        trace.reached(
+            "Committed",
+            format!("block {} committed with app hash {}", height, app_hash),
+        );
+    } else {
+        trace.reached(
+            "Committed",
+            format!(
+                "block {} committed, the last committed block {} has app hash \
+                 {}",
+                response.height, height, app_hash
+            ),
+        );
+    }
+    // The tx info ends with the VPs result, e.g. "VPs result: Accepted: ..."
+    let verdicts = response
+        .info
+        .split("VPs result: ")
+        .nth(1)
+        .unwrap_or(response.info.as_str())
+        .trim();
+    trace.reached("Validity predicates", verdicts);
+    if response.code != 0.to_string() {
+        trace.halted(format!(
+            "the transaction was rejected with code {}",
+            response.code
+        ));
+    }
+}
+
+/// Query the height and the app hash of the last committed block.
+async fn query_last_committed_block(
+    address: &TendermintAddress,
+) -> (String, String) {
+    let client = HttpClient::new(address.clone()).unwrap();
+    match client.abci_info().await {
+        Ok(info) => (
+            info.last_block_height.to_string(),
+            hex::encode_upper(&info.last_block_app_hash),
+        ),
+        Err(err) => ("unknown".to_string(), format!("unknown ({})", err)),
+    }
+}
+
+/// A timeline of the stages reached by a submitted transaction. When enabled,
+/// each stage is printed with a timestamp as soon as it's reached, so that it's
+/// clear at which stage a transaction that fails or gets stuck halted.
+struct TxTrace {
+    enabled: bool,
+    start: Instant,
+    /// The last stage reached by the transaction, if any
+    last_stage: Option<&'static str>,
+}
+
+impl TxTrace {
+    fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            start: Instant::now(),
+            last_stage: None,
+        }
+    }
+
+    /// Record that the transaction has reached the given stage.
+    fn reached(&mut self, stage: &'static str, details: impl Display) {
+        self.last_stage = Some(stage);
+        if self.enabled {
+            println!("{} {}: {}", self.timestamp(), stage, details);
+        }
+    }
+
+    /// Report that the transaction couldn't go past the last reached stage.
+    /// Returns the given error to be propagated.
+    fn halted<E: Display>(&self, err: E) -> E {
+        if self.enabled {
+            println!(
+                "{} Halted after stage {}: {}",
+                self.timestamp(),
+                self.last_stage.unwrap_or("none"),
+                err
+            );
+        }
+        err
+    }
+
+    fn timestamp(&self) -> String {
+        format!(
+            "[trace {} +{:.3}s]",
+            DateTimeUtc::now().0.to_rfc3339(),
+            self.start.elapsed().as_secs_f64()
+        )
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct TxResponse {
     pub info: String,
@@ -1153,6 +2426,9 @@ pub struct TxResponse {
     pub code: String,
     pub gas_used: String,
     pub initialized_accounts: Vec<Address>,
+    /// Whether the applied tx changed the state. An accepted tx that didn't
+    /// may have been a redundant submission.
+    pub state_changed: bool,
 }
 
 /// Parse the JSON payload received from a subscription
@@ -1191,6 +2467,10 @@ fn parse(
         Some(values) => serde_json::from_str(&values).unwrap(),
         _ => vec![],
     };
+    let state_changed = event
+        .take("state_changed")
+        .map(|value| value == "true")
+        .unwrap_or_default();
     TxResponse {
         info,
         log,
@@ -1199,6 +2479,7 @@ fn parse(
         code,
         gas_used,
         initialized_accounts,
+        state_changed,
     }
 }
 
@@ -1223,11 +2504,10 @@ impl TxResponse {
                     index += 1;
                 }
             } else {
-                eprintln!(
+                output::fail(format!(
                     "Couldn't find tx with hash {} in the event string {}",
                     tx_hash, json
-                );
-                safe_exit(1)
+                ))
             }
         };
         let info =
@@ -1266,6 +2546,14 @@ impl TxResponse {
             }
             _ => vec![],
         };
+        let state_changed = selector(&format!(
+            "$.events.['{}.state_changed'][{}]",
+            evt_key, index
+        ))
+        .ok()
+        .and_then(|values| values.first().cloned())
+        .map(|value| value == "true")
+        .unwrap_or_default();
         TxResponse {
             info: serde_json::from_value(info[0].clone()).unwrap(),
             log: serde_json::from_value(log[0].clone()).unwrap(),
@@ -1274,6 +2562,7 @@ impl TxResponse {
             code: serde_json::from_value(code[0].clone()).unwrap(),
             gas_used: serde_json::from_value(gas_used[0].clone()).unwrap(),
             initialized_accounts,
+            state_changed,
         }
     }
 }
@@ -28,7 +28,7 @@ use tendermint_config_abci::net::Address as TendermintAddress;
 use tendermint_stable::node::Id as TendermintNodeId;
 
 use crate::cli::context::ENV_VAR_WASM_DIR;
-use crate::cli::{self, args};
+use crate::cli::{self, args, output};
 use crate::config::genesis::genesis_config;
 use crate::config::global::GlobalConfig;
 use crate::config::{
@@ -64,11 +64,10 @@ pub async fn join_network(
     });
     if let Some(wasm_dir) = wasm_dir.as_ref() {
         if wasm_dir.is_absolute() {
-            eprintln!(
+            output::fail(
                 "The arg `--wasm-dir` cannot be an absolute path. It is \
-                 nested inside the chain directory."
+                 nested inside the chain directory.",
             );
-            cli::safe_exit(1);
         }
     }
     if let Err(err) = fs::canonicalize(base_dir).await {
@@ -82,8 +81,10 @@ pub async fn join_network(
             .await
             .is_ok()
         {
-            eprintln!("The chain directory for {} already exists.", chain_id);
-            cli::safe_exit(1);
+            output::fail(format!(
+                "The chain directory for {} already exists.",
+                chain_id
+            ));
         }
     }
     let base_dir_full = fs::canonicalize(base_dir).await.unwrap();
@@ -97,8 +98,7 @@ pub async fn join_network(
     let release = match download_file(release_url).await {
         Ok(contents) => contents,
         Err(error) => {
-            eprintln!("Error downloading release: {}", error);
-            cli::safe_exit(1);
+            output::fail(format!("Error downloading release: {}", error));
         }
     };
 
@@ -279,7 +279,7 @@ pub fn init_network(
 
     // We need a temporary copy to be able to use this inside the validator
     // loop, which has mutable borrow on the config.
-    let established_accounts = config.established.clone();
+    let matchmaker_accounts = config.matchmaker.clone();
     // Iterate over each validator, generating keys and addresses
     config.validator.iter_mut().for_each(|(name, config)| {
         let validator_dir = accounts_dir.join(name);
@@ -437,10 +437,9 @@ pub fn init_network(
         ) {
             (Some(account), Some(mm_code), Some(tx_code)) => {
                 if config.intent_gossip_seed.unwrap_or_default() {
-                    eprintln!("A bootstrap node cannot run matchmakers");
-                    cli::safe_exit(1)
+                    output::fail("A bootstrap node cannot run matchmakers")
                 }
-                match established_accounts.as_ref().and_then(|e| e.get(account))
+                match matchmaker_accounts.as_ref().and_then(|m| m.get(account))
                 {
                     Some(matchmaker) => {
                         let mut matchmaker = matchmaker.clone();
@@ -461,25 +460,19 @@ pub fn init_network(
                         matchmaker_configs
                             .insert(name.clone(), matchmaker_config);
                     }
-                    None => {
-                        eprintln!(
-                            "Misconfigured validator's matchmaker. No \
-                             established account with alias {} found",
-                            account
-                        );
-                        cli::safe_exit(1)
-                    }
+                    None => output::fail(format!(
+                        "Misconfigured validator's matchmaker. No matchmaker \
+                         account with alias {} found",
+                        account
+                    )),
                 }
             }
             (None, None, None) => {}
-            _ => {
-                eprintln!(
-                    "Misconfigured validator's matchmaker. \
-                     `matchmaker_account`, `matchmaker_code` and \
-                     `matchmaker_tx` must be all or none present."
-                );
-                cli::safe_exit(1)
-            }
+            _ => output::fail(
+                "Misconfigured validator's matchmaker. `matchmaker_account`, \
+                 `matchmaker_code` and `matchmaker_tx` must be all or none \
+                 present.",
+            ),
         }
 
         // Store the gossip config
@@ -501,7 +494,10 @@ pub fn init_network(
     // Create a wallet for all accounts other than validators
     let mut wallet =
         Wallet::load_or_new(&accounts_dir.join(NET_OTHER_ACCOUNTS_DIR));
-    if let Some(established) = &mut config.established {
+    for established in [&mut config.established, &mut config.matchmaker]
+        .into_iter()
+        .flatten()
+    {
         established.iter_mut().for_each(|(name, config)| {
             match validator_owned_accounts.get(name) {
                 Some(validator_owned) => {
@@ -561,6 +557,7 @@ pub fn init_network(
 
     // Generate the chain ID first
     let genesis = genesis_config::load_genesis_config(config_clean.clone());
+    let address_hrp = config.address_hrp.clone();
     let genesis_bytes = genesis.try_to_vec().unwrap();
     let chain_id = ChainId::from_genesis(chain_id_prefix, genesis_bytes);
     let chain_dir = global_args.base_dir.join(chain_id.as_str());
@@ -581,11 +578,10 @@ pub fn init_network(
         })
         .unwrap_or_else(|| config::DEFAULT_WASM_DIR.into());
     if wasm_dir.is_absolute() {
-        eprintln!(
+        output::fail(
             "The arg `--wasm-dir` cannot be an absolute path. It is nested \
-             inside the chain directory."
+             inside the chain directory.",
         );
-        cli::safe_exit(1);
     }
 
     // Write the genesis file
@@ -663,6 +659,7 @@ pub fn init_network(
 
             // Configure the ledger
             config.ledger.genesis_time = genesis.genesis_time.into();
+            config.ledger.address_hrp = address_hrp.clone();
             // In `config::Ledger`'s `base_dir`, `chain_id` and `tendermint`,
             // the paths are prefixed with `validator_dir` given in the first
             // parameter. We need to remove this prefix, because
@@ -755,6 +752,7 @@ pub fn init_network(
             .set_ip(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)));
     }
     config.ledger.genesis_time = genesis.genesis_time.into();
+    config.ledger.address_hrp = address_hrp;
     config.intent_gossiper.seed_peers = seed_peers;
     config
         .write(&global_args.base_dir, &chain_id, true)
@@ -865,6 +863,43 @@ pub fn init_genesis_validator(
     );
 }
 
+/// Validate a genesis configuration file offline and print a report of every
+/// problem found in it. Exits with an error code if there are any problems.
+pub fn verify_genesis(
+    global_args: args::Global,
+    args::VerifyGenesis { path }: args::VerifyGenesis,
+) {
+    let wasm_dir = global_args
+        .wasm_dir
+        .or_else(|| env::var(ENV_VAR_WASM_DIR).ok().map(PathBuf::from))
+        .unwrap_or_else(|| config::DEFAULT_WASM_DIR.into());
+    let config: genesis_config::GenesisConfig = match fs::read_to_string(&path)
+        .map_err(|err| err.to_string())
+        .and_then(|file| genesis_config::parse_genesis_config(&file))
+    {
+        Ok(config) => config,
+        Err(err) => output::fail(format!(
+            "Failed to read genesis file {}: {}",
+            path.to_string_lossy(),
+            err
+        )),
+    };
+    let errors = genesis_config::validate_genesis_config(&config, &wasm_dir);
+    if errors.is_empty() {
+        println!("Genesis file {} is valid.", path.to_string_lossy());
+    } else {
+        let mut message = format!(
+            "Genesis file {} is invalid, found {} problem(s):",
+            path.to_string_lossy(),
+            errors.len()
+        );
+        for err in errors {
+            message.push_str(&format!("\n  - {}", err));
+        }
+        output::fail(message)
+    }
+}
+
 /// Initialize genesis validator's address, staking reward address,
 /// consensus key, validator account key and staking rewards key and use
 /// it in the ledger's node.
@@ -954,6 +989,7 @@ fn init_genesis_validator_aux(
             tokens: token::Amount::whole(200_000),
             consensus_key: consensus_key.ref_to(),
             staking_reward_key: rewards_key.ref_to(),
+            commission_rate: anoma::ledger::pos::types::BasisPoints::new(0),
         },
         account_key: validator_key.ref_to(),
         protocol_key,
@@ -20,27 +20,36 @@ use derivative::Derivative;
 /// Genesis configuration file format
 pub mod genesis_config {
     use std::array::TryFromSliceError;
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, BTreeSet, HashMap};
     use std::convert::TryInto;
     use std::path::Path;
     use std::str::FromStr;
 
+    use anoma::ledger::gas::StorageGasCosts;
     use anoma::ledger::governance::parameters::GovParams;
-    use anoma::ledger::parameters::{EpochDuration, Parameters};
+    use anoma::ledger::parameters::{
+        EpochDuration, FeeExemption, FeeRefundPolicy, FeeTokenRates,
+        Parameters, TxOrdering, TxSizeLimits,
+    };
     use anoma::ledger::pos::types::BasisPoints;
     use anoma::ledger::pos::{GenesisValidator, PosParams};
-    use anoma::types::address::Address;
+    use anoma::types::address::{self, Address};
+    use anoma::types::governance::ProposalContentLimits;
     use anoma::types::key::dkg_session_keys::DkgPublicKey;
     use anoma::types::key::*;
-    use anoma::types::time::Rfc3339String;
+    use anoma::types::time::{DateTimeUtc, Rfc3339String};
     use anoma::types::{storage, token};
+    use anoma::vm::validate_untrusted_wasm;
+    use anoma::vm::wasm::run::prepare_wasm_code;
     use hex;
     use serde::{Deserialize, Serialize};
+    use sha2::{Digest, Sha256};
+    use thiserror::Error;
 
     use super::{
         EstablishedAccount, Genesis, ImplicitAccount, TokenAccount, Validator,
     };
-    use crate::cli;
+    use crate::{cli, wasm_loader};
 
     #[derive(Clone, Debug, Deserialize, Serialize)]
     pub struct HexString(pub String);
@@ -99,6 +108,9 @@ pub mod genesis_config {
     pub struct GenesisConfig {
         // Genesis timestamp
         pub genesis_time: Rfc3339String,
+        // Human-readable part of the Bech32m encoded addresses of the chain.
+        // When not set, defaults to `address::DEFAULT_ADDRESS_HRP`.
+        pub address_hrp: Option<String>,
         // Initial validator set
         pub validator: HashMap<String, ValidatorConfig>,
         // Token accounts present at genesis
@@ -107,6 +119,9 @@ pub mod genesis_config {
         pub established: Option<HashMap<String, EstablishedAccountConfig>>,
         // Implicit accounts present at genesis
         pub implicit: Option<HashMap<String, ImplicitAccountConfig>>,
+        // Matchmaker accounts authorized to settle the matched intents. They
+        // are established accounts, but they must have a public key.
+        pub matchmaker: Option<HashMap<String, EstablishedAccountConfig>>,
         // Protocol parameters
         pub parameters: ParametersConfig,
         // PoS parameters
@@ -134,6 +149,9 @@ pub mod genesis_config {
         // Minimum number of epoch between end and grace epoch
         // XXX: u64 doesn't work with toml-rs!
         pub min_grace_epoch: u64,
+        // Bounds of the number of characters of the proposal content fields
+        // (default: `ProposalContentLimits::default()`)
+        pub proposal_content_limits: Option<ProposalContentLimits>,
     }
 
     #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -159,6 +177,9 @@ pub mod genesis_config {
         // Unstaked balance at genesis.
         // XXX: u64 doesn't work with toml-rs!
         pub non_staked_balance: u64,
+        // Commission rate taken from the validator's rewards (in basis
+        // points). (default: 0)
+        pub commission_rate: Option<u64>,
         // Filename of validator VP. (default: default validator VP)
         pub validator_vp: Option<String>,
         // Filename of staking reward account VP. (default: user VP)
@@ -186,6 +207,16 @@ pub mod genesis_config {
         // Initial balances held by accounts defined elsewhere.
         // XXX: u64 doesn't work with toml-rs!
         pub balances: Option<HashMap<String, u64>>,
+        // Alias or address of the account allowed to freeze and unfreeze
+        // the token's transfers (default: none).
+        pub admin: Option<String>,
+        // Alias or address of the only account allowed to mint the token
+        // after the genesis (default: none, the supply is fixed).
+        pub minter: Option<String>,
+        // The maximum total amount of whole tokens that the minter may mint
+        // (default: not capped).
+        // XXX: u64 doesn't work with toml-rs!
+        pub mint_cap: Option<u64>,
     }
 
     #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -223,6 +254,47 @@ pub mod genesis_config {
         // Hashes of whitelisted txs array. `None` value or an empty array
         // disables whitelisting.
         pub tx_whitelist: Option<Vec<String>>,
+        // The order in which the txs of a block are applied, either
+        // "proposed" or "fee_then_hash". `None` value applies the txs in the
+        // proposed order.
+        pub tx_ordering: Option<TxOrdering>,
+        // Maximum size of a tx's WASM code (in bytes). `None` value doesn't
+        // limit the size.
+        pub max_tx_code_size: Option<u64>,
+        // Maximum size of a tx's data (in bytes). `None` value doesn't limit
+        // the size.
+        pub max_tx_data_size: Option<u64>,
+        // Addresses of the fee payers exempt from the wrapper tx fee. `None`
+        // value or an empty array exempts no one.
+        pub fee_exempt_addresses: Option<Vec<Address>>,
+        // Whether the gas of the exempt txs is also not counted towards the
+        // block's gas. `None` value meters their gas.
+        pub fee_exempt_gas: Option<bool>,
+        // The conversion rates of the tokens other than the native token in
+        // which the fees can be paid, by the token's address. A rate is the
+        // amount of the native token that one whole unit of the token is
+        // worth. `None` value allows to pay fees only in the native token.
+        pub fee_token_rates: Option<BTreeMap<Address, token::Amount>>,
+        // Whether the wrapper fee of a tx rejected by its VPs is refunded,
+        // either "always-charge" or "refund-on-vp-rejection". `None` value
+        // always charges the fee.
+        pub fee_refund_policy: Option<FeeRefundPolicy>,
+        // The gas per byte of a key and its value read from the storage by a
+        // tx. `None` value applies the default cost.
+        pub storage_read_gas_per_byte: Option<u64>,
+        // The gas per byte of a key and its value written to the storage by
+        // a tx. `None` value applies the default cost.
+        pub storage_write_gas_per_byte: Option<u64>,
+        // The gas refunded per byte of a key and its value deleted from the
+        // storage by a tx. `None` value applies the default refund.
+        pub storage_delete_refund_per_byte: Option<u64>,
+        // Size of the changes of a block held in memory (in bytes), above
+        // which they're staged in the DB until the block is committed.
+        // `None` value holds all the changes in memory.
+        pub write_log_flush_threshold: Option<u64>,
+        // The base fee per unit of gas of the first block, below which the
+        // base fee never falls. `None` value starts the base fee from zero.
+        pub min_base_fee: Option<token::Amount>,
     }
 
     #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -253,6 +325,37 @@ pub mod genesis_config {
         // light client attack (in basis points).
         // XXX: u64 doesn't work with toml-rs!
         pub light_client_attack_slash_rate: u64,
+        // How many epochs after a committed fault a slashed validator is
+        // jailed for.
+        // XXX: u64 doesn't work with toml-rs!
+        pub jail_duration: u64,
+        // Minimum amount of tokens that a validator must have self-bonded to
+        // be able to receive bonds and to become an active validator.
+        // XXX: u64 doesn't work with toml-rs!
+        pub min_validator_self_bond: u64,
+        // Maximum amount of tokens bonded to a single validator from both
+        // its self-bonds and delegations, if any.
+        // XXX: u64 doesn't work with toml-rs!
+        pub max_validator_stake: Option<u64>,
+        // Maximum change of a validator's commission rate from one epoch to
+        // the next (in basis points).
+        // XXX: u64 doesn't work with toml-rs!
+        pub max_commission_rate_change: u64,
+        // The number of the most recent blocks over which validators'
+        // liveness is tracked.
+        // XXX: u64 doesn't work with toml-rs!
+        pub liveness_window_len: u64,
+        // Maximum number of blocks within the liveness window that a
+        // validator may fail to sign before it's jailed.
+        // XXX: u64 doesn't work with toml-rs!
+        pub liveness_max_missed_blocks: u64,
+        // Portion of a validator's stake that should be slashed when it's
+        // jailed for missing too many blocks (in basis points).
+        // XXX: u64 doesn't work with toml-rs!
+        pub liveness_slash_rate: u64,
+        // Whether a validator slashed for a duplicate vote is tombstoned,
+        // i.e. it can never be unjailed.
+        pub tombstone_on_double_sign: bool,
     }
 
     #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -291,6 +394,9 @@ pub mod genesis_config {
                     .unwrap()
                     .to_public_key()
                     .unwrap(),
+                commission_rate: BasisPoints::new(
+                    config.commission_rate.unwrap_or_default(),
+                ),
             },
             account_key: config
                 .account_public_key
@@ -340,6 +446,49 @@ pub mod genesis_config {
     ) -> TokenAccount {
         let token_vp_name = config.vp.as_ref().unwrap();
         let token_vp_config = wasm.get(token_vp_name).unwrap();
+        let resolve_address = |alias_or_address: &String| -> Address {
+            match Address::decode(alias_or_address) {
+                Ok(address) => address,
+                Err(decode_err) => {
+                    if let Some(alias) =
+                        alias_or_address.strip_suffix(".public_key")
+                    {
+                        if let Some(established) =
+                            established_accounts.get(alias)
+                        {
+                            established.public_key.as_ref().unwrap().into()
+                        } else if let Some(validator) = validators.get(alias) {
+                            (&validator.account_key).into()
+                        } else {
+                            eprintln!(
+                                "No established or validator account with \
+                                 alias {} found",
+                                alias
+                            );
+                            cli::safe_exit(1)
+                        }
+                    } else if let Some(established) =
+                        established_accounts.get(alias_or_address)
+                    {
+                        established.address.clone()
+                    } else if let Some(validator) =
+                        validators.get(alias_or_address)
+                    {
+                        validator.pos_data.address.clone()
+                    } else if let Some(implicit) =
+                        implicit_accounts.get(alias_or_address)
+                    {
+                        (&implicit.public_key).into()
+                    } else {
+                        eprintln!(
+                            "{} is unknown alias and not a valid address: {}",
+                            alias_or_address, decode_err
+                        );
+                        cli::safe_exit(1)
+                    }
+                }
+            }
+        };
 
         TokenAccount {
             address: Address::decode(&config.address.as_ref().unwrap())
@@ -361,58 +510,14 @@ pub mod genesis_config {
                 .iter()
                 .map(|(alias_or_address, amount)| {
                     (
-                        match Address::decode(&alias_or_address) {
-                            Ok(address) => address,
-                            Err(decode_err) => {
-                                if let Some(alias) =
-                                    alias_or_address.strip_suffix(".public_key")
-                                {
-                                    if let Some(established) =
-                                        established_accounts.get(alias)
-                                    {
-                                        established
-                                            .public_key
-                                            .as_ref()
-                                            .unwrap()
-                                            .into()
-                                    } else if let Some(validator) =
-                                        validators.get(alias)
-                                    {
-                                        (&validator.account_key).into()
-                                    } else {
-                                        eprintln!(
-                                            "No established or validator \
-                                             account with alias {} found",
-                                            alias
-                                        );
-                                        cli::safe_exit(1)
-                                    }
-                                } else if let Some(established) =
-                                    established_accounts.get(alias_or_address)
-                                {
-                                    established.address.clone()
-                                } else if let Some(validator) =
-                                    validators.get(alias_or_address)
-                                {
-                                    validator.pos_data.address.clone()
-                                } else if let Some(implicit) =
-                                    implicit_accounts.get(alias_or_address)
-                                {
-                                    (&implicit.public_key).into()
-                                } else {
-                                    eprintln!(
-                                        "{} is unknown alias and not a valid \
-                                         address: {}",
-                                        alias_or_address, decode_err
-                                    );
-                                    cli::safe_exit(1)
-                                }
-                            }
-                        },
+                        resolve_address(alias_or_address),
                         token::Amount::whole(*amount),
                     )
                 })
                 .collect(),
+            admin: config.admin.as_ref().map(resolve_address),
+            minter: config.minter.as_ref().map(resolve_address),
+            mint_cap: config.mint_cap.map(token::Amount::whole),
         }
     }
 
@@ -473,12 +578,28 @@ pub mod genesis_config {
             .iter()
             .map(|(name, cfg)| (name.clone(), load_validator(cfg, &wasms)))
             .collect();
-        let established_accounts: HashMap<String, EstablishedAccount> = config
-            .established
+        let mut established_accounts: HashMap<String, EstablishedAccount> =
+            config
+                .established
+                .unwrap_or_default()
+                .iter()
+                .map(|(name, cfg)| {
+                    (name.clone(), load_established(cfg, &wasms))
+                })
+                .collect();
+        let matchmaker_accounts: HashMap<String, EstablishedAccount> = config
+            .matchmaker
             .unwrap_or_default()
             .iter()
             .map(|(name, cfg)| (name.clone(), load_established(cfg, &wasms)))
             .collect();
+        let matchmakers = matchmaker_accounts
+            .values()
+            .map(|account| account.address.clone())
+            .collect();
+        // The matchmaker accounts are initialized like the other established
+        // accounts
+        established_accounts.extend(matchmaker_accounts);
         let implicit_accounts: HashMap<String, ImplicitAccount> = config
             .implicit
             .unwrap_or_default()
@@ -514,6 +635,51 @@ pub mod genesis_config {
             .into(),
             vp_whitelist: config.parameters.vp_whitelist.unwrap_or_default(),
             tx_whitelist: config.parameters.tx_whitelist.unwrap_or_default(),
+            tx_ordering: config.parameters.tx_ordering.unwrap_or_default(),
+            tx_size_limits: TxSizeLimits {
+                max_code_size: config.parameters.max_tx_code_size,
+                max_data_size: config.parameters.max_tx_data_size,
+            },
+            fee_exemption: FeeExemption {
+                addresses: config
+                    .parameters
+                    .fee_exempt_addresses
+                    .unwrap_or_default()
+                    .into_iter()
+                    .collect(),
+                exempt_gas: config
+                    .parameters
+                    .fee_exempt_gas
+                    .unwrap_or_default(),
+            },
+            fee_token_rates: FeeTokenRates {
+                rates: config.parameters.fee_token_rates.unwrap_or_default(),
+            },
+            fee_refund_policy: config
+                .parameters
+                .fee_refund_policy
+                .unwrap_or_default(),
+            storage_gas_costs: {
+                let default = StorageGasCosts::default();
+                StorageGasCosts {
+                    read_gas_per_byte: config
+                        .parameters
+                        .storage_read_gas_per_byte
+                        .unwrap_or(default.read_gas_per_byte),
+                    write_gas_per_byte: config
+                        .parameters
+                        .storage_write_gas_per_byte
+                        .unwrap_or(default.write_gas_per_byte),
+                    delete_refund_per_byte: config
+                        .parameters
+                        .storage_delete_refund_per_byte
+                        .unwrap_or(default.delete_refund_per_byte),
+                }
+            },
+            write_log_flush_threshold: config
+                .parameters
+                .write_log_flush_threshold,
+            min_base_fee: config.parameters.min_base_fee.unwrap_or_default(),
         };
 
         let gov_params = GovParams {
@@ -522,6 +688,10 @@ pub mod genesis_config {
             min_proposal_period: 3,
             max_proposal_content_size: 10_000,
             min_proposal_grace_epochs: 6,
+            proposal_content_limits: config
+                .gov_params
+                .proposal_content_limits
+                .unwrap_or_default(),
         };
 
         let pos_params = PosParams {
@@ -539,6 +709,28 @@ pub mod genesis_config {
             light_client_attack_slash_rate: BasisPoints::new(
                 config.pos_params.light_client_attack_slash_rate,
             ),
+            jail_duration: config.pos_params.jail_duration,
+            min_validator_self_bond: token::Amount::whole(
+                config.pos_params.min_validator_self_bond,
+            )
+            .into(),
+            max_validator_stake: config
+                .pos_params
+                .max_validator_stake
+                .map(|stake| token::Amount::whole(stake).into()),
+            max_commission_rate_change: BasisPoints::new(
+                config.pos_params.max_commission_rate_change,
+            ),
+            liveness_window_len: config.pos_params.liveness_window_len,
+            liveness_max_missed_blocks: config
+                .pos_params
+                .liveness_max_missed_blocks,
+            liveness_slash_rate: BasisPoints::new(
+                config.pos_params.liveness_slash_rate,
+            ),
+            tombstone_on_double_sign: config
+                .pos_params
+                .tombstone_on_double_sign,
         };
 
         let mut genesis = Genesis {
@@ -547,6 +739,7 @@ pub mod genesis_config {
             token_accounts,
             established_accounts: established_accounts.into_values().collect(),
             implicit_accounts: implicit_accounts.into_values().collect(),
+            matchmakers,
             parameters,
             pos_params,
             gov_params,
@@ -557,7 +750,28 @@ pub mod genesis_config {
 
     pub fn open_genesis_config(path: impl AsRef<Path>) -> GenesisConfig {
         let config_file = std::fs::read_to_string(path).unwrap();
-        toml::from_str(&config_file).unwrap()
+        parse_genesis_config(&config_file).unwrap()
+    }
+
+    /// Parse a genesis configuration. The human-readable part of the
+    /// addresses of this process is set from the configuration first, because
+    /// the addresses in it are encoded with it.
+    pub fn parse_genesis_config(
+        config_file: &str,
+    ) -> Result<GenesisConfig, String> {
+        #[derive(Deserialize)]
+        struct AddressHrpConfig {
+            address_hrp: Option<String>,
+        }
+        let AddressHrpConfig { address_hrp } =
+            toml::from_str(config_file).map_err(|err| err.to_string())?;
+        address::set_address_hrp(
+            address_hrp
+                .as_deref()
+                .unwrap_or(address::DEFAULT_ADDRESS_HRP),
+        )
+        .map_err(|err| err.to_string())?;
+        toml::from_str(config_file).map_err(|err| err.to_string())
     }
 
     pub fn write_genesis_config(
@@ -569,7 +783,602 @@ pub mod genesis_config {
     }
 
     pub fn read_genesis_config(path: impl AsRef<Path>) -> Genesis {
-        load_genesis_config(open_genesis_config(path))
+        let config = open_genesis_config(path);
+        // A validator set that Tendermint would reject must not get to the
+        // chain's initialization
+        let errors = validate_genesis_validators(&config);
+        if !errors.is_empty() {
+            eprintln!(
+                "Invalid genesis validators, found {} problem(s):",
+                errors.len()
+            );
+            for err in errors {
+                eprintln!("  - {}", err);
+            }
+            cli::safe_exit(1)
+        }
+        load_genesis_config(config)
+    }
+
+    /// A problem found in a genesis configuration by
+    /// [`validate_genesis_config`].
+    #[derive(Error, Debug)]
+    pub enum ValidationError {
+        #[error("Invalid genesis time {0}: {1}")]
+        InvalidGenesisTime(String, String),
+        #[error("{account} is missing its {field}")]
+        MissingField {
+            account: String,
+            field: &'static str,
+        },
+        #[error("{account} has an invalid {field}: {error}")]
+        InvalidField {
+            account: String,
+            field: &'static str,
+            error: String,
+        },
+        #[error("{account} refers to an unknown VP WASM {name}")]
+        UnknownWasm { account: String, name: String },
+        #[error("VP WASM {0} is missing its sha256")]
+        MissingWasmSha256(String),
+        #[error("VP WASM {0} has an invalid sha256: {1}")]
+        InvalidWasmSha256(String, String),
+        #[error("VP WASM {0} cannot be read: {1}")]
+        WasmNotFound(String, String),
+        #[error(
+            "VP WASM {name} sha256 mismatch, expected {expected}, got {actual}"
+        )]
+        WasmHashMismatch {
+            name: String,
+            expected: String,
+            actual: String,
+        },
+        #[error("VP WASM {0} is not valid: {1}")]
+        InvalidWasm(String, String),
+        #[error("{token} has a balance for an unknown account {owner}")]
+        UnknownBalanceOwner { token: String, owner: String },
+        #[error("{token} balance of {owner} overflows")]
+        BalanceOverflow { token: String, owner: String },
+        #[error("Address {address} is used by both {first} and {second}")]
+        DuplicateAddress {
+            address: String,
+            first: String,
+            second: String,
+        },
+        #[error("Invalid parameter {0}: {1}")]
+        InvalidParameter(&'static str, String),
+        #[error("{0} has no self-bond")]
+        MissingSelfBond(String),
+        #[error("Consensus key {key} is used by both {first} and {second}")]
+        DuplicateConsensusKey {
+            key: String,
+            first: String,
+            second: String,
+        },
+    }
+
+    /// Check the given genesis configuration and collect every problem found
+    /// in it, instead of stopping at the first one like
+    /// [`load_genesis_config`] does. The referenced VP WASMs are read from the
+    /// given WASM directory. This doesn't touch any DB or network.
+    pub fn validate_genesis_config(
+        config: &GenesisConfig,
+        wasm_dir: impl AsRef<Path>,
+    ) -> Vec<ValidationError> {
+        let mut errors = vec![];
+
+        if let Err(err) =
+            TryInto::<DateTimeUtc>::try_into(config.genesis_time.clone())
+        {
+            errors.push(ValidationError::InvalidGenesisTime(
+                config.genesis_time.0.clone(),
+                err.to_string(),
+            ));
+        }
+
+        // Addresses of all the accounts, used to find duplicates
+        let mut addresses: HashMap<Address, String> = HashMap::default();
+        let mut add_address = |errors: &mut Vec<ValidationError>,
+                               address: Address,
+                               account: &str| {
+            if let Some(first) =
+                addresses.insert(address.clone(), account.to_owned())
+            {
+                errors.push(ValidationError::DuplicateAddress {
+                    address: address.encode(),
+                    first,
+                    second: account.to_owned(),
+                });
+            }
+        };
+        // Names of all the VP WASMs used by the accounts
+        let mut used_wasms: BTreeSet<String> = BTreeSet::default();
+        let mut use_wasm = |errors: &mut Vec<ValidationError>,
+                            name: &Option<String>,
+                            account: &str,
+                            field: &'static str| {
+            match name {
+                Some(name) if config.wasm.contains_key(name) => {
+                    used_wasms.insert(name.clone());
+                }
+                Some(name) => errors.push(ValidationError::UnknownWasm {
+                    account: account.to_owned(),
+                    name: name.clone(),
+                }),
+                None => errors.push(ValidationError::MissingField {
+                    account: account.to_owned(),
+                    field,
+                }),
+            }
+        };
+
+        let established = config.established.clone().unwrap_or_default();
+        let matchmakers = config.matchmaker.clone().unwrap_or_default();
+
+        for (name, validator) in sorted(&config.validator) {
+            let account = format!("validator {}", name);
+            for (field, address) in [
+                ("address", &validator.address),
+                ("staking_reward_address", &validator.staking_reward_address),
+            ] {
+                if let Some(address) =
+                    check_address(&mut errors, address, &account, field)
+                {
+                    add_address(&mut errors, address, &account);
+                }
+            }
+            for (field, key) in [
+                ("account_public_key", &validator.account_public_key),
+                (
+                    "staking_reward_public_key",
+                    &validator.staking_reward_public_key,
+                ),
+                ("protocol_public_key", &validator.protocol_public_key),
+            ] {
+                check_field(&mut errors, key, &account, field, |key| {
+                    key.to_public_key().map(|_| ())
+                });
+            }
+            check_field(
+                &mut errors,
+                &validator.dkg_public_key,
+                &account,
+                "dkg_public_key",
+                |key| key.to_dkg_public_key().map(|_| ()),
+            );
+            for (field, amount) in [
+                ("tokens", validator.tokens),
+                ("non_staked_balance", validator.non_staked_balance),
+            ] {
+                if amount.checked_mul(1_000_000).is_none() {
+                    errors.push(ValidationError::InvalidField {
+                        account: account.clone(),
+                        field,
+                        error: format!("{} overflows", amount),
+                    });
+                }
+            }
+            if let Some(rate) = validator.commission_rate {
+                if BasisPoints::new(rate) > BasisPoints::HUNDRED_PERCENT {
+                    errors.push(ValidationError::InvalidField {
+                        account: account.clone(),
+                        field: "commission_rate",
+                        error: format!("{} is greater than 100%", rate),
+                    });
+                }
+            }
+            if let Some(matchmaker) = &validator.matchmaker_account {
+                if !matchmakers.contains_key(matchmaker) {
+                    errors.push(ValidationError::InvalidField {
+                        account: account.clone(),
+                        field: "matchmaker_account",
+                        error: format!("unknown matchmaker {}", matchmaker),
+                    });
+                }
+            }
+            use_wasm(
+                &mut errors,
+                &validator.validator_vp,
+                &account,
+                "validator_vp",
+            );
+            use_wasm(
+                &mut errors,
+                &validator.staking_reward_vp,
+                &account,
+                "staking_reward_vp",
+            );
+        }
+
+        errors.extend(validate_genesis_validators(config));
+
+        let established_accounts =
+            sorted(&established).into_iter().map(|(name, cfg)| {
+                (format!("established account {}", name), cfg, false)
+            });
+        let matchmaker_accounts = sorted(&matchmakers)
+            .into_iter()
+            .map(|(name, cfg)| (format!("matchmaker {}", name), cfg, true));
+        for (account, established, is_matchmaker) in
+            established_accounts.chain(matchmaker_accounts)
+        {
+            if let Some(address) = check_address(
+                &mut errors,
+                &established.address,
+                &account,
+                "address",
+            ) {
+                add_address(&mut errors, address, &account);
+            }
+            match &established.public_key {
+                Some(key) => {
+                    if let Err(err) = key.to_public_key() {
+                        errors.push(ValidationError::InvalidField {
+                            account: account.clone(),
+                            field: "public_key",
+                            error: format!("{:?}", err),
+                        });
+                    }
+                }
+                // A matchmaker's key is needed to verify the txs it crafts
+                None if is_matchmaker => {
+                    errors.push(ValidationError::MissingField {
+                        account: account.clone(),
+                        field: "public_key",
+                    })
+                }
+                None => {}
+            }
+            for (key, value) in sorted(
+                established.storage.as_ref().unwrap_or(&HashMap::default()),
+            ) {
+                if let Err(err) = storage::Key::parse(key) {
+                    errors.push(ValidationError::InvalidField {
+                        account: account.clone(),
+                        field: "storage key",
+                        error: format!("{}: {}", key, err),
+                    });
+                }
+                if let Err(err) = value.to_bytes() {
+                    errors.push(ValidationError::InvalidField {
+                        account: account.clone(),
+                        field: "storage value",
+                        error: format!("{}: {:?}", key, err),
+                    });
+                }
+            }
+            use_wasm(&mut errors, &established.vp, &account, "vp");
+        }
+
+        let implicit = config.implicit.clone().unwrap_or_default();
+        for (name, implicit) in sorted(&implicit) {
+            let account = format!("implicit account {}", name);
+            if let Some(Ok(key)) = check_field(
+                &mut errors,
+                &implicit.public_key,
+                &account,
+                "public_key",
+                |key| key.to_public_key(),
+            ) {
+                add_address(&mut errors, (&key).into(), &account);
+            }
+        }
+
+        let tokens = config.token.clone().unwrap_or_default();
+        for (name, token) in sorted(&tokens) {
+            let account = format!("token {}", name);
+            if let Some(address) =
+                check_address(&mut errors, &token.address, &account, "address")
+            {
+                add_address(&mut errors, address, &account);
+            }
+            use_wasm(&mut errors, &token.vp, &account, "vp");
+            for (owner, amount) in
+                sorted(token.balances.as_ref().unwrap_or(&HashMap::default()))
+            {
+                let alias = owner.strip_suffix(".public_key");
+                let is_known = Address::decode(owner).is_ok()
+                    || match alias {
+                        Some(alias) => {
+                            established.contains_key(alias)
+                                || matchmakers.contains_key(alias)
+                                || config.validator.contains_key(alias)
+                        }
+                        None => {
+                            established.contains_key(owner)
+                                || matchmakers.contains_key(owner)
+                                || config.validator.contains_key(owner)
+                                || implicit.contains_key(owner)
+                        }
+                    };
+                if !is_known {
+                    errors.push(ValidationError::UnknownBalanceOwner {
+                        token: account.clone(),
+                        owner: owner.clone(),
+                    });
+                }
+                if amount.checked_mul(1_000_000).is_none() {
+                    errors.push(ValidationError::BalanceOverflow {
+                        token: account.clone(),
+                        owner: owner.clone(),
+                    });
+                }
+            }
+        }
+
+        for name in used_wasms {
+            let wasm = &config.wasm[&name];
+            let expected = match &wasm.sha256 {
+                Some(sha256) => match sha256.to_sha256_bytes() {
+                    Ok(bytes) => Some(bytes),
+                    Err(err) => {
+                        errors.push(ValidationError::InvalidWasmSha256(
+                            name.to_owned(),
+                            format!("{:?}", err),
+                        ));
+                        None
+                    }
+                },
+                None => {
+                    errors.push(ValidationError::MissingWasmSha256(
+                        name.to_owned(),
+                    ));
+                    None
+                }
+            };
+            let code =
+                match wasm_loader::try_read_wasm(&wasm_dir, &wasm.filename) {
+                    Ok(code) => code,
+                    Err(err) => {
+                        errors.push(ValidationError::WasmNotFound(
+                            name.to_owned(),
+                            err.to_string(),
+                        ));
+                        continue;
+                    }
+                };
+            if let Some(expected) = expected {
+                let actual = Sha256::digest(&code);
+                if actual.as_slice() != &expected[..] {
+                    errors.push(ValidationError::WasmHashMismatch {
+                        name: name.to_owned(),
+                        expected: hex::encode(expected),
+                        actual: hex::encode(actual),
+                    });
+                }
+            }
+            if let Err(err) = validate_untrusted_wasm(&code) {
+                errors.push(ValidationError::InvalidWasm(
+                    name.to_owned(),
+                    err.to_string(),
+                ));
+            } else if let Err(err) = prepare_wasm_code(&code) {
+                errors.push(ValidationError::InvalidWasm(
+                    name.to_owned(),
+                    err.to_string(),
+                ));
+            }
+        }
+
+        let parameters = &config.parameters;
+        if parameters.min_num_of_blocks == 0 {
+            errors.push(ValidationError::InvalidParameter(
+                "parameters.min_num_of_blocks",
+                "must be greater than 0".into(),
+            ));
+        }
+        if parameters.min_duration < 0 {
+            errors.push(ValidationError::InvalidParameter(
+                "parameters.min_duration",
+                "must not be negative".into(),
+            ));
+        }
+        if parameters.max_expected_time_per_block <= 0 {
+            errors.push(ValidationError::InvalidParameter(
+                "parameters.max_expected_time_per_block",
+                "must be greater than 0".into(),
+            ));
+        }
+
+        let pos_params = &config.pos_params;
+        if pos_params.max_validator_slots == 0 {
+            errors.push(ValidationError::InvalidParameter(
+                "pos_params.max_validator_slots",
+                "must be greater than 0".into(),
+            ));
+        }
+        if pos_params.pipeline_len == 0 {
+            errors.push(ValidationError::InvalidParameter(
+                "pos_params.pipeline_len",
+                "must be greater than 0".into(),
+            ));
+        }
+        if pos_params.unbonding_len < pos_params.pipeline_len {
+            errors.push(ValidationError::InvalidParameter(
+                "pos_params.unbonding_len",
+                format!(
+                    "must be greater or equal to pipeline_len {}",
+                    pos_params.pipeline_len
+                ),
+            ));
+        }
+        for (name, rate) in [
+            ("pos_params.votes_per_token", pos_params.votes_per_token),
+            (
+                "pos_params.duplicate_vote_slash_rate",
+                pos_params.duplicate_vote_slash_rate,
+            ),
+            (
+                "pos_params.light_client_attack_slash_rate",
+                pos_params.light_client_attack_slash_rate,
+            ),
+        ] {
+            if rate > 10_000 {
+                errors.push(ValidationError::InvalidParameter(
+                    name,
+                    format!("{} basis points is more than 100%", rate),
+                ));
+            }
+        }
+
+        let gov_params = &config.gov_params;
+        for (name, value) in [
+            (
+                "gov_params.max_proposal_code_size",
+                gov_params.max_proposal_code_size,
+            ),
+            (
+                "gov_params.min_proposal_period",
+                gov_params.min_proposal_period,
+            ),
+            (
+                "gov_params.max_proposal_content_size",
+                gov_params.max_proposal_content_size,
+            ),
+        ] {
+            if value == 0 {
+                errors.push(ValidationError::InvalidParameter(
+                    name,
+                    "must be greater than 0".into(),
+                ));
+            }
+        }
+        if let Some(limits) = gov_params.proposal_content_limits.as_ref() {
+            for (name, max) in [
+                (
+                    "gov_params.proposal_content_limits.max_title_size",
+                    limits.max_title_size,
+                ),
+                (
+                    "gov_params.proposal_content_limits.max_abstract_size",
+                    limits.max_abstract_size,
+                ),
+            ] {
+                if max < limits.min_field_size {
+                    errors.push(ValidationError::InvalidParameter(
+                        name,
+                        format!(
+                            "must be at least the min. field size {}",
+                            limits.min_field_size
+                        ),
+                    ));
+                }
+            }
+        }
+
+        errors
+    }
+
+    /// Check that the genesis validators can form a validator set: each of
+    /// them must have a self-bond of at least the minimum and within the
+    /// maximum validator stake and a consensus key that is not used by any
+    /// other validator. Every problem found is collected. This is a part of
+    /// [`validate_genesis_config`] and it's also checked when a node reads its
+    /// chain's genesis file.
+    pub fn validate_genesis_validators(
+        config: &GenesisConfig,
+    ) -> Vec<ValidationError> {
+        let mut errors = vec![];
+        let pos_params = &config.pos_params;
+        // The validators by their consensus keys, used to find duplicates
+        let mut consensus_keys: HashMap<common::PublicKey, String> =
+            HashMap::default();
+        for (name, validator) in sorted(&config.validator) {
+            let account = format!("validator {}", name);
+            if validator.tokens == 0 {
+                errors.push(ValidationError::MissingSelfBond(account.clone()));
+            } else if validator.tokens < pos_params.min_validator_self_bond {
+                errors.push(ValidationError::InvalidField {
+                    account: account.clone(),
+                    field: "tokens",
+                    error: format!(
+                        "{} is below the minimum validator self-bond {}",
+                        validator.tokens, pos_params.min_validator_self_bond
+                    ),
+                });
+            }
+            if let Some(max_validator_stake) = pos_params.max_validator_stake {
+                if validator.tokens > max_validator_stake {
+                    errors.push(ValidationError::InvalidField {
+                        account: account.clone(),
+                        field: "tokens",
+                        error: format!(
+                            "{} is above the maximum validator stake {}",
+                            validator.tokens, max_validator_stake
+                        ),
+                    });
+                }
+            }
+            if let Some(Ok(key)) = check_field(
+                &mut errors,
+                &validator.consensus_public_key,
+                &account,
+                "consensus_public_key",
+                |key| key.to_public_key(),
+            ) {
+                if let Some(first) =
+                    consensus_keys.insert(key.clone(), account.clone())
+                {
+                    errors.push(ValidationError::DuplicateConsensusKey {
+                        key: key.to_string(),
+                        first,
+                        second: account,
+                    });
+                }
+            }
+        }
+        errors
+    }
+
+    /// Iterate a config map in a deterministic order
+    fn sorted<T>(map: &HashMap<String, T>) -> Vec<(&String, &T)> {
+        let mut entries: Vec<_> = map.iter().collect();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        entries
+    }
+
+    /// Check that a required address field is present and decodes
+    fn check_address(
+        errors: &mut Vec<ValidationError>,
+        address: &Option<String>,
+        account: &str,
+        field: &'static str,
+    ) -> Option<Address> {
+        check_field(errors, address, account, field, |address| {
+            Address::decode(address)
+        })
+        .and_then(Result::ok)
+    }
+
+    /// Check that a required field is present and can be decoded with the
+    /// given function, reporting any problems into `errors`.
+    fn check_field<T, U, E: std::fmt::Debug>(
+        errors: &mut Vec<ValidationError>,
+        value: &Option<T>,
+        account: &str,
+        field: &'static str,
+        decode: impl Fn(&T) -> Result<U, E>,
+    ) -> Option<Result<U, E>> {
+        match value {
+            Some(value) => {
+                let result = decode(value);
+                if let Err(err) = &result {
+                    errors.push(ValidationError::InvalidField {
+                        account: account.to_owned(),
+                        field,
+                        error: format!("{:?}", err),
+                    });
+                }
+                Some(result)
+            }
+            None => {
+                errors.push(ValidationError::MissingField {
+                    account: account.to_owned(),
+                    field,
+                });
+                None
+            }
+        }
     }
 }
 
@@ -581,6 +1390,8 @@ pub struct Genesis {
     pub token_accounts: Vec<TokenAccount>,
     pub established_accounts: Vec<EstablishedAccount>,
     pub implicit_accounts: Vec<ImplicitAccount>,
+    /// The addresses of the registered matchmakers
+    pub matchmakers: Vec<Address>,
     pub parameters: Parameters,
     pub pos_params: PosParams,
     pub gov_params: GovParams,
@@ -593,6 +1404,7 @@ impl Genesis {
         self.token_accounts.sort();
         self.established_accounts.sort();
         self.implicit_accounts.sort();
+        self.matchmakers.sort();
     }
 }
 
@@ -665,6 +1477,13 @@ pub struct TokenAccount {
     /// Accounts' balances of this token
     #[derivative(PartialOrd = "ignore", Ord = "ignore")]
     pub balances: HashMap<Address, token::Amount>,
+    /// The token's admin, allowed to freeze and unfreeze its transfers
+    pub admin: Option<Address>,
+    /// The token's minter, the only account allowed to mint it after the
+    /// genesis
+    pub minter: Option<Address>,
+    /// The maximum total amount that the minter may mint
+    pub mint_cap: Option<token::Amount>,
 }
 
 #[derive(
@@ -693,6 +1512,7 @@ pub fn genesis(base_dir: impl AsRef<Path>, chain_id: &ChainId) -> Genesis {
 #[cfg(feature = "dev")]
 pub fn genesis() -> Genesis {
     use anoma::ledger::parameters::EpochDuration;
+    use anoma::ledger::pos::types::BasisPoints;
     use anoma::types::address;
 
     use crate::wallet;
@@ -713,7 +1533,7 @@ pub fn genesis() -> Genesis {
     let staking_reward_keypair =
         common::SecretKey::try_from_sk(&ed_staking_reward_keypair).unwrap();
     let address = wallet::defaults::validator_address();
-    let staking_reward_address = Address::decode("atest1v4ehgw36xcersvee8qerxd35x9prsw2xg5erxv6pxfpygd2x89z5xsf5xvmnysejgv6rwd2rnj2avt").unwrap();
+    let staking_reward_address = Address::decode_with_hrp("atest1v4ehgw36xcersvee8qerxd35x9prsw2xg5erxv6pxfpygd2x89z5xsf5xvmnysejgv6rwd2rnj2avt", address::DEFAULT_ADDRESS_HRP).unwrap();
     let (protocol_keypair, dkg_keypair) = wallet::defaults::validator_keys();
     let validator = Validator {
         pos_data: GenesisValidator {
@@ -722,6 +1542,7 @@ pub fn genesis() -> Genesis {
             tokens: token::Amount::whole(200_000),
             consensus_key: consensus_keypair.ref_to(),
             staking_reward_key: staking_reward_keypair.ref_to(),
+            commission_rate: BasisPoints::new(0),
         },
         account_key: account_keypair.ref_to(),
         protocol_key: protocol_keypair.ref_to(),
@@ -741,6 +1562,14 @@ pub fn genesis() -> Genesis {
         max_expected_time_per_block: anoma::types::time::DurationSecs(30),
         vp_whitelist: vec![],
         tx_whitelist: vec![],
+        tx_ordering: Default::default(),
+        tx_size_limits: Default::default(),
+        fee_exemption: Default::default(),
+        fee_token_rates: Default::default(),
+        fee_refund_policy: Default::default(),
+        storage_gas_costs: Default::default(),
+        write_log_flush_threshold: None,
+        min_base_fee: Default::default(),
     };
     let albert = EstablishedAccount {
         address: wallet::defaults::albert_address(),
@@ -770,6 +1599,7 @@ pub fn genesis() -> Genesis {
         public_key: Some(wallet::defaults::matchmaker_keypair().ref_to()),
         storage: HashMap::default(),
     };
+    let matchmakers = vec![matchmaker.address.clone()];
     let implicit_accounts = vec![ImplicitAccount {
         public_key: wallet::defaults::daewon_keypair().ref_to(),
     }];
@@ -808,6 +1638,9 @@ pub fn genesis() -> Genesis {
             vp_code_path: vp_token_path.into(),
             vp_sha256: Default::default(),
             balances: balances.clone(),
+            admin: None,
+            minter: None,
+            mint_cap: None,
         })
         .collect();
     Genesis {
@@ -815,6 +1648,7 @@ pub fn genesis() -> Genesis {
         validators: vec![validator],
         established_accounts: vec![albert, bertha, christel, matchmaker],
         implicit_accounts,
+        matchmakers,
         token_accounts,
         parameters,
         pos_params: PosParams::default(),
@@ -830,6 +1664,10 @@ pub mod tests {
     use rand::prelude::ThreadRng;
     use rand::thread_rng;
 
+    use super::genesis_config::{
+        parse_genesis_config, validate_genesis_validators, HexString,
+        ValidationError,
+    };
     use crate::wallet;
 
     /// Run `cargo test gen_genesis_validator -- --nocapture` to generate a
@@ -854,4 +1692,46 @@ pub mod tests {
         println!("protocol_keypair: {:?}", protocol_keypair);
         println!("dkg_keypair: {:?}", dkg_keypair.try_to_vec().unwrap());
     }
+
+    /// Test that a genesis validator without a self-bond and a genesis
+    /// validator with the consensus key of another validator are both
+    /// reported
+    #[test]
+    fn test_validate_genesis_validators() {
+        let mut config = parse_genesis_config(include_str!(
+            "../../../../genesis/e2e-tests-single-node.toml"
+        ))
+        .expect("Test failed");
+        let consensus_key = |keypair: common::SecretKey| {
+            Some(HexString(keypair.ref_to().to_string()))
+        };
+        let mut validator = config.validator["validator-0"].clone();
+        validator.consensus_public_key = consensus_key(testing::keypair_1());
+        config
+            .validator
+            .insert("validator-0".into(), validator.clone());
+        // A validator valid on its own, but using the consensus key of
+        // `validator-0`
+        config
+            .validator
+            .insert("validator-1".into(), validator.clone());
+        // A validator without a self-bond
+        validator.consensus_public_key = consensus_key(testing::keypair_2());
+        validator.tokens = 0;
+        config.validator.insert("validator-2".into(), validator);
+
+        let errors = validate_genesis_validators(&config);
+        assert_eq!(errors.len(), 2, "Unexpected errors {:?}", errors);
+        assert!(errors.iter().any(|err| matches!(
+            err,
+            ValidationError::DuplicateConsensusKey { first, second, .. }
+                if first == "validator validator-0"
+                    && second == "validator validator-1"
+        )));
+        assert!(errors.iter().any(|err| matches!(
+            err,
+            ValidationError::MissingSelfBond(account)
+                if account == "validator validator-2"
+        )));
+    }
 }
@@ -86,6 +86,10 @@ impl From<String> for TendermintMode {
 pub struct Ledger {
     pub genesis_time: Rfc3339String,
     pub chain_id: ChainId,
+    /// The human-readable part of the Bech32m encoded addresses of the chain.
+    /// The addresses with any other human-readable part are rejected. When
+    /// not set, defaults to `address::DEFAULT_ADDRESS_HRP`.
+    pub address_hrp: Option<String>,
     pub shell: Shell,
     pub tendermint: Tendermint,
 }
@@ -94,8 +98,13 @@ pub struct Ledger {
 pub struct Shell {
     pub base_dir: PathBuf,
     pub ledger_address: SocketAddr,
-    /// RocksDB block cache maximum size in bytes.
-    /// When not set, defaults to 1/3 of the available memory.
+    /// The DB backend of the storage. A node's DB can only be opened with
+    /// the backend that created it, the data isn't migrated when it's
+    /// changed. When not set, defaults to RocksDB.
+    pub storage_backend: Option<StorageBackend>,
+    /// DB cache maximum size in bytes, which is RocksDB's block cache or
+    /// sled's page cache. When not set, defaults to 1/3 of the available
+    /// memory.
     pub block_cache_bytes: Option<u64>,
     /// VP WASM compilation cache maximum size in bytes.
     /// When not set, defaults to 1/6 of the available memory.
@@ -103,10 +112,121 @@ pub struct Shell {
     /// Tx WASM compilation in-memory cache maximum size in bytes.
     /// When not set, defaults to 1/6 of the available memory.
     pub tx_wasm_compilation_cache_bytes: Option<u64>,
+    /// Maximum size in bytes of the keys and values read from the storage
+    /// that are cached in a block, the least recently used are evicted first.
+    /// When not set or 0, defaults to 64 MiB.
+    pub read_cache_bytes: Option<u64>,
+    /// Maximum number of ABCI requests from Tendermint queued for the shell.
+    /// When the queue is full, new requests wait for the shell to catch up.
+    /// When not set, defaults to 1024.
+    pub abci_channel_capacity: Option<usize>,
+    /// Maximum number of txs in a block proposed by this node. The decrypted
+    /// txs of the previous block are always included and the remaining room
+    /// is filled with new wrapper txs, the rest of which are deferred to the
+    /// next blocks. The block gas limit still applies to the included txs.
+    /// Only enforced with ABCI++, where the node builds its proposals. When
+    /// not set, the number of txs is not limited.
+    pub max_txs_per_block: Option<usize>,
     /// Use the [`Ledger::db_dir()`] method to read the value.
     db_dir: PathBuf,
     /// Use the [`Ledger::tendermint_dir()`] method to read the value.
     tendermint_dir: PathBuf,
+    /// An optional hook notified after every committed block. It's off by
+    /// default.
+    pub commit_hook: Option<CommitHook>,
+    /// An optional retention of the events of committed blocks. When not set,
+    /// the events are retained forever.
+    pub events_retention: Option<EventsRetention>,
+    /// Compact the DB once the events retention has pruned more than this
+    /// many keys since the last compaction, so that the disk space of the
+    /// pruned events is reclaimed. The compaction blocks the commit of the
+    /// block that triggers it. When not set, the DB is only compacted by its
+    /// background compactions or with the `ledger compact` command while the
+    /// node is stopped.
+    pub compact_after_pruned_keys: Option<u64>,
+    /// Maximum wall-clock time in milliseconds of a single tx or VP wasm
+    /// execution in a dry run or a simulated block. A tx that runs over it
+    /// fails and a VP that runs over it rejects the tx. It bounds the time
+    /// this node spends serving these queries independently of the gas
+    /// costs. The txs applied in a block are never timed out, because the
+    /// result would depend on the speed of the node, and they are only
+    /// bounded by gas. When not set, the queries are only bounded by gas.
+    pub wasm_timeout_ms: Option<u64>,
+    /// An optional limit of the rate of the queries served by this node. It's
+    /// off by default.
+    pub query_rate_limit: Option<QueryRateLimit>,
+    /// Verify the VPs written to the storage at the genesis: each VP is read
+    /// back from the storage, validated and its code hash is logged, so that
+    /// it can be compared with the expected one. The chain's initialization
+    /// is aborted if any VP is invalid. When not set, the VPs are verified.
+    pub verify_genesis_vps: Option<bool>,
+    /// Halt the node before it processes the block at this height, e.g. for
+    /// a coordinated upgrade in which all the nodes stop at an agreed height
+    /// so that their operators can swap the binaries. The blocks below it
+    /// are committed and the node shuts down. When not set or set to 0, the
+    /// node doesn't halt.
+    pub halt_height: Option<u64>,
+}
+
+/// The DB backend of the ledger's storage
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// RocksDB, the default backend
+    RocksDb,
+    /// sled, a pure Rust embedded DB
+    Sled,
+}
+
+impl Default for StorageBackend {
+    fn default() -> Self {
+        Self::RocksDb
+    }
+}
+
+/// A hook notified after every committed block, e.g. to push new blocks to an
+/// indexer without polling. The notification is a JSON object with the
+/// block's `height`, its Merkle `root` and, when `include_events` is set, its
+/// `events`. A failure of the hook is logged, but never fails the block.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CommitHook {
+    /// A command to execute, given as the program followed by its arguments.
+    /// The notification is written to its stdin.
+    pub command: Option<Vec<String>>,
+    /// An HTTP endpoint to which the notification is POSTed
+    pub http_url: Option<String>,
+    /// Include the block's events in the notification
+    pub include_events: bool,
+}
+
+/// The retention of the events of committed blocks, which index the blocks'
+/// tx results and balance changes for the `query-events` command. The events
+/// of a block are pruned on commit once either of the set limits is
+/// exceeded, independently of the state, which is not affected. The events
+/// of the last committed block are always retained.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EventsRetention {
+    /// Retain the events of at most this many of the latest blocks
+    pub max_blocks: Option<u64>,
+    /// Retain the events of the blocks at most this many seconds older than
+    /// the last committed block
+    pub max_age_secs: Option<u64>,
+}
+
+/// A limit of the rate of the queries served by this node, so that the
+/// queries that scan the storage or run the VPs can't overwhelm it. Tendermint
+/// forwards the queries of all its RPC clients over a single ABCI connection
+/// that doesn't identify them, so the limit is shared by all the clients of
+/// this node. A query over the limit is refused without being served.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QueryRateLimit {
+    /// Maximum number of queries served per second
+    pub max_queries_per_sec: u64,
+    /// Maximum number of the heavy queries served per second, which are the
+    /// tx dry-runs, the block simulations and the storage prefix scans. They
+    /// also count towards `max_queries_per_sec`. When not set, they're only
+    /// limited by `max_queries_per_sec`.
+    pub max_heavy_queries_per_sec: Option<u64>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -164,17 +284,29 @@ impl Ledger {
         Self {
             genesis_time: Rfc3339String("1970-01-01T00:00:00Z".to_owned()),
             chain_id,
+            address_hrp: None,
             shell: Shell {
                 base_dir: base_dir.as_ref().to_owned(),
                 ledger_address: SocketAddr::new(
                     IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
                     26658,
                 ),
+                storage_backend: None,
                 block_cache_bytes: None,
                 vp_wasm_compilation_cache_bytes: None,
                 tx_wasm_compilation_cache_bytes: None,
+                read_cache_bytes: None,
+                abci_channel_capacity: None,
+                max_txs_per_block: None,
                 db_dir: DB_DIR.into(),
                 tendermint_dir: TENDERMINT_DIR.into(),
+                commit_hook: None,
+                events_retention: None,
+                compact_after_pruned_keys: None,
+                wasm_timeout_ms: None,
+                query_rate_limit: None,
+                verify_genesis_vps: None,
+                halt_height: None,
             },
             tendermint: Tendermint {
                 rpc_address: SocketAddr::new(
@@ -0,0 +1,182 @@
+//! A hook notifying external services, such as indexers, of the committed
+//! blocks. The notifications are delivered from a dedicated thread, so that a
+//! slow or failing hook never blocks or fails the consensus.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+
+use anoma::ledger::storage::MerkleRoot;
+use anoma::types::storage::BlockHeight;
+use borsh::BorshDeserialize;
+use serde_json::{json, Value};
+
+use crate::config;
+use crate::node::ledger::events::BlockEvent;
+
+/// The notification of a committed block
+#[derive(Debug)]
+pub struct CommitNotification {
+    /// The height of the committed block
+    pub height: BlockHeight,
+    /// The Merkle root of the committed block
+    pub root: MerkleRoot,
+    /// The borsh encoded events of the block, if they're to be included
+    pub events: Option<Vec<u8>>,
+}
+
+impl CommitNotification {
+    /// The notification as a JSON value
+    fn to_json(&self) -> Value {
+        let mut notification = json!({
+            "height": self.height.0,
+            "root": self.root.to_string(),
+        });
+        if let Some(events) = self.events.as_ref() {
+            // A block without any txs has no encoded events
+            let events = if events.is_empty() {
+                Ok(vec![])
+            } else {
+                Vec::<BlockEvent>::try_from_slice(events)
+            };
+            notification["events"] = match events {
+                Ok(events) => events
+                    .into_iter()
+                    .map(|event| {
+                        json!({
+                            "type": event.event_type,
+                            "attributes": event.attributes,
+                        })
+                    })
+                    .collect(),
+                Err(err) => {
+                    tracing::error!(
+                        "Couldn't decode the events of the block at height {} \
+                         for the commit hook: {}",
+                        self.height,
+                        err
+                    );
+                    Value::Null
+                }
+            };
+        }
+        notification
+    }
+}
+
+/// A handle to the thread that delivers the notifications of the commit hook
+#[derive(Debug)]
+pub struct CommitHook {
+    sender: mpsc::Sender<CommitNotification>,
+    include_events: bool,
+}
+
+impl CommitHook {
+    /// Start the thread delivering the notifications to the configured
+    /// targets.
+    pub fn new(config: config::CommitHook) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let include_events = config.include_events;
+        thread::Builder::new()
+            .name("commit-hook".into())
+            .spawn(move || run(config, receiver))
+            .expect("Starting the commit hook thread shouldn't fail");
+        Self {
+            sender,
+            include_events,
+        }
+    }
+
+    /// Should the block's events be included in the notifications?
+    pub fn include_events(&self) -> bool {
+        self.include_events
+    }
+
+    /// Queue the notification of a committed block. This doesn't wait for
+    /// the notification to be delivered.
+    pub fn notify(&self, notification: CommitNotification) {
+        if let Err(err) = self.sender.send(notification) {
+            tracing::error!(
+                "The commit hook has stopped, the notification of the block \
+                 at height {} is dropped",
+                err.0.height
+            );
+        }
+    }
+}
+
+/// Deliver the notifications until the shell drops its [`CommitHook`].
+fn run(
+    config: config::CommitHook,
+    receiver: mpsc::Receiver<CommitNotification>,
+) {
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            tracing::error!("Couldn't start the commit hook: {}", err);
+            return;
+        }
+    };
+    let client = reqwest::Client::new();
+    for notification in receiver {
+        let payload = notification.to_json().to_string();
+        if let Some(command) = config.command.as_ref() {
+            if let Err(err) = exec(command, &payload) {
+                tracing::error!(
+                    "The commit hook command failed for the block at height \
+                     {}: {}",
+                    notification.height,
+                    err
+                );
+            }
+        }
+        if let Some(url) = config.http_url.as_ref() {
+            let response = runtime.block_on(
+                client
+                    .post(url)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .body(payload)
+                    .send(),
+            );
+            if let Err(err) =
+                response.and_then(|response| response.error_for_status())
+            {
+                tracing::error!(
+                    "The commit hook request to {} failed for the block at \
+                     height {}: {}",
+                    url,
+                    notification.height,
+                    err
+                );
+            }
+        }
+    }
+}
+
+/// Execute the command with the payload written to its stdin and wait for it
+/// to finish.
+fn exec(command: &[String], payload: &str) -> std::io::Result<()> {
+    let (program, args) = command.split_first().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "empty command")
+    })?;
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(payload.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("exited with {}", status),
+        ))
+    }
+}
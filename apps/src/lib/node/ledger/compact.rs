@@ -0,0 +1,87 @@
+//! Compaction of the ledger node's DB, which reclaims the disk space of the
+//! deleted and overwritten values, e.g. of the pruned block events. The DB
+//! can only be opened by a single process, so the node must be stopped
+//! before it's compacted.
+
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use anoma::ledger::storage::{self, DB};
+use thiserror::Error;
+
+use crate::config::{self, StorageBackend};
+use crate::node::ledger::storage::{open_db, open_sled_db};
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("There's no DB at {0}")]
+    NoDb(PathBuf),
+    #[error(
+        "Cannot open the DB at {0}. The node must be stopped before its DB is \
+         compacted: {1}"
+    )]
+    Open(PathBuf, storage::Error),
+    #[error("Error compacting the DB: {0}")]
+    Compact(storage::Error),
+    #[error("Error reading the size of the DB at {0}: {1}")]
+    ReadSize(PathBuf, io::Error),
+}
+
+/// Compact the DB of the ledger node with the given config.
+pub fn compact(config: config::Ledger) -> Result<(), Error> {
+    let db_path = config.db_dir();
+    let storage_backend = config.shell.storage_backend.unwrap_or_default();
+    println!(
+        "Compacting the DB at {}, this may take a while...",
+        db_path.to_string_lossy()
+    );
+    let (size_before, size_after) = compact_db(&db_path, storage_backend)?;
+    println!(
+        "The DB has been compacted from {} to {} bytes.",
+        size_before, size_after
+    );
+    Ok(())
+}
+
+/// Compact the DB of the given backend at the given path. Returns the size of
+/// the DB's files in bytes before and after the compaction.
+pub fn compact_db(
+    db_path: impl AsRef<Path>,
+    storage_backend: StorageBackend,
+) -> Result<(u64, u64), Error> {
+    let db_path = db_path.as_ref();
+    if !db_path.exists() {
+        return Err(Error::NoDb(db_path.to_owned()));
+    }
+    let size_before = dir_size(db_path)?;
+    {
+        let open_err = |err| Error::Open(db_path.to_owned(), err);
+        match storage_backend {
+            StorageBackend::RocksDb => {
+                open_db(db_path).map_err(open_err)?.compact()
+            }
+            StorageBackend::Sled => {
+                open_sled_db(db_path).map_err(open_err)?.compact()
+            }
+        }
+        .map_err(Error::Compact)?;
+        // The obsolete files are deleted once the DB is closed at the latest
+    }
+    let size_after = dir_size(db_path)?;
+    Ok((size_before, size_after))
+}
+
+/// The total size in bytes of the files in the given directory
+fn dir_size(path: &Path) -> Result<u64, Error> {
+    let read_err = |err| Error::ReadSize(path.to_owned(), err);
+    let mut size = 0;
+    for entry in fs::read_dir(path).map_err(read_err)? {
+        let metadata = entry.and_then(|entry| entry.metadata());
+        let metadata = metadata.map_err(read_err)?;
+        if metadata.is_file() {
+            size += metadata.len();
+        }
+    }
+    Ok(size)
+}
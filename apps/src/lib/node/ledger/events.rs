@@ -1,10 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::{self, Display};
 use std::ops::{Index, IndexMut};
 
+use anoma::types::address::Address;
 use anoma::types::ibc::IbcEvent;
+use anoma::types::key::common;
+use anoma::types::token;
 use anoma::types::transaction::{hash_tx, TxType};
-use borsh::BorshSerialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 #[cfg(not(feature = "ABCI"))]
 use tendermint_proto::abci::EventAttribute;
 #[cfg(feature = "ABCI")]
@@ -18,7 +21,7 @@ pub struct Event {
     pub attributes: HashMap<String, String>,
 }
 
-/// The two types of custom events we currently use
+/// The types of custom events we currently use
 #[derive(Clone)]
 pub enum EventType {
     // The transaction was accepted to be included in a block
@@ -27,6 +30,10 @@ pub enum EventType {
     Applied,
     // The IBC transaction was applied during block finalization
     Ibc(String),
+    // An applied transaction changed a token balance
+    BalanceChange,
+    // A new epoch changed the voting power of a validator
+    ValidatorUpdate,
 }
 
 #[cfg(not(feature = "ABCI"))]
@@ -36,6 +43,8 @@ impl Display for EventType {
             EventType::Accepted => write!(f, "accepted"),
             EventType::Applied => write!(f, "applied"),
             EventType::Ibc(t) => write!(f, "{}", t),
+            EventType::BalanceChange => write!(f, "balance-change"),
+            EventType::ValidatorUpdate => write!(f, "validator-update"),
         }?;
         Ok(())
     }
@@ -48,6 +57,8 @@ impl Display for EventType {
             EventType::Accepted => write!(f, "applied"),
             EventType::Applied => write!(f, "applied"),
             EventType::Ibc(t) => write!(f, "{}", t),
+            EventType::BalanceChange => write!(f, "balance-change"),
+            EventType::ValidatorUpdate => write!(f, "validator-update"),
         }?;
         Ok(())
     }
@@ -102,6 +113,47 @@ impl Event {
         event
     }
 
+    /// Creates a new event for a balance changed by an applied transaction
+    /// with the given hash
+    pub fn new_balance_change_event(
+        tx_hash: String,
+        height: u64,
+        token: &Address,
+        owner: &Address,
+        balance: token::Amount,
+    ) -> Self {
+        let mut event = Event {
+            event_type: EventType::BalanceChange,
+            attributes: HashMap::new(),
+        };
+        event["hash"] = tx_hash;
+        event["height"] = height.to_string();
+        event["token"] = token.encode();
+        event["owner"] = owner.encode();
+        event["balance"] = balance.to_string();
+        event
+    }
+
+    /// Creates a new event for an update of the active validator set sent to
+    /// Tendermint with the block at the given height. The `change` is one of
+    /// "added", "removed" or "power-changed".
+    pub fn new_validator_update_event(
+        height: u64,
+        consensus_key: &common::PublicKey,
+        power: i64,
+        change: &str,
+    ) -> Self {
+        let mut event = Event {
+            event_type: EventType::ValidatorUpdate,
+            attributes: HashMap::new(),
+        };
+        event["height"] = height.to_string();
+        event["consensus_key"] = consensus_key.to_string();
+        event["power"] = power.to_string();
+        event["change"] = change.to_owned();
+        event
+    }
+
     pub fn contains_key(&self, key: &str) -> bool {
         self.attributes.contains_key(key)
     }
@@ -171,6 +223,29 @@ impl From<Event> for tendermint_proto_abci::abci::Event {
     }
 }
 
+/// An event emitted in a committed block, as persisted in the storage and
+/// returned by the [`crate::node::ledger::rpc::Path::BlockEvents`] query
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct BlockEvent {
+    /// The type of the event, as reported to Tendermint
+    pub event_type: String,
+    /// The event's attributes
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl From<&Event> for BlockEvent {
+    fn from(event: &Event) -> Self {
+        Self {
+            event_type: event.event_type.to_string(),
+            attributes: event
+                .attributes
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        }
+    }
+}
+
 /// A thin wrapper around a HashMap for parsing event JSONs
 /// returned in tendermint subscription responses.
 #[derive(Debug)]
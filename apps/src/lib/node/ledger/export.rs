@@ -0,0 +1,204 @@
+//! Export of the events of the committed blocks, e.g. for indexers that
+//! backfill their data from the node's storage. The events are written as
+//! JSON lines, one per event in the order of the blocks and in the order in
+//! which they were emitted in a block:
+//!
+//! ```json
+//! {"height":2,"type":"applied","attributes":{"code":"0","hash":"..."}}
+//! ```
+//!
+//! The result of every tx included in a block is reported with a tx event and
+//! the other events of the tx, e.g. its balance changes, carry the tx's hash.
+//! The attributes are ordered by their key, so that the same block is always
+//! exported the same way.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use anoma::ledger::storage::{self, Storage, StorageHasher, DB};
+use anoma::types::storage::BlockHeight;
+use borsh::BorshDeserialize;
+use serde_json::json;
+use thiserror::Error;
+
+use crate::cli::args;
+use crate::config::{self, StorageBackend};
+use crate::node::ledger::events::BlockEvent;
+use crate::node::ledger::storage::{
+    PersistentStorage, PersistentStorageHasher, SledDB,
+};
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error(
+        "The block {height} hasn't been committed yet, the last committed \
+         block is {last_height}"
+    )]
+    NotCommitted {
+        height: BlockHeight,
+        last_height: BlockHeight,
+    },
+    #[error(
+        "The events of the block {height} are not retained, the oldest block \
+         with retained events is {retained_from}"
+    )]
+    NotRetained {
+        height: BlockHeight,
+        retained_from: BlockHeight,
+    },
+    #[error("Storage error: {0}")]
+    Storage(storage::Error),
+    #[error("Error decoding the events of the block {0}: {1}")]
+    Decode(BlockHeight, io::Error),
+    #[error("Error writing the events: {0}")]
+    Write(io::Error),
+    #[error("Error reading the cursor file {0}: {1}")]
+    ReadCursor(PathBuf, io::Error),
+    #[error("Invalid height in the cursor file {0}: {1}")]
+    InvalidCursor(PathBuf, std::num::ParseIntError),
+    #[error("Error writing the cursor file {0}: {1}")]
+    WriteCursor(PathBuf, io::Error),
+    #[error("Error creating the output file {0}: {1}")]
+    CreateFile(PathBuf, io::Error),
+}
+
+/// Export the events of the blocks committed by the ledger node with the
+/// given config from its storage to the output file and update the cursor
+/// file, if any, with the height of the next block to export.
+pub fn export_events(
+    config: config::Ledger,
+    args: args::LedgerExportEvents,
+) -> Result<(), Error> {
+    match config.shell.storage_backend.unwrap_or_default() {
+        StorageBackend::RocksDb => export_storage_events(
+            PersistentStorage::open(config.db_dir(), config.chain_id, None),
+            args,
+        ),
+        StorageBackend::Sled => export_storage_events(
+            Storage::<SledDB, PersistentStorageHasher>::open(
+                config.db_dir(),
+                config.chain_id,
+                None,
+            ),
+            args,
+        ),
+    }
+}
+
+/// Export the events from the given storage, see [`export_events`].
+fn export_storage_events<D, H>(
+    mut storage: Storage<D, H>,
+    args: args::LedgerExportEvents,
+) -> Result<(), Error>
+where
+    D: DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    storage.load_last_state().map_err(Error::Storage)?;
+
+    let cursor = match args.cursor.as_ref() {
+        Some(path) if path.exists() => {
+            let cursor = fs::read_to_string(path)
+                .map_err(|err| Error::ReadCursor(path.clone(), err))?;
+            let height = cursor
+                .trim()
+                .parse()
+                .map_err(|err| Error::InvalidCursor(path.clone(), err))?;
+            Some(BlockHeight(height))
+        }
+        _ => None,
+    };
+    let from_height = args.from_height.or(cursor).unwrap_or(BlockHeight(1));
+    let to_height = args.to_height.unwrap_or(storage.last_height);
+    if from_height > to_height {
+        println!(
+            "No blocks to export from the height {}, the last block is {}.",
+            from_height, to_height
+        );
+        return Ok(());
+    }
+
+    let file = File::create(&args.out)
+        .map_err(|err| Error::CreateFile(args.out.clone(), err))?;
+    let num_events = export_block_events(
+        &storage,
+        from_height,
+        to_height,
+        &mut BufWriter::new(file),
+    )?;
+    let next_height = to_height.next_height();
+    if let Some(path) = args.cursor.as_ref() {
+        fs::write(path, next_height.to_string())
+            .map_err(|err| Error::WriteCursor(path.clone(), err))?;
+    }
+    println!(
+        "Exported {} events of the blocks {} to {} into {}. The next export \
+         can resume from the height {}.",
+        num_events,
+        from_height,
+        to_height,
+        args.out.to_string_lossy(),
+        next_height
+    );
+    Ok(())
+}
+
+/// Export the events of the committed blocks in the given inclusive range of
+/// heights. The events are read and written one block at a time, so that the
+/// memory use doesn't grow with the range. Returns the number of the exported
+/// events.
+pub fn export_block_events<D, H>(
+    storage: &Storage<D, H>,
+    from_height: BlockHeight,
+    to_height: BlockHeight,
+    w: &mut impl Write,
+) -> Result<u64, Error>
+where
+    D: DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    if to_height > storage.last_height {
+        return Err(Error::NotCommitted {
+            height: to_height,
+            last_height: storage.last_height,
+        });
+    }
+    let retained_from =
+        storage.get_events_retained_from().map_err(Error::Storage)?;
+    // No block is committed at the height 0
+    let from_height = BlockHeight(from_height.0.max(1));
+    if from_height < retained_from {
+        return Err(Error::NotRetained {
+            height: from_height,
+            retained_from,
+        });
+    }
+    let mut num_events = 0;
+    for height in from_height.0..=to_height.0 {
+        let height = BlockHeight(height);
+        let events =
+            match storage.get_block_events(height).map_err(Error::Storage)? {
+                // A block without any txs has no encoded events and no events
+                // have been stored for the blocks committed before the events
+                // were persisted
+                Some(bytes) if !bytes.is_empty() => {
+                    Vec::<BlockEvent>::try_from_slice(&bytes)
+                        .map_err(|err| Error::Decode(height, err))?
+                }
+                _ => continue,
+            };
+        for event in events {
+            let line = json!({
+                "height": height.0,
+                "type": event.event_type,
+                "attributes": event.attributes,
+            });
+            writeln!(w, "{}", line).map_err(Error::Write)?;
+            num_events += 1;
+        }
+    }
+    w.flush().map_err(Error::Write)?;
+    Ok(num_events)
+}
@@ -1,5 +1,8 @@
 mod broadcaster;
+mod commit_hook;
+mod compact;
 pub mod events;
+mod export;
 pub mod protocol;
 pub mod rpc;
 mod shell;
@@ -10,12 +13,18 @@ pub mod tendermint_node;
 use std::convert::TryInto;
 use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::str::FromStr;
 
+use anoma::ledger::storage::{DBIter, DB};
 use byte_unit::Byte;
 use futures::future::TryFutureExt;
 use once_cell::unsync::Lazy;
 use sysinfo::{RefreshKind, System, SystemExt};
 #[cfg(not(feature = "ABCI"))]
+use tendermint_config::net::Address as TendermintAddress;
+#[cfg(feature = "ABCI")]
+use tendermint_config_abci::net::Address as TendermintAddress;
+#[cfg(not(feature = "ABCI"))]
 use tendermint_proto::abci::CheckTxType;
 #[cfg(feature = "ABCI")]
 use tendermint_proto_abci::abci::CheckTxType;
@@ -26,13 +35,15 @@ use tower_abci::{response, split, Server};
 use tower_abci_old::{response, split, Server};
 
 use self::shims::abcipp_shim::AbciService;
+use crate::cli::args;
 use crate::config::utils::num_of_threads;
-use crate::config::TendermintMode;
+use crate::config::{StorageBackend, TendermintMode};
 use crate::node::ledger::broadcaster::Broadcaster;
+pub use crate::node::ledger::shell::ErrorCodes;
 use crate::node::ledger::shell::{Error, MempoolTxType, Shell};
 use crate::node::ledger::shims::abcipp_shim::AbcippShim;
 use crate::node::ledger::shims::abcipp_shim_types::shim::{Request, Response};
-use crate::{config, wasm_loader};
+use crate::{client, config, wasm_loader};
 
 /// Env. var to set a number of Tokio RT worker threads
 const ENV_VAR_TOKIO_THREADS: &str = "ANOMA_TOKIO_THREADS";
@@ -40,6 +51,9 @@ const ENV_VAR_TOKIO_THREADS: &str = "ANOMA_TOKIO_THREADS";
 /// Env. var to set a number of Rayon global worker threads
 const ENV_VAR_RAYON_THREADS: &str = "ANOMA_RAYON_THREADS";
 
+/// The default capacity of the channel of ABCI requests to the shell
+const DEFAULT_ABCI_CHANNEL_CAPACITY: usize = 1024;
+
 // Until ABCI++ is ready, the shim provides the service implementation.
 // We will add this part back in once the shim is no longer needed.
 //```
@@ -57,7 +71,10 @@ const ENV_VAR_RAYON_THREADS: &str = "ANOMA_RAYON_THREADS";
 //     }
 //```
 
-impl Shell {
+impl<D> Shell<D>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+{
     fn call(&mut self, req: Request) -> Result<Response, Error> {
         match req {
             Request::InitChain(init) => {
@@ -99,7 +116,7 @@ impl Shell {
             Request::FinalizeBlock(finalize) => {
                 self.finalize_block(finalize).map(Response::FinalizeBlock)
             }
-            Request::Commit(_) => Ok(Response::Commit(self.commit())),
+            Request::Commit(_) => self.commit().map(Response::Commit),
             Request::Flush(_) => Ok(Response::Flush(Default::default())),
             Request::Echo(msg) => Ok(Response::Echo(response::Echo {
                 message: msg.message,
@@ -111,7 +128,7 @@ impl Shell {
                     CheckTxType::New => MempoolTxType::NewTransaction,
                     CheckTxType::Recheck => MempoolTxType::RecheckTransaction,
                 };
-                Ok(Response::CheckTx(self.mempool_validate(&*tx.tx, r#type)))
+                Ok(Response::CheckTx(self.check_tx(&*tx.tx, r#type)))
             }
             Request::ListSnapshots(_) => {
                 Ok(Response::ListSnapshots(Default::default()))
@@ -172,6 +189,31 @@ pub fn reset(config: config::Ledger) -> Result<(), shell::Error> {
     shell::reset(config)
 }
 
+/// Exports the events of the blocks committed by the ledger node with the
+/// given config from its storage
+pub fn export_events(
+    config: config::Ledger,
+    args: args::LedgerExportEvents,
+) -> Result<(), export::Error> {
+    export::export_events(config, args)
+}
+
+/// Compacts the DB of the ledger node with the given config. The node must be
+/// stopped.
+pub fn compact(config: config::Ledger) -> Result<(), compact::Error> {
+    compact::compact(config)
+}
+
+/// Prints a line for every block committed by the ledger node with the given
+/// config, connecting to its Tendermint RPC address
+#[tokio::main]
+pub async fn tail(config: config::Ledger, args: args::LedgerTail) {
+    let ledger_address =
+        TendermintAddress::from_str(&config.tendermint.rpc_address.to_string())
+            .expect("The RPC address should be a valid Tendermint address");
+    client::rpc::tail_blocks(ledger_address, args.from_height).await
+}
+
 /// Runs three concurrent tasks: A tendermint node, a shell which contains an
 /// ABCI, server for talking to the tendermint node, and a broadcaster so that
 /// the ledger may submit txs to the chain. All must be alive for correct
@@ -253,12 +295,12 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
         }
     };
     tracing::info!(
-        "RocksDB block cache size: {}",
+        "DB block cache size: {}",
         Byte::from_bytes(block_cache_size_bytes as u128)
             .get_appropriate_unit(true)
     );
-    let db_cache =
-        rocksdb::Cache::new_lru_cache(block_cache_size_bytes as usize).unwrap();
+    let storage_backend = config.shell.storage_backend.unwrap_or_default();
+    tracing::info!("Storage backend: {:?}", storage_backend);
 
     let tendermint_dir = config.tendermint_dir();
     let ledger_address = config.shell.ledger_address.to_string();
@@ -342,16 +384,49 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
 
     // Construct our ABCI application.
     let ledger_address = config.shell.ledger_address;
-    let (shell, abci_service) = AbcippShim::new(
-        config,
-        wasm_dir,
-        broadcaster_sender,
-        &db_cache,
-        vp_wasm_compilation_cache,
-        tx_wasm_compilation_cache,
-    );
+    let abci_channel_capacity = config
+        .shell
+        .abci_channel_capacity
+        .unwrap_or(DEFAULT_ABCI_CHANNEL_CAPACITY);
+    tracing::info!("ABCI channel capacity: {}", abci_channel_capacity);
+    // The shell is run with the DB of the configured backend. RocksDB's
+    // cache must outlive the DB instance that's in the shell.
+    let rocksdb_cache;
+    let (shell, abci_service): (Box<dyn FnOnce() + Send>, AbciService) =
+        match storage_backend {
+            StorageBackend::RocksDb => {
+                rocksdb_cache = rocksdb::Cache::new_lru_cache(
+                    block_cache_size_bytes as usize,
+                )
+                .unwrap();
+                let (shell, abci_service) =
+                    AbcippShim::<storage::PersistentDB>::new(
+                        config,
+                        wasm_dir,
+                        broadcaster_sender,
+                        &rocksdb_cache,
+                        vp_wasm_compilation_cache,
+                        tx_wasm_compilation_cache,
+                        abci_channel_capacity,
+                    );
+                (Box::new(move || shell.run()), abci_service)
+            }
+            StorageBackend::Sled => {
+                let (shell, abci_service) = AbcippShim::<storage::SledDB>::new(
+                    config,
+                    wasm_dir,
+                    broadcaster_sender,
+                    &block_cache_size_bytes,
+                    vp_wasm_compilation_cache,
+                    tx_wasm_compilation_cache,
+                    abci_channel_capacity,
+                );
+                (Box::new(move || shell.run()), abci_service)
+            }
+        };
 
     // Start the ABCI server
+    let abort_send_for_shell = abort_send.clone();
     let abci = tokio::spawn(async move {
         // On panic or exit, the `Drop` of `AbortSender` will send abort
         // message
@@ -371,8 +446,15 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
         std::thread::Builder::new().name("ledger-shell".into());
     let shell_handler = thread_builder
         .spawn(move || {
+            // On panic or exit (e.g. when a block cannot be committed), the
+            // `Drop` of `AbortSender` will send abort message
+            let aborter = Aborter {
+                sender: abort_send_for_shell,
+                who: "Shell",
+            };
             tracing::info!("Anoma ledger node started.");
-            shell.run()
+            shell();
+            drop(aborter);
         })
         .expect("Must be able to start a thread for the shell");
 
@@ -1,6 +1,7 @@
 //! The ledger's protocol
 use std::collections::BTreeSet;
 use std::panic;
+use std::time::Duration;
 
 use anoma::ledger::gas::{self, BlockGasMeter, VpGasMeter};
 use anoma::ledger::governance::GovernanceVp;
@@ -8,14 +9,19 @@ use anoma::ledger::ibc::vp::{Ibc, IbcToken};
 use anoma::ledger::native_vp::{self, NativeVp};
 use anoma::ledger::parameters::{self, ParametersVp};
 use anoma::ledger::pos::{self, PosVP};
-use anoma::ledger::storage::write_log::WriteLog;
+use anoma::ledger::storage::write_log::{self, StorageModification, WriteLog};
 use anoma::ledger::storage::{DBIter, Storage, StorageHasher, DB};
 use anoma::proto::{self, Tx};
 use anoma::types::address::{Address, InternalAddress};
-use anoma::types::storage;
-use anoma::types::transaction::{DecryptedTx, TxResult, TxType, VpsResult};
+use anoma::types::storage::QueuedWrapper;
+use anoma::types::transaction::bundle::TxBundle;
+use anoma::types::transaction::{
+    DecryptedTx, TxResult, TxType, VpsResult, WrapperTx, WrapperTxErr,
+};
+use anoma::types::{storage, token};
 use anoma::vm::wasm::{TxCache, VpCache};
 use anoma::vm::{self, wasm, WasmCacheAccess};
+use borsh::{BorshDeserialize, BorshSerialize};
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use thiserror::Error;
 
@@ -31,6 +37,12 @@ pub enum Error {
     TxTypeError,
     #[error("Gas error: {0}")]
     GasError(gas::Error),
+    #[error(
+        "Out of gas: the tx used {used} gas, which is at least {} more than \
+         its gas limit {limit}. Submit it again with a higher --gas-limit.",
+        .used - .limit
+    )]
+    OutOfGas { used: u64, limit: u64 },
     #[error("Error executing VP for addresses: {0:?}")]
     VpRunnerError(vm::wasm::run::Error),
     #[error("The address {0} doesn't exist")]
@@ -49,6 +61,52 @@ pub enum Error {
     GovernanceNativeVpError(anoma::ledger::governance::Error),
     #[error("Access to an internal address {0} is forbidden")]
     AccessForbidden(InternalAddress),
+    #[error("Invalid wrapper tx fee: {0}")]
+    WrapperFee(WrapperTxErr),
+    #[error(
+        "The token {0} can't pay fees, it has no conversion rate to the \
+         native token"
+    )]
+    FeeTokenNotAllowed(Address),
+    #[error("Error reading the fee token rates: {0}")]
+    ReadFeeTokenRates(parameters::ReadError),
+    #[error("The gas price {gas_price} is below the base fee {base_fee}")]
+    GasPriceBelowBaseFee {
+        gas_price: token::Amount,
+        base_fee: token::Amount,
+    },
+    #[error(
+        "The fee {fee} is below the minimum fee {min_fee} given by the \
+         current base fee"
+    )]
+    FeeBelowMinFee {
+        fee: token::Amount,
+        min_fee: token::Amount,
+    },
+    #[error("Error reading the base fee: {0}")]
+    ReadBaseFee(parameters::ReadError),
+    #[error("{0}")]
+    TxSize(parameters::TxSizeError),
+    #[error("Error reading the tx size limits: {0}")]
+    ReadTxSizeLimits(parameters::ReadError),
+    #[error("Error reading the fee exemption: {0}")]
+    ReadFeeExemption(parameters::ReadError),
+    #[error("Error reading the fee refund policy: {0}")]
+    ReadFeeRefundPolicy(parameters::ReadError),
+    #[error("Error reading the storage gas costs: {0}")]
+    ReadStorageGasCosts(parameters::ReadError),
+    #[error(
+        "The fee payer {payer} has an insufficient balance {balance} of token \
+         {token} to pay the fee {fee}"
+    )]
+    InsufficientFeeBalance {
+        payer: Address,
+        token: Address,
+        fee: token::Amount,
+        balance: token::Amount,
+    },
+    #[error("Write log error: {0}")]
+    WriteLogError(write_log::Error),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -60,14 +118,107 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// If the given tx is a successfully decrypted payload apply the necessary
 /// vps. Otherwise, we include the tx on chain with the gas charge added
 /// but no further validations.
+///
+/// The fee of a wrapper tx is debited when the wrapper is applied, see
+/// [`charge_fee`]. A wrapper whose fee payer is exempt by the fee exemption
+/// protocol parameter pays no fee and, if the exemption also covers the gas,
+/// its gas isn't counted towards the block's gas. The protocol txs never pay
+/// a fee.
+///
+/// A decrypted tx that carries a [`TxBundle`] is applied with
+/// [`apply_bundle`].
+///
+/// The storage access of a tx is metered with the storage gas costs protocol
+/// parameter on top of its compute and reported in
+/// [`TxResult::storage_gas_used`]. The bytes that the tx deletes from the
+/// storage are refunded once it has run.
+///
+/// Each tx and VP wasm execution is interrupted after the `wasm_timeout`, if
+/// any. A wall-clock timeout depends on the speed of the node, so it must not
+/// be given when the tx is applied in a block, otherwise the nodes may
+/// disagree on the result of the tx. It's only meant for the txs applied
+/// outside of a block, like a dry run or a simulated block.
+///
+/// A tx that consumes more gas than the transaction gas limit of the
+/// `block_gas_meter` fails with [`Error::OutOfGas`], whichever step of its
+/// application ran out of gas.
+///
+/// The fee charged for a wrapper tx is reported in [`TxResult::fee_charged`]
+/// and it's queued with the wrapper. The `charged_wrapper` is the queued
+/// wrapper tx whose fee has been charged for a decrypted tx. When the
+/// decrypted tx is rejected by the VPs and the fee refund policy protocol
+/// parameter is [`parameters::FeeRefundPolicy::RefundOnVpRejection`], the
+/// changes of the rejected tx are dropped from the `write_log` and the
+/// charged fee is credited back to the fee payer in their place, see
+/// [`refund_fee`]. The caller then
+/// commits the refund, as reported by [`TxResult::fee_refunded`]. A tx that
+/// fails, e.g. one that can't be decoded or runs out of gas, is still
+/// charged.
+#[allow(clippy::too_many_arguments)]
 pub fn apply_tx<D, H, CA>(
     tx: TxType,
+    charged_wrapper: Option<&QueuedWrapper>,
     tx_length: usize,
     block_gas_meter: &mut BlockGasMeter,
     write_log: &mut WriteLog,
     storage: &Storage<D, H>,
     vp_wasm_cache: &mut VpCache<CA>,
     tx_wasm_cache: &mut TxCache<CA>,
+    wasm_timeout: Option<Duration>,
+) -> Result<TxResult>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+    CA: 'static + WasmCacheAccess + Sync,
+{
+    let mut result = apply_tx_type(
+        tx,
+        tx_length,
+        block_gas_meter,
+        write_log,
+        storage,
+        vp_wasm_cache,
+        tx_wasm_cache,
+        wasm_timeout,
+    )
+    .map_err(|err| {
+        // The gas meter keeps the gas of the failed tx, so this also catches
+        // the gas exhausted inside of the tx and VP wasm executions, which
+        // are reported as runtime errors
+        if block_gas_meter.is_out_of_gas() {
+            Error::OutOfGas {
+                used: block_gas_meter.get_current_transaction_gas(),
+                limit: block_gas_meter.get_transaction_gas_limit(),
+            }
+        } else {
+            err
+        }
+    })?;
+    if let Some(queued) = charged_wrapper {
+        if !result.is_accepted() {
+            let (policy, _gas) = parameters::read_fee_refund_policy(storage)
+                .map_err(Error::ReadFeeRefundPolicy)?;
+            if policy == parameters::FeeRefundPolicy::RefundOnVpRejection {
+                // Only the refund is kept from the rejected tx
+                write_log.drop_tx();
+                result.fee_refunded = refund_fee(queued, write_log, storage)?;
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Apply a given transaction by its type, see [`apply_tx`]
+#[allow(clippy::too_many_arguments)]
+fn apply_tx_type<D, H, CA>(
+    tx: TxType,
+    tx_length: usize,
+    block_gas_meter: &mut BlockGasMeter,
+    write_log: &mut WriteLog,
+    storage: &Storage<D, H>,
+    vp_wasm_cache: &mut VpCache<CA>,
+    tx_wasm_cache: &mut TxCache<CA>,
+    wasm_timeout: Option<Duration>,
 ) -> Result<TxResult>
 where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
@@ -78,9 +229,24 @@ where
     block_gas_meter
         .add_base_transaction_fee(tx_length)
         .map_err(Error::GasError)?;
+    let (storage_gas_costs, _gas) = parameters::read_storage_gas_costs(storage)
+        .map_err(Error::ReadStorageGasCosts)?;
+    block_gas_meter.set_storage_gas_costs(storage_gas_costs);
     match tx {
         TxType::Raw(_) => Err(Error::TxTypeError),
         TxType::Decrypted(DecryptedTx::Decrypted(tx)) => {
+            if let Some(bundle) = TxBundle::from_tx(&tx) {
+                return apply_bundle(
+                    &bundle,
+                    block_gas_meter,
+                    write_log,
+                    storage,
+                    vp_wasm_cache,
+                    tx_wasm_cache,
+                    wasm_timeout,
+                );
+            }
+            check_tx_size(&tx, storage)?;
             let verifiers = execute_tx(
                 &tx,
                 storage,
@@ -88,6 +254,7 @@ where
                 write_log,
                 vp_wasm_cache,
                 tx_wasm_cache,
+                wasm_timeout,
             )?;
 
             let vps_result = check_vps(
@@ -97,14 +264,17 @@ where
                 write_log,
                 &verifiers,
                 vp_wasm_cache,
+                wasm_timeout,
             )?;
 
+            let storage_gas_used = block_gas_meter.get_current_storage_gas();
             let gas_used = block_gas_meter
                 .finalize_transaction()
                 .map_err(Error::GasError)?;
             let initialized_accounts = write_log.get_initialized_accounts();
             let changed_keys = write_log.get_keys();
             let ibc_event = write_log.take_ibc_event();
+            let state_changed = !changed_keys.is_empty();
 
             Ok(TxResult {
                 gas_used,
@@ -112,6 +282,33 @@ where
                 vps_result,
                 initialized_accounts,
                 ibc_event,
+                state_changed,
+                fee_refunded: false,
+                fee_charged: token::Amount::default(),
+                storage_gas_used,
+            })
+        }
+        TxType::Wrapper(wrapper) => {
+            check_wrapper_fee(&wrapper, storage)?;
+            let fee_charged = charge_fee(&wrapper, write_log, storage)?;
+            // An exempt payer isn't charged any fee
+            let state_changed = !write_log.get_keys().is_empty();
+            let exempt_gas = fee_exemption(&wrapper, storage)?
+                .map(|exemption| exemption.exempt_gas)
+                .unwrap_or_default();
+            let gas_used = if exempt_gas {
+                block_gas_meter.discard_transaction();
+                0
+            } else {
+                block_gas_meter
+                    .finalize_transaction()
+                    .map_err(Error::GasError)?
+            };
+            Ok(TxResult {
+                gas_used,
+                state_changed,
+                fee_charged,
+                ..Default::default()
             })
         }
         _ => {
@@ -126,6 +323,319 @@ where
     }
 }
 
+/// Apply the txs of a bundle in order. Each member is validated by the VPs
+/// triggered by its own changes and its changes are then visible to the
+/// following members. If any member fails or is rejected, the changes of all
+/// the members are discarded and the result is the one of the failed member.
+/// The gas of the members that were applied is still charged.
+pub fn apply_bundle<D, H, CA>(
+    bundle: &TxBundle,
+    block_gas_meter: &mut BlockGasMeter,
+    write_log: &mut WriteLog,
+    storage: &Storage<D, H>,
+    vp_wasm_cache: &mut VpCache<CA>,
+    tx_wasm_cache: &mut TxCache<CA>,
+    wasm_timeout: Option<Duration>,
+) -> Result<TxResult>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+    CA: 'static + WasmCacheAccess + Sync,
+{
+    // The members' changes are committed to the block's write log one by one,
+    // so this is restored if the bundle fails
+    let snapshot = write_log.clone();
+    let mut result = TxResult::default();
+    for tx in &bundle.txs {
+        let member = check_tx_size(tx, storage).and_then(|()| {
+            let verifiers = execute_tx(
+                tx,
+                storage,
+                block_gas_meter,
+                write_log,
+                vp_wasm_cache,
+                tx_wasm_cache,
+                wasm_timeout,
+            )?;
+            let vps_result = check_vps(
+                tx,
+                storage,
+                block_gas_meter,
+                write_log,
+                &verifiers,
+                vp_wasm_cache,
+                wasm_timeout,
+            )?;
+            let storage_gas_used = block_gas_meter.get_current_storage_gas();
+            let gas_used = block_gas_meter
+                .finalize_transaction()
+                .map_err(Error::GasError)?;
+            let changed_keys = write_log.get_keys();
+            Ok(TxResult {
+                gas_used,
+                state_changed: !changed_keys.is_empty(),
+                changed_keys,
+                vps_result,
+                initialized_accounts: write_log.get_initialized_accounts(),
+                ibc_event: write_log.take_ibc_event(),
+                fee_refunded: false,
+                fee_charged: token::Amount::default(),
+                storage_gas_used,
+            })
+        });
+        match member {
+            Ok(member) if member.is_accepted() => {
+                write_log.commit_tx();
+                result.gas_used += member.gas_used;
+                result.storage_gas_used += member.storage_gas_used;
+                result.changed_keys.extend(member.changed_keys);
+                result
+                    .vps_result
+                    .accepted_vps
+                    .extend(member.vps_result.accepted_vps);
+                result
+                    .initialized_accounts
+                    .extend(member.initialized_accounts);
+                result.ibc_event = member.ibc_event.or(result.ibc_event);
+                result.state_changed |= member.state_changed;
+            }
+            Ok(mut member) => {
+                *write_log = snapshot;
+                member.gas_used += result.gas_used;
+                member.storage_gas_used += result.storage_gas_used;
+                return Ok(member);
+            }
+            Err(err) => {
+                *write_log = snapshot;
+                return Err(err);
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Get the fee exemption that covers the fee payer of a wrapper tx, if any.
+pub fn fee_exemption<D, H>(
+    wrapper: &WrapperTx,
+    storage: &Storage<D, H>,
+) -> Result<Option<parameters::FeeExemption>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let (exemption, _gas) = parameters::read_fee_exemption(storage)
+        .map_err(Error::ReadFeeExemption)?;
+    Ok(Some(exemption)
+        .filter(|exemption| exemption.is_exempt(&wrapper.fee_payer())))
+}
+
+/// Convert the given amount of the fee token of a wrapper tx to the native
+/// token with the fee token rates protocol parameter, so that it can be
+/// compared with the base fee and with the fees paid in the other tokens.
+/// Fails with [`Error::FeeTokenNotAllowed`] for a token without a rate.
+pub fn fee_in_native_token<D, H>(
+    wrapper: &WrapperTx,
+    amount: token::Amount,
+    storage: &Storage<D, H>,
+) -> Result<token::Amount>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let (rates, _gas) = parameters::read_fee_token_rates(storage)
+        .map_err(Error::ReadFeeTokenRates)?;
+    rates
+        .to_native(&wrapper.fee.token, amount)
+        .ok_or_else(|| Error::FeeTokenNotAllowed(wrapper.fee.token.clone()))
+}
+
+/// Get the minimum fee that a wrapper tx must pay in the native token, i.e.
+/// the current base fee multiplied by the wrapper's gas limit. An exempt fee
+/// payer's minimum fee is zero.
+pub fn min_fee<D, H>(
+    wrapper: &WrapperTx,
+    storage: &Storage<D, H>,
+) -> Result<token::Amount>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    if fee_exemption(wrapper, storage)?.is_some() {
+        return Ok(token::Amount::default());
+    }
+    let (base_fee, _gas) =
+        parameters::read_base_fee(storage).map_err(Error::ReadBaseFee)?;
+    let min_fee = u64::from(base_fee)
+        .checked_mul(u64::from(&wrapper.gas_limit))
+        .unwrap_or(u64::MAX);
+    Ok(token::Amount::from(min_fee))
+}
+
+/// Check that the fee of a wrapper tx is paid in a token with a conversion
+/// rate, that its fee with a gas price is consistent with its gas limit and
+/// that the gas price, converted to the native token, covers the current base
+/// fee. The fee converted to the native token must also cover the
+/// [`min_fee`]. The fee of an exempt fee payer isn't checked.
+///
+/// This is checked both when a wrapper tx is validated for the mempool or in
+/// a block proposal and when it's applied.
+pub fn check_wrapper_fee<D, H>(
+    wrapper: &WrapperTx,
+    storage: &Storage<D, H>,
+) -> Result<()>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    if fee_exemption(wrapper, storage)?.is_some() {
+        return Ok(());
+    }
+    wrapper
+        .fee
+        .check_gas_price(&wrapper.gas_limit)
+        .map_err(Error::WrapperFee)?;
+    let fee = fee_in_native_token(wrapper, wrapper.fee.amount, storage)?;
+    if let Some(gas_price) = wrapper.fee.gas_price {
+        let gas_price = fee_in_native_token(wrapper, gas_price, storage)?;
+        let (base_fee, _gas) =
+            parameters::read_base_fee(storage).map_err(Error::ReadBaseFee)?;
+        if gas_price < base_fee {
+            return Err(Error::GasPriceBelowBaseFee {
+                gas_price,
+                base_fee,
+            });
+        }
+    }
+    let min_fee = min_fee(wrapper, storage)?;
+    if fee < min_fee {
+        return Err(Error::FeeBelowMinFee { fee, min_fee });
+    }
+    Ok(())
+}
+
+/// Debit the fee of a wrapper tx from the fee payer's balance. The fee is
+/// debited before the wrapped tx is decrypted and executed, so a tx whose
+/// payer can't cover the fee fails before doing any work and the wrapped tx
+/// then runs against the balance left after the fee. The fee is burned.
+///
+/// The debit is written to the current tx's write log, so on an error it's
+/// dropped together with the rest of the tx and the balance is untouched.
+///
+/// Nothing is debited from an exempt fee payer, see [`fee_exemption`].
+/// Returns the debited amount.
+pub fn charge_fee<D, H>(
+    wrapper: &WrapperTx,
+    write_log: &mut WriteLog,
+    storage: &Storage<D, H>,
+) -> Result<token::Amount>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let fee = wrapper.fee.amount;
+    if fee == token::Amount::default()
+        || fee_exemption(wrapper, storage)?.is_some()
+    {
+        return Ok(token::Amount::default());
+    }
+    let payer = wrapper.fee_payer();
+    let balance_key = token::balance_key(&wrapper.fee.token, &payer);
+    let mut balance = read_balance(&balance_key, write_log, storage)?;
+    if balance < fee {
+        return Err(Error::InsufficientFeeBalance {
+            payer,
+            token: wrapper.fee.token.clone(),
+            fee,
+            balance,
+        });
+    }
+    balance.spend(&fee);
+    write_log
+        .write(
+            &balance_key,
+            balance
+                .try_to_vec()
+                .expect("Encoding an amount shouldn't fail"),
+        )
+        .map_err(Error::WriteLogError)?;
+    Ok(fee)
+}
+
+/// Credit the fee charged for a queued wrapper tx back to the fee payer's
+/// balance, undoing [`charge_fee`]. Exactly the amount recorded in the queue
+/// when the wrapper was applied is refunded, as the fee parameters may have
+/// changed since. The refund is written to the current tx's write log.
+/// Returns `false` when there is nothing to refund, because no fee has been
+/// charged.
+pub fn refund_fee<D, H>(
+    queued: &QueuedWrapper,
+    write_log: &mut WriteLog,
+    storage: &Storage<D, H>,
+) -> Result<bool>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let fee = queued.charged_fee;
+    if fee == token::Amount::default() {
+        return Ok(false);
+    }
+    let wrapper = &queued.wrapper;
+    let balance_key =
+        token::balance_key(&wrapper.fee.token, &wrapper.fee_payer());
+    let mut balance = read_balance(&balance_key, write_log, storage)?;
+    balance.receive(&fee);
+    write_log
+        .write(
+            &balance_key,
+            balance
+                .try_to_vec()
+                .expect("Encoding an amount shouldn't fail"),
+        )
+        .map_err(Error::WriteLogError)?;
+    Ok(true)
+}
+
+/// Read a token balance with the changes in the write log, if any. A missing
+/// balance is zero.
+fn read_balance<D, H>(
+    balance_key: &storage::Key,
+    write_log: &WriteLog,
+    storage: &Storage<D, H>,
+) -> Result<token::Amount>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let balance = match write_log.read(balance_key).0 {
+        Some(StorageModification::Write { value }) => {
+            token::Amount::try_from_slice(value).ok()
+        }
+        Some(_) => None,
+        None => storage
+            .read_with_staged(balance_key)
+            .map_err(Error::StorageError)?
+            .0
+            .and_then(|value| token::Amount::try_from_slice(&value).ok()),
+    };
+    Ok(balance.unwrap_or_default())
+}
+
+/// Check that the sizes of a tx's code and data are within the limits set by
+/// the protocol parameters.
+pub fn check_tx_size<D, H>(tx: &Tx, storage: &Storage<D, H>) -> Result<()>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let (tx_size_limits, _gas) = parameters::read_tx_size_limits(storage)
+        .map_err(Error::ReadTxSizeLimits)?;
+    let data_size = tx.data.as_ref().map(Vec::len).unwrap_or_default();
+    tx_size_limits
+        .check(tx.code.len(), data_size)
+        .map_err(Error::TxSize)
+}
+
 /// Execute a transaction code. Returns verifiers requested by the transaction.
 fn execute_tx<D, H, CA>(
     tx: &Tx,
@@ -134,6 +644,7 @@ fn execute_tx<D, H, CA>(
     write_log: &mut WriteLog,
     vp_wasm_cache: &mut VpCache<CA>,
     tx_wasm_cache: &mut TxCache<CA>,
+    wasm_timeout: Option<Duration>,
 ) -> Result<BTreeSet<Address>>
 where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
@@ -145,7 +656,7 @@ where
         .map_err(Error::GasError)?;
     let empty = vec![];
     let tx_data = tx.data.as_ref().unwrap_or(&empty);
-    wasm::run::tx(
+    let verifiers = wasm::run::tx(
         storage,
         write_log,
         gas_meter,
@@ -153,8 +664,45 @@ where
         tx_data,
         vp_wasm_cache,
         tx_wasm_cache,
+        wasm_timeout,
     )
-    .map_err(Error::TxRunnerError)
+    .map_err(Error::TxRunnerError)?;
+    refund_storage_deletions(storage, write_log, gas_meter)?;
+    Ok(verifiers)
+}
+
+/// Refund the storage gas of the bytes deleted from the storage by a tx, i.e.
+/// of the keys that it deleted together with their values before the tx. A
+/// key that was only written by the tx itself before it deleted it frees
+/// nothing and isn't refunded.
+fn refund_storage_deletions<D, H>(
+    storage: &Storage<D, H>,
+    write_log: &WriteLog,
+    gas_meter: &mut BlockGasMeter,
+) -> Result<()>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    for key in write_log.get_keys() {
+        if !matches!(write_log.read(&key).0, Some(StorageModification::Delete))
+        {
+            continue;
+        }
+        let deleted_len = match write_log.read_pre(&key).0 {
+            Some(StorageModification::Write { value }) => Some(value.len()),
+            Some(_) => None,
+            None => storage
+                .read_with_staged(&key)
+                .map_err(Error::StorageError)?
+                .0
+                .map(|value| value.len()),
+        };
+        if let Some(len) = deleted_len {
+            gas_meter.refund_storage_deletion((key.len() + len) as u64);
+        }
+    }
+    Ok(())
 }
 
 /// Check the acceptance of a transaction by validity predicates
@@ -165,6 +713,7 @@ fn check_vps<D, H, CA>(
     write_log: &WriteLog,
     verifiers_from_tx: &BTreeSet<Address>,
     vp_wasm_cache: &mut VpCache<CA>,
+    wasm_timeout: Option<Duration>,
 ) -> Result<VpsResult>
 where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
@@ -184,6 +733,7 @@ where
         write_log,
         initial_gas,
         vp_wasm_cache,
+        wasm_timeout,
     )?;
     tracing::debug!("Total VPs gas cost {:?}", vps_result.gas_used);
 
@@ -194,7 +744,23 @@ where
     Ok(vps_result)
 }
 
-/// Execute verifiers' validity predicates
+/// The order in which the validity predicates of a tx's verifiers are
+/// evaluated: sorted by the encoding of the verifiers' addresses. The VPs may
+/// run in parallel, but their results, including the errors and the gas used
+/// by each of them, are merged in this order, so that every node evaluates
+/// the VPs of a tx to the same result and the same gas total.
+pub fn vp_execution_order(verifiers: &BTreeSet<Address>) -> Vec<&Address> {
+    let mut order: Vec<&Address> = verifiers
+        .iter()
+        // TODO temporary pending on <https://github.com/anoma/anoma/issues/193>
+        .filter(|addr| !matches!(addr, Address::Implicit(_)))
+        .collect();
+    order.sort_by_cached_key(|addr| addr.encode());
+    order
+}
+
+/// Execute verifiers' validity predicates in the [`vp_execution_order`]
+#[allow(clippy::too_many_arguments)]
 fn execute_vps<D, H, CA>(
     verifiers: BTreeSet<Address>,
     keys_changed: BTreeSet<storage::Key>,
@@ -203,17 +769,17 @@ fn execute_vps<D, H, CA>(
     write_log: &WriteLog,
     initial_gas: u64,
     vp_wasm_cache: &mut VpCache<CA>,
+    wasm_timeout: Option<Duration>,
 ) -> Result<VpsResult>
 where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
     CA: 'static + WasmCacheAccess + Sync,
 {
-    verifiers
+    let results: Vec<Result<VpsResult>> = vp_execution_order(&verifiers)
         .par_iter()
-        // TODO temporary pending on <https://github.com/anoma/anoma/issues/193>
-        .filter(|addr| !matches!(addr, Address::Implicit(_)))
-        .try_fold(VpsResult::default, |mut result, addr| {
+        .map(|&addr| {
+            let mut result = VpsResult::default();
             let mut gas_meter = VpGasMeter::new(initial_gas);
             let accept = match &addr {
                 Address::Established(_) => {
@@ -238,6 +804,7 @@ where
                         &keys_changed,
                         &verifiers,
                         vp_wasm_cache.clone(),
+                        wasm_timeout,
                     )
                     .map_err(Error::VpRunnerError)
                 }
@@ -338,8 +905,8 @@ where
                 Address::Implicit(_) => unreachable!(),
             };
 
-            // Returning error from here will short-circuit the VP parallel
-            // execution. It's important that we only short-circuit gas
+            // Returning error from here will short-circuit the merge of the
+            // VP results. It's important that we only short-circuit gas
             // errors to get deterministic gas costs
             result.gas_used.set(&gas_meter).map_err(Error::GasError)?;
             match accept {
@@ -361,9 +928,12 @@ where
                 },
             }
         })
-        .try_reduce(VpsResult::default, |a, b| {
-            merge_vp_results(a, b, initial_gas)
-        })
+        .collect();
+    // The results are collected in the execution order, which doesn't depend
+    // on the scheduling of the parallel runs
+    results.into_iter().try_fold(VpsResult::default(), |a, b| {
+        merge_vp_results(a, b?, initial_gas)
+    })
 }
 
 /// Merge VP results from parallel runs
@@ -380,9 +950,9 @@ fn merge_vp_results(
     errors.append(&mut b.errors);
     let mut gas_used = a.gas_used;
 
-    // Returning error from here will short-circuit the VP parallel execution.
-    // It's important that we only short-circuit gas errors to get deterministic
-    // gas costs
+    // Returning error from here will short-circuit the merge of the VP
+    // results. It's important that we only short-circuit gas errors to get
+    // deterministic gas costs
 
     gas_used
         .merge(&mut b.gas_used, initial_gas)
@@ -395,3 +965,63 @@ fn merge_vp_results(
         errors,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::address::testing::{
+        established_address_1, established_address_2, gen_implicit_address,
+    };
+    use anoma::types::address::xan;
+
+    use super::*;
+
+    /// Find the VP execution order of a transfer with the given balance
+    /// changes, written in the given order, and signed by the source
+    fn transfer_vp_order(
+        source: &Address,
+        balance_keys: &[storage::Key],
+    ) -> Vec<Address> {
+        let mut write_log = WriteLog::default();
+        for key in balance_keys {
+            write_log
+                .write(key, token::Amount::whole(1).try_to_vec().unwrap())
+                .expect("Test failed");
+        }
+        let verifiers_from_tx = BTreeSet::from([source.clone()]);
+        let (verifiers, _keys_changed) =
+            write_log.verifiers_and_changed_keys(&verifiers_from_tx);
+        vp_execution_order(&verifiers)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Test that the VPs of a transfer touching multiple accounts are
+    /// evaluated in the same order, regardless of the order of the changes,
+    /// sorted by the encoding of their addresses
+    #[test]
+    fn test_vp_execution_order_is_stable() {
+        let source = established_address_1();
+        let target = established_address_2();
+        let implicit = gen_implicit_address();
+        let token = xan();
+        let keys = vec![
+            token::balance_key(&token, &source),
+            token::balance_key(&token, &target),
+            token::balance_key(&token, &implicit),
+        ];
+        let mut reversed_keys = keys.clone();
+        reversed_keys.reverse();
+
+        let order = transfer_vp_order(&source, &keys);
+        for _ in 0..10 {
+            assert_eq!(transfer_vp_order(&source, &keys), order);
+        }
+        assert_eq!(transfer_vp_order(&source, &reversed_keys), order);
+
+        // The implicit addresses have no VPs to run
+        let mut expected = vec![source, target, token];
+        expected.sort_by_key(Address::encode);
+        assert_eq!(order, expected);
+    }
+}
@@ -3,27 +3,328 @@
 use std::fmt::Display;
 use std::str::FromStr;
 
-use anoma::types::address::Address;
+use anoma::ledger::parameters::ProtocolUpgrade;
+use anoma::types::address::{self, Address};
+use anoma::types::hash::Hash;
+use anoma::types::key::common;
 use anoma::types::storage;
+use anoma::types::time::DateTimeUtc;
+use anoma::types::transaction::{Fee, TxResult};
+use borsh::{BorshDeserialize, BorshSerialize};
 #[cfg(not(feature = "ABCI"))]
 use tendermint::abci::Path as AbciPath;
 #[cfg(feature = "ABCI")]
 use tendermint_stable::abci::Path as AbciPath;
 use thiserror::Error;
 
+use crate::node::ledger::events::BlockEvent;
+
 /// RPC query path
 #[derive(Debug, Clone)]
 pub enum Path {
-    /// Dry run a transaction
+    /// Dry run a transaction, against the state at the query's height if
+    /// it's older than the last committed block
     DryRunTx,
+    /// Simulate applying a sequence of transactions in a block
+    SimulateBlock,
     /// Epoch of the last committed block
     Epoch,
+    /// Progress of the current epoch towards its end
+    EpochProgress,
+    /// The height of the first block of the given epoch
+    EpochStartHeight(storage::Epoch),
     /// Read a storage value with exact storage key
     Value(storage::Key),
     /// Read a range of storage values with a matching key prefix
     Prefix(storage::Key),
     /// Check if the given storage key exists
     HasKey(storage::Key),
+    /// Describe the kind of the account with the given address
+    AccountMetadata(Address),
+    /// The events emitted in the committed block with the given height,
+    /// optionally only the ones of the given type
+    BlockEvents {
+        height: storage::BlockHeight,
+        event_type: Option<String>,
+    },
+    /// The summary of the committed block with the given height
+    BlockSummary(storage::BlockHeight),
+    /// The transactions in this node's mempool, optionally with a listing of
+    /// the transactions
+    Mempool { list_txs: bool },
+    /// The status of the node
+    NodeStatus,
+    /// The number and the sizes of the values in storage, optionally only of
+    /// the ones with a matching key prefix
+    StorageSize(Option<storage::Key>),
+    /// The consensus validator set of the given epoch or of the epoch of the
+    /// last committed block
+    ValidatorSet(Option<storage::Epoch>),
+}
+
+impl Path {
+    /// Can the query be served at a historical height, given by the `height`
+    /// of the ABCI query? The other queries are only served at the last
+    /// committed height.
+    pub fn supports_height(&self) -> bool {
+        matches!(
+            self,
+            Path::DryRunTx
+                | Path::Epoch
+                | Path::Value(_)
+                | Path::Prefix(_)
+                | Path::HasKey(_)
+        )
+    }
+
+    /// Can the result of the query be proven with a merkle proof, requested
+    /// with the `prove` flag of the ABCI query? Only the queries that read
+    /// storage keys directly can be proven, the results of the others are
+    /// computed by the node.
+    pub fn supports_proof(&self) -> bool {
+        matches!(self, Path::Value(_) | Path::Prefix(_) | Path::HasKey(_))
+    }
+
+    /// Is the query costly to serve, because it runs txs and VPs or scans the
+    /// storage? These queries can have a stricter rate limit than the rest.
+    pub fn is_heavy(&self) -> bool {
+        matches!(
+            self,
+            Path::DryRunTx
+                | Path::SimulateBlock
+                | Path::Prefix(_)
+                | Path::StorageSize(_)
+        )
+    }
+}
+
+/// The status of a node, as returned by the [`Path::NodeStatus`] query
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct NodeStatus {
+    /// The time at which the chain starts. It's `None` on chains initialized
+    /// before the genesis time has been stored.
+    pub genesis_time: Option<DateTimeUtc>,
+    /// Has the node's wall-clock time reached the genesis time? Blocks are
+    /// not processed before it has.
+    pub genesis_time_reached: bool,
+    /// The height of the last committed block
+    pub last_height: storage::BlockHeight,
+    /// The protocol version of the chain at the last committed block
+    pub protocol_version: u64,
+    /// The protocol version supported by the node's binary
+    pub supported_protocol_version: u64,
+    /// The scheduled upgrade of the protocol version, if any
+    pub protocol_upgrade: Option<ProtocolUpgrade>,
+}
+
+/// The metadata of an account, as returned by the
+/// [`Path::AccountMetadata`] query
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct AccountMetadata {
+    /// The address of the account
+    pub address: Address,
+    /// The name of the built-in VP template that matches the account's VP,
+    /// if any
+    pub vp_template: Option<String>,
+    /// Is the account registered as a validator in the PoS system?
+    pub is_validator: bool,
+    /// The IDs of the governance proposals authored by the account
+    pub authored_proposals: Vec<u64>,
+}
+
+/// The outcome of a transaction dry run with the [`Path::DryRunTx`] query
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct DryRunResult {
+    /// The reason why the mempool would reject the transaction, if it would.
+    /// Such a transaction is not applied.
+    pub mempool_error: Option<String>,
+    /// The result of applying the transaction, if it passed the mempool
+    /// validation
+    pub tx_result: Option<TxResult>,
+}
+
+impl DryRunResult {
+    /// Check if the transaction would be accepted by the mempool and by all
+    /// the triggered validity predicates
+    pub fn is_accepted(&self) -> bool {
+        self.mempool_error.is_none()
+            && self
+                .tx_result
+                .as_ref()
+                .map(TxResult::is_accepted)
+                .unwrap_or_default()
+    }
+}
+
+/// The outcome of a block simulated with the [`Path::SimulateBlock`] query
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct BlockSimResult {
+    /// The outcome of each transaction, in the order in which they were
+    /// applied
+    pub txs: Vec<SimulatedTx>,
+    /// The total gas used by the block's transactions
+    pub gas_used: u64,
+    /// The merkle root of the storage after committing the block
+    pub root: Vec<u8>,
+}
+
+/// The outcome of a transaction in a simulated block
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub enum SimulatedTx {
+    /// The transaction was applied. Its changes are kept for the
+    /// following transactions only if all the validity predicates accepted
+    /// them.
+    Applied(TxResult),
+    /// The transaction couldn't be decoded, decrypted or applied
+    Failed(String),
+}
+
+/// The events of a committed block, as returned by the [`Path::BlockEvents`]
+/// query
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct BlockEvents {
+    /// The events matching the query, in the order in which they were
+    /// emitted
+    pub events: Vec<BlockEvent>,
+    /// Set when there were more matching events than
+    /// [`MAX_BLOCK_EVENTS`] and the rest have been left out
+    pub truncated: bool,
+}
+
+/// The maximum number of events returned by the [`Path::BlockEvents`] query
+pub const MAX_BLOCK_EVENTS: usize = 1000;
+
+/// The summary of a committed block, as returned by the
+/// [`Path::BlockSummary`] query
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct BlockSummary {
+    /// The height of the block
+    pub height: storage::BlockHeight,
+    /// The merkle root of the storage after committing the block
+    pub root: Vec<u8>,
+    /// The epoch of the block
+    pub epoch: storage::Epoch,
+    /// The number of transactions applied in the block
+    pub num_txs: u64,
+    /// The total gas used by the block's transactions
+    pub gas_used: u64,
+}
+
+/// The sizes of the values in storage, as returned by the
+/// [`Path::StorageSize`] query
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct StorageSize {
+    /// The number of keys
+    pub num_keys: u64,
+    /// The total size of the values in bytes
+    pub total_bytes: u64,
+    /// The number of keys and the size of their values by the category of
+    /// the keys, ordered by the category's name
+    pub categories: Vec<StorageSizeCategory>,
+    /// The largest values, at most [`MAX_LARGEST_VALUES`], from the largest
+    pub largest: Vec<StorageValueSize>,
+}
+
+/// The keys of a category, e.g. the token balances or the validity
+/// predicates, in the [`Path::StorageSize`] query
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct StorageSizeCategory {
+    /// The name of the category
+    pub name: String,
+    /// The number of keys in the category
+    pub num_keys: u64,
+    /// The total size of the category's values in bytes
+    pub bytes: u64,
+}
+
+/// The size of a value in storage
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct StorageValueSize {
+    /// The storage key of the value
+    pub key: storage::Key,
+    /// The size of the value in bytes
+    pub bytes: u64,
+}
+
+/// The maximum number of the largest values returned by the
+/// [`Path::StorageSize`] query
+pub const MAX_LARGEST_VALUES: usize = 10;
+
+/// The contents of this node's mempool, as returned by the [`Path::Mempool`]
+/// query
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct MempoolContents {
+    /// The number of transactions waiting to be included in a block
+    pub num_txs: u64,
+    /// The sum of the gas limits of the wrapper transactions
+    pub total_gas: u64,
+    /// The transactions with the highest fees, if the listing was requested
+    pub txs: Vec<MempoolTx>,
+    /// Set when there were more transactions than [`MAX_MEMPOOL_TXS`] and
+    /// the rest have been left out of the listing
+    pub truncated: bool,
+}
+
+/// A transaction in the mempool
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct MempoolTx {
+    /// The hash of the transaction
+    pub hash: Hash,
+    /// The fee of a wrapper transaction
+    pub fee: Option<Fee>,
+    /// The gas limit of a wrapper transaction
+    pub gas_limit: Option<u64>,
+}
+
+/// The maximum number of transactions listed by the [`Path::Mempool`] query
+pub const MAX_MEMPOOL_TXS: usize = 100;
+
+/// The consensus validator set of an epoch, as returned by the
+/// [`Path::ValidatorSet`] query. Unlike the PoS validator set, it only has the
+/// active validators, which are the ones given to Tendermint.
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct ConsensusValidatorSet {
+    /// The epoch of the validator set
+    pub epoch: storage::Epoch,
+    /// The validators, from the one with the highest voting power
+    pub validators: Vec<ConsensusValidator>,
+}
+
+/// A validator in the [`ConsensusValidatorSet`]
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct ConsensusValidator {
+    /// The address of the validator
+    pub address: Address,
+    /// The voting power of the validator in the epoch
+    pub voting_power: u64,
+    /// The consensus key of the validator in the epoch
+    pub consensus_key: common::PublicKey,
+}
+
+impl ConsensusValidatorSet {
+    /// The sum of the voting powers of the validators
+    pub fn total_voting_power(&self) -> u64 {
+        self.validators
+            .iter()
+            .map(|validator| validator.voting_power)
+            .sum()
+    }
+}
+
+impl AccountMetadata {
+    /// A friendly label for the kind of the account.
+    pub fn kind(&self) -> &str {
+        if self.is_validator {
+            return "validator";
+        }
+        match &self.address {
+            Address::Implicit(_) => "implicit",
+            Address::Internal(_) => "internal",
+            Address::Established(_) => {
+                self.vp_template.as_deref().unwrap_or("custom")
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -35,16 +336,32 @@ pub struct BalanceQuery {
 }
 
 const DRY_RUN_TX_PATH: &str = "dry_run_tx";
+const SIMULATE_BLOCK_PATH: &str = "simulate_block";
 const EPOCH_PATH: &str = "epoch";
+const EPOCH_PROGRESS_PATH: &str = "epoch_progress";
+const EPOCH_START_HEIGHT_PREFIX: &str = "epoch_start_height";
 const VALUE_PREFIX: &str = "value";
 const PREFIX_PREFIX: &str = "prefix";
 const HAS_KEY_PREFIX: &str = "has_key";
+const ACCOUNT_METADATA_PREFIX: &str = "account_metadata";
+const BLOCK_EVENTS_PREFIX: &str = "block_events";
+const BLOCK_SUMMARY_PREFIX: &str = "block_summary";
+const MEMPOOL_PATH: &str = "mempool";
+const MEMPOOL_TXS_PATH: &str = "mempool/txs";
+const NODE_STATUS_PATH: &str = "node_status";
+const STORAGE_SIZE_PATH: &str = "storage_size";
+const VALIDATOR_SET_PATH: &str = "validator_set";
 
 impl Display for Path {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Path::DryRunTx => write!(f, "{}", DRY_RUN_TX_PATH),
+            Path::SimulateBlock => write!(f, "{}", SIMULATE_BLOCK_PATH),
             Path::Epoch => write!(f, "{}", EPOCH_PATH),
+            Path::EpochProgress => write!(f, "{}", EPOCH_PROGRESS_PATH),
+            Path::EpochStartHeight(epoch) => {
+                write!(f, "{}/{}", EPOCH_START_HEIGHT_PREFIX, epoch)
+            }
             Path::Value(storage_key) => {
                 write!(f, "{}/{}", VALUE_PREFIX, storage_key)
             }
@@ -54,6 +371,37 @@ impl Display for Path {
             Path::HasKey(storage_key) => {
                 write!(f, "{}/{}", HAS_KEY_PREFIX, storage_key)
             }
+            Path::AccountMetadata(address) => {
+                write!(f, "{}/{}", ACCOUNT_METADATA_PREFIX, address.encode())
+            }
+            Path::BlockEvents {
+                height,
+                event_type: None,
+            } => {
+                write!(f, "{}/{}", BLOCK_EVENTS_PREFIX, height)
+            }
+            Path::BlockEvents {
+                height,
+                event_type: Some(event_type),
+            } => {
+                write!(f, "{}/{}/{}", BLOCK_EVENTS_PREFIX, height, event_type)
+            }
+            Path::BlockSummary(height) => {
+                write!(f, "{}/{}", BLOCK_SUMMARY_PREFIX, height)
+            }
+            Path::Mempool { list_txs: false } => write!(f, "{}", MEMPOOL_PATH),
+            Path::Mempool { list_txs: true } => {
+                write!(f, "{}", MEMPOOL_TXS_PATH)
+            }
+            Path::NodeStatus => write!(f, "{}", NODE_STATUS_PATH),
+            Path::StorageSize(None) => write!(f, "{}", STORAGE_SIZE_PATH),
+            Path::StorageSize(Some(prefix)) => {
+                write!(f, "{}/{}", STORAGE_SIZE_PATH, prefix)
+            }
+            Path::ValidatorSet(None) => write!(f, "{}", VALIDATOR_SET_PATH),
+            Path::ValidatorSet(Some(epoch)) => {
+                write!(f, "{}/{}", VALIDATOR_SET_PATH, epoch)
+            }
         }
     }
 }
@@ -64,8 +412,20 @@ impl FromStr for Path {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             DRY_RUN_TX_PATH => Ok(Self::DryRunTx),
+            SIMULATE_BLOCK_PATH => Ok(Self::SimulateBlock),
             EPOCH_PATH => Ok(Self::Epoch),
+            EPOCH_PROGRESS_PATH => Ok(Self::EpochProgress),
+            MEMPOOL_PATH => Ok(Self::Mempool { list_txs: false }),
+            MEMPOOL_TXS_PATH => Ok(Self::Mempool { list_txs: true }),
+            NODE_STATUS_PATH => Ok(Self::NodeStatus),
+            STORAGE_SIZE_PATH => Ok(Self::StorageSize(None)),
+            VALIDATOR_SET_PATH => Ok(Self::ValidatorSet(None)),
             _ => match s.split_once('/') {
+                Some((EPOCH_START_HEIGHT_PREFIX, epoch)) => {
+                    let epoch =
+                        epoch.parse().map_err(PathParseError::InvalidEpoch)?;
+                    Ok(Self::EpochStartHeight(epoch))
+                }
                 Some((VALUE_PREFIX, storage_key)) => {
                     let key = storage::Key::parse(storage_key)
                         .map_err(PathParseError::InvalidStorageKey)?;
@@ -81,6 +441,41 @@ impl FromStr for Path {
                         .map_err(PathParseError::InvalidStorageKey)?;
                     Ok(Self::HasKey(key))
                 }
+                Some((ACCOUNT_METADATA_PREFIX, address)) => {
+                    let address = Address::decode(address)
+                        .map_err(PathParseError::InvalidAddress)?;
+                    Ok(Self::AccountMetadata(address))
+                }
+                Some((BLOCK_EVENTS_PREFIX, rest)) => {
+                    let (height, event_type) = match rest.split_once('/') {
+                        Some((height, event_type)) => {
+                            (height, Some(event_type.to_owned()))
+                        }
+                        None => (rest, None),
+                    };
+                    let height = height
+                        .parse()
+                        .map(storage::BlockHeight)
+                        .map_err(PathParseError::InvalidHeight)?;
+                    Ok(Self::BlockEvents { height, event_type })
+                }
+                Some((BLOCK_SUMMARY_PREFIX, height)) => {
+                    let height = height
+                        .parse()
+                        .map(storage::BlockHeight)
+                        .map_err(PathParseError::InvalidHeight)?;
+                    Ok(Self::BlockSummary(height))
+                }
+                Some((STORAGE_SIZE_PATH, prefix)) => {
+                    let prefix = storage::Key::parse(prefix)
+                        .map_err(PathParseError::InvalidStorageKey)?;
+                    Ok(Self::StorageSize(Some(prefix)))
+                }
+                Some((VALIDATOR_SET_PATH, epoch)) => {
+                    let epoch =
+                        epoch.parse().map_err(PathParseError::InvalidEpoch)?;
+                    Ok(Self::ValidatorSet(Some(epoch)))
+                }
                 _ => Err(PathParseError::InvalidPath(s.to_string())),
             },
         }
@@ -103,4 +498,10 @@ pub enum PathParseError {
     InvalidPath(String),
     #[error("Invalid storage key: {0}")]
     InvalidStorageKey(storage::Error),
+    #[error("Invalid address: {0}")]
+    InvalidAddress(address::Error),
+    #[error("Invalid epoch: {0}")]
+    InvalidEpoch(std::num::ParseIntError),
+    #[error("Invalid block height: {0}")]
+    InvalidHeight(std::num::ParseIntError),
 }
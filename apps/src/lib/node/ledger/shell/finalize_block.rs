@@ -1,20 +1,29 @@
 //! Implementation of the `FinalizeBlock` ABCI++ method for the Shell
 
-use anoma::types::storage::BlockHash;
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashSet};
+
+use anoma::ledger::parameters::TxOrdering;
+use anoma::ledger::storage::write_log::StorageModification;
+use anoma::types::hash::Hash;
+use anoma::types::storage::{BlockHash, DbKeySeg};
+use anoma::types::transaction::bundle::TxBundle;
+use borsh::BorshDeserialize;
 #[cfg(not(feature = "ABCI"))]
 use tendermint::block::Header;
 #[cfg(not(feature = "ABCI"))]
-use tendermint_proto::abci::Evidence;
+use tendermint_proto::abci::{Evidence, VoteInfo};
 #[cfg(not(feature = "ABCI"))]
 use tendermint_proto::crypto::PublicKey as TendermintPublicKey;
 #[cfg(feature = "ABCI")]
-use tendermint_proto_abci::abci::Evidence;
+use tendermint_proto_abci::abci::{Evidence, VoteInfo};
 #[cfg(feature = "ABCI")]
 use tendermint_proto_abci::crypto::PublicKey as TendermintPublicKey;
 #[cfg(feature = "ABCI")]
 use tendermint_stable::block::Header;
 
 use super::*;
+use crate::node::ledger::events::BlockEvent;
 
 impl<D, H> Shell<D, H>
 where
@@ -34,6 +43,25 @@ where
     /// Incoming wrapper txs need no further validation. They
     /// are added to the block.
     ///
+    /// Before they are applied, the txs are sorted by the deterministic key
+    /// set by the [`TxOrdering`] protocol parameter.
+    ///
+    /// A block produced before the chain's genesis time is refused with an
+    /// error, see [`Shell::check_genesis_time`]. So is a block that requires
+    /// a newer protocol version than the one supported by this binary, see
+    /// [`Shell::check_protocol_version`]. A scheduled upgrade of the protocol
+    /// version is applied at the beginning of the block at its height.
+    ///
+    /// The fee of a wrapper tx is debited when the wrapper is applied, before
+    /// its inner tx is decrypted and executed. A wrapper whose fee can't be
+    /// paid is rejected and its inner tx is never executed. Under the
+    /// [`parameters::FeeRefundPolicy::RefundOnVpRejection`] protocol
+    /// parameter, the fee is refunded when the inner tx is rejected by the
+    /// VPs and the tx event reports it in its `fee_refunded` attribute.
+    ///
+    /// A tx that is identical to a tx already applied in the same block is
+    /// a replay and it's rejected without being applied again.
+    ///
     /// Error codes:
     ///   0: Ok
     ///   1: Invalid tx
@@ -41,16 +69,44 @@ where
     ///   3: Wasm runtime error
     ///   4: Invalid order of decrypted txs
     ///   5. More decrypted txs than expected
+    ///   6: Undecryptable
+    ///   7: Replay of a tx applied in the same block
+    ///   8: Out of gas
     pub fn finalize_block(
         &mut self,
         req: shim::request::FinalizeBlock,
     ) -> Result<shim::response::FinalizeBlock> {
+        let block_time: DateTimeUtc = req
+            .header
+            .time
+            .try_into()
+            .expect("Time conversion shouldn't failed");
+        self.check_genesis_time(block_time)?;
+        self.check_protocol_version(BlockHeight(req.header.height.into()))?;
         let mut response = shim::response::FinalizeBlock::default();
         // begin the next block and check if a new epoch began
-        let (height, new_epoch) =
-            self.update_state(req.header, req.hash, req.byzantine_validators);
+        let (height, new_epoch) = self.update_state(
+            req.header,
+            req.hash,
+            req.byzantine_validators,
+            req.last_commit_votes,
+        );
+
+        let mut txs = req.txs;
+        self.order_txs(&mut txs);
+        // The staging of the block's changes affects the gas of the storage
+        // access, so all the nodes must stage them at the same threshold
+        let (flush_threshold, _gas) =
+            parameters::read_write_log_flush_threshold(&self.storage).expect(
+                "Couldn't read the write log flush threshold parameter",
+            );
+        self.write_log.set_flush_threshold(flush_threshold);
+        // The events emitted in this block
+        let mut events: Vec<Event> = vec![];
+        // The hashes of the txs applied in this block, to reject the replays
+        let mut applied_txs: HashSet<Hash> = HashSet::new();
 
-        for processed_tx in &req.txs {
+        for processed_tx in &txs {
             let tx = if let Ok(tx) = Tx::try_from(processed_tx.tx.as_ref()) {
                 tx
             } else {
@@ -93,7 +149,7 @@ where
                 tx_result["info"] =
                     format!("Tx rejected: {}", &processed_tx.result.info);
                 tx_result["gas_used"] = "0".into();
-                response.events.push(tx_result.into());
+                events.push(tx_result);
                 continue;
             }
 
@@ -119,7 +175,7 @@ where
                 tx_result["info"] =
                     format!("Tx rejected: {}", &processed_tx.result.info);
                 tx_result["gas_used"] = "0".into();
-                response.events.push(tx_result.into());
+                events.push(tx_result);
                 // if the rejected tx was decrypted, remove it
                 // from the queue of txs to be processed
                 if let TxType::Decrypted(_) = &tx_type {
@@ -128,39 +184,63 @@ where
                 continue;
             }
 
+            // If [`process_proposal`] detected that decrypted txs were
+            // submitted out of order, we apply none of those. New encrypted
+            // txs may still be accepted.
+            if req.reject_all_decrypted
+                && matches!(tx_type, TxType::Decrypted(_))
+            {
+                let mut tx_result = Event::new_tx_event(&tx_type, height.0);
+                tx_result["code"] = ErrorCodes::InvalidOrder.into();
+                tx_result["info"] = "All decrypted txs rejected as they were \
+                                     not submitted in correct order"
+                    .into();
+                tx_result["gas_used"] = "0".into();
+                events.push(tx_result);
+                continue;
+            }
+
+            // The same tx may be delivered more than once in a block, but
+            // it's only applied once. A replayed decrypted tx is rejected
+            // before it takes a wrapper from the queue, so that the wrapper
+            // is left for the decrypted tx it belongs to.
+            if !applied_txs.insert(hash_tx(&processed_tx.tx)) {
+                let mut tx_result = Event::new_tx_event(&tx_type, height.0);
+                tx_result["code"] = ErrorCodes::ReplayTx.into();
+                tx_result["info"] = "Tx rejected: it's a replay of a tx \
+                                     applied in this block"
+                    .into();
+                tx_result["gas_used"] = "0".into();
+                events.push(tx_result);
+                continue;
+            }
+
+            // A wrapper is only queued for decryption once its fee is paid
+            let wrapper = match &tx_type {
+                TxType::Wrapper(wrapper) => Some(wrapper.clone()),
+                _ => None,
+            };
+            // The wrapper whose fee has been charged for a decrypted tx
+            let mut charged_wrapper = None;
             let mut tx_result = match &tx_type {
-                TxType::Wrapper(_wrapper) => {
-                    if !cfg!(feature = "ABCI") {
-                        self.storage.tx_queue.push(_wrapper.clone());
-                    }
-                    Event::new_tx_event(&tx_type, height.0)
-                }
+                TxType::Wrapper(_) => Event::new_tx_event(&tx_type, height.0),
                 TxType::Decrypted(inner) => {
-                    // If [`process_proposal`] detected that decrypted txs were
-                    // submitted out of order, we apply none
-                    // of those. New encrypted txs may still
-                    // be accepted.
-                    if req.reject_all_decrypted {
-                        let mut tx_result =
-                            Event::new_tx_event(&tx_type, height.0);
-                        tx_result["code"] = ErrorCodes::InvalidOrder.into();
-                        tx_result["info"] = "All decrypted txs rejected as \
-                                             they were not submitted in \
-                                             correct order"
-                            .into();
-                        tx_result["gas_used"] = "0".into();
-                        response.events.push(tx_result.into());
-                        continue;
-                    }
                     // We remove the corresponding wrapper tx from the queue
                     if !cfg!(feature = "ABCI") {
-                        self.storage.tx_queue.pop();
+                        charged_wrapper = self.storage.tx_queue.pop();
                     }
                     let mut event = Event::new_tx_event(&tx_type, height.0);
-                    if let DecryptedTx::Undecryptable(_) = inner {
-                        event["log"] =
-                            "Transaction could not be decrypted.".into();
-                        event["code"] = ErrorCodes::Undecryptable.into();
+                    match inner {
+                        DecryptedTx::Undecryptable(_) => {
+                            event["log"] =
+                                "Transaction could not be decrypted.".into();
+                            event["code"] = ErrorCodes::Undecryptable.into();
+                        }
+                        DecryptedTx::Decrypted(tx) => {
+                            if let Some(bundle) = TxBundle::from_tx(tx) {
+                                event["bundle_id"] = bundle.id().to_string();
+                            }
+                        }
                     }
                     event
                 }
@@ -182,12 +262,16 @@ where
 
             match protocol::apply_tx(
                 tx_type,
+                charged_wrapper.as_ref(),
                 tx_length,
                 &mut self.gas_meter,
                 &mut self.write_log,
                 &self.storage,
                 &mut self.vp_wasm_cache,
                 &mut self.tx_wasm_cache,
+                // The txs of a block are only bounded by gas, so that all the
+                // nodes get the same results
+                None,
             )
             .map_err(Error::TxApply)
             {
@@ -199,14 +283,33 @@ where
                             result
                         );
                         self.write_log.commit_tx();
+                        self.write_log
+                            .stage_block_changes(&mut self.storage)
+                            .expect(
+                                "Staging the block's changes in storage \
+                                 shouldn't fail",
+                            );
+                        if let Some(wrapper) = wrapper {
+                            if !cfg!(feature = "ABCI") {
+                                self.storage
+                                    .tx_queue
+                                    .push(wrapper, result.fee_charged);
+                            }
+                        }
                         if !tx_result.contains_key("code") {
                             tx_result["code"] = ErrorCodes::Ok.into();
                         }
                         if let Some(ibc_event) = &result.ibc_event {
                             // Add the IBC event besides the tx_result
                             let event = Event::from(ibc_event.clone());
-                            response.events.push(event.into());
+                            events.push(event);
                         }
+                        self.push_balance_change_events(
+                            &tx_result["hash"],
+                            height,
+                            &result.changed_keys,
+                            &mut events,
+                        );
                         match serde_json::to_string(
                             &result.initialized_accounts,
                         ) {
@@ -228,10 +331,30 @@ where
                              {:#?}",
                             result.vps_result.rejected_vps
                         );
-                        self.write_log.drop_tx();
+                        if result.fee_refunded {
+                            // The tx's changes have been dropped, only the
+                            // refund of its fee is kept
+                            self.write_log.commit_tx();
+                            self.write_log
+                                .stage_block_changes(&mut self.storage)
+                                .expect(
+                                    "Staging the block's changes in storage \
+                                     shouldn't fail",
+                                );
+                        } else {
+                            self.write_log.drop_tx();
+                        }
                         tx_result["code"] = ErrorCodes::InvalidTx.into();
                     }
+                    if charged_wrapper.is_some() {
+                        tx_result["fee_refunded"] =
+                            result.fee_refunded.to_string();
+                    }
                     tx_result["gas_used"] = result.gas_used.to_string();
+                    // The changes of a rejected tx are dropped
+                    tx_result["state_changed"] = (result.is_accepted()
+                        && result.state_changed)
+                        .to_string();
                     tx_result["info"] = result.to_string();
                 }
                 Err(msg) => {
@@ -241,28 +364,143 @@ where
                         .gas_meter
                         .get_current_transaction_gas()
                         .to_string();
+                    // The failed tx's gas is added to the block's gas, so
+                    // that it doesn't count towards the next tx's gas limit.
+                    // A block over the gas limit is still caught when the
+                    // block's gas is finalized below.
+                    let _ = self.gas_meter.finalize_transaction();
+                    // A failed tx is always charged its fee
+                    if charged_wrapper.is_some() {
+                        tx_result["fee_refunded"] = false.to_string();
+                    }
                     tx_result["info"] = msg.to_string();
-                    tx_result["code"] = ErrorCodes::WasmRuntimeError.into();
+                    tx_result["code"] = match msg {
+                        Error::TxApply(
+                            protocol::Error::InsufficientFeeBalance { .. },
+                        ) => ErrorCodes::InvalidTx.into(),
+                        Error::TxApply(protocol::Error::OutOfGas {
+                            ..
+                        }) => ErrorCodes::OutOfGas.into(),
+                        _ => ErrorCodes::WasmRuntimeError.into(),
+                    };
                 }
             }
-            response.events.push(tx_result.into());
+            events.push(tx_result);
         }
         self.reset_tx_queue_iter();
 
         if new_epoch {
-            self.update_epoch(&mut response);
+            self.update_epoch(height, &mut response, &mut events);
         }
 
+        self.storage.block_events = events
+            .iter()
+            .map(BlockEvent::from)
+            .collect::<Vec<_>>()
+            .try_to_vec()
+            .expect("Serializing the block events shouldn't fail");
+        response.events = events.into_iter().map(Into::into).collect();
+
         response.gas_used = self
             .gas_meter
             .finalize_transaction()
             .map_err(|_| Error::GasOverflow)?;
+        self.update_base_fee();
         Ok(response)
     }
 
+    /// Add an event for every token balance changed by an applied tx, with
+    /// the balance after the tx. The tx's changes must have been committed to
+    /// the block's write log, which may have staged them in the storage.
+    fn push_balance_change_events(
+        &self,
+        tx_hash: &str,
+        height: BlockHeight,
+        changed_keys: &BTreeSet<Key>,
+        events: &mut Vec<Event>,
+    ) {
+        for key in changed_keys {
+            let owner = match token::is_any_token_balance_key(key) {
+                Some(owner) => owner,
+                None => continue,
+            };
+            let token = match &key.segments[0] {
+                DbKeySeg::AddressSeg(token) => token,
+                _ => continue,
+            };
+            let value = match self.write_log.read(key).0 {
+                Some(StorageModification::Write { value }) => {
+                    Some(value.clone())
+                }
+                Some(_) => None,
+                // the block's changes staged in the storage
+                None => {
+                    self.storage
+                        .read_with_staged(key)
+                        .expect("Reading the token balance shouldn't fail")
+                        .0
+                }
+            };
+            let balance = value
+                .and_then(|value| {
+                    token::Amount::try_from_slice(&value[..]).ok()
+                })
+                .unwrap_or_default();
+            events.push(Event::new_balance_change_event(
+                tx_hash.to_owned(),
+                height.0,
+                token,
+                owner,
+                balance,
+            ));
+        }
+    }
+
+    /// Sort the wrapper txs of a block by the deterministic key set by the
+    /// [`TxOrdering`] protocol parameter, so that all the nodes apply them in
+    /// the same order. The fees paid in different tokens are compared in the
+    /// native token, see [`Shell::get_native_fee`].
+    ///
+    /// The wrapper txs are only moved between the positions held by wrapper
+    /// txs. All the other txs keep their position, in particular the
+    /// decrypted txs must stay in the order of the tx queue, as each of them
+    /// is paired with the next wrapper popped from it.
+    fn order_txs(&self, txs: &mut [shim::request::ProcessedTx]) {
+        let (tx_ordering, _gas) = parameters::read_tx_ordering(&self.storage)
+            .expect("Couldn't read the tx ordering parameter");
+        match tx_ordering {
+            TxOrdering::Proposed => {}
+            TxOrdering::FeeThenHash => {
+                let (slots, mut wrappers): (Vec<usize>, Vec<_>) = txs
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, processed_tx)| {
+                        let tx = Tx::try_from(processed_tx.tx.as_ref()).ok()?;
+                        match process_tx(tx) {
+                            Ok(TxType::Wrapper(wrapper)) => {
+                                let fee = self.get_native_fee(&wrapper);
+                                let key =
+                                    (Reverse(fee), hash_tx(&processed_tx.tx).0);
+                                Some((index, (key, processed_tx.clone())))
+                            }
+                            _ => None,
+                        }
+                    })
+                    .unzip();
+                wrappers.sort_by(|(key, _), (other_key, _)| key.cmp(other_key));
+                for (index, (_key, wrapper)) in slots.into_iter().zip(wrappers)
+                {
+                    txs[index] = wrapper;
+                }
+            }
+        }
+    }
+
     /// Sets the metadata necessary for a new block, including
     /// the hash, height, validator changes, and evidence of
-    /// byzantine behavior. Applies slashes if necessary.
+    /// byzantine behavior. Applies slashes and jails the slashed validators
+    /// if necessary, jails the validators that missed too many blocks and on
+    /// a new epoch, distributes the staking rewards for the last epoch.
     /// Returns a bool indicating if a new epoch began and
     /// the height of the new block.
     fn update_state(
@@ -270,11 +508,15 @@ where
         header: Header,
         hash: BlockHash,
         byzantine_validators: Vec<Evidence>,
+        last_commit_votes: Vec<VoteInfo>,
     ) -> (BlockHeight, bool) {
         let height = BlockHeight(header.height.into());
 
         self.gas_meter.reset();
 
+        // The hash of the last committed block, used to derive the seed of a
+        // new epoch
+        let last_block_hash = self.storage.block.hash.clone();
         self.storage
             .begin_block(hash, height)
             .expect("Beginning a block shouldn't fail");
@@ -300,15 +542,52 @@ where
             .update_epoch(height, time)
             .expect("Must be able to update epoch");
 
+        if new_epoch {
+            self.update_epoch_seed(&last_block_hash);
+        }
+        self.apply_protocol_upgrade(height);
         self.slash();
+        self.record_liveness(height, last_commit_votes);
+        if new_epoch {
+            self.distribute_rewards();
+        }
         (height, new_epoch)
     }
 
     /// If a new epoch begins, we update the response to include
-    /// changes to the validator sets and consensus parameters
-    fn update_epoch(&self, response: &mut shim::response::FinalizeBlock) {
+    /// changes to the validator sets and consensus parameters. Every
+    /// validator update is also added to the block's events, so that light
+    /// clients can follow the validator set from the committed blocks.
+    fn update_epoch(
+        &self,
+        height: BlockHeight,
+        response: &mut shim::response::FinalizeBlock,
+        events: &mut Vec<Event>,
+    ) {
         // Apply validator set update
         let (current_epoch, _gas) = self.storage.get_current_epoch();
+        // The consensus keys of the validators that were active in the last
+        // epoch, to tell the added validators from the updated ones
+        let prev_active_keys: HashSet<common::PublicKey> = current_epoch
+            .0
+            .checked_sub(1)
+            .map(Epoch)
+            .and_then(|prev_epoch| {
+                let validator_sets = self.storage.read_validator_set();
+                let prev_validators = validator_sets.get(prev_epoch)?;
+                let keys = prev_validators
+                    .active
+                    .iter()
+                    .filter_map(|validator| {
+                        self.storage
+                            .read_validator_consensus_key(&validator.address)?
+                            .get(prev_epoch)
+                            .cloned()
+                    })
+                    .collect();
+                Some(keys)
+            })
+            .unwrap_or_default();
         // TODO ABCI validator updates on block H affects the validator set
         // on block H+2, do we need to update a block earlier?
         self.storage.validator_set_update(current_epoch, |update| {
@@ -331,6 +610,19 @@ where
                     (consensus_key, power)
                 }
             };
+            let change = if power == 0 {
+                "removed"
+            } else if prev_active_keys.contains(&consensus_key) {
+                "power-changed"
+            } else {
+                "added"
+            };
+            events.push(Event::new_validator_update_event(
+                height.0,
+                &consensus_key,
+                power,
+                change,
+            ));
             let pub_key = TendermintPublicKey {
                 sum: Some(key_to_tendermint(&consensus_key).unwrap()),
             };
@@ -351,22 +643,112 @@ where
             ..response.consensus_param_updates.take().unwrap_or_default()
         });
     }
+
+    /// Apply the scheduled upgrade of the protocol version, if it's due at the
+    /// given height. The new version is written and the upgrade is removed in
+    /// the block's write log, so they're committed with the block.
+    fn apply_protocol_upgrade(&mut self, height: BlockHeight) {
+        let (upgrade, _gas) = parameters::read_protocol_upgrade(&self.storage)
+            .expect("Couldn't read the protocol upgrade");
+        let upgrade = match upgrade {
+            Some(upgrade) if upgrade.height <= height => upgrade,
+            _ => return,
+        };
+        self.write_block_value(
+            &parameters::protocol_version_storage_key(),
+            &upgrade.version,
+        );
+        self.write_log
+            .delete(&parameters::protocol_upgrade_storage_key())
+            .expect("Couldn't remove the protocol upgrade");
+        self.write_log.commit_tx();
+        tracing::info!("Upgraded the protocol to the {}", upgrade);
+    }
+
+    /// Derive the random seed of the new epoch from the seed of the last
+    /// epoch and the hash of its last block. The seed is written to the
+    /// block's write log, from which the block's txs read it.
+    fn update_epoch_seed(&mut self, last_block_hash: &BlockHash) {
+        let (last_seed, _gas) = parameters::read_epoch_seed(&self.storage)
+            .expect("Couldn't read the epoch seed");
+        let (epoch, _gas) = self.storage.get_current_epoch();
+        let seed =
+            parameters::next_epoch_seed(&last_seed, last_block_hash, epoch);
+        self.write_block_value(&parameters::epoch_seed_storage_key(), &seed);
+    }
+
+    /// Adjust the base fee for the next block based on how full the current
+    /// block was relative to the [`gas::BLOCK_GAS_TARGET`]. The base fee
+    /// doesn't fall below the min base fee parameter.
+    fn update_base_fee(&mut self) {
+        let (base_fee, _gas) = parameters::read_base_fee(&self.storage)
+            .expect("Couldn't read the base fee");
+        let (min_base_fee, _gas) = parameters::read_min_base_fee(&self.storage)
+            .expect("Couldn't read the min base fee");
+        let next_base_fee =
+            gas::next_base_fee(base_fee.into(), self.gas_meter.get_block_gas())
+                .max(min_base_fee.into());
+        self.write_block_value(
+            &parameters::base_fee_storage_key(),
+            &token::Amount::from(next_base_fee),
+        );
+    }
+
+    /// Write a value updated by the ledger itself in the block's write log,
+    /// so that it's committed to the storage together with the block's txs
+    /// in [`Shell::commit`].
+    fn write_block_value(&mut self, key: &Key, value: &impl BorshSerialize) {
+        let value = value
+            .try_to_vec()
+            .expect("Encoding a storage value shouldn't fail");
+        self.write_log
+            .write(key, value)
+            .expect("Writing to the block's write log shouldn't fail");
+        self.write_log.commit_tx();
+    }
 }
 
 /// We test the failure cases of [`finalize_block`]. The happy flows
 /// are covered by the e2e tests.
 #[cfg(test)]
 mod test_finalize_block {
-    use anoma::types::address::xan;
-    use anoma::types::storage::Epoch;
-    use anoma::types::transaction::{EncryptionKey, Fee};
+    use anoma::ledger::gas::{BlockGasMeter, StorageGasCosts};
+    use anoma::ledger::parameters::{
+        FeeRefundPolicy, FeeTokenRates, Parameters, ProtocolUpgrade,
+    };
+    use anoma::ledger::pos::anoma_proof_of_stake::epoched::DynEpochOffset;
+    use anoma::ledger::pos::types::{BasisPoints, WeightedValidator};
+    use anoma::ledger::pos::PosParams;
+    use anoma::types::address::{btc, dot, eth, xan, Address, InternalAddress};
+    use anoma::types::storage::{Epoch, Key, QueuedWrapper};
+    use anoma::types::time::{Duration, DurationSecs};
+    use anoma::types::transaction::{EncryptionKey, Fee, GasLimit};
 
     use super::*;
+    use crate::node::ledger::export::{
+        export_block_events, Error as ExportError,
+    };
     use crate::node::ledger::shell::test_utils::*;
     use crate::node::ledger::shims::abcipp_shim_types::shim::request::{
         FinalizeBlock, ProcessedTx,
     };
 
+    /// Credit the implicit account of the given key with enough XAN to pay
+    /// the fees of the test wrapper txs
+    fn fund_fee_payer(shell: &mut TestShell, keypair: &common::SecretKey) {
+        let payer = Address::from(&keypair.ref_to());
+        shell
+            .shell
+            .storage
+            .write(
+                &token::balance_key(&xan(), &payer),
+                token::Amount::whole(1_000)
+                    .try_to_vec()
+                    .expect("Test failed"),
+            )
+            .expect("Test failed");
+    }
+
     #[cfg(not(feature = "ABCI"))]
     /// Check that if a wrapper tx was rejected by [`process_proposal`],
     /// check that the correct event is returned. Check that it does
@@ -375,6 +757,7 @@ mod test_finalize_block {
     fn test_process_proposal_rejected_wrapper_tx() {
         let (mut shell, _) = setup();
         let keypair = gen_keypair();
+        fund_fee_payer(&mut shell, &keypair);
         let mut processed_txs = vec![];
         let mut valid_wrappers = vec![];
         // create some wrapper txs
@@ -387,6 +770,7 @@ mod test_finalize_block {
                 Fee {
                     amount: i.into(),
                     token: xan(),
+                    gas_price: None,
                 },
                 &keypair,
                 Epoch(0),
@@ -456,6 +840,7 @@ mod test_finalize_block {
     fn test_process_proposal_rejected_wrapper_tx() {
         let (mut shell, _) = setup();
         let keypair = gen_keypair();
+        fund_fee_payer(&mut shell, &keypair);
         let mut processed_txs = vec![];
         // create some wrapper txs
         for i in 1..5 {
@@ -467,6 +852,7 @@ mod test_finalize_block {
                 Fee {
                     amount: i.into(),
                     token: xan(),
+                    gas_price: None,
                 },
                 &keypair,
                 Epoch(0),
@@ -530,6 +916,7 @@ mod test_finalize_block {
             Fee {
                 amount: 0.into(),
                 token: xan(),
+                gas_price: None,
             },
             &keypair,
             Epoch(0),
@@ -635,6 +1022,7 @@ mod test_finalize_block {
             fee: Fee {
                 amount: 0.into(),
                 token: xan(),
+                gas_price: None,
             },
             pk: keypair.ref_to(),
             epoch: Epoch(0),
@@ -705,6 +1093,7 @@ mod test_finalize_block {
             fee: Fee {
                 amount: 0.into(),
                 token: xan(),
+                gas_price: None,
             },
             pk: keypair.ref_to(),
             epoch: Epoch(0),
@@ -789,6 +1178,7 @@ mod test_finalize_block {
                 Fee {
                     amount: 0.into(),
                     token: xan(),
+                    gas_price: None,
                 },
                 &keypair,
                 Epoch(0),
@@ -820,6 +1210,7 @@ mod test_finalize_block {
                 Fee {
                     amount: 0.into(),
                     token: xan(),
+                    gas_price: None,
                 },
                 &keypair,
                 Epoch(0),
@@ -949,6 +1340,7 @@ mod test_finalize_block {
             Fee {
                 amount: 0.into(),
                 token: xan(),
+                gas_price: None,
             },
             &keypair,
             Epoch(0),
@@ -979,6 +1371,7 @@ mod test_finalize_block {
                 Fee {
                     amount: 0.into(),
                     token: xan(),
+                    gas_price: None,
                 },
                 &keypair,
                 Epoch(0),
@@ -1087,4 +1480,1746 @@ mod test_finalize_block {
         }
         assert_eq!(counter, 3);
     }
+
+    /// Test that a double-sign evidence delivered with a block slashes the
+    /// validator's bonded stake by the duplicate vote slash rate, jails the
+    /// validator, which removes it from the active validator set at the
+    /// pipeline offset, and tombstones it.
+    #[test]
+    fn test_slash_and_jail_double_signing_validator() {
+        let (mut shell, _) = setup();
+        let pos_params = shell.shell.storage.read_pos_params();
+        let current_epoch = shell.shell.storage.block.epoch;
+        let pipeline_epoch = current_epoch + pos_params.pipeline_len;
+        let validator = shell
+            .shell
+            .storage
+            .read_validator_set()
+            .get(current_epoch)
+            .and_then(|validator_set| validator_set.active.iter().next())
+            .expect("There should be an active genesis validator")
+            .address
+            .clone();
+        let stake_pre: i128 = shell
+            .shell
+            .storage
+            .read_validator_total_deltas(&validator)
+            .and_then(|total_deltas| total_deltas.get(current_epoch))
+            .expect("Test failed");
+
+        // Deliver a double-sign evidence with the block
+        let evidence = Evidence {
+            r#type: abci::EvidenceType::DuplicateVote as i32,
+            validator: Some(abci::Validator {
+                address: validator.raw_hash().unwrap().as_bytes().to_vec(),
+                power: 0,
+            }),
+            height: 1,
+            ..Default::default()
+        };
+        shell
+            .finalize_block(FinalizeBlock {
+                byzantine_validators: vec![evidence],
+                ..Default::default()
+            })
+            .expect("Test failed");
+
+        // The validator's bonded stake is reduced by the slash rate
+        let stake_post: i128 = shell
+            .shell
+            .storage
+            .read_validator_total_deltas(&validator)
+            .and_then(|total_deltas| total_deltas.get(pipeline_epoch))
+            .expect("Test failed");
+        let slashed = pos_params.duplicate_vote_slash_rate * stake_pre;
+        assert!(slashed > 0);
+        assert_eq!(stake_post, stake_pre - slashed);
+        let slashes = shell.shell.storage.read_validator_slashes(&validator);
+        assert_eq!(slashes.len(), 1);
+        assert!(shell.shell.storage.is_validator_tombstoned(&validator));
+
+        // The validator is jailed and removed from the validator set
+        let validator_state = shell
+            .shell
+            .storage
+            .read_validator_state(&validator)
+            .expect("Test failed");
+        assert_eq!(
+            validator_state.get(pipeline_epoch),
+            Some(&pos::types::ValidatorState::Inactive)
+        );
+        let validator_set = shell.shell.storage.read_validator_set();
+        let validator_set =
+            validator_set.get(pipeline_epoch).expect("Test failed");
+        assert!(validator_set
+            .active
+            .iter()
+            .chain(validator_set.inactive.iter())
+            .all(|weighted| weighted.address != validator));
+    }
+
+    /// Test that a validator that fails to sign more than the maximum number
+    /// of blocks within the liveness window is slashed by the liveness slash
+    /// rate and jailed, which removes it from the validator set and so it
+    /// loses its voting power at the pipeline offset.
+    #[test]
+    fn test_jail_validator_for_missing_blocks() {
+        let (mut shell, _) = setup();
+        let pos_params = PosParams {
+            liveness_window_len: 4,
+            liveness_max_missed_blocks: 2,
+            liveness_slash_rate: BasisPoints::new(1000),
+            ..shell.shell.storage.read_pos_params()
+        };
+        shell.shell.storage.write_pos_params(&pos_params);
+        let current_epoch = shell.shell.storage.block.epoch;
+        let validator = shell
+            .shell
+            .storage
+            .read_validator_set()
+            .get(current_epoch)
+            .and_then(|validator_set| validator_set.active.iter().next())
+            .expect("There should be an active genesis validator")
+            .address
+            .clone();
+        let stake_pre: i128 = shell
+            .shell
+            .storage
+            .read_validator_total_deltas(&validator)
+            .and_then(|total_deltas| total_deltas.get(current_epoch))
+            .expect("Test failed");
+        let missed_vote = VoteInfo {
+            validator: Some(abci::Validator {
+                address: validator.raw_hash().unwrap().as_bytes().to_vec(),
+                power: 0,
+            }),
+            signed_last_block: false,
+        };
+        let finalize = |shell: &mut TestShell, height: u64| {
+            let mut req = FinalizeBlock {
+                last_commit_votes: vec![missed_vote.clone()],
+                ..Default::default()
+            };
+            req.header.height = height.try_into().expect("Test failed");
+            shell.finalize_block(req).expect("Test failed");
+            shell.shell.commit().expect("Test failed");
+        };
+
+        // Missing up to the maximum number of blocks is tolerated
+        finalize(&mut shell, 2);
+        finalize(&mut shell, 3);
+        let missed_blocks =
+            shell.shell.storage.read_validator_missed_blocks(&validator);
+        assert_eq!(missed_blocks, vec![1, 2]);
+        assert!(shell
+            .shell
+            .storage
+            .read_validator_slashes(&validator)
+            .is_empty());
+
+        // One more missed block jails the validator
+        finalize(&mut shell, 4);
+        let current_epoch = shell.shell.storage.block.epoch;
+        let pipeline_epoch = current_epoch + pos_params.pipeline_len;
+        let slashes = shell.shell.storage.read_validator_slashes(&validator);
+        assert_eq!(slashes.len(), 1);
+        assert!(matches!(slashes[0].r#type, pos::types::SlashType::Liveness));
+        assert!(shell
+            .shell
+            .storage
+            .read_validator_missed_blocks(&validator)
+            .is_empty());
+        // Jailing for downtime is recoverable
+        assert!(!shell.shell.storage.is_validator_tombstoned(&validator));
+
+        // The validator's bonded stake is reduced by the slash rate
+        let stake_post: i128 = shell
+            .shell
+            .storage
+            .read_validator_total_deltas(&validator)
+            .and_then(|total_deltas| total_deltas.get(pipeline_epoch))
+            .expect("Test failed");
+        let slashed = pos_params.liveness_slash_rate * stake_pre;
+        assert!(slashed > 0);
+        assert_eq!(stake_post, stake_pre - slashed);
+
+        // The validator is jailed and removed from the validator set
+        let validator_state = shell
+            .shell
+            .storage
+            .read_validator_state(&validator)
+            .expect("Test failed");
+        assert_eq!(
+            validator_state.get(pipeline_epoch),
+            Some(&pos::types::ValidatorState::Inactive)
+        );
+        let validator_set = shell.shell.storage.read_validator_set();
+        let validator_set =
+            validator_set.get(pipeline_epoch).expect("Test failed");
+        assert!(validator_set
+            .active
+            .iter()
+            .chain(validator_set.inactive.iter())
+            .all(|weighted| weighted.address != validator));
+
+        // A jailed validator's missed blocks are no longer tracked
+        finalize(&mut shell, 5);
+        assert!(shell
+            .shell
+            .storage
+            .read_validator_missed_blocks(&validator)
+            .is_empty());
+        assert_eq!(
+            shell.shell.storage.read_validator_slashes(&validator).len(),
+            1
+        );
+    }
+
+    /// Test that the base fee starts from the min base fee at genesis, that
+    /// it's adjusted by the finalized blocks when they're committed and that
+    /// it never falls below the minimum. A block can't be filled above the
+    /// [`gas::BLOCK_GAS_TARGET`] here, so the rise of the base fee is only
+    /// covered by the tests of [`gas::next_base_fee`].
+    #[test]
+    fn test_base_fee_adjustment() {
+        let (mut shell, _) = setup();
+        let read_base_fee = |shell: &TestShell| {
+            parameters::read_base_fee(&shell.shell.storage)
+                .expect("Test failed")
+                .0
+        };
+        let min_base_fee = token::Amount::from(1_000_000);
+        let (params, _gas) =
+            parameters::read(&shell.shell.storage).expect("Test failed");
+        parameters::init_genesis_storage(
+            &mut shell.shell.storage,
+            &Parameters {
+                min_base_fee,
+                ..params
+            },
+        );
+        assert_eq!(read_base_fee(&shell), min_base_fee);
+
+        // The base fee has risen, e.g. after some full blocks
+        parameters::update_base_fee_parameter(
+            &mut shell.shell.storage,
+            &token::Amount::from(2_000_000),
+        )
+        .expect("Test failed");
+
+        // Every empty block lowers the base fee by 1/8, down to the minimum
+        let expected: [u64; 7] = [
+            1_750_000, 1_531_250, 1_339_844, 1_172_364, 1_025_819, 1_000_000,
+            1_000_000,
+        ];
+        for (height, expected) in (1..).zip(expected) {
+            let base_fee = read_base_fee(&shell);
+            finalize_block_at(&mut shell, height).expect("Test failed");
+            // The new base fee is only written to storage on commit
+            assert_eq!(read_base_fee(&shell), base_fee);
+            shell.shell.commit().expect("Test failed");
+            assert_eq!(read_base_fee(&shell), token::Amount::from(expected));
+        }
+    }
+
+    /// Test that the fees paid in different tokens are converted to the
+    /// native token with the fee token rates, both to rank the txs and to
+    /// check them against the base fee, and that a token without a rate
+    /// can't pay fees
+    #[test]
+    fn test_fee_token_rates() {
+        let (mut shell, _) = setup();
+        let keypair = gen_keypair();
+        parameters::update_fee_token_rates_parameter(
+            &mut shell.shell.storage,
+            &FeeTokenRates {
+                rates: [
+                    // A unit of BTC is worth two units of XAN
+                    (btc(), token::Amount::whole(2)),
+                    // A unit of ETH is worth half a unit of XAN
+                    (eth(), token::Amount::from(500_000)),
+                ]
+                .into_iter()
+                .collect(),
+            },
+        )
+        .expect("Test failed");
+        parameters::update_tx_ordering_parameter(
+            &mut shell.shell.storage,
+            &TxOrdering::FeeThenHash,
+        )
+        .expect("Test failed");
+        let gas_limit = GasLimit::from(1);
+        let wrapper_tx = |amount: u64, token: Address| {
+            WrapperTx::new(
+                Fee {
+                    amount: amount.into(),
+                    token,
+                    gas_price: None,
+                },
+                &keypair,
+                Epoch(0),
+                gas_limit.clone(),
+                Tx::new("wasm_code".as_bytes().to_owned(), None),
+                Default::default(),
+            )
+            .sign(&keypair)
+            .expect("Test failed")
+            .to_bytes()
+        };
+
+        // The largest amount of ETH is worth the least and the smallest
+        // amount of BTC is worth the most
+        let mut txs: Vec<ProcessedTx> =
+            [(100, eth()), (60, xan()), (40, btc())]
+                .into_iter()
+                .map(|(amount, token)| ProcessedTx {
+                    tx: wrapper_tx(amount, token),
+                    result: TxResult {
+                        code: ErrorCodes::Ok.into(),
+                        info: "".into(),
+                    },
+                })
+                .collect();
+        let hashes = |txs: &[ProcessedTx]| -> Vec<Hash> {
+            txs.iter().map(|tx| hash_tx(&tx.tx)).collect()
+        };
+        let mut expected = hashes(&txs);
+        expected.reverse();
+        shell.shell.order_txs(&mut txs);
+        assert_eq!(hashes(&txs), expected);
+
+        // The minimum fee for the gas limit is 1 XAN
+        parameters::update_base_fee_parameter(
+            &mut shell.shell.storage,
+            &token::Amount::from(1),
+        )
+        .expect("Test failed");
+        let min_fee = token::Amount::whole(1);
+        let validate = |shell: &TestShell, tx: Vec<u8>| {
+            shell
+                .shell
+                .mempool_validate(&tx, MempoolTxType::NewTransaction)
+        };
+        // 1.5 ETH is worth 0.75 XAN
+        let response = validate(&shell, wrapper_tx(1_500_000, eth()));
+        assert_eq!(response.code, 1);
+        assert_eq!(
+            response.log,
+            format!(
+                "The fee {} is below the minimum fee {} given by the current \
+                 base fee",
+                token::Amount::from(750_000),
+                min_fee
+            )
+        );
+        // 0.6 BTC is worth 1.2 XAN
+        let response = validate(&shell, wrapper_tx(600_000, btc()));
+        assert_eq!(response.code, 0, "{}", response.log);
+        // DOT has no rate
+        let response = validate(&shell, wrapper_tx(10_000_000, dot()));
+        assert_eq!(response.code, 1);
+        assert!(response.log.contains("can't pay fees"), "{}", response.log);
+    }
+
+    /// Test that with the fee-then-hash tx ordering, the txs fed in a
+    /// scrambled order are applied in the canonical order and result in the
+    /// same state as the txs fed in the canonical order. The decrypted txs
+    /// must keep the order of the tx queue.
+    #[test]
+    fn test_tx_ordering() {
+        let keypair = gen_keypair();
+        // create some wrapper txs, two of them with the same fee
+        let wrappers: Vec<ProcessedTx> = [2_u64, 4, 1, 3, 2]
+            .iter()
+            .enumerate()
+            .map(|(i, fee)| {
+                let raw_tx = Tx::new(
+                    "wasm_code".as_bytes().to_owned(),
+                    Some(
+                        format!("transaction data: {}", i)
+                            .as_bytes()
+                            .to_owned(),
+                    ),
+                );
+                let wrapper = WrapperTx::new(
+                    Fee {
+                        amount: (*fee).into(),
+                        token: xan(),
+                        gas_price: None,
+                    },
+                    &keypair,
+                    Epoch(0),
+                    0.into(),
+                    raw_tx,
+                    Default::default(),
+                );
+                ProcessedTx {
+                    tx: wrapper.sign(&keypair).expect("Test failed").to_bytes(),
+                    result: TxResult {
+                        code: ErrorCodes::Ok.into(),
+                        info: "".into(),
+                    },
+                }
+            })
+            .collect();
+        // create some decrypted txs and their queued wrappers, queued in the
+        // reverse order of the hashes of the decrypted txs, so that sorting
+        // them by their hash would change their order
+        let mut decrypted: Vec<(WrapperTx, Tx)> = (0..3)
+            .map(|i| {
+                let raw_tx = Tx::new(
+                    "wasm_code".as_bytes().to_owned(),
+                    Some(
+                        format!("decrypted transaction data: {}", i)
+                            .as_bytes()
+                            .to_owned(),
+                    ),
+                );
+                let wrapper = WrapperTx::new(
+                    Fee {
+                        amount: 0.into(),
+                        token: xan(),
+                        gas_price: None,
+                    },
+                    &keypair,
+                    Epoch(0),
+                    0.into(),
+                    raw_tx.clone(),
+                    Default::default(),
+                );
+                let tx =
+                    Tx::from(TxType::Decrypted(DecryptedTx::Decrypted(raw_tx)));
+                (wrapper, tx)
+            })
+            .collect();
+        decrypted.sort_by_key(|(_, tx)| Reverse(hash_tx(&tx.to_bytes()).0));
+        let decrypted_txs: Vec<ProcessedTx> = decrypted
+            .iter()
+            .map(|(_, tx)| ProcessedTx {
+                tx: tx.to_bytes(),
+                result: TxResult {
+                    code: ErrorCodes::Ok.into(),
+                    info: "".into(),
+                },
+            })
+            .collect();
+        let expected_decrypted: Vec<String> = decrypted
+            .iter()
+            .map(|(_, tx)| match process_tx(tx.clone()) {
+                Ok(TxType::Decrypted(inner)) => {
+                    inner.hash_commitment().to_string()
+                }
+                _ => panic!("Test failed"),
+            })
+            .collect();
+        // interleave the decrypted txs between the wrapper txs
+        let interleave = |wrappers: &[ProcessedTx]| -> Vec<ProcessedTx> {
+            vec![
+                decrypted_txs[0].clone(),
+                wrappers[0].clone(),
+                wrappers[1].clone(),
+                decrypted_txs[1].clone(),
+                wrappers[2].clone(),
+                wrappers[3].clone(),
+                decrypted_txs[2].clone(),
+                wrappers[4].clone(),
+            ]
+        };
+        let scrambled = interleave(&wrappers);
+        // the canonical order has the higher fees first and the txs with an
+        // equal fee ordered by their hash, in the positions of the wrappers
+        let mut sorted_wrappers = wrappers.clone();
+        sorted_wrappers.sort_by_key(|processed_tx| {
+            let tx =
+                Tx::try_from(processed_tx.tx.as_ref()).expect("Test failed");
+            let fee = match process_tx(tx).expect("Test failed") {
+                TxType::Wrapper(wrapper) => wrapper.fee.amount,
+                _ => panic!("Test failed"),
+            };
+            (Reverse(fee), hash_tx(&processed_tx.tx).0)
+        });
+        let canonical = interleave(&sorted_wrappers);
+
+        // Apply the txs with two shells, each fed in a different order
+        let mut results = vec![];
+        for txs in [scrambled, canonical] {
+            let (mut shell, _) = setup();
+            fund_fee_payer(&mut shell, &keypair);
+            parameters::update_tx_ordering_parameter(
+                &mut shell.shell.storage,
+                &TxOrdering::FeeThenHash,
+            )
+            .expect("Test failed");
+            for (wrapper, _) in &decrypted {
+                shell.enqueue_tx(wrapper.clone());
+            }
+            let applied: Vec<(String, String)> = shell
+                .finalize_block(FinalizeBlock {
+                    txs,
+                    ..Default::default()
+                })
+                .expect("Test failed")
+                .iter()
+                .map(|event| {
+                    let hash = event
+                        .attributes
+                        .iter()
+                        .find(|attr| attr.key.as_str() == "hash")
+                        .expect("Test failed")
+                        .value
+                        .clone();
+                    (event.r#type.clone(), hash)
+                })
+                .collect();
+            // the decrypted txs are applied in the order of the queue
+            let applied_decrypted: Vec<String> = applied
+                .iter()
+                .filter(|(event_type, _)| event_type == "applied")
+                .map(|(_, hash)| hash.clone())
+                .collect();
+            assert_eq!(applied_decrypted, expected_decrypted);
+            shell.shell.commit().expect("Test failed");
+            results.push((applied, shell.shell.storage.merkle_root().0));
+        }
+        assert_eq!(results[0], results[1]);
+    }
+
+    /// Test that a tx delivered twice in the same block is only applied once
+    /// and that its replay is rejected
+    #[test]
+    fn test_duplicate_tx_in_block() {
+        let (mut shell, _) = setup();
+        let keypair = gen_keypair();
+        fund_fee_payer(&mut shell, &keypair);
+        let payer = Address::from(&keypair.ref_to());
+        let balance_key = token::balance_key(&xan(), &payer);
+        let fee = token::Amount::whole(10);
+        let wrapper = WrapperTx::new(
+            Fee {
+                amount: fee,
+                token: xan(),
+                gas_price: None,
+            },
+            &keypair,
+            Epoch(0),
+            0.into(),
+            Tx::new("wasm_code".as_bytes().to_owned(), None),
+            Default::default(),
+        );
+        let processed_tx = ProcessedTx {
+            tx: wrapper.sign(&keypair).expect("Test failed").to_bytes(),
+            result: TxResult {
+                code: ErrorCodes::Ok.into(),
+                info: "".into(),
+            },
+        };
+
+        let codes: Vec<String> = shell
+            .finalize_block(FinalizeBlock {
+                txs: vec![processed_tx.clone(), processed_tx],
+                ..Default::default()
+            })
+            .expect("Test failed")
+            .iter()
+            .map(|event| {
+                event
+                    .attributes
+                    .iter()
+                    .find(|attr| attr.key.as_str() == "code")
+                    .expect("Test failed")
+                    .value
+                    .clone()
+            })
+            .collect();
+        assert_eq!(
+            codes,
+            vec![
+                String::from(ErrorCodes::Ok),
+                String::from(ErrorCodes::ReplayTx)
+            ]
+        );
+
+        // The fee has only been charged once
+        let balance = match shell.shell.write_log.read(&balance_key).0 {
+            Some(StorageModification::Write { value }) => {
+                token::Amount::try_from_slice(value).expect("Test failed")
+            }
+            _ => panic!("Test failed"),
+        };
+        let mut expected = token::Amount::whole(1_000);
+        expected.spend(&fee);
+        assert_eq!(balance, expected);
+    }
+
+    #[cfg(not(feature = "ABCI"))]
+    /// Test that a replayed decrypted tx doesn't take a wrapper from the queue,
+    /// so that the valid decrypted tx after it is still charged by its own
+    /// wrapper
+    #[test]
+    fn test_replayed_decrypted_tx_keeps_wrapper_queued() {
+        let (mut shell, _) = setup();
+        let keypair = gen_keypair();
+        let tx_code = std::fs::read(
+            top_level_directory().join("wasm_for_tests/tx_no_op.wasm"),
+        )
+        .expect("Expected a file at given code path");
+        let mut decrypted_txs = vec![];
+        for i in 0..2 {
+            let raw_tx = Tx::new(
+                tx_code.clone(),
+                Some(format!("Decrypted transaction data: {}", i).into_bytes()),
+            );
+            let wrapper_tx = WrapperTx::new(
+                Fee {
+                    amount: 0.into(),
+                    token: xan(),
+                    gas_price: None,
+                },
+                &keypair,
+                Epoch(0),
+                0.into(),
+                raw_tx.clone(),
+                Default::default(),
+            );
+            shell.enqueue_tx(wrapper_tx);
+            decrypted_txs.push(ProcessedTx {
+                tx: Tx::from(TxType::Decrypted(DecryptedTx::Decrypted(raw_tx)))
+                    .to_bytes(),
+                result: TxResult {
+                    code: ErrorCodes::Ok.into(),
+                    info: "".into(),
+                },
+            });
+        }
+        // The first decrypted tx is replayed before the second one
+        let txs = vec![
+            decrypted_txs[0].clone(),
+            decrypted_txs[0].clone(),
+            decrypted_txs[1].clone(),
+        ];
+
+        let events = shell
+            .finalize_block(FinalizeBlock {
+                txs,
+                ..Default::default()
+            })
+            .expect("Test failed");
+        let codes: Vec<String> = events
+            .iter()
+            .map(|event| {
+                event
+                    .attributes
+                    .iter()
+                    .find(|attr| attr.key.as_str() == "code")
+                    .expect("Test failed")
+                    .value
+                    .clone()
+            })
+            .collect();
+        assert_eq!(
+            codes,
+            vec![
+                String::from(ErrorCodes::Ok),
+                String::from(ErrorCodes::ReplayTx),
+                String::from(ErrorCodes::Ok),
+            ]
+        );
+        // Both applied decrypted txs have been charged by their wrapper
+        let charged: Vec<bool> = events
+            .iter()
+            .map(|event| {
+                event
+                    .attributes
+                    .iter()
+                    .any(|attr| attr.key.as_str() == "fee_refunded")
+            })
+            .collect();
+        assert_eq!(charged, vec![true, false, true]);
+        // and both wrappers have been removed from the queue
+        assert!(shell.shell.storage.tx_queue.is_empty());
+    }
+
+    #[cfg(not(feature = "ABCI"))]
+    /// Test that the events of a committed block with a transfer are persisted
+    /// and that the balance-change event can be read back for the block's
+    /// height
+    #[test]
+    fn test_query_block_events() {
+        let (mut shell, _) = setup();
+        // Let the token's VP accept any change
+        let vp_code = std::fs::read(
+            top_level_directory().join("wasm_for_tests/vp_always_true.wasm"),
+        )
+        .expect("Expected a file at given code path");
+        shell
+            .shell
+            .storage
+            .write(&Key::validity_predicate(&xan()), vp_code)
+            .expect("Test failed");
+
+        let tx_code = std::fs::read(
+            top_level_directory().join("wasm_for_tests/tx_mint_tokens.wasm"),
+        )
+        .expect("Expected a file at given code path");
+        let keypair = gen_keypair();
+        let target = Address::from(&gen_keypair().ref_to());
+        let transfer = token::Transfer {
+            source: xan(),
+            target: target.clone(),
+            token: xan(),
+            amount: token::Amount::whole(1),
+        };
+        let data = transfer.try_to_vec().expect("Test failed");
+        let raw_tx = Tx::new(tx_code, Some(data)).sign(&keypair);
+        let wrapper = WrapperTx::new(
+            Fee {
+                amount: 0.into(),
+                token: xan(),
+                gas_price: None,
+            },
+            &keypair,
+            Epoch(0),
+            0.into(),
+            raw_tx.clone(),
+            Default::default(),
+        );
+        shell.enqueue_tx(wrapper);
+        let processed_tx = ProcessedTx {
+            tx: Tx::from(TxType::Decrypted(DecryptedTx::Decrypted(raw_tx)))
+                .to_bytes(),
+            result: TxResult {
+                code: ErrorCodes::Ok.into(),
+                info: "".into(),
+            },
+        };
+        let mut req = FinalizeBlock {
+            txs: vec![processed_tx],
+            ..Default::default()
+        };
+        req.header.height = 1u64.try_into().expect("Test failed");
+        shell.finalize_block(req).expect("Test failed");
+        shell.shell.commit().expect("Test failed");
+
+        let query = |event_type: Option<&str>| {
+            let path = rpc::Path::BlockEvents {
+                height: BlockHeight(1),
+                event_type: event_type.map(String::from),
+            };
+            let response = shell.shell.query(request::Query {
+                path: path.to_string(),
+                ..Default::default()
+            });
+            assert_eq!(response.code, 0, "{}", response.info);
+            rpc::BlockEvents::try_from_slice(&response.value[..])
+                .expect("Test failed")
+        };
+
+        let result = query(Some("balance-change"));
+        assert!(!result.truncated);
+        assert_eq!(result.events.len(), 1);
+        let event = &result.events[0];
+        assert_eq!(event.event_type, "balance-change");
+        assert_eq!(event.attributes["height"], "1");
+        assert_eq!(event.attributes["token"], xan().encode());
+        assert_eq!(event.attributes["owner"], target.encode());
+        assert_eq!(
+            event.attributes["balance"],
+            token::Amount::whole(1).to_string()
+        );
+
+        // Without a filter, the tx's event is returned too
+        let result = query(None);
+        assert_eq!(result.events.len(), 2);
+        assert!(result
+            .events
+            .iter()
+            .any(|event| event.event_type == "applied"
+                && event.attributes["code"] == "0"));
+    }
+
+    /// Test that the balance-change event of a tx has the balance after the
+    /// tx when the block's changes are staged in the storage
+    #[test]
+    fn test_balance_change_event_with_staged_changes() {
+        let (mut shell, _) = setup();
+        // Stage the block's changes after every tx
+        parameters::update_write_log_flush_threshold_parameter(
+            &mut shell.shell.storage,
+            &Some(0),
+        )
+        .expect("Test failed");
+        // Let the token's VP accept any change
+        let vp_code = std::fs::read(
+            top_level_directory().join("wasm_for_tests/vp_always_true.wasm"),
+        )
+        .expect("Expected a file at given code path");
+        shell
+            .shell
+            .storage
+            .write(&Key::validity_predicate(&xan()), vp_code)
+            .expect("Test failed");
+
+        let tx_code = std::fs::read(
+            top_level_directory().join("wasm_for_tests/tx_mint_tokens.wasm"),
+        )
+        .expect("Expected a file at given code path");
+        let keypair = gen_keypair();
+        let target = Address::from(&gen_keypair().ref_to());
+        let transfer = token::Transfer {
+            source: xan(),
+            target: target.clone(),
+            token: xan(),
+            amount: token::Amount::whole(1),
+        };
+        let data = transfer.try_to_vec().expect("Test failed");
+        let raw_tx = Tx::new(tx_code, Some(data)).sign(&keypair);
+        let wrapper = WrapperTx::new(
+            Fee {
+                amount: 0.into(),
+                token: xan(),
+                gas_price: None,
+            },
+            &keypair,
+            Epoch(0),
+            0.into(),
+            raw_tx.clone(),
+            Default::default(),
+        );
+        shell.enqueue_tx(wrapper);
+        let processed_tx = ProcessedTx {
+            tx: Tx::from(TxType::Decrypted(DecryptedTx::Decrypted(raw_tx)))
+                .to_bytes(),
+            result: TxResult {
+                code: ErrorCodes::Ok.into(),
+                info: "".into(),
+            },
+        };
+        let events = shell
+            .finalize_block(FinalizeBlock {
+                txs: vec![processed_tx],
+                ..Default::default()
+            })
+            .expect("Test failed");
+        assert!(!shell.shell.storage.staged_keys.is_empty());
+
+        let event = events
+            .iter()
+            .find(|event| event.r#type == "balance-change")
+            .expect("Test failed");
+        let attribute = |key: &str| {
+            event
+                .attributes
+                .iter()
+                .find(|attr| attr.key.as_str() == key)
+                .expect("Test failed")
+                .value
+                .clone()
+        };
+        assert_eq!(attribute("owner"), target.encode());
+        assert_eq!(attribute("balance"), token::Amount::whole(1).to_string());
+    }
+
+    /// Test that the export of the events of a range of committed blocks
+    /// writes the events of only the blocks in the range, in their order
+    #[test]
+    fn test_export_block_events() {
+        let (mut shell, _) = setup();
+        // Let the token's VP accept any change
+        let vp_code = std::fs::read(
+            top_level_directory().join("wasm_for_tests/vp_always_true.wasm"),
+        )
+        .expect("Expected a file at given code path");
+        shell
+            .shell
+            .storage
+            .write(&Key::validity_predicate(&xan()), vp_code)
+            .expect("Test failed");
+        let tx_code = std::fs::read(
+            top_level_directory().join("wasm_for_tests/tx_mint_tokens.wasm"),
+        )
+        .expect("Expected a file at given code path");
+        let keypair = gen_keypair();
+
+        // Commit a few blocks, each with a transfer to a new target
+        let mut targets = vec![];
+        for height in 1..=4_u64 {
+            let target = Address::from(&gen_keypair().ref_to());
+            let transfer = token::Transfer {
+                source: xan(),
+                target: target.clone(),
+                token: xan(),
+                amount: token::Amount::whole(1),
+            };
+            let data = transfer.try_to_vec().expect("Test failed");
+            let raw_tx = Tx::new(tx_code.clone(), Some(data)).sign(&keypair);
+            let wrapper = WrapperTx::new(
+                Fee {
+                    amount: 0.into(),
+                    token: xan(),
+                    gas_price: None,
+                },
+                &keypair,
+                Epoch(0),
+                0.into(),
+                raw_tx.clone(),
+                Default::default(),
+            );
+            shell.enqueue_tx(wrapper);
+            let processed_tx = ProcessedTx {
+                tx: Tx::from(TxType::Decrypted(DecryptedTx::Decrypted(raw_tx)))
+                    .to_bytes(),
+                result: TxResult {
+                    code: ErrorCodes::Ok.into(),
+                    info: "".into(),
+                },
+            };
+            let mut req = FinalizeBlock {
+                txs: vec![processed_tx],
+                ..Default::default()
+            };
+            req.header.height = height.try_into().expect("Test failed");
+            shell.finalize_block(req).expect("Test failed");
+            shell.shell.commit().expect("Test failed");
+            targets.push(target);
+        }
+
+        let mut out = vec![];
+        let num_events = export_block_events(
+            &shell.shell.storage,
+            BlockHeight(2),
+            BlockHeight(3),
+            &mut out,
+        )
+        .expect("Test failed");
+        let events: Vec<serde_json::Value> = String::from_utf8(out)
+            .expect("Test failed")
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("Test failed"))
+            .collect();
+        // Every block has the event of its tx and of the changed balance
+        assert_eq!(num_events, 4);
+        assert_eq!(events.len(), 4);
+        let heights: Vec<u64> = events
+            .iter()
+            .map(|event| event["height"].as_u64().expect("Test failed"))
+            .collect();
+        assert_eq!(heights, vec![2, 2, 3, 3]);
+        let owners: Vec<&str> = events
+            .iter()
+            .filter(|event| event["type"] == "balance-change")
+            .map(|event| {
+                event["attributes"]["owner"].as_str().expect("Test failed")
+            })
+            .collect();
+        assert_eq!(owners, vec![targets[1].encode(), targets[2].encode()]);
+
+        // The blocks that haven't been committed can't be exported
+        let result = export_block_events(
+            &shell.shell.storage,
+            BlockHeight(4),
+            BlockHeight(5),
+            &mut vec![],
+        );
+        assert!(matches!(result, Err(ExportError::NotCommitted { .. })));
+    }
+
+    /// Test that the fee of a wrapper tx is debited from the payer's balance
+    /// when the wrapper is applied, so that its inner tx runs against the
+    /// balance left after the fee. A wrapper whose payer can only cover the
+    /// inner tx's amount, but not the fee, is rejected without debiting
+    /// anything.
+    #[test]
+    fn test_fee_debited_before_inner_tx() {
+        let (mut shell, _) = setup();
+        let keypair = gen_keypair();
+        let payer = Address::from(&keypair.ref_to());
+        let balance_key = token::balance_key(&xan(), &payer);
+        let amount = token::Amount::whole(5);
+        let fee = token::Amount::whole(10);
+        let wrapper = WrapperTx::new(
+            Fee {
+                amount: fee,
+                token: xan(),
+                gas_price: None,
+            },
+            &keypair,
+            Epoch(0),
+            0.into(),
+            Tx::new("wasm_code".as_bytes().to_owned(), None),
+            Default::default(),
+        );
+        let read_balance = |shell: &TestShell| match shell
+            .shell
+            .write_log
+            .read(&balance_key)
+            .0
+        {
+            Some(StorageModification::Write { value }) => {
+                token::Amount::try_from_slice(value).expect("Test failed")
+            }
+            _ => {
+                let (value, _gas) = shell
+                    .shell
+                    .storage
+                    .read(&balance_key)
+                    .expect("Test failed");
+                token::Amount::try_from_slice(&value.unwrap()[..])
+                    .expect("Test failed")
+            }
+        };
+
+        // The payer can cover both the fee and the inner tx's amount
+        let mut balance = amount;
+        balance.receive(&fee);
+        shell
+            .shell
+            .storage
+            .write(&balance_key, balance.try_to_vec().expect("Test failed"))
+            .expect("Test failed");
+        let result = protocol::apply_tx(
+            TxType::Wrapper(wrapper.clone()),
+            None,
+            0,
+            &mut shell.shell.gas_meter,
+            &mut shell.shell.write_log,
+            &shell.shell.storage,
+            &mut shell.shell.vp_wasm_cache,
+            &mut shell.shell.tx_wasm_cache,
+            None,
+        )
+        .expect("Test failed");
+        assert!(result.is_accepted());
+        shell.shell.write_log.commit_tx();
+        assert_eq!(read_balance(&shell), amount);
+        shell.shell.commit().expect("Test failed");
+
+        // The payer can only cover the inner tx's amount
+        let result = protocol::apply_tx(
+            TxType::Wrapper(wrapper),
+            None,
+            0,
+            &mut shell.shell.gas_meter,
+            &mut shell.shell.write_log,
+            &shell.shell.storage,
+            &mut shell.shell.vp_wasm_cache,
+            &mut shell.shell.tx_wasm_cache,
+            None,
+        );
+        assert!(matches!(
+            result,
+            Err(protocol::Error::InsufficientFeeBalance { .. })
+        ));
+        shell.shell.write_log.drop_tx();
+        assert_eq!(read_balance(&shell), amount);
+    }
+
+    /// Apply a wrapper tx and then its inner tx, which is rejected by the
+    /// parameters VP, under the given fee refund policy. If `exempt_payer`,
+    /// the fee payer is exempt from the fee when the wrapper is applied and
+    /// the exemption is lifted before the inner tx. The inner tx's changes
+    /// are committed or dropped like in [`Shell::finalize_block`]. Returns
+    /// whether the fee has been refunded and the fee payer's balance after
+    /// the inner tx.
+    fn apply_vp_rejected_tx(
+        policy: FeeRefundPolicy,
+        exempt_payer: bool,
+    ) -> (bool, token::Amount) {
+        let (mut shell, _) = setup();
+        parameters::update_fee_refund_policy_parameter(
+            &mut shell.shell.storage,
+            &policy,
+        )
+        .expect("Test failed");
+        let keypair = gen_keypair();
+        let payer = Address::from(&keypair.ref_to());
+        fund_fee_payer(&mut shell, &keypair);
+        let set_fee_exemption = |shell: &mut TestShell, exempt: bool| {
+            let addresses = if exempt {
+                [payer.clone()].into_iter().collect()
+            } else {
+                Default::default()
+            };
+            parameters::update_fee_exemption_parameter(
+                &mut shell.shell.storage,
+                &parameters::FeeExemption {
+                    addresses,
+                    exempt_gas: false,
+                },
+            )
+            .expect("Test failed");
+        };
+        set_fee_exemption(&mut shell, exempt_payer);
+        let tx_code = std::fs::read(
+            top_level_directory().join("wasm_for_tests/tx_mint_tokens.wasm"),
+        )
+        .expect("Expected a file at given code path");
+        // No changes of the parameters are permitted by its VP
+        let transfer = token::Transfer {
+            source: payer.clone(),
+            target: payer.clone(),
+            token: Address::Internal(InternalAddress::Parameters),
+            amount: token::Amount::whole(1),
+        };
+        let tx =
+            Tx::new(tx_code, Some(transfer.try_to_vec().expect("Test failed")))
+                .sign(&keypair);
+        let wrapper = WrapperTx::new(
+            Fee {
+                amount: token::Amount::whole(10),
+                token: xan(),
+                gas_price: None,
+            },
+            &keypair,
+            Epoch(0),
+            0.into(),
+            tx.clone(),
+            Default::default(),
+        );
+
+        let result = protocol::apply_tx(
+            TxType::Wrapper(wrapper.clone()),
+            None,
+            0,
+            &mut shell.shell.gas_meter,
+            &mut shell.shell.write_log,
+            &shell.shell.storage,
+            &mut shell.shell.vp_wasm_cache,
+            &mut shell.shell.tx_wasm_cache,
+            None,
+        )
+        .expect("Test failed");
+        assert!(result.is_accepted());
+        shell.shell.write_log.commit_tx();
+        shell.shell.commit().expect("Test failed");
+        // The fee parameters may change before the inner tx is applied
+        set_fee_exemption(&mut shell, false);
+        let queued = QueuedWrapper {
+            wrapper,
+            charged_fee: result.fee_charged,
+        };
+
+        let result = protocol::apply_tx(
+            TxType::Decrypted(DecryptedTx::Decrypted(tx)),
+            Some(&queued),
+            0,
+            &mut shell.shell.gas_meter,
+            &mut shell.shell.write_log,
+            &shell.shell.storage,
+            &mut shell.shell.vp_wasm_cache,
+            &mut shell.shell.tx_wasm_cache,
+            None,
+        )
+        .expect("Test failed");
+        assert!(!result.is_accepted());
+        assert!(result
+            .vps_result
+            .rejected_vps
+            .contains(&Address::Internal(InternalAddress::Parameters)));
+        if result.fee_refunded {
+            shell.shell.write_log.commit_tx();
+        } else {
+            shell.shell.write_log.drop_tx();
+        }
+        let balance_key = token::balance_key(&xan(), &payer);
+        let balance = match shell.shell.write_log.read(&balance_key).0 {
+            Some(StorageModification::Write { value }) => {
+                token::Amount::try_from_slice(value).expect("Test failed")
+            }
+            _ => {
+                let (value, _gas) = shell
+                    .shell
+                    .storage
+                    .read(&balance_key)
+                    .expect("Test failed");
+                token::Amount::try_from_slice(&value.unwrap()[..])
+                    .expect("Test failed")
+            }
+        };
+        (result.fee_refunded, balance)
+    }
+
+    /// Test that the fee of a tx rejected by the VPs is kept under the
+    /// default `always-charge` fee refund policy
+    #[test]
+    fn test_fee_charged_on_vp_rejection() {
+        let (fee_refunded, balance) =
+            apply_vp_rejected_tx(FeeRefundPolicy::AlwaysCharge, false);
+        assert!(!fee_refunded);
+        let mut expected = token::Amount::whole(1_000);
+        expected.spend(&token::Amount::whole(10));
+        assert_eq!(balance, expected);
+    }
+
+    /// Test that the fee of a tx rejected by the VPs is refunded to the fee
+    /// payer under the `refund-on-vp-rejection` fee refund policy, while the
+    /// rejected tx's changes are still dropped
+    #[test]
+    fn test_fee_refunded_on_vp_rejection() {
+        let (fee_refunded, balance) =
+            apply_vp_rejected_tx(FeeRefundPolicy::RefundOnVpRejection, false);
+        assert!(fee_refunded);
+        assert_eq!(balance, token::Amount::whole(1_000));
+    }
+
+    /// Test that a fee payer who was exempt from the fee when the wrapper tx
+    /// was applied is not refunded the fee that was never charged, even if
+    /// the exemption is lifted before the rejected inner tx is applied
+    #[test]
+    fn test_no_refund_of_uncharged_fee() {
+        let (fee_refunded, balance) =
+            apply_vp_rejected_tx(FeeRefundPolicy::RefundOnVpRejection, true);
+        assert!(!fee_refunded);
+        assert_eq!(balance, token::Amount::whole(1_000));
+    }
+
+    /// Test that a tx that consumes more gas than the transaction gas limit
+    /// fails with the out of gas error, which carries the limit and the gas
+    /// used by the tx and suggests raising the tx's gas limit
+    #[test]
+    fn test_tx_out_of_gas() {
+        let (mut shell, _) = setup();
+        let limit = 10;
+        shell.shell.gas_meter =
+            BlockGasMeter::with_transaction_gas_limit(limit);
+        let tx_code = std::fs::read(
+            top_level_directory().join("wasm_for_tests/tx_no_op.wasm"),
+        )
+        .expect("Expected a file at given code path");
+        let tx = Tx::new(tx_code, None);
+
+        let result = protocol::apply_tx(
+            TxType::Decrypted(DecryptedTx::Decrypted(tx)),
+            None,
+            0,
+            &mut shell.shell.gas_meter,
+            &mut shell.shell.write_log,
+            &shell.shell.storage,
+            &mut shell.shell.vp_wasm_cache,
+            &mut shell.shell.tx_wasm_cache,
+            None,
+        );
+        let err = result.expect_err("The tx should run out of gas");
+        match &err {
+            protocol::Error::OutOfGas {
+                used,
+                limit: err_limit,
+            } => {
+                assert_eq!(*err_limit, limit);
+                assert!(*used > limit);
+                assert_eq!(
+                    *used,
+                    shell.shell.gas_meter.get_current_transaction_gas()
+                );
+            }
+            _ => panic!("Unexpected error {}", err),
+        }
+        let message = err.to_string();
+        assert!(message.contains("its gas limit 10"), "{}", message);
+        assert!(message.contains("a higher --gas-limit"), "{}", message);
+    }
+
+    /// Test that the storage access of a tx is metered apart from its
+    /// compute: a tx that reads a larger value from the storage is charged
+    /// more storage gas, in proportion to the extra bytes read
+    #[test]
+    fn test_storage_gas() {
+        let (mut shell, _) = setup();
+        let costs = StorageGasCosts {
+            read_gas_per_byte: 3,
+            write_gas_per_byte: 5,
+            delete_refund_per_byte: 1,
+        };
+        parameters::update_storage_gas_costs_parameter(
+            &mut shell.shell.storage,
+            &costs,
+        )
+        .expect("Test failed");
+        let tx_code = std::fs::read(
+            top_level_directory()
+                .join("wasm_for_tests/tx_read_storage_key.wasm"),
+        )
+        .expect("Expected a file at given code path");
+        let light_key = Key::parse("light").expect("Test failed");
+        let light_value = vec![0_u8; 10].try_to_vec().expect("Test failed");
+        let heavy_key = Key::parse("heavy").expect("Test failed");
+        let heavy_value = vec![0_u8; 10_000].try_to_vec().expect("Test failed");
+        let extra_bytes = (heavy_value.len() - light_value.len()) as u64;
+        for (key, value) in
+            [(&light_key, light_value), (&heavy_key, heavy_value)]
+        {
+            shell.shell.storage.write(key, value).expect("Test failed");
+        }
+
+        let mut results = vec![];
+        for key in [&light_key, &heavy_key] {
+            let tx = Tx::new(
+                tx_code.clone(),
+                Some(key.try_to_vec().expect("Test failed")),
+            );
+            let result = protocol::apply_tx(
+                TxType::Decrypted(DecryptedTx::Decrypted(tx)),
+                None,
+                0,
+                &mut shell.shell.gas_meter,
+                &mut shell.shell.write_log,
+                &shell.shell.storage,
+                &mut shell.shell.vp_wasm_cache,
+                &mut shell.shell.tx_wasm_cache,
+                None,
+            )
+            .expect("Test failed");
+            assert!(result.is_accepted());
+            assert!(result.storage_gas_used > 0);
+            assert!(result.storage_gas_used < result.gas_used);
+            results.push((result.gas_used, result.storage_gas_used));
+        }
+        let (light_gas, light_storage_gas) = results[0];
+        let (heavy_gas, heavy_storage_gas) = results[1];
+        assert_eq!(
+            heavy_storage_gas - light_storage_gas,
+            extra_bytes * costs.read_gas_per_byte
+        );
+        assert!(heavy_gas - light_gas >= heavy_storage_gas - light_storage_gas);
+    }
+
+    /// Test that a wrapper tx of a fee payer exempt by the fee exemption
+    /// parameter is applied with a zero fee and without being metered for
+    /// gas, while a non-exempt payer must pay and is charged the base fee for
+    /// every unit of its gas limit.
+    #[test]
+    fn test_fee_exempt_payer() {
+        let (mut shell, _) = setup();
+        let exempt_keypair = gen_keypair();
+        let exempt = Address::from(&exempt_keypair.ref_to());
+        let keypair = gen_keypair();
+        let payer = Address::from(&keypair.ref_to());
+        parameters::update_base_fee_parameter(
+            &mut shell.shell.storage,
+            &token::Amount::from(10),
+        )
+        .expect("Test failed");
+        parameters::update_fee_exemption_parameter(
+            &mut shell.shell.storage,
+            &parameters::FeeExemption {
+                addresses: [exempt.clone()].into_iter().collect(),
+                exempt_gas: true,
+            },
+        )
+        .expect("Test failed");
+        fund_fee_payer(&mut shell, &keypair);
+        let gas_limit = GasLimit::from(1_000_000);
+        let tx = Tx::new("wasm_code".as_bytes().to_owned(), None);
+
+        // The exempt payer has no balance and pays no fee
+        let exempt_wrapper = WrapperTx::new(
+            Fee {
+                amount: token::Amount::default(),
+                token: xan(),
+                gas_price: None,
+            },
+            &exempt_keypair,
+            Epoch(0),
+            gas_limit.clone(),
+            tx.clone(),
+            Default::default(),
+        );
+        assert_eq!(
+            shell.shell.get_min_fee(&exempt_wrapper),
+            token::Amount::default()
+        );
+        let result = protocol::apply_tx(
+            TxType::Wrapper(exempt_wrapper),
+            None,
+            0,
+            &mut shell.shell.gas_meter,
+            &mut shell.shell.write_log,
+            &shell.shell.storage,
+            &mut shell.shell.vp_wasm_cache,
+            &mut shell.shell.tx_wasm_cache,
+            None,
+        )
+        .expect("Test failed");
+        assert!(result.is_accepted());
+        assert_eq!(result.gas_used, 0);
+        assert_eq!(shell.shell.gas_meter.get_block_gas(), 0);
+        shell.shell.write_log.commit_tx();
+        assert!(shell
+            .shell
+            .write_log
+            .read(&token::balance_key(&xan(), &exempt))
+            .0
+            .is_none());
+
+        // The non-exempt payer is charged the base fee for its gas limit
+        let min_fee = token::Amount::from(10 * u64::from(&gas_limit));
+        let wrapper = WrapperTx::new(
+            Fee::from_gas_price(token::Amount::from(10), xan(), &gas_limit),
+            &keypair,
+            Epoch(0),
+            gas_limit,
+            tx,
+            Default::default(),
+        );
+        assert_eq!(shell.shell.get_min_fee(&wrapper), min_fee);
+        let result = protocol::apply_tx(
+            TxType::Wrapper(wrapper),
+            None,
+            0,
+            &mut shell.shell.gas_meter,
+            &mut shell.shell.write_log,
+            &shell.shell.storage,
+            &mut shell.shell.vp_wasm_cache,
+            &mut shell.shell.tx_wasm_cache,
+            None,
+        )
+        .expect("Test failed");
+        assert!(result.is_accepted());
+        assert_ne!(result.gas_used, 0);
+        shell.shell.write_log.commit_tx();
+        let mut expected = token::Amount::whole(1_000);
+        expected.spend(&min_fee);
+        match shell
+            .shell
+            .write_log
+            .read(&token::balance_key(&xan(), &payer))
+            .0
+        {
+            Some(StorageModification::Write { value }) => assert_eq!(
+                token::Amount::try_from_slice(value).expect("Test failed"),
+                expected
+            ),
+            _ => panic!("Test failed"),
+        }
+    }
+
+    /// Test that a wrapper tx whose fee is below the base fee for its gas
+    /// limit is rejected when it's applied, even without a gas price, and
+    /// that its fee is not charged
+    #[test]
+    fn test_wrapper_below_min_fee_rejected_on_apply() {
+        let (mut shell, _) = setup();
+        let keypair = gen_keypair();
+        let payer = Address::from(&keypair.ref_to());
+        parameters::update_base_fee_parameter(
+            &mut shell.shell.storage,
+            &token::Amount::from(10),
+        )
+        .expect("Test failed");
+        fund_fee_payer(&mut shell, &keypair);
+        let gas_limit = GasLimit::from(1_000_000);
+        let min_fee = token::Amount::from(10 * u64::from(&gas_limit));
+        let fee = token::Amount::from(10 * u64::from(&gas_limit) - 1);
+        let wrapper = WrapperTx::new(
+            Fee {
+                amount: fee,
+                token: xan(),
+                gas_price: None,
+            },
+            &keypair,
+            Epoch(0),
+            gas_limit,
+            Tx::new("wasm_code".as_bytes().to_owned(), None),
+            Default::default(),
+        );
+        let result = protocol::apply_tx(
+            TxType::Wrapper(wrapper),
+            None,
+            0,
+            &mut shell.shell.gas_meter,
+            &mut shell.shell.write_log,
+            &shell.shell.storage,
+            &mut shell.shell.vp_wasm_cache,
+            &mut shell.shell.tx_wasm_cache,
+            None,
+        );
+        match result {
+            Err(protocol::Error::FeeBelowMinFee {
+                fee: actual_fee,
+                min_fee: actual_min_fee,
+            }) => {
+                assert_eq!(actual_fee, fee);
+                assert_eq!(actual_min_fee, min_fee);
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+        assert!(shell
+            .shell
+            .write_log
+            .read(&token::balance_key(&xan(), &payer))
+            .0
+            .is_none());
+    }
+
+    /// Query the node status of the test shell
+    fn query_node_status(shell: &TestShell) -> rpc::NodeStatus {
+        let response = shell.shell.query(request::Query {
+            path: rpc::Path::NodeStatus.to_string(),
+            ..Default::default()
+        });
+        assert_eq!(response.code, 0, "{}", response.info);
+        rpc::NodeStatus::try_from_slice(&response.value[..])
+            .expect("Test failed")
+    }
+
+    /// Test that the blocks are refused until the wall-clock time reaches a
+    /// genesis time in the future
+    #[test]
+    fn test_blocks_gated_until_genesis_time() {
+        let (mut shell, _) = setup();
+        // The genesis time is a day after the time of the blocks
+        let genesis_time = DateTimeUtc::now() + DurationSecs(24 * 60 * 60);
+        parameters::init_genesis_time(&mut shell.shell.storage, genesis_time);
+
+        // The clock hasn't reached the genesis time
+        shell.shell.clock = || Utc.timestamp(0, 0).into();
+        let status = query_node_status(&shell);
+        assert_eq!(status.genesis_time, Some(genesis_time));
+        assert!(!status.genesis_time_reached);
+        let result = shell.finalize_block(FinalizeBlock::default());
+        assert!(matches!(result, Err(Error::BeforeGenesisTime { .. })));
+        assert!(shell.shell.storage.header.is_none());
+
+        // The clock has passed the genesis time
+        shell.shell.clock = || Utc.timestamp(i64::from(u32::MAX), 0).into();
+        assert!(query_node_status(&shell).genesis_time_reached);
+        shell
+            .finalize_block(FinalizeBlock::default())
+            .expect("Test failed");
+        assert!(shell.shell.storage.header.is_some());
+    }
+
+    /// Test that a block produced after the genesis time can be replayed
+    /// even if the wall-clock time is before the genesis time
+    #[test]
+    fn test_replay_block_after_genesis_time() {
+        let (mut shell, _) = setup();
+        // The genesis time is an hour before the time of the blocks
+        let genesis_time = DateTimeUtc::now() - Duration::hours(1);
+        parameters::init_genesis_time(&mut shell.shell.storage, genesis_time);
+
+        shell.shell.clock = || Utc.timestamp(0, 0).into();
+        shell
+            .finalize_block(FinalizeBlock::default())
+            .expect("Test failed");
+    }
+
+    /// Finalize a block with the given height
+    fn finalize_block_at(
+        shell: &mut TestShell,
+        height: u64,
+    ) -> Result<shim::response::FinalizeBlock> {
+        let mut req = FinalizeBlock::default();
+        req.header.height = height.try_into().expect("Test failed");
+        shell.finalize_block(req)
+    }
+
+    /// Test that a scheduled upgrade of the protocol version is applied at
+    /// its height
+    #[test]
+    fn test_protocol_upgrade_applied_at_height() {
+        let (mut shell, _) = setup();
+        // The chain started with an older version
+        let old_version = PROTOCOL_VERSION - 1;
+        parameters::update_protocol_version(
+            &mut shell.shell.storage,
+            old_version,
+        )
+        .expect("Test failed");
+        let upgrade = ProtocolUpgrade {
+            version: PROTOCOL_VERSION,
+            height: BlockHeight(2),
+        };
+        parameters::schedule_protocol_upgrade(
+            &mut shell.shell.storage,
+            &upgrade,
+        )
+        .expect("Test failed");
+
+        finalize_block_at(&mut shell, 1).expect("Test failed");
+        shell.shell.commit().expect("Test failed");
+        let status = query_node_status(&shell);
+        assert_eq!(status.protocol_upgrade, Some(upgrade));
+        assert_eq!(status.protocol_version, old_version);
+
+        finalize_block_at(&mut shell, 2).expect("Test failed");
+        shell.shell.commit().expect("Test failed");
+        let status = query_node_status(&shell);
+        assert_eq!(status.protocol_upgrade, None);
+        assert_eq!(status.protocol_version, PROTOCOL_VERSION);
+        let (version, _gas) =
+            parameters::read_protocol_version(&shell.shell.storage)
+                .expect("Test failed");
+        assert_eq!(version, Some(PROTOCOL_VERSION));
+    }
+
+    /// Test that a binary which doesn't support the protocol version of an
+    /// upgrade halts at the upgrade's height rather than processing the block
+    #[test]
+    fn test_halt_on_unsupported_protocol_version() {
+        let (mut shell, _) = setup();
+        let required = PROTOCOL_VERSION + 1;
+        parameters::schedule_protocol_upgrade(
+            &mut shell.shell.storage,
+            &ProtocolUpgrade {
+                version: required,
+                height: BlockHeight(2),
+            },
+        )
+        .expect("Test failed");
+
+        // The blocks before the upgrade are processed
+        finalize_block_at(&mut shell, 1).expect("Test failed");
+        shell.shell.commit().expect("Test failed");
+        let status = query_node_status(&shell);
+        assert_eq!(status.protocol_version, PROTOCOL_VERSION);
+        assert_eq!(status.supported_protocol_version, PROTOCOL_VERSION);
+
+        // The block at the upgrade's height is refused and the state is left
+        // at the last committed block
+        let result = finalize_block_at(&mut shell, 2);
+        assert!(matches!(
+            result,
+            Err(Error::UnsupportedProtocolVersion {
+                height: BlockHeight(2),
+                required: version,
+                supported: PROTOCOL_VERSION,
+            }) if version == required
+        ));
+        assert_eq!(shell.shell.storage.block.height, BlockHeight(1));
+        let (version, _gas) =
+            parameters::read_protocol_version(&shell.shell.storage)
+                .expect("Test failed");
+        assert_eq!(version, Some(PROTOCOL_VERSION));
+    }
+
+    /// Finalize a block with the given height and hash that begins a new
+    /// epoch and return the seed of the new epoch
+    fn finalize_new_epoch(
+        shell: &mut TestShell,
+        height: u64,
+        hash: BlockHash,
+    ) -> Hash {
+        shell.shell.storage.next_epoch_min_start_height = BlockHeight(height);
+        shell.shell.storage.next_epoch_min_start_time =
+            DateTimeUtc::now() - Duration::hours(1);
+        let mut req = FinalizeBlock {
+            hash,
+            ..Default::default()
+        };
+        req.header.height = height.try_into().expect("Test failed");
+        let epoch = shell.shell.storage.get_current_epoch().0;
+        shell.finalize_block(req).expect("Test failed");
+        assert_eq!(shell.shell.storage.get_current_epoch().0, epoch.next());
+        shell.shell.commit().expect("Test failed");
+        let (seed, _gas) = parameters::read_epoch_seed(&shell.shell.storage)
+            .expect("Test failed");
+        seed
+    }
+
+    /// Test that two shells with the same history derive the same epoch
+    /// seeds and that the seed changes with every new epoch
+    #[test]
+    fn test_epoch_seed_is_deterministic() {
+        let (mut shell_a, _) = setup();
+        let (mut shell_b, _) = setup();
+        let (genesis_seed, _gas) =
+            parameters::read_epoch_seed(&shell_a.shell.storage)
+                .expect("Test failed");
+        let mut seeds = vec![genesis_seed];
+
+        for height in 1..=3_u64 {
+            let hash = BlockHash([height as u8; 32]);
+            let seed_a = finalize_new_epoch(&mut shell_a, height, hash.clone());
+            let seed_b = finalize_new_epoch(&mut shell_b, height, hash);
+            assert_eq!(seed_a, seed_b);
+            assert!(!seeds.contains(&seed_a));
+            seeds.push(seed_a);
+        }
+    }
+
+    /// Test that a validator that gains voting power at the pipeline epoch is
+    /// updated with its new voting power in the validator updates of the
+    /// block that begins the pipeline epoch and that the update is added to
+    /// the block's events
+    #[test]
+    fn test_validator_update_at_pipeline_epoch() {
+        let (mut shell, _) = setup();
+        let pos_params = shell.shell.storage.read_pos_params();
+        let current_epoch = shell.shell.storage.block.epoch;
+        let mut validator_sets = shell.shell.storage.read_validator_set();
+        let validator = validator_sets
+            .get(current_epoch)
+            .and_then(|validator_set| validator_set.active.iter().next())
+            .expect("There should be an active genesis validator")
+            .clone();
+        let consensus_key = shell
+            .shell
+            .storage
+            .read_validator_consensus_key(&validator.address)
+            .and_then(|keys| keys.get(current_epoch).cloned())
+            .expect("Test failed");
+        let voting_power: u64 = validator.voting_power.into();
+        let new_voting_power = voting_power + 10;
+
+        // The validator gains voting power at the pipeline epoch
+        validator_sets.update_from_offset(
+            |validator_set, _epoch| {
+                validator_set.active.remove(&validator);
+                validator_set.active.insert(WeightedValidator {
+                    voting_power: new_voting_power.into(),
+                    address: validator.address.clone(),
+                });
+            },
+            current_epoch,
+            DynEpochOffset::PipelineLen,
+            &pos_params,
+        );
+        shell.shell.storage.write_validator_set(&validator_sets);
+
+        for height in 1..=pos_params.pipeline_len {
+            shell.shell.storage.next_epoch_min_start_height =
+                BlockHeight(height);
+            shell.shell.storage.next_epoch_min_start_time =
+                DateTimeUtc::now() - Duration::hours(1);
+            let mut req = FinalizeBlock::default();
+            req.header.height = height.try_into().expect("Test failed");
+            let response =
+                shell.shell.finalize_block(req).expect("Test failed");
+            let events: Vec<BlockEvent> = BorshDeserialize::try_from_slice(
+                &shell.shell.storage.block_events,
+            )
+            .expect("Test failed");
+            let update_events: Vec<_> = events
+                .iter()
+                .filter(|event| event.event_type == "validator-update")
+                .collect();
+
+            if height < pos_params.pipeline_len {
+                // The validator set doesn't change before the pipeline epoch
+                assert!(response.validator_updates.is_empty());
+                assert!(update_events.is_empty());
+                continue;
+            }
+            let pub_key = TendermintPublicKey {
+                sum: Some(key_to_tendermint(&consensus_key).unwrap()),
+            };
+            assert_eq!(
+                response.validator_updates,
+                vec![ValidatorUpdate {
+                    pub_key: Some(pub_key),
+                    power: new_voting_power as i64,
+                }]
+            );
+            assert_eq!(update_events.len(), 1);
+            let event = update_events[0];
+            assert_eq!(event.attributes["height"], height.to_string());
+            assert_eq!(
+                event.attributes["consensus_key"],
+                consensus_key.to_string()
+            );
+            assert_eq!(event.attributes["power"], new_voting_power.to_string());
+            assert_eq!(event.attributes["change"], "power-changed");
+        }
+    }
 }
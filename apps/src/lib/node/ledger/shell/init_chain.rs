@@ -3,7 +3,8 @@ use std::collections::HashMap;
 use std::hash::Hash;
 
 use anoma::types::key::*;
-#[cfg(not(feature = "dev"))]
+use anoma::vm::validate_untrusted_wasm;
+use anoma::vm::wasm::run::prepare_wasm_code;
 use sha2::{Digest, Sha256};
 #[cfg(not(feature = "ABCI"))]
 use tendermint_proto::abci;
@@ -29,7 +30,10 @@ where
     /// Create a new genesis for the chain with specified id. This includes
     /// 1. A set of initial users and tokens
     /// 2. Setting up the validity predicates for both users and tokens
-    /// 3. A matchmaker
+    /// 3. The registered matchmakers
+    ///
+    /// Unless disabled in the config, the VPs written to the storage are then
+    /// verified, see [`Shell::check_genesis_vps`].
     pub fn init_chain(
         &mut self,
         init: request::InitChain,
@@ -72,6 +76,17 @@ where
             &genesis.parameters,
         );
 
+        parameters::init_genesis_time(&mut self.storage, genesis_time);
+        parameters::init_genesis_epoch_seed(&mut self.storage);
+        parameters::init_genesis_protocol_version(
+            &mut self.storage,
+            PROTOCOL_VERSION,
+        );
+        parameters::init_genesis_matchmakers(
+            &mut self.storage,
+            &genesis.matchmakers,
+        );
+
         genesis.gov_params.init_storage(&mut self.storage);
 
         // Depends on parameters being initialized
@@ -85,6 +100,9 @@ where
 
         // Loaded VP code cache to avoid loading the same files multiple times
         let mut vp_code_cache: HashMap<String, Vec<u8>> = HashMap::default();
+        // The accounts' storage is written in a single batch once it's all
+        // collected
+        let mut genesis_writes: Vec<(Key, Vec<u8>)> = Vec::new();
 
         // Initialize genesis established accounts
         for genesis::EstablishedAccount {
@@ -116,30 +134,22 @@ where
                 );
             }
 
-            self.storage
-                .write(&Key::validity_predicate(&address), vp_code)
-                .unwrap();
+            genesis_writes.push((Key::validity_predicate(&address), vp_code));
 
             if let Some(pk) = public_key {
-                let pk_storage_key = pk_key(&address);
-                self.storage
-                    .write(&pk_storage_key, pk.try_to_vec().unwrap())
-                    .unwrap();
+                genesis_writes
+                    .push((pk_key(&address), pk.try_to_vec().unwrap()));
             }
 
-            for (key, value) in storage {
-                self.storage.write(&key, value).unwrap();
-            }
+            genesis_writes.extend(storage);
         }
 
         // Initialize genesis implicit
         for genesis::ImplicitAccount { public_key } in genesis.implicit_accounts
         {
             let address: address::Address = (&public_key).into();
-            let pk_storage_key = pk_key(&address);
-            self.storage
-                .write(&pk_storage_key, public_key.try_to_vec().unwrap())
-                .unwrap();
+            genesis_writes
+                .push((pk_key(&address), public_key.try_to_vec().unwrap()));
         }
 
         // Initialize genesis token accounts
@@ -148,6 +158,9 @@ where
             vp_code_path,
             vp_sha256,
             balances,
+            admin,
+            minter,
+            mint_cap,
         } in genesis.token_accounts
         {
             let vp_code = vp_code_cache
@@ -171,17 +184,34 @@ where
                 );
             }
 
-            self.storage
-                .write(&Key::validity_predicate(&address), vp_code)
-                .unwrap();
+            genesis_writes.push((Key::validity_predicate(&address), vp_code));
 
             for (owner, amount) in balances {
-                self.storage
-                    .write(
-                        &token::balance_key(&address, &owner),
-                        amount.try_to_vec().unwrap(),
-                    )
-                    .unwrap();
+                genesis_writes.push((
+                    token::balance_key(&address, &owner),
+                    amount.try_to_vec().unwrap(),
+                ));
+            }
+
+            if let Some(admin) = admin {
+                genesis_writes.push((
+                    token::admin_key(&address),
+                    admin.try_to_vec().unwrap(),
+                ));
+            }
+
+            if let Some(minter) = minter {
+                genesis_writes.push((
+                    token::minter_key(&address),
+                    minter.try_to_vec().unwrap(),
+                ));
+            }
+
+            if let Some(mint_cap) = mint_cap {
+                genesis_writes.push((
+                    token::mint_cap_key(&address),
+                    mint_cap.try_to_vec().unwrap(),
+                ));
             }
         }
 
@@ -211,49 +241,48 @@ where
             }
 
             let addr = &validator.pos_data.address;
-            self.storage
-                .write(&Key::validity_predicate(addr), vp_code)
-                .expect("Unable to write user VP");
+            genesis_writes.push((Key::validity_predicate(addr), vp_code));
             // Validator account key
-            let pk_key = pk_key(addr);
-            self.storage
-                .write(
-                    &pk_key,
-                    validator
-                        .account_key
-                        .try_to_vec()
-                        .expect("encode public key"),
-                )
-                .expect("Unable to set genesis user public key");
+            genesis_writes.push((
+                pk_key(addr),
+                validator
+                    .account_key
+                    .try_to_vec()
+                    .expect("encode public key"),
+            ));
             // Account balance (tokens no staked in PoS)
-            self.storage
-                .write(
-                    &token::balance_key(&address::xan(), addr),
-                    validator
-                        .non_staked_balance
-                        .try_to_vec()
-                        .expect("encode token amount"),
-                )
-                .expect("Unable to set genesis balance");
-            self.storage
-                .write(
-                    &protocol_pk_key(addr),
-                    validator
-                        .protocol_key
-                        .try_to_vec()
-                        .expect("encode protocol public key"),
-                )
-                .expect("Unable to set genesis user protocol public key");
-
-            self.storage
-                .write(
-                    &dkg_session_keys::dkg_pk_key(addr),
-                    validator
-                        .dkg_public_key
-                        .try_to_vec()
-                        .expect("encode public DKG session key"),
-                )
-                .expect("Unable to set genesis user public DKG session key");
+            genesis_writes.push((
+                token::balance_key(&address::xan(), addr),
+                validator
+                    .non_staked_balance
+                    .try_to_vec()
+                    .expect("encode token amount"),
+            ));
+            genesis_writes.push((
+                protocol_pk_key(addr),
+                validator
+                    .protocol_key
+                    .try_to_vec()
+                    .expect("encode protocol public key"),
+            ));
+            genesis_writes.push((
+                dkg_session_keys::dkg_pk_key(addr),
+                validator
+                    .dkg_public_key
+                    .try_to_vec()
+                    .expect("encode public DKG session key"),
+            ));
+        }
+
+        let vp_addresses: Vec<address::Address> = genesis_writes
+            .iter()
+            .filter_map(|(key, _)| key.is_validity_predicate().cloned())
+            .collect();
+        self.storage
+            .write_batch(genesis_writes)
+            .expect("Unable to write the genesis accounts");
+        if self.verify_genesis_vps {
+            self.check_genesis_vps(&vp_addresses)?;
         }
 
         // PoS system depends on epoch being initialized
@@ -296,6 +325,37 @@ where
         }
         Ok(response)
     }
+
+    /// Read back the VPs of the given genesis accounts from the storage and
+    /// check that each of them is a valid wasm that can be instrumented for
+    /// gas metering, so that a corrupted or wrong VP file isn't silently
+    /// used. The sha256 hash of each VP is logged for the operators to
+    /// compare with the expected ones.
+    fn check_genesis_vps(&self, addresses: &[address::Address]) -> Result<()> {
+        for address in addresses {
+            let invalid = |error: String| Error::InvalidGenesisVp {
+                address: address.clone(),
+                error,
+            };
+            let (vp_code, _gas) = self
+                .storage
+                .read(&Key::validity_predicate(address))
+                .map_err(|err| invalid(err.to_string()))?;
+            let vp_code = vp_code.ok_or_else(|| {
+                invalid("the VP is missing in storage".into())
+            })?;
+            validate_untrusted_wasm(&vp_code)
+                .map_err(|err| invalid(err.to_string()))?;
+            prepare_wasm_code(&vp_code)
+                .map_err(|err| invalid(err.to_string()))?;
+            tracing::info!(
+                "Verified the VP of the genesis account {} with sha256 hash {}",
+                address,
+                hex::encode(Sha256::digest(&vp_code))
+            );
+        }
+        Ok(())
+    }
 }
 
 trait HashMapExt<K, V>
@@ -321,3 +381,58 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod test_init_chain {
+    use std::fs;
+
+    use anoma::types::chain::ChainId;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::node::ledger::shell::test_utils::*;
+
+    /// Test that the chain's initialization is aborted with a validation error
+    /// when a genesis VP file is corrupted, instead of running with bad code
+    #[test]
+    fn test_init_chain_with_corrupted_vp() {
+        let (mut shell, _) = TestShell::new();
+        // The genesis VPs are loaded from a directory without checksums, so
+        // that they're read by their name
+        let wasm_dir = tempdir().unwrap();
+        fs::write(wasm_dir.path().join("checksums.json"), "{}").unwrap();
+        let read_vp = |name: &str| {
+            wasm_loader::read_wasm(top_level_directory().join("wasm"), name)
+        };
+        fs::write(
+            wasm_dir.path().join("vp_token.wasm"),
+            read_vp("vp_token.wasm"),
+        )
+        .unwrap();
+        let mut vp_user = read_vp("vp_user.wasm");
+        vp_user.truncate(vp_user.len() / 2);
+        fs::write(wasm_dir.path().join("vp_user.wasm"), vp_user).unwrap();
+        shell.shell.wasm_dir = wasm_dir.path().to_owned();
+
+        let result = shell.shell.init_chain(request::InitChain {
+            time: Some(protobuf::Timestamp {
+                seconds: 0,
+                nanos: 0,
+            }),
+            chain_id: ChainId::default().to_string(),
+            ..Default::default()
+        });
+        match result {
+            Err(err @ Error::InvalidGenesisVp { .. }) => {
+                assert!(
+                    err.to_string()
+                        .starts_with("Invalid VP of the genesis account"),
+                    "{}",
+                    err
+                );
+            }
+            Err(err) => panic!("Unexpected error {}", err),
+            Ok(_) => panic!("The initialization should be aborted"),
+        }
+    }
+}
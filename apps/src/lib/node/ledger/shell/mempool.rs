@@ -0,0 +1,197 @@
+//! Tracking of the transactions in this node's mempool, so that its contents
+//! can be queried
+
+use std::collections::HashMap;
+
+use anoma::types::hash::Hash;
+use anoma::types::transaction::hash_tx;
+
+use super::*;
+use crate::node::ledger::rpc::{MempoolContents, MempoolTx};
+
+/// The transactions that passed the mempool validation and are waiting to be
+/// included in a block, keyed by their hash.
+///
+/// Tendermint doesn't tell the application when a transaction leaves its
+/// mempool, so the tracked transactions are cleared when a block is
+/// committed. Tendermint then re-checks every transaction left in its mempool,
+/// which adds back the ones that are still valid.
+#[derive(Debug, Default)]
+pub(super) struct Mempool {
+    txs: HashMap<Hash, MempoolTx>,
+}
+
+impl Mempool {
+    /// Track a transaction that passed the mempool validation
+    fn insert(&mut self, tx_bytes: &[u8]) {
+        let hash = hash_tx(tx_bytes);
+        let wrapper =
+            Tx::try_from(tx_bytes)
+                .ok()
+                .and_then(|tx| match process_tx(tx) {
+                    Ok(TxType::Wrapper(wrapper)) => Some(wrapper),
+                    _ => None,
+                });
+        let tx = MempoolTx {
+            hash: hash.clone(),
+            fee: wrapper.as_ref().map(|wrapper| wrapper.fee.clone()),
+            gas_limit: wrapper.map(|wrapper| u64::from(&wrapper.gas_limit)),
+        };
+        self.txs.insert(hash, tx);
+    }
+
+    /// Stop tracking a transaction, e.g. when it failed a re-check
+    fn remove(&mut self, tx_bytes: &[u8]) {
+        self.txs.remove(&hash_tx(tx_bytes));
+    }
+
+    /// Stop tracking all the transactions, when a block is committed
+    pub(super) fn clear(&mut self) {
+        self.txs.clear();
+    }
+
+    /// Summarize the tracked transactions. If `list_txs` is set, the
+    /// transactions with the highest fees are listed too, at most
+    /// [`rpc::MAX_MEMPOOL_TXS`] of them.
+    fn contents(&self, list_txs: bool) -> MempoolContents {
+        let total_gas = self
+            .txs
+            .values()
+            .filter_map(|tx| tx.gas_limit)
+            .fold(0_u64, u64::saturating_add);
+        let mut txs: Vec<MempoolTx> = if list_txs {
+            self.txs.values().cloned().collect()
+        } else {
+            vec![]
+        };
+        // List the txs in the order of their fee, highest first, and then by
+        // their hash
+        txs.sort_by_key(|tx| {
+            (
+                std::cmp::Reverse(tx.fee.as_ref().map(|fee| fee.amount)),
+                tx.hash.0,
+            )
+        });
+        let truncated = txs.len() > rpc::MAX_MEMPOOL_TXS;
+        txs.truncate(rpc::MAX_MEMPOOL_TXS);
+        MempoolContents {
+            num_txs: self.txs.len() as u64,
+            total_gas,
+            txs,
+            truncated,
+        }
+    }
+}
+
+impl<D, H> Shell<D, H>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    /// Validate a transaction submitted to the mempool with
+    /// [`Shell::mempool_validate`] and keep track of it while it's in the
+    /// mempool.
+    pub fn check_tx(
+        &mut self,
+        tx_bytes: &[u8],
+        r#type: MempoolTxType,
+    ) -> response::CheckTx {
+        let response = self.mempool_validate(tx_bytes, r#type);
+        if response.code == 0 {
+            self.mempool.insert(tx_bytes);
+        } else {
+            self.mempool.remove(tx_bytes);
+        }
+        response
+    }
+
+    /// Query the contents of the mempool. The value in the response is a
+    /// [`MempoolContents`] encoded with [`BorshSerialize`].
+    pub(super) fn mempool_query(&self, list_txs: bool) -> response::Query {
+        response::Query {
+            value: self.mempool.contents(list_txs).try_to_vec().unwrap(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_mempool {
+    use anoma::types::address::xan;
+    use anoma::types::storage::Epoch;
+    use anoma::types::transaction::Fee;
+    use borsh::BorshDeserialize;
+
+    use super::*;
+    use crate::node::ledger::shell::test_utils::*;
+
+    /// Test that the txs submitted to the mempool are reported by the mempool
+    /// query until a block is committed
+    #[test]
+    fn test_query_mempool() {
+        let (mut shell, _) = setup();
+        let keypair = gen_keypair();
+        let query = |shell: &TestShell, list_txs: bool| {
+            let response = shell.shell.query(request::Query {
+                path: rpc::Path::Mempool { list_txs }.to_string(),
+                ..Default::default()
+            });
+            assert_eq!(response.code, 0, "{}", response.info);
+            MempoolContents::try_from_slice(&response.value[..])
+                .expect("Test failed")
+        };
+
+        let mut hashes = vec![];
+        for fee in [1, 2] {
+            let tx = Tx::new(
+                "wasm_code".as_bytes().to_owned(),
+                Some(format!("transaction data: {}", fee).into_bytes()),
+            );
+            let wrapper = WrapperTx::new(
+                Fee {
+                    amount: fee.into(),
+                    token: xan(),
+                    gas_price: None,
+                },
+                &keypair,
+                Epoch(0),
+                1_000.into(),
+                tx,
+                Default::default(),
+            )
+            .sign(&keypair)
+            .expect("Test failed")
+            .to_bytes();
+            let response = shell
+                .shell
+                .check_tx(&wrapper, MempoolTxType::NewTransaction);
+            assert_eq!(response.code, 0, "{}", response.log);
+            hashes.push(hash_tx(&wrapper));
+        }
+        // A tx that fails the validation is not tracked
+        let response = shell
+            .shell
+            .check_tx(b"not a tx", MempoolTxType::NewTransaction);
+        assert_ne!(response.code, 0);
+
+        let contents = query(&shell, false);
+        assert_eq!(contents.num_txs, 2);
+        assert_eq!(contents.total_gas, 2_000);
+        assert!(contents.txs.is_empty());
+
+        // The txs are listed with the highest fee first
+        let contents = query(&shell, true);
+        assert_eq!(contents.num_txs, 2);
+        assert!(!contents.truncated);
+        let listed: Vec<Hash> =
+            contents.txs.iter().map(|tx| tx.hash.clone()).collect();
+        assert_eq!(listed, vec![hashes[1].clone(), hashes[0].clone()]);
+        assert_eq!(
+            contents.txs[0].fee.as_ref().map(|fee| fee.amount),
+            Some(2.into())
+        );
+
+        shell.shell.commit().expect("Test failed");
+        assert_eq!(query(&shell, false).num_txs, 0);
+    }
+}
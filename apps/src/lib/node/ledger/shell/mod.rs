@@ -7,11 +7,15 @@
 //! More info in <https://github.com/anoma/anoma/issues/362>.
 mod finalize_block;
 mod init_chain;
+mod mempool;
 #[cfg(not(feature = "ABCI"))]
 mod prepare_proposal;
 mod process_proposal;
 mod queries;
+mod query_limit;
+mod simulate_block;
 
+use std::cell::RefCell;
 use std::convert::{TryFrom, TryInto};
 use std::mem;
 use std::path::{Path, PathBuf};
@@ -19,21 +23,24 @@ use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::str::FromStr;
 
-use anoma::ledger::gas::BlockGasMeter;
+use anoma::ledger::gas::{self, BlockGasMeter};
 use anoma::ledger::pos::anoma_proof_of_stake::types::{
     ActiveValidator, ValidatorSetUpdate,
 };
 use anoma::ledger::pos::anoma_proof_of_stake::PosBase;
-use anoma::ledger::storage::write_log::WriteLog;
+#[cfg(not(feature = "ABCI"))]
+use anoma::ledger::storage::read_cache::{self, ReadCache};
+use anoma::ledger::storage::types::decode_versioned;
+use anoma::ledger::storage::write_log::{WriteLog, WriteLogStorage};
 use anoma::ledger::storage::{
-    DBIter, Sha256Hasher, Storage, StorageHasher, DB,
+    DBIter, MerkleRoot, Sha256Hasher, Storage, StorageHasher, DB,
 };
 use anoma::ledger::{ibc, parameters, pos};
 use anoma::proto::{self, Tx};
 use anoma::types::chain::ChainId;
 use anoma::types::key::*;
-use anoma::types::storage::{BlockHeight, Key};
-use anoma::types::time::{DateTimeUtc, TimeZone, Utc};
+use anoma::types::storage::{BlockHeight, Epoch, Key};
+use anoma::types::time::{DateTimeUtc, Duration, TimeZone, Utc};
 use anoma::types::transaction::{
     hash_tx, process_tx, verify_decrypted_correctly, AffineCurve, DecryptedTx,
     EllipticCurve, PairingEngine, TxType, WrapperTx,
@@ -41,14 +48,12 @@ use anoma::types::transaction::{
 use anoma::types::{address, token};
 use anoma::vm::wasm::{TxCache, VpCache};
 use anoma::vm::WasmCacheRwAccess;
-#[cfg(not(feature = "ABCI"))]
-use borsh::BorshDeserialize;
 use borsh::BorshSerialize;
 use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::{FromPrimitive, ToPrimitive};
 #[cfg(not(feature = "ABCI"))]
 use tendermint_proto::abci::{
-    self, Evidence, RequestPrepareProposal, ValidatorUpdate,
+    self, Evidence, RequestPrepareProposal, ValidatorUpdate, VoteInfo,
 };
 #[cfg(not(feature = "ABCI"))]
 use tendermint_proto::crypto::public_key;
@@ -57,7 +62,7 @@ use tendermint_proto::types::ConsensusParams;
 #[cfg(feature = "ABCI")]
 use tendermint_proto_abci::abci::ConsensusParams;
 #[cfg(feature = "ABCI")]
-use tendermint_proto_abci::abci::{self, Evidence, ValidatorUpdate};
+use tendermint_proto_abci::abci::{self, Evidence, ValidatorUpdate, VoteInfo};
 #[cfg(feature = "ABCI")]
 use tendermint_proto_abci::crypto::public_key;
 use thiserror::Error;
@@ -69,6 +74,7 @@ use tower_abci_old::{request, response};
 
 use super::rpc;
 use crate::config::{genesis, TendermintMode};
+use crate::node::ledger::commit_hook::{CommitHook, CommitNotification};
 use crate::node::ledger::events::Event;
 use crate::node::ledger::shims::abcipp_shim_types::shim;
 use crate::node::ledger::shims::abcipp_shim_types::shim::response::TxResult;
@@ -77,6 +83,11 @@ use crate::node::ledger::{protocol, storage, tendermint_node};
 use crate::wallet::ValidatorData;
 use crate::{config, wallet};
 
+/// The version of the protocol supported by this binary. A chain whose
+/// protocol version has been upgraded over this version can't be processed
+/// by this binary, which must then be upgraded.
+pub const PROTOCOL_VERSION: u64 = 1;
+
 fn key_to_tendermint<PK: PublicKey>(
     pk: &PK,
 ) -> std::result::Result<public_key::Sum, ParsePublicKeyError> {
@@ -102,6 +113,35 @@ pub enum Error {
     TowerServer(String),
     #[error("{0}")]
     Broadcaster(tokio::sync::mpsc::error::TryRecvError),
+    #[error("Error committing a block to the storage: {0}")]
+    StorageCommit(anoma::ledger::storage::Error),
+    #[error("Error simulating a block: {0}")]
+    SimulateBlock(anoma::ledger::storage::write_log::Error),
+    #[error(
+        "Refusing to process a block before the genesis time {}, the current \
+         time is {}",
+        .genesis_time.0.to_rfc3339(),
+        .now.0.to_rfc3339()
+    )]
+    BeforeGenesisTime {
+        genesis_time: DateTimeUtc,
+        now: DateTimeUtc,
+    },
+    #[error(
+        "The block {height} requires the protocol version {required}, but \
+         this binary only supports the version {supported}. The node must be \
+         upgraded to process it."
+    )]
+    UnsupportedProtocolVersion {
+        height: BlockHeight,
+        required: u64,
+        supported: u64,
+    },
+    #[error("Invalid VP of the genesis account {address}: {error}")]
+    InvalidGenesisVp {
+        address: address::Address,
+        error: String,
+    },
 }
 
 /// The different error codes that the ledger may
@@ -116,6 +156,8 @@ pub enum ErrorCodes {
     InvalidOrder = 4,
     ExtraTxs = 5,
     Undecryptable = 6,
+    ReplayTx = 7,
+    OutOfGas = 8,
 }
 
 impl From<ErrorCodes> for u32 {
@@ -206,6 +248,30 @@ where
     vp_wasm_cache: VpCache<WasmCacheRwAccess>,
     /// Tx WASM compilation cache
     tx_wasm_cache: TxCache<WasmCacheRwAccess>,
+    /// The txs in the mempool, as seen by this node
+    mempool: mempool::Mempool,
+    /// The source of the current wall-clock time
+    clock: fn() -> DateTimeUtc,
+    /// The optional hook notified after every committed block
+    commit_hook: Option<CommitHook>,
+    /// The maximum number of txs in a block proposed by this node
+    #[allow(dead_code)]
+    max_txs_per_block: Option<usize>,
+    /// The retention of the events of committed blocks
+    events_retention: Option<config::EventsRetention>,
+    /// The number of pruned keys above which the DB is compacted
+    compact_after_pruned_keys: Option<u64>,
+    /// The number of keys pruned since the last compaction
+    pruned_keys_since_compaction: u64,
+    /// The maximum wall-clock time of a single tx or VP wasm execution in a
+    /// dry run or a simulated block
+    wasm_timeout: Option<std::time::Duration>,
+    /// The optional limiter of the rate of the served queries
+    query_limiter: Option<RefCell<query_limit::QueryRateLimiter>>,
+    /// Verify the VPs written to the storage at the genesis
+    verify_genesis_vps: bool,
+    /// The height of the block before which the node halts, if any
+    halt_height: Option<BlockHeight>,
 }
 
 impl<D, H> Shell<D, H>
@@ -227,16 +293,51 @@ where
         let db_path = config.shell.db_dir(&chain_id);
         let base_dir = config.shell.base_dir;
         let mode = config.tendermint.tendermint_mode;
+        let commit_hook = config.shell.commit_hook.map(CommitHook::new);
+        let max_txs_per_block = config.shell.max_txs_per_block;
+        let events_retention = config.shell.events_retention;
+        let compact_after_pruned_keys = config.shell.compact_after_pruned_keys;
+        let wasm_timeout = config
+            .shell
+            .wasm_timeout_ms
+            .map(std::time::Duration::from_millis);
+        let query_limiter = config.shell.query_rate_limit.map(|limit| {
+            RefCell::new(query_limit::QueryRateLimiter::new(limit))
+        });
+        let verify_genesis_vps =
+            config.shell.verify_genesis_vps.unwrap_or(true);
+        let halt_height = config
+            .shell
+            .halt_height
+            .filter(|height| *height != 0)
+            .map(BlockHeight);
+        let read_cache_bytes = config
+            .shell
+            .read_cache_bytes
+            .filter(|bytes| *bytes != 0)
+            .map(|bytes| bytes as usize)
+            .unwrap_or(read_cache::DEFAULT_MAX_BYTES);
+        if cfg!(feature = "ABCI") && max_txs_per_block.is_some() {
+            tracing::warn!(
+                "The maximum number of txs per block is only enforced with \
+                 ABCI++, it's ignored"
+            );
+        }
         if !Path::new(&base_dir).is_dir() {
             std::fs::create_dir(&base_dir)
                 .expect("Creating directory for Anoma should not fail");
         }
         // load last state from storage
         let mut storage = Storage::open(db_path, chain_id.clone(), db_cache);
+        storage.read_cache = ReadCache::new(read_cache_bytes);
         storage
             .load_last_state()
             .map_err(|e| {
-                tracing::error!("Cannot load the last state from the DB {}", e);
+                tracing::error!(
+                    "Cannot load the last state from the DB, refusing to \
+                     start due to storage corruption: {}",
+                    e
+                );
             })
             .expect("PersistentStorage cannot be initialized");
 
@@ -311,6 +412,80 @@ where
                 tx_wasm_cache_dir,
                 tx_wasm_compilation_cache as usize,
             ),
+            mempool: mempool::Mempool::default(),
+            clock: DateTimeUtc::now,
+            commit_hook,
+            max_txs_per_block,
+            events_retention,
+            compact_after_pruned_keys,
+            pruned_keys_since_compaction: 0,
+            wasm_timeout,
+            query_limiter,
+            verify_genesis_vps,
+            halt_height,
+        }
+    }
+
+    /// Check that a block with the given time may be processed. A block is
+    /// refused while the chain's genesis time hasn't been reached by both the
+    /// block's time and the wall-clock time. Historical blocks, which can only
+    /// have been produced after the genesis time, can always be replayed.
+    pub fn check_genesis_time(&self, block_time: DateTimeUtc) -> Result<()> {
+        let (genesis_time, _gas) = parameters::read_genesis_time(&self.storage)
+            .expect("Reading the genesis time shouldn't fail");
+        let genesis_time = match genesis_time {
+            Some(genesis_time) => genesis_time,
+            None => return Ok(()),
+        };
+        let now = (self.clock)();
+        if block_time < genesis_time && now < genesis_time {
+            return Err(Error::BeforeGenesisTime { genesis_time, now });
+        }
+        Ok(())
+    }
+
+    /// Check if the node must halt before processing the block with the given
+    /// height, because it's the configured halt height. The blocks below it
+    /// have been committed and the DB is flushed before the node halts.
+    pub fn halt_at(&self, height: BlockHeight) -> bool {
+        if self.halt_height != Some(height) {
+            return false;
+        }
+        tracing::info!("halting for upgrade at height {}", height);
+        if let Err(err) = self.storage.db.flush(true) {
+            tracing::error!("Failed to flush the DB before halting: {}", err);
+        }
+        true
+    }
+
+    /// Check that the block with the given height may be processed by this
+    /// binary. A block is refused if the protocol version of the chain at its
+    /// height, including a scheduled upgrade that is due at it, is newer than
+    /// the [`PROTOCOL_VERSION`] supported by this binary. The node then halts
+    /// instead of processing the block with outdated rules.
+    pub fn check_protocol_version(&self, height: BlockHeight) -> Result<()> {
+        let required = self.required_protocol_version(height);
+        if required > PROTOCOL_VERSION {
+            return Err(Error::UnsupportedProtocolVersion {
+                height,
+                required,
+                supported: PROTOCOL_VERSION,
+            });
+        }
+        Ok(())
+    }
+
+    /// The protocol version of the chain required by the block with the given
+    /// height. A chain initialized before the version has been stored has the
+    /// initial version 1.
+    fn required_protocol_version(&self, height: BlockHeight) -> u64 {
+        let (version, _gas) = parameters::read_protocol_version(&self.storage)
+            .expect("Reading the protocol version shouldn't fail");
+        let (upgrade, _gas) = parameters::read_protocol_upgrade(&self.storage)
+            .expect("Reading the protocol upgrade shouldn't fail");
+        match upgrade {
+            Some(upgrade) if upgrade.height <= height => upgrade.version,
+            _ => version.unwrap_or(1),
         }
     }
 
@@ -323,7 +498,7 @@ where
     /// Iterate lazily over the wrapper txs in order
     #[cfg(feature = "ABCI")]
     fn next_wrapper(&mut self) -> Option<WrapperTx> {
-        self.storage.tx_queue.pop()
+        self.storage.tx_queue.pop().map(|queued| queued.wrapper)
     }
 
     /// If we reject the decrypted txs because they were out of
@@ -333,7 +508,8 @@ where
     }
 
     /// Load the Merkle root hash and the height of the last committed block, if
-    /// any. This is returned when ABCI sends an `info` request.
+    /// any, and the protocol version of the chain at this height. This is
+    /// returned when ABCI sends an `info` request.
     pub fn last_state(&mut self) -> response::Info {
         let mut response = response::Info::default();
         let result = self.storage.get_state();
@@ -346,6 +522,8 @@ where
                     height
                 );
                 response.last_block_app_hash = root.0;
+                response.app_version =
+                    self.required_protocol_version(BlockHeight(height));
                 response.last_block_height =
                     height.try_into().expect("Invalid block height");
             }
@@ -473,6 +651,108 @@ where
         }
     }
 
+    /// Record which validators signed the last block from its commit votes
+    /// and jail the validators that missed too many blocks. The changes are
+    /// written to the block's write log.
+    fn record_liveness(&mut self, height: BlockHeight, votes: Vec<VoteInfo>) {
+        // The votes are for the block before the current one
+        let last_height = match height.0.checked_sub(1) {
+            Some(last_height) if last_height > 0 => last_height,
+            _ => return,
+        };
+        let pos_params = self.storage.read_pos_params();
+        let current_epoch = self.storage.block.epoch;
+        let mut storage =
+            WriteLogStorage::new(&mut self.write_log, &self.storage);
+        for vote in votes {
+            let validator_raw_hash = match vote.validator {
+                Some(validator) => match String::from_utf8(validator.address) {
+                    Ok(raw_hash) => raw_hash,
+                    Err(err) => {
+                        tracing::error!(
+                            "Vote failed to decode validator address from \
+                             utf-8 with {}",
+                            err
+                        );
+                        continue;
+                    }
+                },
+                None => {
+                    tracing::error!("Vote without a validator {:#?}", vote);
+                    continue;
+                }
+            };
+            let validator = match storage
+                .read_validator_address_raw_hash(&validator_raw_hash)
+            {
+                Some(validator) => validator,
+                None => {
+                    tracing::error!(
+                        "Cannot find validator's address from raw hash {}",
+                        validator_raw_hash
+                    );
+                    continue;
+                }
+            };
+            match storage.record_validator_liveness(
+                &pos_params,
+                current_epoch,
+                last_height,
+                &validator,
+                vote.signed_last_block,
+            ) {
+                Ok(true) => tracing::info!(
+                    "Jailed {} for missing too many blocks in epoch {}, block \
+                     height {}",
+                    validator,
+                    current_epoch,
+                    last_height
+                ),
+                Ok(false) => {}
+                Err(err) => {
+                    tracing::error!("Error in jailing for liveness: {}", err)
+                }
+            }
+        }
+        self.write_log.commit_tx();
+    }
+
+    /// Distribute the staking rewards for the last epoch. The rewards are
+    /// written to the block's write log.
+    fn distribute_rewards(&mut self) {
+        let pos_params = self.storage.read_pos_params();
+        let last_epoch =
+            match u64::from(self.storage.block.epoch).checked_sub(1) {
+                Some(epoch) => Epoch(epoch),
+                None => return,
+            };
+        let num_of_blocks = match self
+            .storage
+            .block
+            .pred_epochs
+            .get_num_of_blocks(last_epoch)
+        {
+            Some(num_of_blocks) => num_of_blocks,
+            None => {
+                tracing::error!(
+                    "Cannot find the number of blocks in epoch {} to \
+                     distribute the staking rewards",
+                    last_epoch
+                );
+                return;
+            }
+        };
+        let distributed =
+            WriteLogStorage::new(&mut self.write_log, &self.storage)
+                .distribute_rewards(&pos_params, last_epoch, num_of_blocks);
+        self.write_log.commit_tx();
+        tracing::info!(
+            "Distributed staking rewards {} for epoch {}",
+            distributed,
+            last_epoch
+        );
+    }
+
     #[cfg(not(feature = "ABCI"))]
     /// INVARIANT: This method must be stateless.
     pub fn extend_vote(
@@ -493,19 +773,45 @@ where
 
     /// Commit a block. Persist the application state and return the Merkle root
     /// hash.
-    pub fn commit(&mut self) -> response::Commit {
+    ///
+    /// A failure to store the block in the DB is fatal, because the node
+    /// cannot continue with an uncommitted block. The error is returned so
+    /// that the node can be halted.
+    ///
+    /// The commit hook, if any, is notified of the committed block without
+    /// waiting for it. The events of the blocks that are no longer retained
+    /// are pruned afterwards.
+    pub fn commit(&mut self) -> Result<response::Commit> {
         let mut response = response::Commit::default();
+        // the events are cleared from storage on commit
+        let events = self
+            .commit_hook
+            .as_ref()
+            .filter(|hook| hook.include_events())
+            .map(|_| self.storage.block_events.clone());
         // commit changes from the write-log to storage
         self.write_log
             .commit_block(&mut self.storage)
-            .expect("Expected committing block write log success");
+            .map_err(|e| {
+                tracing::error!(
+                    "Encountered a storage error while committing the write \
+                     log of a block at height {}, halting due to storage \
+                     corruption: {}",
+                    self.storage.block.height,
+                    e
+                );
+                Error::StorageCommit(e)
+            })?;
         // store the block's data in DB
-        self.storage.commit().unwrap_or_else(|e| {
+        self.storage.commit().map_err(|e| {
             tracing::error!(
-                "Encountered a storage error while committing a block {:?}",
+                "Encountered a storage error while committing a block at \
+                 height {}, halting due to storage corruption: {}",
+                self.storage.block.height,
                 e
-            )
-        });
+            );
+            Error::StorageCommit(e)
+        })?;
 
         let root = self.storage.merkle_root();
         tracing::info!(
@@ -513,13 +819,139 @@ where
             root,
             self.storage.last_height,
         );
+        if let Some(hook) = self.commit_hook.as_ref() {
+            hook.notify(CommitNotification {
+                height: self.storage.last_height,
+                root: MerkleRoot(root.0.clone()),
+                events,
+            });
+        }
+        self.prune_block_events();
         response.data = root.0;
-        response
+        // Tendermint re-checks the txs left in its mempool after a commit,
+        // which adds them back to the tracked mempool
+        self.mempool.clear();
+        Ok(response)
+    }
+
+    /// Prune the events of the committed blocks that are no longer retained
+    /// according to the events retention config. A failure is only logged,
+    /// as the events aren't a part of the state.
+    fn prune_block_events(&mut self) {
+        let retention = match self.events_retention.as_ref() {
+            Some(retention) => retention,
+            None => return,
+        };
+        let last_height = self.storage.last_height;
+        let retain_from = self.storage.get_events_retained_from().and_then(
+            |mut retain_from| {
+                if let Some(max_blocks) = retention.max_blocks {
+                    // The events of the last block are always retained
+                    let from =
+                        (last_height.0 + 1).saturating_sub(max_blocks.max(1));
+                    retain_from = std::cmp::max(retain_from, BlockHeight(from));
+                }
+                if let Some(max_age_secs) = retention.max_age_secs {
+                    let block_time = |height| {
+                        self.storage.get_block_header(Some(height)).map(
+                            |(header, _gas)| {
+                                header.and_then(|header| {
+                                    DateTimeUtc::try_from(header.time).ok()
+                                })
+                            },
+                        )
+                    };
+                    let max_age = Duration::seconds(
+                        max_age_secs.min(i64::MAX as u64 / 1000) as i64,
+                    );
+                    let min_time =
+                        block_time(last_height)?.and_then(|last_time| {
+                            last_time.0.checked_sub_signed(max_age)
+                        });
+                    if let Some(min_time) = min_time {
+                        while retain_from < last_height {
+                            match block_time(retain_from)? {
+                                Some(time) if time.0 < min_time => {
+                                    retain_from = retain_from.next_height()
+                                }
+                                _ => break,
+                            }
+                        }
+                    }
+                }
+                Ok(retain_from)
+            },
+        );
+        match retain_from.and_then(|retain_from| {
+            self.storage.prune_block_events(retain_from)
+        }) {
+            Ok(num_pruned) => self.compact_after_pruning(num_pruned),
+            Err(err) => {
+                tracing::error!("Failed to prune the block events: {}", err)
+            }
+        }
+    }
+
+    /// Compact the DB once the number of the pruned keys since the last
+    /// compaction exceeds the configured threshold. A failure is only logged,
+    /// as the compaction doesn't affect the state.
+    fn compact_after_pruning(&mut self, num_pruned: u64) {
+        let threshold = match self.compact_after_pruned_keys {
+            Some(threshold) => threshold,
+            None => return,
+        };
+        self.pruned_keys_since_compaction =
+            self.pruned_keys_since_compaction.saturating_add(num_pruned);
+        if self.pruned_keys_since_compaction <= threshold {
+            return;
+        }
+        tracing::info!(
+            "Compacting the DB after pruning {} keys...",
+            self.pruned_keys_since_compaction
+        );
+        match self.storage.compact() {
+            Ok(()) => {
+                tracing::info!("The DB has been compacted.");
+                self.pruned_keys_since_compaction = 0;
+            }
+            Err(err) => tracing::error!("Failed to compact the DB: {}", err),
+        }
+    }
+
+    /// Check if the fee payer of a wrapper tx is exempt from the fee.
+    pub fn is_fee_exempt(&self, wrapper: &WrapperTx) -> bool {
+        protocol::fee_exemption(wrapper, &self.storage)
+            .expect("Couldn't read the fee exemption")
+            .is_some()
+    }
+
+    /// Get the fee of a wrapper tx converted to the native token with the fee
+    /// token rates, which is how the fees paid in different tokens are
+    /// compared. The fee in a token without a rate is worth nothing.
+    pub fn get_native_fee(&self, wrapper: &WrapperTx) -> token::Amount {
+        protocol::fee_in_native_token(
+            wrapper,
+            wrapper.fee.amount,
+            &self.storage,
+        )
+        .unwrap_or_default()
+    }
+
+    /// Get the minimum fee that a wrapper tx must pay in the native token,
+    /// i.e. the current base fee multiplied by the wrapper's gas limit. An
+    /// exempt fee payer's minimum fee is zero.
+    pub fn get_min_fee(&self, wrapper: &WrapperTx) -> token::Amount {
+        protocol::min_fee(wrapper, &self.storage)
+            .expect("Couldn't read the minimum fee")
     }
 
     /// Validate a transaction request. On success, the transaction will
     /// included in the mempool and propagated to peers, otherwise it will be
     /// rejected.
+    ///
+    /// Wrapper transactions must pay at least the current base fee for every
+    /// unit of their gas limit, after their fee is converted to the native
+    /// token with the fee token rates.
     pub fn mempool_validate(
         &self,
         tx_bytes: &[u8],
@@ -527,7 +959,33 @@ where
     ) -> response::CheckTx {
         let mut response = response::CheckTx::default();
         match Tx::try_from(tx_bytes).map_err(Error::TxDecoding) {
-            Ok(_) => response.log = String::from("Mempool validation passed"),
+            Ok(tx) => {
+                match process_tx(tx) {
+                    Ok(TxType::Wrapper(wrapper)) => {
+                        if let Err(err) =
+                            protocol::check_wrapper_fee(&wrapper, &self.storage)
+                        {
+                            response.code = 1;
+                            response.log = err.to_string();
+                            return response;
+                        }
+                    }
+                    // The code and data of a wrapper's inner tx are
+                    // encrypted, so their sizes are only checked once it's
+                    // decrypted. An unencrypted tx is checked here.
+                    Ok(TxType::Raw(raw)) => {
+                        if let Err(err) =
+                            protocol::check_tx_size(&raw, &self.storage)
+                        {
+                            response.code = 1;
+                            response.log = err.to_string();
+                            return response;
+                        }
+                    }
+                    _ => {}
+                }
+                response.log = String::from("Mempool validation passed")
+            }
             Err(msg) => {
                 response.code = 1;
                 response.log = msg.to_string();
@@ -536,28 +994,80 @@ where
         response
     }
 
-    /// Simulate validation and application of a transaction.
-    fn dry_run_tx(&self, tx_bytes: &[u8]) -> response::Query {
+    /// Simulate validation and application of a transaction. The
+    /// transaction is first checked with the same validation as an
+    /// unencrypted transaction submitted to the mempool, so that a dry run
+    /// also reports a transaction that would be rejected before it's applied.
+    /// The value in a successful response is a [`rpc::DryRunResult`] encoded
+    /// with [`BorshSerialize`].
+    ///
+    /// With a `height`, the transaction is applied against the state
+    /// committed at that height instead of the latest state.
+    fn dry_run_tx(
+        &self,
+        tx_bytes: &[u8],
+        height: Option<BlockHeight>,
+    ) -> response::Query {
         let mut response = response::Query::default();
         let mut gas_meter = BlockGasMeter::default();
         let mut write_log = WriteLog::default();
+        if let Some(height) = height {
+            if let Err(info) =
+                self.write_historical_state(height, &mut write_log)
+            {
+                response.code = 1;
+                response.info = info;
+                return response;
+            }
+        }
         let mut vp_wasm_cache = self.vp_wasm_cache.read_only();
         let mut tx_wasm_cache = self.tx_wasm_cache.read_only();
         match Tx::try_from(tx_bytes) {
             Ok(tx) => {
+                let mempool_bytes =
+                    Tx::from(TxType::Raw(tx.clone())).to_bytes();
+                let mempool_response = self.mempool_validate(
+                    &mempool_bytes,
+                    MempoolTxType::NewTransaction,
+                );
+                if mempool_response.code != 0 {
+                    response.info = format!(
+                        "Mempool validation failed: {}",
+                        mempool_response.log
+                    );
+                    response.value = rpc::DryRunResult {
+                        mempool_error: Some(mempool_response.log),
+                        tx_result: None,
+                    }
+                    .try_to_vec()
+                    .expect("Serializing dry run result shouldn't fail");
+                    return response;
+                }
                 let tx = TxType::Decrypted(DecryptedTx::Decrypted(tx));
                 match protocol::apply_tx(
                     tx,
+                    None,
                     tx_bytes.len(),
                     &mut gas_meter,
                     &mut write_log,
                     &self.storage,
                     &mut vp_wasm_cache,
                     &mut tx_wasm_cache,
+                    self.wasm_timeout,
                 )
                 .map_err(Error::TxApply)
                 {
-                    Ok(result) => response.info = result.to_string(),
+                    Ok(result) => {
+                        response.info = result.to_string();
+                        // The structured result, used by the client to
+                        // explain which VPs were invoked
+                        response.value = rpc::DryRunResult {
+                            mempool_error: None,
+                            tx_result: Some(result),
+                        }
+                        .try_to_vec()
+                        .expect("Serializing dry run result shouldn't fail");
+                    }
                     Err(error) => {
                         response.code = 1;
                         response.log = format!("{}", error);
@@ -573,6 +1083,43 @@ where
         }
     }
 
+    /// Write the state committed at the given height over the latest state
+    /// into the block write log, so that a transaction applied with this
+    /// write log runs against the historical state. The height must not have
+    /// been pruned from the predecessor epochs.
+    fn write_historical_state(
+        &self,
+        height: BlockHeight,
+        write_log: &mut WriteLog,
+    ) -> std::result::Result<(), String> {
+        if self.storage.block.pred_epochs.get_epoch(height).is_none() {
+            return Err(format!(
+                "The state at height {} has been pruned and it can no longer \
+                 be queried",
+                height
+            ));
+        }
+        let changes = self
+            .storage
+            .read_changes_since(height)
+            .map_err(|err| format!("Storage error: {}", err))?;
+        for (key, value) in changes {
+            let result = match value {
+                Some(value) => write_log.write(&key, value),
+                // The validity predicates can't be deleted, so the accounts
+                // initialized after the height are left in place. No key of
+                // the historical state refers to them.
+                None if key.is_validity_predicate().is_some() => continue,
+                None => write_log.delete(&key),
+            };
+            result.map_err(|err| format!("Write log error: {}", err))?;
+        }
+        // Commit the historical state to the block write log, so that it's not
+        // seen as changed by the transaction
+        write_log.commit_tx();
+        Ok(())
+    }
+
     /// Lookup a validator's keypair for their established account from their
     /// wallet. If the node is not validator, this function returns None
     #[cfg(not(feature = "ABCI"))]
@@ -599,9 +1146,8 @@ where
                     "A validator should have a public key associated with \
                      it's established account",
                 );
-            let pk = common::SecretKey::deserialize(&mut pk_bytes.as_slice())
-                .expect("Validator's public key should be deserializable")
-                .ref_to();
+            let pk: common::PublicKey = decode_versioned(&pk_bytes)
+                .expect("Validator's public key should be deserializable");
             wallet.find_key_by_pk(&pk).expect(
                 "A validator's established keypair should be stored in its \
                  wallet",
@@ -623,6 +1169,7 @@ mod test_utils {
     use anoma::types::key::*;
     use anoma::types::storage::{BlockHash, Epoch};
     use anoma::types::transaction::Fee;
+    use borsh::BorshDeserialize;
     use tempfile::tempdir;
     #[cfg(not(feature = "ABCI"))]
     use tendermint::block::{header::Version, Header};
@@ -752,10 +1299,11 @@ mod test_utils {
         }
 
         /// Add a wrapper tx to the queue of txs to be decrypted
-        /// in the current block proposal
+        /// in the current block proposal, as if its fee had been charged
         #[cfg(test)]
         pub fn enqueue_tx(&mut self, wrapper: WrapperTx) {
-            self.shell.storage.tx_queue.push(wrapper);
+            let charged_fee = wrapper.fee.amount;
+            self.shell.storage.tx_queue.push(wrapper, charged_fee);
             self.shell.reset_tx_queue_iter();
         }
 
@@ -817,12 +1365,288 @@ mod test_utils {
                         .expect("Should not fail"),
                 },
                 byzantine_validators: vec![],
+                last_commit_votes: vec![],
                 txs: vec![],
                 reject_all_decrypted: false,
             }
         }
     }
 
+    /// Test that a failure to store a block in the DB is returned from the
+    /// commit, so that the node halts instead of proceeding with an
+    /// uncommitted block.
+    #[test]
+    fn test_commit_failure_halts() {
+        let (mut shell, _) = setup();
+        let last_height = shell.shell.storage.last_height;
+        shell.shell.storage.block.height = last_height.next_height();
+        shell.shell.storage.db.fail_block_writes();
+
+        let result = shell.shell.commit();
+        assert!(matches!(result, Err(Error::StorageCommit(_))));
+        // The block must not be considered committed
+        assert_eq!(shell.shell.storage.last_height, last_height);
+    }
+
+    /// Test that a failure to apply the block's write log to the DB is
+    /// returned from the commit like a failure to store the block.
+    #[test]
+    fn test_commit_write_log_failure_halts() {
+        let (mut shell, _) = setup();
+        let last_height = shell.shell.storage.last_height;
+        shell.shell.storage.block.height = last_height.next_height();
+        let key = Key::parse("key").expect("Test failed");
+        shell
+            .shell
+            .write_log
+            .write(&key, vec![1_u8])
+            .expect("Test failed");
+        shell.shell.write_log.commit_tx();
+        shell.shell.storage.db.fail_batch_writes();
+
+        let result = shell.shell.commit();
+        assert!(matches!(result, Err(Error::StorageCommit(_))));
+        // The block must not be considered committed
+        assert_eq!(shell.shell.storage.last_height, last_height);
+    }
+
+    /// Test that the node halts only before the block at the configured halt
+    /// height
+    #[test]
+    fn test_halt_at_height() {
+        let (mut shell, _) = setup();
+        assert!(!shell.shell.halt_at(BlockHeight(1)));
+
+        shell.shell.halt_height = Some(BlockHeight(3));
+        assert!(!shell.shell.halt_at(BlockHeight(2)));
+        assert!(shell.shell.halt_at(BlockHeight(3)));
+        assert!(!shell.shell.halt_at(BlockHeight(4)));
+    }
+
+    /// Test that a commit hook configured with a local HTTP listener receives
+    /// the height and root of a committed block
+    #[test]
+    fn test_commit_hook_notified() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+        use std::time::{Duration, Instant};
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (mut shell, _) = setup();
+        shell.shell.commit_hook = Some(CommitHook::new(config::CommitHook {
+            command: None,
+            http_url: Some(url),
+            include_events: true,
+        }));
+        let height = shell.shell.storage.last_height.next_height();
+        shell.shell.storage.block.height = height;
+        shell.shell.commit().expect("Test failed");
+        let root = shell.shell.storage.merkle_root();
+
+        // Wait for the hook's request
+        listener.set_nonblocking(true).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut stream = loop {
+            match listener.accept() {
+                Ok((stream, _)) => break stream,
+                Err(_) if Instant::now() < deadline => {
+                    std::thread::sleep(Duration::from_millis(10))
+                }
+                Err(err) => panic!("The commit hook wasn't notified: {}", err),
+            }
+        };
+        stream.set_nonblocking(false).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+
+        // Read the request's headers and its JSON body
+        let mut request = vec![];
+        let mut buf = [0u8; 1024];
+        let body = loop {
+            let read = stream.read(&mut buf).expect("Test failed");
+            assert_ne!(read, 0, "The request ended early");
+            request.extend_from_slice(&buf[..read]);
+            let request = String::from_utf8_lossy(&request);
+            if let Some((headers, body)) = request.split_once("\r\n\r\n") {
+                let content_length = headers
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse::<usize>().unwrap())
+                    })
+                    .expect("Test failed");
+                if body.len() >= content_length {
+                    break body.to_owned();
+                }
+            }
+        };
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+            .unwrap();
+
+        let notification: serde_json::Value =
+            serde_json::from_str(&body).expect("Test failed");
+        assert_eq!(notification["height"], height.0);
+        assert_eq!(notification["root"], root.to_string());
+        assert_eq!(notification["events"], serde_json::json!([]));
+    }
+
+    /// Set limits that permit small code with larger data
+    fn set_tx_size_limits(shell: &mut TestShell) {
+        parameters::update_tx_size_limits_parameter(
+            &mut shell.shell.storage,
+            &parameters::TxSizeLimits {
+                max_code_size: Some(100),
+                max_data_size: Some(1_000),
+            },
+        )
+        .expect("Test failed");
+    }
+
+    /// Test that a tx with small code and a data payload over the data size
+    /// limit is rejected from the mempool with the data size error
+    #[test]
+    fn test_mempool_tx_data_too_large() {
+        let (mut shell, _) = setup();
+        set_tx_size_limits(&mut shell);
+
+        let tx = Tx::new(vec![0; 10], Some(vec![0; 1_000]));
+        let tx_bytes = Tx::from(TxType::Raw(tx)).to_bytes();
+        let response = shell
+            .shell
+            .mempool_validate(&tx_bytes, MempoolTxType::NewTransaction);
+        assert_eq!(response.code, 0, "{}", response.log);
+
+        let tx = Tx::new(vec![0; 10], Some(vec![0; 1_001]));
+        let tx_bytes = Tx::from(TxType::Raw(tx)).to_bytes();
+        let response = shell
+            .shell
+            .mempool_validate(&tx_bytes, MempoolTxType::NewTransaction);
+        assert_eq!(response.code, 1);
+        assert_eq!(
+            response.log,
+            parameters::TxSizeError::DataTooLarge {
+                size: 1_001,
+                max_size: 1_000,
+            }
+            .to_string()
+        );
+    }
+
+    /// Test that a tx with small data and code over the code size limit is
+    /// rejected from the mempool with the code size error, even though its
+    /// code is smaller than the data size limit
+    #[test]
+    fn test_mempool_tx_code_too_large() {
+        let (mut shell, _) = setup();
+        set_tx_size_limits(&mut shell);
+
+        let tx = Tx::new(vec![0; 101], Some(vec![0; 10]));
+        let tx_bytes = Tx::from(TxType::Raw(tx)).to_bytes();
+        let response = shell
+            .shell
+            .mempool_validate(&tx_bytes, MempoolTxType::NewTransaction);
+        assert_eq!(response.code, 1);
+        assert_eq!(
+            response.log,
+            parameters::TxSizeError::CodeTooLarge {
+                size: 101,
+                max_size: 100,
+            }
+            .to_string()
+        );
+    }
+
+    /// Test that a dry run of a tx that is accepted by the VPs reports that
+    /// it would be rejected by the mempool once its code is over the size
+    /// limit
+    #[test]
+    fn test_dry_run_tx_mempool_error() {
+        let (mut shell, _) = setup();
+        let tx_code = std::fs::read(
+            top_level_directory().join("wasm_for_tests/tx_no_op.wasm"),
+        )
+        .expect("Expected a file at given code path");
+        let tx_bytes = Tx::new(tx_code, Some(vec![]))
+            .sign(&gen_keypair())
+            .to_bytes();
+
+        let response = shell.shell.dry_run_tx(&tx_bytes, None);
+        assert_eq!(response.code, 0, "{}", response.log);
+        let result = rpc::DryRunResult::try_from_slice(&response.value[..])
+            .expect("Test failed");
+        assert!(result.mempool_error.is_none());
+        assert!(result.is_accepted());
+
+        set_tx_size_limits(&mut shell);
+        let response = shell.shell.dry_run_tx(&tx_bytes, None);
+        assert_eq!(response.code, 0, "{}", response.log);
+        let result = rpc::DryRunResult::try_from_slice(&response.value[..])
+            .expect("Test failed");
+        assert!(!result.is_accepted());
+        assert!(result.tx_result.is_none());
+        let error = result.mempool_error.expect("Test failed");
+        assert!(error.starts_with("The tx code size"), "{}", error);
+    }
+
+    /// Test that a dry run at a historical height is applied against the
+    /// state committed at that height and that a pruned height is rejected
+    #[test]
+    fn test_dry_run_tx_at_height() {
+        let (mut shell, _) = setup();
+        // The key is only written in the second block
+        let key = Key::parse("test_dry_run").expect("Test failed");
+        for height in [1, 2] {
+            shell
+                .shell
+                .storage
+                .begin_block(BlockHash::default(), BlockHeight(height))
+                .expect("Test failed");
+            if height == 2 {
+                shell
+                    .shell
+                    .storage
+                    .write(&key, vec![1])
+                    .expect("Test failed");
+            }
+            shell.shell.storage.commit().expect("Test failed");
+        }
+
+        // The tx fails when the key it reads doesn't exist
+        let tx_code = std::fs::read(
+            top_level_directory()
+                .join("wasm_for_tests/tx_read_storage_key.wasm"),
+        )
+        .expect("Expected a file at given code path");
+        let tx_bytes =
+            Tx::new(tx_code, Some(key.try_to_vec().expect("Test failed")))
+                .to_bytes();
+        let response = shell.shell.dry_run_tx(&tx_bytes, None);
+        assert_eq!(response.code, 0, "{}", response.log);
+        let response = shell.shell.dry_run_tx(&tx_bytes, Some(BlockHeight(1)));
+        assert_eq!(response.code, 1);
+        assert!(
+            response.log.contains("Transaction runner error"),
+            "{}",
+            response.log
+        );
+
+        // Prune the epochs that ended before the second block
+        let pred_epochs = &mut shell.shell.storage.block.pred_epochs;
+        pred_epochs.new_epoch(BlockHeight(2), 1);
+        pred_epochs.new_epoch(BlockHeight(3), 1);
+        let response = shell.shell.dry_run_tx(&tx_bytes, Some(BlockHeight(1)));
+        assert_eq!(response.code, 1);
+        assert!(
+            response.info.contains("has been pruned"),
+            "{}",
+            response.info
+        );
+    }
+
     /// We test that on shell shutdown, the tx queue gets persisted in a DB, and
     /// on startup it is read successfully
     #[test]
@@ -854,6 +1678,7 @@ mod test_utils {
             Fee {
                 amount: 0.into(),
                 token: xan(),
+                gas_price: None,
             },
             &keypair,
             Epoch(0),
@@ -861,7 +1686,10 @@ mod test_utils {
             tx,
             Default::default(),
         );
-        shell.storage.tx_queue.push(wrapper);
+        shell
+            .storage
+            .tx_queue
+            .push(wrapper, token::Amount::default());
         // Artificially increase the block height so that chain
         // will read the new block when restarted
         let merkle_tree = MerkleTree::<Sha256Hasher>::default();
@@ -883,6 +1711,7 @@ mod test_utils {
                 next_epoch_min_start_time: DateTimeUtc::now(),
                 address_gen: &address_gen,
                 tx_queue: &shell.storage.tx_queue,
+                events: &[],
             })
             .expect("Test failed");
 
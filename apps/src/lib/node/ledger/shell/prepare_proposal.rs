@@ -17,6 +17,11 @@ mod prepare_block {
         /// by tendermint. The rest of the block is filled with decryptions
         /// of the wrapper txs from the previously committed block.
         ///
+        /// When the maximum number of txs per block is configured, the
+        /// decryptions are always included and only as many new wrapper txs
+        /// as there's room left for are included. The other wrapper txs stay
+        /// in the mempool for the next blocks.
+        ///
         /// INVARIANT: Any changes applied in this method must be reverted if
         /// the proposal is rejected (unless we can simply overwrite
         /// them in the next block).
@@ -32,6 +37,26 @@ mod prepare_block {
                 // TODO: This should not be hardcoded
                 let privkey = <EllipticCurve as PairingEngine>::G2Affine::prime_subgroup_generator();
 
+                // decrypt the wrapper txs included in the previous block
+                let mut decrypted_txs: Vec<TxBytes> = self
+                    .storage
+                    .tx_queue
+                    .iter()
+                    .map(|tx| {
+                        Tx::from(match tx.decrypt(privkey) {
+                            Ok(tx) => DecryptedTx::Decrypted(tx),
+                            _ => DecryptedTx::Undecryptable(tx.clone()),
+                        })
+                        .to_bytes()
+                    })
+                    .collect();
+                // the decrypted txs must be included, the new wrappers fill
+                // the rest of the block
+                let max_wrapper_txs = self
+                    .max_txs_per_block
+                    .map(|max| max.saturating_sub(decrypted_txs.len()))
+                    .unwrap_or(usize::MAX);
+
                 // TODO: Craft the Ethereum state update tx
                 // filter in half of the new txs from Tendermint, only keeping
                 // wrappers
@@ -47,20 +72,7 @@ mod prepare_block {
                             false
                         }
                     })
-                    .collect();
-
-                // decrypt the wrapper txs included in the previous block
-                let mut decrypted_txs = self
-                    .storage
-                    .tx_queue
-                    .iter()
-                    .map(|tx| {
-                        Tx::from(match tx.decrypt(privkey) {
-                            Ok(tx) => DecryptedTx::Decrypted(tx),
-                            _ => DecryptedTx::Undecryptable(tx.clone()),
-                        })
-                        .to_bytes()
-                    })
+                    .take(max_wrapper_txs)
                     .collect();
 
                 txs.append(&mut decrypted_txs);
@@ -117,6 +129,7 @@ mod prepare_block {
                         Fee {
                             amount: 0.into(),
                             token: xan(),
+                            gas_price: None,
                         },
                         &keypair,
                         Epoch(0),
@@ -167,6 +180,7 @@ mod prepare_block {
                     Fee {
                         amount: 0.into(),
                         token: xan(),
+                        gas_price: None,
                     },
                     &keypair,
                     Epoch(0),
@@ -202,6 +216,68 @@ mod prepare_block {
             // check that the order of the txs is correct
             assert_eq!(received, expected_txs);
         }
+
+        /// Test that when more wrapper txs than the maximum number of txs
+        /// per block are given, the decrypted txs are included and the excess
+        /// wrappers are deferred to the next block
+        #[test]
+        fn test_max_txs_per_block() {
+            let (mut shell, _) = TestShell::new();
+            shell.shell.max_txs_per_block = Some(3);
+            let keypair = gen_keypair();
+            let new_wrapper = |i: usize| {
+                let tx = Tx::new(
+                    "wasm_code".as_bytes().to_owned(),
+                    Some(format!("transaction data: {}", i).into_bytes()),
+                );
+                WrapperTx::new(
+                    Fee {
+                        amount: 0.into(),
+                        token: xan(),
+                        gas_price: None,
+                    },
+                    &keypair,
+                    Epoch(0),
+                    0.into(),
+                    tx,
+                    Default::default(),
+                )
+            };
+            // a wrapper from the previous block to be decrypted
+            shell.enqueue_tx(new_wrapper(0));
+            // new wrappers from the mempool, more than the limit
+            let wrappers: Vec<TxBytes> = (1..=8)
+                .map(|i| {
+                    new_wrapper(i)
+                        .sign(&keypair)
+                        .expect("Test failed")
+                        .to_bytes()
+                })
+                .collect();
+
+            let req = RequestPrepareProposal {
+                block_data: wrappers.clone(),
+                block_data_size: 0,
+            };
+            let block_data = shell.prepare_proposal(req).block_data;
+            // the first two wrappers and the decrypted tx fill the block
+            assert_eq!(block_data.len(), 3);
+            assert_eq!(&block_data[..2], &wrappers[..2]);
+            assert!(matches!(
+                process_tx(Tx::try_from(block_data[2].as_slice()).unwrap()),
+                Ok(TxType::Decrypted(_))
+            ));
+
+            // the excess wrappers are left in the mempool and included in
+            // the next block
+            let req = RequestPrepareProposal {
+                block_data: wrappers[2..].to_vec(),
+                block_data_size: 0,
+            };
+            let block_data = shell.prepare_proposal(req).block_data;
+            assert_eq!(block_data.len(), 3);
+            assert_eq!(&block_data[..2], &wrappers[2..4]);
+        }
     }
 }
 
@@ -118,13 +118,24 @@ where
                                 hash_tx(&req.tx)
                             ),
                         }
+                    } else if let Err(err) =
+                        protocol::check_wrapper_fee(&tx, &self.storage)
+                    {
+                        // the fee amount must match the gas price, which
+                        // must cover the base fee, and the fee must cover
+                        // the base fee for the gas limit
+                        TxResult {
+                            code: ErrorCodes::InvalidTx.into(),
+                            info: err.to_string(),
+                        }
                     } else {
-                        // check that the fee payer has sufficient balance
+                        // check that the fee payer has sufficient balance,
+                        // unless it's exempt from the fee
                         let balance = self
                             .get_balance(&tx.fee.token, &tx.fee_payer())
                             .unwrap_or_default();
 
-                        if tx.fee.amount <= balance {
+                        if tx.fee.amount <= balance || self.is_fee_exempt(&tx) {
                             shim::response::TxResult {
                                 code: ErrorCodes::Ok.into(),
                                 info: "Process proposal accepted this \
@@ -147,7 +158,8 @@ where
     }
 
     /// If we are not using ABCI++, we check the wrapper,
-    /// decode it, and check the decoded payload all at once
+    /// decode it, and check the decoded payload all at once. The wrapper's
+    /// fee is debited before the decoded payload is checked.
     #[cfg(feature = "ABCI")]
     pub fn process_and_decode_proposal(
         &mut self,
@@ -200,13 +212,35 @@ where
         if wrapper_resp.result.code == 0 {
             // if the wrapper passed, decode it
             if let Ok(TxType::Wrapper(wrapper)) = process_tx(req_tx) {
+                // The wrapper is not applied in `finalize_block`, so its fee
+                // is charged here, before its inner tx is applied
+                let charged_fee = match protocol::charge_fee(
+                    &wrapper,
+                    &mut self.write_log,
+                    &self.storage,
+                ) {
+                    Ok(charged_fee) => charged_fee,
+                    Err(err) => {
+                        self.write_log.drop_tx();
+                        return shim::response::ProcessProposal {
+                            result: shim::response::TxResult {
+                                code: ErrorCodes::InvalidTx.into(),
+                                info: err.to_string(),
+                            },
+                            // this ensures that emitted events are of the correct
+                            // type
+                            tx: req.tx,
+                        };
+                    }
+                };
+                self.write_log.commit_tx();
                 let decoded = Tx::from(match wrapper.decrypt(privkey) {
                     Ok(tx) => DecryptedTx::Decrypted(tx),
                     _ => DecryptedTx::Undecryptable(wrapper.clone()),
                 })
                 .to_bytes();
                 // we are not checking that txs are out of order
-                self.storage.tx_queue.push(wrapper);
+                self.storage.tx_queue.push(wrapper, charged_fee);
                 // check the decoded tx
                 let mut decoded_resp =
                     self.process_proposal(shim::request::ProcessProposal {
@@ -250,7 +284,7 @@ mod test_process_proposal {
     use anoma::types::storage::Epoch;
     use anoma::types::token::Amount;
     use anoma::types::transaction::encrypted::EncryptedTx;
-    use anoma::types::transaction::{EncryptionKey, Fee};
+    use anoma::types::transaction::{EncryptionKey, Fee, GasLimit};
     use borsh::BorshDeserialize;
     #[cfg(not(feature = "ABCI"))]
     use tendermint_proto::abci::RequestInitChain;
@@ -279,6 +313,7 @@ mod test_process_proposal {
             Fee {
                 amount: 0.into(),
                 token: xan(),
+                gas_price: None,
             },
             &keypair,
             Epoch(0),
@@ -321,6 +356,7 @@ mod test_process_proposal {
             Fee {
                 amount: 100.into(),
                 token: xan(),
+                gas_price: None,
             },
             &keypair,
             Epoch(0),
@@ -400,6 +436,7 @@ mod test_process_proposal {
             Fee {
                 amount: 1.into(),
                 token: xan(),
+                gas_price: None,
             },
             &keypair,
             Epoch(0),
@@ -450,6 +487,7 @@ mod test_process_proposal {
             Fee {
                 amount: Amount::whole(1_000_100),
                 token: xan(),
+                gas_price: None,
             },
             &keypair,
             Epoch(0),
@@ -479,6 +517,132 @@ mod test_process_proposal {
         }
     }
 
+    /// Test that if the fee of a wrapper tx doesn't cover the base fee for its
+    /// gas limit, [`process_proposal`] rejects that tx
+    #[test]
+    fn test_wrapper_fee_below_base_fee() {
+        let (mut shell, _) = TestShell::new();
+        shell.init_chain(RequestInitChain {
+            time: Some(Timestamp {
+                seconds: 0,
+                nanos: 0,
+            }),
+            chain_id: ChainId::default().to_string(),
+            ..Default::default()
+        });
+        parameters::update_base_fee_parameter(
+            &mut shell.shell.storage,
+            &Amount::from(10),
+        )
+        .expect("Test failed");
+        let keypair = crate::wallet::defaults::daewon_keypair();
+
+        let tx = Tx::new(
+            "wasm_code".as_bytes().to_owned(),
+            Some("transaction data".as_bytes().to_owned()),
+        );
+        let wrapper = WrapperTx::new(
+            Fee {
+                amount: Amount::from(9_999_999),
+                token: xan(),
+                gas_price: None,
+            },
+            &keypair,
+            Epoch(0),
+            1_000_000.into(),
+            tx,
+            Default::default(),
+        )
+        .sign(&keypair)
+        .expect("Test failed");
+
+        let request = ProcessProposal {
+            tx: wrapper.to_bytes(),
+        };
+
+        let response = shell.process_proposal(request);
+        assert_eq!(response.result.code, u32::from(ErrorCodes::InvalidTx));
+        assert_eq!(
+            response.result.info,
+            format!(
+                "The fee {} is below the minimum fee {} given by the current \
+                 base fee",
+                Amount::from(9_999_999),
+                Amount::from(10_000_000)
+            )
+        );
+    }
+
+    /// Test that a wrapper tx with an explicit gas price is accepted if the
+    /// gas price covers the base fee and its fee amount is the gas price
+    /// times the gas limit, and that it is rejected otherwise
+    #[test]
+    fn test_wrapper_gas_price() {
+        let (mut shell, _) = TestShell::new();
+        shell.init_chain(RequestInitChain {
+            time: Some(Timestamp {
+                seconds: 0,
+                nanos: 0,
+            }),
+            chain_id: ChainId::default().to_string(),
+            ..Default::default()
+        });
+        parameters::update_base_fee_parameter(
+            &mut shell.shell.storage,
+            &Amount::from(10),
+        )
+        .expect("Test failed");
+        let keypair = crate::wallet::defaults::daewon_keypair();
+        let gas_limit = GasLimit::from(1_000_000);
+        let make_request = |fee: Fee| {
+            let tx = Tx::new(
+                "wasm_code".as_bytes().to_owned(),
+                Some("transaction data".as_bytes().to_owned()),
+            );
+            let wrapper = WrapperTx::new(
+                fee,
+                &keypair,
+                Epoch(0),
+                gas_limit.clone(),
+                tx,
+                Default::default(),
+            )
+            .sign(&keypair)
+            .expect("Test failed");
+            ProcessProposal {
+                tx: wrapper.to_bytes(),
+            }
+        };
+
+        // The gas price is equal to the base fee
+        let fee = Fee::from_gas_price(Amount::from(10), xan(), &gas_limit);
+        assert_eq!(fee.amount, Amount::from(10_000_000));
+        let response = shell.process_proposal(make_request(fee));
+        assert_eq!(response.result.code, u32::from(ErrorCodes::Ok));
+
+        // The gas price is below the base fee
+        let fee = Fee::from_gas_price(Amount::from(9), xan(), &gas_limit);
+        let response = shell.process_proposal(make_request(fee));
+        assert_eq!(response.result.code, u32::from(ErrorCodes::InvalidTx));
+        assert_eq!(
+            response.result.info,
+            protocol::Error::GasPriceBelowBaseFee {
+                gas_price: Amount::from(9),
+                base_fee: Amount::from(10),
+            }
+            .to_string()
+        );
+
+        // The fee amount doesn't match the gas price
+        let fee = Fee {
+            amount: Amount::from(20_000_000),
+            ..Fee::from_gas_price(Amount::from(10), xan(), &gas_limit)
+        };
+        let response = shell.process_proposal(make_request(fee));
+        assert_eq!(response.result.code, u32::from(ErrorCodes::InvalidTx));
+        assert!(response.result.info.starts_with("Invalid wrapper tx fee"));
+    }
+
     #[cfg(not(feature = "ABCI"))]
     /// Test that if the expected order of decrypted txs is
     /// validated, [`process_proposal`] rejects it
@@ -496,6 +660,7 @@ mod test_process_proposal {
                 Fee {
                     amount: i.into(),
                     token: xan(),
+                    gas_price: None,
                 },
                 &keypair,
                 Epoch(0),
@@ -543,6 +708,7 @@ mod test_process_proposal {
             Fee {
                 amount: 0.into(),
                 token: xan(),
+                gas_price: None,
             },
             &keypair,
             Epoch(0),
@@ -592,6 +758,7 @@ mod test_process_proposal {
             Fee {
                 amount: 0.into(),
                 token: xan(),
+                gas_price: None,
             },
             &keypair,
             Epoch(0),
@@ -656,6 +823,7 @@ mod test_process_proposal {
             fee: Fee {
                 amount: 0.into(),
                 token: xan(),
+                gas_price: None,
             },
             pk: keypair.ref_to(),
             epoch: Epoch(0),
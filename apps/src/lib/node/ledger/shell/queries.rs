@@ -1,15 +1,19 @@
 //! Shell methods for querying state
-use std::cmp::max;
+use std::cmp::{max, Reverse};
+use std::collections::{BTreeMap, BinaryHeap};
 
+use anoma::ledger::governance::storage as gov_storage;
 use anoma::ledger::parameters::EpochDuration;
 use anoma::ledger::pos::PosParams;
-use anoma::types::address::Address;
+use anoma::types::address::{Address, InternalAddress};
 use anoma::types::key;
 use anoma::types::key::dkg_session_keys::DkgPublicKey;
-use anoma::types::storage::{Key, PrefixValue};
+use anoma::types::storage::{DbKeySeg, Key, PrefixValue};
+use anoma::types::time::DateTimeUtc;
 use anoma::types::token::{self, Amount};
 use borsh::{BorshDeserialize, BorshSerialize};
 use ferveo_common::TendermintValidator;
+use sha2::{Digest, Sha256};
 #[cfg(not(feature = "ABCI"))]
 use tendermint_proto::crypto::{ProofOp, ProofOps};
 #[cfg(not(feature = "ABCI"))]
@@ -24,7 +28,41 @@ use tendermint_proto_abci::google::protobuf;
 use tendermint_proto_abci::types::EvidenceParams;
 
 use super::*;
+use crate::node::ledger::events::BlockEvent;
 use crate::node::ledger::response;
+use crate::wasm_loader;
+
+/// The built-in VP templates, by their name and WASM file name
+const VP_TEMPLATES: [(&str, &str); 4] = [
+    ("user", "vp_user.wasm"),
+    ("token", "vp_token.wasm"),
+    ("nft", "vp_nft.wasm"),
+    ("testnet_faucet", "vp_testnet_faucet.wasm"),
+];
+
+/// The category of a storage key for the [`rpc::Path::StorageSize`] query
+fn storage_key_category(key: &Key) -> &'static str {
+    if token::is_any_token_balance_key(key).is_some() {
+        return "balances";
+    }
+    if key.is_validity_predicate().is_some() {
+        return "vps";
+    }
+    match key.segments.first() {
+        Some(DbKeySeg::AddressSeg(Address::Internal(internal))) => {
+            match internal {
+                InternalAddress::PoS | InternalAddress::PosSlashPool => "pos",
+                InternalAddress::Governance => "governance",
+                InternalAddress::Parameters => "parameters",
+                InternalAddress::Ibc
+                | InternalAddress::IbcEscrow(_)
+                | InternalAddress::IbcBurn
+                | InternalAddress::IbcMint => "ibc",
+            }
+        }
+        _ => "other",
+    }
+}
 
 impl<D, H> Shell<D, H>
 where
@@ -34,6 +72,12 @@ where
     /// Uses `path` in the query to forward the request to the
     /// right query method and returns the result (which may be
     /// the default if `path` is not a supported string.
+    /// The query's `height` and `prove` are honored by every path that
+    /// supports them (see [`rpc::Path::supports_height`] and
+    /// [`rpc::Path::supports_proof`]) and an error is returned for the other
+    /// paths, rather than ignoring them.
+    /// The queries over the configured rate limit, if any, are refused with
+    /// the [`query_limit::THROTTLED_QUERY_CODE`].
     /// INVARIANT: This method must be stateless.
     pub fn query(&self, query: request::Query) -> response::Query {
         use rpc::Path;
@@ -51,30 +95,103 @@ where
                 };
             }
         };
-        match Path::from_str(&query.path) {
-            Ok(path) => match path {
-                Path::DryRunTx => self.dry_run_tx(&query.data),
-                Path::Epoch => {
-                    let (epoch, _gas) = self.storage.get_last_epoch();
-                    let value = anoma::ledger::storage::types::encode(&epoch);
-                    response::Query {
-                        value,
-                        ..Default::default()
-                    }
-                }
-                Path::Value(storage_key) => {
-                    self.read_storage_value(&storage_key, height, query.prove)
-                }
-                Path::Prefix(storage_key) => {
-                    self.read_storage_prefix(&storage_key, height, query.prove)
-                }
-                Path::HasKey(storage_key) => self.has_storage_key(&storage_key),
-            },
-            Err(err) => response::Query {
+        let path = match Path::from_str(&query.path) {
+            Ok(path) => path,
+            Err(err) => {
+                return response::Query {
+                    code: 1,
+                    info: format!("RPC error: {}", err),
+                    ..Default::default()
+                };
+            }
+        };
+        if let Some(limiter) = &self.query_limiter {
+            let heavy = path.is_heavy();
+            let mut limiter = limiter.borrow_mut();
+            if !limiter.try_acquire(heavy, (self.clock)()) {
+                return response::Query {
+                    code: query_limit::THROTTLED_QUERY_CODE,
+                    info: format!(
+                        "The query {} was throttled, this node serves at most \
+                         {} such queries per second, please retry later",
+                        path,
+                        limiter.max_per_sec(heavy)
+                    ),
+                    ..Default::default()
+                };
+            }
+        }
+        if query.prove && !path.supports_proof() {
+            return response::Query {
                 code: 1,
-                info: format!("RPC error: {}", err),
+                info: format!(
+                    "The result of the query {} can't be proven, a proof can \
+                     only be requested for the storage queries",
+                    path
+                ),
                 ..Default::default()
-            },
+            };
+        }
+        let is_historical = height < self.storage.get_block_height().0;
+        if is_historical && !path.supports_height() {
+            return response::Query {
+                code: 1,
+                info: format!(
+                    "The query {} can only be served at the last committed \
+                     height {}, not at height {}",
+                    path,
+                    self.storage.get_block_height().0,
+                    height
+                ),
+                ..Default::default()
+            };
+        }
+        match path {
+            Path::DryRunTx => {
+                self.dry_run_tx(&query.data, is_historical.then(|| height))
+            }
+            Path::Epoch if is_historical => self.epoch_at_height(height),
+            Path::Epoch => {
+                let (epoch, _gas) = self.storage.get_last_epoch();
+                let value = anoma::ledger::storage::types::encode(&epoch);
+                response::Query {
+                    value,
+                    ..Default::default()
+                }
+            }
+            Path::EpochProgress => {
+                let progress = self.storage.epoch_progress(
+                    self.storage.last_height,
+                    DateTimeUtc::now(),
+                );
+                let value = anoma::ledger::storage::types::encode(&progress);
+                response::Query {
+                    value,
+                    ..Default::default()
+                }
+            }
+            Path::EpochStartHeight(epoch) => self.epoch_start_height(epoch),
+            Path::Value(storage_key) => {
+                self.read_storage_value(&storage_key, height, query.prove)
+            }
+            Path::Prefix(storage_key) => {
+                self.read_storage_prefix(&storage_key, height, query.prove)
+            }
+            Path::HasKey(storage_key) => {
+                self.has_storage_key(&storage_key, height, query.prove)
+            }
+            Path::AccountMetadata(address) => {
+                self.query_account_metadata(address)
+            }
+            Path::SimulateBlock => self.simulate_block_query(&query.data),
+            Path::BlockEvents { height, event_type } => {
+                self.block_events(height, event_type.as_deref())
+            }
+            Path::BlockSummary(height) => self.block_summary(height),
+            Path::Mempool { list_txs } => self.mempool_query(list_txs),
+            Path::NodeStatus => self.node_status(),
+            Path::StorageSize(prefix) => self.storage_size(prefix.as_ref()),
+            Path::ValidatorSet(epoch) => self.validator_set(epoch),
         }
     }
 
@@ -106,6 +223,353 @@ where
         }
     }
 
+    /// Query the epoch of the committed block with the given height. The
+    /// epoch must not have been pruned from the predecessor epochs.
+    fn epoch_at_height(&self, height: BlockHeight) -> response::Query {
+        match self.storage.block.pred_epochs.get_epoch(height) {
+            Some(epoch) => response::Query {
+                value: anoma::ledger::storage::types::encode(&epoch),
+                ..Default::default()
+            },
+            None => response::Query {
+                code: 1,
+                info: format!(
+                    "The epoch of the block {} has been pruned and it can no \
+                     longer be queried",
+                    height
+                ),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Query the height of the first block of the given epoch. The epoch must
+    /// have started and it must not have been pruned from the predecessor
+    /// epochs.
+    fn epoch_start_height(&self, epoch: Epoch) -> response::Query {
+        let (last_epoch, _gas) = self.storage.get_last_epoch();
+        if epoch > last_epoch {
+            return response::Query {
+                code: 1,
+                info: format!(
+                    "The epoch {} hasn't started yet, the last committed \
+                     epoch is {}",
+                    epoch, last_epoch
+                ),
+                ..Default::default()
+            };
+        }
+        match self.storage.block.pred_epochs.get_first_block_height(epoch) {
+            Some(height) => response::Query {
+                value: height.try_to_vec().unwrap(),
+                ..Default::default()
+            },
+            None => response::Query {
+                code: 1,
+                info: format!(
+                    "The state of the epoch {} has been pruned and it can no \
+                     longer be queried",
+                    epoch
+                ),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Query the status of this node
+    fn node_status(&self) -> response::Query {
+        let (genesis_time, _gas) = parameters::read_genesis_time(&self.storage)
+            .expect("Reading the genesis time shouldn't fail");
+        let genesis_time_reached = genesis_time
+            .map(|genesis_time| (self.clock)() >= genesis_time)
+            .unwrap_or(true);
+        let (protocol_upgrade, _gas) =
+            parameters::read_protocol_upgrade(&self.storage)
+                .expect("Reading the protocol upgrade shouldn't fail");
+        let status = rpc::NodeStatus {
+            genesis_time,
+            genesis_time_reached,
+            last_height: self.storage.last_height,
+            protocol_version: self
+                .required_protocol_version(self.storage.last_height),
+            supported_protocol_version: PROTOCOL_VERSION,
+            protocol_upgrade,
+        };
+        response::Query {
+            value: status.try_to_vec().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    /// Query the consensus validator set of the given epoch, or of the epoch
+    /// of the last committed block, i.e. the active validators with their
+    /// voting powers and consensus keys as they are given to Tendermint. The
+    /// set is known from the oldest epoch retained in the PoS validator sets
+    /// up to the pipeline offset from the last committed epoch.
+    fn validator_set(&self, epoch: Option<Epoch>) -> response::Query {
+        let (last_epoch, _gas) = self.storage.get_last_epoch();
+        let epoch = epoch.unwrap_or(last_epoch);
+        let pos_params = self.storage.read_pos_params();
+        let validator_sets = self.storage.read_validator_set();
+        let retained_from: Epoch = validator_sets.last_update().into();
+        let known_until = last_epoch + pos_params.pipeline_len;
+        let validator_set = match validator_sets.get(epoch) {
+            Some(validator_set)
+                if epoch >= retained_from && epoch <= known_until =>
+            {
+                validator_set
+            }
+            _ => {
+                return response::Query {
+                    code: 1,
+                    info: format!(
+                        "The validator set of the epoch {} is not known, the \
+                         validator sets are known from the epoch {} to {}",
+                        epoch, retained_from, known_until
+                    ),
+                    ..Default::default()
+                };
+            }
+        };
+        let mut validators = vec![];
+        // The active set is ordered from the lowest voting power
+        for validator in validator_set.active.iter().rev() {
+            let consensus_key = match self
+                .storage
+                .read_validator_consensus_key(&validator.address)
+                .and_then(|keys| keys.get(epoch).cloned())
+            {
+                Some(consensus_key) => consensus_key,
+                None => {
+                    return response::Query {
+                        code: 1,
+                        info: format!(
+                            "The consensus key of the validator {} in the \
+                             epoch {} is missing",
+                            validator.address, epoch
+                        ),
+                        ..Default::default()
+                    };
+                }
+            };
+            validators.push(rpc::ConsensusValidator {
+                address: validator.address.clone(),
+                voting_power: validator.voting_power.into(),
+                consensus_key,
+            });
+        }
+        response::Query {
+            value: rpc::ConsensusValidatorSet { epoch, validators }
+                .try_to_vec()
+                .unwrap(),
+            ..Default::default()
+        }
+    }
+
+    /// Query the events emitted in the committed block with the given height,
+    /// optionally only the ones of the given type. At most
+    /// [`rpc::MAX_BLOCK_EVENTS`] events are returned. The query fails for a
+    /// block whose events have been pruned.
+    fn block_events(
+        &self,
+        height: BlockHeight,
+        event_type: Option<&str>,
+    ) -> response::Query {
+        let events = match self.read_block_events(height) {
+            Ok(events) => events,
+            Err(response) => return response,
+        };
+        let mut events: Vec<BlockEvent> = events
+            .into_iter()
+            .filter(|event| match event_type {
+                Some(event_type) => event.event_type == event_type,
+                None => true,
+            })
+            .collect();
+        let truncated = events.len() > rpc::MAX_BLOCK_EVENTS;
+        events.truncate(rpc::MAX_BLOCK_EVENTS);
+        response::Query {
+            value: rpc::BlockEvents { events, truncated }.try_to_vec().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    /// Query the summary of the committed block with the given height. The
+    /// query fails for a block whose events, epoch or merkle tree have been
+    /// pruned.
+    fn block_summary(&self, height: BlockHeight) -> response::Query {
+        let events = match self.read_block_events(height) {
+            Ok(events) => events,
+            Err(response) => return response,
+        };
+        let epoch = match self.storage.block.pred_epochs.get_epoch(height) {
+            Some(epoch) => epoch,
+            None => {
+                return response::Query {
+                    code: 1,
+                    info: format!(
+                        "The epoch of the block {} has been pruned and it can \
+                         no longer be queried",
+                        height
+                    ),
+                    ..Default::default()
+                };
+            }
+        };
+        let root = match self.storage.get_merkle_root(height) {
+            Ok(root) => root.0,
+            Err(err) => {
+                return response::Query {
+                    code: 2,
+                    info: format!("Storage error: {}", err),
+                    ..Default::default()
+                };
+            }
+        };
+        // The result of every transaction included in the block is reported
+        // with a tx event
+        let tx_events: Vec<&BlockEvent> = events
+            .iter()
+            .filter(|event| {
+                event.event_type == "accepted" || event.event_type == "applied"
+            })
+            .collect();
+        let gas_used = tx_events
+            .iter()
+            .filter_map(|event| {
+                event.attributes.get("gas_used")?.parse::<u64>().ok()
+            })
+            .sum();
+        let summary = rpc::BlockSummary {
+            height,
+            root,
+            epoch,
+            num_txs: tx_events.len() as u64,
+            gas_used,
+        };
+        response::Query {
+            value: summary.try_to_vec().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    /// Count the keys in the last committed storage, optionally only the ones
+    /// with the given prefix, and sum up the sizes of their values, in total
+    /// and by the category of the keys. Also finds the largest values.
+    fn storage_size(&self, prefix: Option<&Key>) -> response::Query {
+        // An empty prefix matches all the keys
+        let prefix = prefix.cloned().unwrap_or(Key { segments: vec![] });
+        let (iter, _gas) = self.storage.iter_prefix(&prefix);
+        let mut num_keys = 0;
+        let mut total_bytes = 0;
+        let mut categories: BTreeMap<&str, (u64, u64)> = BTreeMap::new();
+        // The smallest of the largest values found so far is at the top
+        let mut largest: BinaryHeap<Reverse<(u64, Key)>> = BinaryHeap::new();
+        for (key, value, _gas) in iter {
+            let key = match Key::parse(key) {
+                Ok(key) => key,
+                Err(err) => {
+                    return response::Query {
+                        code: 2,
+                        info: format!("Storage error: {}", err),
+                        ..Default::default()
+                    };
+                }
+            };
+            let bytes = value.len() as u64;
+            num_keys += 1;
+            total_bytes += bytes;
+            let category =
+                categories.entry(storage_key_category(&key)).or_default();
+            category.0 += 1;
+            category.1 += bytes;
+            largest.push(Reverse((bytes, key)));
+            if largest.len() > rpc::MAX_LARGEST_VALUES {
+                largest.pop();
+            }
+        }
+        let size = rpc::StorageSize {
+            num_keys,
+            total_bytes,
+            categories: categories
+                .into_iter()
+                .map(|(name, (num_keys, bytes))| rpc::StorageSizeCategory {
+                    name: name.to_owned(),
+                    num_keys,
+                    bytes,
+                })
+                .collect(),
+            largest: largest
+                .into_sorted_vec()
+                .into_iter()
+                .map(|Reverse((bytes, key))| rpc::StorageValueSize {
+                    key,
+                    bytes,
+                })
+                .collect(),
+        };
+        response::Query {
+            value: size.try_to_vec().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    /// Read the events emitted in the committed block with the given height.
+    /// On failure, returns the response of the failed query.
+    fn read_block_events(
+        &self,
+        height: BlockHeight,
+    ) -> std::result::Result<Vec<BlockEvent>, response::Query> {
+        if height > self.storage.last_height {
+            return Err(response::Query {
+                code: 1,
+                info: format!(
+                    "The block {} hasn't been committed yet, the last \
+                     committed block is {}",
+                    height, self.storage.last_height
+                ),
+                ..Default::default()
+            });
+        }
+        match self.storage.get_events_retained_from() {
+            Ok(retained_from) if height < retained_from => {
+                return Err(response::Query {
+                    code: 1,
+                    info: format!(
+                        "The events of the block {} are not retained, the \
+                         oldest block with retained events is {}",
+                        height, retained_from
+                    ),
+                    ..Default::default()
+                });
+            }
+            Ok(_) => {}
+            Err(err) => {
+                return Err(response::Query {
+                    code: 2,
+                    info: format!("Storage error: {}", err),
+                    ..Default::default()
+                });
+            }
+        }
+        match self.storage.get_block_events(height) {
+            Ok(Some(bytes)) => Vec::<BlockEvent>::try_from_slice(&bytes)
+                .map_err(|err| response::Query {
+                    code: 2,
+                    info: format!("Error decoding the events: {}", err),
+                    ..Default::default()
+                }),
+            // No events have been stored for the blocks committed before the
+            // events were persisted
+            Ok(None) => Ok(vec![]),
+            Err(err) => Err(response::Query {
+                code: 2,
+                info: format!("Storage error: {}", err),
+                ..Default::default()
+            }),
+        }
+    }
+
     /// Query to read a value from storage
     pub fn read_storage_value(
         &self,
@@ -172,40 +636,57 @@ where
     /// Query to read a range of values from storage with a matching prefix. The
     /// value in successful response is a [`Vec<PrefixValue>`] encoded with
     /// [`BorshSerialize`].
+    ///
+    /// At a height older than the latest, the values of the keys that are
+    /// present in the latest state are read as they were at that height. Like
+    /// with [`Storage::read_with_height`], the keys that have since been
+    /// deleted are not found.
     pub fn read_storage_prefix(
         &self,
         key: &Key,
         height: BlockHeight,
         is_proven: bool,
     ) -> response::Query {
-        if height != self.storage.get_block_height().0 {
-            return response::Query {
-                code: 2,
-                info: format!(
-                    "Prefix read works with only the latest height: height {}",
-                    height
-                ),
-                ..Default::default()
-            };
-        }
+        let is_latest = height >= self.storage.get_block_height().0;
         let (iter, _gas) = self.storage.iter_prefix(key);
-        let mut iter = iter.peekable();
-        if iter.peek().is_none() {
+        let values: std::result::Result<
+            Vec<PrefixValue>,
+            anoma::types::storage::Error,
+        > = iter
+            .map(|(key, value, _gas)| {
+                let key = Key::parse(key)?;
+                Ok(PrefixValue { key, value })
+            })
+            .collect();
+        let values = match values {
+            Ok(values) if !is_latest => {
+                let mut historical = vec![];
+                for PrefixValue { key, .. } in values {
+                    match self.storage.read_with_height(&key, height) {
+                        Ok((Some(value), _gas)) => {
+                            historical.push(PrefixValue { key, value })
+                        }
+                        Ok((None, _gas)) => {}
+                        Err(err) => {
+                            return response::Query {
+                                code: 2,
+                                info: format!("Storage error: {}", err),
+                                ..Default::default()
+                            };
+                        }
+                    }
+                }
+                Ok(historical)
+            }
+            values => values,
+        };
+        if matches!(&values, Ok(values) if values.is_empty()) {
             response::Query {
                 code: 1,
                 info: format!("No value found for key: {}", key),
                 ..Default::default()
             }
         } else {
-            let values: std::result::Result<
-                Vec<PrefixValue>,
-                anoma::types::storage::Error,
-            > = iter
-                .map(|(key, value, _gas)| {
-                    let key = Key::parse(key)?;
-                    Ok(PrefixValue { key, value })
-                })
-                .collect();
             match values {
                 Ok(values) => {
                     let proof_ops = if is_proven {
@@ -258,10 +739,55 @@ where
     }
 
     /// Query to check if a storage key exists.
-    fn has_storage_key(&self, key: &Key) -> response::Query {
-        match self.storage.has_key(key) {
-            Ok((has_key, _gas)) => response::Query {
-                value: has_key.try_to_vec().unwrap(),
+    fn has_storage_key(
+        &self,
+        key: &Key,
+        height: BlockHeight,
+        is_proven: bool,
+    ) -> response::Query {
+        let value = match self.storage.read_with_height(key, height) {
+            Ok((value, _gas)) => value,
+            Err(err) => {
+                return response::Query {
+                    code: 2,
+                    info: format!("Storage error: {}", err),
+                    ..Default::default()
+                };
+            }
+        };
+        let has_key = value.is_some();
+        let proof_ops = if is_proven {
+            let proof = match value {
+                Some(value) => {
+                    self.storage.get_existence_proof(key, value, height)
+                }
+                None => self.storage.get_non_existence_proof(key, height),
+            };
+            match proof {
+                Ok(proof) => Some(proof.into()),
+                Err(err) => {
+                    return response::Query {
+                        code: 2,
+                        info: format!("Storage error: {}", err),
+                        ..Default::default()
+                    };
+                }
+            }
+        } else {
+            None
+        };
+        response::Query {
+            value: has_key.try_to_vec().unwrap(),
+            proof_ops,
+            ..Default::default()
+        }
+    }
+
+    /// Query to describe the kind of the account with the given address.
+    fn query_account_metadata(&self, address: Address) -> response::Query {
+        match self.account_metadata(address) {
+            Ok(metadata) => response::Query {
+                value: metadata.try_to_vec().unwrap(),
                 ..Default::default()
             },
             Err(err) => response::Query {
@@ -272,6 +798,53 @@ where
         }
     }
 
+    /// Find the metadata of the account with the given address. The account's
+    /// VP is matched by its hash against the built-in VP templates from the
+    /// WASM directory.
+    pub fn account_metadata(
+        &self,
+        address: Address,
+    ) -> std::result::Result<rpc::AccountMetadata, anoma::ledger::storage::Error>
+    {
+        let vp_key = Key::validity_predicate(&address);
+        let (vp, _gas) = self.storage.read(&vp_key)?;
+        let vp_template = vp.and_then(|vp| {
+            let vp_hash = Sha256::digest(&vp);
+            VP_TEMPLATES.iter().find_map(|(name, file_name)| {
+                let template =
+                    wasm_loader::try_read_wasm(&self.wasm_dir, file_name)
+                        .ok()?;
+                (Sha256::digest(&template) == vp_hash).then(|| name.to_string())
+            })
+        });
+
+        let (is_validator, _gas) =
+            self.storage.has_key(&pos::validator_state_key(&address))?;
+
+        let mut authored_proposals = vec![];
+        let (counter, _gas) =
+            self.storage.read(&gov_storage::get_counter_key())?;
+        let counter: u64 = counter
+            .and_then(|counter| u64::try_from_slice(&counter).ok())
+            .unwrap_or_default();
+        for id in 0..counter {
+            let (author, _gas) =
+                self.storage.read(&gov_storage::get_author_key(id))?;
+            let author =
+                author.and_then(|author| Address::try_from_slice(&author).ok());
+            if author.as_ref() == Some(&address) {
+                authored_proposals.push(id);
+            }
+        }
+
+        Ok(rpc::AccountMetadata {
+            address,
+            vp_template,
+            is_validator,
+            authored_proposals,
+        })
+    }
+
     pub fn get_evidence_params(
         &self,
         epoch_duration: &EpochDuration,
@@ -343,3 +916,480 @@ where
             })
     }
 }
+
+#[cfg(test)]
+mod test_queries {
+    use anoma::ledger::pos::types::BasisPoints;
+    use anoma::types::address::{self, xan};
+    use anoma::types::storage::{BlockHash, KeySeg};
+
+    use super::*;
+    use crate::node::ledger::shell::test_utils::*;
+    use crate::node::ledger::shims::abcipp_shim_types::shim::request::FinalizeBlock;
+
+    /// Commit a block with the given balance of the given owner
+    fn commit_balance(
+        shell: &mut TestShell,
+        height: u64,
+        owner: &Address,
+        balance: u64,
+    ) {
+        shell
+            .shell
+            .storage
+            .begin_block(BlockHash::default(), BlockHeight(height))
+            .expect("Test failed");
+        shell
+            .shell
+            .storage
+            .write(
+                &token::balance_key(&xan(), owner),
+                Amount::whole(balance).try_to_vec().unwrap(),
+            )
+            .expect("Test failed");
+        shell.shell.storage.commit().expect("Test failed");
+    }
+
+    /// Test that with a retention of the events of the last two blocks, the
+    /// events of the older blocks are pruned on commit and the query for them
+    /// fails, while the events of the recent blocks remain queryable
+    #[test]
+    fn test_query_pruned_block_events() {
+        let (mut shell, _) = setup();
+        shell.shell.events_retention = Some(config::EventsRetention {
+            max_blocks: Some(2),
+            max_age_secs: None,
+        });
+        let owner = address::testing::established_address_1();
+        for height in 1..=5_u64 {
+            let event = BlockEvent {
+                event_type: "applied".to_owned(),
+                attributes: [("height".to_owned(), height.to_string())]
+                    .into_iter()
+                    .collect(),
+            };
+            shell.shell.storage.block_events =
+                vec![event].try_to_vec().unwrap();
+            commit_balance(&mut shell, height, &owner, height);
+            shell.shell.prune_block_events();
+        }
+
+        let query = |height: u64| {
+            let path = rpc::Path::BlockEvents {
+                height: BlockHeight(height),
+                event_type: None,
+            };
+            shell.shell.query(request::Query {
+                path: path.to_string(),
+                ..Default::default()
+            })
+        };
+        for height in 1..=3 {
+            let response = query(height);
+            assert_eq!(response.code, 1);
+            assert!(
+                response.info.contains("not retained"),
+                "{}",
+                response.info
+            );
+            assert!(shell
+                .shell
+                .storage
+                .get_block_events(BlockHeight(height))
+                .expect("Test failed")
+                .is_none());
+        }
+        for height in 4..=5 {
+            let response = query(height);
+            assert_eq!(response.code, 0, "{}", response.info);
+            let result = rpc::BlockEvents::try_from_slice(&response.value[..])
+                .expect("Test failed");
+            assert_eq!(result.events.len(), 1);
+            assert_eq!(
+                result.events[0].attributes["height"],
+                height.to_string()
+            );
+        }
+
+        // The state isn't pruned
+        let response = shell.shell.query(request::Query {
+            path: rpc::Path::Value(token::balance_key(&xan(), &owner))
+                .to_string(),
+            height: 1,
+            ..Default::default()
+        });
+        assert_eq!(response.code, 0, "{}", response.info);
+        assert_eq!(
+            Amount::try_from_slice(&response.value[..]).expect("Test failed"),
+            Amount::whole(1)
+        );
+    }
+
+    /// Test that the summary of a committed block counts its tx events and
+    /// their gas and that it has the merkle root of the block
+    #[test]
+    fn test_query_block_summary() {
+        let (mut shell, _) = setup();
+        let owner = address::testing::established_address_1();
+        let tx_event = |gas_used: u64| BlockEvent {
+            event_type: "applied".to_owned(),
+            attributes: [("gas_used".to_owned(), gas_used.to_string())]
+                .into_iter()
+                .collect(),
+        };
+        let balance_event = BlockEvent {
+            event_type: "balance-change".to_owned(),
+            attributes: Default::default(),
+        };
+        shell.shell.storage.block_events =
+            vec![tx_event(5)].try_to_vec().unwrap();
+        commit_balance(&mut shell, 1, &owner, 1);
+        let root_1 = shell.shell.storage.merkle_root();
+        shell.shell.storage.block_events =
+            vec![tx_event(10), balance_event, tx_event(20)]
+                .try_to_vec()
+                .unwrap();
+        commit_balance(&mut shell, 2, &owner, 2);
+        let root_2 = shell.shell.storage.merkle_root();
+        assert_ne!(root_1.0, root_2.0);
+
+        let query = |height: u64| {
+            shell.shell.query(request::Query {
+                path: rpc::Path::BlockSummary(BlockHeight(height)).to_string(),
+                ..Default::default()
+            })
+        };
+        for (height, root, num_txs, gas_used) in
+            [(1, root_1, 1, 5), (2, root_2, 2, 30)]
+        {
+            let response = query(height);
+            assert_eq!(response.code, 0, "{}", response.info);
+            let summary =
+                rpc::BlockSummary::try_from_slice(&response.value[..])
+                    .expect("Test failed");
+            assert_eq!(summary.height, BlockHeight(height));
+            assert_eq!(summary.root, root.0);
+            assert_eq!(summary.epoch, Epoch::default());
+            assert_eq!(summary.num_txs, num_txs);
+            assert_eq!(summary.gas_used, gas_used);
+        }
+
+        // The next block hasn't been committed yet
+        let response = query(3);
+        assert_eq!(response.code, 1);
+        assert!(
+            response.info.contains("hasn't been committed"),
+            "{}",
+            response.info
+        );
+    }
+
+    /// Test that the storage size query counts the keys and the sizes of the
+    /// values by their category and finds the largest values
+    #[test]
+    fn test_query_storage_size() {
+        let (mut shell, _) = setup();
+        let token = address::testing::established_address_1();
+        let owner = address::testing::established_address_2();
+        let other_owner = address::testing::established_address_3();
+        let prefix = Key::from(token.to_db_key());
+        let other_key = prefix.push(&"other".to_owned()).expect("Test failed");
+        for (key, value) in [
+            (token::balance_key(&token, &owner), vec![1; 8]),
+            (token::balance_key(&token, &other_owner), vec![2; 8]),
+            (Key::validity_predicate(&token), vec![3; 100]),
+            (other_key.clone(), vec![4; 50]),
+        ] {
+            shell.shell.storage.write(&key, value).expect("Test failed");
+        }
+        let query = |prefix: Option<Key>| {
+            let response = shell.shell.query(request::Query {
+                path: rpc::Path::StorageSize(prefix).to_string(),
+                ..Default::default()
+            });
+            assert_eq!(response.code, 0, "{}", response.info);
+            rpc::StorageSize::try_from_slice(&response.value[..])
+                .expect("Test failed")
+        };
+
+        let size = query(Some(prefix));
+        assert_eq!(size.num_keys, 4);
+        assert_eq!(size.total_bytes, 166);
+        let categories: Vec<(&str, u64, u64)> = size
+            .categories
+            .iter()
+            .map(|category| {
+                (category.name.as_str(), category.num_keys, category.bytes)
+            })
+            .collect();
+        assert_eq!(
+            categories,
+            vec![("balances", 2, 16), ("other", 1, 50), ("vps", 1, 100)]
+        );
+        let largest: Vec<(Key, u64)> = size
+            .largest
+            .into_iter()
+            .map(|value| (value.key, value.bytes))
+            .collect();
+        assert_eq!(largest[0], (Key::validity_predicate(&token), 100));
+        assert_eq!(largest[1], (other_key, 50));
+        assert_eq!(largest.len(), 4);
+
+        // Without a prefix, all the keys are counted
+        let size = query(None);
+        assert!(size.num_keys >= 4);
+        assert!(size.total_bytes >= 166);
+        assert!(size.largest.len() <= rpc::MAX_LARGEST_VALUES);
+    }
+
+    /// Test that a balance is read as it was at the queried height and at
+    /// the last committed height when no height is given
+    #[test]
+    fn test_query_balance_at_height() {
+        let (mut shell, _) = setup();
+        let owner = address::testing::established_address_1();
+        commit_balance(&mut shell, 1, &owner, 100);
+        commit_balance(&mut shell, 2, &owner, 200);
+
+        let path = rpc::Path::Value(token::balance_key(&xan(), &owner));
+        let query = |height: i64| {
+            let response = shell.shell.query(request::Query {
+                path: path.to_string(),
+                height,
+                ..Default::default()
+            });
+            assert_eq!(response.code, 0, "{}", response.info);
+            Amount::try_from_slice(&response.value[..]).expect("Test failed")
+        };
+        assert_eq!(query(1), Amount::whole(100));
+        assert_eq!(query(2), Amount::whole(200));
+        assert_eq!(query(0), Amount::whole(200));
+    }
+
+    /// Test that the queries served between the finalization of a block and
+    /// its commit don't see the block's changes staged in the storage
+    #[test]
+    fn test_query_ignores_staged_changes() {
+        let (mut shell, _) = setup();
+        let owner = address::testing::established_address_1();
+        let new_owner = address::testing::established_address_2();
+        commit_balance(&mut shell, 1, &owner, 100);
+
+        let key = token::balance_key(&xan(), &owner);
+        let new_key = token::balance_key(&xan(), &new_owner);
+        shell
+            .shell
+            .storage
+            .begin_block(BlockHash::default(), BlockHeight(2))
+            .expect("Test failed");
+        for key in [&key, &new_key] {
+            shell
+                .shell
+                .storage
+                .stage_subspace_val(
+                    key,
+                    Some(&Amount::whole(200).try_to_vec().unwrap()),
+                )
+                .expect("Test failed");
+        }
+
+        let query_balance = |shell: &TestShell| {
+            let response = shell.shell.query(request::Query {
+                path: rpc::Path::Value(key.clone()).to_string(),
+                ..Default::default()
+            });
+            assert_eq!(response.code, 0, "{}", response.info);
+            Amount::try_from_slice(&response.value[..]).expect("Test failed")
+        };
+        let query_has_key = |shell: &TestShell| {
+            let response = shell.shell.query(request::Query {
+                path: rpc::Path::HasKey(new_key.clone()).to_string(),
+                ..Default::default()
+            });
+            assert_eq!(response.code, 0, "{}", response.info);
+            bool::try_from_slice(&response.value[..]).expect("Test failed")
+        };
+        assert_eq!(query_balance(&shell), Amount::whole(100));
+        assert!(!query_has_key(&shell));
+
+        // Once the block is committed, its changes are seen
+        shell
+            .shell
+            .write_log
+            .commit_block(&mut shell.shell.storage)
+            .expect("Test failed");
+        shell.shell.storage.commit().expect("Test failed");
+        assert_eq!(query_balance(&shell), Amount::whole(200));
+        assert!(query_has_key(&shell));
+    }
+
+    /// Test that a proof is returned for a storage read, both at a
+    /// historical height and at the last committed height, with the existence
+    /// of a key proven too
+    #[test]
+    fn test_query_proven_storage_value() {
+        let (mut shell, _) = setup();
+        let owner = address::testing::established_address_1();
+        commit_balance(&mut shell, 1, &owner, 100);
+        commit_balance(&mut shell, 2, &owner, 200);
+
+        let key = token::balance_key(&xan(), &owner);
+        for height in [1, 2] {
+            let response = shell.shell.query(request::Query {
+                path: rpc::Path::Value(key.clone()).to_string(),
+                height,
+                prove: true,
+                ..Default::default()
+            });
+            assert_eq!(response.code, 0, "{}", response.info);
+            assert!(response.proof_ops.is_some());
+        }
+
+        let response = shell.shell.query(request::Query {
+            path: rpc::Path::HasKey(key).to_string(),
+            prove: true,
+            ..Default::default()
+        });
+        assert_eq!(response.code, 0, "{}", response.info);
+        assert!(bool::try_from_slice(&response.value[..]).expect("Test failed"));
+        assert!(response.proof_ops.is_some());
+    }
+
+    /// Test that a proof or a historical height requested for a query that
+    /// doesn't support it is rejected
+    #[test]
+    fn test_query_unsupported_height_or_proof() {
+        let (mut shell, _) = setup();
+        let owner = address::testing::established_address_1();
+        commit_balance(&mut shell, 1, &owner, 100);
+        commit_balance(&mut shell, 2, &owner, 200);
+
+        let response = shell.shell.query(request::Query {
+            path: rpc::Path::Epoch.to_string(),
+            prove: true,
+            ..Default::default()
+        });
+        assert_eq!(response.code, 1);
+        assert!(
+            response.info.contains("can't be proven"),
+            "{}",
+            response.info
+        );
+
+        let response = shell.shell.query(request::Query {
+            path: rpc::Path::NodeStatus.to_string(),
+            height: 1,
+            ..Default::default()
+        });
+        assert_eq!(response.code, 1);
+        assert!(
+            response.info.contains("last committed height"),
+            "{}",
+            response.info
+        );
+
+        // The epoch is served at a historical height
+        let response = shell.shell.query(request::Query {
+            path: rpc::Path::Epoch.to_string(),
+            height: 1,
+            ..Default::default()
+        });
+        assert_eq!(response.code, 0, "{}", response.info);
+    }
+
+    /// Test that a jailed validator is absent from the consensus validator
+    /// set from the pipeline epoch, while it's still in the set of the current
+    /// epoch and its bond is still in storage
+    #[test]
+    fn test_validator_set_query_of_jailed_validator() {
+        let (mut shell, _) = setup();
+        let pos_params = PosParams {
+            liveness_window_len: 4,
+            liveness_max_missed_blocks: 2,
+            liveness_slash_rate: BasisPoints::new(1000),
+            ..shell.shell.storage.read_pos_params()
+        };
+        shell.shell.storage.write_pos_params(&pos_params);
+        let query_validator_set = |shell: &TestShell, epoch: Option<Epoch>| {
+            let response = shell.shell.query(request::Query {
+                path: rpc::Path::ValidatorSet(epoch).to_string(),
+                ..Default::default()
+            });
+            assert_eq!(response.code, 0, "{}", response.info);
+            rpc::ConsensusValidatorSet::try_from_slice(&response.value[..])
+                .expect("Test failed")
+        };
+
+        let validator_set = query_validator_set(&shell, None);
+        let validator = validator_set
+            .validators
+            .first()
+            .expect("There should be an active genesis validator")
+            .address
+            .clone();
+
+        // The validator misses more than the maximum number of blocks and
+        // it's jailed
+        let missed_vote = VoteInfo {
+            validator: Some(abci::Validator {
+                address: validator.raw_hash().unwrap().as_bytes().to_vec(),
+                power: 0,
+            }),
+            signed_last_block: false,
+        };
+        for height in 2..=4_u64 {
+            let mut req = FinalizeBlock {
+                last_commit_votes: vec![missed_vote.clone()],
+                ..Default::default()
+            };
+            req.header.height = height.try_into().expect("Test failed");
+            shell.finalize_block(req).expect("Test failed");
+        }
+        shell.shell.commit().expect("Test failed");
+        let (current_epoch, _gas) = shell.shell.storage.get_last_epoch();
+        let pipeline_epoch = current_epoch + pos_params.pipeline_len;
+
+        // The validator is still in the consensus set of the current epoch
+        let validator_set = query_validator_set(&shell, None);
+        assert_eq!(validator_set.epoch, current_epoch);
+        let consensus_validator = validator_set
+            .validators
+            .iter()
+            .find(|consensus_validator| {
+                consensus_validator.address == validator
+            })
+            .expect("Test failed");
+        assert!(consensus_validator.voting_power > 0);
+        assert_eq!(
+            validator_set.total_voting_power(),
+            validator_set
+                .validators
+                .iter()
+                .map(|validator| validator.voting_power)
+                .sum::<u64>()
+        );
+
+        // It's absent from the consensus set from the pipeline epoch
+        let validator_set = query_validator_set(&shell, Some(pipeline_epoch));
+        assert_eq!(validator_set.epoch, pipeline_epoch);
+        assert!(validator_set.validators.iter().all(|consensus_validator| {
+            consensus_validator.address != validator
+        }));
+
+        // The validator's bond is still in storage
+        assert!(shell
+            .shell
+            .storage
+            .read_bonds()
+            .iter()
+            .any(|(bond_id, _bonds)| bond_id.validator == validator));
+
+        // The validator sets after the pipeline offset are not known yet
+        let response = shell.shell.query(request::Query {
+            path: rpc::Path::ValidatorSet(Some(pipeline_epoch + 1)).to_string(),
+            ..Default::default()
+        });
+        assert_eq!(response.code, 1);
+        assert!(response.info.contains("is not known"), "{}", response.info);
+    }
+}
@@ -0,0 +1,121 @@
+//! Limiting of the rate of the queries served by the shell
+
+use anoma::types::time::DateTimeUtc;
+
+use crate::config::QueryRateLimit;
+
+/// The response code of a query refused by the [`QueryRateLimiter`]
+pub const THROTTLED_QUERY_CODE: u32 = 3;
+
+/// Counts the queries served in the current second of the wall-clock time
+/// and refuses the ones over the configured [`QueryRateLimit`]. The counts
+/// don't affect the state, so the limiter doesn't break the invariant that
+/// the queries are stateless.
+#[derive(Debug)]
+pub(super) struct QueryRateLimiter {
+    limit: QueryRateLimit,
+    /// The second of the wall-clock time of the counted queries
+    window: i64,
+    /// The number of the queries served in the `window`
+    queries: u64,
+    /// The number of the heavy queries served in the `window`
+    heavy_queries: u64,
+}
+
+impl QueryRateLimiter {
+    pub(super) fn new(limit: QueryRateLimit) -> Self {
+        Self {
+            limit,
+            window: i64::MIN,
+            queries: 0,
+            heavy_queries: 0,
+        }
+    }
+
+    /// Count a query received at the given time, if it's within the limit.
+    /// Returns `false` for a query over the limit, which must be refused.
+    pub(super) fn try_acquire(
+        &mut self,
+        heavy: bool,
+        now: DateTimeUtc,
+    ) -> bool {
+        let window = now.0.timestamp();
+        if window != self.window {
+            self.window = window;
+            self.queries = 0;
+            self.heavy_queries = 0;
+        }
+        if self.queries >= self.limit.max_queries_per_sec {
+            return false;
+        }
+        if heavy {
+            if let Some(max_heavy) = self.limit.max_heavy_queries_per_sec {
+                if self.heavy_queries >= max_heavy {
+                    return false;
+                }
+            }
+            self.heavy_queries += 1;
+        }
+        self.queries += 1;
+        true
+    }
+
+    /// The maximum number of the queries of the given kind served per second
+    pub(super) fn max_per_sec(&self, heavy: bool) -> u64 {
+        match self.limit.max_heavy_queries_per_sec {
+            Some(max_heavy) if heavy => {
+                max_heavy.min(self.limit.max_queries_per_sec)
+            }
+            _ => self.limit.max_queries_per_sec,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_query_limit {
+    use std::cell::RefCell;
+
+    use anoma::types::time::{TimeZone, Utc};
+
+    use super::*;
+    use crate::node::ledger::rpc::Path;
+    use crate::node::ledger::shell::request;
+    use crate::node::ledger::shell::test_utils::*;
+
+    /// Test that the queries over the rate limit are throttled without being
+    /// served, with a stricter limit for the heavy queries, and that the
+    /// limit is renewed every second
+    #[test]
+    fn test_query_rate_limit() {
+        let (mut shell, _) = setup();
+        shell.shell.query_limiter =
+            Some(RefCell::new(QueryRateLimiter::new(QueryRateLimit {
+                max_queries_per_sec: 3,
+                max_heavy_queries_per_sec: Some(1),
+            })));
+        shell.shell.clock = || Utc.timestamp(1, 0).into();
+        let query = |shell: &TestShell, path: Path| {
+            shell
+                .shell
+                .query(request::Query {
+                    path: path.to_string(),
+                    ..Default::default()
+                })
+                .code
+        };
+
+        // The first heavy query is served, the next one is throttled
+        let heavy = Path::StorageSize(None);
+        assert_eq!(query(&shell, heavy.clone()), 0);
+        assert_eq!(query(&shell, heavy.clone()), THROTTLED_QUERY_CODE);
+        // The cheap queries are served up to the limit of all the queries
+        assert_eq!(query(&shell, Path::Epoch), 0);
+        assert_eq!(query(&shell, Path::Epoch), 0);
+        assert_eq!(query(&shell, Path::Epoch), THROTTLED_QUERY_CODE);
+
+        // The limit is renewed in the next second
+        shell.shell.clock = || Utc.timestamp(2, 0).into();
+        assert_eq!(query(&shell, heavy), 0);
+        assert_eq!(query(&shell, Path::Epoch), 0);
+    }
+}
@@ -0,0 +1,281 @@
+//! Simulation of a block's transactions against the current state, without
+//! committing anything
+
+use borsh::BorshDeserialize;
+
+use super::*;
+use crate::node::ledger::rpc::{BlockSimResult, SimulatedTx};
+
+impl<D, H> Shell<D, H>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    /// Simulate applying the given transactions in a block on top of the
+    /// current state. The transactions are applied in the given order like
+    /// in [`Shell::finalize_block`], so that each one sees the changes of the
+    /// accepted transactions before it and the gas is accumulated over the
+    /// block. Wrapper transactions are decrypted and their inner transaction
+    /// is applied, without charging the wrapper's fee.
+    ///
+    /// Nothing is written to the storage. The root in the result is the one
+    /// that committing the transactions' changes would produce. It doesn't
+    /// include any changes applied at the beginning of a block, e.g. on a new
+    /// epoch.
+    pub fn simulate_block(&self, txs: &[Vec<u8>]) -> Result<BlockSimResult> {
+        let mut gas_meter = BlockGasMeter::default();
+        let mut write_log = self.write_log.clone();
+        let mut vp_wasm_cache = self.vp_wasm_cache.read_only();
+        let mut tx_wasm_cache = self.tx_wasm_cache.read_only();
+        // TODO: This should not be hardcoded, same as in `prepare_proposal`
+        let privkey =
+            <EllipticCurve as PairingEngine>::G2Affine::prime_subgroup_generator();
+
+        let mut results = Vec::with_capacity(txs.len());
+        for tx_bytes in txs {
+            let tx = match Tx::try_from(&tx_bytes[..]) {
+                Ok(tx) => tx,
+                Err(err) => {
+                    let error = Error::TxDecoding(err).to_string();
+                    results.push(SimulatedTx::Failed(error));
+                    continue;
+                }
+            };
+            let tx = match process_tx(tx.clone()) {
+                Ok(TxType::Wrapper(wrapper)) => {
+                    match wrapper.decrypt(privkey) {
+                        Ok(inner) => inner,
+                        Err(_) => {
+                            results.push(SimulatedTx::Failed(
+                                "Transaction could not be decrypted.".into(),
+                            ));
+                            continue;
+                        }
+                    }
+                }
+                _ => tx,
+            };
+            match protocol::apply_tx(
+                TxType::Decrypted(DecryptedTx::Decrypted(tx)),
+                None,
+                tx_bytes.len(),
+                &mut gas_meter,
+                &mut write_log,
+                &self.storage,
+                &mut vp_wasm_cache,
+                &mut tx_wasm_cache,
+                self.wasm_timeout,
+            ) {
+                Ok(result) => {
+                    if result.is_accepted() {
+                        write_log.commit_tx();
+                    } else {
+                        write_log.drop_tx();
+                    }
+                    results.push(SimulatedTx::Applied(result));
+                }
+                Err(err) => {
+                    write_log.drop_tx();
+                    let error = Error::TxApply(err).to_string();
+                    results.push(SimulatedTx::Failed(error));
+                }
+            }
+        }
+
+        let mut tree = self.storage.block.tree.clone();
+        write_log
+            .update_merkle_tree(&mut tree)
+            .map_err(Error::SimulateBlock)?;
+        Ok(BlockSimResult {
+            txs: results,
+            gas_used: gas_meter.get_block_gas(),
+            root: tree.root().0,
+        })
+    }
+
+    /// Simulate a block of transactions, given as a [`Vec<Vec<u8>>`] encoded
+    /// with [`BorshSerialize`]. The value in successful response is a
+    /// [`BlockSimResult`] encoded with [`BorshSerialize`].
+    pub(super) fn simulate_block_query(
+        &self,
+        txs_bytes: &[u8],
+    ) -> response::Query {
+        let txs = match Vec::<Vec<u8>>::try_from_slice(txs_bytes) {
+            Ok(txs) => txs,
+            Err(err) => {
+                return response::Query {
+                    code: 1,
+                    info: format!("Error decoding the transactions: {}", err),
+                    ..Default::default()
+                };
+            }
+        };
+        match self.simulate_block(&txs) {
+            Ok(result) => response::Query {
+                value: result
+                    .try_to_vec()
+                    .expect("Serializing the simulation result shouldn't fail"),
+                ..Default::default()
+            },
+            Err(err) => response::Query {
+                code: 2,
+                info: err.to_string(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_simulate_block {
+    use anoma::types::address::{xan, Address};
+    use anoma::types::token;
+
+    use super::*;
+    use crate::node::ledger::shell::test_utils::*;
+
+    /// Test that simulating a block of two transfers reports both as
+    /// accepted, without changing the storage, and that the reported root is
+    /// the same as the one produced by committing the transfers.
+    #[test]
+    fn test_simulate_block_root() {
+        let (mut shell, _) = setup();
+        // Let the token's VP accept any change
+        let vp_code = std::fs::read(
+            top_level_directory().join("wasm_for_tests/vp_always_true.wasm"),
+        )
+        .expect("Expected a file at given code path");
+        shell
+            .shell
+            .storage
+            .write(&Key::validity_predicate(&xan()), vp_code)
+            .expect("Test failed");
+
+        let tx_code = std::fs::read(
+            top_level_directory().join("wasm_for_tests/tx_mint_tokens.wasm"),
+        )
+        .expect("Expected a file at given code path");
+        let keypair = gen_keypair();
+        let target = Address::from(&gen_keypair().ref_to());
+        // The second transfer reads the balance written by the first one
+        let txs: Vec<Vec<u8>> = [1, 2]
+            .iter()
+            .map(|amount| {
+                let transfer = token::Transfer {
+                    source: xan(),
+                    target: target.clone(),
+                    token: xan(),
+                    amount: token::Amount::whole(*amount),
+                };
+                let data = transfer.try_to_vec().expect("Test failed");
+                Tx::new(tx_code.clone(), Some(data))
+                    .sign(&keypair)
+                    .to_bytes()
+            })
+            .collect();
+
+        let root_before = shell.shell.storage.merkle_root().0;
+        let result = shell.shell.simulate_block(&txs).expect("Test failed");
+        assert_eq!(result.txs.len(), 2);
+        let mut gas_used = 0;
+        for tx_result in &result.txs {
+            match tx_result {
+                SimulatedTx::Applied(tx_result) => {
+                    assert!(tx_result.is_accepted());
+                    gas_used += tx_result.gas_used;
+                }
+                SimulatedTx::Failed(err) => {
+                    panic!("Unexpected tx failure: {}", err)
+                }
+            }
+        }
+        assert_eq!(result.gas_used, gas_used);
+        // Nothing has been written
+        assert_eq!(shell.shell.storage.merkle_root().0, root_before);
+        assert_ne!(result.root, root_before);
+
+        // Apply and commit the same txs for real
+        let mut gas_meter = BlockGasMeter::default();
+        for tx_bytes in &txs {
+            let tx = Tx::try_from(&tx_bytes[..]).expect("Test failed");
+            let result = protocol::apply_tx(
+                TxType::Decrypted(DecryptedTx::Decrypted(tx)),
+                None,
+                tx_bytes.len(),
+                &mut gas_meter,
+                &mut shell.shell.write_log,
+                &shell.shell.storage,
+                &mut shell.shell.vp_wasm_cache,
+                &mut shell.shell.tx_wasm_cache,
+                None,
+            )
+            .expect("Test failed");
+            assert!(result.is_accepted());
+            shell.shell.write_log.commit_tx();
+        }
+        shell.shell.commit().expect("Test failed");
+
+        assert_eq!(shell.shell.storage.merkle_root().0, result.root);
+        let balance_key = token::balance_key(&xan(), &target);
+        let (balance, _gas) =
+            shell.shell.storage.read(&balance_key).expect("Test failed");
+        let balance = token::Amount::try_from_slice(&balance.unwrap()[..])
+            .expect("Test failed");
+        assert_eq!(balance, token::Amount::whole(3));
+    }
+
+    /// Test that a no-op tx is reported as not changing the state, while a
+    /// transfer is reported as changing it
+    #[test]
+    fn test_simulate_block_state_changed() {
+        let (mut shell, _) = setup();
+        // Let the token's VP accept any change
+        let vp_code = std::fs::read(
+            top_level_directory().join("wasm_for_tests/vp_always_true.wasm"),
+        )
+        .expect("Expected a file at given code path");
+        shell
+            .shell
+            .storage
+            .write(&Key::validity_predicate(&xan()), vp_code)
+            .expect("Test failed");
+
+        let keypair = gen_keypair();
+        let no_op_code = std::fs::read(
+            top_level_directory().join("wasm_for_tests/tx_no_op.wasm"),
+        )
+        .expect("Expected a file at given code path");
+        let no_op = Tx::new(no_op_code, Some(vec![])).sign(&keypair);
+        let transfer_code = std::fs::read(
+            top_level_directory().join("wasm_for_tests/tx_mint_tokens.wasm"),
+        )
+        .expect("Expected a file at given code path");
+        let transfer = token::Transfer {
+            source: xan(),
+            target: Address::from(&gen_keypair().ref_to()),
+            token: xan(),
+            amount: token::Amount::whole(1),
+        };
+        let data = transfer.try_to_vec().expect("Test failed");
+        let transfer = Tx::new(transfer_code, Some(data)).sign(&keypair);
+
+        let result = shell
+            .shell
+            .simulate_block(&[no_op.to_bytes(), transfer.to_bytes()])
+            .expect("Test failed");
+        let state_changed: Vec<bool> = result
+            .txs
+            .iter()
+            .map(|tx_result| match tx_result {
+                SimulatedTx::Applied(tx_result) => {
+                    assert!(tx_result.is_accepted());
+                    tx_result.state_changed
+                }
+                SimulatedTx::Failed(err) => {
+                    panic!("Unexpected tx failure: {}", err)
+                }
+            })
+            .collect();
+        assert_eq!(state_changed, vec![false, true]);
+    }
+}
@@ -2,11 +2,15 @@ use std::convert::{TryFrom, TryInto};
 use std::future::Future;
 use std::path::PathBuf;
 use std::pin::Pin;
+use std::sync::mpsc::TrySendError;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
+use anoma::ledger::storage::{DBIter, DB};
 use anoma::types::storage::BlockHeight;
 use futures::future::FutureExt;
 use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::{AcquireError, OwnedSemaphorePermit, Semaphore};
 use tower::Service;
 #[cfg(not(feature = "ABCI"))]
 use tower_abci::{BoxError, Request as Req, Response as Resp};
@@ -19,35 +23,51 @@ use crate::config;
 use crate::node::ledger::shims::abcipp_shim_types::shim::request::{
     BeginBlock, ProcessedTx,
 };
+use crate::node::ledger::storage::PersistentDB;
+
+/// A request from the [`AbciService`] to the shell with the sender of its
+/// response and the request's slot in the channel, which is freed when it's
+/// dropped
+type ShellMsg = (
+    Req,
+    tokio::sync::oneshot::Sender<Result<Resp, BoxError>>,
+    OwnedSemaphorePermit,
+);
 
 /// The shim wraps the shell, which implements ABCI++.
 /// The shim makes a crude translation between the ABCI interface currently used
 /// by tendermint and the shell's interface.
 #[derive(Debug)]
-pub struct AbcippShim {
-    service: Shell,
+pub struct AbcippShim<D = PersistentDB>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+{
+    service: Shell<D>,
     begin_block_request: Option<BeginBlock>,
     block_txs: Vec<ProcessedTx>,
-    shell_recv: std::sync::mpsc::Receiver<(
-        Req,
-        tokio::sync::oneshot::Sender<Result<Resp, BoxError>>,
-    )>,
+    shell_recv: std::sync::mpsc::Receiver<ShellMsg>,
 }
 
-impl AbcippShim {
+impl<D> AbcippShim<D>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+{
     /// Create a shell with a ABCI service that passes messages to and from the
     /// shell.
     pub fn new(
         config: config::Ledger,
         wasm_dir: PathBuf,
         broadcast_sender: UnboundedSender<Vec<u8>>,
-        db_cache: &rocksdb::Cache,
+        db_cache: &D::Cache,
         vp_wasm_compilation_cache: u64,
         tx_wasm_compilation_cache: u64,
+        abci_channel_capacity: usize,
     ) -> (Self, AbciService) {
-        // We can use an unbounded channel here, because tower-abci limits the
-        // the number of requests that can come in
-        let (shell_send, shell_recv) = std::sync::mpsc::channel();
+        // The channel is bounded, so that a burst of requests cannot grow the
+        // queue without limit. When it's full, the `AbciService` isn't ready
+        // until the shell catches up.
+        let (shell_send, shell_recv) =
+            std::sync::mpsc::sync_channel(abci_channel_capacity);
         (
             Self {
                 service: Shell::new(
@@ -62,21 +82,35 @@ impl AbcippShim {
                 block_txs: vec![],
                 shell_recv,
             },
-            AbciService { shell_send },
+            AbciService::new(shell_send, abci_channel_capacity),
         )
     }
 
     /// Run the shell's blocking loop that receives messages from the
-    /// [`AbciService`].
+    /// [`AbciService`]. The loop stops when a block cannot be committed, so
+    /// that the node halts instead of proceeding with an uncommitted block.
+    /// It also stops before the block at the configured halt height is
+    /// processed.
     pub fn run(mut self) {
-        while let Ok((req, resp_sender)) = self.shell_recv.recv() {
+        while let Ok((req, resp_sender, slot)) = self.shell_recv.recv() {
+            // Free the request's slot in the channel
+            drop(slot);
+            let is_commit = matches!(req, Req::Commit(_));
             let resp = match req {
                 Req::BeginBlock(block) => {
-                    // we save this data to be forwarded to finalize later
-                    self.begin_block_request =
-                        Some(block.try_into().unwrap_or_else(|_| {
+                    let block: BeginBlock =
+                        block.try_into().unwrap_or_else(|_| {
                             panic!("Could not read begin block request");
-                        }));
+                        });
+                    if self
+                        .service
+                        .halt_at(BlockHeight(block.header.height.into()))
+                    {
+                        // The block is not processed and the node shuts down
+                        break;
+                    }
+                    // we save this data to be forwarded to finalize later
+                    self.begin_block_request = Some(block);
                     Ok(Resp::BeginBlock(Default::default()))
                 }
                 Req::DeliverTx(deliver_tx) => {
@@ -130,6 +164,8 @@ impl AbcippShim {
                             header: begin_block_request.header,
                             byzantine_validators: begin_block_request
                                 .byzantine_validators,
+                            last_commit_votes: begin_block_request
+                                .last_commit_votes,
                             txs,
                             reject_all_decrypted: out_of_order,
                         }))
@@ -153,19 +189,65 @@ impl AbcippShim {
                 },
             };
             let resp = resp.map_err(|e| e.into());
+            let halt = is_commit && resp.is_err();
             if resp_sender.send(resp).is_err() {
                 tracing::info!("ABCI response channel is closed")
             }
+            if halt {
+                tracing::error!(
+                    "Failed to commit a block, halting due to storage \
+                     corruption"
+                );
+                break;
+            }
         }
     }
 }
 
-#[derive(Debug)]
+/// A future acquiring a slot in the channel to the shell
+type AcquireSlot = Pin<
+    Box<
+        dyn Future<Output = Result<OwnedSemaphorePermit, AcquireError>>
+            + Send
+            + 'static,
+    >,
+>;
+
 pub struct AbciService {
-    shell_send: std::sync::mpsc::Sender<(
-        Req,
-        tokio::sync::oneshot::Sender<Result<Resp, BoxError>>,
-    )>,
+    shell_send: std::sync::mpsc::SyncSender<ShellMsg>,
+    /// The free slots in the channel to the shell. A slot is reserved in
+    /// `poll_ready` and freed when the shell receives the request sent in it.
+    slots: Arc<Semaphore>,
+    /// A pending reservation of a slot
+    acquire_slot: Option<AcquireSlot>,
+    /// The slot reserved for the next request
+    slot: Option<OwnedSemaphorePermit>,
+}
+
+impl AbciService {
+    /// Create a service sending the requests over the given channel of the
+    /// given capacity
+    fn new(
+        shell_send: std::sync::mpsc::SyncSender<ShellMsg>,
+        capacity: usize,
+    ) -> Self {
+        Self {
+            shell_send,
+            slots: Arc::new(Semaphore::new(capacity)),
+            acquire_slot: None,
+            slot: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for AbciService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AbciService")
+            .field("shell_send", &self.shell_send)
+            .field("slots", &self.slots)
+            .field("slot", &self.slot)
+            .finish()
+    }
 }
 
 /// The ABCI tower service implementation sends and receives messages to and
@@ -176,22 +258,50 @@ impl Service<Req> for AbciService {
         Pin<Box<dyn Future<Output = Result<Resp, BoxError>> + Send + 'static>>;
     type Response = Resp;
 
+    /// The service is ready once a slot in the channel to the shell is
+    /// reserved for the next request. When the channel is full, this applies
+    /// backpressure until the shell takes a request from it.
     fn poll_ready(
         &mut self,
-        _cx: &mut Context<'_>,
+        cx: &mut Context<'_>,
     ) -> Poll<Result<(), Self::Error>> {
-        // Nothing to check as the sender's channel is unbounded
-        Poll::Ready(Ok(()))
+        if self.slot.is_some() {
+            return Poll::Ready(Ok(()));
+        }
+        let slots = &self.slots;
+        let acquire_slot = self
+            .acquire_slot
+            .get_or_insert_with(|| Box::pin(slots.clone().acquire_owned()));
+        match acquire_slot.as_mut().poll(cx) {
+            Poll::Ready(result) => {
+                self.acquire_slot = None;
+                let slot =
+                    result.expect("The channel's slots are never closed");
+                self.slot = Some(slot);
+                Poll::Ready(Ok(()))
+            }
+            Poll::Pending => Poll::Pending,
+        }
     }
 
     fn call(&mut self, req: Req) -> Self::Future {
         let (resp_send, recv) = tokio::sync::oneshot::channel();
-        let result = self.shell_send.send((req, resp_send));
+        let slot = self
+            .slot
+            .take()
+            .expect("`poll_ready` must reserve a slot before `call`");
+        let result = match self.shell_send.try_send((req, resp_send, slot)) {
+            Err(TrySendError::Full(_)) => unreachable!(
+                "The channel cannot be full, because the request's slot has \
+                 been reserved"
+            ),
+            result => result,
+        };
         Box::pin(
             async move {
                 if let Err(err) = result {
                     // The shell has shut-down
-                    return Err(err.into());
+                    return Err(err.to_string().into());
                 }
                 match recv.await {
                     Ok(resp) => resp,
@@ -205,3 +315,73 @@ impl Service<Req> for AbciService {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "ABCI"))]
+    use tendermint_proto::abci::{RequestEcho, ResponseEcho};
+    #[cfg(feature = "ABCI")]
+    use tendermint_proto_abci::abci::{RequestEcho, ResponseEcho};
+
+    use super::*;
+
+    /// Test that the service isn't ready while the channel to the shell is
+    /// full, so that a burst of requests applies backpressure instead of
+    /// growing the queue, and that no request is lost or reordered.
+    #[tokio::test]
+    async fn test_abci_channel_backpressure() {
+        const CAPACITY: usize = 4;
+        const NUM_OF_REQUESTS: usize = 100;
+        let (shell_send, shell_recv) = std::sync::mpsc::sync_channel(CAPACITY);
+        let mut service = AbciService::new(shell_send, CAPACITY);
+        let echo = |i: usize| {
+            Req::Echo(RequestEcho {
+                message: i.to_string(),
+            })
+        };
+
+        // Fill the channel while the shell is not receiving
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        let mut responses = vec![];
+        for i in 0..CAPACITY {
+            assert!(matches!(service.poll_ready(&mut cx), Poll::Ready(Ok(()))));
+            responses.push(service.call(echo(i)));
+        }
+        // The next request has to wait for the shell
+        assert!(service.poll_ready(&mut cx).is_pending());
+        assert!(service.poll_ready(&mut cx).is_pending());
+
+        // Start receiving the requests
+        let shell = std::thread::spawn(move || {
+            while let Ok((req, resp_sender, slot)) = shell_recv.recv() {
+                drop(slot);
+                let resp = match req {
+                    Req::Echo(echo) => Resp::Echo(ResponseEcho {
+                        message: echo.message,
+                    }),
+                    _ => unreachable!(),
+                };
+                resp_sender.send(Ok(resp)).unwrap();
+            }
+        });
+
+        // The rest of the requests are sent as the shell catches up
+        for i in CAPACITY..NUM_OF_REQUESTS {
+            futures::future::poll_fn(|cx| service.poll_ready(cx))
+                .await
+                .unwrap();
+            responses.push(service.call(echo(i)));
+        }
+        let responses = futures::future::join_all(responses).await;
+        assert_eq!(responses.len(), NUM_OF_REQUESTS);
+        for (i, resp) in responses.into_iter().enumerate() {
+            match resp.unwrap() {
+                Resp::Echo(echo) => assert_eq!(echo.message, i.to_string()),
+                _ => panic!("Unexpected response"),
+            }
+        }
+        // Dropping the service closes the channel, so the shell's loop stops
+        drop(service);
+        shell.join().unwrap();
+    }
+}
@@ -201,9 +201,11 @@ pub mod shim {
         #[cfg(not(feature = "ABCI"))]
         use tendermint::block::Header;
         #[cfg(not(feature = "ABCI"))]
-        use tendermint_proto::abci::{Evidence, RequestBeginBlock};
+        use tendermint_proto::abci::{Evidence, RequestBeginBlock, VoteInfo};
         #[cfg(feature = "ABCI")]
-        use tendermint_proto_abci::abci::{Evidence, RequestBeginBlock};
+        use tendermint_proto_abci::abci::{
+            Evidence, RequestBeginBlock, VoteInfo,
+        };
         #[cfg(feature = "ABCI")]
         use tendermint_stable::block::Header;
 
@@ -235,6 +237,7 @@ pub mod shim {
             pub hash: BlockHash,
             pub header: Header,
             pub byzantine_validators: Vec<Evidence>,
+            pub last_commit_votes: Vec<VoteInfo>,
         }
 
         impl TryFrom<RequestBeginBlock> for BeginBlock {
@@ -252,6 +255,10 @@ pub mod shim {
                         hash,
                         header,
                         byzantine_validators: req.byzantine_validators,
+                        last_commit_votes: req
+                            .last_commit_info
+                            .map(|info| info.votes)
+                            .unwrap_or_default(),
                     }),
                     (Ok(_), Err(msg)) => {
                         tracing::error!("Unexpected block header {}", msg);
@@ -273,6 +280,7 @@ pub mod shim {
             pub hash: BlockHash,
             pub header: Header,
             pub byzantine_validators: Vec<Evidence>,
+            pub last_commit_votes: Vec<VoteInfo>,
             pub txs: Vec<ProcessedTx>,
             pub reject_all_decrypted: bool,
         }
@@ -2,10 +2,12 @@
 //! state in DB.
 
 mod rocksdb;
+mod sled;
 
 use std::fmt;
+use std::path::Path;
 
-use anoma::ledger::storage::{Storage, StorageHasher};
+use anoma::ledger::storage::{self, Storage, StorageHasher};
 use blake2b_rs::{Blake2b, Blake2bBuilder};
 use sparse_merkle_tree::blake2b::Blake2bHasher;
 use sparse_merkle_tree::traits::Hasher;
@@ -16,8 +18,25 @@ pub struct PersistentStorageHasher(Blake2bHasher);
 
 pub type PersistentDB = rocksdb::RocksDB;
 
+/// The alternative persistent DB, used when the ledger's config selects the
+/// [`crate::config::StorageBackend::Sled`] backend
+pub type SledDB = sled::SledDB;
+
 pub type PersistentStorage = Storage<PersistentDB, PersistentStorageHasher>;
 
+/// Open the persistent DB at the given path. Unlike opening the
+/// [`PersistentStorage`], a failure to open the DB, e.g. when it's locked by a
+/// running node, is returned as an error.
+pub fn open_db(path: impl AsRef<Path>) -> storage::Result<PersistentDB> {
+    rocksdb::open(path, None)
+}
+
+/// Open the sled DB at the given path, returning a failure to open it as an
+/// error like [`open_db`].
+pub fn open_sled_db(path: impl AsRef<Path>) -> storage::Result<SledDB> {
+    sled::open(path, None)
+}
+
 impl Hasher for PersistentStorageHasher {
     fn write_h256(&mut self, h: &H256) {
         self.0.write_h256(h)
@@ -50,9 +69,13 @@ fn new_blake2b() -> Blake2b {
 
 #[cfg(test)]
 mod tests {
-    use anoma::ledger::storage::types;
+    use anoma::ledger::storage::mockdb::MockDB;
+    use anoma::ledger::storage::write_log::WriteLog;
+    use anoma::ledger::storage::{types, DBIter, DB};
+    use anoma::types::address::EstablishedAddressGen;
     use anoma::types::chain::ChainId;
     use anoma::types::storage::{BlockHash, BlockHeight, Key};
+    use anoma::types::token::balance_key;
     use tempfile::TempDir;
 
     use super::*;
@@ -141,6 +164,39 @@ mod tests {
         assert_eq!(val.expect("no value"), value_bytes);
     }
 
+    /// Test that the changes staged in a block that hasn't been committed are
+    /// cleared when the last state is loaded
+    #[test]
+    fn test_clear_staged_vals_on_load() {
+        let db_path =
+            TempDir::new().expect("Unable to create a temporary DB directory");
+        let key = Key::parse("key").expect("cannot parse the key string");
+        let mut storage =
+            PersistentStorage::open(db_path.path(), ChainId::default(), None);
+        storage
+            .stage_subspace_val(&key, Some(&[1_u8; 4]))
+            .expect("staging failed");
+        assert!(storage
+            .db
+            .read_staged_val(&key)
+            .expect("read failed")
+            .is_some());
+        // the node stops before the block is committed
+        drop(storage);
+
+        let mut storage =
+            PersistentStorage::open(db_path.path(), ChainId::default(), None);
+        storage
+            .load_last_state()
+            .expect("loading the last state failed");
+        assert_eq!(
+            storage.db.read_staged_val(&key).expect("read failed"),
+            None
+        );
+        let (val, _) = storage.read(&key).expect("read failed");
+        assert_eq!(val, None);
+    }
+
     #[test]
     fn test_iter() {
         let db_path =
@@ -210,4 +266,386 @@ mod tests {
         assert_eq!(vp.expect("no VP"), vp1);
         assert_eq!(gas, (key.len() + vp1.len()) as u64);
     }
+
+    /// Simulate the storage reads of a block of many transfers of the same
+    /// token and check that the repeated reads are served from the block's
+    /// read cache.
+    #[test]
+    fn test_read_cache_in_block_of_transfers() {
+        let db_path =
+            TempDir::new().expect("Unable to create a temporary DB directory");
+        let mut storage =
+            PersistentStorage::open(db_path.path(), ChainId::default(), None);
+        storage
+            .begin_block(BlockHash::default(), BlockHeight(100))
+            .expect("begin_block failed");
+
+        // Initialize a token and some accounts with a balance
+        let num_of_accounts = 10;
+        let token = storage.address_gen.generate_address("token".as_bytes());
+        let accounts: Vec<_> = (0..num_of_accounts)
+            .map(|i| {
+                storage
+                    .address_gen
+                    .generate_address(format!("account {}", i).as_bytes())
+            })
+            .collect();
+        storage
+            .write(&Key::validity_predicate(&token), "vp_token".as_bytes())
+            .expect("write failed");
+        for account in &accounts {
+            storage
+                .write(&Key::validity_predicate(account), "vp_user".as_bytes())
+                .expect("write failed");
+            storage
+                .write(&balance_key(&token, account), types::encode(&100_u64))
+                .expect("write failed");
+        }
+        storage.commit().expect("commit failed");
+        let db_reads_before = storage.read_cache.db_reads();
+
+        // Apply a block of transfers, each reading the VPs and the balances
+        // of the source and the target and the token's VP
+        storage
+            .begin_block(BlockHash::default(), BlockHeight(101))
+            .expect("begin_block failed");
+        let mut write_log = WriteLog::default();
+        let num_of_txs = 100;
+        let mut num_of_reads = 0;
+        for i in 0..num_of_txs {
+            let source = &accounts[i % num_of_accounts];
+            let target = &accounts[(i + 1) % num_of_accounts];
+            for addr in [&token, source, target] {
+                let (vp, _gas) =
+                    storage.validity_predicate(addr).expect("VP load failed");
+                assert!(vp.is_some());
+                num_of_reads += 1;
+            }
+            for owner in [source, target] {
+                let (balance, _gas) = storage
+                    .read(&balance_key(&token, owner))
+                    .expect("read failed");
+                assert!(balance.is_some());
+                num_of_reads += 1;
+            }
+            write_log
+                .write(&balance_key(&token, source), types::encode(&99_u64))
+                .expect("write failed");
+            write_log.commit_tx();
+        }
+        // Only the first read of each key goes to the DB
+        let db_reads = storage.read_cache.db_reads() - db_reads_before;
+        assert_eq!(db_reads, 1 + 2 * num_of_accounts as u64);
+        assert!(db_reads < num_of_reads);
+
+        // A read after the write log is committed sees the new value without
+        // reading the DB
+        write_log
+            .commit_block(&mut storage)
+            .expect("commit_block failed");
+        let (balance, _gas) = storage
+            .read(&balance_key(&token, &accounts[0]))
+            .expect("read failed");
+        assert_eq!(balance.expect("no balance"), types::encode(&99_u64));
+        assert_eq!(storage.read_cache.db_reads() - db_reads_before, db_reads);
+
+        // After the block is committed, the cache is cleared
+        storage.commit().expect("commit failed");
+        let (balance, _gas) = storage
+            .read(&balance_key(&token, &accounts[0]))
+            .expect("read failed");
+        assert_eq!(balance.expect("no balance"), types::encode(&99_u64));
+        assert_eq!(
+            storage.read_cache.db_reads() - db_reads_before,
+            db_reads + 1
+        );
+    }
+
+    /// Generate the storage writes of a synthetic genesis with the given
+    /// number of accounts, each with a VP, a public key and a balance
+    fn synthetic_genesis_writes(num_of_accounts: usize) -> Vec<(Key, Vec<u8>)> {
+        let mut address_gen = EstablishedAddressGen::new("synthetic genesis");
+        let token = address_gen.generate_address("token".as_bytes());
+        let mut writes =
+            vec![(Key::validity_predicate(&token), b"vp_token".to_vec())];
+        for i in 0..num_of_accounts {
+            let account = address_gen
+                .generate_address(format!("account {}", i).as_bytes());
+            writes
+                .push((Key::validity_predicate(&account), b"vp_user".to_vec()));
+            writes.push((
+                Key::validity_predicate(&account)
+                    .push(&"public_key".to_owned())
+                    .expect("cannot push the key segment"),
+                format!("public key {}", i).into_bytes(),
+            ));
+            writes.push((
+                balance_key(&token, &account),
+                types::encode(&(i as u64)),
+            ));
+        }
+        writes
+    }
+
+    /// Seed the synthetic genesis either with a batch write or with a write
+    /// per value, commit it and return the resulting Merkle root
+    fn seed_synthetic_genesis(
+        db_path: &std::path::Path,
+        num_of_accounts: usize,
+        batched: bool,
+    ) -> Vec<u8> {
+        let mut storage =
+            PersistentStorage::open(db_path, ChainId::default(), None);
+        storage
+            .begin_block(BlockHash::default(), BlockHeight(1))
+            .expect("begin_block failed");
+        let writes = synthetic_genesis_writes(num_of_accounts);
+        if batched {
+            storage.write_batch(writes).expect("write_batch failed");
+        } else {
+            for (key, value) in writes {
+                storage.write(&key, value).expect("write failed");
+            }
+        }
+        storage.commit().expect("commit failed");
+        storage.merkle_root().0
+    }
+
+    /// Test that a batch write produces the same state and Merkle root as a
+    /// write of each value
+    #[test]
+    fn test_write_batch() {
+        let num_of_accounts = 10;
+        let db_path =
+            TempDir::new().expect("Unable to create a temporary DB directory");
+        let root =
+            seed_synthetic_genesis(db_path.path(), num_of_accounts, false);
+        let db_path =
+            TempDir::new().expect("Unable to create a temporary DB directory");
+        let batch_root =
+            seed_synthetic_genesis(db_path.path(), num_of_accounts, true);
+        assert_eq!(root, batch_root);
+
+        // The values are persisted
+        let mut storage =
+            PersistentStorage::open(db_path.path(), ChainId::default(), None);
+        storage.load_last_state().expect("load_last_state failed");
+        for (key, value) in synthetic_genesis_writes(num_of_accounts) {
+            let (read, _gas) = storage.read(&key).expect("read failed");
+            assert_eq!(read, Some(value));
+        }
+    }
+
+    /// Test that a batch write with the same key written more than once has
+    /// the same size difference, state and Merkle root as a write of each
+    /// value, block after block
+    #[test]
+    fn test_write_batch_duplicate_keys() {
+        let key = Key::parse("key").expect("cannot parse the key string");
+        let other_key =
+            Key::parse("other_key").expect("cannot parse the key string");
+        let blocks = vec![
+            // a new key written twice
+            vec![
+                (key.clone(), b"first value".to_vec()),
+                (other_key.clone(), b"other value".to_vec()),
+                (key.clone(), b"last".to_vec()),
+            ],
+            // an existing key written twice
+            vec![
+                (key.clone(), b"a longer value".to_vec()),
+                (key.clone(), b"short".to_vec()),
+            ],
+        ];
+
+        let mut results = Vec::new();
+        for batched in [false, true] {
+            let db_path = TempDir::new()
+                .expect("Unable to create a temporary DB directory");
+            let mut storage = PersistentStorage::open(
+                db_path.path(),
+                ChainId::default(),
+                None,
+            );
+            let mut size_diffs = Vec::new();
+            for (height, writes) in blocks.iter().enumerate() {
+                storage
+                    .begin_block(
+                        BlockHash::default(),
+                        BlockHeight(height as u64 + 1),
+                    )
+                    .expect("begin_block failed");
+                let size_diff = if batched {
+                    let (_gas, size_diff) = storage
+                        .write_batch(writes.clone())
+                        .expect("write_batch failed");
+                    size_diff
+                } else {
+                    writes.iter().fold(0, |size_diff, (key, value)| {
+                        let (_gas, diff) =
+                            storage.write(key, value).expect("write failed");
+                        size_diff + diff
+                    })
+                };
+                size_diffs.push(size_diff);
+                storage.commit().expect("commit failed");
+            }
+            let (value, _gas) = storage.read(&key).expect("read failed");
+            assert_eq!(value, Some(b"short".to_vec()));
+            results.push((size_diffs, storage.merkle_root().0));
+        }
+        assert_eq!(results[0], results[1]);
+    }
+
+    /// Compare the time of seeding a large synthetic genesis with a batch
+    /// write and with a write per value. Run with:
+    ///
+    /// `cargo test --release bench_genesis_write_batch -- --ignored
+    /// --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_genesis_write_batch() {
+        let num_of_accounts = 10_000;
+        let mut roots = Vec::new();
+        for batched in [false, true] {
+            let db_path = TempDir::new()
+                .expect("Unable to create a temporary DB directory");
+            let start = std::time::Instant::now();
+            roots.push(seed_synthetic_genesis(
+                db_path.path(),
+                num_of_accounts,
+                batched,
+            ));
+            println!(
+                "Seeding {} accounts {}: {:?}",
+                num_of_accounts,
+                if batched { "in a batch" } else { "per write" },
+                start.elapsed()
+            );
+        }
+        assert_eq!(roots[0], roots[1]);
+    }
+
+    /// Apply the same sequence of writes and deletes over a few blocks to a
+    /// storage with the given DB backend. Check the reads, the prefix
+    /// iteration and the historical reads along the way. Returns the size
+    /// differences reported by the writes and deletes and the Merkle roots of
+    /// the committed blocks.
+    fn apply_conformance_sequence<D>(
+        db_path: &std::path::Path,
+    ) -> (Vec<i64>, Vec<Vec<u8>>)
+    where
+        D: DB + for<'iter> DBIter<'iter>,
+    {
+        let mut storage = Storage::<D, PersistentStorageHasher>::open(
+            db_path,
+            ChainId::default(),
+            None,
+        );
+        let prefix = Key::parse("prefix").expect("cannot parse the key string");
+        let key = |i: u64| {
+            prefix
+                .push(&format!("{}", i))
+                .expect("cannot push the key segment")
+        };
+        let mut size_diffs = Vec::new();
+        let mut roots = Vec::new();
+
+        // Block 1 writes keys 0 to 4, block 2 updates keys 0 to 3 with longer
+        // values and deletes key 4, block 3 updates key 0 and writes key 5
+        let blocks: [(Vec<u64>, Vec<u64>); 3] = [
+            ((0..5).collect(), vec![]),
+            ((0..4).collect(), vec![4]),
+            (vec![0, 5], vec![]),
+        ];
+        for (height, (writes, deletes)) in (1..).zip(blocks) {
+            storage
+                .begin_block(BlockHash::default(), BlockHeight(height))
+                .expect("begin_block failed");
+            for i in writes {
+                let value = vec![i as u8; height as usize];
+                let (_gas, size_diff) =
+                    storage.write(&key(i), &value).expect("write failed");
+                size_diffs.push(size_diff);
+                let (read, _gas) = storage.read(&key(i)).expect("read failed");
+                assert_eq!(read, Some(value));
+            }
+            for i in deletes {
+                let (_gas, size_diff) =
+                    storage.delete(&key(i)).expect("delete failed");
+                size_diffs.push(size_diff);
+                let (read, _gas) = storage.read(&key(i)).expect("read failed");
+                assert_eq!(read, None);
+            }
+            storage.commit().expect("commit failed");
+            roots.push(storage.merkle_root().0);
+        }
+
+        // Prefix iteration
+        let (iter, _gas) = storage.iter_prefix(&prefix);
+        let keys: Vec<String> = iter.map(|(key, _val, _gas)| key).collect();
+        let expected: Vec<String> = [0, 1, 2, 3, 5]
+            .iter()
+            .map(|i| key(*i).to_string())
+            .collect();
+        assert_eq!(keys, expected);
+
+        // Historical reads
+        let (read, _gas) = storage
+            .read_with_height(&key(1), BlockHeight(1))
+            .expect("read failed");
+        assert_eq!(read, Some(vec![1]));
+        let (read, _gas) = storage
+            .read_with_height(&key(1), BlockHeight(2))
+            .expect("read failed");
+        assert_eq!(read, Some(vec![1, 1]));
+        let (read, _gas) = storage
+            .read_with_height(&key(0), BlockHeight(2))
+            .expect("read failed");
+        assert_eq!(read, Some(vec![0, 0]));
+
+        (size_diffs, roots)
+    }
+
+    /// Test that the storage DB backends behave the same and produce
+    /// identical Merkle roots for the same sequence of writes
+    #[test]
+    fn test_db_backends_conformance() {
+        let db_path =
+            TempDir::new().expect("Unable to create a temporary DB directory");
+        let rocksdb_result =
+            apply_conformance_sequence::<PersistentDB>(db_path.path());
+        let db_path =
+            TempDir::new().expect("Unable to create a temporary DB directory");
+        let sled_result = apply_conformance_sequence::<SledDB>(db_path.path());
+        assert_eq!(rocksdb_result, sled_result);
+        let db_path =
+            TempDir::new().expect("Unable to create a temporary DB directory");
+        let mockdb_result =
+            apply_conformance_sequence::<MockDB>(db_path.path());
+        assert_eq!(rocksdb_result, mockdb_result);
+    }
+
+    /// Test that the state committed with the sled backend is loaded back
+    /// after the DB is reopened, like with the RocksDB backend
+    #[test]
+    fn test_sled_commit_and_load() {
+        let db_path =
+            TempDir::new().expect("Unable to create a temporary DB directory");
+        let (_size_diffs, roots) =
+            apply_conformance_sequence::<SledDB>(db_path.path());
+
+        let mut storage = Storage::<SledDB, PersistentStorageHasher>::open(
+            db_path.path(),
+            ChainId::default(),
+            None,
+        );
+        storage
+            .load_last_state()
+            .expect("loading the last state failed");
+        let (loaded_root, height) =
+            storage.get_state().expect("no block exists");
+        assert_eq!(Some(&loaded_root.0), roots.last());
+        assert_eq!(height, 3);
+    }
 }
@@ -4,6 +4,8 @@
 //! - `chain_id`
 //! - `height`: the last committed block height
 //! - `tx_queue`: txs to be decrypted in the next block
+//! - `events_retained_from`: the height of the oldest block whose events are
+//!   retained
 //! - `pred`: predecessor values of the top-level keys of the same name
 //!   - `tx_queue`
 //! - `next_epoch_min_start_height`: minimum block height from which the next
@@ -26,8 +28,10 @@
 //!     - `new/{dyn}`: value set in block height `h`
 //!     - `old/{dyn}`: value from predecessor block height
 //!   - `header`: block's header
+//!   - `events`: events emitted in the block, encoded by the ledger
 
 use std::cmp::Ordering;
+use std::collections::BTreeSet;
 use std::path::Path;
 use std::str::FromStr;
 
@@ -288,10 +292,11 @@ impl DB for RocksDB {
         {
             Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
             None => {
-                tracing::error!(
-                    "Couldn't load next epoch start height from the DB"
-                );
-                return Ok(None);
+                return Err(Error::DBError(
+                    "Couldn't load next epoch start height from the DB, \
+                     although the last block height is present"
+                        .to_string(),
+                ));
             }
         };
         let next_epoch_min_start_time: DateTimeUtc = match self
@@ -301,10 +306,11 @@ impl DB for RocksDB {
         {
             Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
             None => {
-                tracing::error!(
-                    "Couldn't load next epoch start time from the DB"
-                );
-                return Ok(None);
+                return Err(Error::DBError(
+                    "Couldn't load next epoch start time from the DB, \
+                     although the last block height is present"
+                        .to_string(),
+                ));
             }
         };
         let tx_queue: TxQueue = match self
@@ -314,8 +320,11 @@ impl DB for RocksDB {
         {
             Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
             None => {
-                tracing::error!("Couldn't load tx queue from the DB");
-                return Ok(None);
+                return Err(Error::DBError(
+                    "Couldn't load tx queue from the DB, although the last \
+                     block height is present"
+                        .to_string(),
+                ));
             }
         };
 
@@ -368,6 +377,9 @@ impl DB for RocksDB {
                     "header" => {
                         // the block header doesn't have to be restored
                     }
+                    "events" => {
+                        // the block events don't have to be restored
+                    }
                     "hash" => {
                         hash = Some(
                             types::decode(bytes).map_err(Error::CodingError)?,
@@ -430,6 +442,7 @@ impl DB for RocksDB {
             next_epoch_min_start_time,
             address_gen,
             tx_queue,
+            events,
         }: BlockStateWrite = state;
 
         // Epoch start height and time
@@ -535,6 +548,13 @@ impl DB for RocksDB {
                 .map_err(Error::KeyError)?;
             batch.put(key.to_string(), types::encode(&address_gen));
         }
+        // Block events
+        {
+            let key = prefix_key
+                .push(&"events".to_owned())
+                .map_err(Error::KeyError)?;
+            batch.put(key.to_string(), events);
+        }
 
         // Block height
         batch.put("height", types::encode(&height));
@@ -563,6 +583,55 @@ impl DB for RocksDB {
         }
     }
 
+    fn read_block_events(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<Vec<u8>>> {
+        let prefix_key = Key::from(height.to_db_key());
+        let key = prefix_key
+            .push(&"events".to_owned())
+            .map_err(Error::KeyError)?;
+        self.0
+            .get(key.to_string())
+            .map_err(|e| Error::DBError(e.into_string()))
+    }
+
+    fn read_events_retained_from(&self) -> Result<BlockHeight> {
+        match self
+            .0
+            .get("events_retained_from")
+            .map_err(|e| Error::DBError(e.into_string()))?
+        {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError),
+            None => Ok(BlockHeight(1)),
+        }
+    }
+
+    fn prune_block_events(&mut self, retain_from: BlockHeight) -> Result<u64> {
+        let pruned_from = self.read_events_retained_from()?;
+        if retain_from <= pruned_from {
+            return Ok(0);
+        }
+        let mut batch = WriteBatch::default();
+        for height in pruned_from.0..retain_from.0 {
+            let key = Key::from(BlockHeight(height).to_db_key())
+                .push(&"events".to_owned())
+                .map_err(Error::KeyError)?;
+            batch.delete(key.to_string());
+        }
+        // Every deletion leaves a tombstone until it's compacted, even if the
+        // block had no events
+        let num_deleted = retain_from.0 - pruned_from.0;
+        batch.put("events_retained_from", types::encode(&retain_from));
+        self.exec_batch(batch)?;
+        Ok(num_deleted)
+    }
+
+    fn compact(&self) -> Result<()> {
+        self.0.compact_range::<&[u8], &[u8]>(None, None);
+        Ok(())
+    }
+
     fn read_merkle_tree_stores(
         &self,
         height: BlockHeight,
@@ -626,7 +695,7 @@ impl DB for RocksDB {
         }
 
         let mut height = height.0;
-        while height > 0 {
+        loop {
             let key_prefix = Key::from(BlockHeight(height).to_db_key())
                 .push(&"diffs".to_owned())
                 .map_err(Error::KeyError)?;
@@ -641,10 +710,50 @@ impl DB for RocksDB {
                 .map_err(|e| Error::DBError(e.into_string()))?;
             match val {
                 Some(bytes) => return Ok(Some(bytes)),
+                // The values written at genesis are at height 0
+                None if height == 0 => return Ok(None),
                 None => height -= 1,
             }
         }
-        Ok(None)
+    }
+
+    fn read_subspace_diff_keys(&self, height: BlockHeight) -> Result<Vec<Key>> {
+        let diffs_prefix = format!(
+            "{}/",
+            Key::from(height.to_db_key())
+                .push(&"diffs".to_owned())
+                .map_err(Error::KeyError)?
+        );
+
+        let mut read_opts = ReadOptions::default();
+        // don't use the prefix bloom filter
+        read_opts.set_total_order_seek(true);
+        let mut upper_prefix = diffs_prefix.clone().into_bytes();
+        if let Some(last) = upper_prefix.pop() {
+            upper_prefix.push(last + 1);
+        }
+        read_opts.set_iterate_upper_bound(upper_prefix);
+        let iter = self.0.iterator_opt(
+            IteratorMode::From(diffs_prefix.as_bytes(), Direction::Forward),
+            read_opts,
+        );
+
+        let mut keys = BTreeSet::new();
+        for (db_key, _value) in iter {
+            let db_key = String::from_utf8(db_key.to_vec())
+                .expect("Cannot convert from bytes to key string");
+            let diff_key = match db_key.strip_prefix(&diffs_prefix) {
+                Some(diff_key) => diff_key,
+                None => continue,
+            };
+            if let Some(key) = diff_key
+                .strip_prefix("new/")
+                .or_else(|| diff_key.strip_prefix("old/"))
+            {
+                keys.insert(Key::parse(key).map_err(Error::KeyError)?);
+            }
+        }
+        Ok(keys.into_iter().collect())
     }
 
     fn write_subspace_val(
@@ -696,7 +805,7 @@ impl DB for RocksDB {
         // Check the length of previous value, if any
         let prev_len = match self
             .0
-            .get(key.to_string())
+            .get(subspace_key.to_string())
             .map_err(|e| Error::DBError(e.into_string()))?
         {
             Some(prev_value) => {
@@ -780,7 +889,7 @@ impl DB for RocksDB {
         // Check the length of previous value, if any
         let prev_len = match self
             .0
-            .get(key.to_string())
+            .get(subspace_key.to_string())
             .map_err(|e| Error::DBError(e.into_string()))?
         {
             Some(prev_value) => {
@@ -803,6 +912,66 @@ impl DB for RocksDB {
 
         Ok(prev_len)
     }
+
+    fn read_staged_val(&self, key: &Key) -> Result<Option<Option<Vec<u8>>>> {
+        let staged_key =
+            Key::parse("staged").map_err(Error::KeyError)?.join(key);
+        match self
+            .0
+            .get(staged_key.to_string())
+            .map_err(|e| Error::DBError(e.into_string()))?
+        {
+            Some(bytes) => {
+                Ok(Some(types::decode(bytes).map_err(Error::CodingError)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn write_staged_val(
+        &mut self,
+        key: &Key,
+        value: Option<&[u8]>,
+    ) -> Result<()> {
+        let staged_key =
+            Key::parse("staged").map_err(Error::KeyError)?.join(key);
+        self.0
+            .put(staged_key.to_string(), types::encode(&value))
+            .map_err(|e| Error::DBError(e.into_string()))
+    }
+
+    fn batch_delete_staged_val(
+        &self,
+        batch: &mut Self::WriteBatch,
+        key: &Key,
+    ) -> Result<()> {
+        let staged_key =
+            Key::parse("staged").map_err(Error::KeyError)?.join(key);
+        batch.delete(staged_key.to_string());
+        Ok(())
+    }
+
+    fn clear_staged_vals(&mut self) -> Result<()> {
+        let staged_prefix = "staged/".to_owned();
+
+        let mut read_opts = ReadOptions::default();
+        // don't use the prefix bloom filter
+        read_opts.set_total_order_seek(true);
+        let mut upper_prefix = staged_prefix.clone().into_bytes();
+        if let Some(last) = upper_prefix.pop() {
+            upper_prefix.push(last + 1);
+        }
+        read_opts.set_iterate_upper_bound(upper_prefix);
+
+        let mut batch = WriteBatch::default();
+        for (key, _value) in self.0.iterator_opt(
+            IteratorMode::From(staged_prefix.as_bytes(), Direction::Forward),
+            read_opts,
+        ) {
+            batch.delete(key);
+        }
+        self.exec_batch(batch)
+    }
 }
 
 impl<'iter> DBIter<'iter> for RocksDB {
@@ -985,6 +1154,7 @@ mod test {
             next_epoch_min_start_time,
             address_gen: &address_gen,
             tx_queue: &tx_queue,
+            events: &[],
         };
 
         db.write_block(block).unwrap();
@@ -995,6 +1165,43 @@ mod test {
             .expect("Block should have been written");
     }
 
+    /// Test that loading a block whose state is incomplete fails, instead of
+    /// reporting that no state has been found.
+    #[test]
+    fn test_load_corrupted_state() {
+        let dir = tempdir().unwrap();
+        let mut db = open(dir.path(), None).unwrap();
+
+        let merkle_tree = MerkleTree::<Sha256Hasher>::default();
+        let hash = BlockHash::default();
+        let pred_epochs = Epochs::default();
+        let address_gen = EstablishedAddressGen::new("whatever");
+        let tx_queue = TxQueue::default();
+        let block = BlockStateWrite {
+            merkle_tree_stores: merkle_tree.stores(),
+            header: None,
+            hash: &hash,
+            height: BlockHeight(1),
+            epoch: Epoch::default(),
+            pred_epochs: &pred_epochs,
+            next_epoch_min_start_height: BlockHeight::default(),
+            next_epoch_min_start_time: DateTimeUtc::now(),
+            address_gen: &address_gen,
+            tx_queue: &tx_queue,
+            events: &[],
+        };
+        db.write_block(block).unwrap();
+
+        // Lose a part of the block's state
+        db.0.delete("next_epoch_min_start_time").unwrap();
+
+        let result = db.read_last_block();
+        assert!(
+            matches!(result, Err(Error::DBError(_))),
+            "Reading an incomplete block state must fail"
+        );
+    }
+
     #[test]
     fn test_read() {
         let dir = tempdir().unwrap();
@@ -1033,4 +1240,58 @@ mod test {
             db.read_subspace_val(&key).expect("read should succeed");
         assert_eq!(latest_value, Some(vec![2_u8, 2, 2, 2]));
     }
+
+    /// Test that the compaction reclaims the disk space of the pruned block
+    /// events.
+    #[test]
+    fn test_compact_pruned_events() {
+        let dir = tempdir().unwrap();
+        let mut db = open(dir.path(), None).unwrap();
+
+        // Write incompressible events of many blocks
+        let num_blocks = 2000;
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+        for height in 1..=num_blocks {
+            let events: Vec<u8> = (0..4096)
+                .map(|_| {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 7;
+                    seed ^= seed << 17;
+                    seed as u8
+                })
+                .collect();
+            let key = Key::from(BlockHeight(height).to_db_key())
+                .push(&"events".to_owned())
+                .unwrap();
+            db.0.put(key.to_string(), events).unwrap();
+        }
+        db.flush(true).unwrap();
+
+        let num_deleted =
+            db.prune_block_events(BlockHeight(num_blocks + 1)).unwrap();
+        assert_eq!(num_deleted, num_blocks);
+        // Close the DB, so that it can be opened for the compaction
+        drop(db);
+
+        let (size_before, size_after) =
+            crate::node::ledger::compact::compact_db(
+                dir.path(),
+                crate::config::StorageBackend::RocksDb,
+            )
+            .unwrap();
+        assert!(
+            size_after < size_before / 2,
+            "The DB size {} should have dropped after compaction from {}",
+            size_after,
+            size_before
+        );
+
+        // The DB can be opened again and the events are gone
+        let db = open(dir.path(), None).unwrap();
+        assert_eq!(
+            db.read_events_retained_from().unwrap(),
+            BlockHeight(num_blocks + 1)
+        );
+        assert_eq!(db.read_block_events(BlockHeight(1)).unwrap(), None);
+    }
 }
@@ -0,0 +1,912 @@
+//! The persistent storage in sled, an alternative to the RocksDB backend.
+//!
+//! The storage tree is the same as in the RocksDB backend, see its module
+//! docs. Unlike RocksDB, sled orders the keys lexicographically, which doesn't
+//! order the block heights, but all the reads of the keys under a block height
+//! scan the height's prefix, so the order of the heights doesn't matter.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::str::FromStr;
+
+use anoma::ledger::storage::types::PrefixIterator;
+use anoma::ledger::storage::{
+    types, BlockStateRead, BlockStateWrite, DBIter, DBWriteBatch, Error,
+    MerkleTreeStoresRead, Result, StoreType, DB,
+};
+use anoma::types::storage::{
+    BlockHeight, Key, KeySeg, TxQueue, KEY_SEGMENT_SEPARATOR,
+};
+use anoma::types::time::DateTimeUtc;
+#[cfg(not(feature = "ABCI"))]
+use tendermint::block::Header;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_proto::Protobuf;
+#[cfg(feature = "ABCI")]
+use tendermint_proto_abci::Protobuf;
+#[cfg(feature = "ABCI")]
+use tendermint_stable::block::Header;
+
+/// sled handle
+#[derive(Debug)]
+pub struct SledDB(sled::Db);
+
+/// DB Handle for batch writes.
+#[derive(Default)]
+pub struct SledDBWriteBatch(sled::Batch);
+
+/// Open sled for the DB. The cache is the maximum size in bytes of sled's
+/// page cache, which defaults to sled's own default when not given.
+pub fn open(path: impl AsRef<Path>, cache: Option<&u64>) -> Result<SledDB> {
+    let mut config = sled::Config::new().path(path);
+    if let Some(cache_capacity) = cache {
+        config = config.cache_capacity(*cache_capacity);
+    }
+    config
+        .open()
+        .map(SledDB)
+        .map_err(|e| Error::DBError(e.to_string()))
+}
+
+impl Drop for SledDB {
+    fn drop(&mut self) {
+        self.flush(true).expect("flush failed");
+    }
+}
+
+impl SledDB {
+    fn get(&self, key: impl AsRef<[u8]>) -> Result<Option<Vec<u8>>> {
+        self.0
+            .get(key)
+            .map(|value| value.map(|value| value.to_vec()))
+            .map_err(|e| Error::DBError(e.to_string()))
+    }
+
+    fn put(
+        &self,
+        key: impl AsRef<[u8]>,
+        value: impl AsRef<[u8]>,
+    ) -> Result<()> {
+        self.0
+            .insert(key, value.as_ref())
+            .map(|_| ())
+            .map_err(|e| Error::DBError(e.to_string()))
+    }
+
+    fn delete(&self, key: impl AsRef<[u8]>) -> Result<()> {
+        self.0
+            .remove(key)
+            .map(|_| ())
+            .map_err(|e| Error::DBError(e.to_string()))
+    }
+
+    /// Iterate the key-vals with the given prefix of the key
+    fn iter_prefix_raw(
+        &self,
+        prefix: impl AsRef<[u8]>,
+    ) -> impl Iterator<Item = Result<(String, Vec<u8>)>> {
+        self.0.scan_prefix(prefix).map(|item| {
+            let (key, value) =
+                item.map_err(|e| Error::DBError(e.to_string()))?;
+            let key = String::from_utf8(key.to_vec()).map_err(|e| {
+                Error::Temporary {
+                    error: format!(
+                        "Cannot convert path from utf8 bytes to string: {}",
+                        e
+                    ),
+                }
+            })?;
+            Ok((key, value.to_vec()))
+        })
+    }
+
+    /// Persist the diff of an account subspace key-val under the height where
+    /// it was changed.
+    fn write_subspace_diff(
+        &mut self,
+        height: BlockHeight,
+        key: &Key,
+        old_value: Option<&[u8]>,
+        new_value: Option<&[u8]>,
+    ) -> Result<()> {
+        let mut batch = SledDBWriteBatch::default();
+        Self::batch_write_subspace_diff(
+            &mut batch, height, key, old_value, new_value,
+        )?;
+        self.exec_batch(batch.0)
+    }
+
+    /// Persist the diff of an account subspace key-val under the height where
+    /// it was changed in a batch write.
+    fn batch_write_subspace_diff(
+        batch: &mut SledDBWriteBatch,
+        height: BlockHeight,
+        key: &Key,
+        old_value: Option<&[u8]>,
+        new_value: Option<&[u8]>,
+    ) -> Result<()> {
+        let key_prefix = Key::from(height.to_db_key())
+            .push(&"diffs".to_owned())
+            .map_err(Error::KeyError)?;
+
+        if let Some(old_value) = old_value {
+            let old_val_key = key_prefix
+                .push(&"old".to_owned())
+                .map_err(Error::KeyError)?
+                .join(key)
+                .to_string();
+            batch.put(old_val_key, old_value);
+        }
+
+        if let Some(new_value) = new_value {
+            let new_val_key = key_prefix
+                .push(&"new".to_owned())
+                .map_err(Error::KeyError)?
+                .join(key)
+                .to_string();
+            batch.put(new_val_key, new_value);
+        }
+        Ok(())
+    }
+
+    fn exec_batch(&mut self, batch: sled::Batch) -> Result<()> {
+        self.0
+            .apply_batch(batch)
+            .map_err(|e| Error::DBError(e.to_string()))
+    }
+}
+
+impl DB for SledDB {
+    /// The maximum size in bytes of sled's page cache
+    type Cache = u64;
+    type WriteBatch = SledDBWriteBatch;
+
+    fn open(
+        db_path: impl AsRef<std::path::Path>,
+        cache: Option<&Self::Cache>,
+    ) -> Self {
+        open(db_path, cache).expect("cannot open the DB")
+    }
+
+    fn flush(&self, wait: bool) -> Result<()> {
+        // sled flushes its log in the background every 500ms, so there's
+        // nothing to do unless we have to wait for the data to be persisted
+        if wait {
+            self.0.flush().map_err(|e| Error::DBError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn read_last_block(&mut self) -> Result<Option<BlockStateRead>> {
+        // Block height
+        let height: BlockHeight = match self.get("height")? {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => return Ok(None),
+        };
+
+        // Epoch start height and time
+        let next_epoch_min_start_height: BlockHeight = match self
+            .get("next_epoch_min_start_height")?
+        {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => {
+                return Err(Error::DBError(
+                    "Couldn't load next epoch start height from the DB, \
+                     although the last block height is present"
+                        .to_string(),
+                ));
+            }
+        };
+        let next_epoch_min_start_time: DateTimeUtc = match self
+            .get("next_epoch_min_start_time")?
+        {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => {
+                return Err(Error::DBError(
+                    "Couldn't load next epoch start time from the DB, \
+                     although the last block height is present"
+                        .to_string(),
+                ));
+            }
+        };
+        let tx_queue: TxQueue = match self.get("tx_queue")? {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => {
+                return Err(Error::DBError(
+                    "Couldn't load tx queue from the DB, although the last \
+                     block height is present"
+                        .to_string(),
+                ));
+            }
+        };
+
+        // Load data at the height
+        let prefix = format!("{}/", height.raw());
+        let mut merkle_tree_stores = MerkleTreeStoresRead::default();
+        let mut hash = None;
+        let mut epoch = None;
+        let mut pred_epochs = None;
+        let mut address_gen = None;
+        for item in self.iter_prefix_raw(prefix) {
+            let (path, bytes) = item?;
+            let path = &path;
+            let segments: Vec<&str> =
+                path.split(KEY_SEGMENT_SEPARATOR).collect();
+            match segments.get(1) {
+                Some(prefix) => match *prefix {
+                    "tree" => match segments.get(2) {
+                        Some(s) => {
+                            let st = StoreType::from_str(s)?;
+                            match segments.get(3) {
+                                Some(&"root") => merkle_tree_stores.set_root(
+                                    &st,
+                                    types::decode(bytes)
+                                        .map_err(Error::CodingError)?,
+                                ),
+                                Some(&"store") => merkle_tree_stores.set_store(
+                                    &st,
+                                    types::decode(bytes)
+                                        .map_err(Error::CodingError)?,
+                                ),
+                                _ => unknown_key_error(path)?,
+                            }
+                        }
+                        None => unknown_key_error(path)?,
+                    },
+                    "header" => {
+                        // the block header doesn't have to be restored
+                    }
+                    "events" => {
+                        // the block events don't have to be restored
+                    }
+                    "hash" => {
+                        hash = Some(
+                            types::decode(bytes).map_err(Error::CodingError)?,
+                        )
+                    }
+                    "epoch" => {
+                        epoch = Some(
+                            types::decode(bytes).map_err(Error::CodingError)?,
+                        )
+                    }
+                    "pred_epochs" => {
+                        pred_epochs = Some(
+                            types::decode(bytes).map_err(Error::CodingError)?,
+                        )
+                    }
+                    "address_gen" => {
+                        address_gen = Some(
+                            types::decode(bytes).map_err(Error::CodingError)?,
+                        );
+                    }
+                    "diffs" => {
+                        // ignore the diffs
+                    }
+                    _ => unknown_key_error(path)?,
+                },
+                None => unknown_key_error(path)?,
+            }
+        }
+        match (hash, epoch, pred_epochs, address_gen) {
+            (Some(hash), Some(epoch), Some(pred_epochs), Some(address_gen)) => {
+                Ok(Some(BlockStateRead {
+                    merkle_tree_stores,
+                    hash,
+                    height,
+                    epoch,
+                    pred_epochs,
+                    next_epoch_min_start_height,
+                    next_epoch_min_start_time,
+                    address_gen,
+                    tx_queue,
+                }))
+            }
+            _ => Err(Error::Temporary {
+                error: "Essential data couldn't be read from the DB"
+                    .to_string(),
+            }),
+        }
+    }
+
+    fn write_block(&mut self, state: BlockStateWrite) -> Result<()> {
+        let mut batch = SledDBWriteBatch::default();
+        let BlockStateWrite {
+            merkle_tree_stores,
+            header,
+            hash,
+            height,
+            epoch,
+            pred_epochs,
+            next_epoch_min_start_height,
+            next_epoch_min_start_time,
+            address_gen,
+            tx_queue,
+            events,
+        }: BlockStateWrite = state;
+
+        // Epoch start height and time
+        if let Some(current_value) = self.get("next_epoch_min_start_height")? {
+            // Write the predecessor value for rollback
+            batch.put("pred/next_epoch_min_start_height", current_value);
+        }
+        batch.put(
+            "next_epoch_min_start_height",
+            types::encode(&next_epoch_min_start_height),
+        );
+
+        if let Some(current_value) = self.get("next_epoch_min_start_time")? {
+            // Write the predecessor value for rollback
+            batch.put("pred/next_epoch_min_start_time", current_value);
+        }
+        batch.put(
+            "next_epoch_min_start_time",
+            types::encode(&next_epoch_min_start_time),
+        );
+        // Tx queue
+        if let Some(pred_tx_queue) = self.get("tx_queue")? {
+            // Write the predecessor value for rollback
+            batch.put("pred/tx_queue", pred_tx_queue);
+        }
+        batch.put("tx_queue", types::encode(&tx_queue));
+
+        let prefix_key = Key::from(height.to_db_key());
+        // Merkle tree
+        {
+            let prefix_key = prefix_key
+                .push(&"tree".to_owned())
+                .map_err(Error::KeyError)?;
+            for st in StoreType::iter() {
+                let prefix_key = prefix_key
+                    .push(&st.to_string())
+                    .map_err(Error::KeyError)?;
+                let root_key = prefix_key
+                    .push(&"root".to_owned())
+                    .map_err(Error::KeyError)?;
+                batch.put(
+                    root_key.to_string(),
+                    types::encode(merkle_tree_stores.root(st)),
+                );
+                let store_key = prefix_key
+                    .push(&"store".to_owned())
+                    .map_err(Error::KeyError)?;
+                batch.put(
+                    store_key.to_string(),
+                    types::encode(merkle_tree_stores.store(st)),
+                );
+            }
+        }
+        // Block header
+        {
+            if let Some(h) = header {
+                let key = prefix_key
+                    .push(&"header".to_owned())
+                    .map_err(Error::KeyError)?;
+                batch.put(
+                    key.to_string(),
+                    h.encode_vec().expect("serialization failed"),
+                );
+            }
+        }
+        // Block hash
+        {
+            let key = prefix_key
+                .push(&"hash".to_owned())
+                .map_err(Error::KeyError)?;
+            batch.put(key.to_string(), types::encode(&hash));
+        }
+        // Block epoch
+        {
+            let key = prefix_key
+                .push(&"epoch".to_owned())
+                .map_err(Error::KeyError)?;
+            batch.put(key.to_string(), types::encode(&epoch));
+        }
+        // Predecessor block epochs
+        {
+            let key = prefix_key
+                .push(&"pred_epochs".to_owned())
+                .map_err(Error::KeyError)?;
+            batch.put(key.to_string(), types::encode(&pred_epochs));
+        }
+        // Address gen
+        {
+            let key = prefix_key
+                .push(&"address_gen".to_owned())
+                .map_err(Error::KeyError)?;
+            batch.put(key.to_string(), types::encode(&address_gen));
+        }
+        // Block events
+        {
+            let key = prefix_key
+                .push(&"events".to_owned())
+                .map_err(Error::KeyError)?;
+            batch.put(key.to_string(), events);
+        }
+
+        // Block height
+        batch.put("height", types::encode(&height));
+
+        // Write the batch
+        self.exec_batch(batch.0)?;
+
+        // Flush without waiting
+        self.flush(false)
+    }
+
+    fn read_block_header(&self, height: BlockHeight) -> Result<Option<Header>> {
+        let prefix_key = Key::from(height.to_db_key());
+        let key = prefix_key
+            .push(&"header".to_owned())
+            .map_err(Error::KeyError)?;
+        match self.get(key.to_string())? {
+            Some(v) => Ok(Some(
+                Header::decode_vec(&v).map_err(Error::ProtobufCodingError)?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    fn read_block_events(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<Vec<u8>>> {
+        let prefix_key = Key::from(height.to_db_key());
+        let key = prefix_key
+            .push(&"events".to_owned())
+            .map_err(Error::KeyError)?;
+        self.get(key.to_string())
+    }
+
+    fn read_events_retained_from(&self) -> Result<BlockHeight> {
+        match self.get("events_retained_from")? {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError),
+            None => Ok(BlockHeight(1)),
+        }
+    }
+
+    fn prune_block_events(&mut self, retain_from: BlockHeight) -> Result<u64> {
+        let pruned_from = self.read_events_retained_from()?;
+        if retain_from <= pruned_from {
+            return Ok(0);
+        }
+        let mut batch = SledDBWriteBatch::default();
+        for height in pruned_from.0..retain_from.0 {
+            let key = Key::from(BlockHeight(height).to_db_key())
+                .push(&"events".to_owned())
+                .map_err(Error::KeyError)?;
+            batch.delete(key.to_string());
+        }
+        // Count the deletions the same way as the RocksDB backend, even if
+        // the block had no events
+        let num_deleted = retain_from.0 - pruned_from.0;
+        batch.put("events_retained_from", types::encode(&retain_from));
+        self.exec_batch(batch.0)?;
+        Ok(num_deleted)
+    }
+
+    fn compact(&self) -> Result<()> {
+        // sled reclaims the space of the deleted and overwritten values in
+        // the background by itself, it only has to persist them
+        self.flush(true)
+    }
+
+    fn read_merkle_tree_stores(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<MerkleTreeStoresRead>> {
+        let mut merkle_tree_stores = MerkleTreeStoresRead::default();
+        let height_key = Key::from(height.to_db_key());
+        let tree_key = height_key
+            .push(&"tree".to_owned())
+            .map_err(Error::KeyError)?;
+        for st in StoreType::iter() {
+            let prefix_key =
+                tree_key.push(&st.to_string()).map_err(Error::KeyError)?;
+            let root_key = prefix_key
+                .push(&"root".to_owned())
+                .map_err(Error::KeyError)?;
+            match self.get(root_key.to_string())? {
+                Some(b) => {
+                    let root = types::decode(b).map_err(Error::CodingError)?;
+                    merkle_tree_stores.set_root(st, root);
+                }
+                None => return Ok(None),
+            }
+
+            let store_key = prefix_key
+                .push(&"store".to_owned())
+                .map_err(Error::KeyError)?;
+            match self.get(store_key.to_string())? {
+                Some(b) => {
+                    let store = types::decode(b).map_err(Error::CodingError)?;
+                    merkle_tree_stores.set_store(st, store);
+                }
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(merkle_tree_stores))
+    }
+
+    fn read_subspace_val(&self, key: &Key) -> Result<Option<Vec<u8>>> {
+        let subspace_key =
+            Key::parse("subspace").map_err(Error::KeyError)?.join(key);
+        self.get(subspace_key.to_string())
+    }
+
+    fn read_subspace_val_with_height(
+        &self,
+        key: &Key,
+        height: BlockHeight,
+    ) -> Result<Option<Vec<u8>>> {
+        if self.read_subspace_val(key)?.is_none() {
+            return Ok(None);
+        }
+
+        let mut height = height.0;
+        loop {
+            let key_prefix = Key::from(BlockHeight(height).to_db_key())
+                .push(&"diffs".to_owned())
+                .map_err(Error::KeyError)?;
+            let new_val_key = key_prefix
+                .push(&"new".to_owned())
+                .map_err(Error::KeyError)?
+                .join(key)
+                .to_string();
+            match self.get(new_val_key)? {
+                Some(bytes) => return Ok(Some(bytes)),
+                // The values written at genesis are at height 0
+                None if height == 0 => return Ok(None),
+                None => height -= 1,
+            }
+        }
+    }
+
+    fn read_subspace_diff_keys(&self, height: BlockHeight) -> Result<Vec<Key>> {
+        let diffs_prefix = format!(
+            "{}/",
+            Key::from(height.to_db_key())
+                .push(&"diffs".to_owned())
+                .map_err(Error::KeyError)?
+        );
+
+        let mut keys = BTreeSet::new();
+        for item in self.iter_prefix_raw(&diffs_prefix) {
+            let (db_key, _value) = item?;
+            let diff_key = match db_key.strip_prefix(&diffs_prefix) {
+                Some(diff_key) => diff_key,
+                None => continue,
+            };
+            if let Some(key) = diff_key
+                .strip_prefix("new/")
+                .or_else(|| diff_key.strip_prefix("old/"))
+            {
+                keys.insert(Key::parse(key).map_err(Error::KeyError)?);
+            }
+        }
+        Ok(keys.into_iter().collect())
+    }
+
+    fn write_subspace_val(
+        &mut self,
+        height: BlockHeight,
+        key: &Key,
+        value: impl AsRef<[u8]>,
+    ) -> Result<i64> {
+        let value = value.as_ref();
+        let subspace_key =
+            Key::parse("subspace").map_err(Error::KeyError)?.join(key);
+        let size_diff = match self.get(subspace_key.to_string())? {
+            Some(prev_value) => {
+                let size_diff = value.len() as i64 - prev_value.len() as i64;
+                self.write_subspace_diff(
+                    height,
+                    key,
+                    Some(&prev_value),
+                    Some(value),
+                )?;
+                size_diff
+            }
+            None => {
+                self.write_subspace_diff(height, key, None, Some(value))?;
+                value.len() as i64
+            }
+        };
+
+        // Write the new key-val
+        self.put(subspace_key.to_string(), value)?;
+
+        Ok(size_diff)
+    }
+
+    fn delete_subspace_val(
+        &mut self,
+        height: BlockHeight,
+        key: &Key,
+    ) -> Result<i64> {
+        let subspace_key =
+            Key::parse("subspace").map_err(Error::KeyError)?.join(key);
+
+        // Check the length of previous value, if any
+        let prev_len = match self.get(subspace_key.to_string())? {
+            Some(prev_value) => {
+                let prev_len = prev_value.len() as i64;
+                self.write_subspace_diff(height, key, Some(&prev_value), None)?;
+                prev_len
+            }
+            None => 0,
+        };
+
+        // Delete the key-val
+        self.delete(subspace_key.to_string())?;
+
+        Ok(prev_len)
+    }
+
+    fn batch() -> Self::WriteBatch {
+        SledDBWriteBatch::default()
+    }
+
+    fn exec_batch(&mut self, batch: Self::WriteBatch) -> Result<()> {
+        self.exec_batch(batch.0)
+    }
+
+    fn batch_write_subspace_val(
+        &self,
+        batch: &mut Self::WriteBatch,
+        height: BlockHeight,
+        key: &Key,
+        value: impl AsRef<[u8]>,
+    ) -> Result<i64> {
+        let value = value.as_ref();
+        let subspace_key =
+            Key::parse("subspace").map_err(Error::KeyError)?.join(key);
+        let size_diff = match self.get(subspace_key.to_string())? {
+            Some(old_value) => {
+                let size_diff = value.len() as i64 - old_value.len() as i64;
+                // Persist the previous value
+                Self::batch_write_subspace_diff(
+                    batch,
+                    height,
+                    key,
+                    Some(&old_value),
+                    Some(value),
+                )?;
+                size_diff
+            }
+            None => {
+                Self::batch_write_subspace_diff(
+                    batch,
+                    height,
+                    key,
+                    None,
+                    Some(value),
+                )?;
+                value.len() as i64
+            }
+        };
+
+        // Write the new key-val
+        batch.put(&subspace_key.to_string(), value);
+
+        Ok(size_diff)
+    }
+
+    fn batch_delete_subspace_val(
+        &self,
+        batch: &mut Self::WriteBatch,
+        height: BlockHeight,
+        key: &Key,
+    ) -> Result<i64> {
+        let subspace_key =
+            Key::parse("subspace").map_err(Error::KeyError)?.join(key);
+
+        // Check the length of previous value, if any
+        let prev_len = match self.get(subspace_key.to_string())? {
+            Some(prev_value) => {
+                let prev_len = prev_value.len() as i64;
+                // Persist the previous value
+                Self::batch_write_subspace_diff(
+                    batch,
+                    height,
+                    key,
+                    Some(&prev_value),
+                    None,
+                )?;
+                prev_len
+            }
+            None => 0,
+        };
+
+        // Delete the key-val
+        batch.delete(subspace_key.to_string());
+
+        Ok(prev_len)
+    }
+
+    fn read_staged_val(&self, key: &Key) -> Result<Option<Option<Vec<u8>>>> {
+        let staged_key =
+            Key::parse("staged").map_err(Error::KeyError)?.join(key);
+        match self.get(staged_key.to_string())? {
+            Some(bytes) => {
+                Ok(Some(types::decode(bytes).map_err(Error::CodingError)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn write_staged_val(
+        &mut self,
+        key: &Key,
+        value: Option<&[u8]>,
+    ) -> Result<()> {
+        let staged_key =
+            Key::parse("staged").map_err(Error::KeyError)?.join(key);
+        self.put(staged_key.to_string(), types::encode(&value))
+    }
+
+    fn batch_delete_staged_val(
+        &self,
+        batch: &mut Self::WriteBatch,
+        key: &Key,
+    ) -> Result<()> {
+        let staged_key =
+            Key::parse("staged").map_err(Error::KeyError)?.join(key);
+        batch.delete(staged_key.to_string());
+        Ok(())
+    }
+
+    fn clear_staged_vals(&mut self) -> Result<()> {
+        let mut batch = SledDBWriteBatch::default();
+        for item in self.iter_prefix_raw("staged/") {
+            let (key, _value) = item?;
+            batch.delete(key);
+        }
+        self.exec_batch(batch.0)
+    }
+}
+
+impl<'iter> DBIter<'iter> for SledDB {
+    type PrefixIter = SledPrefixIterator;
+
+    fn iter_prefix(&'iter self, prefix: &Key) -> SledPrefixIterator {
+        let db_prefix = "subspace/".to_owned();
+        let prefix = format!("{}{}", db_prefix, prefix);
+        let iter = self.0.scan_prefix(prefix);
+        SledPrefixIterator(PrefixIterator { iter, db_prefix })
+    }
+}
+
+#[derive(Debug)]
+pub struct SledPrefixIterator(PrefixIterator<sled::Iter>);
+
+impl Iterator for SledPrefixIterator {
+    type Item = (String, Vec<u8>, u64);
+
+    /// Returns the next pair and the gas cost
+    fn next(&mut self) -> Option<(String, Vec<u8>, u64)> {
+        match self.0.iter.next() {
+            Some(item) => {
+                let (key, val) = item.expect("Cannot read from the DB");
+                let key = String::from_utf8(key.to_vec())
+                    .expect("Cannot convert from bytes to key string");
+                match key.strip_prefix(&self.0.db_prefix) {
+                    Some(k) => {
+                        let gas = k.len() + val.len();
+                        Some((k.to_owned(), val.to_vec(), gas as _))
+                    }
+                    None => self.next(),
+                }
+            }
+            None => None,
+        }
+    }
+}
+
+impl DBWriteBatch for SledDBWriteBatch {
+    fn put<K, V>(&mut self, key: K, value: V)
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        self.0.insert(key.as_ref(), value.as_ref())
+    }
+
+    fn delete<K: AsRef<[u8]>>(&mut self, key: K) {
+        self.0.remove(key.as_ref())
+    }
+}
+
+fn unknown_key_error(key: &str) -> Result<()> {
+    Err(Error::UnknownKey {
+        key: key.to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use anoma::ledger::storage::{MerkleTree, Sha256Hasher};
+    use anoma::types::address::EstablishedAddressGen;
+    use anoma::types::storage::{BlockHash, Epoch, Epochs};
+    use tempfile::tempdir;
+
+    use super::*;
+
+    /// Test that a block written can be loaded back from DB, also after the DB
+    /// is reopened.
+    #[test]
+    fn test_load_state() {
+        let dir = tempdir().unwrap();
+        let mut db = open(dir.path(), None).unwrap();
+
+        let merkle_tree = MerkleTree::<Sha256Hasher>::default();
+        let hash = BlockHash::default();
+        let pred_epochs = Epochs::default();
+        let address_gen = EstablishedAddressGen::new("whatever");
+        let tx_queue = TxQueue::default();
+        // Write the blocks 9 and 10, whose heights aren't ordered
+        // lexicographically
+        for height in [9, 10] {
+            let block = BlockStateWrite {
+                merkle_tree_stores: merkle_tree.stores(),
+                header: None,
+                hash: &hash,
+                height: BlockHeight(height),
+                epoch: Epoch::default(),
+                pred_epochs: &pred_epochs,
+                next_epoch_min_start_height: BlockHeight::default(),
+                next_epoch_min_start_time: DateTimeUtc::now(),
+                address_gen: &address_gen,
+                tx_queue: &tx_queue,
+                events: &[],
+            };
+            db.write_block(block).unwrap();
+        }
+        drop(db);
+
+        let mut db = open(dir.path(), None).unwrap();
+        let state = db
+            .read_last_block()
+            .expect("Should be able to read last block")
+            .expect("Block should have been written");
+        assert_eq!(state.height, BlockHeight(10));
+    }
+
+    #[test]
+    fn test_read() {
+        let dir = tempdir().unwrap();
+        let mut db = open(dir.path(), None).unwrap();
+
+        let key = Key::parse("test").unwrap();
+
+        let mut batch = SledDB::batch();
+        let last_height = BlockHeight(100);
+        db.batch_write_subspace_val(
+            &mut batch,
+            last_height,
+            &key,
+            vec![1_u8, 1, 1, 1],
+        )
+        .unwrap();
+        db.exec_batch(batch.0).unwrap();
+
+        let mut batch = SledDB::batch();
+        let last_height = BlockHeight(111);
+        db.batch_write_subspace_val(
+            &mut batch,
+            last_height,
+            &key,
+            vec![2_u8, 2, 2, 2],
+        )
+        .unwrap();
+        db.exec_batch(batch.0).unwrap();
+
+        let prev_value = db
+            .read_subspace_val_with_height(&key, BlockHeight(100))
+            .expect("read should succeed");
+        assert_eq!(prev_value, Some(vec![1_u8, 1, 1, 1]));
+
+        let latest_value =
+            db.read_subspace_val(&key).expect("read should succeed");
+        assert_eq!(latest_value, Some(vec![2_u8, 2, 2, 2]));
+    }
+}
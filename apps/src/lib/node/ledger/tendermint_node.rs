@@ -341,6 +341,9 @@ async fn update_tendermint_config(
     // also implies that it's not possible for an invalid tx to become valid
     // again in the future.
     config.mempool.keep_invalid_txs_in_cache = false;
+    // The txs left in the mempool must be re-checked after a block is
+    // committed, which keeps the ledger's view of the mempool up to date.
+    config.mempool.recheck = true;
 
     config.rpc.laddr =
         TendermintAddress::from_str(&tendermint_config.rpc_address.to_string())
@@ -322,6 +322,7 @@ impl ResultHandler {
                 Fee {
                     amount: 0.into(),
                     token: address::xan(),
+                    gas_price: None,
                 },
                 &self.tx_signing_key,
                 epoch,
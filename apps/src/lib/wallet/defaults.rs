@@ -133,17 +133,17 @@ mod dev {
 
     /// An established user address for testing & development
     pub fn albert_address() -> Address {
-        Address::decode("atest1v4ehgw368ycryv2z8qcnxv3cxgmrgvjpxs6yg333gym5vv2zxepnj334g4rryvj9xucrgve4x3xvr4").expect("The token address decoding shouldn't fail")
+        Address::decode_with_hrp("atest1v4ehgw368ycryv2z8qcnxv3cxgmrgvjpxs6yg333gym5vv2zxepnj334g4rryvj9xucrgve4x3xvr4", address::DEFAULT_ADDRESS_HRP).expect("The token address decoding shouldn't fail")
     }
 
     /// An established user address for testing & development
     pub fn bertha_address() -> Address {
-        Address::decode("atest1v4ehgw36xvcyyvejgvenxs34g3zygv3jxqunjd6rxyeyys3sxy6rwvfkx4qnj33hg9qnvse4lsfctw").expect("The token address decoding shouldn't fail")
+        Address::decode_with_hrp("atest1v4ehgw36xvcyyvejgvenxs34g3zygv3jxqunjd6rxyeyys3sxy6rwvfkx4qnj33hg9qnvse4lsfctw", address::DEFAULT_ADDRESS_HRP).expect("The token address decoding shouldn't fail")
     }
 
     /// An established user address for testing & development
     pub fn christel_address() -> Address {
-        Address::decode("atest1v4ehgw36x3qng3jzggu5yvpsxgcngv2xgguy2dpkgvu5x33kx3pr2w2zgep5xwfkxscrxs2pj8075p").expect("The token address decoding shouldn't fail")
+        Address::decode_with_hrp("atest1v4ehgw36x3qng3jzggu5yvpsxgcngv2xgguy2dpkgvu5x33kx3pr2w2zgep5xwfkxscrxs2pj8075p", address::DEFAULT_ADDRESS_HRP).expect("The token address decoding shouldn't fail")
     }
 
     /// An implicit user address for testing & development
@@ -154,12 +154,12 @@ mod dev {
 
     /// An established validator address for testing & development
     pub fn validator_address() -> Address {
-        Address::decode("atest1v4ehgw36ggcnsdee8qerswph8y6ry3p5xgunvve3xaqngd3kxc6nqwz9gseyydzzg5unys3ht2n48q").expect("The token address decoding shouldn't fail")
+        Address::decode_with_hrp("atest1v4ehgw36ggcnsdee8qerswph8y6ry3p5xgunvve3xaqngd3kxc6nqwz9gseyydzzg5unys3ht2n48q", address::DEFAULT_ADDRESS_HRP).expect("The token address decoding shouldn't fail")
     }
 
     /// An established matchmaker address for testing & development
     pub fn matchmaker_address() -> Address {
-        Address::decode("atest1v4ehgw36x5mnswphx565gv2yxdprzvf5gdp523jpxy6rvv6zxaznzsejxeznzseh8pp5ywz93xwala").expect("The address decoding shouldn't fail")
+        Address::decode_with_hrp("atest1v4ehgw36x5mnswphx565gv2yxdprzvf5gdp523jpxy6rvv6zxaznzsejxeznzseh8pp5ywz93xwala", address::DEFAULT_ADDRESS_HRP).expect("The address decoding shouldn't fail")
     }
 
     pub fn albert_keypair() -> common::SecretKey {
@@ -291,6 +291,23 @@ impl Wallet {
         self.store.find_address(alias)
     }
 
+    /// Find the stored alias of an address.
+    pub fn find_alias(&self, address: &Address) -> Option<String> {
+        self.store.find_alias(address).map(Into::into)
+    }
+
+    /// Check if a key or an address is stored with the given alias.
+    pub fn contains_alias(&self, alias: impl AsRef<str>) -> bool {
+        self.store.contains_alias(&alias.as_ref().into())
+    }
+
+    /// Remove the key and the address stored with the given alias, if any.
+    pub fn remove_alias(&mut self, alias: impl AsRef<str>) {
+        let alias: Alias = alias.as_ref().into();
+        self.store.remove_alias(&alias);
+        self.decrypted_key_cache.remove(&alias);
+    }
+
     /// Get all known addresses by their alias, paired with PKH, if known.
     pub fn get_addresses(&self) -> HashMap<String, Address> {
         self.store
@@ -238,6 +238,16 @@ impl Store {
         self.addresses.get(&alias.into())
     }
 
+    /// Find the stored alias of an address. When the address is stored with
+    /// more than one alias, the first one in their order is returned.
+    pub fn find_alias(&self, address: &Address) -> Option<&Alias> {
+        self.addresses
+            .iter()
+            .filter(|(_alias, stored)| *stored == address)
+            .map(|(alias, _address)| alias)
+            .min()
+    }
+
     /// Get all known keys by their alias, paired with PKH, if known.
     pub fn get_keys(
         &self,
@@ -396,6 +406,19 @@ impl Store {
         Some(alias)
     }
 
+    /// Check if a key or an address is stored with the given alias.
+    pub fn contains_alias(&self, alias: &Alias) -> bool {
+        self.keys.contains_key(alias) || self.addresses.contains_key(alias)
+    }
+
+    /// Remove the key and the address stored with the given alias, if any.
+    pub fn remove_alias(&mut self, alias: &Alias) {
+        if self.keys.remove(alias).is_some() {
+            self.pkhs.retain(|_pkh, key_alias| key_alias != alias);
+        }
+        self.addresses.remove(alias);
+    }
+
     fn decode(data: Vec<u8>) -> Result<Self, toml::de::Error> {
         toml::from_slice(&data)
     }
@@ -481,4 +504,26 @@ mod test_wallet {
         let data = store.encode();
         let _ = Store::decode(data).expect("Test failed");
     }
+
+    #[test]
+    fn test_resolve_alias_and_address() {
+        let mut store = Store::new();
+        let address = Address::decode("atest1v4ehgw36x3prswzxggunzv6pxqmnvdj9xvcyzvpsggeyvs3cg9qnywf589qnwvfsg5erg3fkl09rg5").unwrap();
+        let unknown = Address::decode("atest1v4ehgw36xdzryve5gsc52veeg5cnsv2yx5eygvp38qcrvd29xy6rys6p8yc5xvp4xfpy2v694wgwcp").unwrap();
+        store.insert_address("Bertha".into(), address.clone());
+
+        // The alias is resolved to its address regardless of its case
+        assert_eq!(store.find_address("bertha"), Some(&address));
+        assert_eq!(store.find_address("BERTHA"), Some(&address));
+        // The address is resolved back to its alias
+        assert_eq!(store.find_alias(&address), Some(&"bertha".into()));
+        // An unknown address and alias are not resolved
+        assert_eq!(store.find_alias(&unknown), None);
+        assert_eq!(store.find_address("albert"), None);
+
+        // An address stored with more aliases is always resolved to the
+        // first one
+        store.insert_address("Albert".into(), address.clone());
+        assert_eq!(store.find_alias(&address), Some(&"albert".into()));
+    }
 }
@@ -24,6 +24,8 @@ pub enum Error {
     WasmNotFound(String),
     #[error("Error while downloading {0}: {1}")]
     ServerError(String, String),
+    #[error("Error reading {0}: {1}")]
+    FileRead(String, std::io::Error),
 }
 
 /// A hash map where keys are simple file names and values their full file name
@@ -323,6 +325,34 @@ pub fn read_wasm(
     safe_exit(1);
 }
 
+/// Read the given WASM file like [`read_wasm`], but return an error instead
+/// of exiting when the file cannot be read. When the WASM directory has no
+/// checksums file, the file is looked up by its plain name.
+pub fn try_read_wasm(
+    wasm_directory: impl AsRef<Path>,
+    file_path: impl AsRef<Path>,
+) -> Result<Vec<u8>, Error> {
+    let file_path = file_path.as_ref();
+    let checksums_path =
+        wasm_directory.as_ref().join(DEFAULT_WASM_CHECKSUMS_FILE);
+    let checksums: Option<Checksums> = fs::read(&checksums_path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes[..]).ok());
+    let hashed_name = file_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| {
+            checksums.and_then(|checksums| checksums.0.get(name).cloned())
+        });
+    let wasm_path = match hashed_name {
+        Some(hashed_name) => wasm_directory.as_ref().join(hashed_name),
+        None => wasm_directory.as_ref().join(file_path),
+    };
+    fs::read(&wasm_path).map_err(|err| {
+        Error::FileRead(wasm_path.to_string_lossy().into_owned(), err)
+    })
+}
+
 async fn download_wasm(url: String) -> Result<Vec<u8>, Error> {
     tracing::info!("Downloading WASM {}...", url);
     let response = reqwest::get(&url).await;
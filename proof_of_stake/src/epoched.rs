@@ -1225,6 +1225,14 @@ mod tests {
                             light_client_attack_slash_rate: BasisPoints::new(
                                 light_client_attack_slash_rate,
                             ),
+                            jail_duration: unbonding_len,
+                            min_validator_self_bond: 0,
+                            max_validator_stake: None,
+                            max_commission_rate_change: BasisPoints::new(100),
+                            liveness_window_len: 1000,
+                            liveness_max_missed_blocks: 500,
+                            liveness_slash_rate: BasisPoints::new(1),
+                            tombstone_on_double_sign: true,
                         },
                     )
                 },
@@ -33,7 +33,8 @@ use epoched::{
 use parameters::PosParams;
 use thiserror::Error;
 use types::{
-    ActiveValidator, Bonds, Epoch, GenesisValidator, Slash, SlashType, Slashes,
+    ActiveValidator, BasisPoints, Bonds, CommissionRates, Epoch,
+    GenesisValidator, MissedBlocks, Rewards, Slash, SlashType, Slashes,
     TotalVotingPowers, Unbond, Unbonds, ValidatorConsensusKeys, ValidatorSet,
     ValidatorSetUpdate, ValidatorSets, ValidatorState, ValidatorStates,
     ValidatorTotalDeltas, ValidatorVotingPowers, VotingPower, VotingPowerDelta,
@@ -90,6 +91,7 @@ pub trait PosReadOnly {
     /// Cryptographic public key type
     type PublicKey: Debug
         + Clone
+        + PartialEq
         + BorshDeserialize
         + BorshSerialize
         + BorshSchema;
@@ -131,6 +133,13 @@ pub trait PosReadOnly {
     ) -> Option<ValidatorVotingPowers>;
     /// Read PoS slashes applied to a validator.
     fn read_validator_slashes(&self, key: &Self::Address) -> Vec<Slash>;
+    /// Is the PoS validator tombstoned?
+    fn is_validator_tombstoned(&self, key: &Self::Address) -> bool;
+    /// Read PoS validator's commission rate.
+    fn read_validator_commission_rate(
+        &self,
+        key: &Self::Address,
+    ) -> Option<CommissionRates>;
     /// Read PoS bond (validator self-bond or a delegation).
     fn read_bond(
         &self,
@@ -142,6 +151,12 @@ pub trait PosReadOnly {
         &self,
         key: &BondId<Self::Address>,
     ) -> Option<Unbonds<Self::TokenAmount>>;
+    /// Read PoS unclaimed rewards of a bond (validator self-bond or a
+    /// delegation).
+    fn read_rewards(
+        &self,
+        key: &BondId<Self::Address>,
+    ) -> Option<Rewards<Self::TokenAmount>>;
     /// Read PoS validator set (active and inactive).
     fn read_validator_set(&self) -> ValidatorSets<Self::Address>;
     /// Read PoS total voting power of all validators (active and inactive).
@@ -187,6 +202,12 @@ pub trait PosActions: PosReadOnly {
         key: &Self::Address,
         value: ValidatorVotingPowers,
     );
+    /// Write PoS validator's commission rate.
+    fn write_validator_commission_rate(
+        &mut self,
+        key: &Self::Address,
+        value: CommissionRates,
+    );
     /// Write PoS bond (validator self-bond or a delegation).
     fn write_bond(
         &mut self,
@@ -210,6 +231,9 @@ pub trait PosActions: PosReadOnly {
     /// Delete an emptied PoS unbond (unbonded tokens from validator self-bond
     /// or a delegation).
     fn delete_unbond(&mut self, key: &BondId<Self::Address>);
+    /// Delete claimed PoS rewards of a bond (validator self-bond or a
+    /// delegation).
+    fn delete_rewards(&mut self, key: &BondId<Self::Address>);
 
     /// Transfer tokens from the `src` to the `dest`.
     fn transfer(
@@ -226,6 +250,7 @@ pub trait PosActions: PosReadOnly {
         address: &Self::Address,
         staking_reward_address: &Self::Address,
         consensus_key: &Self::PublicKey,
+        commission_rate: BasisPoints,
         current_epoch: impl Into<Epoch>,
     ) -> Result<(), BecomeValidatorError<Self::Address>> {
         let current_epoch = current_epoch.into();
@@ -243,15 +268,22 @@ pub trait PosActions: PosReadOnly {
                 ),
             );
         }
+        if commission_rate > BasisPoints::HUNDRED_PERCENT {
+            return Err(BecomeValidatorError::InvalidCommissionRate(
+                commission_rate,
+            ));
+        }
         let BecomeValidatorData {
             consensus_key,
             state,
             total_deltas,
             voting_power,
+            commission_rate,
         } = become_validator(
             &params,
             address,
             consensus_key,
+            commission_rate,
             &mut validator_set,
             current_epoch,
         );
@@ -265,6 +297,79 @@ pub trait PosActions: PosReadOnly {
         self.write_validator_address_raw_hash(address);
         self.write_validator_total_deltas(address, total_deltas);
         self.write_validator_voting_power(address, voting_power);
+        self.write_validator_commission_rate(address, commission_rate);
+        Ok(())
+    }
+
+    /// Change a validator's commission rate. The new rate takes effect at the
+    /// pipeline offset and it may only differ from the rate in the epoch
+    /// before it by up to the `max_commission_rate_change` parameter.
+    fn change_validator_commission_rate(
+        &mut self,
+        validator: &Self::Address,
+        new_rate: BasisPoints,
+        current_epoch: impl Into<Epoch>,
+    ) -> Result<(), CommissionRateChangeError<Self::Address>> {
+        let current_epoch = current_epoch.into();
+        if new_rate > BasisPoints::HUNDRED_PERCENT {
+            return Err(CommissionRateChangeError::InvalidCommissionRate(
+                new_rate,
+            ));
+        }
+        let params = self.read_pos_params();
+        let mut commission_rates =
+            self.read_validator_commission_rate(validator).ok_or_else(
+                || CommissionRateChangeError::NotAValidator(validator.clone()),
+            )?;
+        let rate_before = validation::commission_rate_before_pipeline(
+            &commission_rates,
+            current_epoch,
+            &params,
+        )
+        .ok_or_else(|| {
+            CommissionRateChangeError::NotAValidator(validator.clone())
+        })?;
+        let change = new_rate.abs_diff(&rate_before);
+        if change > params.max_commission_rate_change {
+            return Err(CommissionRateChangeError::RateChangeTooLarge(
+                change,
+                params.max_commission_rate_change,
+            ));
+        }
+        commission_rates.set(new_rate, current_epoch, &params);
+        self.write_validator_commission_rate(validator, commission_rates);
+        Ok(())
+    }
+
+    /// Rotate a validator's consensus key. The new key takes effect at the
+    /// pipeline offset, it must differ from the validator's key at the
+    /// pipeline offset and it must not be used by any other validator in the
+    /// validator set.
+    fn change_validator_consensus_key(
+        &mut self,
+        validator: &Self::Address,
+        new_key: &Self::PublicKey,
+        current_epoch: impl Into<Epoch>,
+    ) -> Result<(), ConsensusKeyChangeError<Self::Address>> {
+        let current_epoch = current_epoch.into();
+        let params = self.read_pos_params();
+        let not_a_validator =
+            || ConsensusKeyChangeError::NotAValidator(validator.clone());
+        let mut consensus_keys = self
+            .read_validator_consensus_key(validator)
+            .ok_or_else(not_a_validator)?;
+        let validator_sets = self.read_validator_set();
+        validation::check_consensus_key_change(
+            &params,
+            current_epoch,
+            validator,
+            new_key,
+            &consensus_keys,
+            &validator_sets,
+            |address| self.read_validator_consensus_key(address),
+        )?;
+        consensus_keys.set(new_key.clone(), current_epoch, &params);
+        self.write_validator_consensus_key(validator, consensus_keys);
         Ok(())
     }
 
@@ -300,6 +405,14 @@ pub trait PosActions: PosReadOnly {
             validator: validator.clone(),
         };
         let bond = self.read_bond(&bond_id);
+        let self_bond = if source == validator {
+            bond.clone()
+        } else {
+            self.read_bond(&BondId {
+                source: validator.clone(),
+                validator: validator.clone(),
+            })
+        };
         let validator_total_deltas =
             self.read_validator_total_deltas(validator);
         let validator_voting_power =
@@ -316,6 +429,7 @@ pub trait PosActions: PosReadOnly {
             validator_state,
             &bond_id,
             bond,
+            self_bond.as_ref(),
             amount,
             validator_total_deltas,
             validator_voting_power,
@@ -467,6 +581,94 @@ pub trait PosActions: PosReadOnly {
 
         Ok(slashed)
     }
+
+    /// Claim the unclaimed staking rewards of a self-bond to a validator when
+    /// `source` is `None` or equal to the `validator` address, or of tokens
+    /// delegated to the `validator` by the `source`. The rewards are deleted
+    /// together with their transfer from PoS to the `source`, so they cannot
+    /// be claimed twice. Returns the claimed amount, which is zero when there
+    /// are no unclaimed rewards.
+    fn claim_rewards(
+        &mut self,
+        source: Option<&Self::Address>,
+        validator: &Self::Address,
+    ) -> Result<Self::TokenAmount, ClaimRewardsError<Self::Address>> {
+        if !self.is_validator(validator) {
+            return Err(ClaimRewardsError::NotAValidator(validator.clone()));
+        }
+        let source = source.unwrap_or(validator);
+        let bond_id = BondId {
+            source: source.clone(),
+            validator: validator.clone(),
+        };
+
+        let rewards = match self.read_rewards(&bond_id) {
+            Some(rewards) => rewards,
+            None => return Ok(Self::TokenAmount::default()),
+        };
+        let claimed = rewards
+            .values()
+            .fold(Self::TokenAmount::default(), |acc, amount| acc + *amount);
+        self.delete_rewards(&bond_id);
+
+        // Transfer the rewards from PoS to the source
+        self.transfer(
+            &Self::staking_token_address(),
+            claimed,
+            &Self::POS_ADDRESS,
+            source,
+        );
+
+        Ok(claimed)
+    }
+
+    /// Unjail a jailed validator whose jail duration since its last fault is
+    /// over and whose self-bond satisfies the minimum validator self-bond.
+    /// From the pipeline offset, the validator is a candidate again and it's
+    /// inserted back into the validator set with its voting power.
+    fn unjail_validator(
+        &mut self,
+        validator: &Self::Address,
+        current_epoch: impl Into<Epoch>,
+    ) -> Result<(), UnjailError<Self::Address>> {
+        let current_epoch = current_epoch.into();
+        let params = self.read_pos_params();
+        let mut validator_state = self
+            .read_validator_state(validator)
+            .ok_or_else(|| UnjailError::NotAValidator(validator.clone()))?;
+        let total_deltas = self
+            .read_validator_total_deltas(validator)
+            .ok_or_else(|| UnjailError::NotAValidator(validator.clone()))?;
+        let slashes = self.read_validator_slashes(validator);
+        let is_tombstoned = self.is_validator_tombstoned(validator);
+        let self_bond = self.read_bond(&BondId {
+            source: validator.clone(),
+            validator: validator.clone(),
+        });
+        validation::check_unjail(
+            &params,
+            current_epoch,
+            validator,
+            &validator_state,
+            &slashes,
+            is_tombstoned,
+            self_bond.as_ref(),
+        )?;
+        let mut validator_set = self.read_validator_set();
+
+        unjail(
+            &params,
+            current_epoch,
+            validator,
+            &mut validator_state,
+            &mut validator_set,
+            &total_deltas,
+        );
+
+        self.write_validator_state(validator, validator_state);
+        self.write_validator_set(validator_set);
+        Ok(())
+    }
 }
 
 /// PoS system base trait for system initialization on genesis block, updating
@@ -524,6 +726,7 @@ pub trait PosBase {
     type PublicKey: 'static
         + Debug
         + Clone
+        + PartialEq
         + BorshDeserialize
         + BorshSerialize
         + BorshSchema;
@@ -567,6 +770,27 @@ pub trait PosBase {
     ) -> Option<ValidatorVotingPowers>;
     /// Read PoS slashes applied to a validator.
     fn read_validator_slashes(&self, key: &Self::Address) -> Slashes;
+    /// Is the PoS validator tombstoned?
+    fn is_validator_tombstoned(&self, key: &Self::Address) -> bool;
+    /// Read PoS validator's commission rate.
+    fn read_validator_commission_rate(
+        &self,
+        key: &Self::Address,
+    ) -> Option<CommissionRates>;
+    /// Read the heights of the blocks within the liveness window that a PoS
+    /// validator failed to sign.
+    fn read_validator_missed_blocks(&self, key: &Self::Address)
+        -> MissedBlocks;
+    /// Read all the PoS bonds (validator self-bonds and delegations).
+    fn read_bonds(
+        &self,
+    ) -> Vec<(BondId<Self::Address>, Bonds<Self::TokenAmount>)>;
+    /// Read PoS unclaimed rewards of a bond (validator self-bond or a
+    /// delegation).
+    fn read_rewards(
+        &self,
+        key: &BondId<Self::Address>,
+    ) -> Option<Rewards<Self::TokenAmount>>;
     /// Read PoS validator set (active and inactive).
     fn read_validator_set(&self) -> ValidatorSets<Self::Address>;
     /// Read PoS total voting power of all validators (active and inactive).
@@ -608,18 +832,40 @@ pub trait PosBase {
         key: &Self::Address,
         value: &ValidatorVotingPowers,
     );
+    /// Write PoS validator's commission rate.
+    fn write_validator_commission_rate(
+        &mut self,
+        key: &Self::Address,
+        value: &CommissionRates,
+    );
     /// Write (append) PoS slash applied to a validator.
     fn write_validator_slash(
         &mut self,
         validator: &Self::Address,
         value: Slash,
     );
+    /// Tombstone a PoS validator, which is permanent.
+    fn write_validator_tombstoned(&mut self, key: &Self::Address);
+    /// Write the heights of the blocks within the liveness window that a PoS
+    /// validator failed to sign.
+    fn write_validator_missed_blocks(
+        &mut self,
+        key: &Self::Address,
+        value: &MissedBlocks,
+    );
     /// Write PoS bond (validator self-bond or a delegation).
     fn write_bond(
         &mut self,
         key: &BondId<Self::Address>,
         value: &Bonds<Self::TokenAmount>,
     );
+    /// Write PoS unclaimed rewards of a bond (validator self-bond or a
+    /// delegation).
+    fn write_rewards(
+        &mut self,
+        key: &BondId<Self::Address>,
+        value: &Rewards<Self::TokenAmount>,
+    );
     /// Write PoS validator set (active and inactive).
     fn write_validator_set(&mut self, value: &ValidatorSets<Self::Address>);
     /// Read PoS total voting power of all validators (active and inactive).
@@ -631,7 +877,7 @@ pub trait PosBase {
         pk: &Self::PublicKey,
     );
     /// Credit tokens to the `target` account. This should only be used at
-    /// genesis.
+    /// genesis and for minting of the staking rewards.
     fn credit_tokens(
         &mut self,
         token: &Self::Address,
@@ -683,6 +929,7 @@ pub trait PosBase {
                 state,
                 total_deltas,
                 voting_power,
+                commission_rate,
                 bond: (bond_id, bond),
             } = res?;
             self.write_validator_address_raw_hash(address);
@@ -694,6 +941,7 @@ pub trait PosBase {
             self.write_validator_state(address, &state);
             self.write_validator_total_deltas(address, &total_deltas);
             self.write_validator_voting_power(address, &voting_power);
+            self.write_validator_commission_rate(address, &commission_rate);
             self.write_bond(&bond_id, &bond);
             self.init_staking_reward_account(
                 &staking_reward_address,
@@ -732,6 +980,26 @@ pub trait PosBase {
         let cur_validators = validators.get(current_epoch).unwrap();
         let prev_validators =
             previous_epoch.and_then(|epoch| validators.get(epoch));
+        let read_consensus_key = |address: &Self::Address, epoch: Epoch| {
+            self.read_validator_consensus_key(address)
+                .unwrap()
+                .get(epoch)
+                .unwrap()
+                .clone()
+        };
+        // The validators are deactivated with the consensus key that has been
+        // given to Tendermint, i.e. with their key from the previous epoch, in
+        // case it has been rotated in the current epoch. A new validator has
+        // no key before the epoch in which it's been added.
+        let deactivation_key = |address: &Self::Address| {
+            let consensus_keys =
+                self.read_validator_consensus_key(address).unwrap();
+            previous_epoch
+                .and_then(|prev_epoch| consensus_keys.get(prev_epoch))
+                .or_else(|| consensus_keys.get(current_epoch))
+                .unwrap()
+                .clone()
+        };
 
         // If the validator never been active before and it doesn't have more
         // than 0 voting power, we should not tell Tendermint to update it until
@@ -744,13 +1012,19 @@ pub trait PosBase {
         // still need to guard against it.
         let active_validators = cur_validators.active.iter().filter_map(
             |validator: &WeightedValidator<_>| {
+                let consensus_key =
+                    read_consensus_key(&validator.address, current_epoch);
                 // If the validators set from previous epoch contains the same
                 // validator, it means its voting power hasn't changed and hence
-                // doesn't need to updated.
+                // doesn't need to updated, unless its consensus key has been
+                // rotated.
                 if let (Some(prev_epoch), Some(prev_validators)) =
                     (previous_epoch, prev_validators)
                 {
-                    if prev_validators.active.contains(validator) {
+                    if prev_validators.active.contains(validator)
+                        && read_consensus_key(&validator.address, prev_epoch)
+                            == consensus_key
+                    {
                         println!(
                             "skipping validator update, still the same {}",
                             validator.address
@@ -776,18 +1050,35 @@ pub trait PosBase {
                         }
                     }
                 }
-                let consensus_key = self
-                    .read_validator_consensus_key(&validator.address)
-                    .unwrap()
-                    .get(current_epoch)
-                    .unwrap()
-                    .clone();
                 Some(ValidatorSetUpdate::Active(ActiveValidator {
                     consensus_key,
                     voting_power: validator.voting_power,
                 }))
             },
         );
+        // The validators that stay active with a rotated consensus key must
+        // have their previous key deactivated
+        let rotated_validators = prev_validators
+            .into_iter()
+            .flat_map(|prev_validators| prev_validators.active.iter())
+            .filter_map(|validator: &WeightedValidator<Self::Address>| {
+                let prev_epoch = previous_epoch?;
+                if !cur_validators
+                    .active
+                    .iter()
+                    .any(|cur| cur.address == validator.address)
+                {
+                    return None;
+                }
+                let prev_key =
+                    read_consensus_key(&validator.address, prev_epoch);
+                if prev_key
+                    == read_consensus_key(&validator.address, current_epoch)
+                {
+                    return None;
+                }
+                Some(ValidatorSetUpdate::Deactivated(prev_key))
+            });
         let inactive_validators = cur_validators.inactive.iter().filter_map(
             |validator: &WeightedValidator<Self::Address>| {
                 // If the validators set from previous epoch contains the same
@@ -814,19 +1105,39 @@ pub trait PosBase {
                         }
                     }
                 }
-                let consensus_key = self
-                    .read_validator_consensus_key(&validator.address)
-                    .unwrap()
-                    .get(current_epoch)
-                    .unwrap()
-                    .clone();
-                Some(ValidatorSetUpdate::Deactivated(consensus_key))
+                Some(ValidatorSetUpdate::Deactivated(deactivation_key(
+                    &validator.address,
+                )))
             },
         );
-        active_validators.chain(inactive_validators).for_each(f)
+        // Any previously active validators that have been removed from the
+        // validator set (i.e. jailed) must be deactivated too
+        let removed_validators = prev_validators
+            .into_iter()
+            .flat_map(|prev_validators| prev_validators.active.iter())
+            .filter_map(|validator: &WeightedValidator<Self::Address>| {
+                if cur_validators
+                    .active
+                    .iter()
+                    .chain(cur_validators.inactive.iter())
+                    .any(|cur| cur.address == validator.address)
+                {
+                    return None;
+                }
+                Some(ValidatorSetUpdate::Deactivated(deactivation_key(
+                    &validator.address,
+                )))
+            });
+        active_validators
+            .chain(rotated_validators)
+            .chain(inactive_validators)
+            .chain(removed_validators)
+            .for_each(f)
     }
 
-    /// Apply a slash to a byzantine validator for the given evidence.
+    /// Apply a slash to a byzantine validator for the given evidence. A
+    /// validator slashed for a duplicate vote is also tombstoned, if enabled
+    /// by the `tombstone_on_double_sign` parameter.
     fn slash(
         &mut self,
         params: &PosParams,
@@ -854,6 +1165,10 @@ pub trait PosBase {
             self.read_validator_voting_power(validator).ok_or_else(|| {
                 SlashError::ValidatorHasNoVotingPower(validator.clone())
             })?;
+        let mut validator_state =
+            self.read_validator_state(validator).ok_or_else(|| {
+                SlashError::ValidatorHasNoState(validator.clone())
+            })?;
         let mut validator_set = self.read_validator_set();
         let mut total_voting_power = self.read_total_voting_power();
 
@@ -871,10 +1186,23 @@ pub trait PosBase {
         let slashed_amount = u64::try_from(slashed_change)
             .map_err(|_err| SlashError::InvalidSlashChange(slashed_change))?;
         let slashed_amount = Self::TokenAmount::from(slashed_amount);
+        let tombstone = params.tombstone_on_double_sign
+            && matches!(validator_slash.r#type, SlashType::DuplicateVote);
+        jail(
+            params,
+            current_epoch,
+            validator,
+            &mut validator_state,
+            &mut validator_set,
+        );
+        if tombstone {
+            self.write_validator_tombstoned(validator);
+        }
 
         self.write_validator_total_deltas(validator, &total_deltas);
         self.write_validator_voting_power(validator, &voting_power);
         self.write_validator_slash(validator, validator_slash);
+        self.write_validator_state(validator, &validator_state);
         self.write_validator_set(&validator_set);
         self.write_total_voting_power(&total_voting_power);
         // Transfer the slashed tokens to the PoS slash pool
@@ -886,6 +1214,205 @@ pub trait PosBase {
         );
         Ok(())
     }
+
+    /// Record whether a validator signed the block at the given height. When
+    /// the validator has failed to sign more than
+    /// `liveness_max_missed_blocks` out of the last `liveness_window_len`
+    /// blocks, it's slashed for a liveness fault, which also jails it.
+    /// Validators that are already jailed are not tracked. Returns `true` if
+    /// the validator has been jailed.
+    fn record_validator_liveness(
+        &mut self,
+        params: &PosParams,
+        current_epoch: impl Into<Epoch>,
+        block_height: impl Into<u64>,
+        validator: &Self::Address,
+        signed: bool,
+    ) -> Result<bool, SlashError<Self::Address>> {
+        let current_epoch = current_epoch.into();
+        let block_height = block_height.into();
+        let is_jailed = self
+            .read_validator_state(validator)
+            .and_then(|state| {
+                state
+                    .get_at_offset(
+                        current_epoch,
+                        DynEpochOffset::PipelineLen,
+                        params,
+                    )
+                    .copied()
+            })
+            .map(|state| state == ValidatorState::Inactive)
+            .unwrap_or(true);
+        if is_jailed {
+            return Ok(false);
+        }
+
+        let mut missed_blocks = self.read_validator_missed_blocks(validator);
+        let prev_len = missed_blocks.len();
+        let window_start =
+            block_height.saturating_sub(params.liveness_window_len);
+        missed_blocks.retain(|height| *height > window_start);
+        if !signed {
+            missed_blocks.push(block_height);
+        }
+
+        if missed_blocks.len() as u64 > params.liveness_max_missed_blocks {
+            // The record starts afresh once the validator is jailed
+            self.write_validator_missed_blocks(validator, &MissedBlocks::new());
+            self.slash(
+                params,
+                current_epoch,
+                current_epoch,
+                block_height,
+                SlashType::Liveness,
+                validator,
+            )?;
+            return Ok(true);
+        }
+        if missed_blocks.len() != prev_len {
+            self.write_validator_missed_blocks(validator, &missed_blocks);
+        }
+        Ok(false)
+    }
+
+    /// Distribute the staking rewards of the active validators for the given
+    /// `epoch`, which must have already ended, with the given number of
+    /// blocks. Every active validator is rewarded `block_vote_reward` for
+    /// each block and a share of all the blocks' `block_proposer_reward`
+    /// pro-rata to its voting power, as the block proposers are selected in
+    /// proportion to their voting power. Each validator's reward is split
+    /// with [`split_reward`] and added to the unclaimed rewards of its bonds
+    /// with the validator's commission added to its self-bond. The rewards
+    /// are minted into the PoS account, from which they are transferred when
+    /// claimed. Returns the total amount of distributed rewards.
+    fn distribute_rewards(
+        &mut self,
+        params: &PosParams,
+        epoch: impl Into<Epoch>,
+        num_of_blocks: u64,
+    ) -> Self::TokenAmount {
+        let epoch = epoch.into();
+        let active_validators = match self.read_validator_set().get(epoch) {
+            Some(validator_set) => validator_set.active.clone(),
+            None => return Self::TokenAmount::default(),
+        };
+        let total_voting_power: u64 = active_validators
+            .iter()
+            .map(|validator| u64::from(validator.voting_power))
+            .sum();
+        if total_voting_power == 0 {
+            return Self::TokenAmount::default();
+        }
+
+        // Find the amounts of the bonds in the epoch for each validator
+        let mut validators_bonds: HashMap<
+            Self::Address,
+            Vec<(Self::Address, Self::TokenAmount)>,
+        > = HashMap::default();
+        for (bond_id, bonds) in self.read_bonds() {
+            let amount: Self::TokenAmount =
+                bonds.get(epoch).map(|bond| bond.sum()).unwrap_or_default();
+            if amount > Self::TokenAmount::default() {
+                validators_bonds
+                    .entry(bond_id.validator)
+                    .or_default()
+                    .push((bond_id.source, amount));
+            }
+        }
+
+        let proposer_rewards =
+            params.block_proposer_reward as u128 * num_of_blocks as u128;
+        let mut distributed: u64 = 0;
+        for WeightedValidator {
+            voting_power,
+            address,
+        } in active_validators
+        {
+            let voting_power: u64 = voting_power.into();
+            // This cannot overflow, because the validator's voting power is
+            // at most the total voting power
+            let proposer_reward = (proposer_rewards * voting_power as u128
+                / total_voting_power as u128)
+                as u64;
+            let reward =
+                params.block_vote_reward * num_of_blocks + proposer_reward;
+            let commission_rate = self
+                .read_validator_commission_rate(&address)
+                .and_then(|rates| rates.get(epoch).copied())
+                .unwrap_or_else(|| BasisPoints::new(0));
+            let bonds = validators_bonds.remove(&address).unwrap_or_default();
+            let (commission, bond_rewards) =
+                split_reward(reward.into(), commission_rate, &bonds);
+            for (source, amount) in
+                std::iter::once((address.clone(), commission))
+                    .chain(bond_rewards)
+            {
+                if amount == Self::TokenAmount::default() {
+                    continue;
+                }
+                let bond_id = BondId {
+                    source,
+                    validator: address.clone(),
+                };
+                let mut rewards =
+                    self.read_rewards(&bond_id).unwrap_or_default();
+                *rewards.entry(epoch).or_default() += amount;
+                self.write_rewards(&bond_id, &rewards);
+            }
+            distributed += reward;
+        }
+
+        let distributed = Self::TokenAmount::from(distributed);
+        self.credit_tokens(
+            &Self::staking_token_address(),
+            &Self::POS_ADDRESS,
+            distributed,
+        );
+        distributed
+    }
+}
+
+/// Split a validator's reward between the validator's commission and its
+/// bonds. The validator takes its commission first and the rest of the reward
+/// is split between the given bonds (validator self-bond and delegations)
+/// pro-rata to their amounts. Any remainder left over from the rounding is
+/// added to the commission. Returns the commission and the reward for each
+/// bond.
+pub fn split_reward<Address, TokenAmount>(
+    reward: TokenAmount,
+    commission_rate: BasisPoints,
+    bonds: &[(Address, TokenAmount)],
+) -> (TokenAmount, Vec<(Address, TokenAmount)>)
+where
+    Address: Clone,
+    TokenAmount: Copy + Into<u64> + From<u64>,
+{
+    let reward: u64 = reward.into();
+    let commission = commission_rate * reward;
+    let total_bonds: u128 = bonds
+        .iter()
+        .map(|(_, amount)| Into::<u64>::into(*amount) as u128)
+        .sum();
+    if total_bonds == 0 {
+        return (reward.into(), vec![]);
+    }
+    let to_distribute = (reward - commission) as u128;
+    let mut distributed: u64 = 0;
+    let bond_rewards = bonds
+        .iter()
+        .map(|(source, amount)| {
+            let amount: u64 = (*amount).into();
+            // This cannot overflow, because the result is at most
+            // `to_distribute`
+            let bond_reward =
+                (to_distribute * amount as u128 / total_bonds) as u64;
+            distributed += bond_reward;
+            (source.clone(), TokenAmount::from(bond_reward))
+        })
+        .collect();
+    let commission = reward - distributed;
+    (commission.into(), bond_rewards)
 }
 
 #[allow(missing_docs)]
@@ -905,6 +1432,8 @@ pub enum BecomeValidatorError<Address: Display + Debug> {
          address {0}"
     )]
     StakingRewardAddressEqValidatorAddress(Address),
+    #[error("The commission rate {0} must not be greater than 100%")]
+    InvalidCommissionRate(BasisPoints),
 }
 
 #[allow(missing_docs)]
@@ -923,6 +1452,11 @@ pub enum BondError<Address: Display + Debug> {
     VotingPowerOverflow(TryFromIntError),
     #[error("Given zero amount to unbond")]
     ZeroAmount,
+    #[error(
+        "The self-bond {1} of validator {0} would be below the minimum \
+         validator self-bond {2}"
+    )]
+    SelfBondBelowMinimum(Address, u64, u64),
 }
 
 #[allow(missing_docs)]
@@ -964,6 +1498,65 @@ where
     NoWithdrawableUnbond(BondId<Address>),
 }
 
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum ClaimRewardsError<Address: Display + Debug> {
+    #[error("The given address {0} is not a validator address")]
+    NotAValidator(Address),
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum UnjailError<Address: Display + Debug> {
+    #[error("The given address {0} is not a validator address")]
+    NotAValidator(Address),
+    #[error("The validator {0} is not jailed")]
+    NotJailed(Address),
+    #[error(
+        "The validator {0} has been tombstoned for double-signing and it can \
+         never be unjailed"
+    )]
+    Tombstoned(Address),
+    #[error(
+        "The jail duration of validator {0} is not over yet, it may be \
+         unjailed from epoch {1}"
+    )]
+    JailDurationNotOver(Address, Epoch),
+    #[error(
+        "The self-bond {1} of validator {0} is below the minimum validator \
+         self-bond {2}"
+    )]
+    SelfBondBelowMinimum(Address, u64, u64),
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum ConsensusKeyChangeError<Address: Display + Debug> {
+    #[error("The given address {0} is not a validator address")]
+    NotAValidator(Address),
+    #[error(
+        "The new consensus key of validator {0} is the same as its consensus \
+         key at the pipeline offset"
+    )]
+    KeyUnchanged(Address),
+    #[error("The consensus key is already used by validator {0}")]
+    KeyAlreadyUsed(Address),
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum CommissionRateChangeError<Address: Display + Debug> {
+    #[error("The given address {0} is not a validator address")]
+    NotAValidator(Address),
+    #[error("The commission rate {0} must not be greater than 100%")]
+    InvalidCommissionRate(BasisPoints),
+    #[error(
+        "The commission rate change {0} is greater than the maximum change \
+         per epoch {1}"
+    )]
+    RateChangeTooLarge(BasisPoints, BasisPoints),
+}
+
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
 pub enum SlashError<Address>
@@ -974,6 +1567,8 @@ where
     ValidatorHasNoTotalDeltas(Address),
     #[error("The validator {0} has no voting power")]
     ValidatorHasNoVotingPower(Address),
+    #[error("The validator {0} has no state")]
+    ValidatorHasNoState(Address),
     #[error("Unexpected slash token change")]
     InvalidSlashChange(i128),
     #[error("Voting power overflow: {0}")]
@@ -1055,6 +1650,7 @@ where
     state: ValidatorStates,
     total_deltas: ValidatorTotalDeltas<TokenChange>,
     voting_power: ValidatorVotingPowers,
+    commission_rate: CommissionRates,
     bond: (BondId<Address>, Bonds<TokenAmount>),
 }
 
@@ -1155,6 +1751,7 @@ where
                   tokens,
                   consensus_key,
                   staking_reward_key,
+                  commission_rate,
               }| {
             let consensus_key =
                 Epoched::init_at_genesis(consensus_key.clone(), current_epoch);
@@ -1178,6 +1775,8 @@ where
             deltas.insert(current_epoch, *tokens);
             let bond =
                 EpochedDelta::init_at_genesis(Bond { deltas }, current_epoch);
+            let commission_rate =
+                Epoched::init_at_genesis(*commission_rate, current_epoch);
             Ok(GenesisValidatorData {
                 address: address.clone(),
                 staking_reward_address: staking_reward_address.clone(),
@@ -1186,6 +1785,7 @@ where
                 state,
                 total_deltas,
                 voting_power,
+                commission_rate,
                 bond: (bond_id, bond),
             })
         },
@@ -1284,6 +1884,114 @@ where
     Ok(slashed_amount)
 }
 
+/// A function to jail a slashed validator. From the pipeline offset, the
+/// validator is inactive and it's removed from the validator set until it's
+/// unjailed.
+fn jail<Address>(
+    params: &PosParams,
+    current_epoch: Epoch,
+    validator: &Address,
+    validator_state: &mut ValidatorStates,
+    validator_set: &mut ValidatorSets<Address>,
+) where
+    Address: Display
+        + Debug
+        + Clone
+        + Ord
+        + Hash
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+{
+    validator_state.set(ValidatorState::Inactive, current_epoch, params);
+    validator_set.update_from_offset(
+        |validator_set, _epoch| {
+            let active_len = validator_set.active.len();
+            validator_set
+                .active
+                .retain(|active| &active.address != validator);
+            validator_set
+                .inactive
+                .retain(|inactive| &inactive.address != validator);
+            // Fill in the active slot vacated by the jailed validator
+            if validator_set.active.len() < active_len {
+                if let Some(activate_max) =
+                    validator_set.inactive.pop_last_shim()
+                {
+                    validator_set.active.insert(activate_max);
+                }
+            }
+        },
+        current_epoch,
+        DynEpochOffset::PipelineLen,
+        params,
+    );
+}
+
+/// A function to unjail a validator. From the pipeline offset, the validator
+/// is a candidate and it's inserted back into the validator set with its
+/// voting power.
+fn unjail<Address, TokenChange>(
+    params: &PosParams,
+    current_epoch: Epoch,
+    validator: &Address,
+    validator_state: &mut ValidatorStates,
+    validator_set: &mut ValidatorSets<Address>,
+    total_deltas: &ValidatorTotalDeltas<TokenChange>,
+) where
+    Address: Display
+        + Debug
+        + Clone
+        + Ord
+        + Hash
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+    TokenChange: Display
+        + Debug
+        + Default
+        + Clone
+        + Copy
+        + Add<Output = TokenChange>
+        + Into<i128>
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+{
+    validator_state.set(ValidatorState::Candidate, current_epoch, params);
+    validator_set.update_from_offset(
+        |validator_set, epoch| {
+            let tokens: i128 =
+                total_deltas.get(epoch).unwrap_or_default().into();
+            let tokens: u64 = TryFrom::try_from(tokens).unwrap_or_default();
+            let weighted_validator = WeightedValidator {
+                voting_power: VotingPower::from_tokens(tokens, params),
+                address: validator.clone(),
+            };
+            let min_voting_power = validator_set
+                .active
+                .first_shim()
+                .map(|v| v.voting_power)
+                .unwrap_or_default();
+            if validator_set.active.len() < params.max_validator_slots as usize
+            {
+                validator_set.active.insert(weighted_validator);
+            } else if weighted_validator.voting_power > min_voting_power {
+                let deactivate_min = validator_set.active.pop_first_shim();
+                validator_set.active.insert(weighted_validator);
+                if let Some(deactivate_min) = deactivate_min {
+                    validator_set.inactive.insert(deactivate_min);
+                }
+            } else {
+                validator_set.inactive.insert(weighted_validator);
+            }
+        },
+        current_epoch,
+        DynEpochOffset::PipelineLen,
+        params,
+    );
+}
+
 struct BecomeValidatorData<PK, TokenChange>
 where
     PK: Debug + Clone + BorshDeserialize + BorshSerialize + BorshSchema,
@@ -1300,6 +2008,7 @@ where
     state: ValidatorStates,
     total_deltas: ValidatorTotalDeltas<TokenChange>,
     voting_power: ValidatorVotingPowers,
+    commission_rate: CommissionRates,
 }
 
 /// A function that initialized data for a new validator.
@@ -1307,6 +2016,7 @@ fn become_validator<Address, PK, TokenChange>(
     params: &PosParams,
     address: &Address,
     consensus_key: &PK,
+    commission_rate: BasisPoints,
     validator_set: &mut ValidatorSets<Address>,
     current_epoch: Epoch,
 ) -> BecomeValidatorData<PK, TokenChange>
@@ -1347,6 +2057,8 @@ where
         DynEpochOffset::PipelineLen,
         params,
     );
+    let commission_rate =
+        Epoched::init_at_genesis(commission_rate, current_epoch);
 
     validator_set.update_from_offset(
         |validator_set, _epoch| {
@@ -1354,7 +2066,11 @@ where
                 voting_power: VotingPower::default(),
                 address: address.clone(),
             };
-            if validator_set.active.len() < params.max_validator_slots as usize
+            // A new validator has no self-bond yet, so it may only become
+            // active when there is no minimum self-bond required
+            if params.min_validator_self_bond == 0
+                && validator_set.active.len()
+                    < params.max_validator_slots as usize
             {
                 validator_set.active.insert(validator);
             } else {
@@ -1371,6 +2087,7 @@ where
         state,
         total_deltas,
         voting_power,
+        commission_rate,
     }
 }
 
@@ -1405,6 +2122,7 @@ fn bond_tokens<Address, TokenAmount, TokenChange>(
     validator_state: Option<ValidatorStates>,
     bond_id: &BondId<Address>,
     current_bond: Option<Bonds<TokenAmount>>,
+    self_bond: Option<&Bonds<TokenAmount>>,
     amount: TokenAmount,
     validator_total_deltas: Option<ValidatorTotalDeltas<TokenChange>>,
     validator_voting_power: Option<ValidatorVotingPowers>,
@@ -1475,6 +2193,25 @@ where
         }
     }
 
+    // Check that the validator's self-bond at the pipeline offset satisfies
+    // the minimum. Delegations may only be bonded to validators that satisfy
+    // it too.
+    let self_bond =
+        validation::bond_amount_at_pipeline(self_bond, current_epoch, params);
+    let self_bond = if bond_id.source == bond_id.validator {
+        self_bond + amount
+    } else {
+        self_bond
+    };
+    let self_bond: u64 = self_bond.into();
+    if self_bond < params.min_validator_self_bond {
+        return Err(BondError::SelfBondBelowMinimum(
+            bond_id.validator.clone(),
+            self_bond,
+            params.min_validator_self_bond,
+        ));
+    }
+
     let update_offset = DynEpochOffset::PipelineLen;
 
     // Update or create the bond
@@ -1796,7 +2533,18 @@ fn update_validator_set<Address, TokenChange>(
                     let min_voting_power = min_active_validator
                         .map(|v| v.voting_power)
                         .unwrap_or_default();
-                    if voting_power_post > min_voting_power {
+                    if voting_power_post > voting_power_pre
+                        && validator_set.active.len()
+                            < params.max_validator_slots as usize
+                    {
+                        // There's a free slot in the active set (e.g. when
+                        // the validator has been inactive until it satisfied
+                        // the minimum self-bond)
+                        let popped =
+                            validator_set.inactive.remove(&validator_pre);
+                        debug_assert!(popped);
+                        validator_set.active.insert(validator_post);
+                    } else if voting_power_post > min_voting_power {
                         let deactivate_min =
                             validator_set.active.pop_first_shim();
                         let popped =
@@ -1810,10 +2558,7 @@ fn update_validator_set<Address, TokenChange>(
                         validator_set.inactive.remove(&validator_pre);
                         validator_set.inactive.insert(validator_post);
                     }
-                } else {
-                    debug_assert!(
-                        validator_set.active.contains(&validator_pre)
-                    );
+                } else if validator_set.active.contains(&validator_pre) {
                     let max_inactive_validator =
                         validator_set.inactive.last_shim();
                     let max_voting_power = max_inactive_validator
@@ -1834,6 +2579,8 @@ fn update_validator_set<Address, TokenChange>(
                         validator_set.active.insert(validator_post);
                     }
                 }
+                // Otherwise, the validator is jailed and it's not in the
+                // validator set
             }
         },
         current_epoch,
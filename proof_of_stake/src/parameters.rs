@@ -31,6 +31,36 @@ pub struct PosParams {
     /// Portion of validator's stake that should be slashed on a light client
     /// attack. Given in basis points (slashed amount per ten thousand tokens).
     pub light_client_attack_slash_rate: BasisPoints,
+    /// How many epochs after a committed fault a slashed validator is jailed
+    /// for. A jailed validator is removed from the validator set and it
+    /// cannot receive new bonds until it's unjailed.
+    pub jail_duration: u64,
+    /// Minimum amount of tokens that a validator must have self-bonded to be
+    /// able to receive bonds and to become an active validator. Given in the
+    /// smallest denomination of the staking token.
+    pub min_validator_self_bond: u64,
+    /// Maximum total amount of tokens bonded to a single validator, counting
+    /// both its self-bonds and delegations, if any. Given in the smallest
+    /// denomination of the staking token.
+    pub max_validator_stake: Option<u64>,
+    /// Maximum change of a validator's commission rate from one epoch to the
+    /// next. Given in basis points (a portion of the validator's rewards per
+    /// ten thousand tokens).
+    pub max_commission_rate_change: BasisPoints,
+    /// The number of the most recent blocks over which validators' liveness
+    /// is tracked.
+    pub liveness_window_len: u64,
+    /// Maximum number of blocks within the liveness window that a validator
+    /// may fail to sign. A validator that misses more blocks is jailed.
+    pub liveness_max_missed_blocks: u64,
+    /// Portion of validator's stake that should be slashed when it's jailed
+    /// for missing too many blocks. Given in basis points (slashed amount
+    /// per ten thousand tokens).
+    pub liveness_slash_rate: BasisPoints,
+    /// Whether a validator slashed for a duplicate vote is tombstoned. A
+    /// tombstoned validator stays jailed permanently and it can never be
+    /// unjailed, but its bonds can still be unbonded and withdrawn.
+    pub tombstone_on_double_sign: bool,
 }
 
 impl Default for PosParams {
@@ -47,6 +77,22 @@ impl Default for PosParams {
             duplicate_vote_slash_rate: BasisPoints::new(500),
             // slash 5%
             light_client_attack_slash_rate: BasisPoints::new(500),
+            // jail for 6 epochs
+            jail_duration: 6,
+            // no minimum self-bond
+            min_validator_self_bond: 0,
+            // no maximum stake
+            max_validator_stake: None,
+            // 1% change per epoch
+            max_commission_rate_change: BasisPoints::new(100),
+            // track the last 1000 blocks
+            liveness_window_len: 1000,
+            // jail when more than half of the blocks are missed
+            liveness_max_missed_blocks: 500,
+            // slash 0.01%
+            liveness_slash_rate: BasisPoints::new(1),
+            // double-signing validators cannot be unjailed
+            tombstone_on_double_sign: true,
         }
     }
 }
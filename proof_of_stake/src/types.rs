@@ -1,7 +1,7 @@
 //! Proof of Stake data types
 
 use core::fmt::Debug;
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::TryFrom;
 use std::fmt::Display;
 use std::hash::Hash;
@@ -26,12 +26,17 @@ pub type ValidatorTotalDeltas<TokenChange> =
 /// Epoched validator's voting power.
 pub type ValidatorVotingPowers =
     EpochedDelta<VotingPowerDelta, OffsetUnboundingLen>;
+/// Epoched validator's commission rate.
+pub type CommissionRates = Epoched<BasisPoints, OffsetPipelineLen>;
 /// Epoched bond.
 pub type Bonds<TokenAmount> =
     EpochedDelta<Bond<TokenAmount>, OffsetPipelineLen>;
 /// Epoched unbond.
 pub type Unbonds<TokenAmount> =
     EpochedDelta<Unbond<TokenAmount>, OffsetUnboundingLen>;
+/// Unclaimed staking rewards of a bond, keyed by the epoch in which they have
+/// been earned.
+pub type Rewards<TokenAmount> = BTreeMap<Epoch, TokenAmount>;
 /// Epoched validator set.
 pub type ValidatorSets<Address> =
     Epoched<ValidatorSet<Address>, OffsetUnboundingLen>;
@@ -118,6 +123,9 @@ pub struct GenesisValidator<Address, Token, PK> {
     pub consensus_key: PK,
     /// An public key associated with the staking reward address
     pub staking_reward_key: PK,
+    /// A portion of the validator's rewards that the validator takes before
+    /// the rest is distributed to the bonds
+    pub commission_rate: BasisPoints,
 }
 
 /// An update of the active and inactive validator set.
@@ -328,6 +336,10 @@ pub struct Slash {
 /// their staked tokens at and before the epoch of the slash.
 pub type Slashes = Vec<Slash>;
 
+/// Heights of the blocks within the liveness window that a validator failed
+/// to sign, in ascending order.
+pub type MissedBlocks = Vec<u64>;
+
 /// A type of slashsable event.
 #[derive(Debug, Clone, BorshDeserialize, BorshSerialize, BorshSchema)]
 pub enum SlashType {
@@ -335,11 +347,24 @@ pub enum SlashType {
     DuplicateVote,
     /// Light client attack.
     LightClientAttack,
+    /// Missing too many blocks within the liveness window.
+    Liveness,
 }
 
 /// ‱ (Parts per ten thousand). This can be multiplied by any type that
 /// implements [`Into<u64>`] or [`Into<i128>`].
-#[derive(Debug, Clone, Copy, BorshDeserialize, BorshSerialize, BorshSchema)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    BorshDeserialize,
+    BorshSerialize,
+    BorshSchema,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
 pub struct BasisPoints(u64);
 
 impl VotingPower {
@@ -680,6 +705,7 @@ impl SlashType {
             SlashType::LightClientAttack => {
                 params.light_client_attack_slash_rate
             }
+            SlashType::Liveness => params.liveness_slash_rate,
         }
     }
 }
@@ -689,15 +715,30 @@ impl Display for SlashType {
         match self {
             SlashType::DuplicateVote => write!(f, "Duplicate vote"),
             SlashType::LightClientAttack => write!(f, "Light client attack"),
+            SlashType::Liveness => write!(f, "Liveness fault"),
         }
     }
 }
 
 impl BasisPoints {
+    /// A hundred percent, i.e. ten thousand basis points.
+    pub const HUNDRED_PERCENT: Self = Self(10_000);
+
     /// Initialize basis points from an integer.
     pub fn new(value: u64) -> Self {
         Self(value)
     }
+
+    /// Find the absolute difference from the other basis points.
+    pub fn abs_diff(&self, other: &Self) -> Self {
+        Self(self.0.max(other.0) - self.0.min(other.0))
+    }
+}
+
+impl From<BasisPoints> for u64 {
+    fn from(value: BasisPoints) -> Self {
+        value.0
+    }
 }
 
 impl Display for BasisPoints {
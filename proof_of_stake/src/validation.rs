@@ -1,7 +1,7 @@
 //! Validation of updated PoS data
 
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
@@ -14,11 +14,12 @@ use crate::btree_set::BTreeSetShims;
 use crate::epoched::DynEpochOffset;
 use crate::parameters::PosParams;
 use crate::types::{
-    BondId, Bonds, Epoch, Slashes, TotalVotingPowers, Unbonds,
-    ValidatorConsensusKeys, ValidatorSets, ValidatorState, ValidatorStates,
-    ValidatorTotalDeltas, ValidatorVotingPowers, VotingPower, VotingPowerDelta,
-    WeightedValidator,
+    BasisPoints, BondId, Bonds, CommissionRates, Epoch, Rewards, Slash,
+    Slashes, TotalVotingPowers, Unbonds, ValidatorConsensusKeys, ValidatorSets,
+    ValidatorState, ValidatorStates, ValidatorTotalDeltas,
+    ValidatorVotingPowers, VotingPower, VotingPowerDelta, WeightedValidator,
 };
+use crate::{ConsensusKeyChangeError, UnjailError};
 
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
@@ -54,6 +55,19 @@ where
          address {0}"
     )]
     StakingRewardAddressEqValidator(Address),
+    #[error("Unexpectedly missing commission rate value for validator {0}")]
+    ValidatorCommissionRateIsRequired(Address),
+    #[error("Missing new validator commission rate in epoch {0}")]
+    MissingNewValidatorCommissionRate(u64),
+    #[error("Invalid validator commission rate update in epoch {0}")]
+    InvalidValidatorCommissionRateUpdate(u64),
+    #[error("Validator {0} commission rate {1} is greater than 100%")]
+    InvalidCommissionRate(Address, BasisPoints),
+    #[error(
+        "Validator {0} commission rate change {1} is greater than the maximum \
+         change per epoch {2}"
+    )]
+    CommissionRateChangeTooLarge(Address, BasisPoints, BasisPoints),
     #[error("Unexpectedly missing total deltas value for validator {0}")]
     MissingValidatorTotalDeltas(Address),
     #[error("The sum of total deltas for validator {0} are negative")]
@@ -64,12 +78,13 @@ where
     InvalidLastUpdate,
     #[error(
         "Invalid staking token balances. Balance Δ {balance_delta}, bonds Δ \
-         {bond_delta}, unbonds Δ {unbond_delta}"
+         {bond_delta}, unbonds Δ {unbond_delta}, rewards Δ {rewards_delta}"
     )]
     InvalidBalances {
         balance_delta: TokenChange,
         bond_delta: TokenChange,
         unbond_delta: TokenChange,
+        rewards_delta: TokenChange,
     },
     #[error(
         "Data must be set or updated in the correct epoch. Got epoch {got}, \
@@ -78,6 +93,8 @@ where
     EpochedDataWrongEpoch { got: u64, expected: Vec<u64> },
     #[error("Empty bond {0} must be deleted")]
     EmptyBond(BondId<Address>),
+    #[error("Rewards of bond {0} may only be claimed")]
+    InvalidRewardsUpdate(BondId<Address>),
     #[error(
         "Bond ID {id} must start at the correct epoch. Got epoch {got}, \
          expected {expected}"
@@ -127,6 +144,16 @@ where
     InvalidActiveValidator(WeightedValidator<Address>),
     #[error("Invalid inactive validator {0}")]
     InvalidInactiveValidator(WeightedValidator<Address>),
+    #[error(
+        "Validator {0} self-bond {1} is below the minimum validator self-bond \
+         {2}"
+    )]
+    SelfBondBelowMinimum(Address, u64, u64),
+    #[error(
+        "Validator {0} stake {1} at the pipeline epoch is above the maximum \
+         validator stake {2}"
+    )]
+    StakeAboveMaximum(Address, i128, u64),
     #[error("Unexpectedly missing voting power value for validator {0}")]
     MissingValidatorVotingPower(Address),
     #[error("Validator {0} has an invalid voting power value {1}")]
@@ -226,6 +253,13 @@ where
         /// List of slashes applied to the bond's validator
         slashes: Slashes,
     },
+    /// Unclaimed rewards update
+    Reward {
+        /// Bond ID
+        id: BondId<Address>,
+        /// Rewards prior and posterior state
+        data: Data<Rewards<TokenAmount>>,
+    },
     /// A validator update
     Validator {
         /// Validator's address
@@ -271,6 +305,8 @@ where
     ConsensusKey(Data<ValidatorConsensusKeys<PublicKey>>),
     /// Staking reward address update
     StakingRewardAddress(Data<Address>),
+    /// Commission rate update
+    CommissionRate(Data<CommissionRates>),
     /// Total deltas update
     TotalDeltas(Data<ValidatorTotalDeltas<TokenChange>>),
     /// Voting power update
@@ -298,10 +334,177 @@ pub struct NewValidator {
     has_total_deltas: bool,
     has_voting_power: bool,
     has_staking_reward_address: bool,
+    has_commission_rate: bool,
     has_address_raw_hash: bool,
     voting_power: VotingPower,
 }
 
+/// Find the total amount of the given bond at the pipeline offset from the
+/// current epoch.
+pub fn bond_amount_at_pipeline<TokenAmount>(
+    bond: Option<&Bonds<TokenAmount>>,
+    current_epoch: impl Into<Epoch>,
+    params: &PosParams,
+) -> TokenAmount
+where
+    TokenAmount: Debug
+        + Default
+        + Clone
+        + Copy
+        + Add<Output = TokenAmount>
+        + AddAssign
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+{
+    bond.and_then(|bond| {
+        bond.get_at_offset(current_epoch, DynEpochOffset::PipelineLen, params)
+    })
+    .map(|bond| bond.sum())
+    .unwrap_or_default()
+}
+
+/// Check that a validator may be unjailed in the current epoch. The validator
+/// must not be tombstoned, it must be jailed, i.e. inactive in the current
+/// epoch and at the pipeline offset, the jail duration since its last fault
+/// must be over and its self-bond at the pipeline offset must satisfy the
+/// minimum validator self-bond.
+pub fn check_unjail<Address, TokenAmount>(
+    params: &PosParams,
+    current_epoch: impl Into<Epoch>,
+    validator: &Address,
+    validator_state: &ValidatorStates,
+    slashes: &[Slash],
+    is_tombstoned: bool,
+    self_bond: Option<&Bonds<TokenAmount>>,
+) -> Result<(), UnjailError<Address>>
+where
+    Address: Display + Debug + Clone,
+    TokenAmount: Debug
+        + Default
+        + Clone
+        + Copy
+        + Add<Output = TokenAmount>
+        + AddAssign
+        + Into<u64>
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+{
+    let current_epoch = current_epoch.into();
+    if is_tombstoned {
+        return Err(UnjailError::Tombstoned(validator.clone()));
+    }
+    let is_jailed = |state: Option<&ValidatorState>| {
+        matches!(state, Some(ValidatorState::Inactive))
+    };
+    if !is_jailed(validator_state.get(current_epoch))
+        || !is_jailed(validator_state.get_at_offset(
+            current_epoch,
+            DynEpochOffset::PipelineLen,
+            params,
+        ))
+    {
+        return Err(UnjailError::NotJailed(validator.clone()));
+    }
+    let last_fault_epoch = slashes
+        .iter()
+        .map(|slash| slash.epoch)
+        .max()
+        .ok_or_else(|| UnjailError::NotJailed(validator.clone()))?;
+    let jail_end = last_fault_epoch + params.jail_duration;
+    if jail_end > current_epoch {
+        return Err(UnjailError::JailDurationNotOver(
+            validator.clone(),
+            jail_end,
+        ));
+    }
+    let self_bond: u64 =
+        bond_amount_at_pipeline(self_bond, current_epoch, params).into();
+    if self_bond < params.min_validator_self_bond {
+        return Err(UnjailError::SelfBondBelowMinimum(
+            validator.clone(),
+            self_bond,
+            params.min_validator_self_bond,
+        ));
+    }
+    Ok(())
+}
+
+/// Check that a validator's consensus key may be rotated to the new key at
+/// the pipeline offset from the current epoch. The new key must differ from
+/// the validator's key at the pipeline offset and it must not be used at the
+/// pipeline offset by any other validator in the validator set, whose keys
+/// are read with `read_consensus_keys`.
+pub fn check_consensus_key_change<Address, PublicKey>(
+    params: &PosParams,
+    current_epoch: impl Into<Epoch>,
+    validator: &Address,
+    new_key: &PublicKey,
+    consensus_keys: &ValidatorConsensusKeys<PublicKey>,
+    validator_sets: &ValidatorSets<Address>,
+    read_consensus_keys: impl Fn(
+        &Address,
+    ) -> Option<ValidatorConsensusKeys<PublicKey>>,
+) -> Result<(), ConsensusKeyChangeError<Address>>
+where
+    Address: Display
+        + Debug
+        + Clone
+        + PartialEq
+        + Eq
+        + PartialOrd
+        + Ord
+        + Hash
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+    PublicKey: Debug
+        + Clone
+        + PartialEq
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+{
+    let pipeline_epoch = current_epoch.into() + params.pipeline_len;
+    if consensus_keys.get(pipeline_epoch) == Some(new_key) {
+        return Err(ConsensusKeyChangeError::KeyUnchanged(validator.clone()));
+    }
+    if let Some(validator_set) = validator_sets.get(pipeline_epoch) {
+        for other in validator_set
+            .active
+            .iter()
+            .chain(validator_set.inactive.iter())
+        {
+            if &other.address == validator {
+                continue;
+            }
+            let other_key = read_consensus_keys(&other.address)
+                .and_then(|keys| keys.get(pipeline_epoch).cloned());
+            if other_key.as_ref() == Some(new_key) {
+                return Err(ConsensusKeyChangeError::KeyAlreadyUsed(
+                    other.address.clone(),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Find the validator's commission rate in the epoch before the pipeline
+/// offset from the current epoch. A commission rate change set at the
+/// pipeline offset is limited relative to this rate.
+pub fn commission_rate_before_pipeline(
+    commission_rates: &CommissionRates,
+    current_epoch: impl Into<Epoch>,
+    params: &PosParams,
+) -> Option<BasisPoints> {
+    let pipeline_epoch = current_epoch.into() + params.pipeline_len;
+    commission_rates
+        .get(pipeline_epoch.sub_or_default(Epoch::from(1)))
+        .copied()
+}
+
 /// Validate the given list of PoS data `changes`. Returns empty list, if all
 /// the changes are valid.
 pub fn validate<Address, TokenAmount, TokenChange, PublicKey>(
@@ -379,6 +582,8 @@ where
     let mut bond_delta: HashMap<Address, TokenChange> = HashMap::default();
     // Changes of validators' unbonds
     let mut unbond_delta: HashMap<Address, TokenChange> = HashMap::default();
+    // Changes of unclaimed rewards
+    let mut rewards_delta = TokenChange::default();
 
     // Changes of all validator total deltas (up to `unbonding_epoch`)
     let mut total_deltas: HashMap<Address, TokenChange> = HashMap::default();
@@ -413,6 +618,70 @@ where
     > = HashMap::default();
 
     let mut new_validators: HashMap<Address, NewValidator> = HashMap::default();
+    // Jailed validators that become candidates again at the pipeline epoch
+    let mut unjailed_validators: HashSet<Address> = HashSet::default();
+
+    // Check that any increased self-bond satisfies the minimum validator
+    // self-bond at the pipeline offset
+    for change in &changes {
+        if let Bond { id, data, .. } = change {
+            if id.source != id.validator {
+                continue;
+            }
+            let self_bond_pre: u64 = bond_amount_at_pipeline(
+                data.pre.as_ref(),
+                current_epoch,
+                params,
+            )
+            .into();
+            let self_bond_post: u64 = bond_amount_at_pipeline(
+                data.post.as_ref(),
+                current_epoch,
+                params,
+            )
+            .into();
+            if self_bond_post > self_bond_pre
+                && self_bond_post < params.min_validator_self_bond
+            {
+                errors.push(Error::SelfBondBelowMinimum(
+                    id.validator.clone(),
+                    self_bond_post,
+                    params.min_validator_self_bond,
+                ))
+            }
+        }
+    }
+
+    // Check that any increased validator stake doesn't exceed the maximum
+    // validator stake at the pipeline offset
+    if let Some(max_validator_stake) = params.max_validator_stake {
+        for change in &changes {
+            if let Validator {
+                address,
+                update: TotalDeltas(data),
+            } = change
+            {
+                let stake_at_pipeline =
+                    |deltas: Option<&ValidatorTotalDeltas<TokenChange>>| {
+                        deltas
+                            .and_then(|deltas| deltas.get(pipeline_epoch))
+                            .map(Into::<i128>::into)
+                            .unwrap_or_default()
+                    };
+                let stake_pre = stake_at_pipeline(data.pre.as_ref());
+                let stake_post = stake_at_pipeline(data.post.as_ref());
+                if stake_post > stake_pre
+                    && stake_post > max_validator_stake as i128
+                {
+                    errors.push(Error::StakeAboveMaximum(
+                        address.clone(),
+                        stake_post,
+                        max_validator_stake,
+                    ))
+                }
+            }
+        }
+    }
 
     for change in changes {
         match change {
@@ -472,15 +741,15 @@ where
                             pre.get(pipeline_epoch),
                             post.get(pipeline_epoch),
                         ) {
+                            (Some(Inactive), Some(Candidate)) => {
+                                unjailed_validators.insert(address.clone());
+                            }
                             (
                                 Some(Pending),
                                 Some(Candidate) | Some(Inactive),
                             )
                             | (Some(Candidate), Some(Inactive))
-                            | (
-                                Some(Inactive),
-                                Some(Candidate) | Some(Pending),
-                            ) => {}
+                            | (Some(Inactive), Some(Pending)) => {}
                             _ => errors.push(Error::InvalidNewValidatorState(
                                 pipeline_epoch.into(),
                             )),
@@ -512,7 +781,9 @@ where
                         if post.last_update() != current_epoch {
                             errors.push(Error::InvalidLastUpdate)
                         }
-                        // Before pipeline epoch, the key must not change
+                        // Before pipeline epoch, the key must not change. A
+                        // new validator has no key before the pipeline epoch
+                        // of its initialization.
                         for epoch in
                             Epoch::iter_range(current_epoch, pipeline_offset)
                         {
@@ -522,6 +793,7 @@ where
                                 {
                                     continue;
                                 }
+                                (None, None) => continue,
                                 _ => errors.push(
                                     Error::InvalidValidatorConsensusKeyUpdate(
                                         epoch.into(),
@@ -560,6 +832,95 @@ where
                         address.clone(),
                     )),
                 },
+                CommissionRate(data) => match (data.pre, data.post) {
+                    (None, Some(post)) => {
+                        if post.last_update() != current_epoch {
+                            errors.push(Error::InvalidLastUpdate)
+                        }
+                        // The value must be known at pipeline epoch
+                        match post.get(pipeline_epoch) {
+                            Some(rate)
+                                if *rate > BasisPoints::HUNDRED_PERCENT =>
+                            {
+                                errors.push(Error::InvalidCommissionRate(
+                                    address.clone(),
+                                    *rate,
+                                ))
+                            }
+                            Some(_) => {}
+                            None => errors.push(
+                                Error::MissingNewValidatorCommissionRate(
+                                    pipeline_epoch.into(),
+                                ),
+                            ),
+                        }
+                        let validator =
+                            new_validators.entry(address.clone()).or_default();
+                        validator.has_commission_rate = true;
+                    }
+                    (Some(pre), Some(post)) => {
+                        if post.last_update() != current_epoch {
+                            errors.push(Error::InvalidLastUpdate)
+                        }
+                        // Before pipeline epoch, the rate must not change
+                        for epoch in
+                            Epoch::iter_range(current_epoch, pipeline_offset)
+                        {
+                            match (pre.get(epoch), post.get(epoch)) {
+                                (Some(rate_pre), Some(rate_post))
+                                    if rate_pre == rate_post =>
+                                {
+                                    continue;
+                                }
+                                _ => errors.push(
+                                    Error::InvalidValidatorCommissionRateUpdate(
+                                        epoch.into(),
+                                    ),
+                                ),
+                            }
+                        }
+                        // At pipeline epoch, the rate may only change by up
+                        // to the maximum change from the epoch before it
+                        let rate_before = commission_rate_before_pipeline(
+                            &pre,
+                            current_epoch,
+                            params,
+                        );
+                        match (rate_before, post.get(pipeline_epoch)) {
+                            (_, Some(rate))
+                                if *rate > BasisPoints::HUNDRED_PERCENT =>
+                            {
+                                errors.push(Error::InvalidCommissionRate(
+                                    address.clone(),
+                                    *rate,
+                                ))
+                            }
+                            (Some(rate_before), Some(rate)) => {
+                                let change = rate.abs_diff(&rate_before);
+                                if change > params.max_commission_rate_change {
+                                    errors.push(
+                                        Error::CommissionRateChangeTooLarge(
+                                            address.clone(),
+                                            change,
+                                            params.max_commission_rate_change,
+                                        ),
+                                    )
+                                }
+                            }
+                            _ => errors.push(
+                                Error::InvalidValidatorCommissionRateUpdate(
+                                    pipeline_epoch.into(),
+                                ),
+                            ),
+                        }
+                    }
+                    (Some(_), None) => {
+                        errors.push(Error::ValidatorCommissionRateIsRequired(
+                            address.clone(),
+                        ))
+                    }
+                    (None, None) => continue,
+                },
                 TotalDeltas(data) => match (data.pre, data.post) {
                     (Some(pre), Some(post)) => {
                         if post.last_update() != current_epoch {
@@ -1171,6 +1532,17 @@ where
                 }
                 (None, None) => continue,
             },
+            Reward { id, data } => match (data.pre, data.post) {
+                // Rewards may only be deleted when they are claimed, they are
+                // distributed by the protocol outside of transactions
+                (Some(pre), None) => {
+                    for amount in pre.values() {
+                        rewards_delta -= TokenChange::from(*amount);
+                    }
+                }
+                (None, None) => continue,
+                _ => errors.push(Error::InvalidRewardsUpdate(id)),
+            },
             ValidatorSet(data) => match (data.pre, data.post) {
                 (Some(pre), Some(post)) => {
                     if post.last_update() != current_epoch {
@@ -1317,8 +1689,12 @@ where
                                     }
                                 }
                                 None => {
-                                    // Others must be the same as in pre
-                                    if !pre.active.contains(validator) {
+                                    // Others must be the same as in pre,
+                                    // unless they've been unjailed
+                                    if !pre.active.contains(validator)
+                                        && !unjailed_validators
+                                            .contains(&validator.address)
+                                    {
                                         errors.push(
                                             Error::InvalidActiveValidator(
                                                 validator.clone(),
@@ -1346,8 +1722,14 @@ where
                                     }
                                 }
                                 None => {
-                                    // Others must be the same as in pre
-                                    if !pre.active.contains(validator) {
+                                    // Others must be the same as in pre, but
+                                    // they may have been moved from the
+                                    // active set or unjailed
+                                    if !pre.inactive.contains(validator)
+                                        && !pre.active.contains(validator)
+                                        && !unjailed_validators
+                                            .contains(&validator.address)
+                                    {
                                         errors.push(
                                             Error::InvalidInactiveValidator(
                                                 validator.clone(),
@@ -1505,6 +1887,7 @@ where
                         has_total_deltas,
                         has_voting_power,
                         has_staking_reward_address,
+                        has_commission_rate,
                         has_address_raw_hash,
                         voting_power,
                     } = &new_validator;
@@ -1514,6 +1897,7 @@ where
                         && *has_total_deltas
                         && *has_voting_power
                         && *has_staking_reward_address
+                        && *has_commission_rate
                         && *has_address_raw_hash)
                     {
                         errors.push(Error::InvalidNewValidator(
@@ -1554,13 +1938,185 @@ where
         .into_iter()
         .fold(TokenChange::default(), |acc, delta| acc + (*delta));
 
-    if balance_delta != bond_delta + unbond_delta {
+    if balance_delta != bond_delta + unbond_delta + rewards_delta {
         errors.push(Error::InvalidBalances {
             balance_delta,
             bond_delta,
             unbond_delta,
+            rewards_delta,
         })
     }
 
     errors
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epoched::Epoched;
+    use crate::types::{Bond, SlashType, ValidatorSet};
+
+    /// Test that a jailed validator may only be unjailed once its jail
+    /// duration is over, with its self-bond satisfying the minimum and if
+    /// it's not tombstoned
+    #[test]
+    fn test_check_unjail() {
+        let params = PosParams {
+            min_validator_self_bond: 100,
+            ..Default::default()
+        };
+        let validator = "validator".to_owned();
+        let self_bond = |amount: u64| {
+            Bonds::<u64>::init_at_genesis(
+                Bond {
+                    deltas: HashMap::from([(Epoch(0), amount)]),
+                },
+                Epoch(0),
+            )
+        };
+        // The validator is jailed in epoch 0 for a fault in epoch 0, it's
+        // inactive from the pipeline epoch
+        let mut state = ValidatorStates::init_at_genesis(
+            ValidatorState::Candidate,
+            Epoch(0),
+        );
+        state.set(ValidatorState::Inactive, Epoch(0), &params);
+        let slashes = vec![Slash {
+            epoch: Epoch(0),
+            block_height: 1,
+            r#type: SlashType::DuplicateVote,
+            rate: BasisPoints::new(100),
+        }];
+        let jail_end = Epoch(params.jail_duration);
+
+        // Still active in the current epoch
+        assert!(matches!(
+            check_unjail(
+                &params,
+                Epoch(0),
+                &validator,
+                &state,
+                &slashes,
+                false,
+                Some(&self_bond(100))
+            ),
+            Err(UnjailError::NotJailed(_))
+        ));
+        // Inactive, but before the jail duration is over
+        assert!(matches!(
+            check_unjail(
+                &params,
+                params.pipeline_len,
+                &validator,
+                &state,
+                &slashes,
+                false,
+                Some(&self_bond(100))
+            ),
+            Err(UnjailError::JailDurationNotOver(_, epoch)) if epoch == jail_end
+        ));
+        // The jail duration is over, but the self-bond is below the minimum
+        assert!(matches!(
+            check_unjail(
+                &params,
+                jail_end,
+                &validator,
+                &state,
+                &slashes,
+                false,
+                Some(&self_bond(99))
+            ),
+            Err(UnjailError::SelfBondBelowMinimum(_, 99, 100))
+        ));
+        assert!(check_unjail(
+            &params,
+            jail_end,
+            &validator,
+            &state,
+            &slashes,
+            false,
+            Some(&self_bond(100))
+        )
+        .is_ok());
+        // An inactive validator without any fault is not jailed
+        assert!(matches!(
+            check_unjail(
+                &params,
+                jail_end,
+                &validator,
+                &state,
+                &[],
+                false,
+                Some(&self_bond(100))
+            ),
+            Err(UnjailError::NotJailed(_))
+        ));
+        // A tombstoned validator can never be unjailed
+        assert!(matches!(
+            check_unjail(
+                &params,
+                jail_end + 1000,
+                &validator,
+                &state,
+                &slashes,
+                true,
+                Some(&self_bond(100))
+            ),
+            Err(UnjailError::Tombstoned(_))
+        ));
+    }
+
+    /// Test that a validator's consensus key may only be rotated to a new key
+    /// that isn't used by another validator
+    #[test]
+    fn test_check_consensus_key_change() {
+        let params = PosParams::default();
+        let validator = "validator".to_owned();
+        let other = "other".to_owned();
+        let keys: HashMap<String, ValidatorConsensusKeys<String>> = [
+            (
+                validator.clone(),
+                Epoched::init_at_genesis("key-1".to_owned(), Epoch(0)),
+            ),
+            (
+                other.clone(),
+                Epoched::init_at_genesis("key-2".to_owned(), Epoch(0)),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let validator_set = ValidatorSet {
+            active: [&validator, &other]
+                .into_iter()
+                .map(|address| WeightedValidator {
+                    voting_power: 1.into(),
+                    address: address.clone(),
+                })
+                .collect(),
+            inactive: Default::default(),
+        };
+        let validator_sets = Epoched::init_at_genesis(validator_set, Epoch(0));
+        let check = |new_key: &str| {
+            check_consensus_key_change(
+                &params,
+                Epoch(0),
+                &validator,
+                &new_key.to_owned(),
+                &keys[&validator],
+                &validator_sets,
+                |address| keys.get(address).cloned(),
+            )
+        };
+
+        assert!(matches!(
+            check("key-1"),
+            Err(ConsensusKeyChangeError::KeyUnchanged(_))
+        ));
+        assert!(matches!(
+            check("key-2"),
+            Err(ConsensusKeyChangeError::KeyAlreadyUsed(address))
+                if address == other
+        ));
+        assert!(check("key-3").is_ok());
+    }
+}
@@ -2,8 +2,10 @@
 //! validity predicates triggered by transactions.
 
 use std::convert::TryFrom;
+use std::fmt::Display;
 
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[allow(missing_docs)]
@@ -23,8 +25,15 @@ const PARALLEL_GAS_DIVIDER: u64 = 10;
 
 /// The maximum value should be less or equal to i64::MAX
 /// to avoid the gas overflow when sending this to ABCI
-const BLOCK_GAS_LIMIT: u64 = 10_000_000_000_000;
-const TRANSACTION_GAS_LIMIT: u64 = 10_000_000_000;
+pub const BLOCK_GAS_LIMIT: u64 = 10_000_000_000_000;
+/// The default gas limit of a single transaction
+pub const TRANSACTION_GAS_LIMIT: u64 = 10_000_000_000;
+
+/// The gas used in a block that the base fee adjustment targets, which is a
+/// half of the block gas limit
+pub const BLOCK_GAS_TARGET: u64 = BLOCK_GAS_LIMIT / 2;
+/// Bounds the base fee change from one block to the next one to 1/8 (12.5%)
+const BASE_FEE_MAX_CHANGE_DENOMINATOR: u128 = 8;
 
 /// The minimum gas cost for accessing the storage
 pub const MIN_STORAGE_GAS: u64 = 1;
@@ -32,12 +41,69 @@ pub const MIN_STORAGE_GAS: u64 = 1;
 /// Gas module result for functions that may fail
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// The gas costs of a transaction's storage access, which are metered apart
+/// from the gas of its compute and added to its total gas. A write costs
+/// more than a read, as it also grows the state, and the bytes deleted from
+/// the storage are refunded.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct StorageGasCosts {
+    /// The gas per byte of a key and its value read from the storage
+    pub read_gas_per_byte: u64,
+    /// The gas per byte of a key and its value written to the storage
+    pub write_gas_per_byte: u64,
+    /// The gas refunded per byte of a key and its value deleted from the
+    /// storage
+    pub delete_refund_per_byte: u64,
+}
+
+impl Default for StorageGasCosts {
+    fn default() -> Self {
+        Self {
+            read_gas_per_byte: 1,
+            write_gas_per_byte: 2,
+            delete_refund_per_byte: 1,
+        }
+    }
+}
+
+impl Display for StorageGasCosts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "read {} per byte, write {} per byte, deletion refund {} per byte",
+            self.read_gas_per_byte,
+            self.write_gas_per_byte,
+            self.delete_refund_per_byte
+        )
+    }
+}
+
 /// Gas metering in a block. Tracks the gas in a current block and a current
 /// transaction.
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Clone)]
 pub struct BlockGasMeter {
     block_gas: u64,
     transaction_gas: u64,
+    /// The limit of the gas of a single transaction
+    transaction_gas_limit: u64,
+    /// The part of the current transaction's gas spent on storage access
+    transaction_storage_gas: u64,
+    /// The costs of the storage access
+    storage_gas_costs: StorageGasCosts,
 }
 
 /// Gas metering in a validity predicate
@@ -58,7 +124,31 @@ pub struct VpsGas {
     rest: Vec<u64>,
 }
 
+impl Default for BlockGasMeter {
+    fn default() -> Self {
+        Self::with_transaction_gas_limit(TRANSACTION_GAS_LIMIT)
+    }
+}
+
 impl BlockGasMeter {
+    /// Initialize a gas meter with the given limit of the gas of a single
+    /// transaction, instead of the default [`TRANSACTION_GAS_LIMIT`].
+    pub fn with_transaction_gas_limit(transaction_gas_limit: u64) -> Self {
+        Self {
+            block_gas: 0,
+            transaction_gas: 0,
+            transaction_gas_limit,
+            transaction_storage_gas: 0,
+            storage_gas_costs: StorageGasCosts::default(),
+        }
+    }
+
+    /// Set the costs of the storage access of the following transactions,
+    /// e.g. from the protocol parameters.
+    pub fn set_storage_gas_costs(&mut self, costs: StorageGasCosts) {
+        self.storage_gas_costs = costs;
+    }
+
     /// Add gas cost for the current transaction. It will return error when the
     /// consumed gas exceeds the transaction gas limit, but the state will still
     /// be updated.
@@ -68,12 +158,18 @@ impl BlockGasMeter {
             .checked_add(gas)
             .ok_or(Error::GasOverflow)?;
 
-        if self.transaction_gas > TRANSACTION_GAS_LIMIT {
+        if self.is_out_of_gas() {
             return Err(Error::TransactionGasExceedededError);
         }
         Ok(())
     }
 
+    /// Has the current transaction consumed more gas than the transaction gas
+    /// limit?
+    pub fn is_out_of_gas(&self) -> bool {
+        self.transaction_gas > self.transaction_gas_limit
+    }
+
     /// Add the base transaction fee and the fee per transaction byte that's
     /// charged the moment we try to apply the transaction.
     pub fn add_base_transaction_fee(&mut self, bytes_len: usize) -> Result<()> {
@@ -86,6 +182,44 @@ impl BlockGasMeter {
         self.add(bytes_len as u64 * COMPILE_GAS_PER_BYTE)
     }
 
+    /// Add the storage gas cost of reading the given number of bytes of keys
+    /// and values from the storage.
+    pub fn add_storage_read(&mut self, bytes_len: u64) -> Result<()> {
+        let gas = bytes_len
+            .checked_mul(self.storage_gas_costs.read_gas_per_byte)
+            .ok_or(Error::GasOverflow)?;
+        self.add_storage_gas(gas)
+    }
+
+    /// Add the storage gas cost of writing the given number of bytes of keys
+    /// and values to the storage.
+    pub fn add_storage_write(&mut self, bytes_len: u64) -> Result<()> {
+        let gas = bytes_len
+            .checked_mul(self.storage_gas_costs.write_gas_per_byte)
+            .ok_or(Error::GasOverflow)?;
+        self.add_storage_gas(gas)
+    }
+
+    fn add_storage_gas(&mut self, gas: u64) -> Result<()> {
+        self.transaction_storage_gas = self
+            .transaction_storage_gas
+            .checked_add(gas)
+            .ok_or(Error::GasOverflow)?;
+        self.add(gas)
+    }
+
+    /// Refund the storage gas for the given number of bytes of keys and
+    /// values deleted from the storage. The refund is capped by the storage
+    /// gas of the current transaction, so that it never offsets the gas of
+    /// its compute.
+    pub fn refund_storage_deletion(&mut self, bytes_len: u64) {
+        let refund = bytes_len
+            .saturating_mul(self.storage_gas_costs.delete_refund_per_byte)
+            .min(self.transaction_storage_gas);
+        self.transaction_storage_gas -= refund;
+        self.transaction_gas -= refund;
+    }
+
     /// Add the transaction gas to the block's total gas. Returns the
     /// transaction's gas cost and resets the transaction meter. It will return
     /// error when the consumed gas exceeds the block gas limit, but the state
@@ -98,23 +232,48 @@ impl BlockGasMeter {
 
         let transaction_gas = self.transaction_gas;
         self.transaction_gas = 0;
+        self.transaction_storage_gas = 0;
         if self.block_gas > BLOCK_GAS_LIMIT {
             return Err(Error::BlockGasExceeded);
         }
         Ok(transaction_gas)
     }
 
+    /// Discard the current transaction's gas without adding it to the block's
+    /// total gas. Returns the discarded gas and resets the transaction meter.
+    pub fn discard_transaction(&mut self) -> u64 {
+        self.transaction_storage_gas = 0;
+        std::mem::take(&mut self.transaction_gas)
+    }
+
     /// Reset the gas meter.
     pub fn reset(&mut self) {
         self.transaction_gas = 0;
+        self.transaction_storage_gas = 0;
         self.block_gas = 0;
     }
 
+    /// Get the total gas used in the current block.
+    pub fn get_block_gas(&self) -> u64 {
+        self.block_gas
+    }
+
     /// Get the total gas used in the current transaction.
     pub fn get_current_transaction_gas(&self) -> u64 {
         self.transaction_gas
     }
 
+    /// Get the part of the current transaction's gas spent on storage access,
+    /// which is included in [`BlockGasMeter::get_current_transaction_gas`].
+    pub fn get_current_storage_gas(&self) -> u64 {
+        self.transaction_storage_gas
+    }
+
+    /// Get the gas limit of a single transaction.
+    pub fn get_transaction_gas_limit(&self) -> u64 {
+        self.transaction_gas_limit
+    }
+
     /// Add the gas cost used in validity predicates to the current transaction.
     pub fn add_vps_gas(&mut self, vps_gas: &VpsGas) -> Result<()> {
         self.add(vps_gas.get_current_gas()?)
@@ -213,6 +372,28 @@ impl VpsGas {
     }
 }
 
+/// Calculate the base fee for the next block from the current block's base
+/// fee and its total gas used. When the gas used is above the
+/// [`BLOCK_GAS_TARGET`], the base fee increases (by at least 1) and when it's
+/// below, it decreases, in proportion to the distance from the target.
+pub fn next_base_fee(base_fee: u64, block_gas_used: u64) -> u64 {
+    let base_fee = base_fee as u128;
+    let target = BLOCK_GAS_TARGET as u128;
+    let gas_used = block_gas_used as u128;
+    let next_base_fee = if gas_used > target {
+        let delta = base_fee * (gas_used - target)
+            / target
+            / BASE_FEE_MAX_CHANGE_DENOMINATOR;
+        base_fee + delta.max(1)
+    } else {
+        let delta = base_fee * (target - gas_used)
+            / target
+            / BASE_FEE_MAX_CHANGE_DENOMINATOR;
+        base_fee - delta
+    };
+    u64::try_from(next_base_fee).unwrap_or(u64::MAX)
+}
+
 /// Convert the gas from signed to unsigned int. This will panic on overflow,
 /// but it should never occur for our gas limits (see
 /// `tests::gas_limits_cannot_overflow_i64`).
@@ -242,6 +423,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_next_base_fee() {
+        let base_fee = 1_000_000;
+        // At the target, the base fee is unchanged
+        assert_eq!(next_base_fee(base_fee, BLOCK_GAS_TARGET), base_fee);
+        // A full block increases the base fee by 1/8
+        assert_eq!(next_base_fee(base_fee, BLOCK_GAS_LIMIT), 1_125_000);
+        // An empty block decreases the base fee by 1/8
+        assert_eq!(next_base_fee(base_fee, 0), 875_000);
+        // A zero base fee increases on a block above the target
+        assert_eq!(next_base_fee(0, BLOCK_GAS_LIMIT), 1);
+        assert_eq!(next_base_fee(0, 0), 0);
+    }
+
     #[test]
     fn test_vp_gas_overflow() {
         let mut meter = VpGasMeter::new(1);
@@ -283,6 +478,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_custom_tx_gas_limit() {
+        let mut meter = BlockGasMeter::with_transaction_gas_limit(10);
+        meter.add(10).expect("cannot add the gas");
+        assert!(!meter.is_out_of_gas());
+        assert_matches!(
+            meter.add(1).expect_err("unexpectedly succeeded"),
+            Error::TransactionGasExceedededError
+        );
+        assert!(meter.is_out_of_gas());
+        assert_eq!(meter.get_current_transaction_gas(), 11);
+        assert_eq!(meter.get_transaction_gas_limit(), 10);
+    }
+
+    #[test]
+    fn test_storage_gas() {
+        let costs = StorageGasCosts {
+            read_gas_per_byte: 1,
+            write_gas_per_byte: 3,
+            delete_refund_per_byte: 2,
+        };
+        let mut meter = BlockGasMeter::default();
+        meter.set_storage_gas_costs(costs);
+        meter.add(5).expect("cannot add the gas");
+        meter.add_storage_read(10).expect("cannot add the gas");
+        meter.add_storage_write(10).expect("cannot add the gas");
+        assert_eq!(meter.get_current_storage_gas(), 40);
+        assert_eq!(meter.get_current_transaction_gas(), 45);
+
+        // A deletion is refunded, but not beyond the storage gas
+        meter.refund_storage_deletion(5);
+        assert_eq!(meter.get_current_storage_gas(), 30);
+        assert_eq!(meter.get_current_transaction_gas(), 35);
+        meter.refund_storage_deletion(1_000);
+        assert_eq!(meter.get_current_storage_gas(), 0);
+        assert_eq!(meter.get_current_transaction_gas(), 5);
+
+        assert_eq!(meter.finalize_transaction(), Ok(5));
+        assert_eq!(meter.get_current_storage_gas(), 0);
+    }
+
     #[test]
     fn test_block_gas_limit() {
         let mut meter = BlockGasMeter::default();
@@ -5,7 +5,7 @@ pub mod parameters;
 /// governance storage
 pub mod storage;
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
 
 use borsh::BorshDeserialize;
 use thiserror::Error;
@@ -14,6 +14,7 @@ use self::storage as gov_storage;
 use crate::ledger::native_vp::{self, Ctx, NativeVp};
 use crate::ledger::storage::{self as ledger_storage, StorageHasher};
 use crate::types::address::{xan as m1t, Address, InternalAddress};
+use crate::types::governance::ProposalContentLimits;
 use crate::types::storage::{DbKeySeg, Key};
 use crate::types::token as token_storage;
 use crate::types::token::Amount;
@@ -93,18 +94,49 @@ where
                         ReadType::PRE,
                     )
                     .ok();
+                    let content_limits_parameter_key =
+                        gov_storage::get_proposal_content_limits_key();
+                    let content_limits: Option<ProposalContentLimits> =
+                        match read(
+                            &self.ctx,
+                            &content_limits_parameter_key,
+                            ReadType::PRE,
+                        ) {
+                            Ok(content_limits) => Some(content_limits),
+                            // The chains initialized before the limits were
+                            // added don't have them in storage
+                            Err(Error::NativeVpNonExistingKeyError(_)) => {
+                                Some(ProposalContentLimits::default())
+                            }
+                            Err(_) => None,
+                        };
                     let has_pre_content =
                         self.ctx.has_key_pre(&content_key).ok();
                     let post_content =
                         self.ctx.read_post(&content_key).unwrap();
-                    match (has_pre_content, post_content, max_content_length) {
+                    match (
+                        has_pre_content,
+                        post_content,
+                        max_content_length,
+                        content_limits,
+                    ) {
                         (
                             Some(has_pre_content),
                             Some(post_content),
                             Some(max_content_length),
+                            Some(content_limits),
                         ) => {
+                            let is_valid_content =
+                                HashMap::<String, String>::try_from_slice(
+                                    &post_content,
+                                )
+                                .map(|content| {
+                                    content_limits.validate(&content).is_empty()
+                                })
+                                .unwrap_or(false);
                             !has_pre_content
                                 && post_content.len() < max_content_length
+                                && is_valid_content
                         }
                         _ => false,
                     }
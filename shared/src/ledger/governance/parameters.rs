@@ -3,6 +3,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use super::storage as gov_storage;
 use crate::ledger::storage::types::encode;
 use crate::ledger::storage::{self, Storage};
+use crate::types::governance::ProposalContentLimits;
 use crate::types::token::Amount;
 
 #[derive(
@@ -28,6 +29,8 @@ pub struct GovParams {
     pub max_proposal_content_size: u64,
     /// Minimum epochs between end and grace epochs
     pub min_proposal_grace_epochs: u64,
+    /// Bounds of the fields of proposal content
+    pub proposal_content_limits: ProposalContentLimits,
 }
 
 impl Default for GovParams {
@@ -38,6 +41,7 @@ impl Default for GovParams {
             min_proposal_period: 3,
             max_proposal_content_size: 10000,
             min_proposal_grace_epochs: 6,
+            proposal_content_limits: ProposalContentLimits::default(),
         }
     }
 }
@@ -89,6 +93,15 @@ impl GovParams {
             )
             .unwrap();
 
+        let proposal_content_limits_key =
+            gov_storage::get_proposal_content_limits_key();
+        storage
+            .write(
+                &proposal_content_limits_key,
+                encode(&self.proposal_content_limits),
+            )
+            .unwrap();
+
         let counter_key = gov_storage::get_counter_key();
         storage.write(&counter_key, encode(&u64::MIN)).unwrap();
     }
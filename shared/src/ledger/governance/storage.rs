@@ -1,4 +1,5 @@
 use super::ADDRESS;
+use crate::types::address::Address;
 use crate::types::storage::{DbKeySeg, Key, KeySeg};
 
 const PROPOSAL_PREFIX: &str = "proposal";
@@ -17,6 +18,7 @@ const MAX_PROPOSAL_CODE_SIZE_KEY: &str = "max_code_size";
 const MIN_PROPOSAL_PERIOD_KEY: &str = "min_period";
 const MAX_PROPOSAL_CONTENT_SIZE_KEY: &str = "max_content";
 const MIN_GRACE_EPOCH_KEY: &str = "min_grace_epoch";
+const PROPOSAL_CONTENT_LIMITS_KEY: &str = "content_limits";
 const COUNTER_KEY: &str = "counter";
 
 /// Check if key is inside governance address space
@@ -43,6 +45,26 @@ pub fn is_vote_key(key: &Key) -> bool {
     }
 }
 
+/// Check if a key is a vote key. If it is, returns the voter's address.
+pub fn get_voter_address(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::StringSeg(id),
+            DbKeySeg::StringSeg(vote),
+            DbKeySeg::AddressSeg(voter),
+        ] if addr == &ADDRESS
+            && prefix == PROPOSAL_PREFIX
+            && vote == PROPOSAL_VOTE
+            && id.parse::<u64>().is_ok() =>
+        {
+            Some(voter)
+        }
+        _ => None,
+    }
+}
+
 /// Check if key is author key
 pub fn is_author_key(key: &Key) -> bool {
     match &key.segments[..] {
@@ -273,6 +295,21 @@ pub fn is_commit_proposal_key(key: &Key) -> bool {
     }
 }
 
+/// Check if key is a proposal content limits parameter key
+pub fn is_proposal_content_limits_key(key: &Key) -> bool {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(content_limits_param),
+        ] if addr == &ADDRESS
+            && content_limits_param == PROPOSAL_CONTENT_LIMITS_KEY =>
+        {
+            true
+        }
+        _ => false,
+    }
+}
+
 /// Check if key is parameter key
 pub fn is_parameter_key(key: &Key) -> bool {
     is_min_proposal_fund_key(key)
@@ -280,6 +317,7 @@ pub fn is_parameter_key(key: &Key) -> bool {
         || is_max_proposal_code_size_key(key)
         || is_min_proposal_period_key(key)
         || is_min_grace_epoch_key(key)
+        || is_proposal_content_limits_key(key)
 }
 
 /// Check if key is start epoch or end epoch key
@@ -329,6 +367,13 @@ pub fn get_min_proposal_grace_epoch_key() -> Key {
         .expect("Cannot obtain a storage key")
 }
 
+/// Get proposal content limits key
+pub fn get_proposal_content_limits_key() -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&PROPOSAL_CONTENT_LIMITS_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
 /// Get key of proposal ids counter
 pub fn get_counter_key() -> Key {
     Key::from(ADDRESS.to_db_key())
@@ -11,7 +11,9 @@ use crate::ledger::storage::{Storage, StorageHasher};
 use crate::ledger::{storage, vp_env};
 use crate::proto::Tx;
 use crate::types::address::{Address, InternalAddress};
+use crate::types::hash::Hash;
 use crate::types::storage::{BlockHash, BlockHeight, Epoch, Key};
+use crate::types::time::DateTimeUtc;
 use crate::vm::prefix_iter::PrefixIterators;
 use crate::vm::WasmCacheAccess;
 
@@ -181,6 +183,13 @@ where
         .map_err(Error::ContextError)
     }
 
+    /// Getting the block time. The time is that of the header of the block
+    /// to which the current transaction is being applied, if any.
+    pub fn get_block_time(&self) -> Result<Option<DateTimeUtc>> {
+        vp_env::get_block_time(&mut *self.gas_meter.borrow_mut(), self.storage)
+            .map_err(Error::ContextError)
+    }
+
     /// Getting the block hash. The height is that of the block to which the
     /// current transaction is being applied.
     pub fn get_block_hash(&self) -> Result<BlockHash> {
@@ -195,6 +204,17 @@ where
             .map_err(Error::ContextError)
     }
 
+    /// Getting the random seed of the current epoch. The seed is the same on
+    /// every node.
+    pub fn get_epoch_seed(&self) -> Result<Hash> {
+        vp_env::get_epoch_seed(
+            &mut *self.gas_meter.borrow_mut(),
+            self.storage,
+            self.write_log,
+        )
+        .map_err(Error::ContextError)
+    }
+
     /// Storage prefix iterator. It will try to get an iterator from the
     /// storage.
     pub fn iter_prefix(
@@ -226,8 +246,9 @@ where
         &self,
         iter: &mut <DB as storage::DBIter<'_>>::PrefixIter,
     ) -> Result<Option<(String, Vec<u8>)>> {
-        vp_env::iter_post_next::<DB>(
+        vp_env::iter_post_next::<DB, H>(
             &mut *self.gas_meter.borrow_mut(),
+            self.storage,
             self.write_log,
             iter,
         )
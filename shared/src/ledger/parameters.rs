@@ -1,17 +1,23 @@
 //! Protocol parameters
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
 
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use super::storage::types::decode;
+use crate::ledger::gas::StorageGasCosts;
 use crate::ledger::native_vp::{self, Ctx, NativeVp};
 use crate::ledger::storage::types::{self, encode};
+use crate::ledger::storage::write_log::{StorageModification, WriteLog};
 use crate::ledger::storage::{self, Storage, StorageHasher};
-use crate::types::address::{Address, InternalAddress};
-use crate::types::storage::{DbKeySeg, Key};
-use crate::types::time::DurationSecs;
+use crate::types::address::{self, Address, InternalAddress};
+use crate::types::hash::{Hash, HASH_LENGTH};
+use crate::types::storage::{BlockHash, BlockHeight, DbKeySeg, Epoch, Key};
+use crate::types::time::{DateTimeUtc, DurationSecs};
+use crate::types::token;
 use crate::vm::WasmCacheAccess;
 
 const ADDR: InternalAddress = InternalAddress::Parameters;
@@ -19,6 +25,20 @@ const EPOCH_DURATION_KEY: &str = "epoch_duration";
 const VP_WHITELIST_KEY: &str = "vp_whitelist";
 const TX_WHITELIST_KEY: &str = "tx_whitelist";
 const MAX_EXPECTED_TIME_PER_BLOCK_KEY: &str = "max_expected_time_per_block";
+const BASE_FEE_KEY: &str = "base_fee";
+const MIN_BASE_FEE_KEY: &str = "min_base_fee";
+const TX_ORDERING_KEY: &str = "tx_ordering";
+const TX_SIZE_LIMITS_KEY: &str = "tx_size_limits";
+const GENESIS_TIME_KEY: &str = "genesis_time";
+const EPOCH_SEED_KEY: &str = "epoch_seed";
+const MATCHMAKERS_KEY: &str = "matchmakers";
+const FEE_EXEMPTION_KEY: &str = "fee_exemption";
+const FEE_TOKEN_RATES_KEY: &str = "fee_token_rates";
+const FEE_REFUND_POLICY_KEY: &str = "fee_refund_policy";
+const STORAGE_GAS_COSTS_KEY: &str = "storage_gas_costs";
+const WRITE_LOG_FLUSH_THRESHOLD_KEY: &str = "write_log_flush_threshold";
+const PROTOCOL_VERSION_KEY: &str = "protocol_version";
+const PROTOCOL_UPGRADE_KEY: &str = "protocol_upgrade";
 
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
@@ -63,6 +83,332 @@ pub struct Parameters {
     pub vp_whitelist: Vec<String>,
     /// Whitelisted tx hashes
     pub tx_whitelist: Vec<String>,
+    /// The order in which the txs of a block are applied
+    pub tx_ordering: TxOrdering,
+    /// The maximum sizes of a tx's code and data
+    pub tx_size_limits: TxSizeLimits,
+    /// The fee payers exempt from the wrapper tx fee
+    pub fee_exemption: FeeExemption,
+    /// The conversion rates of the tokens in which the fees can be paid
+    pub fee_token_rates: FeeTokenRates,
+    /// Whether the wrapper fee of a tx rejected by its VPs is refunded
+    pub fee_refund_policy: FeeRefundPolicy,
+    /// The gas costs of a tx's storage access
+    pub storage_gas_costs: StorageGasCosts,
+    /// The size in bytes of the changes of a block held in memory above
+    /// which they're staged in the DB until the block is committed
+    pub write_log_flush_threshold: Option<u64>,
+    /// The base fee per unit of gas of the first block, below which the
+    /// base fee never falls
+    pub min_base_fee: token::Amount,
+}
+
+/// The deterministic order in which the txs of a block are applied. All the
+/// nodes must apply the txs in the same order to arrive at the same state.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TxOrdering {
+    /// Apply the txs in the order in which they have been included in the
+    /// block by its proposer
+    Proposed,
+    /// Apply the txs with a higher wrapper fee first. Txs with an equal fee
+    /// (and the txs without a fee) are ordered by their hash.
+    FeeThenHash,
+}
+
+impl Default for TxOrdering {
+    fn default() -> Self {
+        Self::Proposed
+    }
+}
+
+impl Display for TxOrdering {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TxOrdering::Proposed => write!(f, "proposed"),
+            TxOrdering::FeeThenHash => write!(f, "fee_then_hash"),
+        }
+    }
+}
+
+/// The maximum sizes in bytes of the code and of the data of a tx. The code
+/// and the data are limited separately, so that a chain may e.g. only permit
+/// small code with larger data.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct TxSizeLimits {
+    /// Maximum size of a tx's WASM code. `None` doesn't limit the size.
+    pub max_code_size: Option<u64>,
+    /// Maximum size of a tx's data. `None` doesn't limit the size.
+    pub max_data_size: Option<u64>,
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug, PartialEq)]
+pub enum TxSizeError {
+    #[error(
+        "The tx code size {size} exceeds the maximum tx code size {max_size}"
+    )]
+    CodeTooLarge { size: u64, max_size: u64 },
+    #[error(
+        "The tx data size {size} exceeds the maximum tx data size {max_size}"
+    )]
+    DataTooLarge { size: u64, max_size: u64 },
+}
+
+impl TxSizeLimits {
+    /// Check the sizes of a tx's code and data against the limits.
+    pub fn check(
+        &self,
+        code_size: usize,
+        data_size: usize,
+    ) -> std::result::Result<(), TxSizeError> {
+        let code_size = code_size as u64;
+        let data_size = data_size as u64;
+        match (self.max_code_size, self.max_data_size) {
+            (Some(max_size), _) if code_size > max_size => {
+                Err(TxSizeError::CodeTooLarge {
+                    size: code_size,
+                    max_size,
+                })
+            }
+            (_, Some(max_size)) if data_size > max_size => {
+                Err(TxSizeError::DataTooLarge {
+                    size: data_size,
+                    max_size,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Display for TxSizeLimits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fmt_limit = |limit: Option<u64>| match limit {
+            Some(limit) => format!("{} bytes", limit),
+            None => "unlimited".to_string(),
+        };
+        write!(
+            f,
+            "code {}, data {}",
+            fmt_limit(self.max_code_size),
+            fmt_limit(self.max_data_size)
+        )
+    }
+}
+
+/// The fee payers that are exempt from paying the fee of their wrapper txs.
+/// Like the other protocol parameters, this can only be changed by
+/// governance, so an account cannot make itself exempt. The protocol txs
+/// never pay a fee and don't need to be listed here.
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+)]
+pub struct FeeExemption {
+    /// The exempt fee payers
+    pub addresses: BTreeSet<Address>,
+    /// If `true`, the gas of the exempt wrapper txs is also not counted
+    /// towards the block's gas, so it doesn't raise the base fee. Otherwise,
+    /// it's metered like the gas of any other tx.
+    pub exempt_gas: bool,
+}
+
+impl FeeExemption {
+    /// Check if the given fee payer is exempt from the fee.
+    pub fn is_exempt(&self, fee_payer: &Address) -> bool {
+        self.addresses.contains(fee_payer)
+    }
+}
+
+impl Display for FeeExemption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.addresses.is_empty() {
+            return write!(f, "none");
+        }
+        let addresses: Vec<String> =
+            self.addresses.iter().map(Address::encode).collect();
+        write!(f, "{}", addresses.join(", "))?;
+        if self.exempt_gas {
+            write!(f, " (including gas)")?;
+        }
+        Ok(())
+    }
+}
+
+/// The conversion rates of the tokens, other than the native token, in which
+/// the wrapper tx fees can be paid. The rate of a token is the amount of the
+/// native token that one whole unit of the token is worth, so that the fees
+/// paid in different tokens can be compared with each other and with the base
+/// fee. The native token is always accepted at par and a token without a rate
+/// can't pay fees. Like the other protocol parameters, this can only be
+/// changed by governance.
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+)]
+pub struct FeeTokenRates {
+    /// The rates by the address of the token
+    pub rates: BTreeMap<Address, token::Amount>,
+}
+
+impl FeeTokenRates {
+    /// Convert an amount of the given token to the native token, rounding
+    /// down. Returns `None` for a token without a rate, which can't pay fees.
+    pub fn to_native(
+        &self,
+        token: &Address,
+        amount: token::Amount,
+    ) -> Option<token::Amount> {
+        if *token == address::xan() {
+            return Some(amount);
+        }
+        let rate = self.rates.get(token)?;
+        let native = u128::from(u64::from(amount))
+            * u128::from(u64::from(*rate))
+            / u128::from(token::SCALE);
+        Some(token::Amount::from(
+            u64::try_from(native).unwrap_or(u64::MAX),
+        ))
+    }
+}
+
+impl Display for FeeTokenRates {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.rates.is_empty() {
+            return write!(f, "only the native token");
+        }
+        let rates: Vec<String> = self
+            .rates
+            .iter()
+            .map(|(token, rate)| format!("{}: {}", token.encode(), rate))
+            .collect();
+        write!(f, "{}", rates.join(", "))
+    }
+}
+
+/// Whether the fee of a wrapper tx is refunded when its inner tx is rejected
+/// by the VPs. The fee is always charged for an inner tx that can't be
+/// decrypted or decoded and for one that runs out of gas, so that the fee
+/// still covers the work of the nodes that applied it.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum FeeRefundPolicy {
+    /// The fee is charged regardless of the outcome of the inner tx
+    AlwaysCharge,
+    /// The fee is refunded to the fee payer when the inner tx is rejected by
+    /// any of the VPs
+    RefundOnVpRejection,
+}
+
+impl Default for FeeRefundPolicy {
+    fn default() -> Self {
+        Self::AlwaysCharge
+    }
+}
+
+impl Display for FeeRefundPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FeeRefundPolicy::AlwaysCharge => write!(f, "always-charge"),
+            FeeRefundPolicy::RefundOnVpRejection => {
+                write!(f, "refund-on-vp-rejection")
+            }
+        }
+    }
+}
+
+/// An upgrade of the protocol version scheduled at a block height. From this
+/// height on, the blocks can only be processed by the nodes that support the
+/// new version.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+)]
+pub struct ProtocolUpgrade {
+    /// The new protocol version
+    pub version: u64,
+    /// The height of the first block that requires the new version
+    pub height: BlockHeight,
+}
+
+impl Display for ProtocolUpgrade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "version {} at the block height {}",
+            self.version, self.height
+        )
+    }
 }
 
 /// Epoch duration. A new epoch begins as soon as both the `min_num_of_blocks`
@@ -128,6 +474,147 @@ pub fn init_genesis_storage<DB, H>(
             "Max expected time per block parameters must be initialized in \
              the genesis block",
         );
+
+    // write tx ordering parameter
+    let tx_ordering_key = tx_ordering_storage_key();
+    let tx_ordering_value = encode(&parameters.tx_ordering);
+    storage.write(&tx_ordering_key, tx_ordering_value).expect(
+        "Tx ordering parameter must be initialized in the genesis block",
+    );
+
+    // write tx size limits parameter
+    let tx_size_limits_key = tx_size_limits_storage_key();
+    let tx_size_limits_value = encode(&parameters.tx_size_limits);
+    storage
+        .write(&tx_size_limits_key, tx_size_limits_value)
+        .expect(
+            "Tx size limits parameter must be initialized in the genesis block",
+        );
+
+    // write fee exemption parameter
+    let fee_exemption_key = fee_exemption_storage_key();
+    let fee_exemption_value = encode(&parameters.fee_exemption);
+    storage
+        .write(&fee_exemption_key, fee_exemption_value)
+        .expect(
+            "Fee exemption parameter must be initialized in the genesis block",
+        );
+
+    // write fee token rates parameter
+    let fee_token_rates_key = fee_token_rates_storage_key();
+    let fee_token_rates_value = encode(&parameters.fee_token_rates);
+    storage
+        .write(&fee_token_rates_key, fee_token_rates_value)
+        .expect(
+            "Fee token rates parameter must be initialized in the genesis \
+             block",
+        );
+
+    // write fee refund policy parameter
+    let fee_refund_policy_key = fee_refund_policy_storage_key();
+    let fee_refund_policy_value = encode(&parameters.fee_refund_policy);
+    storage
+        .write(&fee_refund_policy_key, fee_refund_policy_value)
+        .expect(
+            "Fee refund policy parameter must be initialized in the genesis \
+             block",
+        );
+
+    // write storage gas costs parameter
+    let storage_gas_costs_key = storage_gas_costs_storage_key();
+    let storage_gas_costs_value = encode(&parameters.storage_gas_costs);
+    storage
+        .write(&storage_gas_costs_key, storage_gas_costs_value)
+        .expect(
+            "Storage gas costs parameter must be initialized in the genesis \
+             block",
+        );
+
+    // write write log flush threshold parameter
+    let flush_threshold_key = write_log_flush_threshold_storage_key();
+    let flush_threshold_value = encode(&parameters.write_log_flush_threshold);
+    storage
+        .write(&flush_threshold_key, flush_threshold_value)
+        .expect(
+            "Write log flush threshold parameter must be initialized in the \
+             genesis block",
+        );
+
+    // write min base fee parameter
+    let min_base_fee_key = min_base_fee_storage_key();
+    let min_base_fee_value = encode(&parameters.min_base_fee);
+    storage.write(&min_base_fee_key, min_base_fee_value).expect(
+        "Min base fee parameter must be initialized in the genesis block",
+    );
+
+    // write the initial base fee, which is adjusted in every block
+    let base_fee_key = base_fee_storage_key();
+    let base_fee_value = encode(&parameters.min_base_fee);
+    storage
+        .write(&base_fee_key, base_fee_value)
+        .expect("Base fee must be initialized in the genesis block");
+}
+
+/// Write the seed of the first epoch in the genesis block. It's derived from
+/// the chain ID, so that the seeds of different chains diverge from the start.
+pub fn init_genesis_epoch_seed<DB, H>(storage: &mut Storage<DB, H>)
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let epoch_seed_key = epoch_seed_storage_key();
+    let epoch_seed_value = encode(&Hash::sha256(storage.chain_id.as_str()));
+    storage
+        .write(&epoch_seed_key, epoch_seed_value)
+        .expect("Epoch seed must be initialized in the genesis block");
+}
+
+/// Write the time at which the chain starts in the genesis block. The blocks
+/// must not be produced before this time.
+pub fn init_genesis_time<DB, H>(
+    storage: &mut Storage<DB, H>,
+    genesis_time: DateTimeUtc,
+) where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let genesis_time_key = genesis_time_storage_key();
+    let genesis_time_value = encode(&genesis_time);
+    storage
+        .write(&genesis_time_key, genesis_time_value)
+        .expect("Genesis time must be initialized in the genesis block");
+}
+
+/// Write the version of the protocol with which the chain starts in the
+/// genesis block.
+pub fn init_genesis_protocol_version<DB, H>(
+    storage: &mut Storage<DB, H>,
+    protocol_version: u64,
+) where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let protocol_version_key = protocol_version_storage_key();
+    let protocol_version_value = encode(&protocol_version);
+    storage
+        .write(&protocol_version_key, protocol_version_value)
+        .expect("Protocol version must be initialized in the genesis block");
+}
+
+/// Write the addresses of the matchmakers registered in the genesis block. The
+/// intents may only be settled by txs crafted by one of these matchmakers.
+pub fn init_genesis_matchmakers<DB, H>(
+    storage: &mut Storage<DB, H>,
+    matchmakers: &[Address],
+) where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let matchmakers_key = matchmakers_storage_key();
+    let matchmakers_value = encode(&matchmakers);
+    storage
+        .write(&matchmakers_key, matchmakers_value)
+        .expect("Matchmakers must be initialized in the genesis block");
 }
 
 #[allow(missing_docs)]
@@ -180,14 +667,60 @@ where
         decode(value.ok_or(ReadError::ParametersMissing)?)
             .map_err(ReadError::StorageTypeError)?;
 
+    // read tx ordering
+    let (tx_ordering, gas_ordering) = read_tx_ordering(storage)?;
+
+    // read tx size limits
+    let (tx_size_limits, gas_size_limits) = read_tx_size_limits(storage)?;
+
+    // read fee exemption
+    let (fee_exemption, gas_fee_exemption) = read_fee_exemption(storage)?;
+
+    // read fee token rates
+    let (fee_token_rates, gas_fee_token_rates) = read_fee_token_rates(storage)?;
+
+    // read fee refund policy
+    let (fee_refund_policy, gas_fee_refund_policy) =
+        read_fee_refund_policy(storage)?;
+
+    // read storage gas costs
+    let (storage_gas_costs, gas_storage_gas_costs) =
+        read_storage_gas_costs(storage)?;
+
+    // read write log flush threshold
+    let (write_log_flush_threshold, gas_flush_threshold) =
+        read_write_log_flush_threshold(storage)?;
+
+    // read min base fee
+    let (min_base_fee, gas_min_base_fee) = read_min_base_fee(storage)?;
+
     Ok((
         Parameters {
             epoch_duration,
             max_expected_time_per_block,
             vp_whitelist,
             tx_whitelist,
+            tx_ordering,
+            tx_size_limits,
+            fee_exemption,
+            fee_token_rates,
+            fee_refund_policy,
+            storage_gas_costs,
+            write_log_flush_threshold,
+            min_base_fee,
         },
-        gas_epoch + gas_tx + gas_vp + gas_time,
+        gas_epoch
+            + gas_tx
+            + gas_vp
+            + gas_time
+            + gas_ordering
+            + gas_size_limits
+            + gas_fee_exemption
+            + gas_fee_token_rates
+            + gas_fee_refund_policy
+            + gas_storage_gas_costs
+            + gas_flush_threshold
+            + gas_min_base_fee,
     ))
 }
 
@@ -210,81 +743,454 @@ where
     Ok((epoch_duration, gas))
 }
 
-#[allow(missing_docs)]
-#[derive(Error, Debug)]
-pub enum WriteError {
-    #[error("Storage error: {0}")]
-    StorageError(storage::Error),
-    #[error("Serialize error: {0}")]
-    SerializeError(String),
-}
-
-/// Update the  parameters in storage. Returns the parameters and gas
-/// cost.
-pub fn update<DB, H, T>(
-    storage: &mut Storage<DB, H>,
-    value: &T,
-    key: Key,
-) -> std::result::Result<u64, WriteError>
+/// Read the base fee per unit of gas for transactions in the current block
+/// from store. The base fee is zero if it hasn't been set yet. Returns the
+/// base fee and gas cost.
+pub fn read_base_fee<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(token::Amount, u64), ReadError>
 where
     DB: storage::DB + for<'iter> storage::DBIter<'iter>,
     H: storage::StorageHasher,
-    T: BorshSerialize,
 {
-    let serialized_value = value
-        .try_to_vec()
-        .map_err(|e| WriteError::SerializeError(e.to_string()))?;
-    let (gas, _size_diff) = storage
-        .write(&key, serialized_value)
-        .map_err(WriteError::StorageError)?;
-    Ok(gas)
+    let base_fee_key = base_fee_storage_key();
+    let (value, gas) = storage
+        .read(&base_fee_key)
+        .map_err(ReadError::StorageError)?;
+    let base_fee: token::Amount = value
+        .map(decode)
+        .transpose()
+        .map_err(ReadError::StorageTypeError)?
+        .unwrap_or_default();
+
+    Ok((base_fee, gas))
 }
 
-/// Update the epoch parameter in storage. Returns the parameters and gas
-/// cost.
-pub fn update_epoch_parameter<DB, H>(
-    storage: &mut Storage<DB, H>,
-    value: &EpochDuration,
-) -> std::result::Result<u64, WriteError>
+/// Read the minimum base fee per unit of gas from store. The minimum is zero
+/// if it hasn't been set. Returns the minimum base fee and gas cost.
+pub fn read_min_base_fee<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(token::Amount, u64), ReadError>
 where
     DB: storage::DB + for<'iter> storage::DBIter<'iter>,
     H: storage::StorageHasher,
 {
-    let key = epoch_storage_key();
-    update(storage, value, key)
+    let min_base_fee_key = min_base_fee_storage_key();
+    let (value, gas) = storage
+        .read(&min_base_fee_key)
+        .map_err(ReadError::StorageError)?;
+    let min_base_fee: token::Amount = value
+        .map(decode)
+        .transpose()
+        .map_err(ReadError::StorageTypeError)?
+        .unwrap_or_default();
+
+    Ok((min_base_fee, gas))
 }
 
-/// Update the tx whitelist parameter in storage. Returns the parameters and gas
-/// cost.
-pub fn update_tx_whitelist_parameter<DB, H>(
-    storage: &mut Storage<DB, H>,
-    value: Vec<String>,
-) -> std::result::Result<u64, WriteError>
+/// Read the order in which the txs of a block are applied from store. The
+/// txs are applied in the proposed order if it hasn't been set. Returns the
+/// ordering and gas cost.
+pub fn read_tx_ordering<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(TxOrdering, u64), ReadError>
 where
     DB: storage::DB + for<'iter> storage::DBIter<'iter>,
     H: storage::StorageHasher,
 {
-    let key = tx_whitelist_storage_key();
-    update(storage, &value, key)
+    let tx_ordering_key = tx_ordering_storage_key();
+    let (value, gas) = storage
+        .read(&tx_ordering_key)
+        .map_err(ReadError::StorageError)?;
+    let tx_ordering: TxOrdering = value
+        .map(decode)
+        .transpose()
+        .map_err(ReadError::StorageTypeError)?
+        .unwrap_or_default();
+
+    Ok((tx_ordering, gas))
 }
 
-/// Update the vp whitelist parameter in storage. Returns the parameters and gas
-/// cost.
-pub fn update_vp_whitelist_parameter<DB, H>(
-    storage: &mut Storage<DB, H>,
-    value: Vec<String>,
-) -> std::result::Result<u64, WriteError>
+/// Read the maximum sizes of a tx's code and data from store. The sizes are
+/// unlimited if they haven't been set. Returns the limits and gas cost.
+pub fn read_tx_size_limits<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(TxSizeLimits, u64), ReadError>
 where
     DB: storage::DB + for<'iter> storage::DBIter<'iter>,
     H: storage::StorageHasher,
 {
-    let key = vp_whitelist_storage_key();
-    update(storage, &value, key)
+    let tx_size_limits_key = tx_size_limits_storage_key();
+    let (value, gas) = storage
+        .read(&tx_size_limits_key)
+        .map_err(ReadError::StorageError)?;
+    let tx_size_limits: TxSizeLimits = value
+        .map(decode)
+        .transpose()
+        .map_err(ReadError::StorageTypeError)?
+        .unwrap_or_default();
+
+    Ok((tx_size_limits, gas))
 }
 
-/// Update the max_expected_time_per_block parameter in storage. Returns the
-/// parameters and gas cost.
-pub fn update_max_expected_time_per_block_parameter<DB, H>(
+/// Read the fee exemption parameter from store. No fee payer is exempt if it
+/// hasn't been set. Returns the exemption and gas cost.
+pub fn read_fee_exemption<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(FeeExemption, u64), ReadError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let fee_exemption_key = fee_exemption_storage_key();
+    let (value, gas) = storage
+        .read(&fee_exemption_key)
+        .map_err(ReadError::StorageError)?;
+    let fee_exemption: FeeExemption = value
+        .map(decode)
+        .transpose()
+        .map_err(ReadError::StorageTypeError)?
+        .unwrap_or_default();
+
+    Ok((fee_exemption, gas))
+}
+
+/// Read the fee token rates parameter from store. Only the native token can
+/// pay fees if it hasn't been set. Returns the rates and gas cost.
+pub fn read_fee_token_rates<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(FeeTokenRates, u64), ReadError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let fee_token_rates_key = fee_token_rates_storage_key();
+    let (value, gas) = storage
+        .read(&fee_token_rates_key)
+        .map_err(ReadError::StorageError)?;
+    let fee_token_rates: FeeTokenRates = value
+        .map(decode)
+        .transpose()
+        .map_err(ReadError::StorageTypeError)?
+        .unwrap_or_default();
+
+    Ok((fee_token_rates, gas))
+}
+
+/// Read the fee refund policy parameter from store. The fee is always charged
+/// if it hasn't been set. Returns the policy and gas cost.
+pub fn read_fee_refund_policy<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(FeeRefundPolicy, u64), ReadError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let fee_refund_policy_key = fee_refund_policy_storage_key();
+    let (value, gas) = storage
+        .read(&fee_refund_policy_key)
+        .map_err(ReadError::StorageError)?;
+    let fee_refund_policy: FeeRefundPolicy = value
+        .map(decode)
+        .transpose()
+        .map_err(ReadError::StorageTypeError)?
+        .unwrap_or_default();
+
+    Ok((fee_refund_policy, gas))
+}
+
+/// Read the storage gas costs parameter from store. The default costs apply
+/// if it hasn't been set. Returns the costs and gas cost.
+pub fn read_storage_gas_costs<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(StorageGasCosts, u64), ReadError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let storage_gas_costs_key = storage_gas_costs_storage_key();
+    let (value, gas) = storage
+        .read(&storage_gas_costs_key)
+        .map_err(ReadError::StorageError)?;
+    let storage_gas_costs: StorageGasCosts = value
+        .map(decode)
+        .transpose()
+        .map_err(ReadError::StorageTypeError)?
+        .unwrap_or_default();
+
+    Ok((storage_gas_costs, gas))
+}
+
+/// Read the size in bytes of the changes of a block held in memory above
+/// which they're staged in the DB from store. All the changes are held in
+/// memory if it hasn't been set. Returns the threshold and gas cost.
+pub fn read_write_log_flush_threshold<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(Option<u64>, u64), ReadError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let flush_threshold_key = write_log_flush_threshold_storage_key();
+    let (value, gas) = storage
+        .read(&flush_threshold_key)
+        .map_err(ReadError::StorageError)?;
+    let flush_threshold: Option<u64> = value
+        .map(decode)
+        .transpose()
+        .map_err(ReadError::StorageTypeError)?
+        .flatten();
+
+    Ok((flush_threshold, gas))
+}
+
+/// Read the time at which the chain starts from store. The time is `None` on
+/// chains initialized before it's been stored. Returns the time and gas cost.
+pub fn read_genesis_time<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(Option<DateTimeUtc>, u64), ReadError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let genesis_time_key = genesis_time_storage_key();
+    let (value, gas) = storage
+        .read(&genesis_time_key)
+        .map_err(ReadError::StorageError)?;
+    let genesis_time: Option<DateTimeUtc> = value
+        .map(decode)
+        .transpose()
+        .map_err(ReadError::StorageTypeError)?;
+
+    Ok((genesis_time, gas))
+}
+
+/// Read the version of the protocol of the chain from store. The version is
+/// `None` on chains initialized before it's been stored. Returns the version
+/// and gas cost.
+pub fn read_protocol_version<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(Option<u64>, u64), ReadError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let protocol_version_key = protocol_version_storage_key();
+    let (value, gas) = storage
+        .read(&protocol_version_key)
+        .map_err(ReadError::StorageError)?;
+    let protocol_version: Option<u64> = value
+        .map(decode)
+        .transpose()
+        .map_err(ReadError::StorageTypeError)?;
+
+    Ok((protocol_version, gas))
+}
+
+/// Read the scheduled upgrade of the protocol version from store, if any.
+/// Returns the upgrade and gas cost.
+pub fn read_protocol_upgrade<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(Option<ProtocolUpgrade>, u64), ReadError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let protocol_upgrade_key = protocol_upgrade_storage_key();
+    let (value, gas) = storage
+        .read(&protocol_upgrade_key)
+        .map_err(ReadError::StorageError)?;
+    let protocol_upgrade: Option<ProtocolUpgrade> = value
+        .map(decode)
+        .transpose()
+        .map_err(ReadError::StorageTypeError)?;
+
+    Ok((protocol_upgrade, gas))
+}
+
+/// Read the random seed of the current epoch from store. The seed is all
+/// zeros if it hasn't been set yet. Returns the seed and gas cost.
+pub fn read_epoch_seed<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(Hash, u64), ReadError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let epoch_seed_key = epoch_seed_storage_key();
+    let (value, gas) = storage
+        .read(&epoch_seed_key)
+        .map_err(ReadError::StorageError)?;
+    let epoch_seed: Hash = value
+        .map(decode)
+        .transpose()
+        .map_err(ReadError::StorageTypeError)?
+        .unwrap_or(Hash([0; HASH_LENGTH]));
+
+    Ok((epoch_seed, gas))
+}
+
+/// Read the random seed of the current epoch for a transaction applied in a
+/// block. The seed of a new epoch is written to the block's write log at the
+/// beginning of its first block, so it's read from there before the store.
+/// Returns the seed and gas cost.
+pub fn read_block_epoch_seed<DB, H>(
+    storage: &Storage<DB, H>,
+    write_log: &WriteLog,
+) -> std::result::Result<(Hash, u64), ReadError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let (value, gas) = write_log.read_pre(&epoch_seed_storage_key());
+    match value {
+        Some(StorageModification::Write { value }) => {
+            let epoch_seed =
+                decode(value).map_err(ReadError::StorageTypeError)?;
+            Ok((epoch_seed, gas))
+        }
+        _ => {
+            // The seed may have been staged in the storage with the block's
+            // changes
+            let (value, read_gas) = storage
+                .read_with_staged(&epoch_seed_storage_key())
+                .map_err(ReadError::StorageError)?;
+            let epoch_seed: Hash = value
+                .map(decode)
+                .transpose()
+                .map_err(ReadError::StorageTypeError)?
+                .unwrap_or(Hash([0; HASH_LENGTH]));
+            Ok((epoch_seed, gas + read_gas))
+        }
+    }
+}
+
+/// Read the VP whitelist parameter from store. The whitelist is empty if it
+/// hasn't been set. Returns the whitelist and gas cost.
+pub fn read_vp_whitelist<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(Vec<String>, u64), ReadError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let vp_whitelist_key = vp_whitelist_storage_key();
+    let (value, gas) = storage
+        .read(&vp_whitelist_key)
+        .map_err(ReadError::StorageError)?;
+    let vp_whitelist: Vec<String> = value
+        .map(decode)
+        .transpose()
+        .map_err(ReadError::StorageTypeError)?
+        .unwrap_or_default();
+
+    Ok((vp_whitelist, gas))
+}
+
+/// Read the addresses of the registered matchmakers from store. Returns the
+/// matchmakers and gas cost.
+pub fn read_matchmakers<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(Vec<Address>, u64), ReadError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let matchmakers_key = matchmakers_storage_key();
+    let (value, gas) = storage
+        .read(&matchmakers_key)
+        .map_err(ReadError::StorageError)?;
+    let matchmakers: Vec<Address> = value
+        .map(decode)
+        .transpose()
+        .map_err(ReadError::StorageTypeError)?
+        .unwrap_or_default();
+
+    Ok((matchmakers, gas))
+}
+
+/// Check if a VP with the given code may be used by an account. An empty
+/// whitelist permits any VP.
+pub fn is_vp_whitelisted(vp_whitelist: &[String], vp_code: &[u8]) -> bool {
+    vp_whitelist.is_empty()
+        || vp_whitelist.contains(&Hash::sha256(vp_code).to_string())
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum WriteError {
+    #[error("Storage error: {0}")]
+    StorageError(storage::Error),
+    #[error("Serialize error: {0}")]
+    SerializeError(String),
+}
+
+/// Update the  parameters in storage. Returns the parameters and gas
+/// cost.
+pub fn update<DB, H, T>(
+    storage: &mut Storage<DB, H>,
+    value: &T,
+    key: Key,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+    T: BorshSerialize,
+{
+    let serialized_value = value
+        .try_to_vec()
+        .map_err(|e| WriteError::SerializeError(e.to_string()))?;
+    let (gas, _size_diff) = storage
+        .write(&key, serialized_value)
+        .map_err(WriteError::StorageError)?;
+    Ok(gas)
+}
+
+/// Update the epoch parameter in storage. Returns the parameters and gas
+/// cost.
+pub fn update_epoch_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &EpochDuration,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let key = epoch_storage_key();
+    update(storage, value, key)
+}
+
+/// Update the tx whitelist parameter in storage. Returns the parameters and gas
+/// cost.
+pub fn update_tx_whitelist_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: Vec<String>,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let key = tx_whitelist_storage_key();
+    update(storage, &value, key)
+}
+
+/// Update the vp whitelist parameter in storage. Returns the parameters and gas
+/// cost.
+pub fn update_vp_whitelist_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: Vec<String>,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let key = vp_whitelist_storage_key();
+    update(storage, &value, key)
+}
+
+/// Update the max_expected_time_per_block parameter in storage. Returns the
+/// parameters and gas cost.
+pub fn update_max_expected_time_per_block_parameter<DB, H>(
     storage: &mut Storage<DB, H>,
     value: &DurationSecs,
 ) -> std::result::Result<u64, WriteError>
@@ -296,6 +1202,182 @@ where
     update(storage, value, key)
 }
 
+/// Update the base fee in storage. Returns the gas cost.
+pub fn update_base_fee_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &token::Amount,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let key = base_fee_storage_key();
+    update(storage, value, key)
+}
+
+/// Update the min base fee parameter in storage. Returns the gas cost.
+pub fn update_min_base_fee_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &token::Amount,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let key = min_base_fee_storage_key();
+    update(storage, value, key)
+}
+
+/// Update the tx ordering parameter in storage. Returns the gas cost.
+pub fn update_tx_ordering_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &TxOrdering,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let key = tx_ordering_storage_key();
+    update(storage, value, key)
+}
+
+/// Update the tx size limits parameter in storage. Returns the gas cost.
+pub fn update_tx_size_limits_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &TxSizeLimits,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let key = tx_size_limits_storage_key();
+    update(storage, value, key)
+}
+
+/// Update the fee exemption parameter in storage. Returns the gas cost.
+pub fn update_fee_exemption_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &FeeExemption,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let key = fee_exemption_storage_key();
+    update(storage, value, key)
+}
+
+/// Update the fee token rates parameter in storage. Returns the gas cost.
+pub fn update_fee_token_rates_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &FeeTokenRates,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let key = fee_token_rates_storage_key();
+    update(storage, value, key)
+}
+
+/// Update the fee refund policy parameter in storage. Returns the gas cost.
+pub fn update_fee_refund_policy_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &FeeRefundPolicy,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let key = fee_refund_policy_storage_key();
+    update(storage, value, key)
+}
+
+/// Update the storage gas costs parameter in storage. Returns the gas cost.
+pub fn update_storage_gas_costs_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &StorageGasCosts,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let key = storage_gas_costs_storage_key();
+    update(storage, value, key)
+}
+
+/// Update the write log flush threshold parameter in storage. Returns the gas
+/// cost.
+pub fn update_write_log_flush_threshold_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &Option<u64>,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let key = write_log_flush_threshold_storage_key();
+    update(storage, value, key)
+}
+
+/// Derive the seed of a new epoch from the seed of the previous epoch and the
+/// hash of its last block. The seed only depends on the chain's history, so
+/// every node computes the same one.
+pub fn next_epoch_seed(
+    last_seed: &Hash,
+    last_block_hash: &BlockHash,
+    epoch: Epoch,
+) -> Hash {
+    let mut data = Vec::with_capacity(2 * HASH_LENGTH + 8);
+    data.extend_from_slice(&last_seed.0);
+    data.extend_from_slice(&last_block_hash.0);
+    data.extend_from_slice(&epoch.0.to_be_bytes());
+    Hash::sha256(data)
+}
+
+/// Update the random seed of the current epoch in storage. Returns the gas
+/// cost.
+pub fn update_epoch_seed<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &Hash,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let key = epoch_seed_storage_key();
+    update(storage, value, key)
+}
+
+/// Schedule an upgrade of the protocol version, e.g. on the execution of an
+/// accepted governance upgrade proposal. It replaces any upgrade scheduled
+/// before. Returns the gas cost.
+pub fn schedule_protocol_upgrade<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &ProtocolUpgrade,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let key = protocol_upgrade_storage_key();
+    update(storage, value, key)
+}
+
+/// Update the protocol version of the chain in storage. Returns the gas
+/// cost.
+pub fn update_protocol_version<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: u64,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let key = protocol_version_storage_key();
+    update(storage, &value, key)
+}
+
 impl<'a, DB, H, CA> NativeVp for ParametersVp<'a, DB, H, CA>
 where
     DB: 'static + storage::DB + for<'iter> storage::DBIter<'iter>,
@@ -358,6 +1440,146 @@ pub fn max_expected_time_per_block_key() -> Key {
     }
 }
 
+/// Storage key used for the base fee per unit of gas.
+pub fn base_fee_storage_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(Address::Internal(ADDR)),
+            DbKeySeg::StringSeg(BASE_FEE_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for the min base fee parameter.
+pub fn min_base_fee_storage_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(Address::Internal(ADDR)),
+            DbKeySeg::StringSeg(MIN_BASE_FEE_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for the tx ordering parameter.
+pub fn tx_ordering_storage_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(Address::Internal(ADDR)),
+            DbKeySeg::StringSeg(TX_ORDERING_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for the tx size limits parameter.
+pub fn tx_size_limits_storage_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(Address::Internal(ADDR)),
+            DbKeySeg::StringSeg(TX_SIZE_LIMITS_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for the fee exemption parameter.
+pub fn fee_exemption_storage_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(Address::Internal(ADDR)),
+            DbKeySeg::StringSeg(FEE_EXEMPTION_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for the fee token rates parameter.
+pub fn fee_token_rates_storage_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(Address::Internal(ADDR)),
+            DbKeySeg::StringSeg(FEE_TOKEN_RATES_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for the fee refund policy parameter.
+pub fn fee_refund_policy_storage_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(Address::Internal(ADDR)),
+            DbKeySeg::StringSeg(FEE_REFUND_POLICY_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for the storage gas costs parameter.
+pub fn storage_gas_costs_storage_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(Address::Internal(ADDR)),
+            DbKeySeg::StringSeg(STORAGE_GAS_COSTS_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for the write log flush threshold parameter.
+pub fn write_log_flush_threshold_storage_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(Address::Internal(ADDR)),
+            DbKeySeg::StringSeg(WRITE_LOG_FLUSH_THRESHOLD_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for the genesis time.
+pub fn genesis_time_storage_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(Address::Internal(ADDR)),
+            DbKeySeg::StringSeg(GENESIS_TIME_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for the random seed of the current epoch.
+pub fn epoch_seed_storage_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(Address::Internal(ADDR)),
+            DbKeySeg::StringSeg(EPOCH_SEED_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for the addresses of the registered matchmakers.
+pub fn matchmakers_storage_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(Address::Internal(ADDR)),
+            DbKeySeg::StringSeg(MATCHMAKERS_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for the protocol version of the chain.
+pub fn protocol_version_storage_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(Address::Internal(ADDR)),
+            DbKeySeg::StringSeg(PROTOCOL_VERSION_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for the scheduled upgrade of the protocol version.
+pub fn protocol_upgrade_storage_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(Address::Internal(ADDR)),
+            DbKeySeg::StringSeg(PROTOCOL_UPGRADE_KEY.to_string()),
+        ],
+    }
+}
+
 impl From<native_vp::Error> for Error {
     fn from(err: native_vp::Error) -> Self {
         Self::NativeVpError(err)
@@ -6,7 +6,7 @@ pub mod vp;
 pub use anoma_proof_of_stake;
 pub use anoma_proof_of_stake::parameters::PosParams;
 pub use anoma_proof_of_stake::types::{
-    self, Slash, Slashes, TotalVotingPowers, ValidatorStates,
+    self, CommissionRates, Slash, Slashes, TotalVotingPowers, ValidatorStates,
     ValidatorVotingPowers,
 };
 use anoma_proof_of_stake::PosBase;
@@ -59,6 +59,9 @@ pub type Bonds = anoma_proof_of_stake::types::Bonds<token::Amount>;
 /// Alias for a PoS type with the same name with concrete type parameters
 pub type Unbonds = anoma_proof_of_stake::types::Unbonds<token::Amount>;
 
+/// Alias for a PoS type with the same name with concrete type parameters
+pub type Rewards = anoma_proof_of_stake::types::Rewards<token::Amount>;
+
 /// Alias for a PoS type with the same name with concrete type parameters
 pub type ValidatorSets = anoma_proof_of_stake::types::ValidatorSets<Address>;
 
@@ -2,15 +2,16 @@
 
 use anoma_proof_of_stake::parameters::PosParams;
 use anoma_proof_of_stake::types::{
-    TotalVotingPowers, ValidatorStates, ValidatorVotingPowers,
+    CommissionRates, TotalVotingPowers, ValidatorStates, ValidatorVotingPowers,
 };
 use anoma_proof_of_stake::{types, PosBase};
 
 use super::{
-    BondId, Bonds, ValidatorConsensusKeys, ValidatorSets, ValidatorTotalDeltas,
-    ADDRESS,
+    BondId, Bonds, Rewards, ValidatorConsensusKeys, ValidatorSets,
+    ValidatorTotalDeltas, ADDRESS,
 };
 use crate::ledger::storage::types::{decode, encode};
+use crate::ledger::storage::write_log::WriteLogStorage;
 use crate::ledger::storage::{self, Storage, StorageHasher};
 use crate::types::address::Address;
 use crate::types::storage::{DbKeySeg, Key, KeySeg};
@@ -25,9 +26,14 @@ const VALIDATOR_CONSENSUS_KEY_STORAGE_KEY: &str = "consensus_key";
 const VALIDATOR_STATE_STORAGE_KEY: &str = "state";
 const VALIDATOR_TOTAL_DELTAS_STORAGE_KEY: &str = "total_deltas";
 const VALIDATOR_VOTING_POWER_STORAGE_KEY: &str = "voting_power";
+const VALIDATOR_COMMISSION_RATE_STORAGE_KEY: &str = "commission_rate";
+const VALIDATOR_METADATA_STORAGE_KEY: &str = "metadata";
+const VALIDATOR_MISSED_BLOCKS_STORAGE_KEY: &str = "missed_blocks";
+const VALIDATOR_TOMBSTONED_STORAGE_KEY: &str = "tombstoned";
 const SLASHES_PREFIX: &str = "slash";
 const BOND_STORAGE_KEY: &str = "bond";
 const UNBOND_STORAGE_KEY: &str = "unbond";
+const REWARDS_STORAGE_KEY: &str = "rewards";
 const VALIDATOR_SET_STORAGE_KEY: &str = "validator_set";
 const TOTAL_VOTING_POWER_STORAGE_KEY: &str = "total_voting_power";
 
@@ -67,6 +73,22 @@ fn validator_prefix(validator: &Address) -> Key {
         .expect("Cannot obtain a storage key")
 }
 
+/// Is storage key for any data of a validator? If it is, returns the
+/// validator's address.
+pub fn is_validator_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(validator),
+            ..,
+        ] if addr == &ADDRESS && prefix == VALIDATOR_STORAGE_PREFIX => {
+            Some(validator)
+        }
+        _ => None,
+    }
+}
+
 /// Storage key for validator's address raw hash for look-up from raw hash of an
 /// address to address.
 pub fn validator_address_raw_hash_key(raw_hash: impl AsRef<str>) -> Key {
@@ -217,6 +239,108 @@ pub fn is_validator_voting_power_key(key: &Key) -> Option<&Address> {
     }
 }
 
+/// Storage key for validator's commission rate.
+pub fn validator_commission_rate_key(validator: &Address) -> Key {
+    validator_prefix(validator)
+        .push(&VALIDATOR_COMMISSION_RATE_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Is storage key for validator's commission rate?
+pub fn is_validator_commission_rate_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(validator),
+            DbKeySeg::StringSeg(key),
+        ] if addr == &ADDRESS
+            && prefix == VALIDATOR_STORAGE_PREFIX
+            && key == VALIDATOR_COMMISSION_RATE_STORAGE_KEY =>
+        {
+            Some(validator)
+        }
+        _ => None,
+    }
+}
+
+/// Storage key for validator's metadata.
+pub fn validator_metadata_key(validator: &Address) -> Key {
+    validator_prefix(validator)
+        .push(&VALIDATOR_METADATA_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Is storage key for validator's metadata?
+pub fn is_validator_metadata_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(validator),
+            DbKeySeg::StringSeg(key),
+        ] if addr == &ADDRESS
+            && prefix == VALIDATOR_STORAGE_PREFIX
+            && key == VALIDATOR_METADATA_STORAGE_KEY =>
+        {
+            Some(validator)
+        }
+        _ => None,
+    }
+}
+
+/// Storage key for the blocks within the liveness window that a validator
+/// failed to sign.
+pub fn validator_missed_blocks_key(validator: &Address) -> Key {
+    validator_prefix(validator)
+        .push(&VALIDATOR_MISSED_BLOCKS_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Is storage key for the blocks within the liveness window that a validator
+/// failed to sign?
+pub fn is_validator_missed_blocks_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(validator),
+            DbKeySeg::StringSeg(key),
+        ] if addr == &ADDRESS
+            && prefix == VALIDATOR_STORAGE_PREFIX
+            && key == VALIDATOR_MISSED_BLOCKS_STORAGE_KEY =>
+        {
+            Some(validator)
+        }
+        _ => None,
+    }
+}
+
+/// Storage key for the flag of a tombstoned validator.
+pub fn validator_tombstoned_key(validator: &Address) -> Key {
+    validator_prefix(validator)
+        .push(&VALIDATOR_TOMBSTONED_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Is storage key for the flag of a tombstoned validator?
+pub fn is_validator_tombstoned_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(validator),
+            DbKeySeg::StringSeg(key),
+        ] if addr == &ADDRESS
+            && prefix == VALIDATOR_STORAGE_PREFIX
+            && key == VALIDATOR_TOMBSTONED_STORAGE_KEY =>
+        {
+            Some(validator)
+        }
+        _ => None,
+    }
+}
+
 /// Storage prefix for slashes.
 pub fn slashes_prefix() -> Key {
     Key::from(ADDRESS.to_db_key())
@@ -317,6 +441,44 @@ pub fn is_unbond_key(key: &Key) -> Option<BondId> {
     }
 }
 
+/// Storage key prefix for all unclaimed rewards.
+pub fn rewards_prefix() -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&REWARDS_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Storage key prefix for all unclaimed rewards of the given source address.
+pub fn rewards_for_source_prefix(source: &Address) -> Key {
+    rewards_prefix()
+        .push(&source.to_db_key())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Storage key for unclaimed rewards of a bond with the given ID (source and
+/// validator).
+pub fn rewards_key(bond_id: &BondId) -> Key {
+    rewards_for_source_prefix(&bond_id.source)
+        .push(&bond_id.validator.to_db_key())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Is storage key for unclaimed rewards?
+pub fn is_rewards_key(key: &Key) -> Option<BondId> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(source),
+            DbKeySeg::AddressSeg(validator),
+        ] if addr == &ADDRESS && prefix == REWARDS_STORAGE_KEY => Some(BondId {
+            source: source.clone(),
+            validator: validator.clone(),
+        }),
+        _ => None,
+    }
+}
+
 /// Storage key for validator set (active and inactive).
 pub fn validator_set_key() -> Key {
     Key::from(ADDRESS.to_db_key())
@@ -355,245 +517,346 @@ pub fn is_total_voting_power_key(key: &Key) -> bool {
     }
 }
 
-impl<D, H> PosBase for Storage<D, H>
-where
-    D: storage::DB + for<'iter> storage::DBIter<'iter>,
-    H: StorageHasher,
-{
-    type Address = Address;
-    type PublicKey = key::common::PublicKey;
-    type TokenAmount = token::Amount;
-    type TokenChange = token::Change;
+/// Implement [`PosBase`] for a storage type with `read`, `write` and
+/// `iter_prefix` methods like those of [`Storage`].
+macro_rules! impl_pos_base {
+    (impl<$($gen:tt),*> PosBase for $ty:ty) => {
+        impl<$($gen),*> PosBase for $ty
+        where
+            D: storage::DB + for<'iter> storage::DBIter<'iter>,
+            H: StorageHasher,
+        {
+            type Address = Address;
+            type PublicKey = key::common::PublicKey;
+            type TokenAmount = token::Amount;
+            type TokenChange = token::Change;
 
-    const POS_ADDRESS: Self::Address = super::ADDRESS;
-    const POS_SLASH_POOL_ADDRESS: Self::Address = super::SLASH_POOL_ADDRESS;
+            const POS_ADDRESS: Self::Address = super::ADDRESS;
+            const POS_SLASH_POOL_ADDRESS: Self::Address =
+                super::SLASH_POOL_ADDRESS;
 
-    fn staking_token_address() -> Self::Address {
-        super::staking_token_address()
-    }
+            fn staking_token_address() -> Self::Address {
+                super::staking_token_address()
+            }
 
-    fn read_pos_params(&self) -> PosParams {
-        let (value, _gas) = self.read(&params_key()).unwrap();
-        decode(value.unwrap()).unwrap()
-    }
+            fn read_pos_params(&self) -> PosParams {
+                let (value, _gas) = self.read(&params_key()).unwrap();
+                decode(value.unwrap()).unwrap()
+            }
 
-    fn read_validator_address_raw_hash(
-        &self,
-        raw_hash: impl AsRef<str>,
-    ) -> Option<Self::Address> {
-        let (value, _gas) = self
-            .read(&validator_address_raw_hash_key(raw_hash))
-            .unwrap();
-        value.map(|value| decode(value).unwrap())
-    }
+            fn read_validator_address_raw_hash(
+                &self,
+                raw_hash: impl AsRef<str>,
+            ) -> Option<Self::Address> {
+                let (value, _gas) = self
+                    .read(&validator_address_raw_hash_key(raw_hash))
+                    .unwrap();
+                value.map(|value| decode(value).unwrap())
+            }
 
-    fn read_validator_consensus_key(
-        &self,
-        key: &Self::Address,
-    ) -> Option<ValidatorConsensusKeys> {
-        let (value, _gas) =
-            self.read(&validator_consensus_key_key(key)).unwrap();
-        value.map(|value| decode(value).unwrap())
-    }
+            fn read_validator_consensus_key(
+                &self,
+                key: &Self::Address,
+            ) -> Option<ValidatorConsensusKeys> {
+                let (value, _gas) =
+                    self.read(&validator_consensus_key_key(key)).unwrap();
+                value.map(|value| decode(value).unwrap())
+            }
 
-    fn read_validator_state(
-        &self,
-        key: &Self::Address,
-    ) -> Option<ValidatorStates> {
-        let (value, _gas) = self.read(&validator_state_key(key)).unwrap();
-        value.map(|value| decode(value).unwrap())
-    }
+            fn read_validator_state(
+                &self,
+                key: &Self::Address,
+            ) -> Option<ValidatorStates> {
+                let (value, _gas) =
+                    self.read(&validator_state_key(key)).unwrap();
+                value.map(|value| decode(value).unwrap())
+            }
 
-    fn read_validator_total_deltas(
-        &self,
-        key: &Self::Address,
-    ) -> Option<types::ValidatorTotalDeltas<Self::TokenChange>> {
-        let (value, _gas) =
-            self.read(&validator_total_deltas_key(key)).unwrap();
-        value.map(|value| decode(value).unwrap())
-    }
+            fn read_validator_total_deltas(
+                &self,
+                key: &Self::Address,
+            ) -> Option<types::ValidatorTotalDeltas<Self::TokenChange>> {
+                let (value, _gas) =
+                    self.read(&validator_total_deltas_key(key)).unwrap();
+                value.map(|value| decode(value).unwrap())
+            }
 
-    fn read_validator_voting_power(
-        &self,
-        key: &Self::Address,
-    ) -> Option<ValidatorVotingPowers> {
-        let (value, _gas) =
-            self.read(&validator_voting_power_key(key)).unwrap();
-        value.map(|value| decode(value).unwrap())
-    }
+            fn read_validator_voting_power(
+                &self,
+                key: &Self::Address,
+            ) -> Option<ValidatorVotingPowers> {
+                let (value, _gas) =
+                    self.read(&validator_voting_power_key(key)).unwrap();
+                value.map(|value| decode(value).unwrap())
+            }
 
-    fn read_validator_slashes(&self, key: &Self::Address) -> types::Slashes {
-        let (value, _gas) = self.read(&validator_slashes_key(key)).unwrap();
-        value
-            .map(|value| decode(value).unwrap())
-            .unwrap_or_default()
-    }
+            fn read_validator_slashes(
+                &self,
+                key: &Self::Address,
+            ) -> types::Slashes {
+                let (value, _gas) =
+                    self.read(&validator_slashes_key(key)).unwrap();
+                value
+                    .map(|value| decode(value).unwrap())
+                    .unwrap_or_default()
+            }
 
-    fn read_validator_set(&self) -> ValidatorSets {
-        let (value, _gas) = self.read(&validator_set_key()).unwrap();
-        decode(value.unwrap()).unwrap()
-    }
+            fn is_validator_tombstoned(&self, key: &Self::Address) -> bool {
+                let (value, _gas) =
+                    self.read(&validator_tombstoned_key(key)).unwrap();
+                value
+                    .map(|value| decode(value).unwrap())
+                    .unwrap_or_default()
+            }
 
-    fn read_total_voting_power(&self) -> TotalVotingPowers {
-        let (value, _gas) = self.read(&total_voting_power_key()).unwrap();
-        decode(value.unwrap()).unwrap()
-    }
+            fn read_validator_commission_rate(
+                &self,
+                key: &Self::Address,
+            ) -> Option<CommissionRates> {
+                let (value, _gas) =
+                    self.read(&validator_commission_rate_key(key)).unwrap();
+                value.map(|value| decode(value).unwrap())
+            }
 
-    fn write_pos_params(&mut self, params: &PosParams) {
-        self.write(&params_key(), encode(params)).unwrap();
-    }
+            fn read_validator_missed_blocks(
+                &self,
+                key: &Self::Address,
+            ) -> types::MissedBlocks {
+                let (value, _gas) =
+                    self.read(&validator_missed_blocks_key(key)).unwrap();
+                value
+                    .map(|value| decode(value).unwrap())
+                    .unwrap_or_default()
+            }
 
-    fn write_validator_address_raw_hash(&mut self, address: &Self::Address) {
-        let raw_hash = address.raw_hash().unwrap();
-        self.write(&validator_address_raw_hash_key(raw_hash), encode(address))
-            .unwrap();
-    }
+            fn read_bonds(&self) -> Vec<(BondId, Bonds)> {
+                let (iter, _gas) = self.iter_prefix(&bonds_prefix());
+                iter.filter_map(|(key, value, _gas)| {
+                    let key = Key::parse(key).ok()?;
+                    let bond_id = is_bond_key(&key)?;
+                    Some((bond_id, decode(value).unwrap()))
+                })
+                .collect()
+            }
 
-    fn write_validator_staking_reward_address(
-        &mut self,
-        key: &Self::Address,
-        value: &Self::Address,
-    ) {
-        self.write(&validator_staking_reward_address_key(key), encode(value))
-            .unwrap();
-    }
+            fn read_rewards(&self, key: &BondId) -> Option<Rewards> {
+                let (value, _gas) = self.read(&rewards_key(key)).unwrap();
+                value.map(|value| decode(value).unwrap())
+            }
 
-    fn write_validator_consensus_key(
-        &mut self,
-        key: &Self::Address,
-        value: &ValidatorConsensusKeys,
-    ) {
-        self.write(&validator_consensus_key_key(key), encode(value))
-            .unwrap();
-    }
+            fn read_validator_set(&self) -> ValidatorSets {
+                let (value, _gas) = self.read(&validator_set_key()).unwrap();
+                decode(value.unwrap()).unwrap()
+            }
 
-    fn write_validator_state(
-        &mut self,
-        key: &Self::Address,
-        value: &ValidatorStates,
-    ) {
-        self.write(&validator_state_key(key), encode(value))
-            .unwrap();
-    }
+            fn read_total_voting_power(&self) -> TotalVotingPowers {
+                let (value, _gas) =
+                    self.read(&total_voting_power_key()).unwrap();
+                decode(value.unwrap()).unwrap()
+            }
 
-    fn write_validator_total_deltas(
-        &mut self,
-        key: &Self::Address,
-        value: &ValidatorTotalDeltas,
-    ) {
-        self.write(&validator_total_deltas_key(key), encode(value))
-            .unwrap();
-    }
+            fn write_pos_params(&mut self, params: &PosParams) {
+                self.write(&params_key(), encode(params)).unwrap();
+            }
 
-    fn write_validator_voting_power(
-        &mut self,
-        key: &Self::Address,
-        value: &ValidatorVotingPowers,
-    ) {
-        self.write(&validator_voting_power_key(key), encode(value))
-            .unwrap();
-    }
+            fn write_validator_address_raw_hash(
+                &mut self,
+                address: &Self::Address,
+            ) {
+                let raw_hash = address.raw_hash().unwrap();
+                self.write(
+                    &validator_address_raw_hash_key(raw_hash),
+                    encode(address),
+                )
+                .unwrap();
+            }
 
-    fn write_validator_slash(
-        &mut self,
-        validator: &Self::Address,
-        value: types::Slash,
-    ) {
-        let mut slashes = self.read_validator_slashes(validator);
-        slashes.push(value);
-        self.write(&validator_slashes_key(validator), encode(&slashes))
-            .unwrap();
-    }
+            fn write_validator_staking_reward_address(
+                &mut self,
+                key: &Self::Address,
+                value: &Self::Address,
+            ) {
+                self.write(
+                    &validator_staking_reward_address_key(key),
+                    encode(value),
+                )
+                .unwrap();
+            }
 
-    fn write_bond(&mut self, key: &BondId, value: &Bonds) {
-        self.write(&bond_key(key), encode(value)).unwrap();
-    }
+            fn write_validator_consensus_key(
+                &mut self,
+                key: &Self::Address,
+                value: &ValidatorConsensusKeys,
+            ) {
+                self.write(&validator_consensus_key_key(key), encode(value))
+                    .unwrap();
+            }
 
-    fn write_validator_set(&mut self, value: &ValidatorSets) {
-        self.write(&validator_set_key(), encode(value)).unwrap();
-    }
+            fn write_validator_state(
+                &mut self,
+                key: &Self::Address,
+                value: &ValidatorStates,
+            ) {
+                self.write(&validator_state_key(key), encode(value))
+                    .unwrap();
+            }
 
-    fn write_total_voting_power(&mut self, value: &TotalVotingPowers) {
-        self.write(&total_voting_power_key(), encode(value))
-            .unwrap();
-    }
+            fn write_validator_total_deltas(
+                &mut self,
+                key: &Self::Address,
+                value: &ValidatorTotalDeltas,
+            ) {
+                self.write(&validator_total_deltas_key(key), encode(value))
+                    .unwrap();
+            }
 
-    fn init_staking_reward_account(
-        &mut self,
-        address: &Self::Address,
-        pk: &Self::PublicKey,
-    ) {
-        // let user_vp =
-        //     std::fs::read("wasm/vp_user.wasm").expect("cannot load user VP");
-        // // The staking reward accounts are setup with a user VP
-        // self.write(&Key::validity_predicate(address), user_vp.to_vec())
-        //     .unwrap();
-
-        // Write the public key
-        let pk_key = key::pk_key(address);
-        self.write(&pk_key, encode(pk)).unwrap();
-    }
+            fn write_validator_voting_power(
+                &mut self,
+                key: &Self::Address,
+                value: &ValidatorVotingPowers,
+            ) {
+                self.write(&validator_voting_power_key(key), encode(value))
+                    .unwrap();
+            }
 
-    fn credit_tokens(
-        &mut self,
-        token: &Self::Address,
-        target: &Self::Address,
-        amount: Self::TokenAmount,
-    ) {
-        let key = token::balance_key(token, target);
-        let new_balance = match self
-            .read(&key)
-            .expect("Unable to read token balance for PoS system")
-        {
-            (Some(balance), _gas) => {
-                let balance: Self::TokenAmount =
-                    decode(balance).unwrap_or_default();
-                balance + amount
-            }
-            _ => amount,
-        };
-        self.write(&key, encode(&new_balance))
-            .expect("Unable to write token balance for PoS system");
-    }
+            fn write_validator_commission_rate(
+                &mut self,
+                key: &Self::Address,
+                value: &CommissionRates,
+            ) {
+                self.write(&validator_commission_rate_key(key), encode(value))
+                    .unwrap();
+            }
 
-    fn transfer(
-        &mut self,
-        token: &Self::Address,
-        amount: Self::TokenAmount,
-        src: &Self::Address,
-        dest: &Self::Address,
-    ) {
-        let src_key = token::balance_key(token, src);
-        let dest_key = token::balance_key(token, dest);
-        if let (Some(src_balance), _gas) = self
-            .read(&src_key)
-            .expect("Unable to read token balance for PoS system")
-        {
-            let mut src_balance: Self::TokenAmount =
-                decode(src_balance).unwrap_or_default();
-            if src_balance < amount {
-                tracing::error!(
-                    "PoS system transfer error, the source doesn't have \
-                     sufficient balance. It has {}, but {} is required",
-                    src_balance,
-                    amount
-                );
-                return;
-            }
-            src_balance.spend(&amount);
-            let (dest_balance, _gas) = self.read(&dest_key).unwrap_or_default();
-            let mut dest_balance: Self::TokenAmount = dest_balance
-                .and_then(|b| decode(b).ok())
-                .unwrap_or_default();
-            dest_balance.receive(&amount);
-            self.write(&src_key, encode(&src_balance))
-                .expect("Unable to write token balance for PoS system");
-            self.write(&dest_key, encode(&dest_balance))
-                .expect("Unable to write token balance for PoS system");
-        } else {
-            tracing::error!(
-                "PoS system transfer error, the source has no balance"
-            );
+            fn write_validator_slash(
+                &mut self,
+                validator: &Self::Address,
+                value: types::Slash,
+            ) {
+                let mut slashes = self.read_validator_slashes(validator);
+                slashes.push(value);
+                self.write(&validator_slashes_key(validator), encode(&slashes))
+                    .unwrap();
+            }
+
+            fn write_validator_tombstoned(&mut self, key: &Self::Address) {
+                self.write(&validator_tombstoned_key(key), encode(&true))
+                    .unwrap();
+            }
+
+            fn write_validator_missed_blocks(
+                &mut self,
+                key: &Self::Address,
+                value: &types::MissedBlocks,
+            ) {
+                self.write(&validator_missed_blocks_key(key), encode(value))
+                    .unwrap();
+            }
+
+            fn write_bond(&mut self, key: &BondId, value: &Bonds) {
+                self.write(&bond_key(key), encode(value)).unwrap();
+            }
+
+            fn write_rewards(&mut self, key: &BondId, value: &Rewards) {
+                self.write(&rewards_key(key), encode(value)).unwrap();
+            }
+
+            fn write_validator_set(&mut self, value: &ValidatorSets) {
+                self.write(&validator_set_key(), encode(value)).unwrap();
+            }
+
+            fn write_total_voting_power(&mut self, value: &TotalVotingPowers) {
+                self.write(&total_voting_power_key(), encode(value))
+                    .unwrap();
+            }
+
+            fn init_staking_reward_account(
+                &mut self,
+                address: &Self::Address,
+                pk: &Self::PublicKey,
+            ) {
+                // let user_vp =
+                //     std::fs::read("wasm/vp_user.wasm")
+                //         .expect("cannot load user VP");
+                // // The staking reward accounts are setup with a user VP
+                // self.write(
+                //     &Key::validity_predicate(address),
+                //     user_vp.to_vec(),
+                // )
+                //     .unwrap();
+
+                // Write the public key
+                let pk_key = key::pk_key(address);
+                self.write(&pk_key, encode(pk)).unwrap();
+            }
+
+            fn credit_tokens(
+                &mut self,
+                token: &Self::Address,
+                target: &Self::Address,
+                amount: Self::TokenAmount,
+            ) {
+                let key = token::balance_key(token, target);
+                let new_balance = match self
+                    .read(&key)
+                    .expect("Unable to read token balance for PoS system")
+                {
+                    (Some(balance), _gas) => {
+                        let balance: Self::TokenAmount =
+                            decode(balance).unwrap_or_default();
+                        balance + amount
+                    }
+                    _ => amount,
+                };
+                self.write(&key, encode(&new_balance))
+                    .expect("Unable to write token balance for PoS system");
+            }
+
+            fn transfer(
+                &mut self,
+                token: &Self::Address,
+                amount: Self::TokenAmount,
+                src: &Self::Address,
+                dest: &Self::Address,
+            ) {
+                let src_key = token::balance_key(token, src);
+                let dest_key = token::balance_key(token, dest);
+                if let (Some(src_balance), _gas) = self
+                    .read(&src_key)
+                    .expect("Unable to read token balance for PoS system")
+                {
+                    let mut src_balance: Self::TokenAmount =
+                        decode(src_balance).unwrap_or_default();
+                    if src_balance < amount {
+                        tracing::error!(
+                            "PoS system transfer error, the source doesn't \
+                             have sufficient balance. It has {}, but {} is \
+                             required",
+                            src_balance,
+                            amount
+                        );
+                        return;
+                    }
+                    src_balance.spend(&amount);
+                    let (dest_balance, _gas) =
+                        self.read(&dest_key).unwrap_or_default();
+                    let mut dest_balance: Self::TokenAmount = dest_balance
+                        .and_then(|b| decode(b).ok())
+                        .unwrap_or_default();
+                    dest_balance.receive(&amount);
+                    self.write(&src_key, encode(&src_balance))
+                        .expect("Unable to write token balance for PoS system");
+                    self.write(&dest_key, encode(&dest_balance))
+                        .expect("Unable to write token balance for PoS system");
+                } else {
+                    tracing::error!(
+                        "PoS system transfer error, the source has no balance"
+                    );
+                }
+            }
         }
-    }
+    };
 }
+
+impl_pos_base!(impl<D, H> PosBase for Storage<D, H>);
+impl_pos_base!(impl<'a, D, H> PosBase for WriteLogStorage<'a, D, H>);
@@ -4,37 +4,44 @@ use std::collections::BTreeSet;
 use std::panic::{RefUnwindSafe, UnwindSafe};
 
 pub use anoma_proof_of_stake;
+use anoma_proof_of_stake::epoched::DynEpochOffset;
 pub use anoma_proof_of_stake::parameters::PosParams;
+use anoma_proof_of_stake::types::ValidatorState;
 pub use anoma_proof_of_stake::types::{
-    self, Slash, Slashes, TotalVotingPowers, ValidatorStates,
+    self, CommissionRates, Slash, Slashes, TotalVotingPowers, ValidatorStates,
     ValidatorVotingPowers,
 };
-use anoma_proof_of_stake::validation::validate;
+use anoma_proof_of_stake::validation::{
+    bond_amount_at_pipeline, check_consensus_key_change, check_unjail, validate,
+};
 use anoma_proof_of_stake::{validation, PosReadOnly};
 use borsh::BorshDeserialize;
 use itertools::Itertools;
 use thiserror::Error;
 
 use super::{
-    bond_key, is_bond_key, is_params_key, is_total_voting_power_key,
-    is_unbond_key, is_validator_set_key,
-    is_validator_staking_reward_address_key, is_validator_total_deltas_key,
-    is_validator_voting_power_key, params_key, staking_token_address,
-    total_voting_power_key, unbond_key, validator_consensus_key_key,
+    bond_key, is_bond_key, is_params_key, is_rewards_key,
+    is_total_voting_power_key, is_unbond_key, is_validator_commission_rate_key,
+    is_validator_set_key, is_validator_staking_reward_address_key,
+    is_validator_total_deltas_key, is_validator_voting_power_key, params_key,
+    rewards_key, staking_token_address, total_voting_power_key, unbond_key,
+    validator_commission_rate_key, validator_consensus_key_key,
     validator_set_key, validator_slashes_key,
     validator_staking_reward_address_key, validator_state_key,
-    validator_total_deltas_key, validator_voting_power_key, BondId, Bonds,
-    Unbonds, ValidatorConsensusKeys, ValidatorSets, ValidatorTotalDeltas,
+    validator_tombstoned_key, validator_total_deltas_key,
+    validator_voting_power_key, BondId, Bonds, Rewards, Unbonds,
+    ValidatorConsensusKeys, ValidatorSets, ValidatorTotalDeltas,
 };
 use crate::ledger::native_vp::{self, Ctx, NativeVp};
 use crate::ledger::pos::{
     is_validator_address_raw_hash_key, is_validator_consensus_key_key,
-    is_validator_state_key,
+    is_validator_metadata_key, is_validator_state_key,
 };
-use crate::ledger::storage::types::decode;
+use crate::ledger::storage::types::{decode, decode_versioned};
 use crate::ledger::storage::{self as ledger_storage, StorageHasher};
 use crate::types::address::{Address, InternalAddress};
 use crate::types::storage::{Key, KeySeg};
+use crate::types::transaction::pos::ValidatorMetadata;
 use crate::types::{key, token};
 use crate::vm::WasmCacheAccess;
 
@@ -106,6 +113,14 @@ where
         let addr = Address::Internal(Self::ADDR);
         let mut changes: Vec<DataUpdate<_, _, _, _>> = vec![];
         let current_epoch = self.ctx.get_block_epoch()?;
+        let params = self.read_pos_params();
+        // Validators that received new delegations
+        let mut delegated_validators: BTreeSet<Address> = BTreeSet::default();
+        // Jailed validators that become candidates at the pipeline offset
+        let mut unjailed_validators: BTreeSet<Address> = BTreeSet::default();
+        // Validators that rotate their consensus key at the pipeline offset,
+        // with their prior consensus keys and the new key
+        let mut rotated_validators = vec![];
         for key in keys_changed {
             if is_params_key(key) {
                 // TODO parameters changes are not yet implemented
@@ -135,6 +150,24 @@ where
                 let post = self.ctx.read_post(key)?.and_then(|bytes| {
                     ValidatorStates::try_from_slice(&bytes[..]).ok()
                 });
+                let state_at_pipeline = |states: Option<&ValidatorStates>| {
+                    states
+                        .and_then(|states| {
+                            states.get_at_offset(
+                                current_epoch,
+                                DynEpochOffset::PipelineLen,
+                                &params,
+                            )
+                        })
+                        .cloned()
+                };
+                if state_at_pipeline(pre.as_ref())
+                    == Some(ValidatorState::Inactive)
+                    && state_at_pipeline(post.as_ref())
+                        == Some(ValidatorState::Candidate)
+                {
+                    unjailed_validators.insert(validator.clone());
+                }
                 changes.push(Validator {
                     address: validator.clone(),
                     update: State(Data { pre, post }),
@@ -154,6 +187,19 @@ where
                     address: validator.clone(),
                     update: StakingRewardAddress(Data { pre, post }),
                 });
+            } else if let Some(validator) =
+                is_validator_commission_rate_key(key)
+            {
+                let pre = self.ctx.read_pre(key)?.and_then(|bytes| {
+                    CommissionRates::try_from_slice(&bytes[..]).ok()
+                });
+                let post = self.ctx.read_post(key)?.and_then(|bytes| {
+                    CommissionRates::try_from_slice(&bytes[..]).ok()
+                });
+                changes.push(Validator {
+                    address: validator.clone(),
+                    update: CommissionRate(Data { pre, post }),
+                });
             } else if let Some(validator) = is_validator_consensus_key_key(key)
             {
                 let pre = self.ctx.read_pre(key)?.and_then(|bytes| {
@@ -162,6 +208,26 @@ where
                 let post = self.ctx.read_post(key)?.and_then(|bytes| {
                     ValidatorConsensusKeys::try_from_slice(&bytes[..]).ok()
                 });
+                if let (Some(pre), Some(post)) = (&pre, &post) {
+                    let key_at_pipeline =
+                        |keys: &ValidatorConsensusKeys| -> Option<_> {
+                            keys.get_at_offset(
+                                current_epoch,
+                                DynEpochOffset::PipelineLen,
+                                &params,
+                            )
+                            .cloned()
+                        };
+                    if let Some(new_key) = key_at_pipeline(post) {
+                        if key_at_pipeline(pre).as_ref() != Some(&new_key) {
+                            rotated_validators.push((
+                                validator.clone(),
+                                pre.clone(),
+                                new_key,
+                            ));
+                        }
+                    }
+                }
                 changes.push(Validator {
                     address: validator.clone(),
                     update: ConsensusKey(Data { pre, post }),
@@ -220,12 +286,14 @@ where
                 if owner != &addr {
                     continue;
                 }
-                let pre = self.ctx.read_pre(key)?.and_then(|bytes| {
-                    token::Amount::try_from_slice(&bytes[..]).ok()
-                });
-                let post = self.ctx.read_post(key)?.and_then(|bytes| {
-                    token::Amount::try_from_slice(&bytes[..]).ok()
-                });
+                let pre = self
+                    .ctx
+                    .read_pre(key)?
+                    .and_then(|bytes| decode_versioned(&bytes).ok());
+                let post = self
+                    .ctx
+                    .read_post(key)?
+                    .and_then(|bytes| decode_versioned(&bytes).ok());
                 changes.push(Balance(Data { pre, post }));
             } else if let Some(bond_id) = is_bond_key(key) {
                 let pre = self
@@ -242,6 +310,19 @@ where
                     .read_pre(&validator_slashes_key(&bond_id.validator))?
                     .and_then(|bytes| Slashes::try_from_slice(&bytes[..]).ok())
                     .unwrap_or_default();
+                if bond_id.source != bond_id.validator
+                    && bond_amount_at_pipeline(
+                        post.as_ref(),
+                        current_epoch,
+                        &params,
+                    ) > bond_amount_at_pipeline(
+                        pre.as_ref(),
+                        current_epoch,
+                        &params,
+                    )
+                {
+                    delegated_validators.insert(bond_id.validator.clone());
+                }
                 changes.push(Bond {
                     id: bond_id.clone(),
                     data: Data { pre, post },
@@ -267,6 +348,19 @@ where
                     data: Data { pre, post },
                     slashes,
                 });
+            } else if let Some(rewards_id) = is_rewards_key(key) {
+                let pre = self
+                    .ctx
+                    .read_pre(key)?
+                    .and_then(|bytes| Rewards::try_from_slice(&bytes[..]).ok());
+                let post = self
+                    .ctx
+                    .read_post(key)?
+                    .and_then(|bytes| Rewards::try_from_slice(&bytes[..]).ok());
+                changes.push(Reward {
+                    id: rewards_id,
+                    data: Data { pre, post },
+                });
             } else if is_total_voting_power_key(key) {
                 let pre = self.ctx.read_pre(key)?.and_then(|bytes| {
                     TotalVotingPowers::try_from_slice(&bytes[..]).ok()
@@ -275,6 +369,33 @@ where
                     TotalVotingPowers::try_from_slice(&bytes[..]).ok()
                 });
                 changes.push(TotalVotingPower(Data { pre, post }));
+            } else if let Some(validator) = is_validator_metadata_key(key) {
+                // The metadata is not used by the PoS system, it only has to
+                // be valid and belong to an existing validator. The
+                // validator's VP checks that the change is authorized.
+                let post = match self.ctx.read_post(key)?.and_then(|bytes| {
+                    ValidatorMetadata::try_from_slice(&bytes[..]).ok()
+                }) {
+                    Some(post) => post,
+                    None => {
+                        tracing::info!(
+                            "Invalid validator metadata change {} rejected",
+                            key
+                        );
+                        return Ok(false);
+                    }
+                };
+                if let Err(err) = post.validate() {
+                    tracing::info!("Validator metadata rejected: {}", err);
+                    return Ok(false);
+                }
+                if !self.ctx.has_key_post(&validator_state_key(validator))? {
+                    tracing::info!(
+                        "Metadata of a non-validator address {} rejected",
+                        validator
+                    );
+                    return Ok(false);
+                }
             } else if key.segments.get(0) == Some(&addr.to_db_key()) {
                 // Unknown changes to this address space are disallowed
                 tracing::info!("PoS unrecognized key change {} rejected", key);
@@ -285,7 +406,80 @@ where
             }
         }
 
-        let params = self.read_pos_params();
+        // Jailed validators may only be unjailed once their jail duration is
+        // over and if their self-bond satisfies the minimum validator
+        // self-bond
+        for validator in unjailed_validators {
+            let state = match self.read_validator_state(&validator) {
+                Some(state) => state,
+                None => return Ok(false),
+            };
+            let slashes = self.read_validator_slashes(&validator);
+            let is_tombstoned = self.is_validator_tombstoned(&validator);
+            let self_bond = self.read_bond(&BondId {
+                source: validator.clone(),
+                validator: validator.clone(),
+            });
+            if let Err(err) = check_unjail(
+                &params,
+                current_epoch,
+                &validator,
+                &state,
+                &slashes,
+                is_tombstoned,
+                self_bond.as_ref(),
+            ) {
+                tracing::info!("PoS unjail rejected: {}", err);
+                return Ok(false);
+            }
+        }
+
+        // A rotated consensus key must not be used by another validator
+        let validator_sets = self.read_validator_set();
+        for (validator, consensus_keys, new_key) in rotated_validators {
+            if let Err(err) = check_consensus_key_change(
+                &params,
+                current_epoch,
+                &validator,
+                &new_key,
+                &consensus_keys,
+                &validator_sets,
+                |address| self.read_validator_consensus_key(address),
+            ) {
+                tracing::info!("PoS consensus key change rejected: {}", err);
+                return Ok(false);
+            }
+        }
+
+        // Delegations may only be bonded to validators whose self-bond
+        // satisfies the minimum validator self-bond
+        for validator in delegated_validators {
+            let self_bond_key = bond_key(&BondId {
+                source: validator.clone(),
+                validator: validator.clone(),
+            });
+            let self_bond = self
+                .ctx
+                .read_post(&self_bond_key)?
+                .and_then(|bytes| Bonds::try_from_slice(&bytes[..]).ok());
+            let self_bond: u64 = bond_amount_at_pipeline(
+                self_bond.as_ref(),
+                current_epoch,
+                &params,
+            )
+            .into();
+            if self_bond < params.min_validator_self_bond {
+                tracing::info!(
+                    "PoS delegation to validator {} with self-bond {} below \
+                     the minimum {} rejected",
+                    validator,
+                    self_bond,
+                    params.min_validator_self_bond
+                );
+                return Ok(false);
+            }
+        }
+
         let errors = validate(&params, changes, current_epoch);
         Ok(if errors.is_empty() {
             true
@@ -376,6 +570,24 @@ where
             .unwrap_or_default()
     }
 
+    fn is_validator_tombstoned(&self, key: &Self::Address) -> bool {
+        let value = self.ctx.read_pre(&validator_tombstoned_key(key)).unwrap();
+        value
+            .map(|value| decode(value).unwrap())
+            .unwrap_or_default()
+    }
+
+    fn read_validator_commission_rate(
+        &self,
+        key: &Self::Address,
+    ) -> Option<CommissionRates> {
+        let value = self
+            .ctx
+            .read_pre(&validator_commission_rate_key(key))
+            .unwrap();
+        value.map(|value| decode(value).unwrap())
+    }
+
     fn read_bond(&self, key: &BondId) -> Option<Bonds> {
         let value = self.ctx.read_pre(&bond_key(key)).unwrap();
         value.map(|value| decode(value).unwrap())
@@ -386,6 +598,11 @@ where
         value.map(|value| decode(value).unwrap())
     }
 
+    fn read_rewards(&self, key: &BondId) -> Option<Rewards> {
+        let value = self.ctx.read_pre(&rewards_key(key)).unwrap();
+        value.map(|value| decode(value).unwrap())
+    }
+
     fn read_validator_set(&self) -> ValidatorSets {
         let value = self.ctx.read_pre(&validator_set_key()).unwrap().unwrap();
         decode(value).unwrap()
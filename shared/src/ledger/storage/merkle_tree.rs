@@ -143,6 +143,18 @@ impl<H: StorageHasher + Default> core::fmt::Debug for MerkleTree<H> {
     }
 }
 
+impl<H: StorageHasher + Default> Clone for MerkleTree<H> {
+    fn clone(&self) -> Self {
+        let stores = self.stores();
+        let mut cloned = MerkleTreeStoresRead::default();
+        for store_type in StoreType::iter() {
+            cloned.set_root(store_type, *stores.root(store_type));
+            cloned.set_store(store_type, stores.store(store_type).clone());
+        }
+        Self::new(cloned)
+    }
+}
+
 impl<H: StorageHasher + Default> MerkleTree<H> {
     /// Restore the tree from the stores
     pub fn new(stores: MerkleTreeStoresRead) -> Self {
@@ -255,7 +267,7 @@ impl<H: StorageHasher + Default> MerkleTree<H> {
                 proof: Some(Ics23Proof::Exist(ExistenceProof {
                     key: sub_key.to_string().as_bytes().to_vec(),
                     value,
-                    leaf: Some(self.leaf_spec()),
+                    leaf: Some(Self::leaf_spec()),
                     ..ep
                 })),
             },
@@ -313,7 +325,7 @@ impl<H: StorageHasher + Default> MerkleTree<H> {
             Ics23Proof::Exist(ep) => CommitmentProof {
                 proof: Some(Ics23Proof::Exist(ExistenceProof {
                     key: base_key.as_bytes().to_vec(),
-                    leaf: Some(self.base_leaf_spec()),
+                    leaf: Some(Self::base_leaf_spec()),
                     ..ep
                 })),
             },
@@ -339,13 +351,18 @@ impl<H: StorageHasher + Default> MerkleTree<H> {
 
     /// Get the proof specs
     pub fn proof_specs(&self) -> Vec<ProofSpec> {
+        Self::specs()
+    }
+
+    /// Get the proof specs of the subtree and of the base tree
+    fn specs() -> Vec<ProofSpec> {
         let spec = sparse_merkle_tree::proof_ics23::get_spec(H::hash_op());
         let sub_tree_spec = ProofSpec {
-            leaf_spec: Some(self.leaf_spec()),
+            leaf_spec: Some(Self::leaf_spec()),
             ..spec.clone()
         };
         let base_tree_spec = ProofSpec {
-            leaf_spec: Some(self.base_leaf_spec()),
+            leaf_spec: Some(Self::base_leaf_spec()),
             ..spec
         };
         vec![sub_tree_spec, base_tree_spec]
@@ -353,7 +370,7 @@ impl<H: StorageHasher + Default> MerkleTree<H> {
 
     /// Get the leaf spec for the base tree. The key is stored after hashing,
     /// but the stored value is the subtree's root without hashing.
-    fn base_leaf_spec(&self) -> LeafOp {
+    fn base_leaf_spec() -> LeafOp {
         LeafOp {
             hash: H::hash_op().into(),
             prehash_key: H::hash_op().into(),
@@ -366,7 +383,7 @@ impl<H: StorageHasher + Default> MerkleTree<H> {
     /// Get the leaf spec for the subtree. Non-hashed values are used for the
     /// verification with this spec because a subtree stores the key-value pairs
     /// after hashing.
-    fn leaf_spec(&self) -> LeafOp {
+    fn leaf_spec() -> LeafOp {
         LeafOp {
             hash: H::hash_op().into(),
             prehash_key: H::hash_op().into(),
@@ -377,6 +394,57 @@ impl<H: StorageHasher + Default> MerkleTree<H> {
     }
 }
 
+/// Verify an existence proof of a storage value, as returned by
+/// [`MerkleTree::get_existence_proof`], against the given root of the merkle
+/// tree. The sub proof proves the value in its subtree and the base proof
+/// proves the subtree's root in the base tree, whose root must be the given
+/// one.
+pub fn verify_merkle_proof<H: StorageHasher + Default>(
+    root: &MerkleRoot,
+    key: &Key,
+    value: &[u8],
+    proof: &Proof,
+) -> bool {
+    let (store_type, sub_key) = match StoreType::sub_key(key) {
+        Ok(keys) => keys,
+        Err(_) => return false,
+    };
+    if proof.ops.len() != 2 {
+        return false;
+    }
+    let specs = MerkleTree::<H>::specs();
+    let paths = [sub_key.to_string(), store_type.to_string()];
+    // First, the sub proof is verified. Next the base proof is verified with
+    // the sub root as the value
+    let mut value = value.to_vec();
+    for ((op, spec), path) in proof.ops.iter().zip(specs.iter()).zip(&paths) {
+        let commitment_proof = match CommitmentProof::decode(&*op.data) {
+            Ok(proof) => proof,
+            Err(_) => return false,
+        };
+        let sub_root = match &commitment_proof.proof {
+            Some(Ics23Proof::Exist(ep)) => {
+                match ics23::calculate_existence_root(ep) {
+                    Ok(root) => root,
+                    Err(_) => return false,
+                }
+            }
+            _ => return false,
+        };
+        if !ics23::verify_membership(
+            &commitment_proof,
+            spec,
+            &sub_root,
+            path.as_bytes(),
+            &value,
+        ) {
+            return false;
+        }
+        value = sub_root;
+    }
+    value == root.0
+}
+
 /// The root hash of the merkle tree as bytes
 pub struct MerkleRoot(pub Vec<u8>);
 
@@ -618,4 +686,60 @@ mod test {
         // Check the base root
         assert_eq!(sub_root, tree.root().0);
     }
+
+    #[test]
+    fn test_verify_merkle_proof() {
+        let mut tree = MerkleTree::<Sha256Hasher>::default();
+
+        let key_prefix: Key =
+            Address::Internal(InternalAddress::Ibc).to_db_key().into();
+        let ibc_key = key_prefix.push(&"test".to_string()).unwrap();
+        let key_prefix: Key =
+            Address::Internal(InternalAddress::PoS).to_db_key().into();
+        let pos_key = key_prefix.push(&"test".to_string()).unwrap();
+
+        let ibc_val = [1u8; 8].to_vec();
+        tree.update(&ibc_key, ibc_val.clone()).unwrap();
+        let pos_val = [2u8; 8].to_vec();
+        tree.update(&pos_key, pos_val.clone()).unwrap();
+        let root = tree.root();
+
+        // A genuine proof passes
+        let proof =
+            tree.get_existence_proof(&ibc_key, ibc_val.clone()).unwrap();
+        assert!(verify_merkle_proof::<Sha256Hasher>(
+            &root, &ibc_key, &ibc_val, &proof
+        ));
+
+        // A tampered value fails
+        assert!(!verify_merkle_proof::<Sha256Hasher>(
+            &root, &ibc_key, &pos_val, &proof
+        ));
+
+        // A proof of another key fails
+        let pos_proof = tree.get_existence_proof(&pos_key, pos_val).unwrap();
+        assert!(!verify_merkle_proof::<Sha256Hasher>(
+            &root, &ibc_key, &ibc_val, &pos_proof
+        ));
+
+        // A proof with a tampered value fails
+        let tampered_proof = tree
+            .get_existence_proof(&ibc_key, [3u8; 8].to_vec())
+            .unwrap();
+        assert!(!verify_merkle_proof::<Sha256Hasher>(
+            &root,
+            &ibc_key,
+            &ibc_val,
+            &tampered_proof
+        ));
+
+        // A genuine proof fails against another root
+        tree.update(&ibc_key, [3u8; 8]).unwrap();
+        assert!(!verify_merkle_proof::<Sha256Hasher>(
+            &tree.root(),
+            &ibc_key,
+            &ibc_val,
+            &proof
+        ));
+    }
 }
@@ -1,7 +1,7 @@
 //! DB mock for testing
 
 use std::cell::RefCell;
-use std::collections::{btree_map, BTreeMap};
+use std::collections::{btree_map, BTreeMap, BTreeSet};
 use std::ops::Bound::{Excluded, Included};
 use std::path::Path;
 use std::str::FromStr;
@@ -24,8 +24,108 @@ pub struct MockDB(
     // The state is wrapped in `RefCell` to allow modifying it directly from
     // batch write method (which requires immutable self ref).
     RefCell<BTreeMap<String, Vec<u8>>>,
+    // When set, writing a block fails to simulate a storage error.
+    bool,
+    // When set, executing a batch write fails to simulate a storage error.
+    bool,
 );
 
+impl MockDB {
+    /// Make all the following block writes fail with a DB error, to simulate
+    /// a corrupted storage.
+    pub fn fail_block_writes(&mut self) {
+        self.1 = true;
+    }
+
+    /// Make all the following batch writes fail with a DB error, e.g. to
+    /// simulate a corrupted storage when a block's write log is committed.
+    pub fn fail_batch_writes(&mut self) {
+        self.2 = true;
+    }
+
+    /// Write a subspace key-val and the diff of its value under the height
+    /// where it was changed, like the persistent DB does. Returns the size
+    /// difference.
+    fn write_subspace_val_with_diff(
+        &self,
+        height: BlockHeight,
+        key: &Key,
+        value: &[u8],
+    ) -> Result<i64> {
+        let subspace_key =
+            Key::parse("subspace").map_err(Error::KeyError)?.join(key);
+        let prev_value = self
+            .0
+            .borrow_mut()
+            .insert(subspace_key.to_string(), value.to_owned());
+        self.write_subspace_diff(
+            height,
+            key,
+            prev_value.as_deref(),
+            Some(value),
+        )?;
+        let current_len = value.len() as i64;
+        Ok(match prev_value {
+            Some(prev_value) => current_len - prev_value.len() as i64,
+            None => current_len,
+        })
+    }
+
+    /// Delete a subspace key-val and write the diff of its value under the
+    /// height where it was deleted. Returns the size of the deleted value.
+    fn delete_subspace_val_with_diff(
+        &self,
+        height: BlockHeight,
+        key: &Key,
+    ) -> Result<i64> {
+        let subspace_key =
+            Key::parse("subspace").map_err(Error::KeyError)?.join(key);
+        let prev_value = self.0.borrow_mut().remove(&subspace_key.to_string());
+        Ok(match prev_value {
+            Some(prev_value) => {
+                self.write_subspace_diff(height, key, Some(&prev_value), None)?;
+                prev_value.len() as i64
+            }
+            None => 0,
+        })
+    }
+
+    /// Write the diff of a subspace key-val under the height where it was
+    /// changed.
+    fn write_subspace_diff(
+        &self,
+        height: BlockHeight,
+        key: &Key,
+        old_value: Option<&[u8]>,
+        new_value: Option<&[u8]>,
+    ) -> Result<()> {
+        let key_prefix = Key::from(height.to_db_key())
+            .push(&"diffs".to_owned())
+            .map_err(Error::KeyError)?;
+        if let Some(old_value) = old_value {
+            let old_val_key = key_prefix
+                .push(&"old".to_owned())
+                .map_err(Error::KeyError)?
+                .join(key)
+                .to_string();
+            self.0
+                .borrow_mut()
+                .insert(old_val_key, old_value.to_owned());
+        }
+        if let Some(new_value) = new_value {
+            let new_val_key = key_prefix
+                .push(&"new".to_owned())
+                .map_err(Error::KeyError)?
+                .join(key)
+                .to_string();
+            self.0
+                .borrow_mut()
+                .insert(new_val_key, new_value.to_owned());
+        }
+        Ok(())
+    }
+}
+
 // The `MockDB` is not `Sync`, but we're sharing it across threads for reading
 // only (for parallelized VP runs). In a different context, this may not be
 // safe.
@@ -57,24 +157,30 @@ impl DB for MockDB {
         };
 
         // Epoch start height and time
-        let next_epoch_min_start_height: BlockHeight =
-            match self.0.borrow().get("next_epoch_min_start_height") {
-                Some(bytes) => {
-                    types::decode(bytes).map_err(Error::CodingError)?
-                }
-                None => return Ok(None),
-            };
-        let next_epoch_min_start_time: DateTimeUtc =
-            match self.0.borrow().get("next_epoch_min_start_time") {
-                Some(bytes) => {
-                    types::decode(bytes).map_err(Error::CodingError)?
-                }
-                None => return Ok(None),
-            };
+        let next_epoch_min_start_height: BlockHeight = match self
+            .0
+            .borrow()
+            .get("next_epoch_min_start_height")
+        {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => {
+                return Err(missing_block_state("next_epoch_min_start_height"))
+            }
+        };
+        let next_epoch_min_start_time: DateTimeUtc = match self
+            .0
+            .borrow()
+            .get("next_epoch_min_start_time")
+        {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => {
+                return Err(missing_block_state("next_epoch_min_start_time"))
+            }
+        };
         #[cfg(feature = "ferveo-tpke")]
         let tx_queue: TxQueue = match self.0.borrow().get("tx_queue") {
             Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
-            None => return Ok(None),
+            None => return Err(missing_block_state("tx_queue")),
         };
 
         // Load data at the height
@@ -116,6 +222,9 @@ impl DB for MockDB {
                     "header" => {
                         // the block header doesn't have to be restored
                     }
+                    "events" => {
+                        // the block events don't have to be restored
+                    }
                     "hash" => {
                         hash = Some(
                             types::decode(bytes).map_err(Error::CodingError)?,
@@ -136,6 +245,9 @@ impl DB for MockDB {
                             types::decode(bytes).map_err(Error::CodingError)?,
                         );
                     }
+                    "diffs" => {
+                        // ignore the diffs
+                    }
                     _ => unknown_key_error(path)?,
                 },
                 None => unknown_key_error(path)?,
@@ -176,8 +288,15 @@ impl DB for MockDB {
             address_gen,
             #[cfg(feature = "ferveo-tpke")]
             tx_queue,
+            events,
         }: BlockStateWrite = state;
 
+        if self.1 {
+            return Err(Error::DBError(
+                "Simulated failure to write a block".to_owned(),
+            ));
+        }
+
         // Epoch start height and time
         self.0.borrow_mut().insert(
             "next_epoch_min_start_height".into(),
@@ -269,6 +388,13 @@ impl DB for MockDB {
                 .borrow_mut()
                 .insert(key.to_string(), types::encode(value));
         }
+        // Block events
+        {
+            let key = prefix_key
+                .push(&"events".to_owned())
+                .map_err(Error::KeyError)?;
+            self.0.borrow_mut().insert(key.to_string(), events.to_vec());
+        }
         self.0
             .borrow_mut()
             .insert("height".to_owned(), types::encode(&height));
@@ -289,6 +415,49 @@ impl DB for MockDB {
         }
     }
 
+    fn read_block_events(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<Vec<u8>>> {
+        let prefix_key = Key::from(height.to_db_key());
+        let key = prefix_key
+            .push(&"events".to_owned())
+            .map_err(Error::KeyError)?;
+        let value = self.0.borrow().get(&key.to_string()).cloned();
+        Ok(value)
+    }
+
+    fn read_events_retained_from(&self) -> Result<BlockHeight> {
+        match self.0.borrow().get("events_retained_from") {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError),
+            None => Ok(BlockHeight(1)),
+        }
+    }
+
+    fn prune_block_events(&mut self, retain_from: BlockHeight) -> Result<u64> {
+        let pruned_from = self.read_events_retained_from()?;
+        if retain_from <= pruned_from {
+            return Ok(0);
+        }
+        let mut db = self.0.borrow_mut();
+        for height in pruned_from.0..retain_from.0 {
+            let key = Key::from(BlockHeight(height).to_db_key())
+                .push(&"events".to_owned())
+                .map_err(Error::KeyError)?;
+            db.remove(&key.to_string());
+        }
+        db.insert(
+            "events_retained_from".to_owned(),
+            types::encode(&retain_from),
+        );
+        Ok(retain_from.0 - pruned_from.0)
+    }
+
+    fn compact(&self) -> Result<()> {
+        // Nothing to reclaim in memory
+        Ok(())
+    }
+
     fn read_merkle_tree_stores(
         &self,
         height: BlockHeight,
@@ -335,44 +504,70 @@ impl DB for MockDB {
 
     fn read_subspace_val_with_height(
         &self,
-        _key: &Key,
-        _height: BlockHeight,
+        key: &Key,
+        height: BlockHeight,
     ) -> Result<Option<Vec<u8>>> {
-        // Mock DB can read only the latest value for now
-        unimplemented!()
+        if self.read_subspace_val(key)?.is_none() {
+            return Ok(None);
+        }
+
+        let mut height = height.0;
+        loop {
+            let key_prefix = Key::from(BlockHeight(height).to_db_key())
+                .push(&"diffs".to_owned())
+                .map_err(Error::KeyError)?;
+            let new_val_key = key_prefix
+                .push(&"new".to_owned())
+                .map_err(Error::KeyError)?
+                .join(key)
+                .to_string();
+            match self.0.borrow().get(&new_val_key) {
+                Some(bytes) => return Ok(Some(bytes.clone())),
+                // The values written at genesis are at height 0
+                None if height == 0 => return Ok(None),
+                None => height -= 1,
+            }
+        }
+    }
+
+    fn read_subspace_diff_keys(&self, height: BlockHeight) -> Result<Vec<Key>> {
+        let diffs_prefix = format!(
+            "{}/",
+            Key::from(height.to_db_key())
+                .push(&"diffs".to_owned())
+                .map_err(Error::KeyError)?
+        );
+        let mut keys = BTreeSet::new();
+        for db_key in self.0.borrow().keys() {
+            let diff_key = match db_key.strip_prefix(&diffs_prefix) {
+                Some(diff_key) => diff_key,
+                None => continue,
+            };
+            if let Some(key) = diff_key
+                .strip_prefix("new/")
+                .or_else(|| diff_key.strip_prefix("old/"))
+            {
+                keys.insert(Key::parse(key).map_err(Error::KeyError)?);
+            }
+        }
+        Ok(keys.into_iter().collect())
     }
 
     fn write_subspace_val(
         &mut self,
-        _height: BlockHeight,
+        height: BlockHeight,
         key: &Key,
         value: impl AsRef<[u8]>,
     ) -> Result<i64> {
-        let value = value.as_ref();
-        let key = Key::parse("subspace").map_err(Error::KeyError)?.join(key);
-        let current_len = value.len() as i64;
-        Ok(
-            match self
-                .0
-                .borrow_mut()
-                .insert(key.to_string(), value.to_owned())
-            {
-                Some(prev_value) => current_len - prev_value.len() as i64,
-                None => current_len,
-            },
-        )
+        self.write_subspace_val_with_diff(height, key, value.as_ref())
     }
 
     fn delete_subspace_val(
         &mut self,
-        _height: BlockHeight,
+        height: BlockHeight,
         key: &Key,
     ) -> Result<i64> {
-        let key = Key::parse("subspace").map_err(Error::KeyError)?.join(key);
-        Ok(match self.0.borrow_mut().remove(&key.to_string()) {
-            Some(value) => value.len() as i64,
-            None => 0,
-        })
+        self.delete_subspace_val_with_diff(height, key)
     }
 
     fn batch() -> Self::WriteBatch {
@@ -380,44 +575,72 @@ impl DB for MockDB {
     }
 
     fn exec_batch(&mut self, _batch: Self::WriteBatch) -> Result<()> {
-        // Nothing to do - in MockDB, batch writes are committed directly from
-        // `batch_write_subspace_val` and `batch_delete_subspace_val`.
+        if self.2 {
+            return Err(Error::DBError(
+                "Simulated failure to execute a batch write".to_owned(),
+            ));
+        }
+        // Nothing else to do - in MockDB, batch writes are committed directly
+        // from `batch_write_subspace_val` and `batch_delete_subspace_val`.
         Ok(())
     }
 
     fn batch_write_subspace_val(
         &self,
         _batch: &mut Self::WriteBatch,
-        _height: BlockHeight,
+        height: BlockHeight,
         key: &Key,
         value: impl AsRef<[u8]>,
     ) -> Result<i64> {
-        let value = value.as_ref();
-        let key = Key::parse("subspace").map_err(Error::KeyError)?.join(key);
-        let current_len = value.len() as i64;
-        Ok(
-            match self
-                .0
-                .borrow_mut()
-                .insert(key.to_string(), value.to_owned())
-            {
-                Some(prev_value) => current_len - prev_value.len() as i64,
-                None => current_len,
-            },
-        )
+        self.write_subspace_val_with_diff(height, key, value.as_ref())
     }
 
     fn batch_delete_subspace_val(
         &self,
         _batch: &mut Self::WriteBatch,
-        _height: BlockHeight,
+        height: BlockHeight,
         key: &Key,
     ) -> Result<i64> {
-        let key = Key::parse("subspace").map_err(Error::KeyError)?.join(key);
-        Ok(match self.0.borrow_mut().remove(&key.to_string()) {
-            Some(value) => value.len() as i64,
-            None => 0,
-        })
+        self.delete_subspace_val_with_diff(height, key)
+    }
+
+    fn read_staged_val(&self, key: &Key) -> Result<Option<Option<Vec<u8>>>> {
+        let key = Key::parse("staged").map_err(Error::KeyError)?.join(key);
+        match self.0.borrow().get(&key.to_string()) {
+            Some(bytes) => {
+                Ok(Some(types::decode(bytes).map_err(Error::CodingError)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn write_staged_val(
+        &mut self,
+        key: &Key,
+        value: Option<&[u8]>,
+    ) -> Result<()> {
+        let key = Key::parse("staged").map_err(Error::KeyError)?.join(key);
+        self.0
+            .borrow_mut()
+            .insert(key.to_string(), types::encode(&value));
+        Ok(())
+    }
+
+    fn batch_delete_staged_val(
+        &self,
+        _batch: &mut Self::WriteBatch,
+        key: &Key,
+    ) -> Result<()> {
+        let key = Key::parse("staged").map_err(Error::KeyError)?.join(key);
+        self.0.borrow_mut().remove(&key.to_string());
+        Ok(())
+    }
+
+    fn clear_staged_vals(&mut self) -> Result<()> {
+        self.0
+            .borrow_mut()
+            .retain(|key, _value| !key.starts_with("staged/"));
+        Ok(())
     }
 }
 
@@ -502,3 +725,11 @@ fn unknown_key_error(key: &str) -> Result<()> {
         key: key.to_owned(),
     })
 }
+
+fn missing_block_state(key: &str) -> Error {
+    Error::DBError(format!(
+        "Couldn't load {} from the DB, although the last block height is \
+         present",
+        key
+    ))
+}
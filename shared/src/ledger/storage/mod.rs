@@ -3,11 +3,14 @@
 mod merkle_tree;
 #[cfg(any(test, feature = "testing"))]
 pub mod mockdb;
+pub mod read_cache;
 pub mod types;
 pub mod write_log;
 
 use core::fmt::Debug;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
+use borsh::{BorshDeserialize, BorshSerialize};
 #[cfg(not(feature = "ABCI"))]
 use tendermint::block::Header;
 #[cfg(not(feature = "ABCI"))]
@@ -29,13 +32,12 @@ use thiserror::Error;
 use super::parameters::Parameters;
 use crate::ledger::gas::MIN_STORAGE_GAS;
 use crate::ledger::parameters::{self, EpochDuration};
-use crate::ledger::storage::merkle_tree::{
-    Error as MerkleTreeError, MerkleRoot,
-};
+use crate::ledger::storage::merkle_tree::Error as MerkleTreeError;
 pub use crate::ledger::storage::merkle_tree::{
-    MerkleTree, MerkleTreeStoresRead, MerkleTreeStoresWrite, Sha256Hasher,
-    StorageHasher, StoreType,
+    verify_merkle_proof, MerkleRoot, MerkleTree, MerkleTreeStoresRead,
+    MerkleTreeStoresWrite, Sha256Hasher, StorageHasher, StoreType,
 };
+use crate::ledger::storage::read_cache::ReadCache;
 use crate::types::address::{Address, EstablishedAddressGen, InternalAddress};
 use crate::types::chain::{ChainId, CHAIN_ID_LENGTH};
 #[cfg(feature = "ferveo-tpke")]
@@ -43,7 +45,7 @@ use crate::types::storage::TxQueue;
 use crate::types::storage::{
     BlockHash, BlockHeight, Epoch, Epochs, Key, KeySeg, BLOCK_HASH_LENGTH,
 };
-use crate::types::time::DateTimeUtc;
+use crate::types::time::{DateTimeUtc, DurationSecs};
 
 /// A result of a function that may fail
 pub type Result<T> = std::result::Result<T, Error>;
@@ -76,6 +78,14 @@ where
     /// Wrapper txs to be decrypted in the next block proposal
     #[cfg(feature = "ferveo-tpke")]
     pub tx_queue: TxQueue,
+    /// The cache of the values read from the DB in the current block
+    pub read_cache: ReadCache,
+    /// The events emitted in the current block, encoded by the ledger. They
+    /// are persisted with the block on commit.
+    pub block_events: Vec<u8>,
+    /// The account subspace keys whose changes in the current block are
+    /// staged in the DB, see [`Storage::stage_subspace_val`]
+    pub staged_keys: HashSet<Key>,
 }
 
 /// The block storage data
@@ -93,6 +103,55 @@ pub struct BlockStorage<H: StorageHasher> {
     pub pred_epochs: Epochs,
 }
 
+/// The progress of an epoch towards its end. The epoch is over only when
+/// both its minimum number of blocks and its minimum duration have been
+/// reached.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct EpochProgress {
+    /// The current epoch
+    pub epoch: Epoch,
+    /// Height of the first block of the current epoch
+    pub first_block_height: BlockHeight,
+    /// Height of the block at which the progress is evaluated
+    pub height: BlockHeight,
+    /// Time at which the progress is evaluated
+    pub time: DateTimeUtc,
+    /// Minimum block height at which the next epoch may start
+    pub next_epoch_min_start_height: BlockHeight,
+    /// Minimum block time at which the next epoch may start
+    pub next_epoch_min_start_time: DateTimeUtc,
+}
+
+impl EpochProgress {
+    /// The number of blocks since the start of the epoch.
+    pub fn num_of_blocks(&self) -> u64 {
+        self.height.0.saturating_sub(self.first_block_height.0)
+    }
+
+    /// The number of blocks remaining until the minimum number of blocks of
+    /// the epoch is reached.
+    pub fn remaining_blocks(&self) -> u64 {
+        self.next_epoch_min_start_height
+            .0
+            .saturating_sub(self.height.0)
+    }
+
+    /// The time remaining until the minimum duration of the epoch is reached.
+    pub fn remaining_duration(&self) -> DurationSecs {
+        (self.next_epoch_min_start_time.0 - self.time.0)
+            .to_std()
+            .map(DurationSecs::from)
+            .unwrap_or(DurationSecs(0))
+    }
+
+    /// Check if both the minimum number of blocks and the minimum duration of
+    /// the epoch have been reached.
+    pub fn is_over(&self) -> bool {
+        self.height >= self.next_epoch_min_start_height
+            && self.time >= self.next_epoch_min_start_time
+    }
+}
+
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
 pub enum Error {
@@ -160,6 +219,8 @@ pub struct BlockStateWrite<'a> {
     /// Wrapper txs to be decrypted in the next block proposal
     #[cfg(feature = "ferveo-tpke")]
     pub tx_queue: &'a TxQueue,
+    /// The events emitted in the block, encoded by the ledger
+    pub events: &'a [u8],
 }
 
 /// A database backend.
@@ -178,7 +239,9 @@ pub trait DB: std::fmt::Debug {
     /// Flush data on the memory to persistent them
     fn flush(&self, wait: bool) -> Result<()>;
 
-    /// Read the last committed block's metadata
+    /// Read the last committed block's metadata. Returns `None` when no block
+    /// has been committed yet and an error when the stored state is incomplete
+    /// or cannot be decoded.
     fn read_last_block(&mut self) -> Result<Option<BlockStateRead>>;
 
     /// Write block's metadata
@@ -187,6 +250,23 @@ pub trait DB: std::fmt::Debug {
     /// Read the block header with the given height from the DB
     fn read_block_header(&self, height: BlockHeight) -> Result<Option<Header>>;
 
+    /// Read the encoded events emitted in the block with the given height
+    fn read_block_events(&self, height: BlockHeight)
+        -> Result<Option<Vec<u8>>>;
+
+    /// Read the height of the oldest block whose events are retained. The
+    /// events of the blocks below it have been pruned.
+    fn read_events_retained_from(&self) -> Result<BlockHeight>;
+
+    /// Prune the events of the blocks below the given height, which becomes
+    /// the height of the oldest block whose events are retained. The state
+    /// is not affected. Returns the number of the deleted keys.
+    fn prune_block_events(&mut self, retain_from: BlockHeight) -> Result<u64>;
+
+    /// Compact the whole DB, so that the space of the deleted and overwritten
+    /// values is reclaimed.
+    fn compact(&self) -> Result<()>;
+
     /// Read the merkle tree stores with the given height
     fn read_merkle_tree_stores(
         &self,
@@ -203,6 +283,10 @@ pub trait DB: std::fmt::Debug {
         _height: BlockHeight,
     ) -> Result<Option<Vec<u8>>>;
 
+    /// Read the account subspace keys whose values were written or deleted in
+    /// the block with the given height
+    fn read_subspace_diff_keys(&self, height: BlockHeight) -> Result<Vec<Key>>;
+
     /// Write the value with the given height and account subspace key to the
     /// DB. Returns the size difference from previous value, if any, or the
     /// size of the value otherwise.
@@ -248,6 +332,33 @@ pub trait DB: std::fmt::Debug {
         height: BlockHeight,
         key: &Key,
     ) -> Result<i64>;
+
+    /// Read the change of the current block to the account subspace key that
+    /// has been staged with [`DB::write_staged_val`]. Returns `Some(None)`
+    /// when the key's deletion is staged.
+    fn read_staged_val(&self, key: &Key) -> Result<Option<Option<Vec<u8>>>>;
+
+    /// Stage a change of the current block to the account subspace key in a
+    /// staging area of the DB, apart from the subspace. A `None` value stages
+    /// the key's deletion.
+    fn write_staged_val(
+        &mut self,
+        key: &Key,
+        value: Option<&[u8]>,
+    ) -> Result<()>;
+
+    /// Batch delete the staged change to the account subspace key from the
+    /// DB's staging area
+    fn batch_delete_staged_val(
+        &self,
+        batch: &mut Self::WriteBatch,
+        key: &Key,
+    ) -> Result<()>;
+
+    /// Delete all the changes from the DB's staging area. The changes staged
+    /// in a block that hasn't been committed are left in the DB when the node
+    /// stops in the middle of the block.
+    fn clear_staged_vals(&mut self) -> Result<()>;
 }
 
 /// A database prefix iterator.
@@ -304,12 +415,17 @@ where
             ),
             #[cfg(feature = "ferveo-tpke")]
             tx_queue: TxQueue::default(),
+            read_cache: ReadCache::default(),
+            block_events: Vec::new(),
+            staged_keys: HashSet::new(),
         }
     }
 
     /// Load the full state at the last committed height, if any. Returns the
     /// Merkle root hash and the height of the committed block.
     pub fn load_last_state(&mut self) -> Result<()> {
+        // The changes staged in a block that hasn't been committed
+        self.db.clear_staged_vals()?;
         if let Some(BlockStateRead {
             merkle_tree_stores,
             hash,
@@ -368,10 +484,13 @@ where
             address_gen: &self.address_gen,
             #[cfg(feature = "ferveo-tpke")]
             tx_queue: &self.tx_queue,
+            events: &self.block_events,
         };
         self.db.write_block(state)?;
         self.last_height = self.block.height;
         self.header = None;
+        self.read_cache.clear();
+        self.block_events.clear();
         Ok(())
     }
 
@@ -380,13 +499,26 @@ where
         self.block.tree.root()
     }
 
-    /// Check if the given key is present in storage. Returns the result and the
-    /// gas cost.
+    /// Check if the given key is present in the committed storage. Returns
+    /// the result and the gas cost.
     pub fn has_key(&self, key: &Key) -> Result<(bool, u64)> {
         Ok((self.block.tree.has_key(key)?, key.len() as _))
     }
 
-    /// Returns a value from the specified subspace and the gas cost
+    /// Check if the given key is present in storage with the changes of the
+    /// current block that have been staged in the DB. Returns the result and
+    /// the gas cost.
+    pub fn has_key_with_staged(&self, key: &Key) -> Result<(bool, u64)> {
+        if self.staged_keys.contains(key) {
+            let present = self.db.read_staged_val(key)?.flatten().is_some();
+            return Ok((present, key.len() as _));
+        }
+        self.has_key(key)
+    }
+
+    /// Returns a value from the specified subspace of the committed storage
+    /// and the gas cost. The value is read from the block's read cache, if it
+    /// has been read before.
     pub fn read(&self, key: &Key) -> Result<(Option<Vec<u8>>, u64)> {
         tracing::debug!("storage read key {}", key);
         let (present, gas) = self.has_key(key)?;
@@ -394,12 +526,20 @@ where
             return Ok((None, gas));
         }
 
+        if let Some(v) = self.read_cache.get(key) {
+            let gas = key.len() + v.len();
+            return Ok((Some(v), gas as _));
+        }
         match self.db.read_subspace_val(key)? {
             Some(v) => {
+                self.read_cache.insert_read(key, v.clone());
                 let gas = key.len() + v.len();
                 Ok((Some(v), gas as _))
             }
-            None => Ok((None, key.len() as _)),
+            None => {
+                self.read_cache.record_miss();
+                Ok((None, key.len() as _))
+            }
         }
     }
 
@@ -423,6 +563,69 @@ where
         }
     }
 
+    /// Find the values of the account subspace keys that have been changed in
+    /// the blocks committed after the given height, as they were at that
+    /// height. Applying them on top of the latest state gives the state at
+    /// the given height. Like with [`Storage::read_with_height`], the keys
+    /// that have since been deleted are not found.
+    pub fn read_changes_since(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Vec<(Key, Option<Vec<u8>>)>> {
+        let mut keys = BTreeSet::new();
+        for changed_height in (height.0 + 1)..=self.last_height.0 {
+            keys.extend(
+                self.db
+                    .read_subspace_diff_keys(BlockHeight(changed_height))?,
+            );
+        }
+        keys.into_iter()
+            .map(|key| {
+                let (value, _gas) = self.read_with_height(&key, height)?;
+                Ok((key, value))
+            })
+            .collect()
+    }
+
+    /// Returns a value from the specified subspace with the changes of the
+    /// current block that have been staged in the DB and the gas cost. Like
+    /// the block's changes held in the write log, the staged changes are only
+    /// seen by the block's execution, after the write log. The queries only
+    /// see the committed state with [`Storage::read`].
+    pub fn read_with_staged(
+        &self,
+        key: &Key,
+    ) -> Result<(Option<Vec<u8>>, u64)> {
+        if self.staged_keys.contains(key) {
+            let value = self.db.read_staged_val(key)?.flatten();
+            let gas = key.len() + value.as_ref().map(Vec::len).unwrap_or(0);
+            return Ok((value, gas as _));
+        }
+        self.read(key)
+    }
+
+    /// Returns the change of the current block staged in the DB for the key
+    /// and the gas cost. A staged deletion is `Some(None)` and the result is
+    /// `None` when the key's change isn't staged. The prefix iterators don't
+    /// see the staged changes, so these must be checked for the posterior
+    /// state of the iterated keys, like the write log.
+    pub fn read_staged(
+        &self,
+        key: &Key,
+    ) -> Result<(Option<Option<Vec<u8>>>, u64)> {
+        if !self.staged_keys.contains(key) {
+            return Ok((None, 0));
+        }
+        let value = self.db.read_staged_val(key)?;
+        let gas = key.len()
+            + value
+                .as_ref()
+                .and_then(Option::as_ref)
+                .map(Vec::len)
+                .unwrap_or_default();
+        Ok((value, gas as _))
+    }
+
     /// Returns a prefix iterator and the gas cost
     pub fn iter_prefix(
         &self,
@@ -443,8 +646,43 @@ where
 
         let len = value.as_ref().len();
         let gas = key.len() + len;
-        let size_diff =
-            self.db.write_subspace_val(self.last_height, key, value)?;
+        let size_diff = self.db.write_subspace_val(
+            self.block.height,
+            key,
+            value.clone(),
+        )?;
+        self.read_cache.write(key, value);
+        Ok((gas as _, size_diff))
+    }
+
+    /// Write the values to the specified subspaces in a single atomic write
+    /// batch, e.g. to seed the genesis state. The resulting state and Merkle
+    /// root are the same as with a [`Storage::write`] of each value in order.
+    /// Returns the total gas cost and size difference.
+    pub fn write_batch<V>(
+        &mut self,
+        writes: impl IntoIterator<Item = (Key, V)>,
+    ) -> Result<(u64, i64)>
+    where
+        V: AsRef<[u8]>,
+    {
+        let mut gas = 0;
+        // The previous value of a key is read from the DB, which doesn't see
+        // the batch until it's executed, so only the last write of a key
+        // that is written more than once goes in the batch
+        let mut last_writes = BTreeMap::new();
+        for (key, value) in writes {
+            gas += key.len() + value.as_ref().len();
+            last_writes.insert(key, value);
+        }
+        let mut batch = Self::batch();
+        let mut size_diff = 0;
+        for (key, value) in last_writes {
+            tracing::debug!("storage batch write key {}", key,);
+            size_diff +=
+                self.batch_write_subspace_val(&mut batch, &key, value)?;
+        }
+        self.exec_batch(batch)?;
         Ok((gas as _, size_diff))
     }
 
@@ -455,7 +693,8 @@ where
         if self.has_key(key)?.0 {
             self.block.tree.delete(key)?;
             deleted_bytes_len =
-                self.db.delete_subspace_val(self.last_height, key)?;
+                self.db.delete_subspace_val(self.block.height, key)?;
+            self.read_cache.delete(key);
         }
         let gas = key.len() + deleted_bytes_len as usize;
         Ok((gas as _, deleted_bytes_len))
@@ -548,6 +787,18 @@ where
         }
     }
 
+    /// Get the root hash of the merkle tree at the given height
+    pub fn get_merkle_root(&self, height: BlockHeight) -> Result<MerkleRoot> {
+        if height >= self.get_block_height().0 {
+            Ok(self.block.tree.root())
+        } else {
+            match self.db.read_merkle_tree_stores(height)? {
+                Some(stores) => Ok(MerkleTree::<H>::new(stores).root()),
+                None => Err(Error::NoMerkleTree { height }),
+            }
+        }
+    }
+
     /// Get the current (yet to be committed) block epoch
     pub fn get_current_epoch(&self) -> (Epoch, u64) {
         (self.block.epoch, MIN_STORAGE_GAS)
@@ -594,6 +845,58 @@ where
         }
     }
 
+    /// Get the encoded events emitted in the committed block with the given
+    /// height, if any
+    pub fn get_block_events(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<Vec<u8>>> {
+        self.db.read_block_events(height)
+    }
+
+    /// Get the height of the oldest committed block whose events are
+    /// retained. The events of the blocks below it have been pruned.
+    pub fn get_events_retained_from(&self) -> Result<BlockHeight> {
+        self.db.read_events_retained_from()
+    }
+
+    /// Prune the events of the committed blocks below the given height. The
+    /// state is not affected. Returns the number of the deleted keys.
+    pub fn prune_block_events(
+        &mut self,
+        retain_from: BlockHeight,
+    ) -> Result<u64> {
+        self.db.prune_block_events(retain_from)
+    }
+
+    /// Compact the DB to reclaim the space of the deleted and overwritten
+    /// values.
+    pub fn compact(&self) -> Result<()> {
+        self.db.compact()
+    }
+
+    /// Get the progress of the current epoch towards its end at the given
+    /// block height and time.
+    pub fn epoch_progress(
+        &self,
+        height: BlockHeight,
+        time: DateTimeUtc,
+    ) -> EpochProgress {
+        let first_block_height = self
+            .block
+            .pred_epochs
+            .get_first_block_height(self.block.epoch)
+            .unwrap_or_default();
+        EpochProgress {
+            epoch: self.block.epoch,
+            first_block_height,
+            height,
+            time,
+            next_epoch_min_start_height: self.next_epoch_min_start_height,
+            next_epoch_min_start_time: self.next_epoch_min_start_time,
+        }
+    }
+
     /// Initialize a new epoch when the current epoch is finished. Returns
     /// `true` on a new epoch.
     pub fn update_epoch(
@@ -605,8 +908,7 @@ where
             parameters::read(self).expect("Couldn't read protocol parameters");
 
         // Check if the current epoch is over
-        let new_epoch = height >= self.next_epoch_min_start_height
-            && time >= self.next_epoch_min_start_time;
+        let new_epoch = self.epoch_progress(height, time).is_over();
         if new_epoch {
             // Begin a new epoch
             self.block.epoch = self.block.epoch.next();
@@ -679,6 +981,7 @@ where
     ) -> Result<i64> {
         let value = value.as_ref();
         self.block.tree.update(key, value)?;
+        self.read_cache.write(key, value);
         self.db
             .batch_write_subspace_val(batch, self.block.height, key, value)
     }
@@ -692,9 +995,51 @@ where
         key: &Key,
     ) -> Result<i64> {
         self.block.tree.delete(key)?;
+        self.read_cache.delete(key);
         self.db
             .batch_delete_subspace_val(batch, self.block.height, key)
     }
+
+    /// Stage a change of the current block to the account subspace key in the
+    /// DB, instead of holding it in memory until the block is committed. A
+    /// `None` value stages the key's deletion. The reads see the staged
+    /// change, but the subspace and the Merkle tree are only updated with it
+    /// by [`Storage::batch_commit_staged_vals`].
+    pub fn stage_subspace_val(
+        &mut self,
+        key: &Key,
+        value: Option<&[u8]>,
+    ) -> Result<()> {
+        self.db.write_staged_val(key, value)?;
+        self.staged_keys.insert(key.clone());
+        Ok(())
+    }
+
+    /// Batch write the changes staged in the current block to the account
+    /// subspace and remove them from the staging area. The staged changes of
+    /// the keys that have been changed again since, for which `is_changed`
+    /// returns `true`, are only removed.
+    pub fn batch_commit_staged_vals(
+        &mut self,
+        batch: &mut D::WriteBatch,
+        is_changed: impl Fn(&Key) -> bool,
+    ) -> Result<()> {
+        for key in std::mem::take(&mut self.staged_keys) {
+            if !is_changed(&key) {
+                match self.db.read_staged_val(&key)? {
+                    Some(Some(value)) => {
+                        self.batch_write_subspace_val(batch, &key, value)?;
+                    }
+                    Some(None) => {
+                        self.batch_delete_subspace_val(batch, &key)?;
+                    }
+                    None => {}
+                }
+            }
+            self.db.batch_delete_staged_val(batch, &key)?;
+        }
+        Ok(())
+    }
 }
 
 impl From<MerkleTreeError> for Error {
@@ -739,6 +1084,9 @@ pub mod testing {
                 ),
                 #[cfg(feature = "ferveo-tpke")]
                 tx_queue: TxQueue::default(),
+                read_cache: ReadCache::default(),
+                block_events: Vec::new(),
+                staged_keys: HashSet::new(),
             }
         }
     }
@@ -754,6 +1102,68 @@ mod tests {
     use crate::ledger::parameters::Parameters;
     use crate::types::time::{self, Duration};
 
+    /// Test that the epoch advances exactly at the block at which both the
+    /// minimum number of blocks and the minimum duration have been reached.
+    #[test]
+    fn test_epoch_advances_at_boundary() {
+        let mut storage = TestStorage::default();
+        let parameters = Parameters {
+            epoch_duration: EpochDuration {
+                min_num_of_blocks: 3,
+                min_duration: Duration::seconds(10).into(),
+            },
+            max_expected_time_per_block: Duration::seconds(1).into(),
+            vp_whitelist: vec![],
+            tx_whitelist: vec![],
+            tx_ordering: Default::default(),
+            tx_size_limits: Default::default(),
+            fee_exemption: Default::default(),
+            fee_token_rates: Default::default(),
+            fee_refund_policy: Default::default(),
+            storage_gas_costs: Default::default(),
+            write_log_flush_threshold: None,
+            min_base_fee: Default::default(),
+        };
+        parameters::init_genesis_storage(&mut storage, &parameters);
+        let genesis_time: DateTimeUtc = Utc.timestamp(1_000_000, 0).into();
+        storage
+            .init_genesis_epoch(BlockHeight(1), genesis_time, &parameters)
+            .unwrap();
+        let time_at = |secs: u64| genesis_time + DurationSecs(secs);
+
+        let progress = storage.epoch_progress(BlockHeight(2), time_at(5));
+        assert!(!progress.is_over());
+        assert_eq!(progress.remaining_blocks(), 2);
+        assert_eq!(progress.remaining_duration(), DurationSecs(5));
+
+        // Enough blocks, but not enough time
+        assert!(!storage.update_epoch(BlockHeight(4), time_at(9)).unwrap());
+        assert_eq!(storage.block.epoch, Epoch(0));
+        // Enough time, but not enough blocks
+        let progress = storage.epoch_progress(BlockHeight(3), time_at(15));
+        assert!(!progress.is_over());
+        assert_eq!(progress.remaining_blocks(), 1);
+        assert_eq!(progress.remaining_duration(), DurationSecs(0));
+
+        // Both thresholds are reached
+        assert!(storage.update_epoch(BlockHeight(5), time_at(10)).unwrap());
+        assert_eq!(storage.block.epoch, Epoch(1));
+        let progress = storage.epoch_progress(BlockHeight(5), time_at(10));
+        assert_eq!(progress.first_block_height, BlockHeight(5));
+        assert_eq!(progress.num_of_blocks(), 0);
+        assert_eq!(progress.next_epoch_min_start_height, BlockHeight(8));
+        assert_eq!(progress.next_epoch_min_start_time, time_at(20));
+
+        // The next epoch's thresholds are counted from its first block
+        assert!(!storage.update_epoch(BlockHeight(8), time_at(19)).unwrap());
+        assert_eq!(storage.block.epoch, Epoch(1));
+        let progress = storage.epoch_progress(BlockHeight(8), time_at(19));
+        assert_eq!(progress.num_of_blocks(), 3);
+        assert_eq!(progress.remaining_duration(), DurationSecs(1));
+        assert!(storage.update_epoch(BlockHeight(9), time_at(20)).unwrap());
+        assert_eq!(storage.block.epoch, Epoch(2));
+    }
+
     prop_compose! {
         /// Setup test input data with arbitrary epoch duration, epoch start
         /// height and time, and a block height and time that are greater than
@@ -818,7 +1228,15 @@ mod tests {
                 epoch_duration: epoch_duration.clone(),
                 max_expected_time_per_block: Duration::seconds(max_expected_time_per_block).into(),
                 vp_whitelist: vec![],
-                tx_whitelist: vec![]
+                tx_whitelist: vec![],
+                tx_ordering: Default::default(),
+                tx_size_limits: Default::default(),
+                fee_exemption: Default::default(),
+                fee_token_rates: Default::default(),
+                fee_refund_policy: Default::default(),
+                storage_gas_costs: Default::default(),
+                write_log_flush_threshold: None,
+                min_base_fee: Default::default(),
             };
             parameters::init_genesis_storage(&mut storage, &parameters);
 
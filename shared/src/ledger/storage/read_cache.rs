@@ -0,0 +1,209 @@
+//! A per-block read-through cache of the values read from the storage's DB.
+//!
+//! The transaction and all the validity predicates it triggers often read the
+//! same storage keys (e.g. a token's VP or an account's public key). The cache
+//! memoizes the values read from the DB in the current block, so that repeated
+//! reads of the same key are served from memory. The storage writes and
+//! deletes go through the cache, so that a read after a write in the same block
+//! sees the new value. The cache is cleared when the block is committed.
+//!
+//! The size of the cached keys and values is bounded, the least recently used
+//! values are evicted first.
+
+use std::cmp;
+use std::collections::hash_map::RandomState;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use clru::{CLruCache, CLruCacheConfig, WeightScale};
+
+use crate::types::storage::Key;
+
+/// The default maximum size of the cached keys and values in bytes
+pub const DEFAULT_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+/// In-memory LRU cache of the values
+type ValuesCache = CLruCache<Key, Vec<u8>, RandomState, ValueScale>;
+
+/// Configures the cache scale of the values that limits the maximum capacity
+/// of the cache (CLruCache::len + CLruCache::weight <= CLruCache::capacity).
+#[derive(Debug)]
+struct ValueScale;
+
+impl WeightScale<Key, Vec<u8>> for ValueScale {
+    fn weight(&self, key: &Key, value: &Vec<u8>) -> usize {
+        // We only want to limit the max memory size, not the number of
+        // elements, so we use the size of the key and value as its scale
+        // and subtract 1 from it to negate the increment of the cache length.
+        cmp::max(1, key.len() + value.len()) - 1
+    }
+}
+
+/// The cache of the storage sub-space values read in the current block
+#[derive(Debug)]
+pub struct ReadCache {
+    /// The cached values. The lock is needed because the validity predicates
+    /// read the storage in parallel and a read updates the position of the
+    /// value in the LRU cache.
+    values: Mutex<ValuesCache>,
+    /// The number of reads that missed the cache and went to the DB
+    db_reads: AtomicU64,
+}
+
+impl Default for ReadCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_BYTES)
+    }
+}
+
+impl ReadCache {
+    /// Create a read cache with a given size limit of the cached keys and
+    /// values in bytes.
+    ///
+    /// # Panics
+    /// The `max_bytes` must be non-zero.
+    pub fn new(max_bytes: usize) -> Self {
+        let values = CLruCache::with_config(
+            CLruCacheConfig::new(NonZeroUsize::new(max_bytes).unwrap())
+                .with_scale(ValueScale),
+        );
+        Self {
+            values: Mutex::new(values),
+            db_reads: AtomicU64::default(),
+        }
+    }
+
+    /// Get a cached value, if any.
+    pub fn get(&self, key: &Key) -> Option<Vec<u8>> {
+        self.values
+            .lock()
+            .expect("The read cache lock shouldn't be poisoned")
+            .get(key)
+            .cloned()
+    }
+
+    /// Insert a value that has been read from the DB.
+    pub fn insert_read(&self, key: &Key, value: Vec<u8>) {
+        self.db_reads.fetch_add(1, Ordering::Relaxed);
+        Self::put(
+            &mut self
+                .values
+                .lock()
+                .expect("The read cache lock shouldn't be poisoned"),
+            key,
+            value,
+        );
+    }
+
+    /// Record a read from the DB that found no value.
+    pub fn record_miss(&self) {
+        self.db_reads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Update the cached value after it has been written to the DB.
+    pub fn write(&mut self, key: &Key, value: impl AsRef<[u8]>) {
+        Self::put(self.values_mut(), key, value.as_ref().to_vec());
+    }
+
+    /// Remove the cached value after it has been deleted from the DB.
+    pub fn delete(&mut self, key: &Key) {
+        self.values_mut().pop(key);
+    }
+
+    /// Clear all the cached values, e.g. when a block is committed.
+    pub fn clear(&mut self) {
+        self.values_mut().clear();
+    }
+
+    /// The number of reads that missed the cache and went to the DB.
+    pub fn db_reads(&self) -> u64 {
+        self.db_reads.load(Ordering::Relaxed)
+    }
+
+    /// The size of the cached keys and values in bytes.
+    pub fn size_bytes(&self) -> usize {
+        let values = self
+            .values
+            .lock()
+            .expect("The read cache lock shouldn't be poisoned");
+        values.len() + values.weight()
+    }
+
+    fn values_mut(&mut self) -> &mut ValuesCache {
+        self.values
+            .get_mut()
+            .expect("The read cache lock shouldn't be poisoned")
+    }
+
+    /// Cache a value, evicting the least recently used values to make room
+    /// for it. A value that is larger than the cache isn't cached and the
+    /// previous value of its key is removed, so that it's not read instead.
+    fn put(values: &mut ValuesCache, key: &Key, value: Vec<u8>) {
+        if let Err((key, _value)) = values.put_with_weight(key.clone(), value) {
+            values.pop(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_cache() {
+        let mut cache = ReadCache::default();
+        let key = Key::parse("key").expect("cannot parse the key string");
+
+        assert_eq!(cache.get(&key), None);
+        cache.insert_read(&key, vec![1]);
+        assert_eq!(cache.get(&key), Some(vec![1]));
+        assert_eq!(cache.db_reads(), 1);
+
+        // A write replaces the cached value without a DB read
+        cache.write(&key, [2]);
+        assert_eq!(cache.get(&key), Some(vec![2]));
+        assert_eq!(cache.db_reads(), 1);
+
+        cache.delete(&key);
+        assert_eq!(cache.get(&key), None);
+
+        cache.write(&key, [3]);
+        cache.clear();
+        assert_eq!(cache.get(&key), None);
+    }
+
+    #[test]
+    fn test_read_cache_max_bytes() {
+        let max_bytes = 1024;
+        let mut cache = ReadCache::new(max_bytes);
+        let keys: Vec<Key> = (0..10)
+            .map(|i| {
+                Key::parse(format!("key{}", i))
+                    .expect("cannot parse the key string")
+            })
+            .collect();
+
+        // Only the most recently used values fit in the cache
+        for key in &keys {
+            cache.insert_read(key, vec![0; 200]);
+            assert!(cache.size_bytes() <= max_bytes);
+        }
+        assert_eq!(cache.get(&keys[0]), None);
+        assert_eq!(cache.get(&keys[9]), Some(vec![0; 200]));
+
+        // A read keeps a value from being evicted
+        assert_eq!(cache.get(&keys[6]), Some(vec![0; 200]));
+        cache.write(&keys[0], [1; 200]);
+        cache.write(&keys[1], [1; 200]);
+        assert_eq!(cache.get(&keys[6]), Some(vec![0; 200]));
+        assert_eq!(cache.get(&keys[7]), None);
+        assert!(cache.size_bytes() <= max_bytes);
+
+        // A value larger than the cache isn't cached and it doesn't leave
+        // the previous value of its key in the cache
+        cache.write(&keys[0], vec![2; max_bytes + 1]);
+        assert_eq!(cache.get(&keys[0]), None);
+        assert!(cache.size_bytes() <= max_bytes);
+    }
+}
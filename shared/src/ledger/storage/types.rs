@@ -1,13 +1,23 @@
 //! The key and values that may be persisted in a DB.
 
+use std::io;
+
 use borsh::{BorshDeserialize, BorshSerialize};
 use thiserror::Error;
 
+use crate::types::key::common;
+use crate::types::token;
+
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Deserialization error: {0}")]
     DeserializationError(std::io::Error),
+    #[error(
+        "Unknown version {found} of a stored value, the latest known version \
+         is {latest}"
+    )]
+    UnknownVersion { found: u8, latest: u8 },
 }
 
 /// Result for functions that may fail
@@ -32,6 +42,122 @@ where
     T::try_from_slice(bytes.as_ref()).map_err(Error::DeserializationError)
 }
 
+/// The first byte of a value encoded with a version header by
+/// [`encode_versioned`]. It's followed by the version byte and the borsh
+/// encoding of the value.
+pub const VERSIONED_VALUE_MAGIC: u8 = 0xff;
+
+/// A value type with a versioned storage encoding. When the type's borsh
+/// encoding changes, its `VERSION` must be bumped and the older versions
+/// handled in [`VersionedValue::decode_version`], so that the values stored by
+/// an older release are upgraded on read instead of being silently misread.
+///
+/// The encoding of version `0` is the plain borsh encoding without a version
+/// header, used before the type was versioned. It must not be decodable from
+/// the versioned encoding and vice versa, which holds for the types with a
+/// fixed-size encoding.
+pub trait VersionedValue: BorshSerialize + BorshDeserialize {
+    /// The current version of the encoding
+    const VERSION: u8;
+
+    /// Decode a value from the encoding of an older `version`.
+    fn decode_version(version: u8, bytes: &[u8]) -> io::Result<Self>;
+}
+
+/// Encode a value with borsh, prefixed with a header of
+/// [`VERSIONED_VALUE_MAGIC`] and the current version of its encoding.
+pub fn encode_versioned<T>(value: &T) -> Vec<u8>
+where
+    T: VersionedValue,
+{
+    let mut result = vec![VERSIONED_VALUE_MAGIC, T::VERSION];
+    value.serialize(&mut result).expect("serialization failed");
+    result
+}
+
+/// Decode a value encoded with [`encode_versioned`] with any known version of
+/// its encoding, or a value encoded before the type was versioned.
+pub fn decode_versioned<T>(bytes: impl AsRef<[u8]>) -> Result<T>
+where
+    T: VersionedValue,
+{
+    let bytes = bytes.as_ref();
+    let versioned = match bytes {
+        [VERSIONED_VALUE_MAGIC, version, value @ ..] => {
+            if *version == T::VERSION {
+                decode(value)
+            } else if *version < T::VERSION {
+                T::decode_version(*version, value)
+                    .map_err(Error::DeserializationError)
+            } else {
+                Err(Error::UnknownVersion {
+                    found: *version,
+                    latest: T::VERSION,
+                })
+            }
+        }
+        _ => return decode_unversioned(bytes),
+    };
+    // A value encoded before the type was versioned may start with the
+    // magic byte too
+    versioned.or_else(|err| decode_unversioned(bytes).map_err(|_| err))
+}
+
+/// Re-encode a value with the current version of its encoding, if it's
+/// encoded with an older one. Returns `None` if the value is up-to-date. This
+/// can be used to upgrade the stored values in a migration step.
+pub fn migrate_versioned<T>(bytes: impl AsRef<[u8]>) -> Result<Option<Vec<u8>>>
+where
+    T: VersionedValue,
+{
+    let bytes = bytes.as_ref();
+    let value: T = decode_versioned(bytes)?;
+    let upgraded = encode_versioned(&value);
+    Ok(if upgraded == bytes {
+        None
+    } else {
+        Some(upgraded)
+    })
+}
+
+fn decode_unversioned<T>(bytes: &[u8]) -> Result<T>
+where
+    T: VersionedValue,
+{
+    T::decode_version(0, bytes).map_err(Error::DeserializationError)
+}
+
+/// Decode a value that has a single version of its encoding besides the
+/// unversioned one, which are the same borsh encoding.
+fn decode_first_version<T>(version: u8, bytes: &[u8]) -> io::Result<T>
+where
+    T: BorshDeserialize,
+{
+    match version {
+        0 => T::try_from_slice(bytes),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unknown encoding version {}", version),
+        )),
+    }
+}
+
+impl VersionedValue for token::Amount {
+    const VERSION: u8 = 1;
+
+    fn decode_version(version: u8, bytes: &[u8]) -> io::Result<Self> {
+        decode_first_version(version, bytes)
+    }
+}
+
+impl VersionedValue for common::PublicKey {
+    const VERSION: u8 = 1;
+
+    fn decode_version(version: u8, bytes: &[u8]) -> io::Result<Self> {
+        decode_first_version(version, bytes)
+    }
+}
+
 /// A key-value pair as raw bytes
 pub type KVBytes = (Box<[u8]>, Box<[u8]>);
 
@@ -58,3 +184,111 @@ impl<I> std::fmt::Debug for PrefixIterator<I> {
         f.write_str("PrefixIterator")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::key::{self, RefTo};
+
+    /// The first version of a test value, storing an amount in whole tokens
+    #[derive(BorshSerialize, BorshDeserialize)]
+    struct ValueV1 {
+        whole_tokens: u32,
+    }
+
+    impl VersionedValue for ValueV1 {
+        const VERSION: u8 = 1;
+
+        fn decode_version(version: u8, bytes: &[u8]) -> io::Result<Self> {
+            decode_first_version(version, bytes)
+        }
+    }
+
+    /// The second version of the test value, storing the amount in micro
+    /// units
+    #[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    struct ValueV2 {
+        micro: u64,
+    }
+
+    impl VersionedValue for ValueV2 {
+        const VERSION: u8 = 2;
+
+        fn decode_version(version: u8, bytes: &[u8]) -> io::Result<Self> {
+            let v1 = match version {
+                0 | 1 => ValueV1::try_from_slice(bytes)?,
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Unknown encoding version",
+                    ));
+                }
+            };
+            Ok(Self {
+                micro: v1.whole_tokens as u64 * 1_000_000,
+            })
+        }
+    }
+
+    /// Test that a value written with an older version of the encoding is
+    /// upgraded when it's read with a newer version.
+    #[test]
+    fn test_versioned_value_upgrade() {
+        let v1 = encode_versioned(&ValueV1 { whole_tokens: 5 });
+        assert_eq!(&v1[..2], &[VERSIONED_VALUE_MAGIC, 1]);
+
+        let upgraded: ValueV2 = decode_versioned(&v1).unwrap();
+        assert_eq!(upgraded, ValueV2 { micro: 5_000_000 });
+
+        // The unversioned encoding is upgraded too
+        let unversioned = encode(&ValueV1 { whole_tokens: 7 });
+        let upgraded: ValueV2 = decode_versioned(&unversioned).unwrap();
+        assert_eq!(upgraded, ValueV2 { micro: 7_000_000 });
+
+        // A migration re-encodes the value with the latest version
+        let migrated = migrate_versioned::<ValueV2>(&v1).unwrap().unwrap();
+        assert_eq!(&migrated[..2], &[VERSIONED_VALUE_MAGIC, 2]);
+        assert!(migrate_versioned::<ValueV2>(&migrated).unwrap().is_none());
+
+        // A value from a newer version than known can't be decoded
+        let v2 = encode_versioned(&ValueV2 { micro: 1 });
+        assert!(matches!(
+            decode_versioned::<ValueV1>(&v2),
+            Err(Error::UnknownVersion {
+                found: 2,
+                latest: 1
+            })
+        ));
+    }
+
+    /// Test that the amounts and public keys stored without a version header
+    /// can still be read.
+    #[test]
+    fn test_versioned_amount_and_public_key() {
+        // The unversioned encoding of this amount starts with the magic byte
+        let amount = token::Amount::from(0xff);
+        let unversioned = encode(&amount);
+        assert_eq!(unversioned[0], VERSIONED_VALUE_MAGIC);
+        assert_eq!(
+            decode_versioned::<token::Amount>(&unversioned).unwrap(),
+            amount
+        );
+        let versioned = encode_versioned(&amount);
+        assert_eq!(
+            decode_versioned::<token::Amount>(&versioned).unwrap(),
+            amount
+        );
+
+        let pk: common::PublicKey = key::testing::keypair_1().ref_to();
+        let unversioned = encode(&pk);
+        assert_eq!(
+            decode_versioned::<common::PublicKey>(&unversioned).unwrap(),
+            pk
+        );
+        let versioned = encode_versioned(&pk);
+        assert_eq!(
+            decode_versioned::<common::PublicKey>(&versioned).unwrap(),
+            pk
+        );
+    }
+}
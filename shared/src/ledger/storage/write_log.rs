@@ -1,15 +1,17 @@
 //! Write log is temporary storage for modifications performed by a transaction.
 //! before they are committed to the ledger's storage.
 
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 use thiserror::Error;
 
 use crate::ledger;
-use crate::ledger::storage::{Storage, StorageHasher};
+use crate::ledger::storage::{MerkleTree, Storage, StorageHasher};
+use crate::ledger::{governance, pos};
 use crate::types::address::{Address, EstablishedAddressGen};
 use crate::types::ibc::IbcEvent;
-use crate::types::storage;
+use crate::types::storage::{self, DbKeySeg};
+use crate::types::token;
 
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
@@ -69,6 +71,11 @@ pub struct WriteLog {
     tx_write_log: HashMap<storage::Key, StorageModification>,
     /// The IBC event for the current transaction
     ibc_event: Option<IbcEvent>,
+    /// The size in bytes of the block write log above which its changes are
+    /// staged in the storage, see [`WriteLog::stage_block_changes`]
+    flush_threshold: Option<u64>,
+    /// The approximate size in bytes of the block write log
+    block_write_log_bytes: u64,
 }
 
 impl Default for WriteLog {
@@ -78,6 +85,8 @@ impl Default for WriteLog {
             block_write_log: HashMap::with_capacity(100_000),
             tx_write_log: HashMap::with_capacity(100),
             ibc_event: None,
+            flush_threshold: None,
+            block_write_log_bytes: 0,
         }
     }
 }
@@ -312,6 +321,13 @@ impl WriteLog {
             .collect()
     }
 
+    /// Get the addresses of the accounts affected by the current transaction,
+    /// i.e. the owners of the storage keys that it changed. See [`key_owner`]
+    /// for how a key's owner is found.
+    pub fn affected_addresses(&self) -> BTreeSet<Address> {
+        self.tx_write_log.keys().filter_map(key_owner).collect()
+    }
+
     /// Take the IBC event of the current transaction
     pub fn take_ibc_event(&mut self) -> Option<IbcEvent> {
         self.ibc_event.take()
@@ -330,9 +346,69 @@ impl WriteLog {
             &mut self.tx_write_log,
             HashMap::with_capacity(100),
         );
+        self.block_write_log_bytes += tx_write_log
+            .iter()
+            .map(|(key, entry)| (key.len() + entry_len(entry)) as u64)
+            .sum::<u64>();
         self.block_write_log.extend(tx_write_log);
     }
 
+    /// Set the size in bytes of the block write log above which its changes
+    /// are staged in the storage by [`WriteLog::stage_block_changes`]. When
+    /// not set, the changes are held in memory until the block is committed.
+    /// The staging changes the gas of the storage access, so the threshold
+    /// must be the one given by the protocol parameter.
+    pub fn set_flush_threshold(&mut self, threshold: Option<u64>) {
+        self.flush_threshold = threshold;
+    }
+
+    /// When the block write log is over the flush threshold, stage its
+    /// changes in the storage to bound the memory used by a big block. The
+    /// staged changes are still seen by the storage reads and they're applied
+    /// when the block is committed with [`WriteLog::commit_block`], which
+    /// results in the same state as when they're held in memory. Returns
+    /// `true` if the changes have been staged.
+    pub fn stage_block_changes<DB, H>(
+        &mut self,
+        storage: &mut Storage<DB, H>,
+    ) -> Result<bool>
+    where
+        DB: 'static
+            + ledger::storage::DB
+            + for<'iter> ledger::storage::DBIter<'iter>,
+        H: StorageHasher,
+    {
+        match self.flush_threshold {
+            Some(threshold) if self.block_write_log_bytes > threshold => {}
+            _ => return Ok(false),
+        }
+        let mut kept = HashMap::new();
+        for (key, entry) in self.block_write_log.drain() {
+            let value = match &entry {
+                _ if !key.is_updatable() => None,
+                StorageModification::Write { value } => Some(Some(value)),
+                StorageModification::Delete => Some(None),
+                StorageModification::InitAccount { vp } => Some(Some(vp)),
+                // temporary value isn't persisted
+                StorageModification::Temp { .. } => None,
+            };
+            match value {
+                Some(value) => storage
+                    .stage_subspace_val(&key, value.map(Vec::as_slice))
+                    .map_err(Error::StorageError)?,
+                None => {
+                    kept.insert(key, entry);
+                }
+            }
+        }
+        self.block_write_log_bytes = kept
+            .iter()
+            .map(|(key, entry)| (key.len() + entry_len(entry)) as u64)
+            .sum();
+        self.block_write_log = kept;
+        Ok(true)
+    }
+
     /// Drop the current transaction's write log when it's declined by any of
     /// the triggered validity predicates. Starts a new transaction write log.
     pub fn drop_tx(&mut self) {
@@ -340,11 +416,12 @@ impl WriteLog {
     }
 
     /// Commit the current block's write log to the storage. Starts a new block
-    /// write log.
+    /// write log. It can only fail with an error of the storage, which must be
+    /// treated as a storage corruption.
     pub fn commit_block<DB, H>(
         &mut self,
         storage: &mut Storage<DB, H>,
-    ) -> Result<()>
+    ) -> ledger::storage::Result<()>
     where
         DB: 'static
             + ledger::storage::DB
@@ -352,6 +429,13 @@ impl WriteLog {
         H: StorageHasher,
     {
         let mut batch = Storage::<DB, H>::batch();
+        // The changes staged in the storage that haven't been changed again
+        storage.batch_commit_staged_vals(&mut batch, |key| {
+            !matches!(
+                self.block_write_log.get(key),
+                None | Some(StorageModification::Temp { .. })
+            )
+        })?;
         for (key, entry) in self
             .block_write_log
             .iter()
@@ -359,33 +443,58 @@ impl WriteLog {
         {
             match entry {
                 StorageModification::Write { value } => {
-                    storage
-                        .batch_write_subspace_val(
-                            &mut batch,
-                            key,
-                            value.clone(),
-                        )
-                        .map_err(Error::StorageError)?;
+                    storage.batch_write_subspace_val(
+                        &mut batch,
+                        key,
+                        value.clone(),
+                    )?;
                 }
                 StorageModification::Delete => {
-                    storage
-                        .batch_delete_subspace_val(&mut batch, key)
-                        .map_err(Error::StorageError)?;
+                    storage.batch_delete_subspace_val(&mut batch, key)?;
                 }
                 StorageModification::InitAccount { vp } => {
-                    storage
-                        .batch_write_subspace_val(&mut batch, key, vp.clone())
-                        .map_err(Error::StorageError)?;
+                    storage.batch_write_subspace_val(
+                        &mut batch,
+                        key,
+                        vp.clone(),
+                    )?;
                 }
                 // temporary value isn't persisted
                 StorageModification::Temp { .. } => {}
             }
         }
-        storage.exec_batch(batch).map_err(Error::StorageError)?;
+        storage.exec_batch(batch)?;
         if let Some(address_gen) = self.address_gen.take() {
             storage.address_gen = address_gen
         }
         self.block_write_log.clear();
+        self.block_write_log_bytes = 0;
+        Ok(())
+    }
+
+    /// Update the given merkle tree with the block's write log, like when the
+    /// block is committed with [`WriteLog::commit_block`], but without
+    /// writing anything to the storage. This can be used on a copy of the
+    /// storage's tree to find the root that committing the block would
+    /// produce.
+    pub fn update_merkle_tree<H>(&self, tree: &mut MerkleTree<H>) -> Result<()>
+    where
+        H: StorageHasher,
+    {
+        for (key, entry) in self
+            .block_write_log
+            .iter()
+            .filter(|(k, _)| k.is_updatable())
+        {
+            let result = match entry {
+                StorageModification::Write { value } => tree.update(key, value),
+                StorageModification::Delete => tree.delete(key),
+                StorageModification::InitAccount { vp } => tree.update(key, vp),
+                // temporary value isn't persisted
+                StorageModification::Temp { .. } => Ok(()),
+            };
+            result.map_err(|err| Error::StorageError(err.into()))?;
+        }
         Ok(())
     }
 
@@ -426,12 +535,164 @@ impl WriteLog {
     }
 }
 
+/// Get the address of the account that owns the given storage key, if any:
+/// - the owner of a token balance key
+/// - the validator of a PoS validator's key
+/// - the source of a PoS bond, unbond or rewards key
+/// - the voter of a governance vote key
+/// - otherwise, the first address in the key, e.g. of an account's VP or
+///   public key or of any other key in an account's sub-space
+pub fn key_owner(key: &storage::Key) -> Option<Address> {
+    if let Some(owner) = token::is_any_token_balance_key(key) {
+        return Some(owner.clone());
+    }
+    if let Some(validator) = pos::is_validator_key(key) {
+        return Some(validator.clone());
+    }
+    if let Some(bond_id) = pos::is_bond_key(key)
+        .or_else(|| pos::is_unbond_key(key))
+        .or_else(|| pos::is_rewards_key(key))
+    {
+        return Some(bond_id.source);
+    }
+    if let Some(voter) = governance::storage::get_voter_address(key) {
+        return Some(voter.clone());
+    }
+    // The VP and public keys, like any other keys of an account, are in the
+    // account's sub-space
+    key.segments.iter().find_map(|segment| match segment {
+        DbKeySeg::AddressSeg(addr) => Some(addr.clone()),
+        DbKeySeg::StringSeg(_) => None,
+    })
+}
+
+/// The length of the value of a storage modification
+fn entry_len(entry: &StorageModification) -> usize {
+    match entry {
+        StorageModification::Write { value }
+        | StorageModification::Temp { value } => value.len(),
+        StorageModification::Delete => 0,
+        StorageModification::InitAccount { vp } => vp.len(),
+    }
+}
+
+/// The storage seen through a write log. The values are read from the write
+/// log before the storage and they are written to the write log. The ledger
+/// uses it to make its own changes in a block, so that they're committed
+/// together with the block's txs.
+#[derive(Debug)]
+pub struct WriteLogStorage<'a, DB, H>
+where
+    DB: ledger::storage::DB + for<'iter> ledger::storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    /// The write log on top of the storage
+    pub write_log: &'a mut WriteLog,
+    /// The storage, which is only read
+    pub storage: &'a Storage<DB, H>,
+}
+
+impl<'a, DB, H> WriteLogStorage<'a, DB, H>
+where
+    DB: ledger::storage::DB + for<'iter> ledger::storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    /// Create the storage seen through the given write log
+    pub fn new(
+        write_log: &'a mut WriteLog,
+        storage: &'a Storage<DB, H>,
+    ) -> Self {
+        Self { write_log, storage }
+    }
+
+    /// Read a value from the write log or, if it's not there, from the
+    /// storage. Returns the value and the gas cost.
+    pub fn read(&self, key: &storage::Key) -> Result<(Option<Vec<u8>>, u64)> {
+        match self.write_log.read(key) {
+            (Some(StorageModification::Write { value }), gas) => {
+                Ok((Some(value.clone()), gas))
+            }
+            (Some(StorageModification::InitAccount { vp }), gas) => {
+                Ok((Some(vp.clone()), gas))
+            }
+            // temporary values are not seen in the storage
+            (
+                Some(
+                    StorageModification::Delete
+                    | StorageModification::Temp { .. },
+                ),
+                gas,
+            ) => Ok((None, gas)),
+            (None, gas) => {
+                let (value, read_gas) = self
+                    .storage
+                    .read_with_staged(key)
+                    .map_err(Error::StorageError)?;
+                Ok((value, gas + read_gas))
+            }
+        }
+    }
+
+    /// Write a value to the write log. Returns the gas cost and the size
+    /// difference.
+    pub fn write(
+        &mut self,
+        key: &storage::Key,
+        value: impl AsRef<[u8]>,
+    ) -> Result<(u64, i64)> {
+        self.write_log.write(key, value.as_ref().to_vec())
+    }
+
+    /// Iterate over the key-values with the given prefix in the storage, with
+    /// the changes in the write log applied. Returns the iterator and the gas
+    /// cost.
+    pub fn iter_prefix(
+        &self,
+        prefix: &storage::Key,
+    ) -> (impl Iterator<Item = (String, Vec<u8>, u64)>, u64) {
+        let (iter, gas) = self.storage.iter_prefix(prefix);
+        let mut entries: BTreeMap<String, (Vec<u8>, u64)> =
+            iter.map(|(key, value, gas)| (key, (value, gas))).collect();
+        let prefix = prefix.to_string();
+        // the tx's changes are applied over the block's changes
+        let changes = self
+            .write_log
+            .block_write_log
+            .iter()
+            .chain(self.write_log.tx_write_log.iter());
+        for (key, entry) in changes {
+            let key_str = key.to_string();
+            if !key_str.starts_with(&prefix) {
+                continue;
+            }
+            match entry {
+                StorageModification::Write { value }
+                | StorageModification::InitAccount { vp: value } => {
+                    let gas = (key.len() + value.len()) as u64;
+                    entries.insert(key_str, (value.clone(), gas));
+                }
+                StorageModification::Delete
+                | StorageModification::Temp { .. } => {
+                    entries.remove(&key_str);
+                }
+            }
+        }
+        let iter = entries
+            .into_iter()
+            .map(|(key, (value, gas))| (key, value, gas));
+        (iter, gas)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use pretty_assertions::assert_eq;
     use proptest::prelude::*;
 
     use super::*;
+    use crate::ledger::gas::VpGasMeter;
+    use crate::ledger::vp_env;
+    use crate::types::storage::KeySeg;
     use crate::types::{address, storage};
 
     #[test]
@@ -620,6 +881,152 @@ mod tests {
         assert_eq!(value, None);
     }
 
+    /// Test that a block whose changes are staged in the storage when the
+    /// write log is over the flush threshold results in the same state and
+    /// Merkle root as when they're all held in memory, and that the txs'
+    /// prefix iteration of the posterior state sees the same values
+    #[test]
+    fn test_commit_with_flush_threshold() {
+        let keys: Vec<storage::Key> = (0..50)
+            .map(|i| {
+                storage::Key::parse(format!("key{}", i))
+                    .expect("cannot parse the key string")
+            })
+            .collect();
+        let run_block = |flush_threshold: Option<u64>| {
+            let mut storage =
+                crate::ledger::storage::testing::TestStorage::default();
+            // some values committed before the block
+            for key in &keys[..10] {
+                storage.write(key, vec![0_u8; 512]).expect("write failed");
+            }
+            let mut write_log = WriteLog::default();
+            write_log.set_flush_threshold(flush_threshold);
+            let mut num_of_stagings = 0;
+            let mut iterated = vec![];
+            for tx in 0..20_usize {
+                for i in 0..10 {
+                    let key = &keys[(tx * 7 + i) % keys.len()];
+                    if i % 4 == 0 {
+                        write_log.delete(key).unwrap();
+                    } else {
+                        write_log.write(key, vec![tx as u8; 1024]).unwrap();
+                    }
+                }
+                write_log.commit_tx();
+                if write_log
+                    .stage_block_changes(&mut storage)
+                    .expect("staging failed")
+                {
+                    num_of_stagings += 1;
+                }
+                // the block's changes are seen by the reads, staged or not
+                let key = &keys[(tx * 7 + 1) % keys.len()];
+                let value = match write_log.read(key).0 {
+                    Some(StorageModification::Write { value }) => {
+                        Some(value.clone())
+                    }
+                    _ => storage.read_with_staged(key).expect("read failed").0,
+                };
+                assert_eq!(value, Some(vec![tx as u8; 1024]));
+                // iterate the posterior state of the keys
+                let prefix =
+                    storage::Key::parse("key").expect("cannot parse the key");
+                let (mut iter, _gas) = storage.iter_prefix(&prefix);
+                let mut gas_meter = VpGasMeter::new(0);
+                while let Some(key_val) = vp_env::iter_post_next(
+                    &mut gas_meter,
+                    &storage,
+                    &write_log,
+                    &mut iter,
+                )
+                .expect("iteration failed")
+                {
+                    iterated.push(key_val);
+                }
+            }
+            write_log.commit_block(&mut storage).expect("commit failed");
+            let values: Vec<Option<Vec<u8>>> = keys
+                .iter()
+                .map(|key| storage.read(key).expect("read failed").0)
+                .collect();
+            (num_of_stagings, storage.merkle_root().0, values, iterated)
+        };
+
+        let (num_of_stagings, root, values, iterated) = run_block(None);
+        assert_eq!(num_of_stagings, 0);
+        let (num_of_stagings, spilled_root, spilled_values, spilled_iterated) =
+            run_block(Some(4 * 1024));
+        assert!(num_of_stagings > 1);
+        assert_eq!(spilled_root, root);
+        assert_eq!(spilled_values, values);
+        assert_eq!(spilled_iterated, iterated);
+    }
+
+    /// Test that the affected addresses are the owners of the changed keys of
+    /// various kinds
+    #[test]
+    fn test_affected_addresses() {
+        let mut write_log = WriteLog::default();
+        let balance_owner = address::testing::established_address_1();
+        let vp_owner = address::testing::established_address_2();
+        let pk_owner = address::testing::established_address_3();
+        let validator = address::testing::established_address_4();
+        let bond_source = address::gen_established_address("bond source");
+        let voter = address::gen_established_address("voter");
+        let account = address::gen_established_address("account");
+
+        let vote_key = storage::Key::from(governance::ADDRESS.to_db_key())
+            .push(&"proposal".to_owned())
+            .and_then(|key| key.push(&"0".to_owned()))
+            .and_then(|key| key.push(&"vote".to_owned()))
+            .and_then(|key| key.push(&voter))
+            .unwrap();
+        let bond_id = pos::BondId {
+            source: bond_source.clone(),
+            validator: validator.clone(),
+        };
+        let keys = [
+            token::balance_key(&address::xan(), &balance_owner),
+            storage::Key::validity_predicate(&vp_owner),
+            crate::types::key::pk_key(&pk_owner),
+            pos::validator_state_key(&validator),
+            pos::bond_key(&bond_id),
+            pos::validator_set_key(),
+            vote_key,
+            storage::Key::from(account.to_db_key())
+                .push(&"counter".to_owned())
+                .unwrap(),
+            // A key without any address has no owner
+            storage::Key::parse("key").unwrap(),
+        ];
+        for key in &keys {
+            write_log.write(key, vec![1]).unwrap();
+        }
+        write_log
+            .delete(&token::balance_key(&address::btc(), &balance_owner))
+            .unwrap();
+
+        let expected: BTreeSet<Address> = [
+            balance_owner,
+            vp_owner,
+            pk_owner,
+            validator,
+            bond_source,
+            pos::ADDRESS,
+            voter,
+            account,
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(write_log.affected_addresses(), expected);
+        assert_eq!(key_owner(&keys[8]), None);
+
+        // The changes of a committed tx are not affecting the next tx
+        write_log.commit_tx();
+        assert!(write_log.affected_addresses().is_empty());
+    }
+
     prop_compose! {
         fn arb_verifiers_changed_key_tx_all_key()
             (verifiers_from_tx in testing::arb_verifiers_from_tx())
@@ -630,6 +1037,51 @@ mod tests {
         }
     }
 
+    /// Test that the storage seen through a write log reads and iterates the
+    /// values of the write log over those of the storage and only writes to
+    /// the write log
+    #[test]
+    fn test_write_log_storage() {
+        let mut storage =
+            crate::ledger::storage::testing::TestStorage::default();
+        let mut write_log = WriteLog::default();
+        let prefix =
+            storage::Key::parse("prefix").expect("cannot parse the key string");
+        let key = |name: &str| prefix.push(&name.to_owned()).unwrap();
+        let other_key =
+            storage::Key::parse("other").expect("cannot parse the key string");
+        storage.write(&key("a"), vec![1]).unwrap();
+        storage.write(&key("b"), vec![2]).unwrap();
+        storage.write(&key("c"), vec![3]).unwrap();
+        // changes of the block and of the current tx
+        write_log.write(&key("b"), vec![20]).unwrap();
+        write_log.write(&key("d"), vec![4]).unwrap();
+        write_log.commit_tx();
+        write_log.delete(&key("c")).unwrap();
+        write_log.write(&other_key, vec![5]).unwrap();
+
+        let mut wl_storage = WriteLogStorage::new(&mut write_log, &storage);
+        assert_eq!(wl_storage.read(&key("a")).unwrap().0, Some(vec![1]));
+        assert_eq!(wl_storage.read(&key("b")).unwrap().0, Some(vec![20]));
+        assert_eq!(wl_storage.read(&key("c")).unwrap().0, None);
+        assert_eq!(wl_storage.read(&key("d")).unwrap().0, Some(vec![4]));
+        let (iter, _gas) = wl_storage.iter_prefix(&prefix);
+        let entries: Vec<(String, Vec<u8>)> =
+            iter.map(|(key, value, _gas)| (key, value)).collect();
+        assert_eq!(
+            entries,
+            vec![
+                (key("a").to_string(), vec![1]),
+                (key("b").to_string(), vec![20]),
+                (key("d").to_string(), vec![4]),
+            ]
+        );
+
+        wl_storage.write(&key("a"), vec![10]).unwrap();
+        assert_eq!(wl_storage.read(&key("a")).unwrap().0, Some(vec![10]));
+        assert_eq!(storage.read(&key("a")).unwrap().0, Some(vec![1]));
+    }
+
     proptest! {
         /// Test [`WriteLog::verifiers_changed_keys`] that:
         /// 1. Every address from `verifiers_from_tx` is included in the
@@ -6,13 +6,14 @@ use std::num::TryFromIntError;
 use thiserror::Error;
 
 use super::gas::MIN_STORAGE_GAS;
-use crate::ledger::gas;
 use crate::ledger::gas::VpGasMeter;
 use crate::ledger::storage::write_log::WriteLog;
 use crate::ledger::storage::{self, write_log, Storage, StorageHasher};
+use crate::ledger::{gas, parameters};
 use crate::proto::Tx;
 use crate::types::hash::Hash;
 use crate::types::storage::{BlockHash, BlockHeight, Epoch, Key};
+use crate::types::time::DateTimeUtc;
 
 /// These runtime errors will abort VP execution immediately
 #[allow(missing_docs)]
@@ -24,6 +25,8 @@ pub enum RuntimeError {
     StorageError(storage::Error),
     #[error("Storage data error: {0}")]
     StorageDataError(crate::types::storage::Error),
+    #[error("Protocol parameters read error: {0}")]
+    ParametersReadError(parameters::ReadError),
     #[error("Encoding error: {0}")]
     EncodingError(std::io::Error),
     #[error("Numeric conversion error: {0}")]
@@ -34,6 +37,8 @@ pub enum RuntimeError {
     ReadTemporaryValueError,
     #[error("Trying to read a permament value with read_temp")]
     ReadPermanentValueError,
+    #[error("The wasm execution timed out")]
+    Timeout,
 }
 
 /// VP environment function result
@@ -81,8 +86,9 @@ where
         }
         None => {
             // When not found in write log, try to read from the storage
-            let (value, gas) =
-                storage.read(key).map_err(RuntimeError::StorageError)?;
+            let (value, gas) = storage
+                .read_with_staged(key)
+                .map_err(RuntimeError::StorageError)?;
             add_gas(gas_meter, gas)?;
             Ok(value)
         }
@@ -123,8 +129,9 @@ where
         }
         None => {
             // When not found in write log, try to read from the storage
-            let (value, gas) =
-                storage.read(key).map_err(RuntimeError::StorageError)?;
+            let (value, gas) = storage
+                .read_with_staged(key)
+                .map_err(RuntimeError::StorageError)?;
             add_gas(gas_meter, gas)?;
             Ok(value)
         }
@@ -160,8 +167,9 @@ where
     DB: storage::DB + for<'iter> storage::DBIter<'iter>,
     H: StorageHasher,
 {
-    let (present, gas) =
-        storage.has_key(key).map_err(RuntimeError::StorageError)?;
+    let (present, gas) = storage
+        .has_key_with_staged(key)
+        .map_err(RuntimeError::StorageError)?;
     add_gas(gas_meter, gas)?;
     Ok(present)
 }
@@ -191,8 +199,9 @@ where
         Some(&write_log::StorageModification::Temp { .. }) => Ok(true),
         None => {
             // When not found in write log, try to check the storage
-            let (present, gas) =
-                storage.has_key(key).map_err(RuntimeError::StorageError)?;
+            let (present, gas) = storage
+                .has_key_with_staged(key)
+                .map_err(RuntimeError::StorageError)?;
             add_gas(gas_meter, gas)?;
             Ok(present)
         }
@@ -228,6 +237,24 @@ where
     Ok(height)
 }
 
+/// Getting the block time. The time is that of the header of the block to
+/// which the current transaction is being applied. It's `None` outside of a
+/// block, e.g. when a transaction is dry-ran.
+pub fn get_block_time<DB, H>(
+    gas_meter: &mut VpGasMeter,
+    storage: &Storage<DB, H>,
+) -> Result<Option<DateTimeUtc>>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    let (header, gas) = storage
+        .get_block_header(None)
+        .map_err(RuntimeError::StorageError)?;
+    add_gas(gas_meter, gas)?;
+    Ok(header.and_then(|header| DateTimeUtc::try_from(header.time).ok()))
+}
+
 /// Getting the block hash. The height is that of the block to which the
 /// current transaction is being applied.
 pub fn get_block_hash<DB, H>(
@@ -266,6 +293,23 @@ where
     Ok(epoch)
 }
 
+/// Getting the random seed of the current epoch. The seed is derived from the
+/// chain's history, so every node sees the same one.
+pub fn get_epoch_seed<DB, H>(
+    gas_meter: &mut VpGasMeter,
+    storage: &Storage<DB, H>,
+    write_log: &WriteLog,
+) -> Result<Hash>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    let (seed, gas) = parameters::read_block_epoch_seed(storage, write_log)
+        .map_err(RuntimeError::ParametersReadError)?;
+    add_gas(gas_meter, gas)?;
+    Ok(seed)
+}
+
 /// Storage prefix iterator. It will try to get an iterator from the storage.
 pub fn iter_prefix<'a, DB, H>(
     gas_meter: &mut VpGasMeter,
@@ -299,14 +343,16 @@ where
 
 /// Storage prefix iterator next for posterior state (after tx execution). It
 /// will try to read from the write log first and if no entry found then from
-/// the storage.
-pub fn iter_post_next<DB>(
+/// the block's changes staged in the storage and lastly from the iterator.
+pub fn iter_post_next<DB, H>(
     gas_meter: &mut VpGasMeter,
+    storage: &Storage<DB, H>,
     write_log: &WriteLog,
     iter: &mut <DB as storage::DBIter<'_>>::PrefixIter,
 ) -> Result<Option<(String, Vec<u8>)>>
 where
     DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
 {
     for (key, val, iter_gas) in iter {
         let (log_val, log_gas) = write_log.read(
@@ -328,7 +374,21 @@ where
             Some(&write_log::StorageModification::Temp { .. }) => {
                 return Err(RuntimeError::ReadTemporaryValueError);
             }
-            None => return Ok(Some((key, val))),
+            None => {
+                let (staged_val, staged_gas) = storage
+                    .read_staged(
+                        &Key::parse(key.clone())
+                            .map_err(RuntimeError::StorageDataError)?,
+                    )
+                    .map_err(RuntimeError::StorageError)?;
+                add_gas(gas_meter, staged_gas)?;
+                match staged_val {
+                    Some(Some(value)) => return Ok(Some((key, value))),
+                    // check the next because the key has already deleted
+                    Some(None) => continue,
+                    None => return Ok(Some((key, val))),
+                }
+            }
         }
     }
     Ok(None)
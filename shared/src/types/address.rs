@@ -9,6 +9,7 @@ use std::string;
 
 use bech32::{self, FromBase32, ToBase32, Variant};
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use thiserror::Error;
@@ -19,12 +20,16 @@ use crate::types::key::PublicKeyHash;
 /// The length of an established [`Address`] encoded with Borsh.
 pub const ESTABLISHED_ADDRESS_BYTES_LEN: usize = 45;
 
-/// The length of [`Address`] encoded with Bech32m.
-pub const ADDRESS_LEN: usize = 79 + ADDRESS_HRP.len();
+/// The length of [`Address`] encoded with Bech32m with the default
+/// human-readable part.
+pub const ADDRESS_LEN: usize = 79 + DEFAULT_ADDRESS_HRP.len();
 
-/// human-readable part of Bech32m encoded address
+/// The default human-readable part of Bech32m encoded address. The addresses
+/// in the protocol's data, i.e. in the storage keys and in the calls from the
+/// wasm code to the host, are always encoded with it, so that they don't
+/// depend on the chain's human-readable part.
 // TODO use "a" for live network
-const ADDRESS_HRP: &str = "atest";
+pub const DEFAULT_ADDRESS_HRP: &str = "atest";
 const ADDRESS_BECH32_VARIANT: bech32::Variant = Variant::Bech32m;
 pub(crate) const HASH_LEN: usize = 40;
 
@@ -80,9 +85,17 @@ pub enum Error {
     #[error("Error decoding address from base32: {0}")]
     DecodeBase32(bech32::Error),
     #[error(
-        "Unexpected Bech32m human-readable part {0}, expected {ADDRESS_HRP}"
+        "Unexpected Bech32m human-readable part {prefix}, expected \
+         {expected}. The address may belong to a different network."
     )]
-    UnexpectedBech32Prefix(String),
+    UnexpectedBech32Prefix { prefix: String, expected: String },
+    #[error("Invalid Bech32m human-readable part {0}")]
+    InvalidHrp(String),
+    #[error(
+        "The Bech32m human-readable part is already set to {current}, it \
+         cannot be changed to {hrp}"
+    )]
+    HrpAlreadySet { hrp: String, current: String },
     #[error(
         "Unexpected Bech32m variant {0:?}, expected {ADDRESS_BECH32_VARIANT:?}"
     )]
@@ -98,6 +111,48 @@ pub enum Error {
 /// Result of a function that may fail
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// The human-readable part of the addresses of the chain that this process
+/// works with, if it's been set with [`set_address_hrp`]
+static ADDRESS_HRP: OnceCell<String> = OnceCell::new();
+
+/// Set the human-readable part of the addresses of the chain that this
+/// process works with. The addresses are then encoded with it and the
+/// addresses with any other human-readable part fail to decode. It can only
+/// be set once, at start-up, before any address is encoded or decoded.
+pub fn set_address_hrp(hrp: impl Into<String>) -> Result<()> {
+    let hrp = hrp.into();
+    // The human-readable part must be lowercase, because the addresses are
+    // compared by their encoding
+    let is_valid = !hrp.chars().any(|c| c.is_ascii_uppercase())
+        && bech32::encode(
+            &hrp,
+            Vec::<bech32::u5>::new(),
+            ADDRESS_BECH32_VARIANT,
+        )
+        .is_ok();
+    if !is_valid {
+        return Err(Error::InvalidHrp(hrp));
+    }
+    let current = ADDRESS_HRP.get_or_init(|| hrp.clone());
+    if *current != hrp {
+        return Err(Error::HrpAlreadySet {
+            hrp,
+            current: current.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Get the human-readable part of the addresses of the chain that this
+/// process works with. It's the [`DEFAULT_ADDRESS_HRP`] unless it's been set
+/// with [`set_address_hrp`].
+pub fn address_hrp() -> &'static str {
+    ADDRESS_HRP
+        .get()
+        .map(String::as_str)
+        .unwrap_or(DEFAULT_ADDRESS_HRP)
+}
+
 /// An account's address
 #[derive(
     Clone,
@@ -120,24 +175,41 @@ pub enum Address {
 }
 
 impl Address {
-    /// Encode an address with Bech32m encoding
+    /// Encode an address with Bech32m encoding with the human-readable part
+    /// of the chain, see [`address_hrp`]
     pub fn encode(&self) -> String {
+        self.encode_with_hrp(address_hrp())
+    }
+
+    /// Decode an address from Bech32m encoding. The address must have the
+    /// human-readable part of the chain, see [`address_hrp`].
+    pub fn decode(string: impl AsRef<str>) -> Result<Self> {
+        Self::decode_with_hrp(string, address_hrp())
+    }
+
+    /// Encode an address with Bech32m encoding with the given human-readable
+    /// part, which must be valid.
+    pub fn encode_with_hrp(&self, hrp: &str) -> String {
         let bytes = self.to_fixed_len_string();
-        bech32::encode(ADDRESS_HRP, bytes.to_base32(), ADDRESS_BECH32_VARIANT)
+        bech32::encode(hrp, bytes.to_base32(), ADDRESS_BECH32_VARIANT)
             .unwrap_or_else(|_| {
                 panic!(
                     "The human-readable part {} should never cause a failure",
-                    ADDRESS_HRP
+                    hrp
                 )
             })
     }
 
-    /// Decode an address from Bech32m encoding
-    pub fn decode(string: impl AsRef<str>) -> Result<Self> {
+    /// Decode an address from Bech32m encoding with the given human-readable
+    /// part
+    pub fn decode_with_hrp(string: impl AsRef<str>, hrp: &str) -> Result<Self> {
         let (prefix, hash_base32, variant) =
             bech32::decode(string.as_ref()).map_err(Error::DecodeBech32)?;
-        if prefix != ADDRESS_HRP {
-            return Err(Error::UnexpectedBech32Prefix(prefix));
+        if prefix != hrp {
+            return Err(Error::UnexpectedBech32Prefix {
+                prefix,
+                expected: hrp.to_owned(),
+            });
         }
         match variant {
             ADDRESS_BECH32_VARIANT => {}
@@ -467,37 +539,37 @@ impl Display for InternalAddress {
 
 /// Temporary helper for testing
 pub fn xan() -> Address {
-    Address::decode("atest1v4ehgw36x3prswzxggunzv6pxqmnvdj9xvcyzvpsggeyvs3cg9qnywf589qnwvfsg5erg3fkl09rg5").expect("The token address decoding shouldn't fail")
+    Address::decode_with_hrp("atest1v4ehgw36x3prswzxggunzv6pxqmnvdj9xvcyzvpsggeyvs3cg9qnywf589qnwvfsg5erg3fkl09rg5", DEFAULT_ADDRESS_HRP).expect("The token address decoding shouldn't fail")
 }
 
 /// Temporary helper for testing
 pub fn btc() -> Address {
-    Address::decode("atest1v4ehgw36xdzryve5gsc52veeg5cnsv2yx5eygvp38qcrvd29xy6rys6p8yc5xvp4xfpy2v694wgwcp").expect("The token address decoding shouldn't fail")
+    Address::decode_with_hrp("atest1v4ehgw36xdzryve5gsc52veeg5cnsv2yx5eygvp38qcrvd29xy6rys6p8yc5xvp4xfpy2v694wgwcp", DEFAULT_ADDRESS_HRP).expect("The token address decoding shouldn't fail")
 }
 
 /// Temporary helper for testing
 pub fn eth() -> Address {
-    Address::decode("atest1v4ehgw36xqmr2d3nx3ryvd2xxgmrq33j8qcns33sxezrgv6zxdzrydjrxveygd2yxumrsdpsf9jc2p").expect("The token address decoding shouldn't fail")
+    Address::decode_with_hrp("atest1v4ehgw36xqmr2d3nx3ryvd2xxgmrq33j8qcns33sxezrgv6zxdzrydjrxveygd2yxumrsdpsf9jc2p", DEFAULT_ADDRESS_HRP).expect("The token address decoding shouldn't fail")
 }
 
 /// Temporary helper for testing
 pub fn dot() -> Address {
-    Address::decode("atest1v4ehgw36gg6nvs2zgfpyxsfjgc65yv6pxy6nwwfsxgungdzrggeyzv35gveyxsjyxymyz335hur2jn").expect("The token address decoding shouldn't fail")
+    Address::decode_with_hrp("atest1v4ehgw36gg6nvs2zgfpyxsfjgc65yv6pxy6nwwfsxgungdzrggeyzv35gveyxsjyxymyz335hur2jn", DEFAULT_ADDRESS_HRP).expect("The token address decoding shouldn't fail")
 }
 
 /// Temporary helper for testing
 pub fn schnitzel() -> Address {
-    Address::decode("atest1v4ehgw36xue5xvf5xvuyzvpjx5un2v3k8qeyvd3cxdqns32p89rrxd6xx9zngvpegccnzs699rdnnt").expect("The token address decoding shouldn't fail")
+    Address::decode_with_hrp("atest1v4ehgw36xue5xvf5xvuyzvpjx5un2v3k8qeyvd3cxdqns32p89rrxd6xx9zngvpegccnzs699rdnnt", DEFAULT_ADDRESS_HRP).expect("The token address decoding shouldn't fail")
 }
 
 /// Temporary helper for testing
 pub fn apfel() -> Address {
-    Address::decode("atest1v4ehgw36gfryydj9g3p5zv3kg9znyd358ycnzsfcggc5gvecgc6ygs2rxv6ry3zpg4zrwdfeumqcz9").expect("The token address decoding shouldn't fail")
+    Address::decode_with_hrp("atest1v4ehgw36gfryydj9g3p5zv3kg9znyd358ycnzsfcggc5gvecgc6ygs2rxv6ry3zpg4zrwdfeumqcz9", DEFAULT_ADDRESS_HRP).expect("The token address decoding shouldn't fail")
 }
 
 /// Temporary helper for testing
 pub fn kartoffel() -> Address {
-    Address::decode("atest1v4ehgw36gep5ysecxq6nyv3jg3zygv3e89qn2vp48pryxsf4xpznvve5gvmy23fs89pryvf5a6ht90").expect("The token address decoding shouldn't fail")
+    Address::decode_with_hrp("atest1v4ehgw36gep5ysecxq6nyv3jg3zygv3e89qn2vp48pryxsf4xpznvve5gvmy23fs89pryvf5a6ht90", DEFAULT_ADDRESS_HRP).expect("The token address decoding shouldn't fail")
 }
 
 /// Temporary helper for testing, a hash map of tokens addresses with their
@@ -542,6 +614,32 @@ pub mod tests {
         }
     }
 
+    /// Test that an address only decodes with the human-readable part that
+    /// it's been encoded with
+    #[test]
+    fn test_address_hrp() {
+        let address = testing::established_address_1();
+        let encoded = address.encode_with_hrp("anoma");
+        assert!(encoded.starts_with("anoma1"));
+
+        let decoded =
+            Address::decode_with_hrp(&encoded, "anoma").expect("Test failed");
+        assert_eq!(decoded, address);
+        assert!(matches!(
+            Address::decode_with_hrp(&encoded, DEFAULT_ADDRESS_HRP),
+            Err(Error::UnexpectedBech32Prefix { prefix, expected })
+                if prefix == "anoma" && expected == DEFAULT_ADDRESS_HRP
+        ));
+        // Only an address with the default human-readable part decodes when
+        // none has been set
+        assert!(Address::decode(&encoded).is_err());
+        assert_eq!(
+            Address::decode(address.encode_with_hrp(DEFAULT_ADDRESS_HRP))
+                .expect("Test failed"),
+            address
+        );
+    }
+
     #[test]
     fn test_address_serde_serialize() {
         let original_address = Address::decode("atest1v4ehgw36g56ngwpk8ppnzsf4xqeyvsf3xq6nxde5gseyys3nxgenvvfex5cnyd2rx9zrzwfctgx7sp").unwrap();
@@ -622,22 +720,22 @@ pub mod testing {
 
     /// A sampled established address for tests
     pub fn established_address_1() -> Address {
-        Address::decode("atest1v4ehgw36g56ngwpk8ppnzsf4xqeyvsf3xq6nxde5gseyys3nxgenvvfex5cnyd2rx9zrzwfctgx7sp").expect("The token address decoding shouldn't fail")
+        Address::decode_with_hrp("atest1v4ehgw36g56ngwpk8ppnzsf4xqeyvsf3xq6nxde5gseyys3nxgenvvfex5cnyd2rx9zrzwfctgx7sp", DEFAULT_ADDRESS_HRP).expect("The token address decoding shouldn't fail")
     }
 
     /// A sampled established address for tests
     pub fn established_address_2() -> Address {
-        Address::decode("atest1v4ehgw36xezyzv33x56rws6zxccnwwzzgycy23p3ggur2d3ex56yxdejxerrysejx3rrxdfs44s9wu").expect("The token address decoding shouldn't fail")
+        Address::decode_with_hrp("atest1v4ehgw36xezyzv33x56rws6zxccnwwzzgycy23p3ggur2d3ex56yxdejxerrysejx3rrxdfs44s9wu", DEFAULT_ADDRESS_HRP).expect("The token address decoding shouldn't fail")
     }
 
     /// A sampled established address for tests
     pub fn established_address_3() -> Address {
-        Address::decode("atest1v4ehgw36xcerywfsgsu5vsfeg3zy2v3egcenx32pggcrswzxg4zns3p5xv6rsvf4gvenqwpkdnnqsy").expect("The token address decoding shouldn't fail")
+        Address::decode_with_hrp("atest1v4ehgw36xcerywfsgsu5vsfeg3zy2v3egcenx32pggcrswzxg4zns3p5xv6rsvf4gvenqwpkdnnqsy", DEFAULT_ADDRESS_HRP).expect("The token address decoding shouldn't fail")
     }
 
     /// A sampled established address for tests
     pub fn established_address_4() -> Address {
-        Address::decode("atest1v4ehgw36gscrw333g3z5zvjzg4rrq3psxu6rqd2xxqc5gs35gerrs3pjgfprvdejxqunxs29t6p5s9").expect("The token address decoding shouldn't fail")
+        Address::decode_with_hrp("atest1v4ehgw36gscrw333g3z5zvjzg4rrq3psxu6rqd2xxqc5gs35gerrs3pjgfprvdejxqunxs29t6p5s9", DEFAULT_ADDRESS_HRP).expect("The token address decoding shouldn't fail")
     }
 
     /// Generate an arbitrary [`Address`] (established or implicit).
@@ -132,3 +132,190 @@ impl OfflineProposal {
         Self { data, signature }
     }
 }
+
+/// The fields that the content of a proposal must have
+pub const PROPOSAL_CONTENT_REQUIRED_FIELDS: [&str; 6] = [
+    "title",
+    "authors",
+    "created",
+    "abstract",
+    "motivation",
+    "details",
+];
+
+/// The bounds of the number of characters of the fields of a proposal's
+/// content. The size of the whole content is bounded separately by the
+/// `max_proposal_content_size` governance parameter.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub struct ProposalContentLimits {
+    /// Minimum number of characters of each of the required fields
+    pub min_field_size: u64,
+    /// Maximum number of characters of the title
+    pub max_title_size: u64,
+    /// Maximum number of characters of the abstract
+    pub max_abstract_size: u64,
+}
+
+impl Default for ProposalContentLimits {
+    fn default() -> Self {
+        Self {
+            min_field_size: 1,
+            max_title_size: 100,
+            max_abstract_size: 1000,
+        }
+    }
+}
+
+#[allow(missing_docs)]
+#[derive(Debug, Error, PartialEq)]
+pub enum ProposalContentError {
+    #[error("The required field \"{0}\" is missing")]
+    MissingField(&'static str),
+    #[error(
+        "The field \"{field}\" has {size} characters, but it must have at \
+         least {min}"
+    )]
+    FieldTooShort {
+        field: &'static str,
+        size: u64,
+        min: u64,
+    },
+    #[error(
+        "The field \"{field}\" has {size} characters, but it can have at most \
+         {max}"
+    )]
+    FieldTooLong {
+        field: &'static str,
+        size: u64,
+        max: u64,
+    },
+    #[error("The field \"created\" is not a valid RFC 3339 timestamp: {0}")]
+    InvalidCreated(String),
+}
+
+impl ProposalContentLimits {
+    /// Validate the fields of a proposal's content. Returns the errors of all
+    /// the invalid fields, in the order of [`PROPOSAL_CONTENT_REQUIRED_FIELDS`].
+    pub fn validate(
+        &self,
+        content: &HashMap<String, String>,
+    ) -> Vec<ProposalContentError> {
+        let mut errors = vec![];
+        for field in PROPOSAL_CONTENT_REQUIRED_FIELDS {
+            let value = match content.get(field) {
+                Some(value) => value,
+                None => {
+                    errors.push(ProposalContentError::MissingField(field));
+                    continue;
+                }
+            };
+            let size = value.trim().chars().count() as u64;
+            let max = match field {
+                "title" => Some(self.max_title_size),
+                "abstract" => Some(self.max_abstract_size),
+                _ => None,
+            };
+            if size < self.min_field_size {
+                errors.push(ProposalContentError::FieldTooShort {
+                    field,
+                    size,
+                    min: self.min_field_size,
+                });
+            } else if let Some(max) = max.filter(|max| size > *max) {
+                errors.push(ProposalContentError::FieldTooLong {
+                    field,
+                    size,
+                    max,
+                });
+            } else if field == "created" {
+                if let Err(err) = chrono::DateTime::parse_from_rfc3339(value) {
+                    errors.push(ProposalContentError::InvalidCreated(
+                        err.to_string(),
+                    ));
+                }
+            }
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid_content() -> HashMap<String, String> {
+        [
+            ("title", "TheTitle"),
+            ("authors", "test@test.com"),
+            ("discussions-to", "www.github.com/anoma/aip/1"),
+            ("created", "2022-03-10T08:54:37Z"),
+            ("license", "MIT"),
+            ("abstract", "Ut convallis eleifend orci vel venenatis."),
+            ("motivation", "Ut convallis eleifend orci vel venenatis."),
+            ("details", "Ut convallis eleifend orci vel venenatis."),
+            ("requires", "2"),
+        ]
+        .into_iter()
+        .map(|(field, value)| (field.to_owned(), value.to_owned()))
+        .collect()
+    }
+
+    #[test]
+    fn test_valid_proposal_content() {
+        let limits = ProposalContentLimits::default();
+        assert_eq!(limits.validate(&valid_content()), vec![]);
+    }
+
+    /// Test that the errors of all the invalid fields are reported
+    #[test]
+    fn test_invalid_proposal_content_fields() {
+        let limits = ProposalContentLimits::default();
+        let mut content = valid_content();
+        content.insert("title".to_owned(), "".to_owned());
+        let abstract_size = limits.max_abstract_size + 1;
+        content
+            .insert("abstract".to_owned(), "a".repeat(abstract_size as usize));
+
+        let errors = limits.validate(&content);
+        assert_eq!(
+            errors,
+            vec![
+                ProposalContentError::FieldTooShort {
+                    field: "title",
+                    size: 0,
+                    min: 1,
+                },
+                ProposalContentError::FieldTooLong {
+                    field: "abstract",
+                    size: abstract_size,
+                    max: limits.max_abstract_size,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_proposal_content_fields() {
+        let limits = ProposalContentLimits::default();
+        let mut content = valid_content();
+        content.remove("authors");
+        content.insert("created".to_owned(), "yesterday".to_owned());
+
+        let errors = limits.validate(&content);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0], ProposalContentError::MissingField("authors"));
+        assert!(matches!(errors[1], ProposalContentError::InvalidCreated(_)));
+    }
+}
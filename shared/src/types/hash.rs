@@ -4,6 +4,7 @@ use std::fmt::{self, Display};
 
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 #[cfg(not(feature = "ABCI"))]
 use tendermint::abci::transaction;
 #[cfg(feature = "ABCI")]
@@ -40,6 +41,14 @@ pub type HashResult<T> = std::result::Result<T, Error>;
 /// A hash, typically a sha-2 hash of a tx
 pub struct Hash(pub [u8; 32]);
 
+impl Hash {
+    /// Compute the sha256 hash of the given data
+    pub fn sha256(data: impl AsRef<[u8]>) -> Self {
+        let digest = Sha256::digest(data.as_ref());
+        Self(*digest.as_ref())
+    }
+}
+
 impl Display for Hash {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for byte in &self.0 {
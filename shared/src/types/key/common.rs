@@ -9,8 +9,9 @@ use rand::{CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
 
 use super::{
-    ed25519, ParsePublicKeyError, ParseSecretKeyError, ParseSignatureError,
-    RefTo, SchemeType, SigScheme as SigSchemeTrait, VerifySigError,
+    ed25519, secp256k1, ParsePublicKeyError, ParseSecretKeyError,
+    ParseSignatureError, RefTo, SchemeType, SigScheme as SigSchemeTrait,
+    VerifySigError,
 };
 
 /// Public key
@@ -31,6 +32,8 @@ use super::{
 pub enum PublicKey {
     /// Encapsulate Ed25519 public keys
     Ed25519(ed25519::PublicKey),
+    /// Encapsulate Secp256k1 public keys
+    Secp256k1(secp256k1::PublicKey),
 }
 
 impl super::PublicKey for PublicKey {
@@ -49,6 +52,13 @@ impl super::PublicKey for PublicKey {
                 )
                 .map_err(ParsePublicKeyError::InvalidEncoding)?,
             ))
+        } else if PK::TYPE == secp256k1::PublicKey::TYPE {
+            Ok(Self::Secp256k1(
+                secp256k1::PublicKey::try_from_slice(
+                    pk.try_to_vec().unwrap().as_slice(),
+                )
+                .map_err(ParsePublicKeyError::InvalidEncoding)?,
+            ))
         } else {
             Err(ParsePublicKeyError::MismatchedScheme)
         }
@@ -61,10 +71,24 @@ impl Display for PublicKey {
     }
 }
 
+/// The prefix of a public key string with the key of a specific scheme
+const ED25519_PK_PREFIX: &str = "ed25519:";
+/// The prefix of a public key string with the key of a specific scheme
+const SECP256K1_PK_PREFIX: &str = "secp256k1:";
+
 impl FromStr for PublicKey {
     type Err = ParsePublicKeyError;
 
+    /// Parse a public key from its hex string, as it's displayed. The
+    /// key may also be given as the hex string of a key of a specific scheme
+    /// prefixed with the scheme's name, e.g. `secp256k1:<hex>`.
     fn from_str(str: &str) -> Result<Self, Self::Err> {
+        if let Some(raw) = str.strip_prefix(ED25519_PK_PREFIX) {
+            return ed25519::PublicKey::from_str(raw).map(Self::Ed25519);
+        }
+        if let Some(raw) = str.strip_prefix(SECP256K1_PK_PREFIX) {
+            return secp256k1::PublicKey::from_str(raw).map(Self::Secp256k1);
+        }
         let vec = hex::decode(str).map_err(ParsePublicKeyError::InvalidHex)?;
         Self::try_from_slice(vec.as_slice())
             .map_err(ParsePublicKeyError::InvalidEncoding)
@@ -77,6 +101,8 @@ impl FromStr for PublicKey {
 pub enum SecretKey {
     /// Encapsulate Ed25519 secret keys
     Ed25519(ed25519::SecretKey),
+    /// Encapsulate Secp256k1 secret keys
+    Secp256k1(secp256k1::SecretKey),
 }
 
 impl Serialize for SecretKey {
@@ -94,6 +120,11 @@ impl Serialize for SecretKey {
                     format!("{}{}", "ED25519_SK_PREFIX", ed25519_sk);
                 Serialize::serialize(&keypair_string, serializer)
             }
+            secp256k1_sk @ SecretKey::Secp256k1(_) => {
+                let keypair_string =
+                    format!("{}{}", "SECP256K1_SK_PREFIX", secp256k1_sk);
+                Serialize::serialize(&keypair_string, serializer)
+            }
         }
     }
 }
@@ -110,6 +141,10 @@ impl<'de> Deserialize<'de> for SecretKey {
                 .map_err(D::Error::custom)?;
         if let Some(raw) = keypair_string.strip_prefix("ED25519_SK_PREFIX") {
             SecretKey::from_str(raw).map_err(D::Error::custom)
+        } else if let Some(raw) =
+            keypair_string.strip_prefix("SECP256K1_SK_PREFIX")
+        {
+            SecretKey::from_str(raw).map_err(D::Error::custom)
         } else {
             Err(D::Error::custom(
                 "Could not deserialize SecretKey do to invalid prefix",
@@ -136,6 +171,13 @@ impl super::SecretKey for SecretKey {
                 )
                 .map_err(ParseSecretKeyError::InvalidEncoding)?,
             ))
+        } else if PK::TYPE == secp256k1::SecretKey::TYPE {
+            Ok(Self::Secp256k1(
+                secp256k1::SecretKey::try_from_slice(
+                    pk.try_to_vec().unwrap().as_ref(),
+                )
+                .map_err(ParseSecretKeyError::InvalidEncoding)?,
+            ))
         } else {
             Err(ParseSecretKeyError::MismatchedScheme)
         }
@@ -146,6 +188,7 @@ impl RefTo<PublicKey> for SecretKey {
     fn ref_to(&self) -> PublicKey {
         match self {
             SecretKey::Ed25519(sk) => PublicKey::Ed25519(sk.ref_to()),
+            SecretKey::Secp256k1(sk) => PublicKey::Secp256k1(sk.ref_to()),
         }
     }
 }
@@ -183,6 +226,25 @@ impl FromStr for SecretKey {
 pub enum Signature {
     /// Encapsulate Ed25519 signatures
     Ed25519(ed25519::Signature),
+    /// Encapsulate Secp256k1 signatures
+    Secp256k1(secp256k1::Signature),
+}
+
+impl Display for Signature {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", hex::encode(&self.try_to_vec().unwrap()))
+    }
+}
+
+impl FromStr for Signature {
+    type Err = ParseSignatureError;
+
+    /// Parse a signature from its hex string, as it's displayed.
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        let vec = hex::decode(str).map_err(ParseSignatureError::InvalidHex)?;
+        Self::try_from_slice(vec.as_slice())
+            .map_err(ParseSignatureError::InvalidEncoding)
+    }
 }
 
 impl super::Signature for Signature {
@@ -201,6 +263,13 @@ impl super::Signature for Signature {
                 )
                 .map_err(ParseSignatureError::InvalidEncoding)?,
             ))
+        } else if PK::TYPE == secp256k1::Signature::TYPE {
+            Ok(Self::Secp256k1(
+                secp256k1::Signature::try_from_slice(
+                    pk.try_to_vec().unwrap().as_slice(),
+                )
+                .map_err(ParseSignatureError::InvalidEncoding)?,
+            ))
         } else {
             Err(ParseSignatureError::MismatchedScheme)
         }
@@ -248,6 +317,9 @@ impl super::SigScheme for SigScheme {
             SecretKey::Ed25519(kp) => {
                 Signature::Ed25519(ed25519::SigScheme::sign(kp, data))
             }
+            SecretKey::Secp256k1(kp) => {
+                Signature::Secp256k1(secp256k1::SigScheme::sign(kp, data))
+            }
         }
     }
 
@@ -259,7 +331,11 @@ impl super::SigScheme for SigScheme {
         match (pk, sig) {
             (PublicKey::Ed25519(pk), Signature::Ed25519(sig)) => {
                 ed25519::SigScheme::verify_signature(pk, data, sig)
-            } // _ => Err(VerifySigError::MismatchedScheme),
+            }
+            (PublicKey::Secp256k1(pk), Signature::Secp256k1(sig)) => {
+                secp256k1::SigScheme::verify_signature(pk, data, sig)
+            }
+            _ => Err(VerifySigError::MismatchedScheme),
         }
     }
 
@@ -271,7 +347,86 @@ impl super::SigScheme for SigScheme {
         match (pk, sig) {
             (PublicKey::Ed25519(pk), Signature::Ed25519(sig)) => {
                 ed25519::SigScheme::verify_signature_raw(pk, data, sig)
-            } // _ => Err(VerifySigError::MismatchedScheme),
+            }
+            (PublicKey::Secp256k1(pk), Signature::Secp256k1(sig)) => {
+                secp256k1::SigScheme::verify_signature_raw(pk, data, sig)
+            }
+            _ => Err(VerifySigError::MismatchedScheme),
         }
     }
 }
+
+/// The prefix of the data signed for an off-chain message with
+/// [`sign_message`]. With the prefix, the signed data is always longer than
+/// the 32 bytes hash signed for a tx, so the signature of a message can't be
+/// replayed as the signature of a tx.
+const MESSAGE_SIGNING_PREFIX: &[u8] = b"\x19Anoma signed off-chain message:\n";
+
+/// The data signed for an off-chain message: the [`MESSAGE_SIGNING_PREFIX`],
+/// the length of the message and the message.
+fn message_signing_data(message: &[u8]) -> Vec<u8> {
+    let mut data =
+        Vec::with_capacity(MESSAGE_SIGNING_PREFIX.len() + 8 + message.len());
+    data.extend_from_slice(MESSAGE_SIGNING_PREFIX);
+    data.extend_from_slice(&(message.len() as u64).to_be_bytes());
+    data.extend_from_slice(message);
+    data
+}
+
+/// Sign an off-chain message, e.g. a challenge given by a dApp to
+/// authenticate the owner of an account. The message is signed in a domain
+/// separated from the signing of txs.
+pub fn sign_message(
+    keypair: &SecretKey,
+    message: impl AsRef<[u8]>,
+) -> Signature {
+    SigScheme::sign(keypair, message_signing_data(message.as_ref()))
+}
+
+/// Check that the signature of an off-chain message was made with
+/// [`sign_message`] by the secret key of the given public key.
+pub fn verify_message(
+    pk: &PublicKey,
+    message: impl AsRef<[u8]>,
+    sig: &Signature,
+) -> Result<(), VerifySigError> {
+    SigScheme::verify_signature_raw(
+        pk,
+        &message_signing_data(message.as_ref()),
+        sig,
+    )
+}
+
+#[cfg(test)]
+mod test_message {
+    use super::*;
+    use crate::types::key::testing::{keypair_1, keypair_2};
+
+    /// Test that a signed message is verified only with the signer's key and
+    /// the same message, and that its signature isn't valid for the raw
+    /// message
+    #[test]
+    fn test_sign_and_verify_message() {
+        let keypair = keypair_1();
+        let pk = keypair.ref_to();
+        let message = "Sign in to the dApp with the challenge 42";
+        let sig = sign_message(&keypair, message);
+
+        verify_message(&pk, message, &sig).expect("Test failed");
+        // The signature survives the round-trip through its string
+        let parsed: Signature = sig.to_string().parse().expect("Test failed");
+        verify_message(&pk, message, &parsed).expect("Test failed");
+
+        // A tampered message fails
+        let tampered = "Sign in to the dApp with the challenge 43";
+        assert!(verify_message(&pk, tampered, &sig).is_err());
+        // Another key fails
+        let other_pk = keypair_2().ref_to();
+        assert!(verify_message(&other_pk, message, &sig).is_err());
+        // The signature is domain separated from a signature of the raw data
+        assert!(
+            SigScheme::verify_signature_raw(&pk, message.as_bytes(), &sig)
+                .is_err()
+        );
+    }
+}
@@ -30,11 +30,9 @@ impl super::PublicKey for PublicKey {
         pk: &PK,
     ) -> Result<Self, ParsePublicKeyError> {
         if PK::TYPE == super::common::PublicKey::TYPE {
-            // TODO remove once the wildcard match is used below
-            #[allow(clippy::bind_instead_of_map)]
             super::common::PublicKey::try_from_pk(pk).and_then(|x| match x {
                 super::common::PublicKey::Ed25519(epk) => Ok(epk),
-                // _ => Err(ParsePublicKeyError::MismatchedScheme),
+                _ => Err(ParsePublicKeyError::MismatchedScheme),
             })
         } else if PK::TYPE == Self::TYPE {
             Self::try_from_slice(pk.try_to_vec().unwrap().as_slice())
@@ -134,11 +132,9 @@ impl super::SecretKey for SecretKey {
         pk: &PK,
     ) -> Result<Self, ParseSecretKeyError> {
         if PK::TYPE == super::common::SecretKey::TYPE {
-            // TODO remove once the wildcard match is used below
-            #[allow(clippy::bind_instead_of_map)]
             super::common::SecretKey::try_from_sk(pk).and_then(|x| match x {
                 super::common::SecretKey::Ed25519(epk) => Ok(epk),
-                // _ => Err(ParseSecretKeyError::MismatchedScheme),
+                _ => Err(ParseSecretKeyError::MismatchedScheme),
             })
         } else if PK::TYPE == Self::TYPE {
             Self::try_from_slice(pk.try_to_vec().unwrap().as_slice())
@@ -229,11 +225,9 @@ impl super::Signature for Signature {
         pk: &PK,
     ) -> Result<Self, ParseSignatureError> {
         if PK::TYPE == super::common::Signature::TYPE {
-            // TODO remove once the wildcard match is used below
-            #[allow(clippy::bind_instead_of_map)]
             super::common::Signature::try_from_sig(pk).and_then(|x| match x {
                 super::common::Signature::Ed25519(epk) => Ok(epk),
-                // _ => Err(ParseSignatureError::MismatchedScheme),
+                _ => Err(ParseSignatureError::MismatchedScheme),
             })
         } else if PK::TYPE == Self::TYPE {
             Self::try_from_slice(pk.try_to_vec().unwrap().as_slice())
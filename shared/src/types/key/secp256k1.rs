@@ -0,0 +1,433 @@
+//! Secp256k1 keys and related functionality
+
+use std::fmt::{Debug, Display};
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::str::FromStr;
+
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+#[cfg(feature = "rand")]
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{
+    ParsePublicKeyError, ParseSecretKeyError, ParseSignatureError, RefTo,
+    SchemeType, SigScheme as SigSchemeTrait, VerifySigError,
+};
+
+/// The length of a public key in the compressed SEC1 encoding
+const PUBLIC_KEY_LENGTH: usize = 33;
+const SECRET_KEY_LENGTH: usize = 32;
+const SIGNATURE_LENGTH: usize = 64;
+
+/// Secp256k1 public key
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PublicKey(pub secp256k1::PublicKey);
+
+impl super::PublicKey for PublicKey {
+    const TYPE: SchemeType = SigScheme::TYPE;
+
+    fn try_from_pk<PK: super::PublicKey>(
+        pk: &PK,
+    ) -> Result<Self, ParsePublicKeyError> {
+        if PK::TYPE == super::common::PublicKey::TYPE {
+            super::common::PublicKey::try_from_pk(pk).and_then(|x| match x {
+                super::common::PublicKey::Secp256k1(pk) => Ok(pk),
+                _ => Err(ParsePublicKeyError::MismatchedScheme),
+            })
+        } else if PK::TYPE == Self::TYPE {
+            Self::try_from_slice(pk.try_to_vec().unwrap().as_slice())
+                .map_err(ParsePublicKeyError::InvalidEncoding)
+        } else {
+            Err(ParsePublicKeyError::MismatchedScheme)
+        }
+    }
+}
+
+impl BorshDeserialize for PublicKey {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        // Borsh doesn't support arrays longer than 32 bytes
+        if buf.len() < PUBLIC_KEY_LENGTH {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Unexpected length of a secp256k1 public key",
+            ));
+        }
+        let (bytes, rest) = buf.split_at(PUBLIC_KEY_LENGTH);
+        let mut array = [0u8; PUBLIC_KEY_LENGTH];
+        array.copy_from_slice(bytes);
+        let pk =
+            secp256k1::PublicKey::parse_compressed(&array).map_err(|e| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("{:?}", e),
+                )
+            })?;
+        *buf = rest;
+        Ok(PublicKey(pk))
+    }
+}
+
+impl BorshSerialize for PublicKey {
+    fn serialize<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.0.serialize_compressed())
+    }
+}
+
+impl BorshSchema for PublicKey {
+    fn add_definitions_recursively(
+        definitions: &mut std::collections::HashMap<
+            borsh::schema::Declaration,
+            borsh::schema::Definition,
+        >,
+    ) {
+        // Encoded as `[u8; PUBLIC_KEY_LENGTH]`
+        let elements = "u8".into();
+        let length = PUBLIC_KEY_LENGTH as u32;
+        let definition = borsh::schema::Definition::Array { elements, length };
+        definitions.insert(Self::declaration(), definition);
+    }
+
+    fn declaration() -> borsh::schema::Declaration {
+        "secp256k1::PublicKey".into()
+    }
+}
+
+#[allow(clippy::derive_hash_xor_eq)]
+impl Hash for PublicKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.serialize_compressed().hash(state);
+    }
+}
+
+impl PartialOrd for PublicKey {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PublicKey {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0
+            .serialize_compressed()
+            .cmp(&other.0.serialize_compressed())
+    }
+}
+
+impl Display for PublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", hex::encode(&self.0.serialize_compressed()))
+    }
+}
+
+impl FromStr for PublicKey {
+    type Err = ParsePublicKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let vec = hex::decode(s).map_err(ParsePublicKeyError::InvalidHex)?;
+        BorshDeserialize::try_from_slice(&vec)
+            .map_err(ParsePublicKeyError::InvalidEncoding)
+    }
+}
+
+// The secp256k1 types don't implement serde, so we use the hex string
+impl Serialize for PublicKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        Serialize::serialize(&self.to_string(), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PublicKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let hex: String = Deserialize::deserialize(deserializer)?;
+        Self::from_str(&hex).map_err(D::Error::custom)
+    }
+}
+
+/// Secp256k1 secret key
+#[derive(Debug, Clone)]
+pub struct SecretKey(pub secp256k1::SecretKey);
+
+impl super::SecretKey for SecretKey {
+    type PublicKey = PublicKey;
+
+    const TYPE: SchemeType = SigScheme::TYPE;
+
+    fn try_from_sk<PK: super::SecretKey>(
+        pk: &PK,
+    ) -> Result<Self, ParseSecretKeyError> {
+        if PK::TYPE == super::common::SecretKey::TYPE {
+            super::common::SecretKey::try_from_sk(pk).and_then(|x| match x {
+                super::common::SecretKey::Secp256k1(sk) => Ok(sk),
+                _ => Err(ParseSecretKeyError::MismatchedScheme),
+            })
+        } else if PK::TYPE == Self::TYPE {
+            Self::try_from_slice(pk.try_to_vec().unwrap().as_slice())
+                .map_err(ParseSecretKeyError::InvalidEncoding)
+        } else {
+            Err(ParseSecretKeyError::MismatchedScheme)
+        }
+    }
+}
+
+impl RefTo<PublicKey> for SecretKey {
+    fn ref_to(&self) -> PublicKey {
+        PublicKey(secp256k1::PublicKey::from_secret_key(&self.0))
+    }
+}
+
+impl BorshDeserialize for SecretKey {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        Ok(SecretKey(
+            secp256k1::SecretKey::parse(
+                &<[u8; SECRET_KEY_LENGTH] as BorshDeserialize>::deserialize(
+                    buf,
+                )?,
+            )
+            .map_err(|e| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("{:?}", e),
+                )
+            })?,
+        ))
+    }
+}
+
+impl BorshSerialize for SecretKey {
+    fn serialize<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        BorshSerialize::serialize(&self.0.serialize(), writer)
+    }
+}
+
+impl BorshSchema for SecretKey {
+    fn add_definitions_recursively(
+        definitions: &mut std::collections::HashMap<
+            borsh::schema::Declaration,
+            borsh::schema::Definition,
+        >,
+    ) {
+        // Encoded as `[u8; SECRET_KEY_LENGTH]`
+        let elements = "u8".into();
+        let length = SECRET_KEY_LENGTH as u32;
+        let definition = borsh::schema::Definition::Array { elements, length };
+        definitions.insert(Self::declaration(), definition);
+    }
+
+    fn declaration() -> borsh::schema::Declaration {
+        "secp256k1::SecretKey".into()
+    }
+}
+
+impl Display for SecretKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", hex::encode(&self.0.serialize()))
+    }
+}
+
+impl FromStr for SecretKey {
+    type Err = ParseSecretKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let vec = hex::decode(s).map_err(ParseSecretKeyError::InvalidHex)?;
+        BorshDeserialize::try_from_slice(&vec)
+            .map_err(ParseSecretKeyError::InvalidEncoding)
+    }
+}
+
+/// Secp256k1 signature
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Signature(pub secp256k1::Signature);
+
+impl super::Signature for Signature {
+    const TYPE: SchemeType = SigScheme::TYPE;
+
+    fn try_from_sig<PK: super::Signature>(
+        pk: &PK,
+    ) -> Result<Self, ParseSignatureError> {
+        if PK::TYPE == super::common::Signature::TYPE {
+            super::common::Signature::try_from_sig(pk).and_then(|x| match x {
+                super::common::Signature::Secp256k1(sig) => Ok(sig),
+                _ => Err(ParseSignatureError::MismatchedScheme),
+            })
+        } else if PK::TYPE == Self::TYPE {
+            Self::try_from_slice(pk.try_to_vec().unwrap().as_slice())
+                .map_err(ParseSignatureError::InvalidEncoding)
+        } else {
+            Err(ParseSignatureError::MismatchedScheme)
+        }
+    }
+}
+
+impl BorshDeserialize for Signature {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        // Borsh doesn't support arrays longer than 32 bytes
+        if buf.len() < SIGNATURE_LENGTH {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Unexpected length of a secp256k1 signature",
+            ));
+        }
+        let (bytes, rest) = buf.split_at(SIGNATURE_LENGTH);
+        let sig = secp256k1::Signature::parse_slice(bytes).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{:?}", e),
+            )
+        })?;
+        *buf = rest;
+        Ok(Signature(sig))
+    }
+}
+
+impl BorshSerialize for Signature {
+    fn serialize<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.0.serialize())
+    }
+}
+
+impl BorshSchema for Signature {
+    fn add_definitions_recursively(
+        definitions: &mut std::collections::HashMap<
+            borsh::schema::Declaration,
+            borsh::schema::Definition,
+        >,
+    ) {
+        // Encoded as `[u8; SIGNATURE_LENGTH]`
+        let elements = "u8".into();
+        let length = SIGNATURE_LENGTH as u32;
+        let definition = borsh::schema::Definition::Array { elements, length };
+        definitions.insert(Self::declaration(), definition);
+    }
+
+    fn declaration() -> borsh::schema::Declaration {
+        "secp256k1::Signature".into()
+    }
+}
+
+#[allow(clippy::derive_hash_xor_eq)]
+impl Hash for Signature {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.serialize().hash(state);
+    }
+}
+
+impl PartialOrd for Signature {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.0.serialize().partial_cmp(&other.0.serialize())
+    }
+}
+
+// The secp256k1 types don't implement serde, so we use the hex string
+impl Serialize for Signature {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        Serialize::serialize(&hex::encode(&self.0.serialize()), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Signature {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let hex: String = Deserialize::deserialize(deserializer)?;
+        let bytes = hex::decode(hex).map_err(D::Error::custom)?;
+        Self::try_from_slice(&bytes).map_err(D::Error::custom)
+    }
+}
+
+/// Hash the signed data into a secp256k1 message
+fn message(data: &[u8]) -> secp256k1::Message {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&hasher.finalize());
+    secp256k1::Message::parse(&hash)
+}
+
+/// An implementation of the Secp256k1 signature scheme
+#[derive(
+    Debug,
+    Clone,
+    BorshSerialize,
+    BorshDeserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    Default,
+)]
+pub struct SigScheme;
+
+impl super::SigScheme for SigScheme {
+    type PublicKey = PublicKey;
+    type SecretKey = SecretKey;
+    type Signature = Signature;
+
+    const TYPE: SchemeType = SchemeType::Secp256k1;
+
+    #[cfg(feature = "rand")]
+    fn generate<R>(csprng: &mut R) -> SecretKey
+    where
+        R: CryptoRng + RngCore,
+    {
+        // The random bytes may be outside of the curve order, in which case
+        // we try again
+        loop {
+            let mut bytes = [0u8; SECRET_KEY_LENGTH];
+            csprng.fill_bytes(&mut bytes);
+            if let Ok(sk) = secp256k1::SecretKey::parse(&bytes) {
+                return SecretKey(sk);
+            }
+        }
+    }
+
+    fn sign(keypair: &SecretKey, data: impl AsRef<[u8]>) -> Self::Signature {
+        let (sig, _recovery_id) =
+            secp256k1::sign(&message(data.as_ref()), &keypair.0)
+                .expect("Signing with a valid secret key shouldn't fail");
+        Signature(sig)
+    }
+
+    fn verify_signature<T: BorshSerialize>(
+        pk: &Self::PublicKey,
+        data: &T,
+        sig: &Self::Signature,
+    ) -> Result<(), VerifySigError> {
+        let bytes = data
+            .try_to_vec()
+            .map_err(VerifySigError::DataEncodingError)?;
+        Self::verify_signature_raw(pk, &bytes, sig)
+    }
+
+    fn verify_signature_raw(
+        pk: &Self::PublicKey,
+        data: &[u8],
+        sig: &Self::Signature,
+    ) -> Result<(), VerifySigError> {
+        if secp256k1::verify(&message(data), &sig.0, &pk.0) {
+            Ok(())
+        } else {
+            Err(VerifySigError::SigVerifyError(
+                "Invalid secp256k1 signature".into(),
+            ))
+        }
+    }
+}
@@ -12,7 +12,11 @@ use thiserror::Error;
 #[cfg(feature = "ferveo-tpke")]
 use super::transaction::WrapperTx;
 use crate::bytes::ByteBuf;
-use crate::types::address::{self, Address, InternalAddress};
+use crate::types::address::{
+    self, Address, InternalAddress, DEFAULT_ADDRESS_HRP,
+};
+#[cfg(feature = "ferveo-tpke")]
+use crate::types::token;
 use crate::types::token::BALANCE_STORAGE_KEY;
 
 #[allow(missing_docs)]
@@ -291,8 +295,9 @@ impl Key {
                     (*segments.get(2).expect("the address not found"))
                         .to_owned();
                 let _ = addr_str.remove(0);
-                let addr = Address::decode(&addr_str)
-                    .expect("cannot decode the address");
+                let addr =
+                    Address::decode_with_hrp(&addr_str, DEFAULT_ADDRESS_HRP)
+                        .expect("cannot decode the address");
                 Self::validity_predicate(&addr)
             }
             _ => Self::parse(
@@ -391,7 +396,7 @@ impl KeySeg for DbKeySeg {
             // address hashes are prefixed with `'#'`
             Some(c) if c == RESERVED_ADDRESS_PREFIX => {
                 let _ = string.remove(0);
-                Address::decode(&string)
+                Address::decode_with_hrp(&string, DEFAULT_ADDRESS_HRP)
                     .map_err(Error::ParseAddress)
                     .map(DbKeySeg::AddressSeg)
             }
@@ -402,7 +407,11 @@ impl KeySeg for DbKeySeg {
     fn raw(&self) -> String {
         match self {
             DbKeySeg::AddressSeg(addr) => {
-                format!("{}{}", RESERVED_ADDRESS_PREFIX, addr.encode())
+                format!(
+                    "{}{}",
+                    RESERVED_ADDRESS_PREFIX,
+                    addr.encode_with_hrp(DEFAULT_ADDRESS_HRP)
+                )
             }
             DbKeySeg::StringSeg(seg) => seg.to_owned(),
         }
@@ -449,14 +458,19 @@ impl KeySeg for Address {
         match seg.chars().next() {
             Some(c) if c == RESERVED_ADDRESS_PREFIX => {
                 let _ = seg.remove(0);
-                Address::decode(seg).map_err(Error::ParseAddress)
+                Address::decode_with_hrp(seg, DEFAULT_ADDRESS_HRP)
+                    .map_err(Error::ParseAddress)
             }
             _ => Err(Error::ParseAddressFromKey),
         }
     }
 
     fn raw(&self) -> String {
-        format!("{}{}", RESERVED_ADDRESS_PREFIX, self.encode())
+        format!(
+            "{}{}",
+            RESERVED_ADDRESS_PREFIX,
+            self.encode_with_hrp(DEFAULT_ADDRESS_HRP)
+        )
     }
 
     fn to_db_key(&self) -> DbKeySeg {
@@ -597,6 +611,35 @@ impl Epochs {
         }
         None
     }
+
+    /// Look-up the height of the first block of the given epoch, if it's
+    /// known.
+    pub fn get_first_block_height(&self, epoch: Epoch) -> Option<BlockHeight> {
+        let index = epoch.0.checked_sub(self.first_known_epoch.0)? as usize;
+        self.first_block_heights.get(index).copied()
+    }
+
+    /// Find the number of blocks in the given epoch, if it's known and it has
+    /// already ended.
+    pub fn get_num_of_blocks(&self, epoch: Epoch) -> Option<u64> {
+        let index = epoch.0.checked_sub(self.first_known_epoch.0)? as usize;
+        let first_height = self.first_block_heights.get(index)?;
+        let next_epoch_first_height =
+            self.first_block_heights.get(index + 1)?;
+        Some(next_epoch_first_height.0 - first_height.0)
+    }
+}
+
+#[cfg(feature = "ferveo-tpke")]
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+/// A wrapper tx in the [`TxQueue`]
+pub struct QueuedWrapper {
+    /// The wrapper tx
+    pub wrapper: WrapperTx,
+    /// The fee debited from the fee payer when the wrapper was applied. This
+    /// is the amount that may be refunded for the wrapped tx, regardless of
+    /// the fee parameters at the time the wrapped tx is applied.
+    pub charged_fee: token::Amount,
 }
 
 #[cfg(feature = "ferveo-tpke")]
@@ -606,18 +649,22 @@ pub struct TxQueue {
     /// Index of next wrapper_tx to fetch from storage
     next_wrapper: usize,
     /// The actual wrappers
-    queue: std::collections::VecDeque<WrapperTx>,
+    queue: std::collections::VecDeque<QueuedWrapper>,
 }
 
 #[cfg(feature = "ferveo-tpke")]
 impl TxQueue {
-    /// Add a new wrapper at the back of the queue
-    pub fn push(&mut self, wrapper: WrapperTx) {
-        self.queue.push_back(wrapper);
+    /// Add a new wrapper at the back of the queue with the fee charged for
+    /// it
+    pub fn push(&mut self, wrapper: WrapperTx, charged_fee: token::Amount) {
+        self.queue.push_back(QueuedWrapper {
+            wrapper,
+            charged_fee,
+        });
     }
 
     /// Remove the wrapper at the head of the queue
-    pub fn pop(&mut self) -> Option<WrapperTx> {
+    pub fn pop(&mut self) -> Option<QueuedWrapper> {
         self.queue.pop_front()
     }
 
@@ -629,7 +676,7 @@ impl TxQueue {
         if self.next_wrapper < self.queue.len() {
             self.next_wrapper += 1;
         }
-        next
+        next.map(|queued| &queued.wrapper)
     }
 
     /// Reset the iterator to the head of the queue
@@ -640,7 +687,7 @@ impl TxQueue {
     /// Get an iterator over the queue
     #[allow(dead_code)]
     pub fn iter(&self) -> impl std::iter::Iterator<Item = &WrapperTx> {
-        self.queue.iter()
+        self.queue.iter().map(|queued| &queued.wrapper)
     }
 
     /// Check if there are any txs in the queue
@@ -763,6 +810,9 @@ mod tests {
         assert_eq!(epochs.get_epoch(BlockHeight(11)), Some(Epoch(1)));
         assert_eq!(epochs.get_epoch(BlockHeight(20)), Some(Epoch(2)));
         assert_eq!(epochs.get_epoch(BlockHeight(100)), Some(Epoch(2)));
+        assert_eq!(epochs.get_num_of_blocks(Epoch(0)), Some(10));
+        assert_eq!(epochs.get_num_of_blocks(Epoch(1)), Some(10));
+        assert_eq!(epochs.get_num_of_blocks(Epoch(2)), None);
 
         // epoch 3, epoch 0 and 1 should be trimmed
         epochs.new_epoch(BlockHeight(200), max_age_num_blocks);
@@ -774,6 +824,8 @@ mod tests {
         assert_eq!(epochs.get_epoch(BlockHeight(20)), Some(Epoch(2)));
         assert_eq!(epochs.get_epoch(BlockHeight(100)), Some(Epoch(2)));
         assert_eq!(epochs.get_epoch(BlockHeight(200)), Some(Epoch(3)));
+        assert_eq!(epochs.get_num_of_blocks(Epoch(1)), None);
+        assert_eq!(epochs.get_num_of_blocks(Epoch(2)), Some(180));
 
         // increase the limit
         max_age_num_blocks = 200;
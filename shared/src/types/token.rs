@@ -1,5 +1,6 @@
 //! A basic fungible token
 
+use std::collections::BTreeSet;
 use std::convert::TryFrom;
 use std::fmt::Display;
 use std::ops::{Add, AddAssign, Sub, SubAssign};
@@ -161,13 +162,33 @@ impl SubAssign for Amount {
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
 pub enum AmountParseError {
-    #[error("Error decoding token amount: {0}")]
-    InvalidDecimal(rust_decimal::Error),
+    #[error("Error decoding token amount, the amount is empty.")]
+    Empty,
+    #[error(
+        "Error decoding token amount, invalid character '{0}'. Only digits, \
+         `_` digit separators and a `.` decimal point are allowed."
+    )]
+    InvalidCharacter(char),
+    #[error("Error decoding token amount, found more than one decimal point.")]
+    MultipleDecimalPoints,
+    #[error(
+        "Error decoding token amount, missing digits before the decimal point."
+    )]
+    MissingWholeDigits,
+    #[error(
+        "Error decoding token amount, missing digits after the decimal point."
+    )]
+    MissingFractionalDigits,
+    #[error(
+        "Error decoding token amount, a `_` digit separator must be placed \
+         between two digits."
+    )]
+    InvalidSeparator,
     #[error(
         "Error decoding token amount, too many decimal places: {0}. Maximum \
-         {MAX_DECIMAL_PLACES}"
+         {1}"
     )]
-    ScaleTooLarge(u32),
+    ScaleTooLarge(u32, u32),
     #[error("Error decoding token amount, the value is within invalid range.")]
     InvalidRange,
 }
@@ -175,25 +196,88 @@ pub enum AmountParseError {
 impl FromStr for Amount {
     type Err = AmountParseError;
 
+    /// Parse a human readable amount of whole tokens with an optional decimal
+    /// part of at most [`MAX_DECIMAL_PLACES`] digits, e.g. `"10.1"`. The digits
+    /// may be separated with `_`, e.g. `"1_000_000.1"`.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match rust_decimal::Decimal::from_str(s) {
-            Ok(decimal) => {
-                let scale = decimal.scale();
-                if scale > 6 {
-                    return Err(AmountParseError::ScaleTooLarge(scale));
-                }
-                let whole =
-                    decimal * rust_decimal::Decimal::new(SCALE as i64, 0);
-                let micro: u64 =
-                    rust_decimal::prelude::ToPrimitive::to_u64(&whole)
-                        .ok_or(AmountParseError::InvalidRange)?;
-                Ok(Self { micro })
+        parse_amount_with_decimals(s, MAX_DECIMAL_PLACES)
+            .map(|micro| Self { micro })
+    }
+}
+
+/// Parse a human readable amount of whole tokens with an optional decimal
+/// part of at most `decimals` digits, e.g. `"10.1"`, into a raw amount in the
+/// smallest denomination of a token with the given number of `decimals`. The
+/// digits may be separated with `_`, e.g. `"1_000_000.1"`.
+pub fn parse_amount_with_decimals(
+    s: &str,
+    decimals: u32,
+) -> Result<u64, AmountParseError> {
+    if s.is_empty() {
+        return Err(AmountParseError::Empty);
+    }
+    if let Some(c) = s
+        .chars()
+        .find(|c| !(c.is_ascii_digit() || *c == '_' || *c == '.'))
+    {
+        return Err(AmountParseError::InvalidCharacter(c));
+    }
+    let mut parts = s.split('.');
+    let whole = parts.next().unwrap_or_default();
+    let fraction = parts.next();
+    if parts.next().is_some() {
+        return Err(AmountParseError::MultipleDecimalPoints);
+    }
+
+    if whole.is_empty() {
+        return Err(AmountParseError::MissingWholeDigits);
+    }
+    let whole = strip_digit_separators(whole)?;
+    let fraction = match fraction {
+        None => String::new(),
+        Some("") => {
+            return Err(AmountParseError::MissingFractionalDigits);
+        }
+        Some(fraction) => {
+            let digits = strip_digit_separators(fraction)?;
+            let scale = digits.len() as u32;
+            if scale > decimals {
+                return Err(AmountParseError::ScaleTooLarge(scale, decimals));
             }
-            Err(err) => Err(AmountParseError::InvalidDecimal(err)),
+            digits
         }
+    };
+    // Pad the fractional digits to the smallest denomination
+    let raw =
+        format!("{}{:0<width$}", whole, fraction, width = decimals as usize);
+    raw.parse().map_err(|_| AmountParseError::InvalidRange)
+}
+
+/// Format a raw amount in the smallest denomination of a token with the given
+/// number of `decimals` as a human readable amount of whole tokens, e.g.
+/// `10100000` with 6 decimals as `"10.1"`.
+pub fn format_amount_with_decimals(raw: u64, decimals: u32) -> String {
+    let decimals = decimals as usize;
+    let digits = format!("{:0>width$}", raw, width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        whole.to_owned()
+    } else {
+        format!("{}.{}", whole, fraction)
     }
 }
 
+/// Remove the `_` separators from a non-empty string of digits. A separator
+/// must be placed between two digits.
+fn strip_digit_separators(digits: &str) -> Result<String, AmountParseError> {
+    if digits.starts_with('_') || digits.ends_with('_') || digits.contains("__")
+    {
+        return Err(AmountParseError::InvalidSeparator);
+    }
+    Ok(digits.replace('_', ""))
+}
+
 impl Display for Amount {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let decimal =
@@ -275,6 +359,81 @@ pub fn is_non_owner_balance_key(key: &Key) -> Option<&Address> {
     }
 }
 
+/// Key segment for a token's admin key
+pub const ADMIN_STORAGE_KEY: &str = "admin";
+/// Key segment for a token's frozen flag
+pub const FROZEN_STORAGE_KEY: &str = "frozen";
+/// Key segment for a token's minter key
+pub const MINTER_STORAGE_KEY: &str = "minter";
+/// Key segment for a token's mint cap
+pub const MINT_CAP_STORAGE_KEY: &str = "mint_cap";
+/// Key segment for the total amount minted by a token's minter
+pub const MINTED_STORAGE_KEY: &str = "minted";
+
+/// Obtain a storage key for the token's admin address, which is allowed to
+/// freeze and unfreeze the token's transfers.
+pub fn admin_key(token_addr: &Address) -> Key {
+    Key::from(token_addr.to_db_key())
+        .push(&ADMIN_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Obtain a storage key for the token's frozen flag. While the flag is set,
+/// the token's balances cannot be changed.
+pub fn frozen_key(token_addr: &Address) -> Key {
+    Key::from(token_addr.to_db_key())
+        .push(&FROZEN_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Check if the given storage key is a frozen flag key for unspecified token.
+/// If it is, returns the token.
+pub fn is_any_token_frozen_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(token),
+            DbKeySeg::StringSeg(key),
+        ] if key == FROZEN_STORAGE_KEY => Some(token),
+        _ => None,
+    }
+}
+
+/// Obtain a storage key for the token's minter address, which is the only
+/// one allowed to increase the token's supply.
+pub fn minter_key(token_addr: &Address) -> Key {
+    Key::from(token_addr.to_db_key())
+        .push(&MINTER_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Obtain a storage key for the token's mint cap, which is the maximum total
+/// amount that the minter may mint. Without it, the minting is not capped.
+pub fn mint_cap_key(token_addr: &Address) -> Key {
+    Key::from(token_addr.to_db_key())
+        .push(&MINT_CAP_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Obtain a storage key for the total amount minted by the token's minter
+/// after the genesis.
+pub fn minted_key(token_addr: &Address) -> Key {
+    Key::from(token_addr.to_db_key())
+        .push(&MINTED_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Check if the given storage key is a minted amount key for unspecified
+/// token. If it is, returns the token.
+pub fn is_any_token_minted_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(token),
+            DbKeySeg::StringSeg(key),
+        ] if key == MINTED_STORAGE_KEY => Some(token),
+        _ => None,
+    }
+}
+
 /// A simple bilateral token transfer
 #[derive(
     Debug,
@@ -300,6 +459,87 @@ pub struct Transfer {
     pub amount: Amount,
 }
 
+/// Freeze or unfreeze all the transfers of a token. Only the token's admin
+/// can submit it.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+)]
+pub struct Freeze {
+    /// Token's address
+    pub token: Address,
+    /// `true` to freeze the token, `false` to unfreeze it
+    pub frozen: bool,
+}
+
+/// Mint new tokens to the target's balance. Only the token's minter can
+/// submit it.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+)]
+pub struct Mint {
+    /// Token's address
+    pub token: Address,
+    /// Target address will receive the minted tokens
+    pub target: Address,
+    /// The amount of minted tokens
+    pub amount: Amount,
+}
+
+/// Set the transfer allow and deny lists of an account. It must be signed by
+/// the account.
+// TODO derive BorshSchema after <https://github.com/near/borsh-rs/issues/82>
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct TransferLists {
+    /// The account whose lists are set
+    pub owner: Address,
+    /// The only counterparties allowed in the account's transfers. `None`
+    /// allows any counterparty that isn't in the deny list.
+    pub allow: Option<BTreeSet<Address>>,
+    /// The counterparties not allowed in the account's transfers
+    pub deny: BTreeSet<Address>,
+}
+
+impl TransferLists {
+    /// Is the given address allowed as a counterparty of the account's
+    /// transfers?
+    pub fn is_allowed(&self, counterparty: &Address) -> bool {
+        self.allow
+            .as_ref()
+            .map(|allow| allow.contains(counterparty))
+            .unwrap_or(true)
+            && !self.deny.contains(counterparty)
+    }
+}
+
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
 pub enum TransferError {
@@ -354,5 +594,108 @@ mod tests {
                 let identity = Amount::from(float);
                 assert_eq!(amount, identity);
         }
+
+        /// A round-trip conversion to and from a string should be an identity
+        #[test]
+        fn test_token_amount_str_round_trip(raw_amount in 0..i64::MAX as u64) {
+            let amount = Amount::from(raw_amount);
+            let identity = Amount::from_str(&amount.to_string()).unwrap();
+            assert_eq!(amount, identity);
+        }
+    }
+
+    #[test]
+    fn test_token_amount_from_str() {
+        for (amount, micro) in [
+            ("0", 0),
+            ("10", 10_000_000),
+            ("10.1", 10_100_000),
+            ("0.000001", 1),
+            ("1_000_000.1", 1_000_000_100_000),
+            ("1.000_001", 1_000_001),
+        ] {
+            assert_eq!(
+                Amount::from_str(amount).unwrap(),
+                Amount::from(micro),
+                "amount {}",
+                amount
+            );
+        }
+
+        let parse = |amount| Amount::from_str(amount).unwrap_err();
+        assert!(matches!(parse(""), AmountParseError::Empty));
+        assert!(matches!(
+            parse("1.2.3"),
+            AmountParseError::MultipleDecimalPoints
+        ));
+        assert!(matches!(
+            parse("1.0000001"),
+            AmountParseError::ScaleTooLarge(7, 6)
+        ));
+        assert!(matches!(parse(".5"), AmountParseError::MissingWholeDigits));
+        assert!(matches!(
+            parse("5."),
+            AmountParseError::MissingFractionalDigits
+        ));
+        for amount in ["_1", "1_", "1__000", "1._5"] {
+            assert!(
+                matches!(parse(amount), AmountParseError::InvalidSeparator),
+                "amount {}",
+                amount
+            );
+        }
+        assert!(matches!(
+            parse("-1"),
+            AmountParseError::InvalidCharacter('-')
+        ));
+        assert!(matches!(
+            parse("1e3"),
+            AmountParseError::InvalidCharacter('e')
+        ));
+        assert!(matches!(
+            parse(" 1"),
+            AmountParseError::InvalidCharacter(' ')
+        ));
+        assert!(matches!(
+            parse("18446744073709551616"),
+            AmountParseError::InvalidRange
+        ));
+        assert!(matches!(
+            parse("18446744073709.551616"),
+            AmountParseError::InvalidRange
+        ));
+    }
+
+    /// An amount of a token with 8 decimal places converts from the human
+    /// readable to the raw representation and back, and an amount with more
+    /// decimal places than the token's precision is rejected.
+    #[test]
+    fn test_token_amount_with_decimals() {
+        let decimals = 8;
+        for (human, raw) in [
+            ("0", 0),
+            ("10", 1_000_000_000),
+            ("10.1", 1_010_000_000),
+            ("0.101", 10_100_000),
+            ("0.00000001", 1),
+        ] {
+            assert_eq!(
+                parse_amount_with_decimals(human, decimals).unwrap(),
+                raw,
+                "amount {}",
+                human
+            );
+            assert_eq!(format_amount_with_decimals(raw, decimals), human);
+        }
+
+        assert!(matches!(
+            parse_amount_with_decimals("0.000000001", decimals),
+            Err(AmountParseError::ScaleTooLarge(9, 8))
+        ));
+        assert!(matches!(
+            parse_amount_with_decimals("1.5", 0),
+            Err(AmountParseError::ScaleTooLarge(1, 0))
+        ));
+        assert_eq!(format_amount_with_decimals(15, 0), "15");
     }
 }
@@ -0,0 +1,78 @@
+//! A bundle of txs that are applied atomically, i.e. either all of them are
+//! applied in order in the same block or none of them is.
+//!
+//! A bundle is submitted as a single tx with an empty code, whose data is the
+//! encoded [`TxBundle`], so that its members cannot be split across blocks.
+
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+
+use crate::proto::{SignedTxData, Tx};
+use crate::types::hash::Hash;
+
+/// An ordered set of txs that are applied atomically
+#[derive(
+    Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+pub struct TxBundle {
+    /// The member txs, in the order in which they are applied
+    pub txs: Vec<Tx>,
+}
+
+impl TxBundle {
+    /// The bundle's id, shared by all of its member txs. It's the hash of the
+    /// encoded members.
+    pub fn id(&self) -> Hash {
+        Hash::sha256(
+            &self
+                .txs
+                .try_to_vec()
+                .expect("Encoding the bundle's txs shouldn't fail"),
+        )
+    }
+
+    /// Make the tx that carries this bundle.
+    pub fn to_tx(&self) -> Tx {
+        Tx::new(
+            vec![],
+            Some(self.try_to_vec().expect("Encoding a bundle shouldn't fail")),
+        )
+    }
+
+    /// Extract a bundle from a tx, if it carries one. The tx may be signed.
+    pub fn from_tx(tx: &Tx) -> Option<Self> {
+        if !tx.code.is_empty() {
+            return None;
+        }
+        let data = tx.data.as_ref()?;
+        let data = match SignedTxData::try_from_slice(data) {
+            Ok(SignedTxData {
+                data: Some(data), ..
+            }) => data,
+            _ => data.clone(),
+        };
+        Self::try_from_slice(&data).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::key::testing::keypair_1;
+
+    /// Test that a signed bundle tx is recognized and that a regular tx isn't
+    #[test]
+    fn test_bundle_from_tx() {
+        let bundle = TxBundle {
+            txs: vec![
+                Tx::new(b"tx_1".to_vec(), Some(vec![1])),
+                Tx::new(b"tx_2".to_vec(), Some(vec![2])),
+            ],
+        };
+        let tx = bundle.to_tx().sign(&keypair_1());
+        assert_eq!(TxBundle::from_tx(&tx), Some(bundle.clone()));
+        assert_eq!(TxBundle::from_tx(&tx).unwrap().id(), bundle.id());
+
+        let tx = Tx::new(b"tx_1".to_vec(), bundle.to_tx().data);
+        assert_eq!(TxBundle::from_tx(&tx), None);
+    }
+}
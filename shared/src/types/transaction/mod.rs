@@ -1,5 +1,7 @@
 //! Types that are used in transactions.
 
+/// atomic bundles of txs
+pub mod bundle;
 /// txs that contain decrypted payloads or assertions of
 /// non-decryptability
 pub mod decrypted;
@@ -15,7 +17,7 @@ pub mod protocol;
 /// wrapper txs with encrypted payloads
 pub mod wrapper;
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
@@ -33,6 +35,7 @@ use crate::ledger::gas::VpsGas;
 use crate::types::address::Address;
 use crate::types::hash::Hash;
 use crate::types::key::*;
+use crate::types::token;
 
 /// Get the hash of a transaction
 pub fn hash_tx(tx_bytes: &[u8]) -> Hash {
@@ -54,6 +57,19 @@ pub struct TxResult {
     pub initialized_accounts: Vec<Address>,
     /// Optional IBC event emitted by the transaction
     pub ibc_event: Option<IbcEvent>,
+    /// Whether the transaction changed the state, i.e. its write log wasn't
+    /// empty after it ran. An accepted transaction that didn't change the
+    /// state may have been a redundant submission.
+    pub state_changed: bool,
+    /// Whether the wrapper fee of the transaction has been refunded to its
+    /// fee payer, because the transaction was rejected by the VPs under the
+    /// `refund-on-vp-rejection` fee refund policy
+    pub fee_refunded: bool,
+    /// The fee debited from the fee payer when a wrapper transaction was
+    /// applied
+    pub fee_charged: token::Amount,
+    /// The part of the gas used spent on the transaction's storage access
+    pub storage_gas_used: u64,
 }
 
 impl TxResult {
@@ -77,17 +93,41 @@ pub struct VpsResult {
     pub errors: Vec<(Address, String)>,
 }
 
+impl VpsResult {
+    /// Get the verdicts of all the validity predicates triggered by the
+    /// transaction, `true` for the ones that accepted it.
+    pub fn verdicts(&self) -> BTreeMap<&Address, bool> {
+        self.accepted_vps
+            .iter()
+            .map(|addr| (addr, true))
+            .chain(self.rejected_vps.iter().map(|addr| (addr, false)))
+            .collect()
+    }
+}
+
 impl fmt::Display for TxResult {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "Transaction is {}. Gas used: {};{} VPs result: {}",
+            "Transaction is {}. Gas used: {} ({} for storage);{}{}{} VPs \
+             result: {}",
             if self.is_accepted() {
                 "valid"
             } else {
                 "invalid"
             },
             self.gas_used,
+            self.storage_gas_used,
+            if self.state_changed {
+                ""
+            } else {
+                " No state changes;"
+            },
+            if self.fee_refunded {
+                " Fee refunded;"
+            } else {
+                ""
+            },
             iterable_to_string("Changed keys", self.changed_keys.iter()),
             self.vps_result,
         )
@@ -199,6 +239,12 @@ pub struct InitValidator {
     pub validator_vp_code: Vec<u8>,
     /// The VP code for validator's staking reward account
     pub rewards_vp_code: Vec<u8>,
+    /// A portion of the validator's rewards that the validator takes before
+    /// the rest is distributed to the bonds. Given in basis points (a portion
+    /// per ten thousand tokens).
+    pub commission_rate: u64,
+    /// Optional human-readable information about the validator
+    pub metadata: pos::ValidatorMetadata,
 }
 
 /// Module that includes helper functions for classifying
@@ -428,6 +474,7 @@ pub mod tx_types {
                 Fee {
                     amount: 10.into(),
                     token: xan(),
+                    gas_price: None,
                 },
                 &keypair,
                 Epoch(0),
@@ -463,6 +510,7 @@ pub mod tx_types {
                 Fee {
                     amount: 10.into(),
                     token: xan(),
+                    gas_price: None,
                 },
                 &keypair,
                 Epoch(0),
@@ -2,8 +2,10 @@
 
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::types::address::Address;
+use crate::types::key::common;
 use crate::types::token;
 
 /// A bond is a validator's self-bond or a delegation from non-validator to a
@@ -53,3 +55,251 @@ pub struct Withdraw {
     /// from self-bonds, the validator is also the source
     pub source: Option<Address>,
 }
+
+/// A claim of unclaimed staking rewards of a bond.
+pub type ClaimRewards = Withdraw;
+
+/// An unjailing of a jailed validator.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct Unjail {
+    /// Validator address
+    pub validator: Address,
+}
+
+/// A change of a validator's commission rate.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct CommissionChange {
+    /// Validator address
+    pub validator: Address,
+    /// The new commission rate. Given in basis points (a portion of the
+    /// validator's rewards per ten thousand tokens).
+    pub new_rate: u64,
+}
+
+/// A rotation of a validator's consensus key.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct ConsensusKeyChange {
+    /// Validator address
+    pub validator: Address,
+    /// The new consensus key, used from the pipeline offset
+    pub consensus_key: common::PublicKey,
+}
+
+/// The maximum length of a validator's moniker, in characters.
+pub const MAX_MONIKER_LEN: usize = 70;
+/// The maximum length of a validator's website, in characters.
+pub const MAX_WEBSITE_LEN: usize = 140;
+/// The maximum length of a validator's security contact, in characters.
+pub const MAX_SECURITY_CONTACT_LEN: usize = 140;
+/// The maximum length of a validator's description, in characters.
+pub const MAX_DESCRIPTION_LEN: usize = 280;
+
+/// Optional human-readable information about a validator.
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct ValidatorMetadata {
+    /// A display name of the validator
+    pub moniker: Option<String>,
+    /// The validator's website
+    pub website: Option<String>,
+    /// An e-mail or other contact for security issues
+    pub security_contact: Option<String>,
+    /// A longer description of the validator
+    pub description: Option<String>,
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ValidatorMetadataError {
+    #[error(
+        "The validator's {field} is {len} characters long, the maximum is \
+         {max_len}"
+    )]
+    TooLong {
+        field: &'static str,
+        len: usize,
+        max_len: usize,
+    },
+    #[error("The validator's {0} must not be empty")]
+    Empty(&'static str),
+    #[error("The validator's {0} must not contain control characters")]
+    ControlCharacter(&'static str),
+}
+
+impl ValidatorMetadata {
+    /// Check that all the set fields are non-empty, within their length
+    /// bounds and free of control characters.
+    pub fn validate(&self) -> Result<(), ValidatorMetadataError> {
+        let fields = [
+            ("moniker", &self.moniker, MAX_MONIKER_LEN),
+            ("website", &self.website, MAX_WEBSITE_LEN),
+            (
+                "security contact",
+                &self.security_contact,
+                MAX_SECURITY_CONTACT_LEN,
+            ),
+            ("description", &self.description, MAX_DESCRIPTION_LEN),
+        ];
+        for (field, value, max_len) in fields {
+            if let Some(value) = value {
+                if value.trim().is_empty() {
+                    return Err(ValidatorMetadataError::Empty(field));
+                }
+                let len = value.chars().count();
+                if len > max_len {
+                    return Err(ValidatorMetadataError::TooLong {
+                        field,
+                        len,
+                        max_len,
+                    });
+                }
+                if value.chars().any(char::is_control) {
+                    return Err(ValidatorMetadataError::ControlCharacter(
+                        field,
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Update the fields that are set in the given `update`, keeping the
+    /// rest unchanged.
+    pub fn merge(&mut self, update: ValidatorMetadata) {
+        let ValidatorMetadata {
+            moniker,
+            website,
+            security_contact,
+            description,
+        } = update;
+        if moniker.is_some() {
+            self.moniker = moniker;
+        }
+        if website.is_some() {
+            self.website = website;
+        }
+        if security_contact.is_some() {
+            self.security_contact = security_contact;
+        }
+        if description.is_some() {
+            self.description = description;
+        }
+    }
+}
+
+/// An update of a validator's metadata. Only the fields that are set are
+/// changed.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct EditValidator {
+    /// Validator address
+    pub validator: Address,
+    /// The metadata fields to update
+    pub metadata: ValidatorMetadata,
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum EditValidatorError {
+    #[error("The given address {0} is not a validator address")]
+    NotAValidator(Address),
+    #[error("Invalid validator metadata: {0}")]
+    InvalidMetadata(ValidatorMetadataError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validator_metadata_validation() {
+        let mut metadata = ValidatorMetadata {
+            moniker: Some("validator".to_owned()),
+            ..Default::default()
+        };
+        assert!(metadata.validate().is_ok());
+
+        metadata.merge(ValidatorMetadata {
+            website: Some("https://example.com".to_owned()),
+            ..Default::default()
+        });
+        assert_eq!(metadata.moniker.as_deref(), Some("validator"));
+        assert!(metadata.validate().is_ok());
+
+        metadata.moniker = Some("x".repeat(MAX_MONIKER_LEN + 1));
+        assert_eq!(
+            metadata.validate(),
+            Err(ValidatorMetadataError::TooLong {
+                field: "moniker",
+                len: MAX_MONIKER_LEN + 1,
+                max_len: MAX_MONIKER_LEN,
+            })
+        );
+
+        metadata.moniker = Some(" ".to_owned());
+        assert_eq!(
+            metadata.validate(),
+            Err(ValidatorMetadataError::Empty("moniker"))
+        );
+
+        metadata.moniker = Some("new\nline".to_owned());
+        assert_eq!(
+            metadata.validate(),
+            Err(ValidatorMetadataError::ControlCharacter("moniker"))
+        );
+    }
+}
@@ -42,6 +42,15 @@ pub mod wrapper_tx {
              differs from that in the WrapperTx"
         )]
         InvalidKeyPair,
+        #[error(
+            "The fee amount {amount} doesn't match the gas price {gas_price} \
+             times the gas limit {gas_limit}"
+        )]
+        FeeMismatch {
+            amount: Amount,
+            gas_price: Amount,
+            gas_limit: u64,
+        },
     }
 
     /// A fee is an amount of a specified token
@@ -60,6 +69,57 @@ pub mod wrapper_tx {
         pub amount: Amount,
         /// address of the token
         pub token: Address,
+        /// The price of a unit of gas, if the amount of the fee has been
+        /// derived from it as the gas price times the gas limit
+        pub gas_price: Option<Amount>,
+    }
+
+    impl Fee {
+        /// Create a fee paying the given price for every unit of the gas
+        /// limit
+        pub fn from_gas_price(
+            gas_price: Amount,
+            token: Address,
+            gas_limit: &GasLimit,
+        ) -> Self {
+            Self {
+                amount: gas_price_amount(gas_price, gas_limit),
+                token,
+                gas_price: Some(gas_price),
+            }
+        }
+
+        /// Check that the amount of a fee with a gas price is the gas price
+        /// times the gas limit. A fee without a gas price is always
+        /// consistent.
+        pub fn check_gas_price(
+            &self,
+            gas_limit: &GasLimit,
+        ) -> Result<(), WrapperTxErr> {
+            match self.gas_price {
+                Some(gas_price)
+                    if gas_price_amount(gas_price, gas_limit)
+                        != self.amount =>
+                {
+                    Err(WrapperTxErr::FeeMismatch {
+                        amount: self.amount,
+                        gas_price,
+                        gas_limit: gas_limit.into(),
+                    })
+                }
+                _ => Ok(()),
+            }
+        }
+    }
+
+    /// The amount paid for the gas limit at the given gas price, saturating
+    /// on overflow
+    fn gas_price_amount(gas_price: Amount, gas_limit: &GasLimit) -> Amount {
+        Amount::from(
+            u64::from(gas_price)
+                .checked_mul(u64::from(gas_limit))
+                .unwrap_or(u64::MAX),
+        )
     }
 
     /// Gas limits must be multiples of GAS_LIMIT_RESOLUTION
@@ -331,6 +391,36 @@ pub mod wrapper_tx {
             let refund = limit.refund_amount(GAS_LIMIT_RESOLUTION + 1);
             assert_eq!(refund, Amount::from(0u64));
         }
+
+        /// Test that a fee from a gas price pays the price for the whole gas
+        /// limit and that a fee whose amount differs from it is inconsistent
+        #[test]
+        fn test_fee_from_gas_price() {
+            let limit = GasLimit { multiplier: 2 };
+            let fee = Fee::from_gas_price(
+                Amount::from(3),
+                crate::types::address::xan(),
+                &limit,
+            );
+            assert_eq!(fee.amount, Amount::from(6 * GAS_LIMIT_RESOLUTION));
+            assert!(fee.check_gas_price(&limit).is_ok());
+
+            let underpaid = Fee {
+                amount: Amount::from(GAS_LIMIT_RESOLUTION),
+                ..fee
+            };
+            assert!(matches!(
+                underpaid.check_gas_price(&limit),
+                Err(WrapperTxErr::FeeMismatch { .. })
+            ));
+
+            // A fee without a gas price is not checked
+            let fee = Fee {
+                gas_price: None,
+                ..underpaid
+            };
+            assert!(fee.check_gas_price(&limit).is_ok());
+        }
     }
 
     #[cfg(test)]
@@ -361,6 +451,7 @@ pub mod wrapper_tx {
                 Fee {
                     amount: 10.into(),
                     token: xan(),
+                    gas_price: None,
                 },
                 &keypair,
                 Epoch(0),
@@ -387,6 +478,7 @@ pub mod wrapper_tx {
                 Fee {
                     amount: 10.into(),
                     token: xan(),
+                    gas_price: None,
                 },
                 &gen_keypair(),
                 Epoch(0),
@@ -419,6 +511,7 @@ pub mod wrapper_tx {
                 Fee {
                     amount: 10.into(),
                     token: xan(),
+                    gas_price: None,
                 },
                 &keypair,
                 Epoch(0),
@@ -1,8 +1,10 @@
 //! Virtual machine's host environment exposes functions that may be called from
 //! within a virtual machine.
+use std::cell::Cell;
 use std::collections::BTreeSet;
 use std::convert::TryInto;
 use std::num::TryFromIntError;
+use std::time::Instant;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use thiserror::Error;
@@ -15,13 +17,15 @@ use super::WasmCacheAccess;
 use crate::ledger::gas::{self, BlockGasMeter, VpGasMeter};
 use crate::ledger::storage::write_log::{self, WriteLog};
 use crate::ledger::storage::{self, Storage, StorageHasher};
-use crate::ledger::vp_env;
+use crate::ledger::{parameters, vp_env};
 use crate::proto::Tx;
 use crate::types::address::{self, Address};
+use crate::types::hash::Hash;
 use crate::types::ibc::IbcEvent;
 use crate::types::internal::HostEnvResult;
 use crate::types::key::*;
 use crate::types::storage::Key;
+use crate::types::time::Rfc3339String;
 use crate::vm::memory::VmMemory;
 use crate::vm::prefix_iter::{PrefixIteratorId, PrefixIterators};
 use crate::vm::types::KeyVal;
@@ -32,6 +36,34 @@ use crate::vm::{
 const VERIFY_TX_SIG_GAS_COST: u64 = 1000;
 const WASM_VALIDATION_GAS_PER_BYTE: u64 = 1;
 
+thread_local! {
+    /// The wall-clock deadline of the wasm invocation running on this
+    /// thread, if it has a timeout. A wasm invocation runs entirely on the
+    /// thread that started it.
+    static WASM_DEADLINE: Cell<Option<Instant>> = Cell::new(None);
+}
+
+/// Run a wasm invocation with the given wall-clock deadline. The invocation is
+/// interrupted with an error from the next gas charge made by the wasm after
+/// the deadline.
+pub fn with_wasm_deadline<T>(
+    deadline: Option<Instant>,
+    run: impl FnOnce() -> T,
+) -> T {
+    let outer_deadline = WASM_DEADLINE.with(|cell| cell.replace(deadline));
+    let result = run();
+    WASM_DEADLINE.with(|cell| cell.set(outer_deadline));
+    result
+}
+
+/// Check if the wasm invocation running on this thread is past its deadline
+fn is_past_wasm_deadline() -> bool {
+    WASM_DEADLINE
+        .with(Cell::get)
+        .map(|deadline| Instant::now() >= deadline)
+        .unwrap_or_default()
+}
+
 /// These runtime errors will abort tx WASM execution immediately
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
@@ -49,6 +81,13 @@ pub enum TxRuntimeError {
          WASM {0}"
     )]
     InitAccountInvalidVpWasm(WasmValidationError),
+    #[error(
+        "Trying to use a validity predicate with hash {0} that is not in the \
+         VP whitelist"
+    )]
+    VpNotWhitelisted(Hash),
+    #[error("Protocol parameters read error: {0}")]
+    ParametersReadError(parameters::ReadError),
     #[error("Storage modification error: {0}")]
     StorageModificationError(write_log::Error),
     #[error("Storage error: {0}")]
@@ -63,6 +102,8 @@ pub enum TxRuntimeError {
     NumConversionError(TryFromIntError),
     #[error("Memory error: {0}")]
     MemoryError(Box<dyn std::error::Error + Sync + Send + 'static>),
+    #[error("The wasm execution timed out")]
+    Timeout,
 }
 
 type TxResult<T> = std::result::Result<T, TxRuntimeError>;
@@ -449,7 +490,12 @@ where
         used_gas
             .try_into()
             .map_err(TxRuntimeError::NumConversionError)?,
-    )
+    )?;
+    if is_past_wasm_deadline() {
+        tracing::info!("Stopping transaction execution because of timeout");
+        return Err(TxRuntimeError::Timeout);
+    }
+    Ok(())
 }
 
 /// Add a gas cost incured in a transaction
@@ -475,6 +521,56 @@ where
     result
 }
 
+/// Add the storage gas cost of the given number of bytes read from the
+/// storage in a transaction
+pub fn tx_add_storage_read_gas<MEM, DB, H, CA>(
+    env: &TxEnv<MEM, DB, H, CA>,
+    bytes_len: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    let result = gas_meter
+        .add_storage_read(bytes_len)
+        .map_err(TxRuntimeError::OutOfGas);
+    if let Err(err) = &result {
+        tracing::info!(
+            "Stopping transaction execution because of gas error: {}",
+            err
+        );
+    }
+    result
+}
+
+/// Add the storage gas cost of the given number of bytes written to the
+/// storage in a transaction
+pub fn tx_add_storage_write_gas<MEM, DB, H, CA>(
+    env: &TxEnv<MEM, DB, H, CA>,
+    bytes_len: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    let result = gas_meter
+        .add_storage_write(bytes_len)
+        .map_err(TxRuntimeError::OutOfGas);
+    if let Err(err) = &result {
+        tracing::info!(
+            "Stopping transaction execution because of gas error: {}",
+            err
+        );
+    }
+    result
+}
+
 /// Called from VP wasm to request to use the given gas amount
 pub fn vp_charge_gas<MEM, DB, H, EVAL, CA>(
     env: &VpEnv<MEM, DB, H, EVAL, CA>,
@@ -493,7 +589,12 @@ where
         used_gas
             .try_into()
             .map_err(vp_env::RuntimeError::NumConversionError)?,
-    )
+    )?;
+    if is_past_wasm_deadline() {
+        tracing::info!("Stopping VP execution because of timeout");
+        return Err(vp_env::RuntimeError::Timeout);
+    }
+    Ok(())
 }
 
 /// Storage `has_key` function exposed to the wasm VM Tx environment. It will
@@ -541,7 +642,7 @@ where
             // when not found in write log, try to check the storage
             let storage = unsafe { env.ctx.storage.get() };
             let (present, gas) = storage
-                .has_key(&key)
+                .has_key_with_staged(&key)
                 .map_err(TxRuntimeError::StorageError)?;
             tx_add_gas(env, gas)?;
             HostEnvResult::from(present).to_i64()
@@ -578,7 +679,7 @@ where
     // try to read from the write log first
     let write_log = unsafe { env.ctx.write_log.get() };
     let (log_val, gas) = write_log.read(&key);
-    tx_add_gas(env, gas)?;
+    tx_add_storage_read_gas(env, gas)?;
     Ok(match log_val {
         Some(&write_log::StorageModification::Write { ref value }) => {
             let len: i64 = value
@@ -617,9 +718,10 @@ where
         None => {
             // when not found in write log, try to read from the storage
             let storage = unsafe { env.ctx.storage.get() };
-            let (value, gas) =
-                storage.read(&key).map_err(TxRuntimeError::StorageError)?;
-            tx_add_gas(env, gas)?;
+            let (value, gas) = storage
+                .read_with_staged(&key)
+                .map_err(TxRuntimeError::StorageError)?;
+            tx_add_storage_read_gas(env, gas)?;
             match value {
                 Some(value) => {
                     let len: i64 = value
@@ -697,7 +799,7 @@ where
 
 /// Storage prefix iterator next function exposed to the wasm VM Tx environment.
 /// It will try to read from the write log first and if no entry found then from
-/// the storage.
+/// the block's changes staged in the storage and lastly from the iterator.
 ///
 /// Returns `-1` when the key is not present, or the length of the data when
 /// the key is present (the length may be `0`).
@@ -713,14 +815,14 @@ where
 {
     tracing::debug!("tx_iter_next iter_id {}", iter_id,);
 
+    let storage = unsafe { env.ctx.storage.get() };
     let write_log = unsafe { env.ctx.write_log.get() };
     let iterators = unsafe { env.ctx.iterators.get() };
     let iter_id = PrefixIteratorId::new(iter_id);
     while let Some((key, val, iter_gas)) = iterators.next(iter_id) {
-        let (log_val, log_gas) = write_log.read(
-            &Key::parse(key.clone())
-                .map_err(TxRuntimeError::StorageDataError)?,
-        );
+        let parsed_key = Key::parse(key.clone())
+            .map_err(TxRuntimeError::StorageDataError)?;
+        let (log_val, log_gas) = write_log.read(&parsed_key);
         tx_add_gas(env, iter_gas + log_gas)?;
         match log_val {
             Some(&write_log::StorageModification::Write { ref value }) => {
@@ -762,6 +864,16 @@ where
                 return Ok(len);
             }
             None => {
+                let (staged_val, staged_gas) = storage
+                    .read_staged(&parsed_key)
+                    .map_err(TxRuntimeError::StorageError)?;
+                tx_add_gas(env, staged_gas)?;
+                let val = match staged_val {
+                    Some(Some(value)) => value,
+                    // check the next because the key has already deleted
+                    Some(None) => continue,
+                    None => val,
+                };
                 let key_val = KeyVal { key, val }
                     .try_to_vec()
                     .map_err(TxRuntimeError::EncodingError)?;
@@ -814,8 +926,7 @@ where
     let (gas, _size_diff) = write_log
         .write(&key, value)
         .map_err(TxRuntimeError::StorageModificationError)?;
-    tx_add_gas(env, gas)
-    // TODO: charge the size diff
+    tx_add_storage_write_gas(env, gas)
 }
 
 /// Temporary storage write function exposed to the wasm VM Tx environment. The
@@ -883,7 +994,7 @@ where
         // delete log of the VP
         if vp.is_none() {
             let (is_present, gas) = storage
-                .has_key(&vp_key)
+                .has_key_with_staged(&vp_key)
                 .map_err(TxRuntimeError::StorageError)?;
             tx_add_gas(env, gas)?;
             if !is_present {
@@ -931,8 +1042,8 @@ where
     let (gas, _size_diff) = write_log
         .delete(&key)
         .map_err(TxRuntimeError::StorageModificationError)?;
-    tx_add_gas(env, gas)
-    // TODO: charge the size diff
+    // The bytes deleted from the storage are refunded once the tx has run
+    tx_add_storage_write_gas(env, gas)
 }
 
 /// Emitting an IBC event function exposed to the wasm VM Tx environment.
@@ -1287,10 +1398,11 @@ where
     let iter_id = PrefixIteratorId::new(iter_id);
     if let Some(iter) = iterators.get_mut(iter_id) {
         let gas_meter = unsafe { env.ctx.gas_meter.get() };
+        let storage = unsafe { env.ctx.storage.get() };
         let write_log = unsafe { env.ctx.write_log.get() };
-        if let Some((key, val)) =
-            vp_env::iter_post_next::<DB>(gas_meter, write_log, iter)?
-        {
+        if let Some((key, val)) = vp_env::iter_post_next::<DB, H>(
+            gas_meter, storage, write_log, iter,
+        )? {
             let key_val = KeyVal { key, val }
                 .try_to_vec()
                 .map_err(vp_env::RuntimeError::EncodingError)?;
@@ -1326,7 +1438,8 @@ where
 
     tracing::debug!("tx_insert_verifier {}, addr_ptr {}", addr, addr_ptr,);
 
-    let addr = Address::decode(&addr).map_err(TxRuntimeError::AddressError)?;
+    let addr = Address::decode_with_hrp(&addr, address::DEFAULT_ADDRESS_HRP)
+        .map_err(TxRuntimeError::AddressError)?;
 
     let verifiers = unsafe { env.ctx.verifiers.get() };
     verifiers.insert(addr);
@@ -1353,7 +1466,8 @@ where
         .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
     tx_add_gas(env, gas)?;
 
-    let addr = Address::decode(addr).map_err(TxRuntimeError::AddressError)?;
+    let addr = Address::decode_with_hrp(addr, address::DEFAULT_ADDRESS_HRP)
+        .map_err(TxRuntimeError::AddressError)?;
     tracing::debug!("tx_update_validity_predicate for addr {}", addr);
 
     let key = Key::validity_predicate(&addr);
@@ -1365,13 +1479,13 @@ where
 
     tx_add_gas(env, code.len() as u64 * WASM_VALIDATION_GAS_PER_BYTE)?;
     validate_untrusted_wasm(&code).map_err(TxRuntimeError::UpdateVpInvalid)?;
+    check_vp_whitelisted(env, &code)?;
 
     let write_log = unsafe { env.ctx.write_log.get() };
     let (gas, _size_diff) = write_log
         .write(&key, code)
         .map_err(TxRuntimeError::StorageModificationError)?;
-    tx_add_gas(env, gas)
-    // TODO: charge the size diff
+    tx_add_storage_write_gas(env, gas)
 }
 
 /// Initialize a new account established address.
@@ -1396,6 +1510,7 @@ where
     tx_add_gas(env, code.len() as u64 * WASM_VALIDATION_GAS_PER_BYTE)?;
     validate_untrusted_wasm(&code)
         .map_err(TxRuntimeError::InitAccountInvalidVpWasm)?;
+    check_vp_whitelisted(env, &code)?;
     #[cfg(feature = "wasm-runtime")]
     {
         let vp_wasm_cache = unsafe { env.ctx.vp_wasm_cache.get() };
@@ -1409,7 +1524,7 @@ where
     let (addr, gas) = write_log.init_account(&storage.address_gen, code);
     let addr_bytes =
         addr.try_to_vec().map_err(TxRuntimeError::EncodingError)?;
-    tx_add_gas(env, gas)?;
+    tx_add_storage_write_gas(env, gas)?;
     let gas = env
         .memory
         .write_bytes(result_ptr, addr_bytes)
@@ -1417,6 +1532,28 @@ where
     tx_add_gas(env, gas)
 }
 
+/// Check that the given VP code is permitted by the VP whitelist parameter.
+fn check_vp_whitelisted<MEM, DB, H, CA>(
+    env: &TxEnv<MEM, DB, H, CA>,
+    code: &[u8],
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let storage = unsafe { env.ctx.storage.get() };
+    let (vp_whitelist, gas) = parameters::read_vp_whitelist(storage)
+        .map_err(TxRuntimeError::ParametersReadError)?;
+    tx_add_gas(env, gas)?;
+    if parameters::is_vp_whitelisted(&vp_whitelist, code) {
+        Ok(())
+    } else {
+        Err(TxRuntimeError::VpNotWhitelisted(Hash::sha256(code)))
+    }
+}
+
 /// Getting the chain ID function exposed to the wasm VM Tx environment.
 pub fn tx_get_chain_id<MEM, DB, H, CA>(
     env: &TxEnv<MEM, DB, H, CA>,
@@ -1478,6 +1615,31 @@ where
     tx_add_gas(env, gas)
 }
 
+/// Getting the epoch seed function exposed to the wasm VM Tx environment. The
+/// seed is that of the epoch of the block to which the current transaction is
+/// being applied.
+pub fn tx_get_epoch_seed<MEM, DB, H, CA>(
+    env: &TxEnv<MEM, DB, H, CA>,
+    result_ptr: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let storage = unsafe { env.ctx.storage.get() };
+    let write_log = unsafe { env.ctx.write_log.get() };
+    let (seed, gas) = parameters::read_block_epoch_seed(storage, write_log)
+        .map_err(TxRuntimeError::ParametersReadError)?;
+    tx_add_gas(env, gas)?;
+    let gas = env
+        .memory
+        .write_bytes(result_ptr, seed.0)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_add_gas(env, gas)
+}
+
 /// Getting the block epoch function exposed to the wasm VM Tx
 /// environment. The epoch is that of the block to which the current
 /// transaction is being applied.
@@ -1573,6 +1735,40 @@ where
     })
 }
 
+/// Getting the block time function exposed to the wasm VM VP environment.
+/// The time is that of the block header to which the current transaction is
+/// being applied. Returns the length of the RFC 3339 time string placed in the
+/// result buffer or `-1` when there's no block header, e.g. in a dry run.
+pub fn vp_get_block_time<MEM, DB, H, EVAL, CA>(
+    env: &VpEnv<MEM, DB, H, EVAL, CA>,
+) -> vp_env::Result<i64>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    let storage = unsafe { env.ctx.storage.get() };
+    Ok(match vp_env::get_block_time(gas_meter, storage)? {
+        Some(time) => {
+            let time = Rfc3339String::from(time)
+                .0
+                .try_to_vec()
+                .map_err(vp_env::RuntimeError::EncodingError)?;
+            let len: i64 = time
+                .len()
+                .try_into()
+                .map_err(vp_env::RuntimeError::NumConversionError)?;
+            let result_buffer = unsafe { env.ctx.result_buffer.get() };
+            result_buffer.replace(time);
+            len
+        }
+        None => HostEnvResult::Fail.to_i64(),
+    })
+}
+
 /// Getting the block hash function exposed to the wasm VM VP environment. The
 /// hash is that of the block to which the current transaction is being applied.
 pub fn vp_get_block_hash<MEM, DB, H, EVAL, CA>(
@@ -1618,6 +1814,31 @@ where
     vp_env::add_gas(gas_meter, gas)
 }
 
+/// Getting the epoch seed function exposed to the wasm VM VP environment. The
+/// seed is that of the epoch of the block to which the current transaction is
+/// being applied.
+pub fn vp_get_epoch_seed<MEM, DB, H, EVAL, CA>(
+    env: &VpEnv<MEM, DB, H, EVAL, CA>,
+    result_ptr: u64,
+) -> vp_env::Result<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    let storage = unsafe { env.ctx.storage.get() };
+    let write_log = unsafe { env.ctx.write_log.get() };
+    let seed = vp_env::get_epoch_seed(gas_meter, storage, write_log)?;
+    let gas = env
+        .memory
+        .write_bytes(result_ptr, seed.0)
+        .map_err(|e| vp_env::RuntimeError::MemoryError(Box::new(e)))?;
+    vp_env::add_gas(gas_meter, gas)
+}
+
 /// Getting the block epoch function exposed to the wasm VM VP
 /// environment. The epoch is that of the block to which the current
 /// transaction is being applied.
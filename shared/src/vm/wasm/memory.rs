@@ -78,7 +78,9 @@ pub struct TxCallInput {
     pub tx_data_len: u64,
 }
 
-/// Write transaction inputs into wasm memory
+/// Write transaction inputs into wasm memory. The data is copied straight from
+/// the given buffer, without any intermediate copies, so that a tx with large
+/// data only has one more copy of it in memory while it's being applied.
 pub fn write_tx_inputs(
     memory: &wasmer::Memory,
     tx_data_bytes: impl AsRef<[u8]>,
@@ -115,7 +117,10 @@ pub struct VpCallInput {
     pub verifiers_len: u64,
 }
 
-/// Write validity predicate inputs into wasm memory
+/// Write validity predicate inputs into wasm memory. The inputs are written
+/// one after another and the tx data is copied straight from the given slice,
+/// because the VPs of a tx run in parallel and each of them would otherwise
+/// hold another copy of the (possibly large) data.
 pub fn write_vp_inputs(
     memory: &wasmer::Memory,
     VpInput {
@@ -142,14 +147,12 @@ pub fn write_vp_inputs(
     let verifiers_ptr = keys_changed_ptr + keys_changed_len;
     let verifiers_len = verifiers_bytes.len() as _;
 
-    let bytes = [
-        &addr_bytes[..],
-        data,
-        &keys_changed_bytes[..],
-        &verifiers_bytes[..],
-    ]
-    .concat();
-    write_memory_bytes(memory, addr_ptr, bytes)?;
+    // Grow the memory for all the inputs at once
+    check_bounds(memory, addr_ptr, (verifiers_ptr + verifiers_len) as _)?;
+    write_memory_bytes(memory, addr_ptr, addr_bytes)?;
+    write_memory_bytes(memory, data_ptr, data)?;
+    write_memory_bytes(memory, keys_changed_ptr, keys_changed_bytes)?;
+    write_memory_bytes(memory, verifiers_ptr, verifiers_bytes)?;
 
     Ok(VpCallInput {
         addr_ptr,
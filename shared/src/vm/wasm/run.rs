@@ -1,7 +1,10 @@
 //! Wasm runners
 
+use std::any::Any;
 use std::collections::BTreeSet;
 use std::marker::PhantomData;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
 
 use parity_wasm::elements;
 use pwasm_utils::{self, rules};
@@ -15,9 +18,10 @@ use crate::ledger::storage::write_log::WriteLog;
 use crate::ledger::storage::{self, Storage, StorageHasher};
 use crate::proto::Tx;
 use crate::types::address::Address;
+use crate::types::hash::Hash;
 use crate::types::internal::HostEnvResult;
 use crate::types::storage::Key;
-use crate::vm::host_env::{TxEnv, VpCtx, VpEnv, VpEvaluator};
+use crate::vm::host_env::{self, TxEnv, VpCtx, VpEnv, VpEvaluator};
 use crate::vm::prefix_iter::PrefixIterators;
 use crate::vm::types::VpInput;
 use crate::vm::wasm::host_env::{tx_imports, vp_imports};
@@ -63,13 +67,18 @@ pub enum Error {
     },
     #[error("Wasm validation error: {0}")]
     ValidationError(WasmValidationError),
+    #[error("Wasm code with hash {code_hash} panicked: {message}")]
+    Panic { code_hash: Hash, message: String },
+    #[error("Wasm code with hash {code_hash} timed out after {timeout:?}")]
+    Timeout { code_hash: Hash, timeout: Duration },
 }
 
 /// Result for functions that may fail
 pub type Result<T> = std::result::Result<T, Error>;
 
 /// Execute a transaction code. Returns the set verifiers addresses requested by
-/// the transaction.
+/// the transaction. The execution is interrupted after the `timeout`, if any.
+#[allow(clippy::too_many_arguments)]
 pub fn tx<DB, H, CA>(
     storage: &Storage<DB, H>,
     write_log: &mut WriteLog,
@@ -78,6 +87,7 @@ pub fn tx<DB, H, CA>(
     tx_data: impl AsRef<[u8]>,
     vp_wasm_cache: &mut VpCache<CA>,
     tx_wasm_cache: &mut TxCache<CA>,
+    timeout: Option<Duration>,
 ) -> Result<BTreeSet<Address>>
 where
     DB: 'static + storage::DB + for<'iter> storage::DBIter<'iter>,
@@ -134,15 +144,19 @@ where
             entrypoint: TX_ENTRYPOINT,
             error,
         })?;
-    match apply_tx
-        .call(tx_data_ptr, tx_data_len)
-        .map_err(Error::RuntimeError)
-    {
+    let tx_code = tx_code.as_ref();
+    match run_with_timeout(tx_code, timeout, || {
+        catch_panic(tx_code, || {
+            apply_tx
+                .call(tx_data_ptr, tx_data_len)
+                .map_err(Error::RuntimeError)
+        })
+    }) {
         Err(Error::RuntimeError(err)) => {
             tracing::debug!("Tx WASM failed with {}", err);
             Err(Error::RuntimeError(err))
         }
-        _ => Ok(()),
+        result => result,
     }?;
 
     Ok(verifiers)
@@ -150,7 +164,8 @@ where
 
 /// Execute a validity predicate code. Returns whether the validity
 /// predicate accepted storage modifications performed by the transaction
-/// that triggered the execution.
+/// that triggered the execution. The execution, including the VPs evaluated
+/// from it, is interrupted after the `timeout`, if any.
 #[allow(clippy::too_many_arguments)]
 pub fn vp<DB, H, CA>(
     vp_code: impl AsRef<[u8]>,
@@ -162,6 +177,7 @@ pub fn vp<DB, H, CA>(
     keys_changed: &BTreeSet<Key>,
     verifiers: &BTreeSet<Address>,
     mut vp_wasm_cache: VpCache<CA>,
+    timeout: Option<Duration>,
 ) -> Result<bool>
 where
     DB: 'static + storage::DB + for<'iter> storage::DBIter<'iter>,
@@ -208,17 +224,21 @@ where
         memory::prepare_vp_memory(&store).map_err(Error::MemoryError)?;
     let imports = vp_imports(&store, initial_memory, env);
 
-    run_vp(
-        module,
-        imports,
-        input_data,
-        address,
-        keys_changed,
-        verifiers,
-    )
+    run_with_timeout(vp_code, timeout, || {
+        run_vp(
+            vp_code,
+            module,
+            imports,
+            input_data,
+            address,
+            keys_changed,
+            verifiers,
+        )
+    })
 }
 
 fn run_vp(
+    vp_code: &[u8],
     module: wasmer::Module,
     vp_imports: wasmer::ImportObject,
     input_data: &[u8],
@@ -264,22 +284,86 @@ fn run_vp(
             entrypoint: VP_ENTRYPOINT,
             error,
         })?;
-    let is_valid = validate_tx
-        .call(
-            addr_ptr,
-            addr_len,
-            data_ptr,
-            data_len,
-            keys_changed_ptr,
-            keys_changed_len,
-            verifiers_ptr,
-            verifiers_len,
-        )
-        .map_err(Error::RuntimeError)?;
+    let is_valid = catch_panic(vp_code, || {
+        validate_tx
+            .call(
+                addr_ptr,
+                addr_len,
+                data_ptr,
+                data_len,
+                keys_changed_ptr,
+                keys_changed_len,
+                verifiers_ptr,
+                verifiers_len,
+            )
+            .map_err(Error::RuntimeError)
+    })?;
     tracing::debug!("is_valid {}", is_valid);
     Ok(is_valid == 1)
 }
 
+/// Run a wasm invocation of the given code with the given wall-clock timeout,
+/// as a bound on its execution time that doesn't rely on the calibration of
+/// the gas costs. The invocation is interrupted by the first gas charge after
+/// the timeout and it then fails with [`Error::Timeout`].
+///
+/// The wasmer engine that we use doesn't provide a handle to interrupt a
+/// running instance from another thread, so the deadline is instead checked
+/// from the host environment. The injected gas metering charges gas at the
+/// start of every basic block of the wasm, so a wasm can't run for long
+/// without giving the host a chance to interrupt it.
+fn run_with_timeout<T>(
+    code: &[u8],
+    timeout: Option<Duration>,
+    run: impl FnOnce() -> Result<T>,
+) -> Result<T> {
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return run(),
+    };
+    let deadline = Instant::now() + timeout;
+    match host_env::with_wasm_deadline(Some(deadline), run) {
+        Err(Error::RuntimeError(_)) if Instant::now() >= deadline => {
+            let code_hash = Hash::sha256(code);
+            tracing::warn!(
+                "Wasm code with hash {} timed out after {:?}",
+                code_hash,
+                timeout
+            );
+            Err(Error::Timeout { code_hash, timeout })
+        }
+        result => result,
+    }
+}
+
+/// Call into a wasm module with the given code. A panic in the call (e.g. from
+/// a host environment function called by the wasm) is turned into an error, so
+/// that it doesn't unwind through the ledger. The caller is responsible for
+/// dropping any partial changes made by the call.
+fn catch_panic<T>(code: &[u8], call: impl FnOnce() -> Result<T>) -> Result<T> {
+    panic::catch_unwind(AssertUnwindSafe(call)).unwrap_or_else(|payload| {
+        let code_hash = Hash::sha256(code);
+        let message = panic_message(payload.as_ref());
+        tracing::error!(
+            "Wasm code with hash {} panicked: {}",
+            code_hash,
+            message
+        );
+        Err(Error::Panic { code_hash, message })
+    })
+}
+
+/// Get the message of a panic from its payload.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
+
 /// Validity predicate wasm evaluator for `eval` host function calls.
 #[derive(Default)]
 pub struct VpEvalWasm<DB, H, CA>
@@ -358,6 +442,7 @@ where
         let imports = vp_imports(&store, initial_memory, env);
 
         run_vp(
+            &vp_code,
             module,
             imports,
             &input_data[..],
@@ -488,6 +573,7 @@ mod tests {
             tx_data,
             &mut vp_cache,
             &mut tx_cache,
+            None,
         );
         assert!(result.is_ok(), "Expected success, got {:?}", result);
 
@@ -502,6 +588,7 @@ mod tests {
             tx_data,
             &mut vp_cache,
             &mut tx_cache,
+            None,
         )
         .expect_err("Expected to run out of memory");
 
@@ -551,6 +638,7 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache.clone(),
+            None,
         )
         .unwrap();
         assert!(passed);
@@ -577,6 +665,7 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache,
+            None,
         )
         .unwrap();
 
@@ -616,6 +705,7 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache.clone(),
+            None,
         );
         assert!(result.is_ok(), "Expected success, got {:?}", result);
 
@@ -633,6 +723,7 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache,
+            None,
         )
         .expect_err("Expected to run out of memory");
 
@@ -668,6 +759,7 @@ mod tests {
             tx_data,
             &mut vp_cache,
             &mut tx_cache,
+            None,
         );
         // Depending on platform, we get a different error from the running out
         // of memory
@@ -721,6 +813,7 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache,
+            None,
         );
         // Depending on platform, we get a different error from the running out
         // of memory
@@ -779,6 +872,7 @@ mod tests {
             tx_data,
             &mut vp_cache,
             &mut tx_cache,
+            None,
         )
         .expect_err("Expected to run out of memory");
 
@@ -824,6 +918,7 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache,
+            None,
         )
         .expect_err("Expected to run out of memory");
 
@@ -878,11 +973,252 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache,
+            None,
         )
         .unwrap();
         assert!(!passed);
     }
 
+    /// Test that a VP wasm that traps or panics in the host environment is
+    /// rejected with an error and that other VPs can still be run afterwards.
+    #[test]
+    fn test_vp_trap_and_host_panic() {
+        // A validity predicate that traps
+        let vp_code = wasmer::wat2wasm(
+            r#"
+            (module
+                (type (;0;) (func (param i64 i64 i64 i64 i64 i64 i64 i64) (result i64)))
+                (func $_validate_tx (type 0) (param i64 i64 i64 i64 i64 i64 i64 i64) (result i64)
+                unreachable)
+                (memory (;0;) 16)
+                (export "memory" (memory 0))
+                (export "_validate_tx" (func $_validate_tx)))
+            "#
+            .as_bytes(),
+        )
+        .expect("unexpected error converting wat2wasm")
+        .into_owned();
+        let error = run_vp_code(vp_code)
+            .expect_err("Expecting runtime error caused by a trap. Got");
+        assert_eq!(
+            get_trap_code(&error),
+            Either::Left(TrapCode::UnreachableCodeReached)
+        );
+
+        // A validity predicate that reads the result buffer without any prior
+        // read, which panics in the host environment
+        let vp_code = wasmer::wat2wasm(
+            r#"
+            (module
+                (type (;0;) (func (param i64 i64 i64 i64 i64 i64 i64 i64) (result i64)))
+                (import "env" "anoma_vp_result_buffer" (func $result_buffer (param i64)))
+                (func $_validate_tx (type 0) (param i64 i64 i64 i64 i64 i64 i64 i64) (result i64)
+                (call $result_buffer (i64.const 0))
+                (i64.const 1))
+                (memory (;0;) 16)
+                (export "memory" (memory 0))
+                (export "_validate_tx" (func $_validate_tx)))
+            "#
+            .as_bytes(),
+        )
+        .expect("unexpected error converting wat2wasm")
+        .into_owned();
+        let expected_hash = Hash::sha256(&vp_code);
+        match run_vp_code(vp_code) {
+            Err(Error::Panic { code_hash, .. }) => {
+                assert_eq!(code_hash, expected_hash)
+            }
+            result => panic!("Expecting a panic error. Got {:?}", result),
+        }
+
+        // Other VPs are not affected
+        let vp_code =
+            std::fs::read(VP_ALWAYS_TRUE_WASM).expect("cannot load wasm");
+        assert!(run_vp_code(vp_code).expect("VP should run"));
+    }
+
+    /// Test that a panic in the host environment during a transaction is
+    /// turned into an error.
+    #[test]
+    fn test_tx_host_panic() {
+        // A transaction that reads the result buffer without any prior read,
+        // which panics in the host environment
+        let tx_code = wasmer::wat2wasm(
+            r#"
+            (module
+                (type (;0;) (func (param i64 i64)))
+                (import "env" "anoma_tx_result_buffer" (func $result_buffer (param i64)))
+                (func $_apply_tx (type 0) (param i64 i64)
+                (call $result_buffer (i64.const 0)))
+                (memory (;0;) 16)
+                (export "memory" (memory 0))
+                (export "_apply_tx" (func $_apply_tx)))
+            "#
+            .as_bytes(),
+        )
+        .expect("unexpected error converting wat2wasm")
+        .into_owned();
+
+        let storage = TestStorage::default();
+        let mut write_log = WriteLog::default();
+        let mut gas_meter = BlockGasMeter::default();
+        let (mut vp_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        let (mut tx_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        let result = tx(
+            &storage,
+            &mut write_log,
+            &mut gas_meter,
+            &tx_code,
+            vec![],
+            &mut vp_cache,
+            &mut tx_cache,
+            None,
+        );
+        match result {
+            Err(Error::Panic { code_hash, .. }) => {
+                assert_eq!(code_hash, Hash::sha256(&tx_code))
+            }
+            result => panic!("Expecting a panic error. Got {:?}", result),
+        }
+    }
+
+    /// Test that a transaction wasm that loops forever within its gas limit
+    /// is interrupted by the timeout and fails with the timeout error.
+    #[test]
+    fn test_tx_timeout() {
+        let tx_code = wasmer::wat2wasm(
+            r#"
+            (module
+                (type (;0;) (func (param i64 i64)))
+
+                ;; an infinite loop
+                (func $_apply_tx (type 0) (param i64 i64)
+                (loop $forever (br $forever)))
+
+                (table (;0;) 1 1 funcref)
+                (memory (;0;) 16)
+                (global (;0;) (mut i32) (i32.const 1048576))
+                (export "memory" (memory 0))
+                (export "_apply_tx" (func $_apply_tx)))
+            "#
+            .as_bytes(),
+        )
+        .expect("unexpected error converting wat2wasm")
+        .into_owned();
+
+        let storage = TestStorage::default();
+        let mut write_log = WriteLog::default();
+        let mut gas_meter = BlockGasMeter::default();
+        let (mut vp_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        let (mut tx_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        let timeout = Duration::from_millis(100);
+        let start = Instant::now();
+        let result = tx(
+            &storage,
+            &mut write_log,
+            &mut gas_meter,
+            tx_code.clone(),
+            vec![],
+            &mut vp_cache,
+            &mut tx_cache,
+            Some(timeout),
+        );
+        match result {
+            Err(Error::Timeout {
+                code_hash,
+                timeout: error_timeout,
+            }) => {
+                assert_eq!(code_hash, Hash::sha256(&tx_code));
+                assert_eq!(error_timeout, timeout);
+            }
+            result => panic!("Expecting a timeout error. Got {:?}", result),
+        }
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    /// Test that a VP wasm that loops forever within its gas limit is
+    /// interrupted by the timeout and fails with the timeout error, which
+    /// rejects the transaction.
+    #[test]
+    fn test_vp_timeout() {
+        let vp_code = wasmer::wat2wasm(
+            r#"
+            (module
+                (type (;0;) (func (param i64 i64 i64 i64 i64 i64 i64 i64) (result i64)))
+
+                ;; an infinite loop
+                (func $_validate_tx (type 0) (param i64 i64 i64 i64 i64 i64 i64 i64) (result i64)
+                (loop $forever (br $forever))
+                (i64.const 1))
+
+                (table (;0;) 1 1 funcref)
+                (memory (;0;) 16)
+                (global (;0;) (mut i32) (i32.const 1048576))
+                (export "memory" (memory 0))
+                (export "_validate_tx" (func $_validate_tx)))
+            "#
+            .as_bytes(),
+        )
+        .expect("unexpected error converting wat2wasm")
+        .into_owned();
+
+        let tx = Tx::new(vec![], None);
+        let mut storage = TestStorage::default();
+        let addr = storage.address_gen.generate_address("rng seed");
+        let write_log = WriteLog::default();
+        let mut gas_meter = VpGasMeter::new(0);
+        let keys_changed = BTreeSet::new();
+        let verifiers = BTreeSet::new();
+        let (vp_cache, _) = wasm::compilation_cache::common::testing::cache();
+        let start = Instant::now();
+        let result = vp(
+            &vp_code,
+            &tx,
+            &addr,
+            &storage,
+            &write_log,
+            &mut gas_meter,
+            &keys_changed,
+            &verifiers,
+            vp_cache,
+            Some(Duration::from_millis(100)),
+        );
+        match result {
+            Err(Error::Timeout { code_hash, .. }) => {
+                assert_eq!(code_hash, Hash::sha256(&vp_code))
+            }
+            result => panic!("Expecting a timeout error. Got {:?}", result),
+        }
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    fn run_vp_code(vp_code: Vec<u8>) -> Result<bool> {
+        let tx = Tx::new(vec![], None);
+        let mut storage = TestStorage::default();
+        let addr = storage.address_gen.generate_address("rng seed");
+        let write_log = WriteLog::default();
+        let mut gas_meter = VpGasMeter::new(0);
+        let keys_changed = BTreeSet::new();
+        let verifiers = BTreeSet::new();
+        let (vp_cache, _) = wasm::compilation_cache::common::testing::cache();
+        vp(
+            vp_code,
+            &tx,
+            &addr,
+            &storage,
+            &write_log,
+            &mut gas_meter,
+            &keys_changed,
+            &verifiers,
+            vp_cache,
+            None,
+        )
+    }
+
     fn loop_in_tx_wasm(loops: u32) -> Result<BTreeSet<Address>> {
         // A transaction with a recursive loop.
         // The boilerplate code is generated from tx_template.wasm using
@@ -934,6 +1270,7 @@ mod tests {
             tx_data,
             &mut vp_cache,
             &mut tx_cache,
+            None,
         )
     }
 
@@ -984,6 +1321,7 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache,
+            None,
         )
     }
 
@@ -0,0 +1,27 @@
+//! The human-readable part of the addresses set for the whole process. This
+//! is a separate test binary, because the human-readable part can only be set
+//! once and the unit tests use the default one.
+
+use anoma::types::address::{self, xan, Address, DEFAULT_ADDRESS_HRP};
+
+/// Test that the token addresses of the protocol are still available with a
+/// non-default human-readable part and that they're encoded with it
+#[test]
+fn test_token_addresses_with_address_hrp() {
+    address::set_address_hrp("anoma").expect("Test failed");
+    assert_eq!(address::address_hrp(), "anoma");
+
+    let token = xan();
+    let encoded = token.encode();
+    assert!(encoded.starts_with("anoma1"), "{}", encoded);
+    assert_eq!(Address::decode(&encoded).expect("Test failed"), token);
+
+    // The addresses with the default human-readable part are rejected
+    let default_encoded = token.encode_with_hrp(DEFAULT_ADDRESS_HRP);
+    assert!(Address::decode(&default_encoded).is_err());
+
+    // The human-readable part can only be set once
+    address::set_address_hrp("anoma").expect("Test failed");
+    assert!(address::set_address_hrp(DEFAULT_ADDRESS_HRP).is_err());
+    assert_eq!(address::address_hrp(), "anoma");
+}
@@ -0,0 +1,182 @@
+//! The peak heap memory used to write the VP inputs into wasm memory and to
+//! run a VP of a tx with large data. This is a separate test binary, because
+//! the allocation tracking needs its own global allocator, which would
+//! otherwise be used by all the unit tests.
+//!
+//! The allocations are tracked per thread, so a VP is run on its own here
+//! rather than through `apply_tx`, which runs the VPs of a tx in parallel.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::collections::BTreeSet;
+
+use anoma::ledger::gas::VpGasMeter;
+use anoma::ledger::storage::testing::TestStorage;
+use anoma::ledger::storage::write_log::WriteLog;
+use anoma::proto::Tx;
+use anoma::types::address::{self, Address};
+use anoma::types::storage::Key;
+use anoma::vm::types::VpInput;
+use anoma::vm::wasm::compilation_cache::common::testing::cache;
+use anoma::vm::wasm::memory::{prepare_vp_memory, write_vp_inputs};
+use anoma::vm::wasm::run::vp;
+use borsh::BorshSerialize;
+use wasmer::{Cranelift, Memory, Store};
+
+/// An allocator that tracks the peak of the heap memory allocated by the
+/// current thread, to check the memory used by writing the inputs
+struct TrackingAllocator;
+
+thread_local! {
+    /// The currently allocated and the peak bytes, when tracked
+    static TRACKED: Cell<Option<(usize, usize)>> = Cell::new(None);
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = TRACKED.try_with(|tracked| {
+            if let Some((current, peak)) = tracked.get() {
+                let current = current + layout.size();
+                tracked.set(Some((current, peak.max(current))));
+            }
+        });
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let _ = TRACKED.try_with(|tracked| {
+            if let Some((current, peak)) = tracked.get() {
+                let current = current.saturating_sub(layout.size());
+                tracked.set(Some((current, peak)));
+            }
+        });
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+/// Call the given function and return its result with the peak of the heap
+/// memory that it allocated
+fn with_peak_allocated<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    TRACKED.with(|tracked| tracked.set(Some((0, 0))));
+    let result = f();
+    let (_current, peak) =
+        TRACKED.with(|tracked| tracked.replace(None)).unwrap();
+    (result, peak)
+}
+
+/// Read bytes from the wasm memory at the given offset and length
+fn read_memory_bytes(memory: &Memory, offset: u64, len: usize) -> Vec<u8> {
+    let offset = offset as usize;
+    memory.view()[offset..(offset + len)]
+        .iter()
+        .map(|cell| cell.get())
+        .collect()
+}
+
+/// Test that writing the VP inputs with large tx data doesn't allocate
+/// another copy of the data and that the memory has the same content as when
+/// the inputs are buffered and written at once.
+#[test]
+fn test_write_vp_inputs_large_data() {
+    let engine =
+        wasmer_engine_universal::Universal::new(Cranelift::default()).engine();
+    let store = Store::new(&engine);
+    let memory = prepare_vp_memory(&store).unwrap();
+
+    let addr = address::testing::established_address_1();
+    let data = vec![6_u8; 8 * 1024 * 1024];
+    let keys_changed: BTreeSet<Key> =
+        [Key::parse("key").unwrap()].into_iter().collect();
+    let verifiers: BTreeSet<Address> = [addr.clone()].into_iter().collect();
+    let input = VpInput {
+        addr: &addr,
+        data: &data,
+        keys_changed: &keys_changed,
+        verifiers: &verifiers,
+    };
+
+    let (call_input, peak) =
+        with_peak_allocated(|| write_vp_inputs(&memory, input).unwrap());
+    assert!(
+        peak < data.len() / 8,
+        "Writing {} bytes of data allocated {} bytes",
+        data.len(),
+        peak
+    );
+
+    let (buffered, buffered_peak) = with_peak_allocated(|| {
+        [
+            addr.try_to_vec().unwrap(),
+            data.clone(),
+            keys_changed.try_to_vec().unwrap(),
+            verifiers.try_to_vec().unwrap(),
+        ]
+        .concat()
+    });
+    assert!(buffered_peak > data.len());
+    assert!(peak < buffered_peak / 2);
+
+    let written = read_memory_bytes(&memory, 0, buffered.len());
+    assert!(written == buffered);
+    assert_eq!(
+        call_input.verifiers_ptr + call_input.verifiers_len,
+        buffered.len() as u64
+    );
+    let written_data = read_memory_bytes(
+        &memory,
+        call_input.data_ptr,
+        call_input.data_len as _,
+    );
+    assert!(written_data == data);
+}
+
+/// Test that running a VP of a tx with large data, from the decoded tx to the
+/// VP's result, doesn't allocate another copy of the data and that the VP's
+/// result is the same as with small data
+#[test]
+fn test_run_vp_large_data() {
+    let storage = TestStorage::default();
+    let write_log = WriteLog::default();
+    let addr = address::testing::established_address_1();
+    let keys_changed = BTreeSet::new();
+    let verifiers = BTreeSet::new();
+    let vp_code = std::fs::read("../wasm_for_tests/vp_always_true.wasm")
+        .expect("cannot load wasm");
+    let (vp_cache, _cache_dir) = cache();
+
+    let run_vp = |data: Vec<u8>| {
+        let tx = Tx::new(vec![], Some(data));
+        let mut gas_meter = VpGasMeter::new(0);
+        with_peak_allocated(|| {
+            vp(
+                &vp_code,
+                &tx,
+                &addr,
+                &storage,
+                &write_log,
+                &mut gas_meter,
+                &keys_changed,
+                &verifiers,
+                vp_cache.clone(),
+                None,
+            )
+            .unwrap()
+        })
+    };
+
+    // The first run compiles the VP into the cache
+    let (small_data_result, _peak) = run_vp(vec![6_u8; 1024]);
+
+    let data_len = 8 * 1024 * 1024;
+    let (large_data_result, peak) = run_vp(vec![6_u8; data_len]);
+    assert_eq!(large_data_result, small_data_result);
+    assert!(
+        peak < data_len / 2,
+        "Running a VP with {} bytes of data allocated {} bytes",
+        data_len,
+        peak
+    );
+}
@@ -4,7 +4,7 @@ use std::str::FromStr;
 
 use anoma::types::address::Address;
 use anoma::types::key::*;
-use anoma::types::storage::Epoch;
+use anoma::types::storage::{BlockHeight, Epoch};
 use anoma_apps::config::{Config, TendermintMode};
 use color_eyre::eyre::Result;
 use eyre::eyre;
@@ -45,6 +45,26 @@ pub fn get_actor_rpc(test: &Test, who: &Who) -> String {
     config.ledger.tendermint.rpc_address.to_string()
 }
 
+/// Update the config of the given actor's node with the given function and
+/// write it back, so that it takes effect on the node's next start.
+pub fn update_actor_config(
+    test: &Test,
+    who: &Who,
+    update: impl FnOnce(&mut Config),
+) {
+    let base_dir = test.get_base_dir(who);
+    let tendermint_mode = match who {
+        Who::NonValidator => TendermintMode::Full,
+        Who::Validator(_) => TendermintMode::Validator,
+    };
+    let mut config =
+        Config::load(&base_dir, &test.net.chain_id, Some(tendermint_mode));
+    update(&mut config);
+    config
+        .write(&base_dir, &test.net.chain_id, true)
+        .expect("Test failed");
+}
+
 /// Find the address of the intent gossiper node's matchmakers server.
 pub fn get_gossiper_mm_server(test: &Test, who: &Who) -> String {
     let base_dir = test.get_base_dir(who);
@@ -116,6 +136,59 @@ pub fn find_voting_power(
     })
 }
 
+/// Find the voting power of a validator by its alias in every epoch of the
+/// given inclusive range
+pub fn find_voting_power_history(
+    test: &Test,
+    alias: impl AsRef<str>,
+    from_epoch: Epoch,
+    to_epoch: Epoch,
+    ledger_address: &str,
+) -> Result<Vec<(Epoch, u64)>> {
+    let from_epoch_str = from_epoch.to_string();
+    let to_epoch_str = to_epoch.to_string();
+    let mut find = run!(
+        test,
+        Bin::Client,
+        &[
+            "voting-power",
+            "--validator",
+            alias.as_ref(),
+            "--from-epoch",
+            &from_epoch_str,
+            "--to-epoch",
+            &to_epoch_str,
+            "--ledger-address",
+            ledger_address
+        ],
+        Some(10)
+    )?;
+    let mut history = vec![];
+    for _ in from_epoch.0..=to_epoch.0 {
+        let (unread, matched) = find.exp_regex(r"Epoch \d+: \d+")?;
+        let parsed = matched
+            .trim()
+            .strip_prefix("Epoch ")
+            .and_then(|entry| entry.split_once(": "))
+            .and_then(|(epoch, voting_power)| {
+                Some((
+                    Epoch::from_str(epoch).ok()?,
+                    u64::from_str(voting_power).ok()?,
+                ))
+            });
+        match parsed {
+            Some(entry) => history.push(entry),
+            None => {
+                return Err(eyre!(format!(
+                    "Couldn't parse the voting power from {}\n\nOutput: {}",
+                    matched, unread
+                )));
+            }
+        }
+    }
+    Ok(history)
+}
+
 /// Get the last committed epoch.
 pub fn get_epoch(test: &Test, ledger_address: &str) -> Result<Epoch> {
     let mut find = run!(
@@ -134,3 +207,46 @@ pub fn get_epoch(test: &Test, ledger_address: &str) -> Result<Epoch> {
     })?;
     Ok(Epoch(epoch))
 }
+
+/// Get the last committed block height.
+pub fn get_height(test: &Test, ledger_address: &str) -> Result<BlockHeight> {
+    let mut find = run!(
+        test,
+        Bin::Client,
+        &["node-status", "--ledger-address", ledger_address],
+        Some(5)
+    )?;
+    let (unread, matched) =
+        find.exp_regex("Last committed block height: .*\n")?;
+    let height_str = matched.trim().rsplit_once(' ').unwrap().1;
+    let height = u64::from_str(height_str).map_err(|e| {
+        eyre!(format!(
+            "Height: {} parsed from {}, Error: {}\n\nOutput: {}",
+            height_str, matched, e, unread
+        ))
+    })?;
+    Ok(BlockHeight(height))
+}
+
+/// Get the value of a parameter with the given name from the output of the
+/// `query-params` command, e.g. "Unbonding length".
+pub fn get_parameter(
+    test: &Test,
+    ledger_address: &str,
+    name: &str,
+) -> Result<u64> {
+    let mut find = run!(
+        test,
+        Bin::Client,
+        &["query-params", "--ledger-address", ledger_address],
+        Some(5)
+    )?;
+    let (unread, matched) = find.exp_regex(&format!("{}: .*\n", name))?;
+    let value_str = matched.trim().rsplit_once(' ').unwrap().1;
+    u64::from_str(value_str).map_err(|e| {
+        eyre!(format!(
+            "{}: {} parsed from {}, Error: {}\n\nOutput: {}",
+            name, value_str, matched, e, unread
+        ))
+    })
+}
@@ -15,9 +15,11 @@ use std::process::Command;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use anoma::types::hash::Hash;
+use anoma::types::storage::{self, BlockHeight, Epoch, KeySeg};
 use anoma::types::token;
 use anoma_apps::config::genesis::genesis_config::{
-    GenesisConfig, ParametersConfig, PosParamsConfig,
+    self, GenesisConfig, HexString, ParametersConfig, PosParamsConfig,
 };
 use borsh::BorshSerialize;
 use color_eyre::eyre::Result;
@@ -25,7 +27,8 @@ use serde_json::json;
 use setup::constants::*;
 
 use crate::e2e::helpers::{
-    find_address, find_voting_power, get_actor_rpc, get_epoch,
+    find_address, find_voting_power, find_voting_power_history, get_actor_rpc,
+    get_epoch, get_height, get_parameter, update_actor_config,
 };
 use crate::e2e::setup::{self, sleep, Bin, Who};
 use crate::{run, run_as};
@@ -161,6 +164,61 @@ fn run_ledger_load_state_and_reset() -> Result<()> {
     Ok(())
 }
 
+/// In this test we:
+/// 1. Set a halt height a few blocks ahead in the node's config
+/// 2. Run the ledger node, it should halt before the block at the halt height
+///    and shut down
+/// 3. Unset the halt height and run the ledger again, it should load the
+///    state committed below the halt height and continue
+#[test]
+fn run_ledger_halt_at_height() -> Result<()> {
+    let test = setup::single_node_net()?;
+    let halt_height = 5;
+
+    // 1. Set the halt height
+    update_actor_config(&test, &Who::Validator(0), |config| {
+        config.ledger.shell.halt_height = Some(halt_height)
+    });
+
+    // 2. Run the ledger node until it halts
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(60))?;
+    ledger.exp_string("Anoma ledger node started")?;
+    // The blocks below the halt height are committed
+    ledger.exp_regex(&format!(
+        r"Committed block hash.*, height: {}\D",
+        halt_height - 1
+    ))?;
+    // The block at the halt height is not
+    let unread = ledger.exp_string(&format!(
+        "halting for upgrade at height {}",
+        halt_height
+    ))?;
+    assert!(!unread.contains("Committed block hash"), "{}", unread);
+    ledger.exp_string("Anoma ledger node has shut down.")?;
+    ledger.exp_eof()?;
+    drop(ledger);
+
+    // 3. Unset the halt height and run the ledger again
+    update_actor_config(&test, &Who::Validator(0), |config| {
+        config.ledger.shell.halt_height = None
+    });
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+    ledger.exp_string("Anoma ledger node started")?;
+    ledger.exp_regex(&format!(
+        r"Last state root hash: .*, height: {}\D",
+        halt_height - 1
+    ))?;
+    // The node continues with the block at the halt height
+    ledger.exp_regex(&format!(
+        r"Committed block hash.*, height: {}\D",
+        halt_height
+    ))?;
+
+    Ok(())
+}
+
 /// In this test we:
 /// 1. Run the ledger node
 /// 2. Submit a token transfer tx
@@ -169,6 +227,7 @@ fn run_ledger_load_state_and_reset() -> Result<()> {
 /// 5. Submit a tx to initialize a new account
 /// 6. Query token balance
 /// 7. Query the raw bytes of a storage key
+/// 8. Query the same storage key decoded as an amount
 #[test]
 fn ledger_txs_and_queries() -> Result<()> {
     let test = setup::network(|genesis| genesis, None)?;
@@ -330,6 +389,20 @@ fn ledger_txs_and_queries() -> Result<()> {
             // expect hex encoded of borsh encoded bytes
             hex::encode(christel_balance.try_to_vec().unwrap()),
         ),
+        // 8. Query storage key and decode it as an amount
+        (
+            vec![
+                "query-bytes",
+                "--storage-key",
+                &storage_key,
+                "--as",
+                "amount",
+                "--ledger-address",
+                &validator_one_rpc,
+            ],
+            // expect the decimal amount
+            christel_balance.to_string(),
+        ),
     ];
     for (query_args, expected) in &query_args_and_expected_response {
         let mut client = run!(test, Bin::Client, query_args, Some(40))?;
@@ -471,6 +544,73 @@ fn invalid_transactions() -> Result<()> {
     Ok(())
 }
 
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Submit an invalid transaction (trying to mint tokens) with the client
+///    check, it must be flagged by the client before it's broadcast
+#[test]
+fn client_check_flags_invalid_tx() -> Result<()> {
+    let test = setup::single_node_net()?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+    // Wait to commit a block
+    ledger.exp_regex(r"Committed block hash.*, height: [0-9]+")?;
+
+    // 2. Submit an invalid transaction (trying to mint tokens should fail
+    // in the token's VP) with the client check
+    let tx_data_path = test.base_dir.path().join("tx.data");
+    let transfer = token::Transfer {
+        source: find_address(&test, DAEWON)?,
+        target: find_address(&test, ALBERT)?,
+        token: find_address(&test, XAN)?,
+        amount: token::Amount::whole(1),
+    };
+    let data = transfer
+        .try_to_vec()
+        .expect("Encoding unsigned transfer shouldn't fail");
+    let tx_wasm_path = wasm_abs_path(TX_MINT_TOKENS_WASM);
+    std::fs::write(&tx_data_path, data).unwrap();
+    let tx_wasm_path = tx_wasm_path.to_string_lossy();
+    let tx_data_path = tx_data_path.to_string_lossy();
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    let tx_args = vec![
+        "tx",
+        "--code-path",
+        &tx_wasm_path,
+        "--data-path",
+        &tx_data_path,
+        "--signing-key",
+        DAEWON,
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--check",
+        "--no-interactive",
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("The transaction would be rejected by the validity")?;
+    client.exp_string("Aborting, the transaction has not been submitted.")?;
+    client.assert_failure();
+
+    Ok(())
+}
+
 /// PoS bonding, unbonding and withdrawal tests. In this test we:
 ///
 /// 1. Run the ledger node with shorter epochs for faster progression
@@ -483,7 +623,6 @@ fn invalid_transactions() -> Result<()> {
 /// 8. Submit a withdrawal of the delegation
 #[test]
 fn pos_bonds() -> Result<()> {
-    let unbonding_len = 2;
     let test = setup::network(
         |genesis| {
             let parameters = ParametersConfig {
@@ -494,7 +633,7 @@ fn pos_bonds() -> Result<()> {
             };
             let pos_params = PosParamsConfig {
                 pipeline_len: 1,
-                unbonding_len,
+                unbonding_len: 2,
                 ..genesis.pos_params
             };
             GenesisConfig {
@@ -606,6 +745,8 @@ fn pos_bonds() -> Result<()> {
     client.assert_success();
 
     // 6. Wait for the unbonding epoch
+    let unbonding_len =
+        get_parameter(&test, &validator_one_rpc, "Unbonding length")?;
     let epoch = get_epoch(&test, &validator_one_rpc)?;
     let earliest_withdrawal_epoch = epoch + unbonding_len;
     println!(
@@ -669,6 +810,110 @@ fn pos_bonds() -> Result<()> {
     Ok(())
 }
 
+/// Test that a withdrawal submitted with `--wait-for-epoch` waits for the
+/// unbonding epoch and is then applied. In this test we:
+///
+/// 1. Run the ledger node with shorter epochs for faster progression
+/// 2. Submit an unbond of the genesis validator's self-bond
+/// 3. Submit a withdrawal that waits for the earliest withdrawal epoch
+/// 4. Check that the withdrawal was submitted in that epoch
+#[test]
+fn pos_withdraw_wait_for_epoch() -> Result<()> {
+    let test = setup::network(
+        |genesis| {
+            let parameters = ParametersConfig {
+                min_num_of_blocks: 2,
+                min_duration: 1,
+                max_expected_time_per_block: 1,
+                ..genesis.parameters
+            };
+            let pos_params = PosParamsConfig {
+                pipeline_len: 1,
+                unbonding_len: 2,
+                ..genesis.pos_params
+            };
+            GenesisConfig {
+                parameters,
+                pos_params,
+                ..genesis
+            }
+        },
+        None,
+    )?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Submit an unbond of the self-bond
+    let tx_args = vec![
+        "unbond",
+        "--validator",
+        "validator-0",
+        "--amount",
+        "5.1",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client =
+        run_as!(test, Who::Validator(0), Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 3. Submit a withdrawal that waits for the unbonding epoch
+    let unbonding_len =
+        get_parameter(&test, &validator_one_rpc, "Unbonding length")?;
+    let epoch = get_epoch(&test, &validator_one_rpc)?;
+    let earliest_withdrawal_epoch = epoch + unbonding_len;
+    let earliest_withdrawal_epoch_str = earliest_withdrawal_epoch.to_string();
+    let tx_args = vec![
+        "withdraw",
+        "--validator",
+        "validator-0",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--wait-for-epoch",
+        &earliest_withdrawal_epoch_str,
+        "--wait-timeout",
+        "30",
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client =
+        run_as!(test, Who::Validator(0), Bin::Client, tx_args, Some(40))?;
+    client.exp_string(&format!(
+        "Waiting for epoch {} to submit the transaction",
+        earliest_withdrawal_epoch
+    ))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 4. Check that the withdrawal waited for the epoch
+    let epoch = get_epoch(&test, &validator_one_rpc)?;
+    assert!(epoch >= earliest_withdrawal_epoch);
+
+    Ok(())
+}
+
 /// PoS validator creation test. In this test we:
 ///
 /// 1. Run the ledger node with shorter epochs for faster progression
@@ -856,22 +1101,43 @@ fn pos_init_validator() -> Result<()> {
 
     Ok(())
 }
-/// Test that multiple txs submitted in the same block all get the tx result.
-///
+
 /// In this test we:
-/// 1. Run the ledger node with 10s consensus timeout
-/// 2. Spawn threads each submitting token transfer tx
+/// 1. Run the ledger node with shorter epochs for faster progression
+/// 2. Generate a consensus key and initialize a new validator with it
+/// 3. Wait for the pipeline epoch and check that the validator is in the
+///    validator set with its consensus key
+/// 4. Rotate the validator's consensus key
+/// 5. Check that the new key appears in the validator set at the pipeline
+///    epoch, while the current validator set still has the previous key
 #[test]
-fn ledger_many_txs_in_a_block() -> Result<()> {
-    let test = Arc::new(setup::network(
-        |genesis| genesis,
-        // Set 10s consensus timeout to have more time to submit txs
-        Some("10s"),
-    )?);
+fn pos_rotate_consensus_key() -> Result<()> {
+    let pipeline_len = 1;
+    let test = setup::network(
+        |genesis| {
+            let parameters = ParametersConfig {
+                min_num_of_blocks: 2,
+                min_duration: 1,
+                max_expected_time_per_block: 1,
+                ..genesis.parameters
+            };
+            let pos_params = PosParamsConfig {
+                pipeline_len,
+                unbonding_len: 2,
+                ..genesis.pos_params
+            };
+            GenesisConfig {
+                parameters,
+                pos_params,
+                ..genesis
+            }
+        },
+        None,
+    )?;
 
     // 1. Run the ledger node
     let mut ledger =
-        run_as!(*test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
 
     ledger.exp_string("Anoma ledger node started")?;
     if !cfg!(feature = "ABCI") {
@@ -880,22 +1146,57 @@ fn ledger_many_txs_in_a_block() -> Result<()> {
         ledger.exp_string("Started node")?;
     }
 
-    // Wait to commit a block
-    ledger.exp_regex(r"Committed block hash.*, height: [0-9]+")?;
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
 
-    let validator_one_rpc = Arc::new(get_actor_rpc(&test, &Who::Validator(0)));
+    // A helper to generate a keypair in the wallet and read its public key
+    let gen_key = |alias: &str| -> Result<String> {
+        let gen_args =
+            vec!["keypair", "gen", "--alias", alias, "--unsafe-dont-encrypt"];
+        let mut wallet = run!(test, Bin::Wallet, &gen_args, Some(20))?;
+        let (_unread, matched) = wallet.exp_regex("Public key: .*\n")?;
+        let public_key = matched.trim().rsplit_once(' ').unwrap().1.to_owned();
+        wallet.assert_success();
+        Ok(public_key)
+    };
 
-    // A token transfer tx args
-    let tx_args = Arc::new(vec![
+    // 2. Generate a consensus key and initialize a new validator with it
+    let new_validator = "new-validator";
+    let new_validator_key = format!("{}-key", new_validator);
+    let consensus_key_alias = "new-validator-first-consensus-key";
+    let consensus_key = gen_key(consensus_key_alias)?;
+    let tx_args = vec![
+        "init-validator",
+        "--alias",
+        new_validator,
+        "--source",
+        BERTHA,
+        "--consensus-key",
+        consensus_key_alias,
+        "--unsafe-dont-encrypt",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // Transfer some tokens to the validator's account key for fees
+    let tx_args = vec![
         "transfer",
         "--source",
         BERTHA,
         "--target",
-        ALBERT,
+        &new_validator_key,
         "--token",
         XAN,
         "--amount",
-        "10.1",
+        "0.5",
         "--fee-amount",
         "0",
         "--gas-limit",
@@ -903,50 +1204,3114 @@ fn ledger_many_txs_in_a_block() -> Result<()> {
         "--fee-token",
         XAN,
         "--ledger-address",
-    ]);
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
 
-    // 2. Spawn threads each submitting token transfer tx
-    // We collect to run the threads in parallel.
-    #[allow(clippy::needless_collect)]
-    let tasks: Vec<std::thread::JoinHandle<_>> = (0..3)
-        .into_iter()
-        .map(|_| {
-            let test = Arc::clone(&test);
-            let validator_one_rpc = Arc::clone(&validator_one_rpc);
-            let tx_args = Arc::clone(&tx_args);
-            std::thread::spawn(move || {
-                let mut args = (*tx_args).clone();
-                args.push(&*validator_one_rpc);
-                let mut client = run!(*test, Bin::Client, args, Some(40))?;
-                if !cfg!(feature = "ABCI") {
-                    client.exp_string("Transaction accepted")?;
-                }
-                client.exp_string("Transaction applied")?;
-                client.exp_string("Transaction is valid.")?;
-                client.assert_success();
-                let res: Result<()> = Ok(());
-                res
-            })
-        })
+    // 3. Wait for the pipeline epoch and check the validator set
+    let wait_for_epoch = |epoch: Epoch| -> Result<()> {
+        let start = Instant::now();
+        let loop_timeout = Duration::new(20, 0);
+        loop {
+            if Instant::now().duration_since(start) > loop_timeout {
+                panic!("Timed out waiting for epoch: {}", epoch);
+            }
+            if get_epoch(&test, &validator_one_rpc)? >= epoch {
+                return Ok(());
+            }
+        }
+    };
+    let epoch = get_epoch(&test, &validator_one_rpc)?;
+    wait_for_epoch(epoch + pipeline_len)?;
+    let query_args =
+        vec!["validator-set", "--ledger-address", &validator_one_rpc];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    client.exp_string(&format!("consensus key {}", consensus_key))?;
+    client.assert_success();
+
+    // 4. Rotate the validator's consensus key
+    let new_consensus_key_alias = "new-validator-second-consensus-key";
+    let new_consensus_key = gen_key(new_consensus_key_alias)?;
+    let tx_args = vec![
+        "rotate-consensus-key",
+        "--validator",
+        new_validator,
+        "--consensus-key",
+        new_consensus_key_alias,
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    let (_unread, matched) =
+        client.exp_regex(r"is used for the validator from the epoch \d+")?;
+    let pipeline_epoch: u64 = matched.rsplit_once(' ').unwrap().1.parse()?;
+    client.assert_success();
+
+    // 5. The new key is in the validator set of the pipeline epoch
+    let pipeline_epoch = pipeline_epoch.to_string();
+    let query_args = vec![
+        "validator-set",
+        "--epoch",
+        &pipeline_epoch,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    client.exp_string(&format!("consensus key {}", new_consensus_key))?;
+    client.assert_success();
+
+    Ok(())
+}
+
+/// Test that the history of a validator's voting power reflects its bond and
+/// unbond at the epochs in which they take effect. In this test we:
+///
+/// 1. Run the ledger node with shorter epochs for faster progression
+/// 2. Submit a self-bond for the genesis validator
+/// 3. Wait for the pipeline epoch and submit an unbond of a half of the bond
+/// 4. Wait for the unbonding epoch
+/// 5. Query the voting power history and check that the bond is added at the
+///    pipeline offset and the unbond is subtracted at the unbonding offset
+///    from the epoch in which they were applied
+#[test]
+fn pos_voting_power_history() -> Result<()> {
+    let test = setup::network(
+        |genesis| {
+            let parameters = ParametersConfig {
+                min_num_of_blocks: 2,
+                min_duration: 1,
+                max_expected_time_per_block: 1,
+                ..genesis.parameters
+            };
+            let pos_params = PosParamsConfig {
+                pipeline_len: 1,
+                unbonding_len: 2,
+                ..genesis.pos_params
+            };
+            GenesisConfig {
+                parameters,
+                pos_params,
+                ..genesis
+            }
+        },
+        None,
+    )?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+    let wait_for_epoch = |epoch| -> Result<()> {
+        let start = Instant::now();
+        let loop_timeout = Duration::new(20, 0);
+        while get_epoch(&test, &validator_one_rpc)? < epoch {
+            if Instant::now().duration_since(start) > loop_timeout {
+                panic!("Timed out waiting for epoch: {}", epoch);
+            }
+        }
+        Ok(())
+    };
+
+    // 2. Submit a self-bond for the genesis validator. The tx is applied in
+    // an epoch between the epochs found before and after it.
+    let bond_epoch_min = get_epoch(&test, &validator_one_rpc)?;
+    let tx_args = vec![
+        "bond",
+        "--validator",
+        "validator-0",
+        "--amount",
+        "100000",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client =
+        run_as!(test, Who::Validator(0), Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+    let bond_epoch_max = get_epoch(&test, &validator_one_rpc)?;
+
+    // 3. Wait for the pipeline epoch and submit an unbond
+    wait_for_epoch(bond_epoch_max + 1)?;
+    let unbond_epoch_min = get_epoch(&test, &validator_one_rpc)?;
+    let tx_args = vec![
+        "unbond",
+        "--validator",
+        "validator-0",
+        "--amount",
+        "50000",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client =
+        run_as!(test, Who::Validator(0), Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+    let unbond_epoch_max = get_epoch(&test, &validator_one_rpc)?;
+
+    // 4. Wait for the unbonding epoch
+    let last_epoch = unbond_epoch_max + 2;
+    wait_for_epoch(last_epoch)?;
+
+    // 5. Query the voting power history. With 1 voting power per 1000 XAN,
+    // the bond adds 100 and the unbond subtracts 50 voting power.
+    let history = find_voting_power_history(
+        &test,
+        "validator-0",
+        bond_epoch_min,
+        last_epoch,
+        &validator_one_rpc,
+    )?;
+    let epochs: Vec<_> = history.iter().map(|(epoch, _)| *epoch).collect();
+    let expected_epochs: Vec<_> =
+        (bond_epoch_min.0..=last_epoch.0).map(Epoch).collect();
+    assert_eq!(epochs, expected_epochs);
+    let initial = history[0].1;
+    let changes: Vec<_> = history
+        .windows(2)
+        .filter(|pair| pair[0].1 != pair[1].1)
+        .map(|pair| pair[1])
         .collect();
-    for task in tasks.into_iter() {
-        task.join().unwrap()?;
+    assert_eq!(changes.len(), 2, "Unexpected history {:?}", history);
+    let (bond_effect_epoch, bonded) = changes[0];
+    assert_eq!(bonded, initial + 100);
+    assert!(bond_effect_epoch >= bond_epoch_min + 1);
+    assert!(bond_effect_epoch <= bond_epoch_max + 1);
+    let (unbond_effect_epoch, unbonded) = changes[1];
+    assert_eq!(unbonded, initial + 50);
+    assert!(unbond_effect_epoch >= unbond_epoch_min + 2);
+    assert!(unbond_effect_epoch <= unbond_epoch_max + 2);
+
+    Ok(())
+}
+
+/// Test that a validator whose self-bond is below the minimum validator
+/// self-bond never gains voting power.
+///
+/// 1. Run the ledger node with a minimum validator self-bond
+/// 2. Initialize two new validators and transfer some XAN to them
+/// 3. Submit a self-bond below the minimum, it must fail
+/// 4. Submit a self-bond at the minimum
+/// 5. Wait for the pipeline epoch
+/// 6. Check that only the validator at the minimum is active
+#[test]
+fn pos_min_validator_self_bond() -> Result<()> {
+    let pipeline_len = 1;
+    let min_validator_self_bond = 1000;
+    let test = setup::network(
+        |genesis| {
+            let parameters = ParametersConfig {
+                min_num_of_blocks: 2,
+                min_duration: 1,
+                max_expected_time_per_block: 1,
+                ..genesis.parameters
+            };
+            let pos_params = PosParamsConfig {
+                pipeline_len,
+                unbonding_len: 2,
+                min_validator_self_bond,
+                ..genesis.pos_params
+            };
+            GenesisConfig {
+                parameters,
+                pos_params,
+                ..genesis
+            }
+        },
+        None,
+    )?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Initialize two new validators and transfer some XAN to them
+    let below_validator = "below-min-validator";
+    let min_validator = "min-validator";
+    for validator in [below_validator, min_validator] {
+        let tx_args = vec![
+            "init-validator",
+            "--alias",
+            validator,
+            "--source",
+            BERTHA,
+            "--unsafe-dont-encrypt",
+            "--fee-amount",
+            "0",
+            "--gas-limit",
+            "0",
+            "--fee-token",
+            XAN,
+            "--ledger-address",
+            &validator_one_rpc,
+        ];
+        let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+        client.exp_string("Transaction is valid.")?;
+        client.assert_success();
+
+        let tx_args = vec![
+            "transfer",
+            "--source",
+            BERTHA,
+            "--target",
+            validator,
+            "--token",
+            XAN,
+            "--amount",
+            "2000",
+            "--fee-amount",
+            "0",
+            "--gas-limit",
+            "0",
+            "--fee-token",
+            XAN,
+            "--ledger-address",
+            &validator_one_rpc,
+        ];
+        let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+        client.exp_string("Transaction is valid.")?;
+        client.assert_success();
+    }
+
+    // 3. Submit a self-bond below the minimum, it must fail
+    let tx_args = vec![
+        "bond",
+        "--validator",
+        below_validator,
+        "--amount",
+        "999.999999",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string(r#""code": "3"#)?;
+    client.assert_success();
+
+    // 4. Submit a self-bond at the minimum
+    let tx_args = vec![
+        "bond",
+        "--validator",
+        min_validator,
+        "--amount",
+        "1000",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 5. Wait for the pipeline epoch when the validators' voting power could
+    // be updated
+    let epoch = get_epoch(&test, &validator_one_rpc)?;
+    let earliest_update_epoch = epoch + pipeline_len;
+    let start = Instant::now();
+    let loop_timeout = Duration::new(20, 0);
+    loop {
+        if Instant::now().duration_since(start) > loop_timeout {
+            panic!("Timed out waiting for epoch: {}", earliest_update_epoch);
+        }
+        let epoch = get_epoch(&test, &validator_one_rpc)?;
+        if epoch >= earliest_update_epoch {
+            break;
+        }
+    }
+
+    // 6. Check that only the validator at the minimum is active
+    for (validator, expected) in [
+        (below_validator, "is inactive, voting power: 0"),
+        (min_validator, "is active, voting power: 1"),
+    ] {
+        let query_args = vec![
+            "voting-power",
+            "--validator",
+            validator,
+            "--ledger-address",
+            &validator_one_rpc,
+        ];
+        let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+        client.exp_string(expected)?;
+        client.assert_success();
     }
 
     Ok(())
 }
 
+/// Test that the bonds to a validator cannot raise its stake above the
+/// maximum validator stake.
+///
+/// 1. Run the ledger node with a maximum validator stake
+/// 2. Initialize a new validator and transfer some XAN to it
+/// 3. Submit a self-bond and a delegation up to the maximum
+/// 4. Submit a delegation and a self-bond above the maximum, they must fail
+/// 5. Wait for the pipeline epoch
+/// 6. Check that the validator's voting power is from the maximum stake
+#[test]
+fn pos_max_validator_stake() -> Result<()> {
+    let pipeline_len = 1;
+    // At the stake of the genesis validator
+    let max_validator_stake = 200000;
+    let test = setup::network(
+        |genesis| {
+            let parameters = ParametersConfig {
+                min_num_of_blocks: 2,
+                min_duration: 1,
+                max_expected_time_per_block: 1,
+                ..genesis.parameters
+            };
+            let pos_params = PosParamsConfig {
+                pipeline_len,
+                unbonding_len: 2,
+                max_validator_stake: Some(max_validator_stake),
+                ..genesis.pos_params
+            };
+            GenesisConfig {
+                parameters,
+                pos_params,
+                ..genesis
+            }
+        },
+        None,
+    )?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Initialize a new validator and transfer some XAN to it
+    let validator = "max-stake-validator";
+    let tx_args = vec![
+        "init-validator",
+        "--alias",
+        validator,
+        "--source",
+        BERTHA,
+        "--unsafe-dont-encrypt",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    let tx_args = vec![
+        "transfer",
+        "--source",
+        BERTHA,
+        "--target",
+        validator,
+        "--token",
+        XAN,
+        "--amount",
+        "300000",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 3. Submit a self-bond and a delegation up to the maximum
+    for (source, amount) in [(validator, "150000"), (ALBERT, "50000")] {
+        let tx_args = vec![
+            "bond",
+            "--validator",
+            validator,
+            "--source",
+            source,
+            "--amount",
+            amount,
+            "--fee-amount",
+            "0",
+            "--gas-limit",
+            "0",
+            "--fee-token",
+            XAN,
+            "--ledger-address",
+            &validator_one_rpc,
+        ];
+        let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+        client.exp_string("Transaction is valid.")?;
+        client.assert_success();
+    }
+
+    // 4. Submit a delegation and a self-bond above the maximum, they must
+    // fail
+    for source in [ALBERT, validator] {
+        let tx_args = vec![
+            "bond",
+            "--validator",
+            validator,
+            "--source",
+            source,
+            "--amount",
+            "0.000001",
+            "--fee-amount",
+            "0",
+            "--gas-limit",
+            "0",
+            "--fee-token",
+            XAN,
+            "--ledger-address",
+            &validator_one_rpc,
+        ];
+        let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+        client.exp_string(r#""code": "3"#)?;
+        client.assert_success();
+        ledger.exp_string("above the maximum validator stake")?;
+    }
+
+    // 5. Wait for the pipeline epoch when the validator's voting power could
+    // be updated
+    let epoch = get_epoch(&test, &validator_one_rpc)?;
+    let earliest_update_epoch = epoch + pipeline_len;
+    let start = Instant::now();
+    let loop_timeout = Duration::new(20, 0);
+    loop {
+        if Instant::now().duration_since(start) > loop_timeout {
+            panic!("Timed out waiting for epoch: {}", earliest_update_epoch);
+        }
+        let epoch = get_epoch(&test, &validator_one_rpc)?;
+        if epoch >= earliest_update_epoch {
+            break;
+        }
+    }
+
+    // 6. Check that the validator's voting power is from the maximum stake
+    let query_args = vec![
+        "voting-power",
+        "--validator",
+        validator,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    client.exp_string("is active, voting power: 200")?;
+    client.assert_success();
+
+    Ok(())
+}
+
+/// Test that the active validator set is capped at the maximum number of
+/// validator slots and that a new validator with a higher voting power
+/// displaces the active validator with the lowest voting power.
+///
+/// 1. Run the ledger node with 2 validator slots
+/// 2. Initialize two new validators and transfer some XAN to them
+/// 3. Submit a self-bond for the first new validator and wait for the
+///    pipeline epoch
+/// 4. Check that it fills the last validator slot
+/// 5. Submit a greater self-bond for the second new validator and wait for
+///    the pipeline epoch
+/// 6. Check that the second validator displaced the first one, which no
+///    longer has any voting power in consensus
+#[test]
+fn pos_max_validator_slots() -> Result<()> {
+    let pipeline_len = 1;
+    let test = setup::network(
+        |genesis| {
+            let parameters = ParametersConfig {
+                min_num_of_blocks: 2,
+                min_duration: 1,
+                max_expected_time_per_block: 1,
+                ..genesis.parameters
+            };
+            let pos_params = PosParamsConfig {
+                pipeline_len,
+                unbonding_len: 2,
+                max_validator_slots: 2,
+                ..genesis.pos_params
+            };
+            GenesisConfig {
+                parameters,
+                pos_params,
+                ..genesis
+            }
+        },
+        None,
+    )?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    let wait_for_pipeline_epoch = || -> Result<()> {
+        let epoch = get_epoch(&test, &validator_one_rpc)?;
+        let earliest_update_epoch = epoch + pipeline_len;
+        let start = Instant::now();
+        let loop_timeout = Duration::new(20, 0);
+        loop {
+            if Instant::now().duration_since(start) > loop_timeout {
+                panic!(
+                    "Timed out waiting for epoch: {}",
+                    earliest_update_epoch
+                );
+            }
+            let epoch = get_epoch(&test, &validator_one_rpc)?;
+            if epoch >= earliest_update_epoch {
+                return Ok(());
+            }
+        }
+    };
+    let check_voting_power = |validator: &str, expected: &str| -> Result<()> {
+        let query_args = vec![
+            "voting-power",
+            "--validator",
+            validator,
+            "--ledger-address",
+            &validator_one_rpc,
+        ];
+        let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+        client.exp_string(expected)?;
+        client.assert_success();
+        Ok(())
+    };
+
+    // 2. Initialize two new validators and transfer some XAN to them
+    let low_validator = "low-power-validator";
+    let high_validator = "high-power-validator";
+    for validator in [low_validator, high_validator] {
+        let tx_args = vec![
+            "init-validator",
+            "--alias",
+            validator,
+            "--source",
+            BERTHA,
+            "--unsafe-dont-encrypt",
+            "--fee-amount",
+            "0",
+            "--gas-limit",
+            "0",
+            "--fee-token",
+            XAN,
+            "--ledger-address",
+            &validator_one_rpc,
+        ];
+        let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+        client.exp_string("Transaction is valid.")?;
+        client.assert_success();
+
+        let tx_args = vec![
+            "transfer",
+            "--source",
+            BERTHA,
+            "--target",
+            validator,
+            "--token",
+            XAN,
+            "--amount",
+            "4000",
+            "--fee-amount",
+            "0",
+            "--gas-limit",
+            "0",
+            "--fee-token",
+            XAN,
+            "--ledger-address",
+            &validator_one_rpc,
+        ];
+        let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+        client.exp_string("Transaction is valid.")?;
+        client.assert_success();
+    }
+
+    // 3. Submit a self-bond for the first new validator and wait for the
+    // pipeline epoch
+    let tx_args = vec![
+        "bond",
+        "--validator",
+        low_validator,
+        "--amount",
+        "1000",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+    wait_for_pipeline_epoch()?;
+
+    // 4. Check that it fills the last validator slot
+    check_voting_power(low_validator, "is active, voting power: 1")?;
+    check_voting_power(high_validator, "is inactive, voting power: 0")?;
+
+    // 5. Submit a greater self-bond for the second new validator and wait for
+    // the pipeline epoch
+    let tx_args = vec![
+        "bond",
+        "--validator",
+        high_validator,
+        "--amount",
+        "3000",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+    wait_for_pipeline_epoch()?;
+
+    // 6. Check that the second validator displaced the first one. An inactive
+    // validator is still bonded, but it's removed from the consensus
+    // validator set and it earns no rewards.
+    check_voting_power(high_validator, "is active, voting power: 3")?;
+    check_voting_power(low_validator, "is inactive, voting power: 1")?;
+
+    Ok(())
+}
+
+/// Test that staking rewards accrue to a bond and that they can only be
+/// claimed once.
+///
+/// 1. Run the ledger node
+/// 2. Submit a self-bond for the genesis validator
+/// 3. Wait for the epoch in which the bond's rewards have been distributed
+/// 4. Query the unclaimed rewards since the bond's epoch
+/// 5. Claim the rewards
+/// 6. Claim the rewards again, there must be none left
+#[test]
+fn pos_claim_rewards() -> Result<()> {
+    let pipeline_len = 1;
+    let test = setup::network(
+        |genesis| {
+            // The epochs are long enough to claim the rewards twice within
+            // the same epoch
+            let parameters = ParametersConfig {
+                min_num_of_blocks: 10,
+                min_duration: 1,
+                max_expected_time_per_block: 1,
+                ..genesis.parameters
+            };
+            let pos_params = PosParamsConfig {
+                pipeline_len,
+                unbonding_len: 2,
+                ..genesis.pos_params
+            };
+            GenesisConfig {
+                parameters,
+                pos_params,
+                ..genesis
+            }
+        },
+        None,
+    )?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Submit a self-bond for the genesis validator
+    let bond_epoch = get_epoch(&test, &validator_one_rpc)?;
+    let tx_args = vec![
+        "bond",
+        "--validator",
+        "validator-0",
+        "--amount",
+        "10000.0",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client =
+        run_as!(test, Who::Validator(0), Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 3. Wait for the epoch in which the bond's rewards have been distributed
+    let rewarded_epoch = bond_epoch + pipeline_len;
+    let start = Instant::now();
+    let loop_timeout = Duration::new(60, 0);
+    loop {
+        if Instant::now().duration_since(start) > loop_timeout {
+            panic!("Timed out waiting for epoch: {}", rewarded_epoch + 1);
+        }
+        let epoch = get_epoch(&test, &validator_one_rpc)?;
+        if epoch > rewarded_epoch {
+            break;
+        }
+    }
+
+    // 4. Query the unclaimed rewards since the bond's epoch
+    let since_epoch = rewarded_epoch.to_string();
+    let query_args = vec![
+        "query-rewards",
+        "--validator",
+        "validator-0",
+        "--since-epoch",
+        &since_epoch,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    client.exp_string(&format!("Earned in epoch {}", rewarded_epoch))?;
+    client.exp_string("Unclaimed rewards total:")?;
+    client.assert_success();
+
+    // 5. Claim the rewards
+    let tx_args = vec![
+        "claim-rewards",
+        "--validator",
+        "validator-0",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run_as!(
+        test,
+        Who::Validator(0),
+        Bin::Client,
+        tx_args.clone(),
+        Some(40)
+    )?;
+    client.exp_string("Claiming unclaimed rewards:")?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 6. Claim the rewards again, there must be none left
+    let mut client =
+        run_as!(test, Who::Validator(0), Bin::Client, tx_args, Some(40))?;
+    client.exp_string("There are no unclaimed rewards to claim")?;
+    client.assert_failure();
+
+    Ok(())
+}
+
+/// Test that a validator's commission rate can be set on initialization and
+/// that a change larger than the maximum change per epoch is rejected.
+///
+/// 1. Run the ledger node
+/// 2. Initialize a new validator with a commission rate
+/// 3. Query the validator's commission rate
+/// 4. Submit a commission rate change that is too large, it must fail
+/// 5. Submit a commission rate change within the limit
+/// 6. Query the pending commission rate
+#[test]
+fn pos_validator_commission_rate() -> Result<()> {
+    let pipeline_len = 1;
+    let test = setup::network(
+        |genesis| {
+            let parameters = ParametersConfig {
+                min_num_of_blocks: 2,
+                min_duration: 1,
+                max_expected_time_per_block: 1,
+                ..genesis.parameters
+            };
+            let pos_params = PosParamsConfig {
+                pipeline_len,
+                unbonding_len: 2,
+                max_commission_rate_change: 100,
+                ..genesis.pos_params
+            };
+            GenesisConfig {
+                parameters,
+                pos_params,
+                ..genesis
+            }
+        },
+        None,
+    )?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Initialize a new validator with a commission rate
+    let new_validator = "new-validator";
+    let tx_args = vec![
+        "init-validator",
+        "--alias",
+        new_validator,
+        "--source",
+        BERTHA,
+        "--unsafe-dont-encrypt",
+        "--commission-rate",
+        "1000",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 3. Query the validator's commission rate
+    let query_args = vec![
+        "query-commission",
+        "--validator",
+        new_validator,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    client.exp_string("commission rate: 1000‱")?;
+    client.assert_success();
+
+    // 4. Submit a commission rate change that is too large, it must fail
+    let tx_args = vec![
+        "change-commission-rate",
+        "--validator",
+        new_validator,
+        "--commission-rate",
+        "5000",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("is greater than the maximum change per epoch")?;
+    client.assert_failure();
+
+    // 5. Submit a commission rate change within the limit
+    let tx_args = vec![
+        "change-commission-rate",
+        "--validator",
+        new_validator,
+        "--commission-rate",
+        "1100",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 6. Query the pending commission rate
+    let query_args = vec![
+        "query-commission",
+        "--validator",
+        new_validator,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    client.exp_string(": 1100‱")?;
+    client.assert_success();
+
+    Ok(())
+}
+
+/// Test that a validator's metadata can be set on initialization, edited and
+/// queried back.
+///
+/// 1. Run the ledger node
+/// 2. Initialize a new validator with a moniker
+/// 3. Query the validator's moniker
+/// 4. Edit the validator's website
+/// 5. Query the validator's metadata again
+#[test]
+fn pos_validator_metadata() -> Result<()> {
+    let test = setup::single_node_net()?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Initialize a new validator with a moniker
+    let new_validator = "new-validator";
+    let tx_args = vec![
+        "init-validator",
+        "--alias",
+        new_validator,
+        "--source",
+        BERTHA,
+        "--unsafe-dont-encrypt",
+        "--moniker",
+        "Stakey McStakeface",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 3. Query the validator's moniker
+    let query_args = vec![
+        "query-validator",
+        "--validator",
+        new_validator,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    client.exp_string("Moniker: Stakey McStakeface")?;
+    client.exp_string("Website: not set")?;
+    client.assert_success();
+
+    // 4. Edit the validator's website
+    let tx_args = vec![
+        "edit-validator",
+        "--validator",
+        new_validator,
+        "--website",
+        "https://stakey.example",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 5. Query the validator's metadata again, the moniker is unchanged
+    let query_args = vec![
+        "query-validator",
+        "--validator",
+        new_validator,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    client.exp_string("Moniker: Stakey McStakeface")?;
+    client.exp_string("Website: https://stakey.example")?;
+    client.assert_success();
+
+    Ok(())
+}
+
+/// Test that a validator that is not jailed cannot be unjailed.
+///
+/// Jailing a validator requires evidence of its byzantine behavior, which a
+/// single node network cannot produce, so the unjailing of a jailed validator
+/// is covered by the PoS validation tests.
+///
+/// 1. Run the ledger node
+/// 2. Submit an unjail of the genesis validator, the client rejects it
+/// 3. Submit it with `--force`, the tx is rejected
+#[test]
+fn pos_unjail_validator() -> Result<()> {
+    let test = setup::single_node_net()?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Submit an unjail of the genesis validator
+    let tx_args = vec![
+        "unjail-validator",
+        "--validator",
+        "validator-0",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run_as!(
+        test,
+        Who::Validator(0),
+        Bin::Client,
+        tx_args.clone(),
+        Some(40)
+    )?;
+    client.exp_regex("The validator .* is not jailed")?;
+    client.assert_failure();
+
+    // 3. Submit it with `--force`
+    let tx_args = vec![tx_args, vec!["--force"]].concat();
+    let mut client =
+        run_as!(test, Who::Validator(0), Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is invalid.")?;
+    client.assert_success();
+
+    Ok(())
+}
+
+/// Test that multiple txs submitted in the same block all get the tx result.
+///
+/// In this test we:
+/// 1. Run the ledger node with 10s consensus timeout
+/// 2. Spawn threads each submitting token transfer tx
+#[test]
+fn ledger_many_txs_in_a_block() -> Result<()> {
+    let test = Arc::new(setup::network(
+        |genesis| genesis,
+        // Set 10s consensus timeout to have more time to submit txs
+        Some("10s"),
+    )?);
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(*test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    // Wait to commit a block
+    ledger.exp_regex(r"Committed block hash.*, height: [0-9]+")?;
+
+    let validator_one_rpc = Arc::new(get_actor_rpc(&test, &Who::Validator(0)));
+
+    // A token transfer tx args
+    let tx_args = Arc::new(vec![
+        "transfer",
+        "--source",
+        BERTHA,
+        "--target",
+        ALBERT,
+        "--token",
+        XAN,
+        "--amount",
+        "10.1",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+    ]);
+
+    // 2. Spawn threads each submitting token transfer tx
+    // We collect to run the threads in parallel.
+    #[allow(clippy::needless_collect)]
+    let tasks: Vec<std::thread::JoinHandle<_>> = (0..3)
+        .into_iter()
+        .map(|_| {
+            let test = Arc::clone(&test);
+            let validator_one_rpc = Arc::clone(&validator_one_rpc);
+            let tx_args = Arc::clone(&tx_args);
+            std::thread::spawn(move || {
+                let mut args = (*tx_args).clone();
+                args.push(&*validator_one_rpc);
+                let mut client = run!(*test, Bin::Client, args, Some(40))?;
+                if !cfg!(feature = "ABCI") {
+                    client.exp_string("Transaction accepted")?;
+                }
+                client.exp_string("Transaction applied")?;
+                client.exp_string("Transaction is valid.")?;
+                client.assert_success();
+                let res: Result<()> = Ok(());
+                res
+            })
+        })
+        .collect();
+    for task in tasks.into_iter() {
+        task.join().unwrap()?;
+    }
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Submit a valid proposal
+/// 3. Query the proposal
+/// 4. Query token balance (submitted funds)
+/// 5. Query governance address balance
+/// 6. Submit an invalid proposal
+/// 7. Check invalid proposal was not accepted
+/// 8. Query token balance (funds shall not be submitted)
+#[test]
+fn proposal_submission() -> Result<()> {
+    let test = setup::network(|genesis| genesis, None)?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    // 2. Submit valid proposal
+    let valid_proposal_json_path =
+        test.base_dir.path().join("valid_proposal.json");
+    let albert = find_address(&test, ALBERT)?;
+    let valid_proposal_json = json!(
+        {
+            "content": {
+                "title": "TheTitle",
+                "authors": "test@test.com",
+                "discussions-to": "www.github.com/anoma/aip/1",
+                "created": "2022-03-10T08:54:37Z",
+                "license": "MIT",
+                "abstract": "Ut convallis eleifend orci vel venenatis. Duis vulputate metus in lacus sollicitudin vestibulum. Suspendisse vel velit ac est consectetur feugiat nec ac urna. Ut faucibus ex nec dictum fermentum. Morbi aliquet purus at sollicitudin ultrices. Quisque viverra varius cursus. Praesent sed mauris gravida, pharetra turpis non, gravida eros. Nullam sed ex justo. Ut at placerat ipsum, sit amet rhoncus libero. Sed blandit non purus non suscipit. Phasellus sed quam nec augue bibendum bibendum ut vitae urna. Sed odio diam, ornare nec sapien eget, congue viverra enim.",
+                "motivation": "Ut convallis eleifend orci vel venenatis. Duis vulputate metus in lacus sollicitudin vestibulum. Suspendisse vel velit ac est consectetur feugiat nec ac urna. Ut faucibus ex nec dictum fermentum. Morbi aliquet purus at sollicitudin ultrices.",
+                "details": "Ut convallis eleifend orci vel venenatis. Duis vulputate metus in lacus sollicitudin vestibulum. Suspendisse vel velit ac est consectetur feugiat nec ac urna. Ut faucibus ex nec dictum fermentum. Morbi aliquet purus at sollicitudin ultrices. Quisque viverra varius cursus. Praesent sed mauris gravida, pharetra turpis non, gravida eros.",
+                "requires": "2"
+            },
+            "author": albert,
+            "voting_start_epoch": 9999,
+            "voting_end_epoch": 10002,
+            "grace_epoch": 10009
+        }
+    );
+    generate_proposal_json(
+        valid_proposal_json_path.clone(),
+        valid_proposal_json,
+    );
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    let submit_proposal_args = vec![
+        "init-proposal",
+        "--data-path",
+        valid_proposal_json_path.to_str().unwrap(),
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+
+    let mut client = run!(test, Bin::Client, submit_proposal_args, Some(15))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 3. Query the proposal
+
+    let proposal_query_args = vec![
+        "query-proposal",
+        "--proposal-id",
+        "0",
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+
+    let mut client = run!(test, Bin::Client, proposal_query_args, Some(15))?;
+    client.exp_string("Proposal: 0")?;
+    client.assert_success();
+
+    // 4. Query token balance proposal author (submitted funds)
+    let query_balance_args = vec![
+        "balance",
+        "--owner",
+        ALBERT,
+        "--token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+
+    let mut client = run!(test, Bin::Client, query_balance_args, Some(15))?;
+    client.exp_string("XAN: 999500")?;
+    client.assert_success();
+
+    // 5. Query token balance governance
+    let query_balance_args = vec![
+        "balance",
+        "--owner",
+        GOVERNANCE_ADDRESS,
+        "--token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+
+    let mut client = run!(test, Bin::Client, query_balance_args, Some(15))?;
+    client.exp_string("XAN: 500")?;
+    client.assert_success();
+
+    // 6. Submit an invalid proposal
+    // proposal is invalid due to voting_end_epoch - voting_start_epoch < 3
+    let invalid_proposal_json_path =
+        test.base_dir.path().join("invalid_proposal.json");
+    let albert = find_address(&test, ALBERT)?;
+    let invalid_proposal_json = json!(
+        {
+            "content": {
+                "title": "TheTitle",
+                "authors": "test@test.com",
+                "discussions-to": "www.github.com/anoma/aip/1",
+                "created": "2022-03-10T08:54:37Z",
+                "license": "MIT",
+                "abstract": "Ut convallis eleifend orci vel venenatis. Duis vulputate metus in lacus sollicitudin vestibulum. Suspendisse vel velit ac est consectetur feugiat nec ac urna. Ut faucibus ex nec dictum fermentum. Morbi aliquet purus at sollicitudin ultrices. Quisque viverra varius cursus. Praesent sed mauris gravida, pharetra turpis non, gravida eros. Nullam sed ex justo. Ut at placerat ipsum, sit amet rhoncus libero. Sed blandit non purus non suscipit. Phasellus sed quam nec augue bibendum bibendum ut vitae urna. Sed odio diam, ornare nec sapien eget, congue viverra enim.",
+                "motivation": "Ut convallis eleifend orci vel venenatis. Duis vulputate metus in lacus sollicitudin vestibulum. Suspendisse vel velit ac est consectetur feugiat nec ac urna. Ut faucibus ex nec dictum fermentum. Morbi aliquet purus at sollicitudin ultrices.",
+                "details": "Ut convallis eleifend orci vel venenatis. Duis vulputate metus in lacus sollicitudin vestibulum. Suspendisse vel velit ac est consectetur feugiat nec ac urna. Ut faucibus ex nec dictum fermentum. Morbi aliquet purus at sollicitudin ultrices. Quisque viverra varius cursus. Praesent sed mauris gravida, pharetra turpis non, gravida eros.",
+                "requires": "2"
+            },
+            "author": albert,
+            "voting_start_epoch": 9999,
+            "voting_end_epoch": 10000,
+            "grace_epoch": 10009
+        }
+    );
+    generate_proposal_json(
+        invalid_proposal_json_path.clone(),
+        invalid_proposal_json,
+    );
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    let submit_proposal_args = vec![
+        "init-proposal",
+        "--data-path",
+        invalid_proposal_json_path.to_str().unwrap(),
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+
+    let mut client = run!(test, Bin::Client, submit_proposal_args, Some(15))?;
+    client.exp_string("Transaction is invalid.")?;
+    client.assert_success();
+
+    // 7. Check invalid proposal was not accepted
+    let proposal_query_args = vec![
+        "query-proposal",
+        "--proposal-id",
+        "1",
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+
+    let mut client = run!(test, Bin::Client, proposal_query_args, Some(15))?;
+    client.exp_string("No valid proposal was found with id 1")?;
+    client.assert_success();
+
+    // 8. Query token balance (funds shall not be submitted)
+    let query_balance_args = vec![
+        "balance",
+        "--owner",
+        ALBERT,
+        "--token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+
+    let mut client = run!(test, Bin::Client, query_balance_args, Some(15))?;
+    client.exp_string("XAN: 999500")?;
+    client.assert_success();
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Submit a proposal with code
+/// 3. Query the proposal's code and check that the written code is identical
+/// 4. Submit a proposal without code
+/// 5. Query the proposal's code, there is none
+#[test]
+fn proposal_code_query() -> Result<()> {
+    let test = setup::network(|genesis| genesis, None)?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+    let albert = find_address(&test, ALBERT)?;
+    let submit_proposal = |name: &str, proposal_code: Option<Vec<u8>>| {
+        let proposal_json_path = test.base_dir.path().join(name);
+        let mut proposal_json = json!(
+            {
+                "content": {
+                    "title": "TheTitle",
+                    "authors": "test@test.com",
+                    "discussions-to": "www.github.com/anoma/aip/1",
+                    "created": "2022-03-10T08:54:37Z",
+                    "license": "MIT",
+                    "abstract": "Ut convallis eleifend orci vel venenatis.",
+                    "motivation": "Ut convallis eleifend orci vel venenatis.",
+                    "details": "Ut convallis eleifend orci vel venenatis.",
+                    "requires": "2"
+                },
+                "author": albert,
+                "voting_start_epoch": 9999,
+                "voting_end_epoch": 10002,
+                "grace_epoch": 10009
+            }
+        );
+        if let Some(proposal_code) = proposal_code {
+            proposal_json["proposal_code"] = json!(proposal_code);
+        }
+        generate_proposal_json(proposal_json_path.clone(), proposal_json);
+
+        let submit_proposal_args = vec![
+            "init-proposal",
+            "--data-path",
+            proposal_json_path.to_str().unwrap(),
+            "--ledger-address",
+            &validator_one_rpc,
+        ];
+        let mut client =
+            run!(test, Bin::Client, submit_proposal_args, Some(15))?;
+        client.exp_string("Transaction is valid.")?;
+        client.assert_success();
+        Ok::<(), color_eyre::eyre::Error>(())
+    };
+
+    // 2. Submit a proposal with code, a minimal wasm module
+    let proposal_code = b"\0asm\x01\0\0\0".to_vec();
+    submit_proposal("proposal_with_code.json", Some(proposal_code.clone()))?;
+
+    // 3. Query the proposal's code
+    let code_path = test.base_dir.path().join("proposal_code.wasm");
+    let query_code_args = vec![
+        "query-proposal-code",
+        "--proposal-id",
+        "0",
+        "--out",
+        code_path.to_str().unwrap(),
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_code_args, Some(15))?;
+    client.exp_string(&format!(
+        "Proposal 0 code hash: {}, size: {} bytes",
+        Hash::sha256(&proposal_code),
+        proposal_code.len()
+    ))?;
+    client.assert_success();
+    assert_eq!(std::fs::read(&code_path)?, proposal_code);
+
+    // 4. Submit a proposal without code
+    submit_proposal("proposal_without_code.json", None)?;
+
+    // 5. The proposal has no code
+    let query_code_args = vec![
+        "query-proposal-code",
+        "--proposal-id",
+        "1",
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_code_args, Some(15))?;
+    client.exp_string("Proposal 1 has no code.")?;
+    client.assert_success();
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Verify the finalized genesis file of a network, it should pass
+/// 2. Break the genesis file in a few different ways
+/// 3. Verify the broken genesis file, it should report every fault and fail
+#[test]
+fn verify_genesis() -> Result<()> {
+    let test = setup::single_node_net()?;
+
+    // 1. Verify the finalized genesis file
+    let genesis_path = test
+        .base_dir
+        .path()
+        .join(format!("{}.toml", test.net.chain_id.as_str()));
+    let genesis_path_str = genesis_path.to_string_lossy();
+    let verify_args =
+        vec!["utils", "verify-genesis", "--path", &genesis_path_str];
+    let mut client = run!(test, Bin::Client, verify_args, Some(40))?;
+    client.exp_string("is valid")?;
+    client.assert_success();
+
+    // 2. Break the genesis file
+    let mut genesis = genesis_config::open_genesis_config(&genesis_path);
+    let validator = genesis.validator.get_mut("validator-0").unwrap();
+    validator.address = Some("not-an-address".to_owned());
+    let established = genesis.established.as_mut().unwrap();
+    let christel_address = established[CHRISTEL].address.clone();
+    established.get_mut(BERTHA).unwrap().address = christel_address;
+    established.get_mut(ALBERT).unwrap().vp = Some("vp_missing".to_owned());
+    genesis
+        .token
+        .as_mut()
+        .unwrap()
+        .get_mut(XAN)
+        .unwrap()
+        .balances
+        .as_mut()
+        .unwrap()
+        .insert("Nobody".to_owned(), 1);
+    genesis.wasm.get_mut("vp_token").unwrap().sha256 =
+        Some(HexString("00".repeat(32)));
+    genesis.pos_params.unbonding_len = 0;
+    genesis.pos_params.duplicate_vote_slash_rate = 20_000;
+    let broken_genesis_path = test.base_dir.path().join("broken-genesis.toml");
+    genesis_config::write_genesis_config(&genesis, &broken_genesis_path);
+
+    // 3. Verify the broken genesis file
+    let broken_genesis_path = broken_genesis_path.to_string_lossy();
+    let verify_args =
+        vec!["utils", "verify-genesis", "--path", &broken_genesis_path];
+    let mut client = run!(test, Bin::Client, verify_args, Some(40))?;
+    client.exp_string("found 7 problem(s)")?;
+    client.exp_string("validator validator-0 has an invalid address")?;
+    client.exp_string(
+        "established account Albert refers to an unknown VP WASM vp_missing",
+    )?;
+    client.exp_string(
+        "is used by both established account Bertha and established account \
+         Christel",
+    )?;
+    client
+        .exp_string("token XAN has a balance for an unknown account Nobody")?;
+    client.exp_string("VP WASM vp_token sha256 mismatch")?;
+    client.exp_string("Invalid parameter pos_params.unbonding_len")?;
+    client
+        .exp_string("Invalid parameter pos_params.duplicate_vote_slash_rate")?;
+    client.assert_failure();
+
+    Ok(())
+}
+
+/// Test that the parameters queried from a running ledger match the ones
+/// configured in genesis.
+#[test]
+fn query_protocol_parameters() -> Result<()> {
+    let test = setup::network(
+        |genesis| {
+            let parameters = ParametersConfig {
+                min_num_of_blocks: 4,
+                min_duration: 2,
+                max_expected_time_per_block: 3,
+                ..genesis.parameters
+            };
+            let pos_params = PosParamsConfig {
+                pipeline_len: 1,
+                unbonding_len: 5,
+                ..genesis.pos_params
+            };
+            GenesisConfig {
+                parameters,
+                pos_params,
+                ..genesis
+            }
+        },
+        None,
+    )?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Query the parameters
+    let query_args =
+        vec!["query-params", "--ledger-address", &validator_one_rpc];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    client.exp_string("Min. number of blocks in an epoch: 4")?;
+    client.exp_string("Min. duration of an epoch: 2s")?;
+    client.exp_string("Max. expected time per block: 3s")?;
+    client.exp_string("Pipeline length: 1")?;
+    client.exp_string("Unbonding length: 5")?;
+    // as setup in `genesis/e2e-tests-single-node.toml`
+    client.exp_string("Jail duration: 6")?;
+    client.exp_string("Min. proposal fund: 500")?;
+    client.exp_string("Max. proposal code size: 300")?;
+    client.exp_string("Min. proposal period: 3")?;
+    client.exp_string("Max. proposal content size: 5000")?;
+    client.exp_string("Min. proposal grace epochs: 6")?;
+    client.assert_success();
+
+    // 3. Query a parameter with the helper used by the other tests
+    let unbonding_len =
+        get_parameter(&test, &validator_one_rpc, "Unbonding length")?;
+    assert_eq!(unbonding_len, 5);
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Build and sign a transfer without connecting to the ledger and write it
+///    to a file
+/// 3. Broadcast the signed transfer from the file
+/// 4. Check that the target's balance changed
+#[test]
+fn offline_signed_transfer() -> Result<()> {
+    let test = setup::single_node_net()?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Build and sign a transfer into a file, without a ledger address
+    let tx_path = test.base_dir.path().join("transfer.tx");
+    let tx_path = tx_path.to_string_lossy();
+    let tx_args = vec![
+        "transfer",
+        "--source",
+        BERTHA,
+        "--target",
+        CHRISTEL,
+        "--token",
+        XAN,
+        "--amount",
+        "10.1",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--signing-key",
+        BERTHA_KEY,
+        "--raw-tx-out",
+        &tx_path,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Signed transaction written to")?;
+    client.assert_success();
+
+    // 3. Broadcast the signed transfer
+    let broadcast_args = vec![
+        "broadcast-tx",
+        "--in",
+        &tx_path,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, broadcast_args, Some(40))?;
+    if !cfg!(feature = "ABCI") {
+        client.exp_string("Transaction accepted")?;
+    }
+    client.exp_string("Transaction applied")?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 4. Check that the target's balance changed, its genesis balance is
+    // set in `genesis/e2e-tests-single-node.toml`
+    let query_args = vec![
+        "balance",
+        "--owner",
+        CHRISTEL,
+        "--token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    client.exp_string("XAN: 1000010.1")?;
+    client.assert_success();
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Dry run a transfer with `--explain`
+/// 3. Check that the VPs of the source, the target and the token were invoked
+///    and all accepted the transfer
+#[test]
+fn dry_run_explain_invoked_vps() -> Result<()> {
+    let test = setup::single_node_net()?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Dry run a transfer with `--explain`
+    let tx_args = vec![
+        "transfer",
+        "--source",
+        BERTHA,
+        "--target",
+        ALBERT,
+        "--token",
+        XAN,
+        "--amount",
+        "10.1",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--dry-run",
+        "--explain",
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.exp_string("Invoked validity predicates:")?;
+    let invoked_vps = client.exp_eof()?;
+
+    // 3. Check that the source, target and token VPs were invoked
+    for alias in [BERTHA, ALBERT, XAN] {
+        let address = find_address(&test, alias)?;
+        let expected = format!("{}: accepted", address.encode());
+        assert!(
+            invoked_vps.contains(&expected),
+            "Expected \"{}\" in the invoked VPs:\n{}",
+            expected,
+            invoked_vps
+        );
+    }
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Run the ledger node with a VP whitelist containing only the user VP
+/// 2. Initialize an account with the whitelisted user VP
+/// 3. Try to initialize an account with a VP that is not whitelisted, it must
+///    be rejected by the client
+/// 4. Force the account with the VP that is not whitelisted, it must be
+///    rejected by the ledger
+#[test]
+fn vp_whitelist_init_account() -> Result<()> {
+    let vp_user = wasm_abs_path(VP_USER_WASM);
+    let vp_user_hash = Hash::sha256(std::fs::read(&vp_user)?);
+    let vp_user = vp_user.to_string_lossy();
+    let vp_always_true = wasm_abs_path(VP_ALWAYS_TRUE_WASM);
+    let vp_always_true = vp_always_true.to_string_lossy();
+
+    let test = setup::network(
+        |genesis| {
+            let parameters = ParametersConfig {
+                vp_whitelist: Some(vec![vp_user_hash.to_string()]),
+                ..genesis.parameters
+            };
+            GenesisConfig {
+                parameters,
+                ..genesis
+            }
+        },
+        None,
+    )?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Initialize an account with the whitelisted user VP
+    let tx_args = vec![
+        "init-account",
+        "--source",
+        BERTHA,
+        "--public-key",
+        // Value obtained from `anoma::types::key::ed25519::tests::gen_keypair`
+        "001be519a321e29020fa3cbfbfd01bd5e92db134305609270b71dace25b5a21168",
+        "--code-path",
+        &vp_user,
+        "--alias",
+        "Whitelisted-Account",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 3. Try to initialize an account with a VP that is not whitelisted
+    let tx_args = vec![
+        "init-account",
+        "--source",
+        BERTHA,
+        "--public-key",
+        // Value obtained from `anoma::types::key::ed25519::tests::gen_keypair`
+        "001be519a321e29020fa3cbfbfd01bd5e92db134305609270b71dace25b5a21168",
+        "--code-path",
+        &vp_always_true,
+        "--alias",
+        "Unknown-VP-Account",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args.clone(), Some(40))?;
+    client.exp_string("is not in the VP whitelist of the chain")?;
+    client.assert_failure();
+
+    // 4. Force the account with the VP that is not whitelisted
+    let tx_args = vec![tx_args, vec!["--force"]].concat();
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Error trying to apply a transaction")?;
+    client.exp_string(r#""code": "3"#)?;
+    client.assert_success();
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Update an account's VP to the built-in user VP template by its name
+/// 3. Submit a token transfer from the updated account
+/// 4. Initialize a new account with the user VP template
+#[test]
+fn update_vp_from_template() -> Result<()> {
+    let test = setup::network(|genesis| genesis, None)?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    let txs_args = vec![
+        // 2. Update an account's VP to the user VP template
+        vec![
+            "update",
+            "--address",
+            BERTHA,
+            "--vp-template",
+            "user",
+            "--fee-amount",
+            "0",
+            "--gas-limit",
+            "0",
+            "--fee-token",
+            XAN,
+            "--ledger-address",
+            &validator_one_rpc,
+        ],
+        // 3. Submit a token transfer from the updated account
+        vec![
+            "transfer",
+            "--source",
+            BERTHA,
+            "--target",
+            ALBERT,
+            "--token",
+            XAN,
+            "--amount",
+            "10.1",
+            "--fee-amount",
+            "0",
+            "--gas-limit",
+            "0",
+            "--fee-token",
+            XAN,
+            "--ledger-address",
+            &validator_one_rpc,
+        ],
+        // 4. Initialize a new account with the user VP template
+        vec![
+            "init-account",
+            "--source",
+            BERTHA,
+            "--public-key",
+            // Value obtained from
+            // `anoma::types::key::ed25519::tests::gen_keypair`
+            "001be519a321e29020fa3cbfbfd01bd5e92db134305609270b71dace25b5a21168",
+            "--vp-template",
+            "user",
+            "--alias",
+            "Template-Account",
+            "--fee-amount",
+            "0",
+            "--gas-limit",
+            "0",
+            "--fee-token",
+            XAN,
+            "--ledger-address",
+            &validator_one_rpc,
+        ],
+    ];
+
+    for tx_args in &txs_args {
+        let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+        if !cfg!(feature = "ABCI") {
+            client.exp_string("Transaction accepted")?;
+        }
+        client.exp_string("Transaction applied")?;
+        client.exp_string("Transaction is valid.")?;
+        client.assert_success();
+    }
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Write a file with three addresses, one of them given by its alias
+/// 3. Query the balances of the listed addresses in CSV
+#[test]
+fn query_balances_csv() -> Result<()> {
+    let test = setup::network(|genesis| genesis, None)?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Write a file with three addresses
+    let albert = find_address(&test, ALBERT)?;
+    let bertha = find_address(&test, BERTHA)?;
+    let christel = find_address(&test, CHRISTEL)?;
+    let xan = find_address(&test, XAN)?;
+    let addresses_file = test.base_dir.path().join("addresses.txt");
+    std::fs::write(
+        &addresses_file,
+        format!("{}\n{}\n{}\n", albert, bertha, CHRISTEL),
+    )?;
+
+    // 3. Query the balances of the listed addresses in CSV
+    let query_args = vec![
+        "query-balances",
+        "--addresses-file",
+        addresses_file.to_str().unwrap(),
+        "--token",
+        XAN,
+        "--format",
+        "csv",
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    client.exp_string("owner,token,amount")?;
+    for owner in [&albert, &bertha, &christel] {
+        client.exp_string(&format!("{},{},1000000", owner, xan))?;
+    }
+    client.assert_success();
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Query the metadata of a genesis user account
+/// 3. Query the metadata of a genesis token account
+/// 4. Query the metadata of the genesis validator
+#[test]
+fn query_account_metadata() -> Result<()> {
+    let test = setup::network(|genesis| genesis, None)?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    let queries = vec![
+        // 2. A genesis user account
+        (ALBERT, "Kind: user"),
+        // 3. A genesis token account
+        (XAN, "Kind: token"),
+        // 4. The genesis validator
+        ("validator-0", "Kind: validator"),
+    ];
+    for (address, expected) in queries {
+        let query_args = vec![
+            "query-metadata",
+            "--address",
+            address,
+            "--ledger-address",
+            &validator_one_rpc,
+        ];
+        let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+        client.exp_string(expected)?;
+        client.exp_string("Governance role: none")?;
+        client.assert_success();
+    }
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Submit a token transfer with `--trace` and check that the trace shows
+///    all the stages of the transaction
+#[test]
+fn transfer_with_trace() -> Result<()> {
+    let test = setup::single_node_net()?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Submit a token transfer with `--trace`
+    let tx_args = vec![
+        "transfer",
+        "--source",
+        BERTHA,
+        "--target",
+        ALBERT,
+        "--token",
+        XAN,
+        "--amount",
+        "10.1",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--trace",
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Broadcast: sent to")?;
+    client.exp_string("Mempool: accepted by CheckTx at height")?;
+    if !cfg!(feature = "ABCI") {
+        client.exp_string("Delivered: wrapper included in block")?;
+    }
+    client.exp_string("Applied:")?;
+    client.exp_string("Committed: block")?;
+    client.exp_string("Validity predicates: Accepted:")?;
+    client.assert_success();
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Run the ledger node with shorter epochs for faster progression
+/// 2. Wait for the first epoch after genesis to start
+/// 3. Submit a token transfer that changes a balance
+/// 4. Wait for the next epoch
+/// 5. Query the balance at the epoch before the transfer and the latest
+///    balance
+/// 6. Query the balance at an epoch that hasn't started yet
+#[test]
+fn query_balance_at_epoch() -> Result<()> {
+    let test = setup::network(
+        |genesis| {
+            let parameters = ParametersConfig {
+                min_num_of_blocks: 2,
+                min_duration: 1,
+                max_expected_time_per_block: 1,
+                ..genesis.parameters
+            };
+            GenesisConfig {
+                parameters,
+                ..genesis
+            }
+        },
+        None,
+    )?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Wait for the first epoch after genesis to start
+    let wait_for_epoch = |target: Epoch| -> Result<()> {
+        let start = Instant::now();
+        let loop_timeout = Duration::new(20, 0);
+        loop {
+            if Instant::now().duration_since(start) > loop_timeout {
+                panic!("Timed out waiting for epoch: {}", target);
+            }
+            let epoch = get_epoch(&test, &validator_one_rpc)?;
+            if epoch >= target {
+                return Ok(());
+            }
+        }
+    };
+    wait_for_epoch(Epoch(1))?;
+    let epoch_before_transfer = get_epoch(&test, &validator_one_rpc)?;
+
+    // 3. Submit a token transfer that changes a balance
+    let tx_args = vec![
+        "transfer",
+        "--source",
+        BERTHA,
+        "--target",
+        CHRISTEL,
+        "--token",
+        XAN,
+        "--amount",
+        "10.1",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 4. Wait for the next epoch
+    let epoch_after_transfer = get_epoch(&test, &validator_one_rpc)?;
+    wait_for_epoch(epoch_after_transfer.next())?;
+
+    // 5. Query the balance at the epoch before the transfer and the latest
+    // balance. As setup in `genesis/e2e-tests-single-node.toml`, Christel
+    // starts with 1000000 XAN.
+    let epoch_arg = epoch_before_transfer.to_string();
+    let query_args = vec![
+        "balance",
+        "--owner",
+        CHRISTEL,
+        "--token",
+        XAN,
+        "--epoch",
+        &epoch_arg,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    client.exp_string("XAN: 1000000")?;
+    client.assert_success();
+
+    let query_args = vec![
+        "balance",
+        "--owner",
+        CHRISTEL,
+        "--token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    client.exp_string("XAN: 1000010.1")?;
+    client.assert_success();
+
+    // 6. Query the balance at an epoch that hasn't started yet
+    let query_args = vec![
+        "balance",
+        "--owner",
+        CHRISTEL,
+        "--token",
+        XAN,
+        "--epoch",
+        "1000000",
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    client.exp_string("hasn't started yet")?;
+    client.assert_failure();
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Generate a keypair with `keypair gen` and read its printed public key
+/// 3. Try to generate a keypair with the same alias, it must be refused
+///    without `--force`
+/// 4. Overwrite the keypair with `--force`, printing it as JSON
+/// 5. Initialize an account with the printed public key
+#[test]
+fn keypair_gen_init_account() -> Result<()> {
+    let test = setup::single_node_net()?;
+    let vp_user = wasm_abs_path(VP_USER_WASM);
+    let vp_user = vp_user.to_string_lossy();
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Generate a keypair and read its public key
+    let key_alias = "Generated-Key";
+    let gen_args = vec![
+        "keypair",
+        "gen",
+        "--alias",
+        key_alias,
+        "--unsafe-dont-encrypt",
+    ];
+    let mut wallet = run!(test, Bin::Wallet, &gen_args, Some(20))?;
+    let (_unread, matched) = wallet.exp_regex("Public key: .*\n")?;
+    let public_key = matched.trim().rsplit_once(' ').unwrap().1.to_owned();
+    wallet.assert_success();
+
+    // 3. The same alias must be refused without `--force`
+    let mut wallet = run!(test, Bin::Wallet, &gen_args, Some(20))?;
+    wallet.exp_string("already exists. Use --force to overwrite it.")?;
+    wallet.assert_failure();
+
+    // 4. Overwrite the keypair with `--force`, printing it as JSON
+    let gen_args = vec![gen_args, vec!["--force", "--format", "json"]].concat();
+    let mut wallet = run!(test, Bin::Wallet, &gen_args, Some(20))?;
+    let (_unread, matched) = wallet.exp_regex("\\{.*\\}")?;
+    wallet.assert_success();
+    let json: serde_json::Value = serde_json::from_str(&matched)?;
+    assert_eq!(json["alias"], key_alias.to_lowercase());
+    let new_public_key = json["public_key"].as_str().unwrap().to_owned();
+    assert_ne!(new_public_key, public_key);
+
+    // 5. Initialize an account with the printed public key
+    let tx_args = vec![
+        "init-account",
+        "--source",
+        BERTHA,
+        "--public-key",
+        &new_public_key,
+        "--code-path",
+        &vp_user,
+        "--alias",
+        "Generated-Key-Account",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Dry run a token transfer with a too low `--assert-max-gas`, it must
+///    fail
+/// 3. Dry run the same transfer with a generous `--assert-max-gas`, it must
+///    succeed
+#[test]
+fn dry_run_assert_max_gas() -> Result<()> {
+    let test = setup::single_node_net()?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    let tx_args = |max_gas| {
+        vec![
+            "transfer",
+            "--source",
+            BERTHA,
+            "--target",
+            ALBERT,
+            "--token",
+            XAN,
+            "--amount",
+            "10.1",
+            "--fee-amount",
+            "0",
+            "--gas-limit",
+            "0",
+            "--fee-token",
+            XAN,
+            "--dry-run",
+            "--assert-max-gas",
+            max_gas,
+            "--ledger-address",
+            &validator_one_rpc,
+        ]
+    };
+
+    // 2. A too low maximum
+    let mut client = run!(test, Bin::Client, tx_args("1"), Some(40))?;
+    client.exp_regex(r"Gas used \d+ exceeds the maximum of 1\.")?;
+    client.assert_failure();
+
+    // 3. A generous maximum
+    let mut client =
+        run!(test, Bin::Client, tx_args("1000000000000"), Some(40))?;
+    client
+        .exp_regex(r"Gas used \d+ is within the maximum of 1000000000000\.")?;
+    client.assert_success();
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Run the ledger node with Albert as the admin of the BTC token
+/// 2. Freeze the BTC token
+/// 3. Submit a BTC transfer, which must be rejected
+/// 4. Query a BTC balance, which still works while frozen
+/// 5. Unfreeze the BTC token
+/// 6. Submit the same transfer again, which must be accepted
+#[test]
+fn token_freeze() -> Result<()> {
+    let test = setup::network(
+        |mut genesis| {
+            let btc = genesis
+                .token
+                .as_mut()
+                .and_then(|tokens| tokens.get_mut(BTC))
+                .unwrap();
+            btc.admin = Some(ALBERT.to_owned());
+            genesis
+        },
+        None,
+    )?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    let admin_args = |flag| {
+        vec![
+            "token-admin",
+            "--token",
+            BTC,
+            flag,
+            "--fee-amount",
+            "0",
+            "--gas-limit",
+            "0",
+            "--fee-token",
+            XAN,
+            "--ledger-address",
+            &validator_one_rpc,
+        ]
+    };
+    let transfer_args = vec![
+        "transfer",
+        "--source",
+        BERTHA,
+        "--target",
+        ALBERT,
+        "--token",
+        BTC,
+        "--amount",
+        "10.1",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+
+    // 2. Freeze the token
+    let mut client = run!(test, Bin::Client, admin_args("--freeze"), Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 3. Transfers of the frozen token are rejected
+    let mut client = run!(test, Bin::Client, transfer_args.clone(), Some(40))?;
+    client.exp_string("Transaction is invalid.")?;
+    client.assert_success();
+
+    // 4. Balances can still be queried
+    let query_args = vec![
+        "balance",
+        "--owner",
+        BERTHA,
+        "--token",
+        BTC,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    client.exp_regex(r"BTC: \d+")?;
+    client.assert_success();
+
+    // 5. Unfreeze the token
+    let mut client =
+        run!(test, Bin::Client, admin_args("--unfreeze"), Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 6. The transfers resume
+    let mut client = run!(test, Bin::Client, transfer_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    Ok(())
+}
+
+/// Test minting of a token by its genesis minter. In this test we:
+/// 1. Run the ledger node
+/// 2. Mint tokens signed by the token's minter
+/// 3. Mint tokens signed by another account, which is rejected
+/// 4. Mint tokens over the token's mint cap, which is rejected
+#[test]
+fn token_mint() -> Result<()> {
+    let test = setup::network(
+        |mut genesis| {
+            let btc = genesis
+                .token
+                .as_mut()
+                .and_then(|tokens| tokens.get_mut(BTC))
+                .unwrap();
+            btc.minter = Some(ALBERT.to_owned());
+            btc.mint_cap = Some(15);
+            genesis
+        },
+        None,
+    )?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    let mint_args = |extra_args: &[&'static str]| {
+        let mut args = vec![
+            "mint",
+            "--token",
+            BTC,
+            "--target",
+            BERTHA,
+            "--amount",
+            "10",
+            "--fee-amount",
+            "0",
+            "--gas-limit",
+            "0",
+            "--fee-token",
+            XAN,
+            "--ledger-address",
+            &validator_one_rpc,
+        ];
+        args.extend_from_slice(extra_args);
+        args
+    };
+
+    // 2. Mint tokens signed by the minter
+    let mut client = run!(test, Bin::Client, mint_args(&[]), Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 3. Mint tokens signed by an account other than the minter
+    let mut client = run!(
+        test,
+        Bin::Client,
+        mint_args(&["--signer", BERTHA]),
+        Some(40)
+    )?;
+    client.exp_string("Transaction is invalid.")?;
+    client.assert_success();
+
+    // 4. Mint tokens over the mint cap
+    let mut client =
+        run!(test, Bin::Client, mint_args(&["--force"]), Some(40))?;
+    client.exp_string("Transaction is invalid.")?;
+    client.assert_success();
+
+    Ok(())
+}
+
+/// Test sweeping an account's entire balance with `transfer --all`. In this
+/// test we:
+/// 1. Run the ledger node
+/// 2. Transfer the entire XAN balance of an implicit account that pays the
+///    fee in XAN
+/// 3. Check that the account's XAN balance is zero
+/// 4. Try to transfer its entire BTC balance, which fails in the client as
+///    the fee in XAN can't be paid anymore
+#[test]
+fn transfer_all() -> Result<()> {
+    let test = setup::single_node_net()?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    let sweep_args = |token| {
+        vec![
+            "transfer",
+            "--source",
+            DAEWON,
+            "--target",
+            ALBERT,
+            "--token",
+            token,
+            "--all",
+            "--fee-amount",
+            "10",
+            "--gas-limit",
+            "0",
+            "--fee-token",
+            XAN,
+            "--ledger-address",
+            &validator_one_rpc,
+        ]
+    };
+
+    // 2. Sweep the XAN balance, less the fee
+    let mut client = run!(test, Bin::Client, sweep_args(XAN), Some(40))?;
+    client.exp_regex(
+        r"Transferring the entire balance of the source .*: 999990\.",
+    )?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 3. Nothing is left in the source's balance
+    let query_args = vec![
+        "balance",
+        "--owner",
+        DAEWON,
+        "--token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    client.exp_string("XAN: 0")?;
+    client.assert_success();
+
+    // 4. The fee of another sweep can't be paid
+    let mut client = run!(test, Bin::Client, sweep_args(BTC), Some(40))?;
+    client
+        .exp_string("can't be paid separately from the transferred balance")?;
+    client.assert_failure();
+
+    Ok(())
+}
+
+/// Test the JSON output mode of the client. In this test we:
+/// 1. Run the ledger node
+/// 2. Query the epoch with `--output json`
+/// 3. Submit an invalid tx (minting tokens is rejected by the token's VP)
+///    with `--output json`
+#[test]
+fn json_output() -> Result<()> {
+    let test = setup::single_node_net()?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+    // Wait to commit a block
+    ledger.exp_regex(r"Committed block hash.*, height: [0-9]+")?;
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Query the epoch
+    let query_args = vec![
+        "--output",
+        "json",
+        "epoch",
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    let (_unread, matched) = client.exp_regex(r#"\{"result":.*\}"#)?;
+    let output: serde_json::Value = serde_json::from_str(matched.trim())?;
+    assert_eq!(output["status"], "ok");
+    assert!(output["result"]["epoch"].is_u64());
+    client.assert_success();
+
+    // 3. Submit an invalid tx
+    let tx_data_path = test.base_dir.path().join("tx.data");
+    let transfer = token::Transfer {
+        source: find_address(&test, DAEWON)?,
+        target: find_address(&test, ALBERT)?,
+        token: find_address(&test, XAN)?,
+        amount: token::Amount::whole(1),
+    };
+    let data = transfer
+        .try_to_vec()
+        .expect("Encoding unsigned transfer shouldn't fail");
+    std::fs::write(&tx_data_path, data).unwrap();
+    let tx_wasm_path = wasm_abs_path(TX_MINT_TOKENS_WASM);
+    let tx_wasm_path = tx_wasm_path.to_string_lossy();
+    let tx_data_path = tx_data_path.to_string_lossy();
+    let tx_args = vec![
+        "--output",
+        "json",
+        "tx",
+        "--code-path",
+        &tx_wasm_path,
+        "--data-path",
+        &tx_data_path,
+        "--signing-key",
+        DAEWON,
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    let (_unread, matched) = client.exp_regex(r#"\{"code":.*\}"#)?;
+    let output: serde_json::Value = serde_json::from_str(matched.trim())?;
+    assert_eq!(output["status"], "error");
+    assert_eq!(output["code"], 1);
+    assert_eq!(output["reason"], "InvalidTx");
+    client.assert_failure();
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Set Bertha's transfer allow list to only Albert
+/// 3. Submit a BTC transfer from Bertha to Albert, which must be accepted
+/// 4. Submit a BTC transfer from Bertha to Christel, which must be rejected
+#[test]
+fn transfer_lists() -> Result<()> {
+    let test = setup::network(|genesis| genesis, None)?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Set Bertha's allow list
+    let tx_args = vec![
+        "transfer-lists",
+        "--address",
+        BERTHA,
+        "--allow",
+        ALBERT,
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    let transfer_args = |target| {
+        vec![
+            "transfer",
+            "--source",
+            BERTHA,
+            "--target",
+            target,
+            "--token",
+            BTC,
+            "--amount",
+            "10.1",
+            "--fee-amount",
+            "0",
+            "--gas-limit",
+            "0",
+            "--fee-token",
+            XAN,
+            "--ledger-address",
+            &validator_one_rpc,
+        ]
+    };
+
+    // 3. A transfer to an allowed address is accepted
+    let mut client = run!(test, Bin::Client, transfer_args(ALBERT), Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 4. A transfer to any other address is rejected
+    let mut client =
+        run!(test, Bin::Client, transfer_args(CHRISTEL), Some(40))?;
+    client.exp_string("Transaction is invalid.")?;
+    client.assert_success();
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Write a BTC transfer from Albert to Bertha and a transfer from Bertha to
+///    Christel, which exceeds Bertha's balance without the first transfer
+/// 3. Submit both transfers in a bundle, which must be accepted
+/// 4. Check that both transfers were applied
+/// 5. Submit a bundle whose second transfer is signed with a wrong key
+/// 6. Check that neither transfer of the failed bundle was applied
+#[test]
+fn tx_bundle() -> Result<()> {
+    let test = setup::network(|genesis| genesis, None)?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // Write a signed transfer to a file, without submitting it
+    let write_transfer = |name: &str,
+                          source: &str,
+                          target: &str,
+                          amount: &str,
+                          signing_key: &str|
+     -> Result<String> {
+        let tx_path = test.base_dir.path().join(name);
+        let tx_path = tx_path.to_string_lossy().to_string();
+        let tx_args = vec![
+            "transfer",
+            "--source",
+            source,
+            "--target",
+            target,
+            "--token",
+            BTC,
+            "--amount",
+            amount,
+            "--signing-key",
+            signing_key,
+            "--fee-amount",
+            "0",
+            "--gas-limit",
+            "0",
+            "--fee-token",
+            XAN,
+            "--raw-tx-out",
+            &tx_path,
+        ];
+        let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+        client.exp_string("Signed transaction written to")?;
+        client.assert_success();
+        Ok(tx_path)
+    };
+    let submit_bundle = |txs: &[String], name: &str, expected| -> Result<()> {
+        let txs_file = test.base_dir.path().join(name);
+        std::fs::write(&txs_file, txs.join("\n"))?;
+        let txs_file = txs_file.to_string_lossy();
+        let tx_args = vec![
+            "submit-bundle",
+            "--txs-file",
+            &txs_file,
+            "--signing-key",
+            ALBERT_KEY,
+            "--fee-amount",
+            "0",
+            "--gas-limit",
+            "0",
+            "--fee-token",
+            XAN,
+            "--ledger-address",
+            &validator_one_rpc,
+        ];
+        let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+        client.exp_string(expected)?;
+        client.assert_success();
+        Ok(())
+    };
+    let check_balance = |owner: &str, expected: &str| -> Result<()> {
+        let query_args = vec![
+            "balance",
+            "--owner",
+            owner,
+            "--token",
+            BTC,
+            "--ledger-address",
+            &validator_one_rpc,
+        ];
+        let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+        client.exp_string(&format!("BTC: {}", expected))?;
+        client.assert_success();
+        Ok(())
+    };
+
+    // 2. Write the transfers, the second depends on the first
+    let txs = vec![
+        write_transfer("tx_1", ALBERT, BERTHA, "500000", ALBERT_KEY)?,
+        write_transfer("tx_2", BERTHA, CHRISTEL, "1200000", BERTHA_KEY)?,
+    ];
+
+    // 3. Submit them in a bundle
+    submit_bundle(&txs, "bundle_1", "Transaction is valid.")?;
+
+    // 4. Both transfers landed together
+    check_balance(ALBERT, "500000")?;
+    check_balance(BERTHA, "300000")?;
+    check_balance(CHRISTEL, "2200000")?;
+
+    // 5. Submit a bundle with a valid transfer and a transfer from Bertha
+    // signed by Christel
+    let txs = vec![
+        write_transfer("tx_3", ALBERT, BERTHA, "100", ALBERT_KEY)?,
+        write_transfer("tx_4", BERTHA, CHRISTEL, "100", CHRISTEL_KEY)?,
+    ];
+    submit_bundle(&txs, "bundle_2", "Transaction is invalid.")?;
+
+    // 6. The failing member voided the whole bundle
+    check_balance(ALBERT, "500000")?;
+    check_balance(BERTHA, "300000")?;
+    check_balance(CHRISTEL, "2200000")?;
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Transfer most of Albert's BTC to Bertha
+/// 3. Dry run a transfer from Albert, which exceeds Albert's current balance,
+///    at the height of the first block, where Albert's balance sufficed
+/// 4. Check that the same dry run against the latest state fails
+#[test]
+fn dry_run_at_height() -> Result<()> {
+    let test = setup::network(|genesis| genesis, None)?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    let transfer_args = |target: &'static str, amount: &'static str| {
+        vec![
+            "transfer",
+            "--source",
+            ALBERT,
+            "--target",
+            target,
+            "--token",
+            BTC,
+            "--amount",
+            amount,
+            "--fee-amount",
+            "0",
+            "--gas-limit",
+            "0",
+            "--fee-token",
+            XAN,
+            "--ledger-address",
+            &validator_one_rpc,
+        ]
+    };
+
+    // 2. Spend most of Albert's balance
+    let mut client =
+        run!(test, Bin::Client, transfer_args(BERTHA, "900000"), Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 3. At the first block, Albert could afford the transfer
+    let tx_args = [
+        transfer_args(CHRISTEL, "500000"),
+        vec!["--dry-run", "--dry-run-at-height", "1"],
+    ]
+    .concat();
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 4. Against the latest state, the transfer fails. The client check of
+    // the balance is forced through to dry run it on the ledger.
+    let tx_args = [
+        transfer_args(CHRISTEL, "500000"),
+        vec!["--dry-run", "--force"],
+    ]
+    .concat();
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("The balance of the source")?;
+    client.exp_string("Transaction runner error")?;
+    client.assert_success();
+
+    Ok(())
+}
+
 /// In this test we:
 /// 1. Run the ledger node
-/// 2. Submit a valid proposal
-/// 3. Query the proposal
-/// 4. Query token balance (submitted funds)
-/// 5. Query governance address balance
-/// 6. Submit an invalid proposal
-/// 7. Check invalid proposal was not accepted
-/// 8. Query token balance (funds shall not be submitted)
+/// 2. Start a client REPL session
+/// 3. Query a balance in the session
+/// 4. Query the epoch in the same session
+/// 5. Exit the session
 #[test]
-fn proposal_submission() -> Result<()> {
+fn client_repl() -> Result<()> {
     let test = setup::network(|genesis| genesis, None)?;
 
     // 1. Run the ledger node
@@ -960,162 +4325,287 @@ fn proposal_submission() -> Result<()> {
         ledger.exp_string("Started node")?;
     }
 
-    // 2. Submit valid proposal
-    let valid_proposal_json_path =
-        test.base_dir.path().join("valid_proposal.json");
-    let albert = find_address(&test, ALBERT)?;
-    let valid_proposal_json = json!(
-        {
-            "content": {
-                "title": "TheTitle",
-                "authors": "test@test.com",
-                "discussions-to": "www.github.com/anoma/aip/1",
-                "created": "2022-03-10T08:54:37Z",
-                "license": "MIT",
-                "abstract": "Ut convallis eleifend orci vel venenatis. Duis vulputate metus in lacus sollicitudin vestibulum. Suspendisse vel velit ac est consectetur feugiat nec ac urna. Ut faucibus ex nec dictum fermentum. Morbi aliquet purus at sollicitudin ultrices. Quisque viverra varius cursus. Praesent sed mauris gravida, pharetra turpis non, gravida eros. Nullam sed ex justo. Ut at placerat ipsum, sit amet rhoncus libero. Sed blandit non purus non suscipit. Phasellus sed quam nec augue bibendum bibendum ut vitae urna. Sed odio diam, ornare nec sapien eget, congue viverra enim.",
-                "motivation": "Ut convallis eleifend orci vel venenatis. Duis vulputate metus in lacus sollicitudin vestibulum. Suspendisse vel velit ac est consectetur feugiat nec ac urna. Ut faucibus ex nec dictum fermentum. Morbi aliquet purus at sollicitudin ultrices.",
-                "details": "Ut convallis eleifend orci vel venenatis. Duis vulputate metus in lacus sollicitudin vestibulum. Suspendisse vel velit ac est consectetur feugiat nec ac urna. Ut faucibus ex nec dictum fermentum. Morbi aliquet purus at sollicitudin ultrices. Quisque viverra varius cursus. Praesent sed mauris gravida, pharetra turpis non, gravida eros.",
-                "requires": "2"
-            },
-            "author": albert,
-            "voting_start_epoch": 9999,
-            "voting_end_epoch": 10002,
-            "grace_epoch": 10009
-        }
-    );
-    generate_proposal_json(
-        valid_proposal_json_path.clone(),
-        valid_proposal_json,
-    );
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Start a session
+    let repl_args = vec!["repl", "--ledger-address", &validator_one_rpc];
+    let mut client = run!(test, Bin::Client, repl_args, Some(40))?;
+    client.exp_string("anoma> ")?;
+
+    // 3. Query a balance
+    client.send_line(&format!("balance --owner {} --token {}", ALBERT, XAN))?;
+    client.exp_string("XAN: 1000000")?;
+    client.exp_string("anoma> ")?;
+
+    // 4. Query the epoch over the same session
+    client.send_line("epoch")?;
+    client.exp_string("Last committed epoch:")?;
+    client.exp_string("anoma> ")?;
+
+    // 5. Exit
+    client.send_line("exit")?;
+    client.assert_success();
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Set an unlock height in Albert's storage
+/// 3. Update Albert's VP to a VP that rejects txs before the unlock height
+/// 4. Submit a transfer from Albert before the unlock height and check that
+///    it's rejected
+/// 5. Wait for the unlock height
+/// 6. Submit the same transfer again and check that it's accepted
+#[test]
+fn vp_time_lock() -> Result<()> {
+    let test = setup::network(|genesis| genesis, None)?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
 
     let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+    let tx_write_storage_key = wasm_abs_path(TX_WRITE_STORAGE_KEY_WASM);
+    let tx_write_storage_key = tx_write_storage_key.to_string_lossy();
+    let vp_time_lock = wasm_abs_path(VP_TIME_LOCK_WASM);
+    let vp_time_lock = vp_time_lock.to_string_lossy();
 
-    let submit_proposal_args = vec![
-        "init-proposal",
+    // 2. Set the unlock height far enough for the following txs to be
+    // applied before it
+    let albert = find_address(&test, ALBERT)?;
+    let unlock_height = get_height(&test, &validator_one_rpc)?.0 + 20;
+    let unlock_height_key = storage::Key::from(albert.to_db_key())
+        .push(&"unlock_height".to_owned())?;
+    let tx_data = (unlock_height_key, BlockHeight(unlock_height).try_to_vec()?)
+        .try_to_vec()?;
+    let tx_data_path = test.base_dir.path().join("tx.data");
+    std::fs::write(&tx_data_path, tx_data)?;
+    let tx_data_path = tx_data_path.to_string_lossy();
+    let tx_args = vec![
+        "tx",
+        "--signer",
+        ALBERT,
+        "--code-path",
+        &tx_write_storage_key,
         "--data-path",
-        valid_proposal_json_path.to_str().unwrap(),
+        &tx_data_path,
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
         "--ledger-address",
         &validator_one_rpc,
     ];
-
-    let mut client = run!(test, Bin::Client, submit_proposal_args, Some(15))?;
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
     client.exp_string("Transaction is valid.")?;
     client.assert_success();
 
-    // 3. Query the proposal
-
-    let proposal_query_args = vec![
-        "query-proposal",
-        "--proposal-id",
+    // 3. Update Albert's VP
+    let tx_args = vec![
+        "update",
+        "--address",
+        ALBERT,
+        "--code-path",
+        &vp_time_lock,
+        "--fee-amount",
         "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
         "--ledger-address",
         &validator_one_rpc,
     ];
-
-    let mut client = run!(test, Bin::Client, proposal_query_args, Some(15))?;
-    client.exp_string("Proposal: 0")?;
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
     client.assert_success();
 
-    // 4. Query token balance proposal author (submitted funds)
-    let query_balance_args = vec![
-        "balance",
-        "--owner",
+    // 4. A transfer before the unlock height is rejected
+    let transfer_args = vec![
+        "transfer",
+        "--source",
         ALBERT,
+        "--target",
+        BERTHA,
         "--token",
         XAN,
+        "--amount",
+        "10",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
         "--ledger-address",
         &validator_one_rpc,
     ];
+    let mut client = run!(test, Bin::Client, transfer_args.clone(), Some(40))?;
+    client.exp_string("Transaction is invalid.")?;
+    client.assert_success();
 
-    let mut client = run!(test, Bin::Client, query_balance_args, Some(15))?;
-    client.exp_string("XAN: 999500")?;
+    // 5. Wait for the unlock height
+    let start = Instant::now();
+    let loop_timeout = Duration::new(60, 0);
+    loop {
+        if Instant::now().duration_since(start) > loop_timeout {
+            panic!("Timed out waiting for height: {}", unlock_height);
+        }
+        let height = get_height(&test, &validator_one_rpc)?;
+        if height.0 >= unlock_height {
+            break;
+        }
+        sleep(1);
+    }
+
+    // 6. The same transfer is accepted from the unlock height on
+    let mut client = run!(test, Bin::Client, transfer_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
     client.assert_success();
 
-    // 5. Query token balance governance
-    let query_balance_args = vec![
-        "balance",
-        "--owner",
-        GOVERNANCE_ADDRESS,
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Submit a token transfer
+/// 3. Run `ledger tail` from the first block and check that it catches up
+///    with a block that has a transaction
+#[test]
+fn ledger_tail() -> Result<()> {
+    let test = setup::single_node_net()?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Submit a token transfer
+    let tx_args = vec![
+        "transfer",
+        "--source",
+        BERTHA,
+        "--target",
+        ALBERT,
         "--token",
         XAN,
+        "--amount",
+        "10.1",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
         "--ledger-address",
         &validator_one_rpc,
     ];
-
-    let mut client = run!(test, Bin::Client, query_balance_args, Some(15))?;
-    client.exp_string("XAN: 500")?;
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
     client.assert_success();
 
-    // 6. Submit an invalid proposal
-    // proposal is invalid due to voting_end_epoch - voting_start_epoch < 3
-    let invalid_proposal_json_path =
-        test.base_dir.path().join("invalid_proposal.json");
-    let albert = find_address(&test, ALBERT)?;
-    let invalid_proposal_json = json!(
-        {
-            "content": {
-                "title": "TheTitle",
-                "authors": "test@test.com",
-                "discussions-to": "www.github.com/anoma/aip/1",
-                "created": "2022-03-10T08:54:37Z",
-                "license": "MIT",
-                "abstract": "Ut convallis eleifend orci vel venenatis. Duis vulputate metus in lacus sollicitudin vestibulum. Suspendisse vel velit ac est consectetur feugiat nec ac urna. Ut faucibus ex nec dictum fermentum. Morbi aliquet purus at sollicitudin ultrices. Quisque viverra varius cursus. Praesent sed mauris gravida, pharetra turpis non, gravida eros. Nullam sed ex justo. Ut at placerat ipsum, sit amet rhoncus libero. Sed blandit non purus non suscipit. Phasellus sed quam nec augue bibendum bibendum ut vitae urna. Sed odio diam, ornare nec sapien eget, congue viverra enim.",
-                "motivation": "Ut convallis eleifend orci vel venenatis. Duis vulputate metus in lacus sollicitudin vestibulum. Suspendisse vel velit ac est consectetur feugiat nec ac urna. Ut faucibus ex nec dictum fermentum. Morbi aliquet purus at sollicitudin ultrices.",
-                "details": "Ut convallis eleifend orci vel venenatis. Duis vulputate metus in lacus sollicitudin vestibulum. Suspendisse vel velit ac est consectetur feugiat nec ac urna. Ut faucibus ex nec dictum fermentum. Morbi aliquet purus at sollicitudin ultrices. Quisque viverra varius cursus. Praesent sed mauris gravida, pharetra turpis non, gravida eros.",
-                "requires": "2"
-            },
-            "author": albert,
-            "voting_start_epoch": 9999,
-            "voting_end_epoch": 10000,
-            "grace_epoch": 10009
-        }
-    );
-    generate_proposal_json(
-        invalid_proposal_json_path.clone(),
-        invalid_proposal_json,
-    );
+    // 3. Tail the blocks from the first one
+    let mut tail = run_as!(
+        test,
+        Who::Validator(0),
+        Bin::Node,
+        &["ledger", "tail", "--from-height", "1"],
+        Some(40)
+    )?;
+    tail.exp_string("Block 1: root ")?;
+    tail.exp_regex(r"Block \d+: root [0-9A-F]{8}, [1-9]\d* txs, gas used \d+")?;
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Sign an off-chain message with the key of an established account
+/// 3. Verify the signature with the account's public key from the ledger
+/// 4. Check that the signature of a tampered message is not valid
+#[test]
+fn sign_and_verify_message() -> Result<()> {
+    let test = setup::single_node_net()?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+
+    ledger.exp_string("Anoma ledger node started")?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
 
     let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+    let message = "Sign in to the dApp with the challenge 42";
 
-    let submit_proposal_args = vec![
-        "init-proposal",
-        "--data-path",
-        invalid_proposal_json_path.to_str().unwrap(),
+    // 2. Sign the message
+    let sign_args = vec![
+        "sign-message",
+        "--signer",
+        ALBERT,
+        "--message",
+        message,
         "--ledger-address",
         &validator_one_rpc,
     ];
-
-    let mut client = run!(test, Bin::Client, submit_proposal_args, Some(15))?;
-    client.exp_string("Transaction is invalid.")?;
+    let mut client = run!(test, Bin::Client, sign_args, Some(40))?;
+    let (_, signature) = client.exp_regex(r"Signature: [0-9a-f]+")?;
+    let signature = signature.trim_start_matches("Signature: ").to_string();
     client.assert_success();
 
-    // 7. Check invalid proposal was not accepted
-    let proposal_query_args = vec![
-        "query-proposal",
-        "--proposal-id",
-        "1",
+    // 3. Verify the signature
+    let verify_args = vec![
+        "verify-message",
+        "--address",
+        ALBERT,
+        "--message",
+        message,
+        "--signature",
+        &signature,
         "--ledger-address",
         &validator_one_rpc,
     ];
-
-    let mut client = run!(test, Bin::Client, proposal_query_args, Some(15))?;
-    client.exp_string("No valid proposal was found with id 1")?;
+    let mut client = run!(test, Bin::Client, verify_args, Some(40))?;
+    client.exp_string("The signature of the message by")?;
+    client.exp_string("is valid.")?;
     client.assert_success();
 
-    // 8. Query token balance (funds shall not be submitted)
-    let query_balance_args = vec![
-        "balance",
-        "--owner",
+    // 4. Verify the signature of a tampered message
+    let verify_args = vec![
+        "verify-message",
+        "--address",
         ALBERT,
-        "--token",
-        XAN,
+        "--message",
+        "Sign in to the dApp with the challenge 43",
+        "--signature",
+        &signature,
         "--ledger-address",
         &validator_one_rpc,
     ];
-
-    let mut client = run!(test, Bin::Client, query_balance_args, Some(15))?;
-    client.exp_string("XAN: 999500")?;
-    client.assert_success();
+    let mut client = run!(test, Bin::Client, verify_args, Some(40))?;
+    client.exp_string("is not valid")?;
+    client.assert_failure();
 
     Ok(())
 }
@@ -455,6 +455,15 @@ impl AnomaCmd {
         );
     }
 
+    /// Assert that the process exited with failure
+    pub fn assert_failure(&self) {
+        let status = self.session.process.wait().unwrap();
+        assert_ne!(
+            WaitStatus::Exited(self.session.process.child_pid, 0),
+            status
+        );
+    }
+
     /// Wait until provided string is seen on stdout of child process.
     /// Return the yet unread output (without the matched string)
     ///
@@ -736,6 +745,9 @@ pub mod constants {
     pub const VP_ALWAYS_FALSE_WASM: &str =
         "wasm_for_tests/vp_always_false.wasm";
     pub const TX_MINT_TOKENS_WASM: &str = "wasm_for_tests/tx_mint_tokens.wasm";
+    pub const TX_WRITE_STORAGE_KEY_WASM: &str =
+        "wasm_for_tests/tx_write_storage_key.wasm";
+    pub const VP_TIME_LOCK_WASM: &str = "wasm_for_tests/vp_time_lock.wasm";
 
     /// Find the absolute path to one of the WASM files above
     pub fn wasm_abs_path(file_name: &str) -> PathBuf {
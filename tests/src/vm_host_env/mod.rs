@@ -22,11 +22,12 @@ mod tests {
     use anoma::ibc::tx_msg::Msg;
     use anoma::ledger::ibc::handler::IbcActions;
     use anoma::ledger::ibc::vp::Error as IbcError;
+    use anoma::ledger::parameters;
     use anoma::proto::{SignedTxData, Tx};
     use anoma::tendermint_proto::Protobuf;
     use anoma::types::key::*;
     use anoma::types::storage::{self, BlockHash, BlockHeight, Key, KeySeg};
-    use anoma::types::time::DateTimeUtc;
+    use anoma::types::time::{DateTimeUtc, Rfc3339String};
     use anoma::types::token::{self, Amount};
     use anoma::types::{address, key};
     use anoma_vm_env::tx_prelude::{
@@ -232,6 +233,111 @@ mod tests {
             tx_host_env::get_block_epoch(),
             tx_host_env::with(|env| env.storage.get_current_epoch().0)
         );
+        assert_eq!(
+            tx_host_env::get_epoch_seed(),
+            tx_host_env::with(|env| parameters::read_epoch_seed(&env.storage)
+                .unwrap()
+                .0)
+        );
+    }
+
+    /// Test that a validator slashed for double-signing is tombstoned, so it
+    /// can never be unjailed, and that its delegators can still unbond and
+    /// withdraw their tokens
+    #[test]
+    fn test_tx_tombstoned_validator() {
+        use anoma::ledger::pos::anoma_proof_of_stake::types::SlashType;
+        use anoma::ledger::pos::anoma_proof_of_stake::{PosBase, UnjailError};
+        use anoma::ledger::pos::types::BasisPoints;
+        use anoma::ledger::pos::{
+            init_genesis_storage, GenesisValidator, PosParams,
+        };
+
+        // The environment must be initialized first
+        tx_host_env::init();
+
+        let params = PosParams::default();
+        let validator = address::testing::established_address_1();
+        let delegator = address::testing::established_address_2();
+        let staking_token = address::xan();
+        let delegation = Amount::whole(1_000);
+        let genesis_validators = [GenesisValidator {
+            address: validator.clone(),
+            staking_reward_address: address::testing::established_address_3(),
+            tokens: Amount::whole(100_000),
+            consensus_key: key::testing::keypair_1().ref_to(),
+            staking_reward_key: key::testing::keypair_2().ref_to(),
+            commission_rate: BasisPoints::new(0),
+        }];
+        tx_host_env::with(|env| {
+            init_genesis_storage(
+                &mut env.storage,
+                &params,
+                genesis_validators.iter(),
+                storage::Epoch(0),
+            );
+            env.credit_tokens(&delegator, &staking_token, delegation);
+        });
+        let set_epoch = |epoch: u64| {
+            tx_host_env::with(|env| {
+                env.storage.block.epoch = storage::Epoch(epoch)
+            })
+        };
+
+        // Delegate to the validator
+        tx_host_env::proof_of_stake::bond_tokens(
+            Some(&delegator),
+            &validator,
+            delegation,
+        )
+        .unwrap();
+        tx_host_env::commit_tx_and_block();
+
+        // The validator double-signs
+        tx_host_env::with(|env| {
+            env.storage
+                .slash(
+                    &params,
+                    storage::Epoch(0),
+                    storage::Epoch(0),
+                    1_u64,
+                    SlashType::DuplicateVote,
+                    &validator,
+                )
+                .unwrap();
+            assert!(env.storage.is_validator_tombstoned(&validator));
+        });
+
+        // The delegator can unbond and withdraw its tokens, which have been
+        // bonded after the fault so they're not slashed
+        set_epoch(params.pipeline_len);
+        tx_host_env::proof_of_stake::unbond_tokens(
+            Some(&delegator),
+            &validator,
+            delegation,
+        )
+        .unwrap();
+        tx_host_env::commit_tx_and_block();
+        set_epoch(params.pipeline_len + params.unbonding_len);
+        let slashed = tx_host_env::proof_of_stake::withdraw_tokens(
+            Some(&delegator),
+            &validator,
+        )
+        .unwrap();
+        assert_eq!(slashed, Amount::default());
+        tx_host_env::commit_tx_and_block();
+        let balance_key = token::balance_key(&staking_token, &delegator);
+        let balance: Option<Amount> =
+            tx_host_env::read(&balance_key.to_string());
+        assert_eq!(balance, Some(delegation));
+
+        // The validator can never be unjailed, even long after its jail
+        // duration is over
+        set_epoch(params.jail_duration + 100);
+        assert!(matches!(
+            tx_host_env::proof_of_stake::unjail_validator(&validator),
+            Err(UnjailError::Tombstoned(_))
+        ));
     }
 
     /// An example how to write a VP host environment integration test
@@ -428,6 +534,60 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_vp_verify_tx_signature_of_different_schemes() {
+        let mut env = TestVpEnv::default();
+
+        // An account with an ed25519 key and an account with a secp256k1 key
+        let ed25519_addr = address::testing::established_address_1();
+        let ed25519_keypair = key::testing::keypair_1();
+        let secp256k1_addr = address::testing::established_address_2();
+        let secp256k1_keypair: common::SecretKey =
+            key::testing::gen_keypair::<secp256k1::SigScheme>()
+                .try_to_sk()
+                .unwrap();
+        let accounts = [
+            (&ed25519_addr, &ed25519_keypair),
+            (&secp256k1_addr, &secp256k1_keypair),
+        ];
+
+        // Write the public keys to storage
+        for (addr, keypair) in accounts {
+            let pk_key = key::pk_key(addr);
+            env.storage
+                .write(&pk_key, keypair.ref_to().try_to_vec().unwrap())
+                .unwrap();
+        }
+        // Initialize the environment
+        vp_host_env::set(env);
+
+        // Use some arbitrary bytes for tx code
+        let code = vec![4, 3, 2, 1, 0];
+        let data = Some(vec![1, 2, 3, 4].repeat(10));
+        let mut sigs = vec![];
+        for (addr, keypair) in accounts {
+            let signed_tx_data = vp_host_env::with(|env| {
+                env.tx = Tx::new(code.clone(), data.clone()).sign(keypair);
+                let tx_data = env.tx.data.as_ref().expect("data should exist");
+
+                SignedTxData::try_from_slice(&tx_data[..])
+                    .expect("decoding signed data we just signed")
+            });
+            // The stored key is tagged with its scheme
+            let pk = anoma_vm_env::key::vp::get(addr)
+                .expect("the key should be stored");
+            assert_eq!(pk, keypair.ref_to());
+            assert!(vp_host_env::verify_tx_signature(&pk, &signed_tx_data.sig));
+            sigs.push((pk, signed_tx_data.sig));
+        }
+        assert!(matches!(sigs[0].0, common::PublicKey::Ed25519(_)));
+        assert!(matches!(sigs[1].0, common::PublicKey::Secp256k1(_)));
+
+        // A signature is not valid for the key of the other scheme
+        assert!(!vp_host_env::verify_tx_signature(&sigs[0].0, &sigs[1].1));
+        assert!(!vp_host_env::verify_tx_signature(&sigs[1].0, &sigs[0].1));
+    }
+
     #[test]
     fn test_vp_get_metadata() {
         // The environment must be initialized first
@@ -449,6 +609,27 @@ mod tests {
             vp_host_env::get_block_epoch(),
             vp_host_env::with(|env| env.storage.get_current_epoch().0)
         );
+        assert_eq!(
+            vp_host_env::get_epoch_seed(),
+            vp_host_env::with(|env| parameters::read_epoch_seed(&env.storage)
+                .unwrap()
+                .0)
+        );
+
+        // The block time is only available with the block's header
+        assert!(vp_host_env::get_block_time().is_none());
+        vp_host_env::with(|env| {
+            env.storage.set_header(ibc::tm_dummy_header()).unwrap()
+        });
+        assert_eq!(
+            vp_host_env::get_block_time().map(|time| time.0),
+            vp_host_env::with(|env| env.storage.header.as_ref().map(
+                |header| {
+                    let time = DateTimeUtc::try_from(header.time).unwrap();
+                    Rfc3339String::from(time).0
+                }
+            ))
+        );
     }
 
     #[test]
@@ -336,5 +336,6 @@ mod native_tx_host_env {
     native_host_fn!(tx_get_block_time() -> i64);
     native_host_fn!(tx_get_block_hash(result_ptr: u64));
     native_host_fn!(tx_get_block_epoch() -> u64);
+    native_host_fn!(tx_get_epoch_seed(result_ptr: u64));
     native_host_fn!(tx_log_string(str_ptr: u64, str_len: u64));
 }
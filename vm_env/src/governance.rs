@@ -35,9 +35,9 @@ pub mod tx {
         let grace_epoch_key = storage::get_grace_epoch_key(proposal_id);
         tx::write(&grace_epoch_key.to_string(), data.grace_epoch);
 
-        if data.proposal_code.is_some() {
+        if let Some(proposal_code) = data.proposal_code {
             let proposal_code_key = storage::get_proposal_code_key(proposal_id);
-            tx::write(&proposal_code_key.to_string(), data.proposal_code);
+            tx::write(&proposal_code_key.to_string(), proposal_code);
         }
 
         tx::write(&counter_key.to_string(), proposal_id + 1);
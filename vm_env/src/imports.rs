@@ -57,6 +57,7 @@ pub mod tx {
     use anoma::types::address;
     use anoma::types::address::Address;
     use anoma::types::chain::CHAIN_ID_LENGTH;
+    use anoma::types::hash::{Hash, HASH_LENGTH};
     use anoma::types::ibc::IbcEvent;
     use anoma::types::internal::HostEnvResult;
     use anoma::types::storage::{
@@ -173,13 +174,13 @@ pub mod tx {
     /// transaction will validate the transaction and will receive all the
     /// changed storage keys and initialized accounts in their inputs.
     pub fn insert_verifier(addr: &Address) {
-        let addr = addr.encode();
+        let addr = addr.encode_with_hrp(address::DEFAULT_ADDRESS_HRP);
         unsafe { anoma_tx_insert_verifier(addr.as_ptr() as _, addr.len() as _) }
     }
 
     /// Update a validity predicate
     pub fn update_validity_predicate(addr: &Address, code: impl AsRef<[u8]>) {
-        let addr = addr.encode();
+        let addr = addr.encode_with_hrp(address::DEFAULT_ADDRESS_HRP);
         let code = code.as_ref();
         unsafe {
             anoma_tx_update_validity_predicate(
@@ -266,6 +267,18 @@ pub mod tx {
         Epoch(unsafe { anoma_tx_get_block_epoch() })
     }
 
+    /// Get the random seed of the current epoch. The seed is the same on
+    /// every node.
+    pub fn get_epoch_seed() -> Hash {
+        let result = Vec::with_capacity(HASH_LENGTH);
+        unsafe {
+            anoma_tx_get_epoch_seed(result.as_ptr() as _);
+        }
+        let slice =
+            unsafe { slice::from_raw_parts(result.as_ptr(), HASH_LENGTH) };
+        Hash::try_from(slice).expect("Cannot convert the hash")
+    }
+
     /// Log a string. The message will be printed at the `tracing::Level::Info`.
     pub fn log_string<T: AsRef<str>>(msg: T) {
         let msg = msg.as_ref();
@@ -350,6 +363,9 @@ pub mod tx {
         // Get the current block epoch
         fn anoma_tx_get_block_epoch() -> u64;
 
+        // Get the current epoch seed
+        fn anoma_tx_get_epoch_seed(result_ptr: u64);
+
         // Requires a node running with "Info" log level
         fn anoma_tx_log_string(str_ptr: u64, str_len: u64);
     }
@@ -368,6 +384,7 @@ pub mod vp {
     use anoma::types::storage::{
         BlockHash, BlockHeight, Epoch, BLOCK_HASH_LENGTH,
     };
+    use anoma::types::time::Rfc3339String;
     pub use borsh::{BorshDeserialize, BorshSerialize};
 
     pub struct PreKeyValIterator<T>(pub u64, pub PhantomData<T>);
@@ -505,6 +522,20 @@ pub mod vp {
         BlockHeight(unsafe { anoma_vp_get_block_height() })
     }
 
+    /// Get time of the current block header as rfc 3339 string, if any. The
+    /// time is not available when a transaction is dry-ran outside of a block.
+    pub fn get_block_time() -> Option<Rfc3339String> {
+        let read_result = unsafe { anoma_vp_get_block_time() };
+        super::read_from_buffer(read_result, anoma_vp_result_buffer).map(
+            |time_value| {
+                Rfc3339String(
+                    String::try_from_slice(&time_value[..])
+                        .expect("The conversion shouldn't fail"),
+                )
+            },
+        )
+    }
+
     /// Get a block hash
     pub fn get_block_hash() -> BlockHash {
         let result = Vec::with_capacity(BLOCK_HASH_LENGTH);
@@ -533,6 +564,18 @@ pub mod vp {
         Epoch(unsafe { anoma_vp_get_block_epoch() })
     }
 
+    /// Get the random seed of the current epoch. The seed is the same on
+    /// every node.
+    pub fn get_epoch_seed() -> Hash {
+        let result = Vec::with_capacity(HASH_LENGTH);
+        unsafe {
+            anoma_vp_get_epoch_seed(result.as_ptr() as _);
+        }
+        let slice =
+            unsafe { slice::from_raw_parts(result.as_ptr(), HASH_LENGTH) };
+        Hash::try_from(slice).expect("Cannot convert the hash")
+    }
+
     /// Verify a transaction signature. The signature is expected to have been
     /// produced on the encoded transaction [`anoma::proto::Tx`]
     /// using [`anoma::proto::Tx::sign`].
@@ -635,6 +678,9 @@ pub mod vp {
         // Get the current block height
         fn anoma_vp_get_block_height() -> u64;
 
+        // Get the time of the current block header
+        fn anoma_vp_get_block_time() -> i64;
+
         // Get the current block hash
         fn anoma_vp_get_block_hash(result_ptr: u64);
 
@@ -644,6 +690,9 @@ pub mod vp {
         // Get the current block epoch
         fn anoma_vp_get_block_epoch() -> u64;
 
+        // Get the current epoch seed
+        fn anoma_vp_get_epoch_seed(result_ptr: u64);
+
         // Verify a transaction signature
         fn anoma_vp_verify_tx_signature(
             pk_ptr: u64,
@@ -1,18 +1,23 @@
 //! Proof of Stake system integration with functions for transactions
 
 use anoma::ledger::pos::anoma_proof_of_stake::{
-    BecomeValidatorError, BondError, UnbondError, WithdrawError,
+    BecomeValidatorError, BondError, ClaimRewardsError,
+    CommissionRateChangeError, ConsensusKeyChangeError, UnbondError,
+    UnjailError, WithdrawError,
 };
-use anoma::ledger::pos::types::Slash;
+use anoma::ledger::pos::types::{BasisPoints, Slash};
 pub use anoma::ledger::pos::*;
 use anoma::ledger::pos::{
-    anoma_proof_of_stake, bond_key, params_key, total_voting_power_key,
-    unbond_key, validator_address_raw_hash_key, validator_consensus_key_key,
-    validator_set_key, validator_slashes_key,
+    anoma_proof_of_stake, bond_key, params_key, rewards_key,
+    total_voting_power_key, unbond_key, validator_address_raw_hash_key,
+    validator_commission_rate_key, validator_consensus_key_key,
+    validator_metadata_key, validator_set_key, validator_slashes_key,
     validator_staking_reward_address_key, validator_state_key,
-    validator_total_deltas_key, validator_voting_power_key,
+    validator_tombstoned_key, validator_total_deltas_key,
+    validator_voting_power_key,
 };
 use anoma::types::address::{self, Address, InternalAddress};
+use anoma::types::transaction::pos::{EditValidatorError, ValidatorMetadata};
 use anoma::types::transaction::InitValidator;
 use anoma::types::{key, token};
 pub use anoma_proof_of_stake::{
@@ -56,6 +61,49 @@ pub fn withdraw_tokens(
     PoS.withdraw_tokens(source, validator, current_epoch)
 }
 
+/// Claim the unclaimed staking rewards of a self-bond to a validator when
+/// `source` is `None` or equal to the `validator` address, or of tokens
+/// delegated to the `validator` by the `source`. Returns the claimed amount.
+pub fn claim_rewards(
+    source: Option<&Address>,
+    validator: &Address,
+) -> Result<token::Amount, ClaimRewardsError<Address>> {
+    PoS.claim_rewards(source, validator)
+}
+
+/// Change a validator's commission rate, given in basis points. The new rate
+/// takes effect at the pipeline offset.
+pub fn change_validator_commission_rate(
+    validator: &Address,
+    new_rate: u64,
+) -> Result<(), CommissionRateChangeError<Address>> {
+    let current_epoch = tx::get_block_epoch();
+    PoS.change_validator_commission_rate(
+        validator,
+        BasisPoints::new(new_rate),
+        current_epoch,
+    )
+}
+
+/// Rotate a validator's consensus key. The new key takes effect at the
+/// pipeline offset.
+pub fn change_validator_consensus_key(
+    validator: &Address,
+    consensus_key: &key::common::PublicKey,
+) -> Result<(), ConsensusKeyChangeError<Address>> {
+    let current_epoch = tx::get_block_epoch();
+    PoS.change_validator_consensus_key(validator, consensus_key, current_epoch)
+}
+
+/// Unjail a jailed validator. From the pipeline offset, the validator is a
+/// candidate again.
+pub fn unjail_validator(
+    validator: &Address,
+) -> Result<(), UnjailError<Address>> {
+    let current_epoch = tx::get_block_epoch();
+    PoS.unjail_validator(validator, current_epoch)
+}
+
 /// Attempt to initialize a validator account. On success, returns the
 /// initialized validator account's address and its staking reward address.
 pub fn init_validator(
@@ -67,6 +115,8 @@ pub fn init_validator(
         dkg_key,
         validator_vp_code,
         rewards_vp_code,
+        commission_rate,
+        metadata,
     }: InitValidator,
 ) -> Result<(Address, Address), BecomeValidatorError<Address>> {
     let current_epoch = tx::get_block_epoch();
@@ -88,11 +138,37 @@ pub fn init_validator(
         &validator_address,
         &rewards_address,
         &consensus_key,
+        BasisPoints::new(commission_rate),
         current_epoch,
     )?;
+    if metadata != ValidatorMetadata::default() {
+        tx::write(
+            &validator_metadata_key(&validator_address).to_string(),
+            &metadata,
+        );
+    }
     Ok((validator_address, rewards_address))
 }
 
+/// Update a validator's metadata. Only the fields that are set in the `update`
+/// are changed.
+pub fn edit_validator_metadata(
+    validator: &Address,
+    update: ValidatorMetadata,
+) -> Result<(), EditValidatorError> {
+    if !tx::has_key(&validator_state_key(validator).to_string()) {
+        return Err(EditValidatorError::NotAValidator(validator.clone()));
+    }
+    let key = validator_metadata_key(validator).to_string();
+    let mut metadata: ValidatorMetadata = tx::read(&key).unwrap_or_default();
+    metadata.merge(update);
+    metadata
+        .validate()
+        .map_err(EditValidatorError::InvalidMetadata)?;
+    tx::write(&key, &metadata);
+    Ok(())
+}
+
 /// Proof of Stake system. This struct integrates and gives access to
 /// lower-level PoS functions.
 pub struct PoS;
@@ -152,6 +228,17 @@ impl anoma_proof_of_stake::PosReadOnly for PoS {
         tx::read(validator_slashes_key(key).to_string()).unwrap_or_default()
     }
 
+    fn is_validator_tombstoned(&self, key: &Self::Address) -> bool {
+        tx::read(validator_tombstoned_key(key).to_string()).unwrap_or_default()
+    }
+
+    fn read_validator_commission_rate(
+        &self,
+        key: &Self::Address,
+    ) -> Option<CommissionRates> {
+        tx::read(validator_commission_rate_key(key).to_string())
+    }
+
     fn read_bond(&self, key: &BondId) -> Option<Bonds> {
         tx::read(bond_key(key).to_string())
     }
@@ -160,6 +247,10 @@ impl anoma_proof_of_stake::PosReadOnly for PoS {
         tx::read(unbond_key(key).to_string())
     }
 
+    fn read_rewards(&self, key: &BondId) -> Option<Rewards> {
+        tx::read(rewards_key(key).to_string())
+    }
+
     fn read_validator_set(&self) -> ValidatorSets {
         tx::read(validator_set_key().to_string()).unwrap()
     }
@@ -225,6 +316,14 @@ impl anoma_proof_of_stake::PosActions for PoS {
         tx::write(validator_voting_power_key(key).to_string(), &value)
     }
 
+    fn write_validator_commission_rate(
+        &mut self,
+        key: &Self::Address,
+        value: CommissionRates,
+    ) {
+        tx::write(validator_commission_rate_key(key).to_string(), &value)
+    }
+
     fn write_bond(&mut self, key: &BondId, value: Bonds) {
         tx::write(bond_key(key).to_string(), &value)
     }
@@ -249,6 +348,10 @@ impl anoma_proof_of_stake::PosActions for PoS {
         tx::delete(unbond_key(key).to_string())
     }
 
+    fn delete_rewards(&mut self, key: &BondId) {
+        tx::delete(rewards_key(key).to_string())
+    }
+
     fn transfer(
         &mut self,
         token: &Self::Address,
@@ -19,13 +19,38 @@ pub mod vp {
         verifiers: &BTreeSet<Address>,
     ) -> bool {
         let mut change: Change = 0;
+        let frozen_key = token::frozen_key(token);
+        // While the token is frozen, none of its balances can change
+        let frozen: bool =
+            vp::read_pre(&frozen_key.to_string()).unwrap_or_default();
+        let minted_key = token::minted_key(token);
         let all_checked = keys_changed.iter().all(|key| {
+            if key == &frozen_key {
+                // Only the token's admin can freeze and unfreeze it
+                let admin: Option<Address> =
+                    vp::read_pre(&token::admin_key(token).to_string());
+                return match admin {
+                    Some(admin) => verifiers.contains(&admin),
+                    None => false,
+                };
+            }
+            if key == &minted_key {
+                // Checked against the change of the supply below
+                return true;
+            }
             match token::is_balance_key(token, key) {
                 None => {
                     // Unknown changes to this address space are disallowed, but
                     // unknown changes anywhere else are permitted
                     key.segments.get(0) != Some(&token.to_db_key())
                 }
+                Some(_) if frozen => {
+                    vp::log_string(format!(
+                        "Token {} is frozen, balance change rejected: {}",
+                        token, key
+                    ));
+                    false
+                }
                 Some(owner) => {
                     // accumulate the change
                     let key = key.to_string();
@@ -54,7 +79,70 @@ pub mod vp {
                 }
             }
         });
-        all_checked && change == 0
+        all_checked && check_mint(token, &minted_key, change, verifiers)
+    }
+
+    /// Check the change of the token's supply. Only the token's minter can
+    /// increase it, by the amount added to the total minted amount, which
+    /// must not exceed the token's mint cap, if any.
+    fn check_mint(
+        token: &Address,
+        minted_key: &Key,
+        change: Change,
+        verifiers: &BTreeSet<Address>,
+    ) -> bool {
+        let minted_key = minted_key.to_string();
+        let minted_pre: Amount = vp::read_pre(&minted_key).unwrap_or_default();
+        let minted_post: Amount =
+            vp::read_post(&minted_key).unwrap_or_default();
+        let minted_change = minted_post.change() - minted_pre.change();
+        if change != minted_change || minted_change < 0 {
+            return false;
+        }
+        if minted_change == 0 {
+            return true;
+        }
+        let minter: Option<Address> =
+            vp::read_pre(&token::minter_key(token).to_string());
+        let is_minter = match minter {
+            Some(minter) => verifiers.contains(&minter),
+            None => false,
+        };
+        if !is_minter {
+            vp::log_string(format!(
+                "Only the minter of the token {} can mint it",
+                token
+            ));
+            return false;
+        }
+        let cap: Option<Amount> =
+            vp::read_pre(&token::mint_cap_key(token).to_string());
+        match cap {
+            Some(cap) if minted_post > cap => {
+                vp::log_string(format!(
+                    "The minted amount {} of the token {} exceeds its mint \
+                     cap {}",
+                    minted_post, token, cap
+                ));
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Read the transfer allow and deny lists of an account before the
+    /// transaction's changes.
+    pub fn read_transfer_lists_pre(owner: &Address) -> TransferLists {
+        let allow: Option<BTreeSet<Address>> =
+            vp::read_pre(&token::transfer_allow_list_key(owner).to_string());
+        let deny: BTreeSet<Address> =
+            vp::read_pre(&token::transfer_deny_list_key(owner).to_string())
+                .unwrap_or_default();
+        TransferLists {
+            owner: owner.clone(),
+            allow,
+            deny,
+        }
     }
 }
 
@@ -65,6 +153,59 @@ pub mod tx {
     use super::*;
     use crate::imports::tx;
 
+    /// Freeze or unfreeze all the transfers of a token. The token's admin is
+    /// added to the verifiers, so that its VP can check the signature.
+    pub fn freeze(token: &Address, frozen: bool) {
+        let admin: Option<Address> =
+            tx::read(&token::admin_key(token).to_string());
+        match admin {
+            Some(admin) => tx::insert_verifier(&admin),
+            None => {
+                tx::log_string(format!("token {} has no admin", token));
+                unreachable!()
+            }
+        }
+        tx::write(&token::frozen_key(token).to_string(), frozen);
+    }
+
+    /// Set the transfer allow and deny lists of an account. A list without
+    /// any restriction is deleted from storage.
+    pub fn set_transfer_lists(lists: &TransferLists) {
+        let allow_key = token::transfer_allow_list_key(&lists.owner);
+        match &lists.allow {
+            Some(allow) => tx::write(&allow_key.to_string(), allow),
+            None => tx::delete(&allow_key.to_string()),
+        }
+        let deny_key = token::transfer_deny_list_key(&lists.owner);
+        if lists.deny.is_empty() {
+            tx::delete(&deny_key.to_string());
+        } else {
+            tx::write(&deny_key.to_string(), &lists.deny);
+        }
+    }
+
+    /// Mint new tokens to the target's balance. The token's minter is added
+    /// to the verifiers, so that its VP can check the signature.
+    pub fn mint(token: &Address, target: &Address, amount: Amount) {
+        let minter: Option<Address> =
+            tx::read(&token::minter_key(token).to_string());
+        match minter {
+            Some(minter) => tx::insert_verifier(&minter),
+            None => {
+                tx::log_string(format!("token {} has no minter", token));
+                unreachable!()
+            }
+        }
+        let minted_key = token::minted_key(token).to_string();
+        let mut minted: Amount = tx::read(&minted_key).unwrap_or_default();
+        minted.receive(&amount);
+        tx::write(&minted_key, minted);
+        let target_key = token::balance_key(token, target).to_string();
+        let mut target_bal: Amount = tx::read(&target_key).unwrap_or_default();
+        target_bal.receive(&amount);
+        tx::write(&target_key, target_bal);
+    }
+
     /// A token transfer that can be used in a transaction.
     pub fn transfer(
         src: &Address,
@@ -25,6 +25,14 @@ pub fn is_tx_whitelisted() -> bool {
     whitelist.is_empty() || whitelist.contains(&tx_hash.to_string())
 }
 
+/// Check if the given address is one of the matchmakers registered at genesis.
+pub fn is_matchmaker(address: &Address) -> bool {
+    let key = parameters::matchmakers_storage_key();
+    let matchmakers: Vec<Address> =
+        read_pre(&key.to_string()).unwrap_or_default();
+    matchmakers.contains(address)
+}
+
 pub fn is_vp_whitelisted(vp_bytes: &[u8]) -> bool {
     let vp_hash = sha256(vp_bytes);
     let key = parameters::vp_whitelist_storage_key();
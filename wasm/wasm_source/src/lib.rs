@@ -1,5 +1,15 @@
 #[cfg(feature = "tx_bond")]
 pub mod tx_bond;
+#[cfg(feature = "tx_change_commission_rate")]
+pub mod tx_change_commission_rate;
+#[cfg(feature = "tx_change_consensus_key")]
+pub mod tx_change_consensus_key;
+#[cfg(feature = "tx_claim_rewards")]
+pub mod tx_claim_rewards;
+#[cfg(feature = "tx_edit_validator")]
+pub mod tx_edit_validator;
+#[cfg(feature = "tx_freeze_token")]
+pub mod tx_freeze_token;
 #[cfg(feature = "tx_from_intent")]
 pub mod tx_from_intent;
 #[cfg(feature = "tx_ibc")]
@@ -14,10 +24,16 @@ pub mod tx_init_proposal;
 pub mod tx_init_validator;
 #[cfg(feature = "tx_mint_nft")]
 pub mod tx_mint_nft;
+#[cfg(feature = "tx_mint_token")]
+pub mod tx_mint_token;
+#[cfg(feature = "tx_set_transfer_lists")]
+pub mod tx_set_transfer_lists;
 #[cfg(feature = "tx_transfer")]
 pub mod tx_transfer;
 #[cfg(feature = "tx_unbond")]
 pub mod tx_unbond;
+#[cfg(feature = "tx_unjail_validator")]
+pub mod tx_unjail_validator;
 #[cfg(feature = "tx_update_vp")]
 pub mod tx_update_vp;
 #[cfg(feature = "tx_withdraw")]
@@ -0,0 +1,20 @@
+//! A tx for a validator to change its commission rate for PoS rewards.
+
+use anoma_tx_prelude::proof_of_stake::change_validator_commission_rate;
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let change = transaction::pos::CommissionChange::try_from_slice(
+        &signed.data.unwrap()[..],
+    )
+    .unwrap();
+
+    if let Err(err) =
+        change_validator_commission_rate(&change.validator, change.new_rate)
+    {
+        debug_log!("Commission rate change failed with: {}", err);
+        panic!()
+    }
+}
@@ -0,0 +1,20 @@
+//! A tx for a validator to rotate its consensus key.
+
+use anoma_tx_prelude::proof_of_stake::change_validator_consensus_key;
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let change = transaction::pos::ConsensusKeyChange::try_from_slice(
+        &signed.data.unwrap()[..],
+    )
+    .unwrap();
+
+    if let Err(err) =
+        change_validator_consensus_key(&change.validator, &change.consensus_key)
+    {
+        debug_log!("Consensus key change failed with: {}", err);
+        panic!()
+    }
+}
@@ -0,0 +1,18 @@
+//! A tx for a validator to update its metadata.
+
+use anoma_tx_prelude::proof_of_stake::edit_validator_metadata;
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let edit = transaction::pos::EditValidator::try_from_slice(
+        &signed.data.unwrap()[..],
+    )
+    .unwrap();
+
+    if let Err(err) = edit_validator_metadata(&edit.validator, edit.metadata) {
+        debug_log!("Validator metadata update failed with: {}", err);
+        panic!()
+    }
+}
@@ -0,0 +1,14 @@
+//! A tx for a token's admin to freeze or unfreeze the token's transfers.
+//! This tx uses `token::Freeze` wrapped inside `SignedTxData`
+//! as its input as declared in `shared` crate.
+
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let freeze =
+        token::Freeze::try_from_slice(&signed.data.unwrap()[..]).unwrap();
+    debug_log!("apply_tx called with freeze: {:#?}", freeze);
+    token::freeze(&freeze.token, freeze.frozen)
+}
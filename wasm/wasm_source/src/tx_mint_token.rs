@@ -0,0 +1,13 @@
+//! A tx for a token's minter to mint new tokens to a target's balance.
+//! This tx uses `token::Mint` wrapped inside `SignedTxData`
+//! as its input as declared in `shared` crate.
+
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let mint = token::Mint::try_from_slice(&signed.data.unwrap()[..]).unwrap();
+    debug_log!("apply_tx called with mint: {:#?}", mint);
+    token::mint(&mint.token, &mint.target, mint.amount)
+}
@@ -0,0 +1,14 @@
+//! A tx to set the transfer allow and deny lists of an account.
+//! This tx uses `token::TransferLists` wrapped inside `SignedTxData`
+//! as its input as declared in `shared` crate.
+
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let lists = token::TransferLists::try_from_slice(&signed.data.unwrap()[..])
+        .unwrap();
+    debug_log!("apply_tx called with transfer lists: {:#?}", lists);
+    token::set_transfer_lists(&lists)
+}
@@ -0,0 +1,18 @@
+//! A tx for a jailed validator to become a candidate again once its jail
+//! duration is over.
+
+use anoma_tx_prelude::proof_of_stake::unjail_validator;
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let unjail =
+        transaction::pos::Unjail::try_from_slice(&signed.data.unwrap()[..])
+            .unwrap();
+
+    if let Err(err) = unjail_validator(&unjail.validator) {
+        debug_log!("Unjail failed with: {}", err);
+        panic!()
+    }
+}
@@ -3,12 +3,22 @@
 //! This VP currently provides a signature verification against a public key for
 //! sending tokens (receiving tokens is permissive).
 //!
-//! It allows to bond, unbond and withdraw tokens to and from PoS system with a
+//! It allows to bond, unbond and withdraw tokens to and from PoS system and to
+//! claim staking rewards with a valid signature.
+//!
+//! It allows to freeze and unfreeze the transfers of tokens administered by
+//! this account and to mint the tokens whose minter is this account with a
 //! valid signature.
 //!
+//! When this account has a transfer allow or deny list, the token balance
+//! changes of this account are only allowed in transactions in which all the
+//! other parties are allowed by the lists. The lists can be set with a valid
+//! signature.
+//!
 //! It allows to fulfil intents that were signed by this account's key if they
 //! haven't already been fulfilled (fulfilled intents are added to the owner's
-//! invalid intent set).
+//! invalid intent set). The tx fulfilling the intents must be signed by one of
+//! the matchmakers registered at genesis.
 //!
 //! Any other storage key changes are allowed only with a valid signature.
 
@@ -22,6 +32,9 @@ use rust_decimal::prelude::*;
 
 enum KeyType<'a> {
     Token(&'a Address),
+    TokenFrozen(&'a Address),
+    TokenMinted(&'a Address),
+    TransferList(&'a Address),
     PoS,
     InvalidIntentSet(&'a Address),
     Nft(&'a Address),
@@ -33,6 +46,12 @@ impl<'a> From<&'a storage::Key> for KeyType<'a> {
     fn from(key: &'a storage::Key) -> KeyType<'a> {
         if let Some(address) = token::is_any_token_balance_key(key) {
             Self::Token(address)
+        } else if let Some(token) = token::is_any_token_frozen_key(key) {
+            Self::TokenFrozen(token)
+        } else if let Some(token) = token::is_any_token_minted_key(key) {
+            Self::TokenMinted(token)
+        } else if let Some(owner) = token::is_transfer_list_key(key) {
+            Self::TransferList(owner)
         } else if proof_of_stake::is_pos_key(key) {
             Self::PoS
         } else if let Some(address) = intent::is_invalid_intent_key(key) {
@@ -80,6 +99,8 @@ fn validate_tx(
         _ => false,
     });
 
+    let transfer_lists = Lazy::new(|| token::read_transfer_lists_pre(&addr));
+
     if !is_tx_whitelisted() {
         return false;
     }
@@ -95,14 +116,26 @@ fn validate_tx(
                         read_post(&key).unwrap_or_default();
                     let change = post.change() - pre.change();
                     // debit has to signed, credit doesn't
-                    let valid = change >= 0 || *valid_sig || *valid_intent;
+                    let valid_debit =
+                        change >= 0 || *valid_sig || *valid_intent;
+                    // the other parties must be allowed by the transfer lists
+                    let valid_counterparties = change == 0
+                        || check_counterparties(
+                            &addr,
+                            &transfer_lists,
+                            key,
+                            &keys_changed,
+                        );
+                    let valid = valid_debit && valid_counterparties;
                     debug_log!(
                         "token key: {}, change: {}, valid_sig: {}, \
-                         valid_intent: {}, valid modification: {}",
+                         valid_intent: {}, valid_counterparties: {}, valid \
+                         modification: {}",
                         key,
                         change,
                         *valid_sig,
                         *valid_intent,
+                        valid_counterparties,
                         valid
                     );
                     valid
@@ -118,19 +151,83 @@ fn validate_tx(
                     true
                 }
             }
+            KeyType::TokenFrozen(token) => {
+                let admin: Option<Address> =
+                    read_pre(&token::admin_key(token).to_string());
+                // Freezing a token administered by this address must be signed
+                let valid = admin.as_ref() != Some(&addr) || *valid_sig;
+                debug_log!(
+                    "token frozen key: {}, valid_sig: {}, valid modification: \
+                     {}",
+                    key,
+                    *valid_sig,
+                    valid
+                );
+                valid
+            }
+            KeyType::TokenMinted(token) => {
+                let minter: Option<Address> =
+                    read_pre(&token::minter_key(token).to_string());
+                // Minting a token whose minter is this address must be signed
+                let valid = minter.as_ref() != Some(&addr) || *valid_sig;
+                debug_log!(
+                    "token minted key: {}, valid_sig: {}, valid modification: \
+                     {}",
+                    key,
+                    *valid_sig,
+                    valid
+                );
+                valid
+            }
+            KeyType::TransferList(owner) => {
+                if owner == &addr {
+                    let list: BTreeSet<Address> =
+                        read_post(&key.to_string()).unwrap_or_default();
+                    // Setting the lists must be signed and their size is
+                    // bounded
+                    let valid = *valid_sig
+                        && list.len() <= token::MAX_TRANSFER_LIST_LEN;
+                    debug_log!(
+                        "transfer list key: {}, len: {}, valid_sig: {}, valid \
+                         modification: {}",
+                        key,
+                        list.len(),
+                        *valid_sig,
+                        valid
+                    );
+                    valid
+                } else {
+                    true
+                }
+            }
             KeyType::PoS => {
                 // Allow the account to be used in PoS
                 let bond_id = proof_of_stake::is_bond_key(key)
-                    .or_else(|| proof_of_stake::is_unbond_key(key));
+                    .or_else(|| proof_of_stake::is_unbond_key(key))
+                    .or_else(|| proof_of_stake::is_rewards_key(key));
                 let valid = match bond_id {
                     Some(bond_id) => {
-                        // Bonds and unbonds changes for this address
+                        // Bonds, unbonds and rewards changes for this address
                         // must be signed
                         bond_id.source != addr || *valid_sig
                     }
                     None => {
-                        // Any other PoS changes are allowed without signature
-                        true
+                        match proof_of_stake::is_validator_commission_rate_key(
+                            key,
+                        )
+                        .or_else(|| {
+                            proof_of_stake::is_validator_metadata_key(key)
+                        })
+                        .or_else(|| proof_of_stake::is_validator_state_key(key))
+                        .or_else(|| {
+                            proof_of_stake::is_validator_consensus_key_key(key)
+                        }) {
+                            // Commission rate, metadata, state and consensus
+                            // key changes for this address must be signed
+                            Some(validator) => validator != &addr || *valid_sig,
+                            // Any other PoS changes are allowed without signature
+                            None => true,
+                        }
                     }
                 };
                 debug_log!(
@@ -203,6 +300,31 @@ fn validate_tx(
     true
 }
 
+/// Check that the owners of the other balances of the same token changed in
+/// the tx are allowed by the transfer lists of this account.
+fn check_counterparties(
+    addr: &Address,
+    transfer_lists: &token::TransferLists,
+    balance_key: &storage::Key,
+    keys_changed: &BTreeSet<storage::Key>,
+) -> bool {
+    let token = match balance_key.segments.get(0) {
+        Some(storage::DbKeySeg::AddressSeg(token)) => token,
+        _ => return true,
+    };
+    keys_changed
+        .iter()
+        .filter_map(|key| token::is_balance_key(token, key))
+        .filter(|owner| *owner != addr)
+        .all(|owner| {
+            let allowed = transfer_lists.is_allowed(owner);
+            if !allowed {
+                debug_log!("transfers with {} are not allowed", owner);
+            }
+            allowed
+        })
+}
+
 fn check_intent_transfers(
     addr: &Address,
     signed_tx_data: &SignedTxData,
@@ -227,6 +349,9 @@ fn try_decode_intent(
     let raw_intent_transfers = signed_tx_data.data.as_ref().cloned()?;
     let mut tx_data =
         IntentTransfers::try_from_slice(&raw_intent_transfers[..]).ok()?;
+    if !check_matchmaker(&tx_data.source, signed_tx_data) {
+        return None;
+    }
     debug_log!(
         "tx_data.matches.exchanges: {:?}, {}",
         tx_data.matches.exchanges,
@@ -243,6 +368,19 @@ fn try_decode_intent(
     None
 }
 
+/// Check that the intent transfers have been crafted by one of the registered
+/// matchmakers and that the tx is signed by its key.
+fn check_matchmaker(source: &Address, signed_tx_data: &SignedTxData) -> bool {
+    if !is_matchmaker(source) {
+        debug_log!("{} is not a registered matchmaker", source);
+        return false;
+    }
+    match key::get(source) {
+        Some(pk) => verify_tx_signature(&pk, &signed_tx_data.sig),
+        None => false,
+    }
+}
+
 fn check_intent(
     addr: &Address,
     exchange: anoma_vp_prelude::Signed<Exchange>,
@@ -347,6 +485,8 @@ fn check_intent(
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use address::testing::arb_non_internal_address;
     // Use this as `#[test]` annotation to enable logging
     use anoma_tests::log::test;
@@ -482,6 +622,62 @@ mod tests {
         assert!(validate_tx(tx_data, vp_owner, keys_changed, verifiers));
     }
 
+    /// Test that a signed debit transfer to an address that is not in the
+    /// owner's transfer allow list is rejected, while a transfer to an
+    /// address in the list is accepted.
+    #[test]
+    fn test_transfer_allow_list() {
+        let vp_owner = address::testing::established_address_1();
+        let keypair = key::testing::keypair_1();
+        let public_key = keypair.ref_to();
+        let allowed = address::testing::established_address_2();
+        let not_allowed = address::testing::established_address_3();
+        let token = address::xan();
+        let amount = token::Amount::from(10_098_123);
+
+        for (target, expected) in
+            [(allowed.clone(), true), (not_allowed, false)]
+        {
+            // Initialize a tx environment
+            let mut tx_env = TestTxEnv::default();
+
+            // Spawn the accounts to be able to modify their storage
+            tx_env.spawn_accounts([&vp_owner, &target, &token]);
+            tx_env.credit_tokens(&vp_owner, &token, amount);
+            tx_env.write_public_key(&vp_owner, &public_key);
+
+            // The owner may only transfer with the allowed address
+            let allow_list = BTreeSet::from([allowed.clone()]);
+            tx_env
+                .storage
+                .write(
+                    &token::transfer_allow_list_key(&vp_owner),
+                    allow_list.try_to_vec().unwrap(),
+                )
+                .unwrap();
+
+            // Initialize VP environment from a transaction
+            vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+                // Apply transfer in a transaction
+                tx_host_env::token::transfer(address, &target, &token, amount);
+            });
+
+            let mut vp_env = vp_host_env::take();
+            let tx = vp_env.tx.clone();
+            let signed_tx = tx.sign(&keypair);
+            let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
+            vp_env.tx = signed_tx;
+            let keys_changed: BTreeSet<storage::Key> =
+                vp_env.all_touched_storage_keys();
+            let verifiers: BTreeSet<Address> = BTreeSet::default();
+            vp_host_env::set(vp_env);
+            assert_eq!(
+                validate_tx(tx_data, vp_owner.clone(), keys_changed, verifiers),
+                expected
+            );
+        }
+    }
+
     /// Test that a transfer on with accounts other than self is accepted.
     #[test]
     fn test_transfer_between_other_parties_accepted() {
@@ -833,4 +1029,137 @@ mod tests {
         vp_host_env::set(vp_env);
         assert!(validate_tx(tx_data, vp_owner, keys_changed, verifiers));
     }
+
+    /// Test that an intent transfer crafted by any of the registered
+    /// matchmakers is accepted, while the same intent transfer from an address
+    /// that is not a registered matchmaker is rejected.
+    #[test]
+    fn test_intent_transfer_from_matchmakers() {
+        let vp_owner = address::testing::established_address_1();
+        let keypair = key::testing::keypair_1();
+        let public_key = keypair.ref_to();
+        let counterparty = address::testing::established_address_2();
+        let token_sell = address::xan();
+        let token_buy = address::btc();
+        let amount = token::Amount::from(10_098_123);
+
+        let matchmaker_1 = (
+            address::testing::established_address_3(),
+            key::testing::keypair_2(),
+        );
+        let matchmaker_2 = (
+            address::testing::established_address_4(),
+            key::testing::gen_keypair::<key::common::SigScheme>(),
+        );
+        let not_matchmaker = (
+            address::testing::gen_established_address(),
+            key::testing::gen_keypair::<key::common::SigScheme>(),
+        );
+        let matchmakers = vec![matchmaker_1.0.clone(), matchmaker_2.0.clone()];
+
+        for ((source, source_keypair), expected) in [
+            (matchmaker_1, true),
+            (matchmaker_2, true),
+            (not_matchmaker, false),
+        ] {
+            // Initialize a tx environment
+            let mut tx_env = TestTxEnv::default();
+
+            parameters::init_genesis_matchmakers(
+                &mut tx_env.storage,
+                &matchmakers,
+            );
+
+            // Spawn the accounts to be able to modify their storage
+            tx_env.spawn_accounts([
+                &vp_owner,
+                &counterparty,
+                &token_sell,
+                &token_buy,
+            ]);
+
+            // Credit the tokens to both parties of the exchange
+            tx_env.credit_tokens(&vp_owner, &token_sell, amount);
+            tx_env.credit_tokens(&counterparty, &token_buy, amount);
+
+            tx_env.write_public_key(&vp_owner, &public_key);
+            tx_env.write_public_key(&source, &source_keypair.ref_to());
+
+            let exchange = Signed::new(
+                &keypair,
+                Exchange {
+                    addr: vp_owner.clone(),
+                    token_sell: token_sell.clone(),
+                    rate_min: intent::DecimalWrapper(Decimal::ONE),
+                    max_sell: amount,
+                    token_buy: token_buy.clone(),
+                    min_buy: amount,
+                    vp: None,
+                },
+            );
+            let intent = Signed::new(
+                &keypair,
+                FungibleTokenIntent {
+                    exchange: HashSet::from([exchange.clone()]),
+                },
+            );
+            let transfers = HashSet::from([
+                token::Transfer {
+                    source: vp_owner.clone(),
+                    target: counterparty.clone(),
+                    token: token_sell.clone(),
+                    amount,
+                },
+                token::Transfer {
+                    source: counterparty.clone(),
+                    target: vp_owner.clone(),
+                    token: token_buy.clone(),
+                    amount,
+                },
+            ]);
+            let intent_transfers = IntentTransfers {
+                matches: intent::MatchedExchanges {
+                    transfers: transfers.clone(),
+                    exchanges: HashMap::from([(
+                        vp_owner.clone(),
+                        exchange.clone(),
+                    )]),
+                    intents: HashMap::from([(vp_owner.clone(), intent)]),
+                },
+                source,
+            };
+
+            // Initialize VP environment from a transaction
+            vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |_address| {
+                // Apply the matched transfers in a transaction
+                for token::Transfer {
+                    source,
+                    target,
+                    token,
+                    amount,
+                } in &transfers
+                {
+                    tx_host_env::token::transfer(
+                        source, target, token, *amount,
+                    );
+                }
+                tx_host_env::intent::invalidate_exchange(&exchange);
+            });
+
+            let mut vp_env = vp_host_env::take();
+            let mut tx = vp_env.tx.clone();
+            tx.data = Some(intent_transfers.try_to_vec().unwrap());
+            let signed_tx = tx.sign(&source_keypair);
+            let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
+            vp_env.tx = signed_tx;
+            let keys_changed: BTreeSet<storage::Key> =
+                vp_env.all_touched_storage_keys();
+            let verifiers: BTreeSet<Address> = BTreeSet::default();
+            vp_host_env::set(vp_env);
+            assert_eq!(
+                validate_tx(tx_data, vp_owner.clone(), keys_changed, verifiers),
+                expected
+            );
+        }
+    }
 }
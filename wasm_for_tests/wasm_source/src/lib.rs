@@ -36,6 +36,24 @@ pub mod main {
     }
 }
 
+/// A tx that writes the given value to the given key in storage. The tx data
+/// is the borsh encoded pair of the key and the value's bytes.
+#[cfg(feature = "tx_write_storage_key")]
+pub mod main {
+    use anoma_vm_env::tx_prelude::*;
+
+    #[transaction]
+    fn apply_tx(tx_data: Vec<u8>) {
+        let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+        let (key, value) = <(storage::Key, Vec<u8>)>::try_from_slice(
+            &signed.data.unwrap()[..],
+        )
+        .unwrap();
+        log_string(format!("write key {}", key));
+        write_bytes(key.to_string(), value);
+    }
+}
+
 /// A tx that attempts to mint tokens in the transfer's target without debiting
 /// the tokens from the source. This tx is expected to be rejected by the
 /// token's VP.
@@ -157,3 +175,35 @@ pub mod main {
         true
     }
 }
+
+/// A VP that rejects any transaction before the unlock height read from the
+/// account's `unlock_height` key in the prior state. Without the key or from
+/// the unlock height on, it accepts any transaction.
+#[cfg(feature = "vp_time_lock")]
+pub mod main {
+    use anoma_vm_env::vp_prelude::storage::KeySeg;
+    use anoma_vm_env::vp_prelude::*;
+
+    #[validity_predicate]
+    fn validate_tx(
+        _tx_data: Vec<u8>,
+        addr: Address,
+        _keys_changed: BTreeSet<storage::Key>,
+        _verifiers: BTreeSet<Address>,
+    ) -> bool {
+        let unlock_height_key = storage::Key::from(addr.to_db_key())
+            .push(&"unlock_height".to_owned())
+            .unwrap();
+        let unlock_height: Option<storage::BlockHeight> =
+            read_pre(unlock_height_key.to_string());
+        let height = get_block_height();
+        log_string(format!(
+            "block height {}, unlock height {:?}",
+            height, unlock_height
+        ));
+        match unlock_height {
+            Some(unlock_height) => height >= unlock_height,
+            None => true,
+        }
+    }
+}